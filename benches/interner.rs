@@ -0,0 +1,74 @@
+//! Benchmark of the lookups by gene symbol, with `SymbolMap` as used by the annotation
+//! tables against the `HashMap<String, _>` they used before.
+//!
+//! Run with `cargo bench --bench interner`.
+
+use std::collections::HashMap;
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use viguno::common::interner::SymbolMap;
+
+/// Number of genes, about the number of human protein-coding genes.
+const N_GENES: usize = 20_000;
+
+/// Return the gene symbols of the benchmark.
+fn symbols() -> Vec<String> {
+    (0..N_GENES).map(|i| format!("GENE{i}")).collect()
+}
+
+fn lookup(c: &mut Criterion) {
+    let symbols = symbols();
+    let mut symbol_map = SymbolMap::default();
+    let mut hash_map = HashMap::new();
+    for (i, symbol) in symbols.iter().enumerate() {
+        symbol_map.insert_first(symbol, i);
+        hash_map.insert(symbol.clone(), i);
+    }
+
+    let mut group = c.benchmark_group("lookup by gene symbol");
+    group.bench_function("SymbolMap", |b| {
+        b.iter(|| {
+            symbols
+                .iter()
+                .filter_map(|symbol| symbol_map.get(black_box(symbol)))
+                .sum::<usize>()
+        });
+    });
+    group.bench_function("HashMap<String, _>", |b| {
+        b.iter(|| {
+            symbols
+                .iter()
+                .filter_map(|symbol| hash_map.get(black_box(symbol.as_str())))
+                .sum::<usize>()
+        });
+    });
+    group.finish();
+}
+
+fn build(c: &mut Criterion) {
+    let symbols = symbols();
+
+    let mut group = c.benchmark_group("build by gene symbol");
+    group.bench_function("SymbolMap", |b| {
+        b.iter(|| {
+            let mut map = SymbolMap::default();
+            for (i, symbol) in symbols.iter().enumerate() {
+                map.insert_first(symbol, i);
+            }
+            map
+        });
+    });
+    group.bench_function("HashMap<String, _>", |b| {
+        b.iter(|| {
+            symbols
+                .iter()
+                .enumerate()
+                .map(|(i, symbol)| (symbol.clone(), i))
+                .collect::<HashMap<_, _>>()
+        });
+    });
+    group.finish();
+}
+
+criterion_group!(benches, lookup, build);
+criterion_main!(benches);
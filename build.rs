@@ -24,5 +24,27 @@ fn main() -> Result<(), anyhow::Error> {
         // Define the protobuf files to compile.
         .compile_protos(&proto_files, &[root])?;
 
+    #[cfg(feature = "ffi")]
+    {
+        println!("cargo:rerun-if-changed=src/ffi.rs");
+        // Only export the functions and the opaque data, not the constants of the crate.
+        let mut config = cbindgen::Config::default();
+        config.export.item_types = vec![
+            cbindgen::ItemType::Functions,
+            cbindgen::ItemType::OpaqueItems,
+        ];
+        cbindgen::Builder::new()
+            .with_config(config)
+            .with_crate(env!("CARGO_MANIFEST_DIR"))
+            .with_language(cbindgen::Language::C)
+            .with_header("/* Generated by cbindgen from src/ffi.rs, do not edit. */")
+            .with_include_guard("VIGUNO_H")
+            .with_sys_include("stdint.h")
+            .with_no_includes()
+            .with_cpp_compat(true)
+            .generate()?
+            .write_to_file(PathBuf::from(env::var("OUT_DIR").unwrap()).join("viguno.h"));
+    }
+
     Ok(())
 }
@@ -0,0 +1,48 @@
+//! I/O-free core of `viguno` with the term ID parsing, the term search, and the Phenomizer
+//! scoring.
+//!
+//! The crate compiles to `wasm32-unknown-unknown` so that queries can run in the browser.
+//! The ontology is loaded from the bytes of a binary `hpo.bin` file with `load`, e.g., as
+//! fetched once by the client.
+#![deny(clippy::pedantic)]
+#![allow(clippy::must_use_candidate)]
+#![allow(clippy::module_name_repetitions)]
+#![warn(missing_docs)]
+
+pub mod phenomizer;
+pub mod search;
+
+/// Load the ontology from the `bytes` of a binary `hpo.bin` file.
+///
+/// # Errors
+///
+/// In the case that the bytes are no valid binary ontology.  Note that `hpo` panics
+/// rather than failing on some malformed input, so the bytes should come from a trusted
+/// `hpo.bin`.
+pub fn load(bytes: &[u8]) -> Result<hpo::Ontology, anyhow::Error> {
+    Ok(hpo::Ontology::from_bytes(bytes)?)
+}
+
+/// Parse an HPO term ID as sent by clients.
+///
+/// Besides the canonical `HP:0001250`, this accepts the prefix in any case, `_` as the
+/// separator as in `HP_0001250`, and bare numeric IDs such as `0001250` or `1250`.
+///
+/// # Errors
+///
+/// In the case that `value` is not an HPO term ID in any of these forms.
+pub fn parse_term_id(value: &str) -> Result<hpo::HpoTermId, anyhow::Error> {
+    let trimmed = value.trim();
+    let digits = match trimmed.get(..3) {
+        Some(prefix)
+            if prefix.eq_ignore_ascii_case("hp:") || prefix.eq_ignore_ascii_case("hp_") =>
+        {
+            &trimmed[3..]
+        }
+        _ => trimmed,
+    };
+    if digits.is_empty() || digits.len() > 7 || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        anyhow::bail!("invalid HPO term ID {value:?}, expected e.g. \"HP:0001250\"");
+    }
+    Ok(hpo::HpoTermId::from_u32(digits.parse()?))
+}
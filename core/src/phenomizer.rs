@@ -0,0 +1,149 @@
+//! Similarity computation using the Phenomizer method.
+
+use std::collections::HashMap;
+
+use hpo::{
+    similarity::{Builtins, Similarity},
+    term::{HpoGroup, InformationContentKind},
+    HpoTerm, HpoTermId, Ontology,
+};
+
+/// Weights of query terms, e.g., from clinical modifiers, terms not listed weigh 1.
+pub type TermWeights = HashMap<HpoTermId, f32>;
+
+/// Return the weight of `term` in `weights`.
+fn term_weight(weights: &TermWeights, term: &HpoTerm) -> f32 {
+    weights.get(&term.id()).copied().unwrap_or(1.0)
+}
+
+// TODO: this is equivalent to `StandardCombiner::FunSimAvg` USE IT.
+
+/// Compute symmetric similarity score.
+pub fn score(q: &HpoGroup, d: &HpoGroup, o: &Ontology) -> f32 {
+    let s = Builtins::Resnik(InformationContentKind::Gene);
+    f32::midpoint(score_dir(q, d, o, &s), score_dir(d, q, o, &s))
+}
+
+/// Compute symmetric similarity score with the directed scores weighted by the
+/// information content of the terms.
+///
+/// In contrast to `score`, matches of specific terms contribute more than matches of
+/// general terms.
+pub fn score_weighted(q: &HpoGroup, d: &HpoGroup, o: &Ontology) -> f32 {
+    let s = Builtins::Resnik(InformationContentKind::Gene);
+    f32::midpoint(
+        score_dir_weighted(q, d, o, &s),
+        score_dir_weighted(d, q, o, &s),
+    )
+}
+
+/// Compute symmetric similarity score with the query terms `q` weighted by `weights`.
+///
+/// The best match scores of the terms in `q` are weighted in the directed score from `q`
+/// to `d`, the directed score from `d` to `q` is the one of `score`.
+pub fn score_modified(q: &HpoGroup, d: &HpoGroup, o: &Ontology, weights: &TermWeights) -> f32 {
+    score_modified_with(
+        q,
+        d,
+        o,
+        weights,
+        &Builtins::Resnik(InformationContentKind::Gene),
+    )
+}
+
+/// Compute the score of `score_modified` with the pairwise similarity `s` instead of
+/// Resnik.
+pub fn score_modified_with(
+    q: &HpoGroup,
+    d: &HpoGroup,
+    o: &Ontology,
+    weights: &TermWeights,
+    s: &impl Similarity,
+) -> f32 {
+    f32::midpoint(
+        score_dir_by(q, d, o, s, |q| term_weight(weights, q)),
+        score_dir(d, q, o, s),
+    )
+}
+
+/// Compute the score of `score_weighted` with the query terms `q` additionally weighted
+/// by `weights`.
+pub fn score_weighted_modified(
+    q: &HpoGroup,
+    d: &HpoGroup,
+    o: &Ontology,
+    weights: &TermWeights,
+) -> f32 {
+    let s = Builtins::Resnik(InformationContentKind::Gene);
+    f32::midpoint(
+        score_dir_by(q, d, o, &s, |q| {
+            q.information_content().gene() * term_weight(weights, q)
+        }),
+        score_dir_weighted(d, q, o, &s),
+    )
+}
+
+/// Maximal similarity of `q` to any `d in ds`.
+fn best_score(q: &HpoTerm, ds: &HpoGroup, o: &Ontology, s: &impl Similarity) -> f32 {
+    ds.iter()
+        .filter_map(|d| o.hpo(d).map(|d| q.similarity_score(&d, s)))
+        .max_by(|a, b| a.partial_cmp(b).expect("try to compare NaN"))
+        .unwrap_or_default()
+}
+
+/// "Directed" score part of phenomizer score.
+///
+/// # Panics
+///
+/// If there are more query terms than fit an `f32` value.
+fn score_dir(qs: &HpoGroup, ds: &HpoGroup, o: &Ontology, s: &impl Similarity) -> f32 {
+    // Handle case of empty `qs`.
+    if qs.is_empty() {
+        return 0f32;
+    }
+
+    // For each `q in qs` compute max similarity to any `d in ds`.
+    let mut tmp: Vec<f32> = Vec::new();
+    for q in qs {
+        if let Some(q) = o.hpo(q) {
+            tmp.push(best_score(&q, ds, o, s));
+        }
+    }
+
+    // NB: we allow loss of precision in this function for the following statement.
+    let len: u16 = qs.len().try_into().expect("more than 2^16 query terms");
+    let len: f32 = len.into();
+    tmp.iter().sum::<f32>() / len
+}
+
+/// "Directed" score part of the weighted phenomizer score.
+///
+/// The best match scores are weighted by the information content of the `qs` terms.
+fn score_dir_weighted(qs: &HpoGroup, ds: &HpoGroup, o: &Ontology, s: &impl Similarity) -> f32 {
+    score_dir_by(qs, ds, o, s, |q| q.information_content().gene())
+}
+
+/// "Directed" score with the best match scores weighted by `weight` of the `qs` terms.
+fn score_dir_by(
+    qs: &HpoGroup,
+    ds: &HpoGroup,
+    o: &Ontology,
+    s: &impl Similarity,
+    weight: impl Fn(&HpoTerm) -> f32,
+) -> f32 {
+    let (weighted_sum, weight_sum) = qs
+        .iter()
+        .filter_map(|q| o.hpo(q))
+        .map(|q| {
+            let w = weight(&q);
+            (w * best_score(&q, ds, o, s), w)
+        })
+        .fold((0.0, 0.0), |(weighted_sum, weight_sum), (score, ic)| {
+            (weighted_sum + score, weight_sum + ic)
+        });
+    if weight_sum > 0.0 {
+        weighted_sum / weight_sum
+    } else {
+        0.0
+    }
+}
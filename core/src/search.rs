@@ -0,0 +1,85 @@
+//! Search of terms by name without a full text index.
+//!
+//! The server uses a `tantivy` index, which is not available in the browser.  This search
+//! scans the term names, which is fast enough for the about 20k terms of the HPO.
+
+use hpo::{HpoTermId, Ontology};
+
+/// Return the terms whose names contain all words of `query`, at most `max_results`.
+///
+/// Matching ignores the case.  Exact matches come first, then names starting with the
+/// query, then the other matches, each by ascending name length and term ID.
+pub fn terms_by_name(ontology: &Ontology, query: &str, max_results: usize) -> Vec<HpoTermId> {
+    let query = query.trim().to_lowercase();
+    let words = query.split_whitespace().collect::<Vec<_>>();
+    if words.is_empty() {
+        return Vec::new();
+    }
+
+    let mut matches = ontology
+        .hpos()
+        .filter_map(|term| {
+            let name = term.name().to_lowercase();
+            if !words.iter().all(|word| name.contains(word)) {
+                return None;
+            }
+            let rank = if name == query {
+                0
+            } else if name.starts_with(&query) {
+                1
+            } else {
+                2
+            };
+            Some((rank, name.len(), term.id()))
+        })
+        .collect::<Vec<_>>();
+    matches.sort_unstable();
+    matches
+        .into_iter()
+        .take(max_results)
+        .map(|(_, _, term_id)| term_id)
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use hpo::{builder::Builder, HpoTermId, Ontology};
+
+    /// Build an ontology with a few seizure terms below the root.
+    fn build_ontology() -> Ontology {
+        let mut builder = Builder::new();
+        builder.new_term("All", 1u32);
+        builder.new_term("Focal-onset seizure", 2u32);
+        builder.new_term("Seizure", 3u32);
+        builder.new_term("Seizures with fever", 4u32);
+        builder.new_term("Arachnodactyly", 5u32);
+        let mut builder = builder.terms_complete();
+        for term in 2u32..=5 {
+            builder.add_parent(1u32, term).expect("term must exist");
+        }
+        builder
+            .connect_all_terms()
+            .calculate_information_content()
+            .expect("could not compute IC")
+            .build_minimal()
+    }
+
+    #[test]
+    fn terms_by_name() {
+        let ontology = build_ontology();
+
+        assert_eq!(
+            super::terms_by_name(&ontology, "seizure", 10),
+            [3u32, 4, 2].map(HpoTermId::from).to_vec()
+        );
+        assert_eq!(
+            super::terms_by_name(&ontology, "  FEVER seizure ", 10),
+            vec![HpoTermId::from(4u32)]
+        );
+        assert_eq!(
+            super::terms_by_name(&ontology, "seizure", 1),
+            vec![HpoTermId::from(3u32)]
+        );
+        assert!(super::terms_by_name(&ontology, " ", 10).is_empty());
+    }
+}
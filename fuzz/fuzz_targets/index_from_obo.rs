@@ -0,0 +1,14 @@
+//! Fuzz target for building the full text index from an OBO document.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use viguno::{sources::translations::Translations, index::Index};
+
+fuzz_target!(|text: &str| {
+    // Documents rejected by the parser are out of scope, all others must be indexed
+    // without panicking.
+    if let Ok(hpo_doc) = fastobo::from_str(text) {
+        let _ = Index::new(hpo_doc, &Translations::default());
+    }
+});
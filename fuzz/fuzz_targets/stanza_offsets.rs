@@ -0,0 +1,14 @@
+//! Fuzz target for the stanza offsets that `Index::from_path` computes from the OBO text.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|text: &str| {
+    // `Index::stanza` slices the text at the offsets.
+    for range in viguno::index::stanza_offsets(text).into_values() {
+        let (start, end) = (range.start as usize, range.end as usize);
+        assert!(start <= end && end <= text.len());
+        assert!(text.is_char_boundary(start) && text.is_char_boundary(end));
+    }
+});
@@ -0,0 +1,89 @@
+/* Generated by cbindgen from src/ffi.rs, do not edit. */
+
+#ifndef VIGUNO_H
+#define VIGUNO_H
+
+#include <stdint.h>
+
+/**
+ * The loaded data, opaque to C.
+ */
+typedef struct Viguno Viguno;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Load the data from the directory `path_hpo_dir` as `viguno server run` does.
+ *
+ * Returns `NULL` on failure.  Release the result with `viguno_free`.
+ *
+ * # Safety
+ *
+ * `path_hpo_dir` must point to a NUL-terminated string.
+ */
+struct Viguno *viguno_init(const char *path_hpo_dir);
+
+/**
+ * Release the data loaded with `viguno_init`.
+ *
+ * # Safety
+ *
+ * `viguno` must be `NULL` or returned by `viguno_init` and not be used afterwards.
+ */
+void viguno_free(struct Viguno *viguno);
+
+/**
+ * Compute the score of the gene `gene_symbol` for the comma-separated `terms` and store it
+ * in `score`.
+ *
+ * Returns 0 on success and -1 on failure.
+ *
+ * # Safety
+ *
+ * `viguno` must be returned by `viguno_init`, `terms` and `gene_symbol` must point to
+ * NUL-terminated strings, and `score` must point to a writeable float.
+ */
+int32_t viguno_score_gene(const struct Viguno *viguno,
+                          const char *terms,
+                          const char *gene_symbol,
+                          float *score);
+
+/**
+ * Prioritize the comma-separated `gene_symbols` (all genes if `NULL` or empty) for the
+ * comma-separated `terms` as `/api/v1/hpo/sim/term-gene` does.
+ *
+ * Returns the JSON result without the `meta` object or `NULL` on failure.  Release the
+ * result with `viguno_string_free`.
+ *
+ * # Safety
+ *
+ * `viguno` must be returned by `viguno_init`, `terms` must point to a NUL-terminated
+ * string, and `gene_symbols` must be `NULL` or point to a NUL-terminated string.
+ */
+char *viguno_sim_term_gene(const struct Viguno *viguno,
+                           const char *terms,
+                           const char *gene_symbols);
+
+/**
+ * Release a string returned by the library.
+ *
+ * # Safety
+ *
+ * `value` must be `NULL` or returned by the library and not be used afterwards.
+ */
+void viguno_string_free(char *value);
+
+/**
+ * Return the message of the last error on the calling thread or `NULL`.
+ *
+ * The message is owned by the library and valid until the next failing call on the thread.
+ */
+const char *viguno_last_error(void);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* VIGUNO_H */
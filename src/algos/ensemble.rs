@@ -0,0 +1,194 @@
+//! Ensemble scoring of genes with several methods fused by reciprocal rank fusion.
+
+use std::str::FromStr;
+
+use hpo::{
+    term::{HpoGroup, InformationContentKind},
+    Ontology,
+};
+
+use crate::{
+    algos::phenomizer::{self, TermWeights},
+    common::{to_builtin_sim, SimilarityMethod},
+};
+
+/// Constant `k` of the reciprocal rank fusion, damping the influence of the top ranks.
+pub const RRF_K: f32 = 60.0;
+
+/// The methods of the ensemble unless configured otherwise.
+pub const DEFAULT_METHODS: &str = "resnik,lin,jc,jaccard";
+
+/// Method contributing to the ensemble score.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum EnsembleMethod {
+    /// The Phenomizer score with the given pairwise similarity instead of Resnik.
+    Similarity(SimilarityMethod),
+    /// Jaccard index of the patient's and the gene's terms including their ancestors.
+    Jaccard,
+}
+
+impl std::fmt::Display for EnsembleMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EnsembleMethod::Similarity(method) => write!(f, "{method}"),
+            EnsembleMethod::Jaccard => write!(f, "jaccard"),
+        }
+    }
+}
+
+impl FromStr for EnsembleMethod {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "jaccard" {
+            Ok(EnsembleMethod::Jaccard)
+        } else {
+            s.parse::<SimilarityMethod>()
+                .map(EnsembleMethod::Similarity)
+                .map_err(|_| anyhow::anyhow!("unknown ensemble method: {}", s))
+        }
+    }
+}
+
+impl EnsembleMethod {
+    /// Compute the score of the gene's `terms` for the `patient` terms.
+    pub fn score(
+        self,
+        patient: &HpoGroup,
+        terms: &HpoGroup,
+        hpo: &Ontology,
+        weights: &TermWeights,
+    ) -> f32 {
+        match self {
+            EnsembleMethod::Similarity(method) => phenomizer::score_modified_with(
+                patient,
+                terms,
+                hpo,
+                weights,
+                &to_builtin_sim(method, InformationContentKind::Gene),
+            ),
+            EnsembleMethod::Jaccard => jaccard(patient, terms, hpo),
+        }
+    }
+}
+
+/// Return the terms together with all their ancestors.
+fn with_ancestors(terms: &HpoGroup, hpo: &Ontology) -> HpoGroup {
+    let mut result = HpoGroup::new();
+    for term in terms.iter().filter_map(|term_id| hpo.hpo(term_id)) {
+        result.insert(term.id());
+        for parent_id in term.all_parent_ids() {
+            result.insert(parent_id);
+        }
+    }
+    result
+}
+
+/// Return the Jaccard index of the term sets `a` and `b` including their ancestors.
+#[allow(clippy::cast_precision_loss)]
+pub fn jaccard(a: &HpoGroup, b: &HpoGroup, hpo: &Ontology) -> f32 {
+    let (a, b) = (with_ancestors(a, hpo), with_ancestors(b, hpo));
+    let intersection = (a.clone() & &b).len();
+    let union = (a | &b).len();
+    if union == 0 {
+        0.0
+    } else {
+        intersection as f32 / union as f32
+    }
+}
+
+/// Fuse the scores in `columns` by reciprocal rank fusion.
+///
+/// Each column holds the scores of all genes by one method.  The genes are ranked by
+/// each column from the best score, tied genes share the best of their ranks, and the
+/// fused score of a gene is the sum of `1 / (RRF_K + rank)` over the columns.
+#[allow(clippy::cast_precision_loss)]
+pub fn reciprocal_rank_fusion(columns: &[Vec<f32>]) -> Vec<f32> {
+    let len = columns.first().map_or(0, Vec::len);
+    let mut result = vec![0.0; len];
+    for column in columns {
+        let mut order = (0..column.len()).collect::<Vec<_>>();
+        order.sort_by(|a, b| column[*b].total_cmp(&column[*a]));
+        let mut rank = 0;
+        for (pos, &idx) in order.iter().enumerate() {
+            if pos == 0 || column[idx].total_cmp(&column[order[pos - 1]]).is_ne() {
+                rank = pos + 1;
+            }
+            result[idx] += 1.0 / (RRF_K + rank as f32);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use hpo::{builder::Builder, term::HpoGroup, HpoTermId, Ontology};
+
+    use super::EnsembleMethod;
+    use crate::common::SimilarityMethod;
+
+    /// Build an ontology with "Left" and "Right" below the root and "Eye" below "Left".
+    fn build_ontology() -> Ontology {
+        let mut builder = Builder::new();
+        builder.new_term("All", 1u32);
+        builder.new_term("Left", 2u32);
+        builder.new_term("Right", 3u32);
+        builder.new_term("Eye", 4u32);
+        let mut builder = builder.terms_complete();
+        builder.add_parent(1u32, 2u32).expect("term must exist");
+        builder.add_parent(1u32, 3u32).expect("term must exist");
+        builder.add_parent(2u32, 4u32).expect("term must exist");
+        builder
+            .connect_all_terms()
+            .calculate_information_content()
+            .expect("could not compute IC")
+            .build_minimal()
+    }
+
+    #[test]
+    fn from_str() -> Result<(), anyhow::Error> {
+        assert_eq!(
+            "lin".parse::<EnsembleMethod>()?,
+            EnsembleMethod::Similarity(SimilarityMethod::Lin)
+        );
+        assert_eq!(
+            "jaccard".parse::<EnsembleMethod>()?,
+            EnsembleMethod::Jaccard
+        );
+        assert!("ensemble".parse::<EnsembleMethod>().is_err());
+        assert_eq!(EnsembleMethod::Jaccard.to_string(), "jaccard");
+
+        Ok(())
+    }
+
+    #[test]
+    fn jaccard() {
+        let hpo = build_ontology();
+        let group = |ids: &[u32]| {
+            ids.iter()
+                .map(|id| HpoTermId::from_u32(*id))
+                .collect::<HpoGroup>()
+        };
+
+        // {All, Left, Eye} and {All, Left} share two of three terms.
+        let score = super::jaccard(&group(&[4]), &group(&[2]), &hpo);
+        assert!((score - 2.0 / 3.0).abs() < 1e-6);
+        let score = super::jaccard(&group(&[4]), &group(&[3]), &hpo);
+        assert!((score - 1.0 / 4.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn reciprocal_rank_fusion() {
+        let k = super::RRF_K;
+        let fused = super::reciprocal_rank_fusion(&[vec![3.0, 2.0, 2.0], vec![0.1, 0.3, 0.2]]);
+
+        let expected = [
+            1.0 / (k + 1.0) + 1.0 / (k + 3.0),
+            1.0 / (k + 2.0) + 1.0 / (k + 1.0),
+            1.0 / (k + 2.0) + 1.0 / (k + 2.0),
+        ];
+        for (fused, expected) in fused.iter().zip(expected) {
+            assert!((fused - expected).abs() < 1e-6);
+        }
+    }
+}
@@ -0,0 +1,555 @@
+//! Information content tables that are not provided by the `hpo` crate.
+
+use std::collections::HashMap;
+
+use hpo::{
+    annotations::{Disease, Gene},
+    similarity::{Builtins, Similarity},
+    term::HpoGroup,
+    HpoTerm, HpoTermId, Ontology,
+};
+
+use strum::IntoEnumIterator as _;
+
+use crate::common::{AnnotationPropagation, IcBasedOn, SimilarityMethod};
+
+/// Term ID of the root term "All" (`HP:0000001`).
+const ROOT: u32 = 1;
+
+/// Information content values per term, computed at load time.
+#[derive(Debug, Clone, Default)]
+pub struct IcTable {
+    /// The information content for each term.
+    values: HashMap<HpoTermId, f32>,
+}
+
+impl IcTable {
+    /// Compute the information content based on the union of OMIM and ORPHA diseases.
+    ///
+    /// Annotations in the `hpo` crate are propagated to all ancestors so the counts of the
+    /// terms can be used directly.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn from_combined_diseases(ontology: &Ontology) -> Self {
+        let total = ontology.omim_diseases().count() + ontology.orpha_diseases().count();
+        let values = ontology
+            .hpos()
+            .map(|term| {
+                let count = term.omim_disease_ids().len() + term.orpha_disease_ids().len();
+                (term.id(), Self::calculate(total as f32, count as f32))
+            })
+            .collect();
+        Self { values }
+    }
+
+    /// Compute the information content from direct annotations only.
+    ///
+    /// In contrast to the `hpo` crate, annotations are not propagated to the ancestors of
+    /// the annotated terms.  Returns `None` for `IcBasedOn::Custom`.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn from_direct_annotations(ontology: &Ontology, ic_based_on: IcBasedOn) -> Option<Self> {
+        if ic_based_on == IcBasedOn::Custom {
+            return None;
+        }
+
+        let mut annotations: Vec<&HpoGroup> = Vec::new();
+        if ic_based_on == IcBasedOn::Gene {
+            annotations.extend(ontology.genes().map(Gene::hpo_terms));
+        }
+        if matches!(ic_based_on, IcBasedOn::Omim | IcBasedOn::Combined) {
+            annotations.extend(ontology.omim_diseases().map(Disease::hpo_terms));
+        }
+        if matches!(ic_based_on, IcBasedOn::Orpha | IcBasedOn::Combined) {
+            annotations.extend(ontology.orpha_diseases().map(Disease::hpo_terms));
+        }
+
+        let mut counts: HashMap<HpoTermId, usize> = HashMap::new();
+        for term_ids in &annotations {
+            for term_id in *term_ids {
+                *counts.entry(term_id).or_default() += 1;
+            }
+        }
+        let total = annotations.len() as f32;
+        let values = ontology
+            .hpos()
+            .map(|term| {
+                let count = counts.get(&term.id()).copied().unwrap_or_default();
+                (term.id(), Self::calculate(total, count as f32))
+            })
+            .collect();
+        Some(Self { values })
+    }
+
+    /// Compute the information content `-ln(count / total)`, zero for empty counts.
+    fn calculate(total: f32, count: f32) -> f32 {
+        if total == 0.0 || count == 0.0 {
+            0.0
+        } else {
+            -(count / total).ln()
+        }
+    }
+
+    /// Load externally computed information content values from a TSV file.
+    ///
+    /// The file must have a header and the columns `term_id` and `ic`.
+    ///
+    /// # Errors
+    ///
+    /// In the case that the file could not be read or contains invalid term IDs.
+    pub fn from_path<P: AsRef<std::path::Path>>(path: P) -> Result<Self, anyhow::Error> {
+        let mut rdr = csv::ReaderBuilder::new()
+            .delimiter(b'\t')
+            .has_headers(true)
+            .from_path(path.as_ref())?;
+        let mut values = HashMap::new();
+        for result in rdr.deserialize() {
+            let entry: CustomIcEntry = result?;
+            let term_id = crate::common::parse_term_id(&entry.term_id)?;
+            values.insert(term_id, entry.ic);
+        }
+        Ok(Self { values })
+    }
+
+    /// Return the number of terms in the table.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Return whether the table is empty.
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Return the information content of the given term, zero if unknown.
+    pub fn get(&self, term_id: &HpoTermId) -> f32 {
+        self.values.get(term_id).copied().unwrap_or_default()
+    }
+
+    /// Return the range of the information content in the table.
+    pub fn range(&self) -> IcRange {
+        IcRange::new(
+            self.get(&HpoTermId::from_u32(ROOT)),
+            self.values.values().copied(),
+        )
+    }
+}
+
+/// Range of the information content of the terms, computed at load time.
+///
+/// Every pair of terms has the root as common ancestor, so the information content of
+/// the root and the maximal information content bound the Resnik similarity.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct IcRange {
+    /// The information content of the root term.
+    pub min: f32,
+    /// The maximal information content of any term.
+    pub max: f32,
+}
+
+impl IcRange {
+    /// Construct from the information content of the root and of all terms.
+    fn new<I: IntoIterator<Item = f32>>(root: f32, values: I) -> Self {
+        Self {
+            min: root,
+            max: values.into_iter().fold(root, f32::max),
+        }
+    }
+}
+
+/// Record of the custom information content TSV file.
+#[derive(Debug, Clone, serde::Deserialize)]
+struct CustomIcEntry {
+    /// The HPO term ID.
+    term_id: String,
+    /// The information content.
+    ic: f32,
+}
+
+/// All information content tables computed at load time.
+#[derive(Debug, Clone, Default)]
+pub struct IcTables {
+    /// Information content based on the union of OMIM and ORPHA diseases.
+    pub combined: IcTable,
+    /// Externally computed information content, if loaded.
+    pub custom: Option<IcTable>,
+    /// Information content from direct annotations only, for all but the custom base.
+    pub direct: HashMap<IcBasedOn, IcTable>,
+    /// Range of the information content for each base and propagation.
+    pub ranges: HashMap<(IcBasedOn, AnnotationPropagation), IcRange>,
+}
+
+impl IcTables {
+    /// Compute all tables for the given ontology.
+    pub fn new(ontology: &Ontology) -> Self {
+        let mut result = Self {
+            combined: IcTable::from_combined_diseases(ontology),
+            custom: None,
+            direct: [
+                IcBasedOn::Gene,
+                IcBasedOn::Omim,
+                IcBasedOn::Orpha,
+                IcBasedOn::Combined,
+            ]
+            .into_iter()
+            .filter_map(|ic_based_on| {
+                IcTable::from_direct_annotations(ontology, ic_based_on)
+                    .map(|table| (ic_based_on, table))
+            })
+            .collect(),
+            ranges: HashMap::new(),
+        };
+
+        let root = ontology.hpo(HpoTermId::from_u32(ROOT));
+        for ic_based_on in IcBasedOn::iter().filter(|ic_based_on| *ic_based_on != IcBasedOn::Custom)
+        {
+            for propagation in AnnotationPropagation::iter() {
+                let range = IcRange::new(
+                    root.map_or(0.0, |root| result.term_ic(&root, ic_based_on, propagation)),
+                    ontology
+                        .hpos()
+                        .map(|term| result.term_ic(&term, ic_based_on, propagation)),
+                );
+                result.ranges.insert((ic_based_on, propagation), range);
+            }
+        }
+        result
+    }
+
+    /// Set the externally computed information content table.
+    #[must_use]
+    pub fn with_custom(self, custom: Option<IcTable>) -> Self {
+        let mut ranges = self.ranges;
+        ranges.retain(|(ic_based_on, _), _| *ic_based_on != IcBasedOn::Custom);
+        if let Some(custom) = &custom {
+            for propagation in AnnotationPropagation::iter() {
+                ranges.insert((IcBasedOn::Custom, propagation), custom.range());
+            }
+        }
+        Self {
+            custom,
+            ranges,
+            ..self
+        }
+    }
+
+    /// Return the range of the information content for the given base and propagation.
+    pub fn range(&self, ic_based_on: IcBasedOn, propagation: AnnotationPropagation) -> IcRange {
+        self.ranges
+            .get(&(ic_based_on, propagation))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Return the table for the given information content base, if not built into `hpo`.
+    ///
+    /// The custom information content is used as is, regardless of `propagation`.
+    pub fn table(
+        &self,
+        ic_based_on: IcBasedOn,
+        propagation: AnnotationPropagation,
+    ) -> Option<&IcTable> {
+        match ic_based_on {
+            IcBasedOn::Custom => self.custom.as_ref(),
+            _ if !propagation.is_propagated() => self.direct.get(&ic_based_on),
+            IcBasedOn::Gene | IcBasedOn::Omim | IcBasedOn::Orpha => None,
+            IcBasedOn::Combined => Some(&self.combined),
+        }
+    }
+
+    /// Return the information content of `term` for the given information content base.
+    pub fn term_ic(
+        &self,
+        term: &HpoTerm,
+        ic_based_on: IcBasedOn,
+        propagation: AnnotationPropagation,
+    ) -> f32 {
+        match (self.table(ic_based_on, propagation), ic_based_on.to_kind()) {
+            (Some(table), _) => table.get(&term.id()),
+            (None, Some(kind)) => term.information_content().get_kind(&kind),
+            (None, None) => 0.0,
+        }
+    }
+
+    /// Ensure that the given information content base can be used.
+    ///
+    /// # Errors
+    ///
+    /// In the case that custom information content is requested but was not loaded.
+    pub fn ensure_available(&self, ic_based_on: IcBasedOn) -> Result<(), anyhow::Error> {
+        if ic_based_on == IcBasedOn::Custom && self.custom.is_none() {
+            anyhow::bail!("custom information content requested but none was loaded")
+        }
+        Ok(())
+    }
+}
+
+/// Pairwise similarity using the information content from an `IcTable`.
+///
+/// The formulas mirror the ones in `hpo::similarity`.
+#[derive(Debug, Clone, Copy)]
+pub struct TableSimilarity<'a> {
+    /// The similarity method to use.
+    method: SimilarityMethod,
+    /// The information content table.
+    table: &'a IcTable,
+}
+
+impl<'a> TableSimilarity<'a> {
+    /// Construct with the given method and table.
+    pub fn new(method: SimilarityMethod, table: &'a IcTable) -> Self {
+        Self { method, table }
+    }
+
+    /// Information content of the most informative common ancestor.
+    fn resnik(&self, a: &HpoTerm, b: &HpoTerm) -> f32 {
+        a.all_common_ancestor_ids(b)
+            .iter()
+            .map(|term_id| self.table.get(&term_id))
+            .fold(0.0, f32::max)
+    }
+
+    /// Lin similarity.
+    fn lin(&self, a: &HpoTerm, b: &HpoTerm) -> f32 {
+        let ic_combined = self.table.get(&a.id()) + self.table.get(&b.id());
+        if ic_combined == 0.0 {
+            0.0
+        } else {
+            2.0 * self.resnik(a, b) / ic_combined
+        }
+    }
+}
+
+impl Similarity for TableSimilarity<'_> {
+    fn calculate(&self, a: &HpoTerm, b: &HpoTerm) -> f32 {
+        match self.method {
+            SimilarityMethod::DistanceGene => {
+                Builtins::Distance(hpo::term::InformationContentKind::Gene).calculate(a, b)
+            }
+            SimilarityMethod::GraphIc => {
+                if a.id() == b.id() {
+                    return 1.0;
+                }
+                let ic_union: f32 = a
+                    .all_union_ancestor_ids(b)
+                    .iter()
+                    .map(|term_id| self.table.get(&term_id))
+                    .sum();
+                if ic_union == 0.0 {
+                    return 0.0;
+                }
+                let ic_common: f32 = a
+                    .all_common_ancestor_ids(b)
+                    .iter()
+                    .map(|term_id| self.table.get(&term_id))
+                    .sum();
+                ic_common / ic_union
+            }
+            SimilarityMethod::InformationCoefficient => {
+                self.lin(a, b) * (1.0 - (1.0 / (1.0 + self.resnik(a, b))))
+            }
+            SimilarityMethod::Jc => {
+                if a.id() == b.id() {
+                    return 1.0;
+                }
+                let ic_a = self.table.get(&a.id());
+                let ic_b = self.table.get(&b.id());
+                if ic_a == 0.0 || ic_b == 0.0 {
+                    return 0.0;
+                }
+                1.0 / (ic_a + ic_b - 2.0 * self.resnik(a, b) + 1.0)
+            }
+            SimilarityMethod::Lin => self.lin(a, b),
+            SimilarityMethod::Mutation => {
+                if a.id() == b.id() {
+                    return 1.0;
+                }
+                // Jaccard index of the (combined) disease annotations.
+                let omim_all = (a.omim_disease_ids() | b.omim_disease_ids()).len();
+                let omim_common = (a.omim_disease_ids() & b.omim_disease_ids()).len();
+                let orpha_all = (a.orpha_disease_ids() | b.orpha_disease_ids()).len();
+                let orpha_common = (a.orpha_disease_ids() & b.orpha_disease_ids()).len();
+                let all = omim_all + orpha_all;
+                if all == 0 {
+                    0.0
+                } else {
+                    #[allow(clippy::cast_precision_loss)]
+                    let result = (omim_common + orpha_common) as f32 / all as f32;
+                    result
+                }
+            }
+            SimilarityMethod::Relevance => self.lin(a, b) * (1.0 - (-self.resnik(a, b)).exp()),
+            SimilarityMethod::Resnik => self.resnik(a, b),
+        }
+    }
+}
+
+/// Pairwise similarity with either built-in or table-based information content.
+#[derive(Debug, Clone, Copy)]
+pub enum PairwiseSimilarity<'a> {
+    /// Use the information content from the `hpo` crate.
+    Builtin(Builtins),
+    /// Use the information content from a table computed at load time.
+    Table(TableSimilarity<'a>),
+}
+
+impl Similarity for PairwiseSimilarity<'_> {
+    fn calculate(&self, a: &HpoTerm, b: &HpoTerm) -> f32 {
+        match self {
+            PairwiseSimilarity::Builtin(builtin) => builtin.calculate(a, b),
+            PairwiseSimilarity::Table(table) => table.calculate(a, b),
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use hpo::{
+        annotations::{OmimDiseaseId, OrphaDiseaseId},
+        builder::Builder,
+        similarity::Similarity as _,
+        HpoTermId, Ontology,
+    };
+
+    use crate::common::{AnnotationPropagation, IcBasedOn, SimilarityMethod};
+
+    /// Build a small ontology with the structure
+    ///
+    /// ```text
+    /// HP:0000001 (All)
+    /// +-- HP:0000002 (Left)
+    /// |   +-- HP:0000004 (Left-left)
+    /// |   +-- HP:0000005 (Left-right)
+    /// +-- HP:0000003 (Right)
+    /// ```
+    ///
+    /// with OMIM diseases 1 and 2 on `HP:0000004` and `HP:0000005`, respectively, and
+    /// ORPHA disease 3 on `HP:0000003`.
+    pub fn small_ontology() -> Ontology {
+        let mut builder = Builder::new();
+        builder.new_term("All", 1u32);
+        builder.new_term("Left", 2u32);
+        builder.new_term("Right", 3u32);
+        builder.new_term("Left-left", 4u32);
+        builder.new_term("Left-right", 5u32);
+        let mut builder = builder.terms_complete();
+        builder.add_parent(1u32, 2u32).expect("term must exist");
+        builder.add_parent(1u32, 3u32).expect("term must exist");
+        builder.add_parent(2u32, 4u32).expect("term must exist");
+        builder.add_parent(2u32, 5u32).expect("term must exist");
+        let mut builder = builder.connect_all_terms();
+        builder
+            .annotate_omim_disease(OmimDiseaseId::from(1), "Disease 1", 4u32.into())
+            .expect("term must exist");
+        builder
+            .annotate_omim_disease(OmimDiseaseId::from(2), "Disease 2", 5u32.into())
+            .expect("term must exist");
+        builder
+            .annotate_orpha_disease(OrphaDiseaseId::from(3), "Disease 3", 3u32.into())
+            .expect("term must exist");
+        builder
+            .calculate_information_content()
+            .expect("could not compute IC")
+            .build_minimal()
+    }
+
+    #[test]
+    fn combined_ic() {
+        let ontology = small_ontology();
+        let table = super::IcTable::from_combined_diseases(&ontology);
+
+        assert!(table.get(&HpoTermId::from_u32(1)).abs() < 1e-6);
+        assert!((table.get(&HpoTermId::from_u32(2)) - (3f32 / 2f32).ln()).abs() < 1e-6);
+        assert!((table.get(&HpoTermId::from_u32(3)) - 3f32.ln()).abs() < 1e-6);
+        assert!((table.get(&HpoTermId::from_u32(4)) - 3f32.ln()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn direct_vs_propagated_ic() {
+        let ontology = small_ontology();
+        let tables = super::IcTables::new(&ontology);
+        let left = ontology.hpo(2u32).expect("term must exist");
+        let left_left = ontology.hpo(4u32).expect("term must exist");
+
+        // "Left" only inherits the annotations of its children.
+        let propagated =
+            tables.term_ic(&left, IcBasedOn::Combined, AnnotationPropagation::Ancestors);
+        let direct = tables.term_ic(&left, IcBasedOn::Combined, AnnotationPropagation::None);
+        assert!((propagated - (3f32 / 2f32).ln()).abs() < 1e-6);
+        assert!(direct.abs() < 1e-6);
+
+        // Leaves are unaffected.
+        for propagation in [
+            AnnotationPropagation::Ancestors,
+            AnnotationPropagation::None,
+        ] {
+            let ic = tables.term_ic(&left_left, IcBasedOn::Combined, propagation);
+            assert!((ic - 3f32.ln()).abs() < 1e-6);
+        }
+
+        assert!(tables
+            .table(IcBasedOn::Omim, AnnotationPropagation::Ancestors)
+            .is_none());
+        assert!(tables
+            .table(IcBasedOn::Omim, AnnotationPropagation::None)
+            .is_some());
+    }
+
+    #[test]
+    fn custom_ic_from_path() -> Result<(), anyhow::Error> {
+        let tmp_dir = temp_testdir::TempDir::default();
+        let path = tmp_dir.join("custom_ic.tsv");
+        std::fs::write(&path, "term_id\tic\nHP:0000002\t1.5\nHP:0000004\t3.25\n")?;
+
+        let table = super::IcTable::from_path(&path)?;
+
+        assert_eq!(table.len(), 2);
+        assert!((table.get(&HpoTermId::from_u32(2)) - 1.5).abs() < 1e-6);
+        assert!((table.get(&HpoTermId::from_u32(4)) - 3.25).abs() < 1e-6);
+        assert!(table.get(&HpoTermId::from_u32(1)).abs() < 1e-6);
+
+        Ok(())
+    }
+
+    #[test]
+    fn ic_ranges() {
+        let ontology = small_ontology();
+        let tables = super::IcTables::new(&ontology);
+
+        let range = tables.range(IcBasedOn::Combined, AnnotationPropagation::Ancestors);
+        assert!(range.min.abs() < 1e-6);
+        assert!((range.max - 3f32.ln()).abs() < 1e-6);
+
+        let mut custom = super::IcTable::default();
+        custom.values.insert(HpoTermId::from_u32(1), 0.5);
+        custom.values.insert(HpoTermId::from_u32(4), 2.0);
+        let tables = tables.with_custom(Some(custom));
+        assert_eq!(
+            tables.range(IcBasedOn::Custom, AnnotationPropagation::None),
+            super::IcRange { min: 0.5, max: 2.0 }
+        );
+    }
+
+    #[test]
+    fn custom_ic_unavailable() {
+        let tables = super::IcTables::default();
+
+        assert!(tables
+            .ensure_available(crate::common::IcBasedOn::Custom)
+            .is_err());
+        assert!(tables
+            .ensure_available(crate::common::IcBasedOn::Combined)
+            .is_ok());
+    }
+
+    #[test]
+    fn combined_resnik() {
+        let ontology = small_ontology();
+        let table = super::IcTable::from_combined_diseases(&ontology);
+        let sim = super::TableSimilarity::new(SimilarityMethod::Resnik, &table);
+        let left_left = ontology.hpo(4u32).expect("term must exist");
+        let left_right = ontology.hpo(5u32).expect("term must exist");
+        let right = ontology.hpo(3u32).expect("term must exist");
+
+        assert!((sim.calculate(&left_left, &left_right) - (3f32 / 2f32).ln()).abs() < 1e-6);
+        assert!(sim.calculate(&left_left, &right).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,204 @@
+//! Modes of inheritance of diseases from the annotations below "Mode of inheritance".
+
+use std::{collections::HashMap, str::FromStr};
+
+use hpo::{
+    annotations::{Disease as _, OmimDiseaseId},
+    term::HpoGroup,
+    HpoTermId, Ontology,
+};
+use strum::{EnumIter, IntoEnumIterator};
+
+/// Mode of inheritance that diseases can be filtered by.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    EnumIter,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    derive_more::Display,
+    serde::Serialize,
+    serde::Deserialize,
+    utoipa::ToSchema,
+)]
+pub enum InheritanceMode {
+    /// Autosomal dominant inheritance (`HP:0000006`).
+    #[display("AD")]
+    #[serde(rename = "AD")]
+    AutosomalDominant,
+    /// Autosomal recessive inheritance (`HP:0000007`).
+    #[display("AR")]
+    #[serde(rename = "AR")]
+    AutosomalRecessive,
+    /// X-linked inheritance (`HP:0001417`), dominant or recessive.
+    #[display("XL")]
+    #[serde(rename = "XL")]
+    XLinked,
+    /// Y-linked inheritance (`HP:0001450`).
+    #[display("YL")]
+    #[serde(rename = "YL")]
+    YLinked,
+    /// Mitochondrial inheritance (`HP:0001427`).
+    #[display("MT")]
+    #[serde(rename = "MT")]
+    Mitochondrial,
+}
+
+impl InheritanceMode {
+    /// Return the ID of the term for the mode of inheritance.
+    pub fn term_id(self) -> HpoTermId {
+        HpoTermId::from_u32(match self {
+            InheritanceMode::AutosomalDominant => 6,
+            InheritanceMode::AutosomalRecessive => 7,
+            InheritanceMode::XLinked => 1417,
+            InheritanceMode::YLinked => 1450,
+            InheritanceMode::Mitochondrial => 1427,
+        })
+    }
+}
+
+impl FromStr for InheritanceMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        InheritanceMode::iter()
+            .find(|m| m.to_string().eq_ignore_ascii_case(s))
+            .ok_or(anyhow::anyhow!("unknown mode of inheritance: {}", s))
+    }
+}
+
+/// Return the modes of inheritance of the annotated `terms`, sorted.
+///
+/// A term gives a mode if it is the mode's term or one of its descendants.
+pub fn inheritance_modes(ontology: &Ontology, terms: &HpoGroup) -> Vec<InheritanceMode> {
+    let mut result = Vec::new();
+    for term in terms.iter().filter_map(|term_id| ontology.hpo(term_id)) {
+        for mode in InheritanceMode::iter() {
+            if term.id() == mode.term_id() || term.all_parent_ids().contains(&mode.term_id()) {
+                result.push(mode);
+            }
+        }
+    }
+    result.sort();
+    result.dedup();
+    result
+}
+
+/// Index of the modes of inheritance of the OMIM diseases, computed at load time.
+#[derive(Debug, Clone, Default)]
+pub struct InheritanceIndex {
+    /// The modes of inheritance by OMIM disease, diseases without any are left out.
+    omim: HashMap<OmimDiseaseId, Vec<InheritanceMode>>,
+}
+
+impl InheritanceIndex {
+    /// Build the index for the given ontology.
+    pub fn new(ontology: &Ontology) -> Self {
+        Self {
+            omim: ontology
+                .omim_diseases()
+                .filter_map(|disease| {
+                    let modes = inheritance_modes(ontology, disease.hpo_terms());
+                    (!modes.is_empty()).then(|| (*disease.id(), modes))
+                })
+                .collect(),
+        }
+    }
+
+    /// Return the modes of inheritance of the OMIM disease, empty if not annotated.
+    pub fn omim_modes(&self, omim_id: &OmimDiseaseId) -> &[InheritanceMode] {
+        self.omim
+            .get(omim_id)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Return whether the OMIM disease has any of the given modes of inheritance.
+    pub fn omim_matches(&self, omim_id: &OmimDiseaseId, modes: &[InheritanceMode]) -> bool {
+        self.omim_modes(omim_id)
+            .iter()
+            .any(|mode| modes.contains(mode))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use hpo::{annotations::OmimDiseaseId, builder::Builder, Ontology};
+
+    use super::InheritanceMode;
+
+    /// Build an ontology with the modes of inheritance below "All", "X-linked recessive"
+    /// below "X-linked", and a phenotype term.
+    ///
+    /// OMIM disease 1 is autosomal recessive, disease 2 X-linked recessive, and disease 3
+    /// has no mode of inheritance.
+    fn build_ontology() -> Ontology {
+        let mut builder = Builder::new();
+        builder.new_term("All", 1u32);
+        builder.new_term("Mode of inheritance", 5u32);
+        builder.new_term("Autosomal dominant inheritance", 6u32);
+        builder.new_term("Autosomal recessive inheritance", 7u32);
+        builder.new_term("Phenotypic abnormality", 118u32);
+        builder.new_term("X-linked inheritance", 1417u32);
+        builder.new_term("X-linked recessive inheritance", 1419u32);
+        let mut builder = builder.terms_complete();
+        builder.add_parent(1u32, 5u32).expect("term must exist");
+        builder.add_parent(1u32, 118u32).expect("term must exist");
+        builder.add_parent(5u32, 6u32).expect("term must exist");
+        builder.add_parent(5u32, 7u32).expect("term must exist");
+        builder.add_parent(5u32, 1417u32).expect("term must exist");
+        builder
+            .add_parent(1417u32, 1419u32)
+            .expect("term must exist");
+        let mut builder = builder.connect_all_terms();
+        for (disease, term) in [(1u32, 7u32), (1, 118), (2, 1419), (3, 118)] {
+            builder
+                .annotate_omim_disease(
+                    OmimDiseaseId::from(disease),
+                    &format!("Disease {disease}"),
+                    term.into(),
+                )
+                .expect("term must exist");
+        }
+        builder
+            .calculate_information_content()
+            .expect("could not compute IC")
+            .build_minimal()
+    }
+
+    #[test]
+    fn from_str() -> Result<(), anyhow::Error> {
+        assert_eq!(
+            "ar".parse::<InheritanceMode>()?,
+            InheritanceMode::AutosomalRecessive
+        );
+        assert_eq!("XL".parse::<InheritanceMode>()?, InheritanceMode::XLinked);
+        assert!("XR".parse::<InheritanceMode>().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn inheritance_index() {
+        let index = super::InheritanceIndex::new(&build_ontology());
+
+        assert_eq!(
+            index.omim_modes(&OmimDiseaseId::from(1)),
+            &[InheritanceMode::AutosomalRecessive]
+        );
+        assert_eq!(
+            index.omim_modes(&OmimDiseaseId::from(2)),
+            &[InheritanceMode::XLinked]
+        );
+        assert!(index.omim_modes(&OmimDiseaseId::from(3)).is_empty());
+
+        let modes = [InheritanceMode::AutosomalDominant, InheritanceMode::XLinked];
+        assert!(!index.omim_matches(&OmimDiseaseId::from(1), &modes));
+        assert!(index.omim_matches(&OmimDiseaseId::from(2), &modes));
+        assert!(!index.omim_matches(&OmimDiseaseId::from(3), &modes));
+    }
+}
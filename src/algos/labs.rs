@@ -0,0 +1,184 @@
+//! Inference of HPO terms from structured lab observations.
+//!
+//! An observation names the analyte, by LOINC code (e.g., `1742-6`) or by name (e.g.,
+//! `ALT`), and the direction of the abnormal value.  Implementations of `LabInference`
+//! map observations to HPO terms; `LabRules` does so by a TSV rules file.
+
+use hpo::HpoTermId;
+
+/// The direction of an abnormal lab value.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    derive_more::Display,
+    serde::Serialize,
+    serde::Deserialize,
+    utoipa::ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum LabDirection {
+    /// The value is below the reference range.
+    #[display("low")]
+    Low,
+    /// The value is above the reference range.
+    #[display("high")]
+    High,
+    /// The value is abnormal, e.g., a positive screening test.
+    #[display("abnormal")]
+    Abnormal,
+}
+
+/// A structured lab observation.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct LabObservation {
+    /// The analyte as LOINC code, e.g., `1742-6`, or name, e.g., `ALT`.
+    pub analyte: String,
+    /// The direction of the abnormal value.
+    pub direction: LabDirection,
+}
+
+/// Extension point for inferring HPO terms from lab observations.
+///
+/// The server holds one implementation, `LabRules` unless configured otherwise.
+pub trait LabInference: Send + Sync {
+    /// Return the IDs of the HPO terms implied by `observation`, empty if none.
+    fn infer(&self, observation: &LabObservation) -> Vec<HpoTermId>;
+}
+
+/// A record of the rules TSV file.
+#[derive(Debug, Clone, serde::Deserialize)]
+struct Record {
+    /// The analyte as LOINC code or name.
+    analyte: String,
+    /// The direction of the abnormal value.
+    direction: LabDirection,
+    /// The implied HPO term.
+    hpo_id: String,
+}
+
+/// Normalize the `analyte` for matching, ignoring case and surrounding whitespace.
+fn normalize_analyte(analyte: &str) -> String {
+    analyte.trim().to_lowercase()
+}
+
+/// Rules mapping analyte and direction to HPO terms, e.g., high `ALT` to `HP:0031964`.
+#[derive(Debug, Clone, Default)]
+pub struct LabRules {
+    /// The implied terms by normalized analyte and direction.
+    rules: std::collections::HashMap<(String, LabDirection), Vec<HpoTermId>>,
+}
+
+impl LabRules {
+    /// Load the rules from the TSV file at `path`.
+    ///
+    /// The file must have a header and the columns `analyte`, `direction` (`low`, `high`,
+    /// or `abnormal`), and `hpo_id`.  An analyte may be listed under its LOINC code and
+    /// its names in separate rules.
+    ///
+    /// # Errors
+    ///
+    /// In the case that the file could not be read or a rule is malformed.
+    pub fn from_path<P: AsRef<std::path::Path>>(path: P) -> Result<Self, anyhow::Error> {
+        let mut rdr = csv::ReaderBuilder::new()
+            .delimiter(b'\t')
+            .has_headers(true)
+            .comment(Some(b'#'))
+            .from_path(path.as_ref())?;
+        let mut rules = std::collections::HashMap::<_, Vec<_>>::new();
+        for (i, record) in rdr.deserialize().enumerate() {
+            let record: Record =
+                record.map_err(|e| anyhow::anyhow!("invalid lab rule {}: {}", i + 1, e))?;
+            let term_id = crate::common::parse_term_id(&record.hpo_id)
+                .map_err(|e| anyhow::anyhow!("invalid lab rule {}: {}", i + 1, e))?;
+            let term_ids = rules
+                .entry((normalize_analyte(&record.analyte), record.direction))
+                .or_default();
+            if !term_ids.contains(&term_id) {
+                term_ids.push(term_id);
+            }
+        }
+        Ok(Self { rules })
+    }
+
+    /// Return the number of analyte and direction pairs with rules.
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+
+    /// Return whether there are no rules.
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+}
+
+impl LabInference for LabRules {
+    fn infer(&self, observation: &LabObservation) -> Vec<HpoTermId> {
+        self.rules
+            .get(&(
+                normalize_analyte(&observation.analyte),
+                observation.direction,
+            ))
+            .cloned()
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use hpo::HpoTermId;
+
+    use super::{LabDirection, LabInference as _, LabObservation, LabRules};
+
+    #[test]
+    fn from_path() -> Result<(), anyhow::Error> {
+        let tmp_dir = temp_testdir::TempDir::default();
+        let path = tmp_dir.join("lab_rules.tsv");
+        std::fs::write(
+            &path,
+            "analyte\tdirection\thpo_id\n\
+             # alanine aminotransferase\n\
+             1742-6\thigh\tHP:0031964\n\
+             ALT\thigh\tHP:0031964\n\
+             ALT\thigh\tHP:0031964\n\
+             2951-2\tlow\tHP:0002902\n",
+        )?;
+
+        let rules = LabRules::from_path(&path)?;
+        let observation = |analyte: &str, direction| LabObservation {
+            analyte: analyte.to_string(),
+            direction,
+        };
+
+        assert_eq!(rules.len(), 3);
+        assert_eq!(
+            rules.infer(&observation(" alt ", LabDirection::High)),
+            [HpoTermId::from_u32(31_964)]
+        );
+        assert_eq!(
+            rules.infer(&observation("1742-6", LabDirection::High)),
+            [HpoTermId::from_u32(31_964)]
+        );
+        assert!(rules
+            .infer(&observation("ALT", LabDirection::Low))
+            .is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn from_path_invalid() -> Result<(), anyhow::Error> {
+        let tmp_dir = temp_testdir::TempDir::default();
+        let path = tmp_dir.join("lab_rules.tsv");
+
+        std::fs::write(&path, "analyte\tdirection\thpo_id\nALT\tup\tHP:0031964\n")?;
+        assert!(LabRules::from_path(&path).is_err());
+        std::fs::write(&path, "analyte\tdirection\thpo_id\nALT\thigh\tALT-high\n")?;
+        assert!(LabRules::from_path(&path).is_err());
+
+        Ok(())
+    }
+}
@@ -1,3 +1,16 @@
 //! Phenotype-related algorithms.
 
+pub mod ensemble;
+pub mod ic;
+pub mod inheritance;
+pub mod labs;
+pub mod modifiers;
+pub mod onset;
 pub mod phenomizer;
+pub mod qc;
+pub mod routes;
+pub mod sex;
+pub mod simulation;
+pub mod term_diseases;
+pub mod term_expr;
+pub mod term_genes;
@@ -0,0 +1,111 @@
+//! Clinical modifiers attached to query terms, e.g., "Severe" (`HP:0012828`).
+
+use hpo::{HpoTermId, Ontology};
+
+/// "Clinical modifier" (`HP:0012823`), the ancestor of all modifier terms.
+const CLINICAL_MODIFIER: u32 = 12_823;
+
+/// Severity modifiers with the weight of the contribution of the modified term.
+const SEVERITY_WEIGHTS: &[(u32, f32)] = &[
+    (12_827, 0.5),  // Borderline
+    (12_825, 0.75), // Mild
+    (12_826, 1.0),  // Moderate
+    (12_828, 1.25), // Severe
+    (12_829, 1.5),  // Profound
+];
+
+/// Split a query term with modifiers, e.g., `HP:0001249[HP:0012828]`, into the term and
+/// its modifiers.
+///
+/// Several modifiers are separated by `;`, e.g., `HP:0001249[HP:0012828;HP:0003680]`.
+///
+/// # Errors
+///
+/// In the case that the brackets are not balanced or empty or a modifier is empty.
+pub fn split_modifiers(value: &str) -> Result<(&str, Vec<&str>), anyhow::Error> {
+    let Some((term, rest)) = value.split_once('[') else {
+        return Ok((value, Vec::new()));
+    };
+    let modifiers = rest
+        .strip_suffix(']')
+        .filter(|modifiers| !modifiers.is_empty() && !modifiers.contains(['[', ']']))
+        .ok_or_else(|| anyhow::anyhow!("malformed modifiers in term: {}", value))?;
+    let modifiers = modifiers.split(';').map(str::trim).collect::<Vec<_>>();
+    if modifiers.iter().any(|modifier| modifier.is_empty()) {
+        anyhow::bail!("empty modifier in term: {}", value);
+    }
+    Ok((term, modifiers))
+}
+
+/// Return whether the term with `term_id` is a clinical modifier.
+pub fn is_clinical_modifier(ontology: &Ontology, term_id: HpoTermId) -> bool {
+    ontology.hpo(term_id).is_some_and(|term| {
+        term.all_parent_ids()
+            .contains(&HpoTermId::from_u32(CLINICAL_MODIFIER))
+    })
+}
+
+/// Return the weight of the contribution of a term with the given `modifiers`.
+///
+/// This is the product of the weights of the severity modifiers, other modifiers weigh 1.
+pub fn modifier_weight(modifiers: &[HpoTermId]) -> f32 {
+    modifiers
+        .iter()
+        .filter_map(|modifier| {
+            SEVERITY_WEIGHTS
+                .iter()
+                .find(|(term_id, _)| HpoTermId::from_u32(*term_id) == *modifier)
+                .map(|(_, weight)| *weight)
+        })
+        .product()
+}
+
+#[cfg(test)]
+mod test {
+    use hpo::HpoTermId;
+
+    #[test]
+    fn split_modifiers() -> Result<(), anyhow::Error> {
+        assert_eq!(
+            super::split_modifiers("HP:0001249")?,
+            ("HP:0001249", Vec::new())
+        );
+        assert_eq!(
+            super::split_modifiers("HP:0001249[HP:0012828; HP:0003680]")?,
+            ("HP:0001249", vec!["HP:0012828", "HP:0003680"])
+        );
+        assert!(super::split_modifiers("HP:0001249[HP:0012828").is_err());
+        assert!(super::split_modifiers("HP:0001249[]").is_err());
+        assert!(super::split_modifiers("HP:0001249[HP:0012828;]").is_err());
+        assert!(super::split_modifiers("HP:0001249[ ]").is_err());
+
+        Ok(())
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn split_modifiers_fuzz(value in crate::fuzz::string(24)) {
+            if let Ok((term, modifiers)) = super::split_modifiers(&value) {
+                // The modifiers are recovered from the normalized form.
+                if !modifiers.is_empty() {
+                    let normalized = format!("{}[{}]", term, modifiers.join(";"));
+                    proptest::prop_assert_eq!(
+                        super::split_modifiers(&normalized).ok(),
+                        Some((term, modifiers))
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn modifier_weight() {
+        let severe = HpoTermId::from_u32(12_828);
+        let mild = HpoTermId::from_u32(12_825);
+        let progressive = HpoTermId::from_u32(3_676);
+
+        assert!((super::modifier_weight(&[]) - 1.0).abs() < f32::EPSILON);
+        assert!((super::modifier_weight(&[severe]) - 1.25).abs() < f32::EPSILON);
+        assert!((super::modifier_weight(&[mild, progressive]) - 0.75).abs() < f32::EPSILON);
+    }
+}
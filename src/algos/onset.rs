@@ -0,0 +1,199 @@
+//! Age of onset from the annotations below "Onset" (`HP:0003674`).
+
+use std::collections::HashMap;
+
+use hpo::{annotations::GeneId, term::HpoGroup, HpoTermId, Ontology};
+
+/// Onset terms with the first and last stage they span.
+///
+/// The stages are numbered chronologically: 0 antenatal, 1 congenital, 2 neonatal,
+/// 3 infantile, 4 childhood, 5 juvenile, 6 young adult, 7 middle age, and 8 late.
+const ONSET_TERMS: &[(u32, usize, usize)] = &[
+    (30_674, 0, 0),  // Antenatal onset
+    (11_460, 0, 0),  // Embryonal onset
+    (11_461, 0, 0),  // Fetal onset
+    (3_577, 1, 1),   // Congenital onset
+    (3_623, 2, 2),   // Neonatal onset
+    (410_280, 2, 5), // Pediatric onset
+    (3_593, 3, 3),   // Infantile onset
+    (11_463, 4, 4),  // Childhood onset
+    (3_621, 5, 5),   // Juvenile onset
+    (3_581, 6, 8),   // Adult onset
+    (11_462, 6, 6),  // Young adult onset
+    (3_596, 7, 7),   // Middle age onset
+    (3_584, 8, 8),   // Late onset
+];
+
+/// Onsets that are at least this many stages apart are incompatible.
+pub const INCOMPATIBLE_GAP: usize = 2;
+
+/// Range of onset stages, see `ONSET_TERMS`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OnsetRange {
+    /// The first stage of the range.
+    pub first: usize,
+    /// The last stage of the range.
+    pub last: usize,
+}
+
+impl OnsetRange {
+    /// Return the number of stages between the ranges, zero if they overlap.
+    pub fn gap(self, other: OnsetRange) -> usize {
+        other
+            .first
+            .saturating_sub(self.last)
+            .max(self.first.saturating_sub(other.last))
+    }
+}
+
+/// Return the onset range of the term with `term_id`.
+///
+/// Terms that are not listed in `ONSET_TERMS` get the narrowest range of their listed
+/// ancestors.  Returns `None` for terms that are not below "Onset".
+pub fn onset_range(ontology: &Ontology, term_id: HpoTermId) -> Option<OnsetRange> {
+    let term = ontology.hpo(term_id)?;
+    ONSET_TERMS
+        .iter()
+        .filter(|(onset_id, _, _)| {
+            let onset_id = HpoTermId::from_u32(*onset_id);
+            term.id() == onset_id || term.all_parent_ids().contains(&onset_id)
+        })
+        .map(|&(_, first, last)| OnsetRange { first, last })
+        .min_by_key(|range| range.last - range.first)
+}
+
+/// Return the onset ranges of the annotated `terms`.
+pub fn onset_ranges(ontology: &Ontology, terms: &HpoGroup) -> Vec<OnsetRange> {
+    terms
+        .iter()
+        .filter_map(|term_id| onset_range(ontology, term_id))
+        .collect()
+}
+
+/// Index of the onset ranges of the genes, computed at load time.
+///
+/// The annotations of a gene are the union of the annotations of its diseases, so the
+/// ranges cover the onsets of all diseases of the gene.
+#[derive(Debug, Clone, Default)]
+pub struct OnsetIndex {
+    /// The onset ranges by gene, genes without onset annotation are left out.
+    genes: HashMap<GeneId, Vec<OnsetRange>>,
+}
+
+impl OnsetIndex {
+    /// Build the index for the given ontology.
+    pub fn new(ontology: &Ontology) -> Self {
+        Self {
+            genes: ontology
+                .genes()
+                .filter_map(|gene| {
+                    let ranges = onset_ranges(ontology, gene.hpo_terms());
+                    (!ranges.is_empty()).then(|| (*gene.id(), ranges))
+                })
+                .collect(),
+        }
+    }
+
+    /// Return the onset ranges of the gene, empty if not annotated.
+    pub fn gene_ranges(&self, gene_id: &GeneId) -> &[OnsetRange] {
+        self.genes
+            .get(gene_id)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Return whether all onsets of the gene are incompatible with the `patient` onset.
+    ///
+    /// Genes without onset annotation are never incompatible.
+    pub fn gene_incompatible(&self, gene_id: &GeneId, patient: OnsetRange) -> bool {
+        let ranges = self.gene_ranges(gene_id);
+        !ranges.is_empty()
+            && ranges
+                .iter()
+                .all(|range| range.gap(patient) >= INCOMPATIBLE_GAP)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use hpo::{annotations::GeneId, builder::Builder, HpoTermId, Ontology};
+
+    use super::OnsetRange;
+
+    /// Build an ontology with a part of the onset terms below "All".
+    ///
+    /// Gene 1 has congenital onset, gene 2 young adult onset, and gene 3 none.
+    fn build_ontology() -> Ontology {
+        let mut builder = Builder::new();
+        builder.new_term("All", 1u32);
+        builder.new_term("Phenotypic abnormality", 118u32);
+        builder.new_term("Congenital onset", 3577u32);
+        builder.new_term("Adult onset", 3581u32);
+        builder.new_term("Onset", 3674u32);
+        builder.new_term("Young adult onset", 11_462u32);
+        builder.new_term("Early young adult onset", 25_708u32);
+        let mut builder = builder.terms_complete();
+        builder.add_parent(1u32, 118u32).expect("term must exist");
+        builder.add_parent(1u32, 3674u32).expect("term must exist");
+        builder
+            .add_parent(3674u32, 3577u32)
+            .expect("term must exist");
+        builder
+            .add_parent(3674u32, 3581u32)
+            .expect("term must exist");
+        builder
+            .add_parent(3581u32, 11_462u32)
+            .expect("term must exist");
+        builder
+            .add_parent(11_462u32, 25_708u32)
+            .expect("term must exist");
+        let mut builder = builder.connect_all_terms();
+        for (gene, term) in [(1u32, 3577u32), (2, 25_708), (2, 118), (3, 118)] {
+            builder
+                .annotate_gene(GeneId::from(gene), &format!("GENE{gene}"), term.into())
+                .expect("term must exist");
+        }
+        builder
+            .calculate_information_content()
+            .expect("could not compute IC")
+            .build_minimal()
+    }
+
+    #[test]
+    fn onset_range() {
+        let ontology = build_ontology();
+
+        assert_eq!(
+            super::onset_range(&ontology, HpoTermId::from_u32(3581)),
+            Some(OnsetRange { first: 6, last: 8 })
+        );
+        assert_eq!(
+            super::onset_range(&ontology, HpoTermId::from_u32(25_708)),
+            Some(OnsetRange { first: 6, last: 6 })
+        );
+        assert_eq!(
+            super::onset_range(&ontology, HpoTermId::from_u32(118)),
+            None
+        );
+    }
+
+    #[test]
+    fn gap() {
+        let congenital = OnsetRange { first: 1, last: 1 };
+        let adult = OnsetRange { first: 6, last: 8 };
+
+        assert_eq!(congenital.gap(adult), 5);
+        assert_eq!(adult.gap(congenital), 5);
+        assert_eq!(adult.gap(OnsetRange { first: 7, last: 7 }), 0);
+    }
+
+    #[test]
+    fn gene_incompatible() {
+        let index = super::OnsetIndex::new(&build_ontology());
+        let congenital = OnsetRange { first: 1, last: 1 };
+
+        assert!(!index.gene_incompatible(&GeneId::from(1), congenital));
+        assert!(index.gene_incompatible(&GeneId::from(2), congenital));
+        assert!(!index.gene_incompatible(&GeneId::from(3), congenital));
+    }
+}
@@ -1,48 +1,6 @@
-//! Similarity computation using the Phenomizer method.
+//! Similarity computation using the Phenomizer method, implemented in `viguno-core`.
 
-use hpo::{
-    similarity::{Builtins, Similarity},
-    term::{HpoGroup, InformationContentKind},
-    Ontology,
-};
-
-// TODO: this is equivalent to `StandardCombiner::FunSimAvg` USE IT.
-
-/// Compute symmetric similarity score.
-pub fn score(q: &HpoGroup, d: &HpoGroup, o: &Ontology) -> f32 {
-    let s = Builtins::Resnik(InformationContentKind::Gene);
-    (score_dir(q, d, o, &s) + score_dir(d, q, o, &s)) / 2.0
-}
-
-/// "Directed" score part of phenomizer score.
-///
-/// # Panics
-///
-/// If there are more query terms than fit an `f32` value.
-fn score_dir(qs: &HpoGroup, ds: &HpoGroup, o: &Ontology, s: &impl Similarity) -> f32 {
-    // Handle case of empty `qs`.
-    if qs.is_empty() {
-        return 0f32;
-    }
-
-    // For each `q in qs` compute max similarity to any `d in ds`.
-    let mut tmp: Vec<f32> = Vec::new();
-    for q in qs {
-        if let Some(q) = o.hpo(q) {
-            tmp.push(
-                ds.iter()
-                    .filter_map(|d| o.hpo(d).map(|d| q.similarity_score(&d, s)))
-                    .max_by(|a, b| a.partial_cmp(b).expect("try to compare NaN"))
-                    .unwrap_or_default(),
-            );
-        }
-    }
-
-    // NB: we allow loss of precision in this function for the following statement.
-    let len: u16 = qs.len().try_into().expect("more than 2^16 query terms");
-    let len: f32 = len.into();
-    tmp.iter().sum::<f32>() / len
-}
+pub use viguno_core::phenomizer::*;
 
 #[cfg(test)]
 mod test {
@@ -92,4 +50,46 @@ mod test {
 
         Ok(())
     }
+
+    /// Build an ontology with two unrelated terms below the root.
+    fn build_ontology() -> Ontology {
+        let mut builder = hpo::builder::Builder::new();
+        builder.new_term("All", 1u32);
+        builder.new_term("Left", 2u32);
+        builder.new_term("Right", 3u32);
+        let mut builder = builder.terms_complete();
+        builder.add_parent(1u32, 2u32).expect("term must exist");
+        builder.add_parent(1u32, 3u32).expect("term must exist");
+        let mut builder = builder.connect_all_terms();
+        for (gene, term) in [(10u32, 2u32), (20, 3)] {
+            builder
+                .annotate_gene(
+                    hpo::annotations::GeneId::from(gene),
+                    &format!("GENE{gene}"),
+                    term.into(),
+                )
+                .expect("term must exist");
+        }
+        builder
+            .calculate_information_content()
+            .expect("could not compute IC")
+            .build_minimal()
+    }
+
+    #[test]
+    fn phenomizer_score_modified() {
+        let hpo = build_ontology();
+        let query = prepare(&["HP:0000002", "HP:0000003"]);
+        let gene = prepare(&["HP:0000002"]);
+        let left = HpoTermId::from_u32(2);
+
+        let plain = score(&query, &gene, &hpo);
+        let unweighted = score_modified(&query, &gene, &hpo, &TermWeights::new());
+        let severe = score_modified(&query, &gene, &hpo, &TermWeights::from([(left, 1.5)]));
+        let mild = score_modified(&query, &gene, &hpo, &TermWeights::from([(left, 0.75)]));
+
+        assert!((plain - unweighted).abs() < 1e-6);
+        assert!(severe > plain);
+        assert!(mild < plain);
+    }
 }
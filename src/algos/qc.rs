@@ -0,0 +1,285 @@
+//! Quality control of phenotype term sets before prioritization.
+
+use std::collections::{HashMap, HashSet};
+
+use hpo::{HpoTerm, HpoTermId, Ontology};
+
+use crate::{
+    algos::ic::IcTables,
+    common::{AnnotationPropagation, IcBasedOn},
+};
+
+/// Term ID of "Phenotypic abnormality" (`HP:0000118`).
+pub const PHENOTYPIC_ABNORMALITY: u32 = 118;
+
+/// A term with its information content.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct QcTerm {
+    /// The HPO term ID.
+    pub term_id: String,
+    /// The term name.
+    pub name: String,
+    /// The information content of the term.
+    pub ic: f32,
+}
+
+/// An obsolete term with its replacement, if any.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct QcObsoleteTerm {
+    /// The HPO term ID.
+    pub term_id: String,
+    /// The term name.
+    pub name: String,
+    /// The ID of the replacing term, if any.
+    pub replaced_by: Option<String>,
+}
+
+/// A pair of terms where one is an ancestor of the other.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct QcRedundantPair {
+    /// The more general term.
+    pub ancestor: String,
+    /// The more specific term.
+    pub descendant: String,
+}
+
+/// Diagnostics for a set of terms.
+#[derive(
+    Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize, utoipa::ToSchema,
+)]
+pub struct QcReport {
+    /// Terms that were given more than once.
+    pub duplicates: Vec<String>,
+    /// Terms that could not be parsed or are not part of the ontology.
+    pub unknown: Vec<String>,
+    /// Terms that are obsolete.
+    pub obsolete: Vec<QcObsoleteTerm>,
+    /// Terms that are not below "Phenotypic abnormality", e.g., mode of inheritance.
+    pub non_phenotype: Vec<QcTerm>,
+    /// Terms with an information content below the threshold.
+    pub low_ic: Vec<QcTerm>,
+    /// Pairs of terms where one is implied by the other.
+    pub redundant: Vec<QcRedundantPair>,
+    /// Sum of the information content of the most specific valid phenotype terms.
+    pub informativeness: f32,
+}
+
+/// Return whether `term` is "Phenotypic abnormality" or one of its descendants.
+fn is_phenotype(term: &HpoTerm) -> bool {
+    let root = HpoTermId::from_u32(PHENOTYPIC_ABNORMALITY);
+    term.id() == root || term.all_parent_ids().contains(&root)
+}
+
+/// Compute the diagnostics for the given term IDs.
+///
+/// Terms are reported in the order of their first occurence in `term_ids`.
+pub fn check_terms(
+    ontology: &Ontology,
+    ic_tables: &IcTables,
+    ic_based_on: IcBasedOn,
+    propagation: AnnotationPropagation,
+    min_ic: f32,
+    term_ids: &[String],
+) -> QcReport {
+    let mut report = QcReport::default();
+
+    // Count occurences by canonical ID and keep the unique terms that exist in the ontology.
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    let mut terms = Vec::new();
+    for term_id in term_ids {
+        let parsed = crate::common::parse_term_id(term_id).ok();
+        let key = parsed.map_or_else(|| term_id.clone(), |parsed| parsed.to_string());
+        let count = counts.entry(key.clone()).or_default();
+        *count += 1;
+        match *count {
+            1 => match parsed.and_then(|term_id| ontology.hpo(term_id)) {
+                Some(term) => terms.push(term),
+                None => report.unknown.push(key),
+            },
+            2 => report.duplicates.push(key),
+            _ => (),
+        }
+    }
+
+    // Flag obsolete, non-phenotype, and low information content terms.
+    let mut valid = Vec::new();
+    for term in &terms {
+        let qc_term = QcTerm {
+            term_id: term.id().to_string(),
+            name: term.name().to_string(),
+            ic: ic_tables.term_ic(term, ic_based_on, propagation),
+        };
+        if term.is_obsolete() {
+            report.obsolete.push(QcObsoleteTerm {
+                term_id: qc_term.term_id,
+                name: qc_term.name,
+                replaced_by: term.replacement_id().map(|id| id.to_string()),
+            });
+        } else if !is_phenotype(term) {
+            report.non_phenotype.push(qc_term);
+        } else {
+            if qc_term.ic < min_ic {
+                report.low_ic.push(qc_term.clone());
+            }
+            valid.push((term, qc_term.ic));
+        }
+    }
+
+    // Find ancestor/descendant pairs among the valid terms.
+    let mut implied = HashSet::new();
+    for (descendant, _) in &valid {
+        for (ancestor, _) in &valid {
+            if descendant.all_parent_ids().contains(&ancestor.id()) {
+                report.redundant.push(QcRedundantPair {
+                    ancestor: ancestor.id().to_string(),
+                    descendant: descendant.id().to_string(),
+                });
+                implied.insert(ancestor.id());
+            }
+        }
+    }
+
+    report.informativeness = valid
+        .iter()
+        .filter(|(term, _)| !implied.contains(&term.id()))
+        .map(|(_, ic)| ic)
+        .sum();
+
+    report
+}
+
+#[cfg(test)]
+mod test {
+    use hpo::{annotations::OmimDiseaseId, builder::Builder, Ontology};
+
+    use crate::{
+        algos::ic::IcTables,
+        common::{AnnotationPropagation, IcBasedOn},
+    };
+
+    /// Build a small ontology with the structure
+    ///
+    /// ```text
+    /// HP:0000001 (All)
+    /// +-- HP:0000005 (Mode of inheritance)
+    /// +-- HP:0000118 (Phenotypic abnormality)
+    ///     +-- HP:0000002 (Left)
+    ///     |   +-- HP:0000004 (Left-left)
+    ///     +-- HP:0000003 (Right)
+    /// ```
+    ///
+    /// with OMIM diseases 1 and 2 on `HP:0000004` and `HP:0000003`, respectively.
+    fn qc_ontology() -> Ontology {
+        let mut builder = Builder::new();
+        builder.new_term("All", 1u32);
+        builder.new_term("Left", 2u32);
+        builder.new_term("Right", 3u32);
+        builder.new_term("Left-left", 4u32);
+        builder.new_term("Mode of inheritance", 5u32);
+        builder.new_term("Phenotypic abnormality", 118u32);
+        let mut builder = builder.terms_complete();
+        builder.add_parent(1u32, 5u32).expect("term must exist");
+        builder.add_parent(1u32, 118u32).expect("term must exist");
+        builder.add_parent(118u32, 2u32).expect("term must exist");
+        builder.add_parent(118u32, 3u32).expect("term must exist");
+        builder.add_parent(2u32, 4u32).expect("term must exist");
+        let mut builder = builder.connect_all_terms();
+        builder
+            .annotate_omim_disease(OmimDiseaseId::from(1), "Disease 1", 4u32.into())
+            .expect("term must exist");
+        builder
+            .annotate_omim_disease(OmimDiseaseId::from(2), "Disease 2", 3u32.into())
+            .expect("term must exist");
+        builder
+            .calculate_information_content()
+            .expect("could not compute IC")
+            .build_minimal()
+    }
+
+    fn check(term_ids: &[&str]) -> super::QcReport {
+        let ontology = qc_ontology();
+        let ic_tables = IcTables::new(&ontology);
+        let term_ids = term_ids.iter().map(ToString::to_string).collect::<Vec<_>>();
+        super::check_terms(
+            &ontology,
+            &ic_tables,
+            IcBasedOn::Omim,
+            AnnotationPropagation::Ancestors,
+            0.5,
+            &term_ids,
+        )
+    }
+
+    #[test]
+    fn check_terms_clean() {
+        let report = check(&["HP:0000004", "HP:0000003"]);
+
+        assert!(report.duplicates.is_empty());
+        assert!(report.unknown.is_empty());
+        assert!(report.non_phenotype.is_empty());
+        assert!(report.low_ic.is_empty());
+        assert!(report.redundant.is_empty());
+        assert!((report.informativeness - 2f32 * 2f32.ln()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn check_terms_problems() {
+        let report = check(&[
+            "HP:0000004",
+            "HP:0000002",
+            "HP:0000004",
+            "HP:0000005",
+            "HP:9999999",
+            "xyz",
+            "HP:0000118",
+        ]);
+
+        assert_eq!(report.duplicates, vec!["HP:0000004"]);
+        assert_eq!(report.unknown, vec!["HP:9999999", "xyz"]);
+        assert_eq!(
+            report
+                .non_phenotype
+                .iter()
+                .map(|t| t.term_id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["HP:0000005"]
+        );
+        assert_eq!(
+            report
+                .low_ic
+                .iter()
+                .map(|t| t.term_id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["HP:0000118"]
+        );
+        assert_eq!(
+            report
+                .redundant
+                .iter()
+                .map(|p| (p.ancestor.as_str(), p.descendant.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("HP:0000002", "HP:0000004"),
+                ("HP:0000118", "HP:0000004"),
+                ("HP:0000118", "HP:0000002"),
+            ]
+        );
+        assert!((report.informativeness - 2f32.ln()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn check_terms_id_variants() {
+        let report = check(&["HP:0000004", "hp_0000004", "2", "HP:000000x"]);
+
+        assert_eq!(report.duplicates, vec!["HP:0000004"]);
+        assert_eq!(report.unknown, vec!["HP:000000x"]);
+        assert_eq!(
+            report
+                .redundant
+                .iter()
+                .map(|p| (p.ancestor.as_str(), p.descendant.as_str()))
+                .collect::<Vec<_>>(),
+            vec![("HP:0000002", "HP:0000004")]
+        );
+    }
+}
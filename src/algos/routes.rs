@@ -0,0 +1,265 @@
+//! Routes of the gene annotations, i.e., the gene-phenotype annotations of
+//! `genes_to_phenotype.txt` and the annotations inherited from the gene's diseases.
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    io::{BufRead, BufReader},
+    path::Path,
+};
+
+use hpo::{
+    annotations::{AnnotationId as _, Disease as _, Gene, OmimDiseaseId},
+    term::HpoGroup,
+    HpoTermId, Ontology,
+};
+
+use super::sex::{data_lines, SexFilter};
+
+/// Route of the annotations a gene is scored with.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    serde::Serialize,
+    serde::Deserialize,
+    utoipa::ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum AnnotationRoute {
+    /// The gene-phenotype annotations of the gene from `genes_to_phenotype.txt`.
+    #[default]
+    Direct,
+    /// The annotations of the OMIM diseases linked to the gene.
+    ViaDisease,
+    /// The union of both.
+    Both,
+}
+
+/// Index of the OMIM diseases linked to the genes in `genes_to_phenotype.txt`.
+#[derive(Debug, Clone, Default)]
+pub struct DiseaseGeneIndex {
+    /// The linked OMIM diseases by NCBI gene ID.
+    genes: HashMap<u32, Vec<OmimDiseaseId>>,
+}
+
+impl DiseaseGeneIndex {
+    /// Build the index from `genes_to_phenotype.txt`.
+    ///
+    /// # Errors
+    ///
+    /// In the case that reading the file fails.
+    pub fn from_reader<R: BufRead>(genes_to_phenotype: R) -> Result<Self, anyhow::Error> {
+        // Columns: ncbi_gene_id, gene_symbol, hpo_id, ..., disease_id as the last one.
+        let mut genes: HashMap<u32, BTreeSet<OmimDiseaseId>> = HashMap::new();
+        for columns in data_lines(genes_to_phenotype) {
+            let columns = columns?;
+            let (Some(Ok(ncbi_gene_id)), Some(omim_id)) = (
+                columns.first().map(|value| value.parse::<u32>()),
+                columns
+                    .last()
+                    .filter(|_| columns.len() > 3)
+                    .and_then(|disease_id| disease_id.strip_prefix("OMIM:"))
+                    .and_then(|omim_id| OmimDiseaseId::try_from(omim_id).ok()),
+            ) else {
+                continue;
+            };
+            genes.entry(ncbi_gene_id).or_default().insert(omim_id);
+        }
+
+        Ok(Self {
+            genes: genes
+                .into_iter()
+                .map(|(ncbi_gene_id, omim_ids)| (ncbi_gene_id, omim_ids.into_iter().collect()))
+                .collect(),
+        })
+    }
+
+    /// Load the index from the file in `path_hpo_dir`, empty if it does not exist.
+    ///
+    /// # Errors
+    ///
+    /// In the case that opening or reading the existing file fails.
+    pub fn from_hpo_dir<P: AsRef<Path>>(path_hpo_dir: P) -> Result<Self, anyhow::Error> {
+        let path_g2p = path_hpo_dir.as_ref().join("genes_to_phenotype.txt");
+        if !path_g2p.exists() {
+            tracing::warn!("  no annotation file, disease-gene links are not known");
+            return Ok(Self::default());
+        }
+        Self::from_reader(BufReader::new(std::fs::File::open(path_g2p)?))
+    }
+
+    /// Return whether no disease-gene links are known.
+    pub fn is_empty(&self) -> bool {
+        self.genes.is_empty()
+    }
+
+    /// Return the OMIM diseases linked to the gene with `ncbi_gene_id`, sorted.
+    pub fn omim_diseases(&self, ncbi_gene_id: u32) -> &[OmimDiseaseId] {
+        self.genes.get(&ncbi_gene_id).map_or(&[], Vec::as_slice)
+    }
+
+    /// Return the union of the terms of the OMIM diseases linked to `gene`, without those
+    /// restricted to the other sex of `sex`, if given.
+    pub fn disease_terms(&self, gene: &Gene, hpo: &Ontology, sex: Option<&SexFilter>) -> HpoGroup {
+        let mut terms = HpoGroup::new();
+        for omim_disease in self
+            .omim_diseases(gene.id().as_u32())
+            .iter()
+            .filter_map(|omim_id| hpo.omim_disease(omim_id))
+        {
+            let disease_terms = match sex {
+                Some(sex) => sex.omim_terms(omim_disease),
+                None => omim_disease.hpo_terms().clone(),
+            };
+            for term_id in &disease_terms {
+                terms.insert(term_id);
+            }
+        }
+        terms
+    }
+}
+
+/// The annotations of a gene by route.
+#[derive(Debug, Clone, Default)]
+pub struct RouteTerms {
+    /// The gene-phenotype annotations.
+    pub direct: HpoGroup,
+    /// The annotations of the gene's diseases.
+    pub via_disease: HpoGroup,
+}
+
+impl RouteTerms {
+    /// Return the annotations of `route`.
+    pub fn terms(&self, route: AnnotationRoute) -> HpoGroup {
+        match route {
+            AnnotationRoute::Direct => self.direct.clone(),
+            AnnotationRoute::ViaDisease => self.via_disease.clone(),
+            AnnotationRoute::Both => self.direct.iter().chain(&self.via_disease).collect(),
+        }
+    }
+
+    /// Return the route that annotates `term_id`, `None` if neither does.
+    pub fn route_of(&self, term_id: HpoTermId) -> Option<AnnotationRoute> {
+        match (
+            self.direct.contains(&term_id),
+            self.via_disease.contains(&term_id),
+        ) {
+            (true, true) => Some(AnnotationRoute::Both),
+            (true, false) => Some(AnnotationRoute::Direct),
+            (false, true) => Some(AnnotationRoute::ViaDisease),
+            (false, false) => None,
+        }
+    }
+}
+
+/// Selection of the annotations of the genes by route.
+#[derive(Debug, Clone, Copy)]
+pub struct RouteFilter<'a> {
+    /// The index of the disease-gene links.
+    pub index: &'a DiseaseGeneIndex,
+    /// The route to score the genes with.
+    pub route: AnnotationRoute,
+}
+
+impl RouteFilter<'_> {
+    /// Return the annotations of `gene` by route, given its `direct` annotations.
+    pub fn route_terms(
+        &self,
+        gene: &Gene,
+        direct: HpoGroup,
+        hpo: &Ontology,
+        sex: Option<&SexFilter>,
+    ) -> RouteTerms {
+        RouteTerms {
+            direct,
+            via_disease: self.index.disease_terms(gene, hpo, sex),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use hpo::{
+        annotations::{AnnotationId as _, GeneId, OmimDiseaseId},
+        builder::Builder,
+        HpoTermId, Ontology,
+    };
+
+    use super::{AnnotationRoute, DiseaseGeneIndex};
+
+    const GENES_TO_PHENOTYPE: &str = "\
+ncbi_gene_id\tgene_symbol\thpo_id\thpo_name\tfrequency\tdisease_id
+10\tGENE10\tHP:0000002\tTerm 2\t-\tOMIM:1
+10\tGENE10\tHP:0000002\tTerm 2\t-\tORPHA:5
+20\tGENE20\tHP:0000003\tTerm 3\t-\tOMIM:2
+20\tGENE20\tHP:0000003\tTerm 3\t-\tOMIM:1
+";
+
+    /// Build an ontology where gene 10 is annotated with term 2 and its disease 1 with
+    /// terms 2 and 3.
+    fn build_ontology() -> Ontology {
+        let mut builder = Builder::new();
+        builder.new_term("All", 1u32);
+        builder.new_term("Term 2", 2u32);
+        builder.new_term("Term 3", 3u32);
+        let mut builder = builder.terms_complete();
+        builder.add_parent(1u32, 2u32).expect("term must exist");
+        builder.add_parent(1u32, 3u32).expect("term must exist");
+        let mut builder = builder.connect_all_terms();
+        builder
+            .annotate_gene(GeneId::from(10), "GENE10", 2u32.into())
+            .expect("term must exist");
+        for term in [2u32, 3] {
+            builder
+                .annotate_omim_disease(OmimDiseaseId::from(1), "Disease 1", term.into())
+                .expect("term must exist");
+        }
+        builder
+            .calculate_information_content()
+            .expect("could not compute IC")
+            .build_minimal()
+    }
+
+    #[test]
+    fn route_terms() -> Result<(), anyhow::Error> {
+        let ontology = build_ontology();
+        let index = DiseaseGeneIndex::from_reader(GENES_TO_PHENOTYPE.as_bytes())?;
+        assert_eq!(
+            index.omim_diseases(20),
+            [OmimDiseaseId::from(1), OmimDiseaseId::from(2)]
+        );
+        assert!(index.omim_diseases(30).is_empty());
+
+        let gene = ontology.gene(&GeneId::from(10)).expect("gene must exist");
+        let filter = super::RouteFilter {
+            index: &index,
+            route: AnnotationRoute::ViaDisease,
+        };
+        let terms = filter.route_terms(gene, gene.hpo_terms().clone(), &ontology, None);
+        let ids = |route| {
+            terms
+                .terms(route)
+                .iter()
+                .map(|term_id| term_id.as_u32())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(ids(AnnotationRoute::Direct), vec![2]);
+        assert_eq!(ids(AnnotationRoute::ViaDisease), vec![2, 3]);
+        assert_eq!(ids(AnnotationRoute::Both), vec![2, 3]);
+        assert_eq!(
+            terms.route_of(HpoTermId::from_u32(2)),
+            Some(AnnotationRoute::Both)
+        );
+        assert_eq!(
+            terms.route_of(HpoTermId::from_u32(3)),
+            Some(AnnotationRoute::ViaDisease)
+        );
+        assert_eq!(terms.route_of(HpoTermId::from_u32(1)), None);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,399 @@
+//! Sex-specific annotations from the `sex` column of `phenotype.hpoa`.
+
+use std::{
+    collections::HashMap,
+    io::{BufRead, BufReader},
+    path::Path,
+    str::FromStr,
+};
+
+use hpo::{
+    annotations::{AnnotationId as _, Disease as _, Gene, OmimDisease, OmimDiseaseId},
+    term::HpoGroup,
+    HpoTermId,
+};
+use strum::{EnumIter, IntoEnumIterator};
+
+/// Sex of a patient that annotations may be restricted to.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    EnumIter,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    derive_more::Display,
+    serde::Serialize,
+    serde::Deserialize,
+    utoipa::ToSchema,
+)]
+pub enum Sex {
+    /// Male.
+    #[display("male")]
+    #[serde(rename = "male")]
+    Male,
+    /// Female.
+    #[display("female")]
+    #[serde(rename = "female")]
+    Female,
+}
+
+impl FromStr for Sex {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Sex::iter()
+            .find(|m| m.to_string().eq_ignore_ascii_case(s))
+            .ok_or(anyhow::anyhow!("unknown sex: {}", s))
+    }
+}
+
+/// Index of the annotations that are restricted to one sex.
+///
+/// Gene annotations are derived from the annotations of the gene's diseases, so a gene
+/// annotation is restricted if the annotations of all of the gene's diseases with the
+/// term are restricted to the same sex.
+#[derive(Debug, Clone, Default)]
+pub struct SexIndex {
+    /// The restricted annotations by OMIM disease.
+    omim: HashMap<OmimDiseaseId, HashMap<HpoTermId, Sex>>,
+    /// The restricted annotations by NCBI gene ID.
+    genes: HashMap<u32, HashMap<HpoTermId, Sex>>,
+}
+
+/// Return the tab-separated columns of the data lines of a file from the HPO release.
+pub(crate) fn data_lines<R: BufRead>(
+    reader: R,
+) -> impl Iterator<Item = Result<Vec<String>, anyhow::Error>> {
+    reader
+        .lines()
+        .filter(|line| {
+            line.as_ref()
+                .map_or(true, |line| !line.is_empty() && !line.starts_with('#'))
+        })
+        .map(|line| Ok(line?.split('\t').map(str::to_string).collect()))
+}
+
+impl SexIndex {
+    /// Build the index from `phenotype.hpoa` and `genes_to_phenotype.txt`.
+    ///
+    /// # Errors
+    ///
+    /// In the case that reading one of the files fails.
+    pub fn from_readers<R1: BufRead, R2: BufRead>(
+        phenotype_hpoa: R1,
+        genes_to_phenotype: R2,
+    ) -> Result<Self, anyhow::Error> {
+        // Columns: database_id, disease_name, qualifier, hpo_id, reference, evidence,
+        // onset, frequency, sex, ...  A disease's term is restricted if all its lines are
+        // restricted alike, independent of their order.
+        let mut diseases: HashMap<(String, HpoTermId), Option<Sex>> = HashMap::new();
+        for columns in data_lines(phenotype_hpoa) {
+            let columns = columns?;
+            if columns.len() < 9 || columns[2] == "NOT" {
+                continue;
+            }
+            let Ok(term_id) = crate::common::parse_term_id(&columns[3]) else {
+                continue;
+            };
+            let sex = columns[8].parse::<Sex>().ok();
+            diseases
+                .entry((columns[0].clone(), term_id))
+                .and_modify(|prev| {
+                    if *prev != sex {
+                        *prev = None;
+                    }
+                })
+                .or_insert(sex);
+        }
+
+        let mut omim: HashMap<OmimDiseaseId, HashMap<HpoTermId, Sex>> = HashMap::new();
+        for ((disease_id, term_id), sex) in &diseases {
+            if let (Some(omim_id), Some(sex)) = (
+                disease_id
+                    .strip_prefix("OMIM:")
+                    .and_then(|omim_id| OmimDiseaseId::try_from(omim_id).ok()),
+                sex,
+            ) {
+                omim.entry(omim_id).or_default().insert(*term_id, *sex);
+            }
+        }
+
+        // Columns: ncbi_gene_id, gene_symbol, hpo_id, ..., disease_id as the last one.  A
+        // gene's term is restricted if all its diseases' annotations are restricted alike.
+        let mut genes: HashMap<u32, HashMap<HpoTermId, Option<Sex>>> = HashMap::new();
+        for columns in data_lines(genes_to_phenotype) {
+            let columns = columns?;
+            let (Some(Ok(ncbi_gene_id)), Some(Ok(term_id)), Some(disease_id)) = (
+                columns.first().map(|value| value.parse::<u32>()),
+                columns
+                    .get(2)
+                    .map(|value| crate::common::parse_term_id(value)),
+                columns.last().filter(|_| columns.len() > 3),
+            ) else {
+                continue;
+            };
+            let sex = diseases
+                .get(&(disease_id.clone(), term_id))
+                .copied()
+                .flatten();
+            genes
+                .entry(ncbi_gene_id)
+                .or_default()
+                .entry(term_id)
+                .and_modify(|prev| {
+                    if *prev != sex {
+                        *prev = None;
+                    }
+                })
+                .or_insert(sex);
+        }
+        let genes = genes
+            .into_iter()
+            .map(|(ncbi_gene_id, terms)| {
+                (
+                    ncbi_gene_id,
+                    terms
+                        .into_iter()
+                        .filter_map(|(term_id, sex)| Some((term_id, sex?)))
+                        .collect::<HashMap<_, _>>(),
+                )
+            })
+            .filter(|(_, terms)| !terms.is_empty())
+            .collect();
+
+        Ok(Self { omim, genes })
+    }
+
+    /// Load the index from the files in `path_hpo_dir`, empty if they do not exist.
+    ///
+    /// # Errors
+    ///
+    /// In the case that opening or reading one of the existing files fails.
+    pub fn from_hpo_dir<P: AsRef<Path>>(path_hpo_dir: P) -> Result<Self, anyhow::Error> {
+        let path_hpoa = path_hpo_dir.as_ref().join("phenotype.hpoa");
+        let path_g2p = path_hpo_dir.as_ref().join("genes_to_phenotype.txt");
+        if !path_hpoa.exists() || !path_g2p.exists() {
+            tracing::warn!("  no annotation files, sex-specific annotations are not known");
+            return Ok(Self::default());
+        }
+        Self::from_readers(
+            BufReader::new(std::fs::File::open(path_hpoa)?),
+            BufReader::new(std::fs::File::open(path_g2p)?),
+        )
+    }
+
+    /// Return the annotated terms of `terms` that are not restricted to the other sex.
+    fn without_other_sex(
+        terms: &HpoGroup,
+        restricted: Option<&HashMap<HpoTermId, Sex>>,
+        sex: Sex,
+    ) -> HpoGroup {
+        terms
+            .iter()
+            .filter(|term_id| {
+                restricted
+                    .and_then(|restricted| restricted.get(term_id))
+                    .is_none_or(|restricted_sex| *restricted_sex == sex)
+            })
+            .collect()
+    }
+
+    /// Return the terms of the gene, without those restricted to the other sex.
+    pub fn gene_terms(&self, gene: &Gene, sex: Sex) -> HpoGroup {
+        Self::without_other_sex(gene.hpo_terms(), self.genes.get(&gene.id().as_u32()), sex)
+    }
+
+    /// Return the terms of the OMIM disease, without those restricted to the other sex.
+    pub fn omim_terms(&self, omim_disease: &OmimDisease, sex: Sex) -> HpoGroup {
+        Self::without_other_sex(
+            omim_disease.hpo_terms(),
+            self.omim.get(omim_disease.id()),
+            sex,
+        )
+    }
+}
+
+/// Exclusion of the annotations restricted to the sex other than `sex`.
+#[derive(Debug, Clone, Copy)]
+pub struct SexFilter<'a> {
+    /// The index of the restricted annotations.
+    pub index: &'a SexIndex,
+    /// The sex of the patient.
+    pub sex: Sex,
+}
+
+impl SexFilter<'_> {
+    /// Return the terms of the gene, without those restricted to the other sex.
+    pub fn gene_terms(&self, gene: &Gene) -> HpoGroup {
+        self.index.gene_terms(gene, self.sex)
+    }
+
+    /// Return the terms of the OMIM disease, without those restricted to the other sex.
+    pub fn omim_terms(&self, omim_disease: &OmimDisease) -> HpoGroup {
+        self.index.omim_terms(omim_disease, self.sex)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use hpo::{
+        annotations::{AnnotationId as _, GeneId, OmimDiseaseId},
+        builder::Builder,
+        HpoTermId, Ontology,
+    };
+
+    use super::{Sex, SexIndex};
+
+    const PHENOTYPE_HPOA: &str = "\
+#description: test
+database_id\tdisease_name\tqualifier\thpo_id\treference\tevidence\tonset\tfrequency\tsex\tmodifier\taspect\tbiocuration
+OMIM:1\tDisease 1\t\tHP:0000002\tOMIM:1\tTAS\t\t\tMALE\t\tP\tHPO:curator
+OMIM:1\tDisease 1\t\tHP:0000003\tOMIM:1\tTAS\t\t\t\t\tP\tHPO:curator
+OMIM:2\tDisease 2\t\tHP:0000002\tOMIM:2\tTAS\t\t\t\t\tP\tHPO:curator
+OMIM:2\tDisease 2\t\tHP:0000003\tOMIM:2\tTAS\t\t\tfemale\t\tP\tHPO:curator
+";
+
+    const GENES_TO_PHENOTYPE: &str = "\
+ncbi_gene_id\tgene_symbol\thpo_id\thpo_name\tfrequency\tdisease_id
+10\tGENE1\tHP:0000002\tTerm 2\t-\tOMIM:1
+10\tGENE1\tHP:0000003\tTerm 3\t-\tOMIM:1
+20\tGENE2\tHP:0000002\tTerm 2\t-\tOMIM:1
+20\tGENE2\tHP:0000002\tTerm 2\t-\tOMIM:2
+20\tGENE2\tHP:0000003\tTerm 3\t-\tOMIM:2
+";
+
+    /// Build an ontology with two terms below the root, annotated as in the test files.
+    fn build_ontology() -> Ontology {
+        let mut builder = Builder::new();
+        builder.new_term("All", 1u32);
+        builder.new_term("Term 2", 2u32);
+        builder.new_term("Term 3", 3u32);
+        let mut builder = builder.terms_complete();
+        builder.add_parent(1u32, 2u32).expect("term must exist");
+        builder.add_parent(1u32, 3u32).expect("term must exist");
+        let mut builder = builder.connect_all_terms();
+        for (gene, term) in [(10u32, 2u32), (10, 3), (20, 2), (20, 3)] {
+            builder
+                .annotate_gene(GeneId::from(gene), &format!("GENE{gene}"), term.into())
+                .expect("term must exist");
+        }
+        for (disease, term) in [(1u32, 2u32), (1, 3), (2, 2), (2, 3)] {
+            builder
+                .annotate_omim_disease(
+                    OmimDiseaseId::from(disease),
+                    &format!("Disease {disease}"),
+                    term.into(),
+                )
+                .expect("term must exist");
+        }
+        builder
+            .calculate_information_content()
+            .expect("could not compute IC")
+            .build_minimal()
+    }
+
+    #[test]
+    fn from_str() -> Result<(), anyhow::Error> {
+        assert_eq!("MALE".parse::<Sex>()?, Sex::Male);
+        assert_eq!("female".parse::<Sex>()?, Sex::Female);
+        assert!("".parse::<Sex>().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn sex_index() -> Result<(), anyhow::Error> {
+        let ontology = build_ontology();
+        let index =
+            SexIndex::from_readers(PHENOTYPE_HPOA.as_bytes(), GENES_TO_PHENOTYPE.as_bytes())?;
+        let ids = |terms: hpo::term::HpoGroup| {
+            terms
+                .iter()
+                .map(|term_id| term_id.as_u32())
+                .collect::<Vec<_>>()
+        };
+
+        let disease_1 = ontology
+            .omim_disease(&OmimDiseaseId::from(1))
+            .expect("disease must exist");
+        assert_eq!(ids(index.omim_terms(disease_1, Sex::Male)), vec![2, 3]);
+        assert_eq!(ids(index.omim_terms(disease_1, Sex::Female)), vec![3]);
+
+        // The term 2 of gene 2 is not restricted through disease 2.
+        let gene_1 = ontology.gene(&GeneId::from(10)).expect("gene must exist");
+        let gene_2 = ontology.gene(&GeneId::from(20)).expect("gene must exist");
+        assert_eq!(ids(index.gene_terms(gene_1, Sex::Female)), vec![3]);
+        assert_eq!(ids(index.gene_terms(gene_2, Sex::Female)), vec![2, 3]);
+        assert_eq!(ids(index.gene_terms(gene_2, Sex::Male)), vec![2]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn from_readers_conflicting_lines() -> Result<(), anyhow::Error> {
+        let restricted = "OMIM:1\tDisease 1\t\tHP:0000002\tOMIM:1\tTAS\t\t\tMALE\n";
+        let unrestricted = "OMIM:1\tDisease 1\t\tHP:0000002\tPMID:1\tPCS\t\t\t\n";
+        let female = "OMIM:1\tDisease 1\t\tHP:0000002\tPMID:2\tPCS\t\t\tFEMALE\n";
+        let genes_to_phenotype = "10\tGENE1\tHP:0000002\tTerm 2\t-\tOMIM:1\n";
+
+        // The term is not restricted with any order of the lines.
+        for phenotype_hpoa in [
+            format!("{restricted}{unrestricted}"),
+            format!("{unrestricted}{restricted}"),
+            format!("{restricted}{female}"),
+        ] {
+            let index =
+                SexIndex::from_readers(phenotype_hpoa.as_bytes(), genes_to_phenotype.as_bytes())?;
+            assert!(index.omim.is_empty());
+            assert!(index.genes.is_empty());
+        }
+
+        let index = SexIndex::from_readers(
+            format!("{restricted}{restricted}").as_bytes(),
+            genes_to_phenotype.as_bytes(),
+        )?;
+        assert_eq!(
+            index.omim[&OmimDiseaseId::from(1)][&HpoTermId::from(2u32)],
+            Sex::Male
+        );
+        assert_eq!(index.genes[&10][&HpoTermId::from(2u32)], Sex::Male);
+
+        Ok(())
+    }
+
+    #[test]
+    fn from_readers_multibyte_term_id() -> Result<(), anyhow::Error> {
+        let index = SexIndex::from_readers(
+            "OMIM:1\tDisease 1\t\tHP\u{e9}0000002\tOMIM:1\tTAS\t\t\tMALE\n".as_bytes(),
+            "10\tGENE1\tHP\u{e9}0000002\tTerm 2\t-\tOMIM:1\n".as_bytes(),
+        )?;
+
+        assert!(index.omim.is_empty());
+        assert!(index.genes.is_empty());
+
+        Ok(())
+    }
+
+    /// Values of the HPOA and `genes_to_phenotype.txt` lines besides the term IDs.
+    const WORDS: &[&str] = &["MALE", "female", "NOT", "OMIM:1", "10"];
+
+    proptest::proptest! {
+        #[test]
+        fn from_readers_fuzz(
+            hpoa in crate::fuzz::lines(crate::fuzz::tsv_line(12, WORDS), 8),
+            g2p in crate::fuzz::lines(crate::fuzz::tsv_line(7, WORDS), 8),
+        ) {
+            // Malformed lines are skipped rather than failing or panicking.
+            proptest::prop_assert!(
+                SexIndex::from_readers(hpoa.as_bytes(), g2p.as_bytes()).is_ok(),
+                "{:?} {:?}",
+                hpoa,
+                g2p
+            );
+        }
+    }
+}
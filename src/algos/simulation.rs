@@ -0,0 +1,695 @@
+//! Simulated score distributions for Phenomizer-style p-values.
+//!
+//! For each gene, the Phenomizer score is computed for random query sets of terms from
+//! the phenotypic abnormality sub-ontology.  The p-value of an observed score is the
+//! fraction of the simulated scores at least as high.  The distributions depend on the
+//! number of query terms and are expensive to compute, so they are built on first use and
+//! kept in memory and, if configured, in the `DiskCache`.  Alternatively, they can be
+//! precomputed into an artifact file with `precompute sim-pvalues`.
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    sync::{Arc, Mutex},
+};
+
+use hpo::{annotations::AnnotationId as _, term::HpoGroup, HpoTermId, Ontology};
+use prost::Message as _;
+use rayon::prelude::*;
+
+use super::phenomizer;
+use crate::{
+    cache::DiskCache,
+    data::artifact::{self, ArtifactHeader, ArtifactKind},
+    pbs::simulation::{SimulationHeader, SimulationResults},
+};
+
+/// The phenotypic abnormality term, the random queries are drawn from its descendants.
+pub const PHENOTYPIC_ABNORMALITY: u32 = 118;
+
+/// Queries with more terms use the distributions for this number of terms.
+pub const MAX_TERM_COUNT: usize = 10;
+
+/// Default number of random queries per number of terms.
+pub const DEFAULT_SIMULATIONS: usize = 1000;
+
+/// Default seed of the random queries, fixed so that the distributions are reproducible.
+pub const DEFAULT_SEED: u64 = 42;
+
+/// Fraction of the memory budget that the distributions kept in memory may use.
+const MEMORY_BUDGET_RATIO: f64 = 0.25;
+
+/// The parameters of a simulation, part of the cache key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+pub struct SimulationParams {
+    /// The number of terms of each random query.
+    pub term_count: usize,
+    /// The number of random queries.
+    pub n_simulations: usize,
+    /// The seed of the random queries.
+    pub seed: u64,
+}
+
+/// Draw `params.n_simulations` random queries of `params.term_count` distinct terms.
+fn random_queries(ontology: &Ontology, params: SimulationParams) -> Vec<HpoGroup> {
+    let root = HpoTermId::from(PHENOTYPIC_ABNORMALITY);
+    let mut candidates = ontology
+        .hpos()
+        .filter(|term| term.all_parent_ids().contains(&root))
+        .map(|term| term.id())
+        .collect::<Vec<_>>();
+    candidates.sort();
+
+    let term_count = params.term_count.min(candidates.len());
+    let n_candidates = candidates.len() as u64;
+    let mut rng = fastrand::Rng::with_seed(params.seed);
+    (0..params.n_simulations)
+        .map(|_| {
+            let mut query = HpoGroup::new();
+            while query.len() < term_count {
+                // Draw 64-bit numbers so that a seed gives the same queries on all
+                // platforms, `usize` ranges draw 32-bit numbers on 32-bit platforms.
+                let index = usize::try_from(rng.u64(..n_candidates)).expect("index must fit");
+                query.insert(candidates[index]);
+            }
+            query
+        })
+        .collect()
+}
+
+/// Simulate the score distribution of each gene, sorted by NCBI gene ID.
+pub fn simulate(ontology: &Ontology, params: SimulationParams) -> Vec<SimulationResults> {
+    let queries = random_queries(ontology, params);
+    let mut genes = ontology.genes().collect::<Vec<_>>();
+    genes.sort_by_key(|gene| gene.id().as_u32());
+
+    genes
+        .par_iter()
+        .map(|gene| {
+            let gene_terms = hpo::HpoSet::new(ontology, gene.hpo_terms().clone())
+                .child_nodes()
+                .without_modifier()
+                .into_iter()
+                .collect::<HpoGroup>();
+            let mut scores = queries
+                .iter()
+                .map(|query| phenomizer::score(query, &gene_terms, ontology))
+                .collect::<Vec<_>>();
+            scores.sort_by(f32::total_cmp);
+            SimulationResults {
+                ncbi_gene_id: gene.id().as_u32(),
+                gene_symbol: gene.name().to_string(),
+                term_count: u32::try_from(params.term_count).unwrap_or(u32::MAX),
+                scores,
+            }
+        })
+        .collect()
+}
+
+/// Return the p-value of `score` given the ascending simulated `scores`.
+///
+/// One is added to the counts so that the p-value is never zero.
+#[allow(clippy::cast_precision_loss)]
+pub fn p_value(scores: &[f32], score: f32) -> f32 {
+    let n_less = scores.partition_point(|simulated| *simulated < score);
+    (scores.len() - n_less + 1) as f32 / (scores.len() + 1) as f32
+}
+
+/// Simulated score distributions of all genes for one number of terms.
+#[derive(Debug, Clone, Default)]
+pub struct ScoreDistributions {
+    /// The ascending simulated scores by NCBI gene ID.
+    scores: HashMap<u32, Vec<f32>>,
+}
+
+impl ScoreDistributions {
+    /// Encode `results` as length-delimited protobuf messages.
+    ///
+    /// # Panics
+    ///
+    /// Never, the buffer grows as needed.
+    pub fn encode(results: &[SimulationResults]) -> Vec<u8> {
+        let mut buf = Vec::new();
+        for result in results {
+            result
+                .encode_length_delimited(&mut buf)
+                .expect("vector has enough capacity");
+        }
+        buf
+    }
+
+    /// Decode the distributions from the output of `encode`.
+    ///
+    /// # Errors
+    ///
+    /// In the case that `bytes` is not a sequence of length-delimited messages.
+    pub fn decode(mut bytes: &[u8]) -> Result<Self, anyhow::Error> {
+        let mut distributions = Self::default();
+        while !bytes.is_empty() {
+            let result = decode_message::<SimulationResults>(&mut bytes)?;
+            distributions
+                .scores
+                .insert(result.ncbi_gene_id, result.scores);
+        }
+        Ok(distributions)
+    }
+
+    /// Return the approximate memory used by the distributions in bytes.
+    pub fn memory_bytes(&self) -> usize {
+        crate::sources::hash_map_bytes(&self.scores)
+            + self
+                .scores
+                .values()
+                .map(|scores| scores.capacity() * std::mem::size_of::<f32>())
+                .sum::<usize>()
+    }
+
+    /// Return the p-value of `score` for the gene with `ncbi_gene_id`, if simulated.
+    pub fn p_value(&self, ncbi_gene_id: u32, score: f32) -> Option<f32> {
+        self.scores
+            .get(&ncbi_gene_id)
+            .map(|scores| p_value(scores, score))
+    }
+}
+
+/// Decode the next length-delimited message from `bytes`.
+fn decode_message<M: prost::Message + Default>(bytes: &mut &[u8]) -> Result<M, anyhow::Error> {
+    M::decode_length_delimited(bytes)
+        .map_err(|e| anyhow::anyhow!("could not decode score distributions: {}", e))
+}
+
+/// Encode the distributions `results` for several numbers of terms into an artifact
+/// file for `hpo_release`.
+///
+/// The payload after the `ArtifactHeader` starts with a `SimulationHeader` followed by
+/// the `SimulationResults`, all as length-delimited protobuf messages.
+///
+/// # Panics
+///
+/// Never, the buffer grows as needed.
+pub fn encode_artifact(
+    hpo_release: &str,
+    n_simulations: usize,
+    seed: u64,
+    term_counts: &[usize],
+    results: &[SimulationResults],
+) -> Vec<u8> {
+    let header = SimulationHeader {
+        hpo_release: hpo_release.to_string(),
+        n_simulations: u32::try_from(n_simulations).unwrap_or(u32::MAX),
+        seed,
+        term_counts: term_counts
+            .iter()
+            .map(|count| u32::try_from(*count).unwrap_or(u32::MAX))
+            .collect(),
+    };
+    let mut buf = Vec::new();
+    header
+        .encode_length_delimited(&mut buf)
+        .expect("vector has enough capacity");
+    buf.extend(ScoreDistributions::encode(results));
+    ArtifactHeader::new(ArtifactKind::SimPvalues, hpo_release).encode(&buf)
+}
+
+/// Return the HPO release of the score distributions `bytes` written without
+/// `ArtifactHeader`, i.e., before schema version 1, for `data migrate`.
+///
+/// # Errors
+///
+/// In the case that `bytes` does not start with a `SimulationHeader`.
+pub fn artifact_release(mut bytes: &[u8]) -> Result<String, anyhow::Error> {
+    let header = decode_message::<SimulationHeader>(&mut bytes)?;
+    if header.hpo_release.is_empty() {
+        anyhow::bail!("score distributions without HPO release");
+    }
+    Ok(header.hpo_release)
+}
+
+/// Decode an artifact file from `encode_artifact` into the seed of the random queries
+/// and the distributions by number of terms.
+///
+/// # Errors
+///
+/// In the case that the file is malformed, has an outdated schema, or was computed for
+/// another release than `hpo_release`.
+pub fn decode_artifact(
+    bytes: &[u8],
+    hpo_release: &str,
+) -> Result<(u64, BTreeMap<usize, ScoreDistributions>), anyhow::Error> {
+    let mut bytes = artifact::open(bytes, ArtifactKind::SimPvalues, hpo_release)?;
+    let header = decode_message::<SimulationHeader>(&mut bytes)?;
+
+    let mut distributions = BTreeMap::<usize, ScoreDistributions>::new();
+    while !bytes.is_empty() {
+        let result = decode_message::<SimulationResults>(&mut bytes)?;
+        distributions
+            .entry(result.term_count as usize)
+            .or_default()
+            .scores
+            .insert(result.ncbi_gene_id, result.scores);
+    }
+    Ok((header.seed, distributions))
+}
+
+/// The key of the distributions in the cache, the number of terms and the seed.
+type CacheKey = (usize, u64);
+
+/// A slot of the cache, locked while its distributions are built.
+type Slot = Arc<Mutex<Option<Arc<ScoreDistributions>>>>;
+
+/// An entry of the in-memory cache.
+#[derive(Debug, Default)]
+struct CacheEntry {
+    /// The slot with the distributions, empty until built.
+    slot: Slot,
+    /// The tick of the last use, for evicting the least recently used entries.
+    last_used: u64,
+    /// The approximate memory used by the distributions in bytes, 0 until built.
+    memory_bytes: usize,
+    /// Whether the entry was precomputed and is never evicted.
+    pinned: bool,
+}
+
+/// The entries of the in-memory cache.
+#[derive(Debug, Default)]
+struct CacheEntries {
+    /// The entries by key.
+    entries: HashMap<CacheKey, CacheEntry>,
+    /// Counter for the last use of the entries.
+    tick: u64,
+}
+
+impl CacheEntries {
+    /// Return the slot of `key`, creating it if needed, and mark it as used.
+    fn slot(&mut self, key: CacheKey) -> Slot {
+        self.tick += 1;
+        let entry = self.entries.entry(key).or_default();
+        entry.last_used = self.tick;
+        entry.slot.clone()
+    }
+
+    /// Remove the least recently used entries other than `keep` until the distributions
+    /// use at most `max_bytes`.  Pinned entries are never removed.
+    fn evict(&mut self, keep: CacheKey, max_bytes: usize) {
+        let mut total = self
+            .entries
+            .values()
+            .map(|entry| entry.memory_bytes)
+            .sum::<usize>();
+        let mut candidates = self
+            .entries
+            .iter()
+            .filter(|(key, entry)| **key != keep && !entry.pinned && entry.memory_bytes > 0)
+            .map(|(key, entry)| (entry.last_used, *key))
+            .collect::<Vec<_>>();
+        candidates.sort_unstable();
+        for (_, key) in candidates {
+            if total <= max_bytes {
+                break;
+            }
+            if let Some(entry) = self.entries.remove(&key) {
+                tracing::info!(?key, "evicting score distributions from memory");
+                total -= entry.memory_bytes;
+            }
+        }
+    }
+}
+
+/// The score distributions by number of terms and seed, built on first use.
+#[derive(Debug)]
+pub struct SimulationCache {
+    /// The cache directory to persist the distributions in, if any.
+    disk: Option<DiskCache>,
+    /// The number of random queries per number of terms.
+    n_simulations: usize,
+    /// The maximal memory used by the distributions in bytes, if limited.
+    max_bytes: Option<usize>,
+    /// The seed of the precomputed distributions, `DEFAULT_SEED` if none.
+    default_seed: u64,
+    /// The seeds that may be requested besides the default seed.
+    allowed_seeds: BTreeSet<u64>,
+    /// The distributions built or loaded so far.  The lock is only held to look up the
+    /// slots, which are locked on their own while their distributions are built.
+    loaded: Mutex<CacheEntries>,
+}
+
+impl Default for SimulationCache {
+    fn default() -> Self {
+        Self::new(None, DEFAULT_SIMULATIONS)
+    }
+}
+
+impl SimulationCache {
+    /// Create the cache with `n_simulations` random queries, persisted in `disk` if given.
+    pub fn new(disk: Option<DiskCache>, n_simulations: usize) -> Self {
+        Self {
+            disk,
+            n_simulations,
+            max_bytes: None,
+            default_seed: DEFAULT_SEED,
+            allowed_seeds: BTreeSet::new(),
+            loaded: Mutex::default(),
+        }
+    }
+
+    /// Return the seed to use if none is requested, that of the precomputed distributions.
+    pub fn default_seed(&self) -> u64 {
+        self.default_seed
+    }
+
+    /// Also allow the `seeds` to be requested, e.g., from the command line.
+    #[must_use]
+    pub fn with_allowed_seeds(mut self, seeds: impl IntoIterator<Item = u64>) -> Self {
+        self.allowed_seeds.extend(seeds);
+        self
+    }
+
+    /// Return whether `seed` may be requested, i.e., it is the default seed or allowed with
+    /// `with_allowed_seeds`.
+    ///
+    /// Each other seed would simulate the distributions anew, so clients must not choose
+    /// arbitrary ones.
+    pub fn is_allowed_seed(&self, seed: u64) -> bool {
+        seed == self.default_seed || self.allowed_seeds.contains(&seed)
+    }
+
+    /// Keep the distributions in memory within a fraction of the memory budget
+    /// `max_memory_bytes`, if given, evicting the least recently used ones.
+    #[must_use]
+    pub fn with_memory_budget(mut self, max_memory_bytes: Option<u64>) -> Self {
+        #[allow(
+            clippy::cast_possible_truncation,
+            clippy::cast_precision_loss,
+            clippy::cast_sign_loss
+        )]
+        let max_bytes = max_memory_bytes.map(|bytes| (bytes as f64 * MEMORY_BUDGET_RATIO) as usize);
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// Use the precomputed `distributions` by number of terms for `seed`, e.g., from
+    /// `decode_artifact`, instead of simulating them.  They are never evicted and `seed`
+    /// becomes the default seed if there are any.
+    ///
+    /// # Panics
+    ///
+    /// If the lock is poisoned.
+    #[must_use]
+    pub fn with_precomputed(
+        mut self,
+        seed: u64,
+        distributions: BTreeMap<usize, ScoreDistributions>,
+    ) -> Self {
+        if !distributions.is_empty() {
+            self.default_seed = seed;
+        }
+        self.loaded
+            .lock()
+            .expect("poisoned lock")
+            .entries
+            .extend(
+                distributions
+                    .into_iter()
+                    .map(|(term_count, distributions)| {
+                        let entry = CacheEntry {
+                            memory_bytes: distributions.memory_bytes(),
+                            slot: Arc::new(Mutex::new(Some(Arc::new(distributions)))),
+                            last_used: 0,
+                            pinned: true,
+                        };
+                        ((term_count, seed), entry)
+                    }),
+            );
+        self
+    }
+
+    /// Return the distributions for queries with `term_count` terms and random queries
+    /// drawn with `seed`, and whether they were cached in memory or on disk.
+    ///
+    /// The distributions are loaded from the disk cache or simulated if needed, which
+    /// takes minutes for the full ontology, so call this outside of the async workers,
+    /// e.g., with `web::block`.  Concurrent callers for the same distributions wait for
+    /// the first one, callers for other distributions are not blocked.
+    ///
+    /// # Errors
+    ///
+    /// In the case that the disk cache cannot be read or written.
+    ///
+    /// # Panics
+    ///
+    /// If a lock is poisoned.
+    pub fn get(
+        &self,
+        ontology: &Ontology,
+        term_count: usize,
+        seed: u64,
+    ) -> Result<(Arc<ScoreDistributions>, bool), anyhow::Error> {
+        let params = SimulationParams {
+            term_count: term_count.clamp(1, MAX_TERM_COUNT),
+            n_simulations: self.n_simulations,
+            seed,
+        };
+        let key = (params.term_count, params.seed);
+        let slot = self.loaded.lock().expect("poisoned lock").slot(key);
+        let mut slot = slot.lock().expect("poisoned lock");
+        if let Some(distributions) = slot.as_ref() {
+            return Ok((distributions.clone(), true));
+        }
+
+        let disk_key = DiskCache::key("score-dists", &ontology.hpo_version(), &params)?;
+        let cached = match &self.disk {
+            Some(disk) => disk.get(&disk_key)?,
+            None => None,
+        };
+        let cache_hit = cached.is_some();
+        let bytes = if let Some(bytes) = cached {
+            bytes
+        } else {
+            tracing::info!(?params, "simulating score distributions");
+            let bytes = ScoreDistributions::encode(&simulate(ontology, params));
+            if let Some(disk) = &self.disk {
+                disk.put(&disk_key, &bytes)?;
+            }
+            bytes
+        };
+        let distributions = Arc::new(ScoreDistributions::decode(&bytes)?);
+        *slot = Some(distributions.clone());
+        drop(slot);
+
+        let mut loaded = self.loaded.lock().expect("poisoned lock");
+        if let Some(entry) = loaded.entries.get_mut(&key) {
+            entry.memory_bytes = distributions.memory_bytes();
+        }
+        if let Some(max_bytes) = self.max_bytes {
+            loaded.evict(key, max_bytes);
+        }
+        Ok((distributions, cache_hit))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use hpo::annotations::GeneId;
+
+    /// Build an ontology with four terms below the phenotypic abnormality and two genes.
+    fn build_ontology() -> hpo::Ontology {
+        let mut builder = hpo::builder::Builder::new();
+        builder.new_term("All", 1u32);
+        builder.new_term("Phenotypic abnormality", super::PHENOTYPIC_ABNORMALITY);
+        for term in 2u32..6 {
+            builder.new_term(&format!("Term {term}"), term);
+        }
+        let mut builder = builder.terms_complete();
+        builder
+            .add_parent(1u32, super::PHENOTYPIC_ABNORMALITY)
+            .expect("term must exist");
+        for (parent, child) in [
+            (super::PHENOTYPIC_ABNORMALITY, 2u32),
+            (super::PHENOTYPIC_ABNORMALITY, 3),
+            (2, 4),
+            (3, 5),
+        ] {
+            builder.add_parent(parent, child).expect("term must exist");
+        }
+        let mut builder = builder.connect_all_terms();
+        for (gene, term) in [(10u32, 4u32), (20, 5), (20, 3)] {
+            builder
+                .annotate_gene(GeneId::from(gene), &format!("GENE{gene}"), term.into())
+                .expect("term must exist");
+        }
+        builder
+            .calculate_information_content()
+            .expect("could not compute IC")
+            .build_minimal()
+    }
+
+    #[test]
+    fn p_value() {
+        let scores = [0.0, 1.0, 1.0, 2.0];
+
+        assert!((super::p_value(&scores, 3.0) - 0.2).abs() < 1e-6);
+        assert!((super::p_value(&scores, 2.0) - 0.4).abs() < 1e-6);
+        assert!((super::p_value(&scores, 1.0) - 0.8).abs() < 1e-6);
+        assert!((super::p_value(&scores, 0.0) - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn random_queries_golden() {
+        use hpo::annotations::AnnotationId as _;
+
+        let ontology = build_ontology();
+        let queries = |seed| {
+            super::random_queries(
+                &ontology,
+                super::SimulationParams {
+                    term_count: 2,
+                    n_simulations: 4,
+                    seed,
+                },
+            )
+            .iter()
+            .map(|query| query.iter().map(|term_id| term_id.as_u32()).collect())
+            .collect::<Vec<Vec<u32>>>()
+        };
+
+        // The queries of a seed must not change across platforms and versions, as the
+        // seed reported with the p-values identifies them.
+        assert_eq!(
+            queries(super::DEFAULT_SEED),
+            vec![vec![3, 5], vec![2, 5], vec![3, 4], vec![3, 5]]
+        );
+        assert_eq!(
+            queries(7),
+            vec![vec![2, 5], vec![3, 4], vec![2, 5], vec![2, 3]]
+        );
+    }
+
+    #[test]
+    fn simulate_encode_decode() -> Result<(), anyhow::Error> {
+        let ontology = build_ontology();
+        let params = super::SimulationParams {
+            term_count: 2,
+            n_simulations: 50,
+            seed: 1,
+        };
+
+        let results = super::simulate(&ontology, params);
+        assert_eq!(
+            results.iter().map(|r| r.ncbi_gene_id).collect::<Vec<_>>(),
+            vec![10, 20]
+        );
+        assert!(results
+            .iter()
+            .all(|r| r.scores.len() == 50 && r.scores.windows(2).all(|pair| pair[0] <= pair[1])));
+        assert_eq!(results, super::simulate(&ontology, params));
+
+        let distributions =
+            super::ScoreDistributions::decode(&super::ScoreDistributions::encode(&results))?;
+        let max_score = results[0].scores[49];
+        assert_eq!(distributions.p_value(10, max_score + 1.0), Some(1.0 / 51.0));
+        assert_eq!(distributions.p_value(30, 1.0), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn artifact() -> Result<(), anyhow::Error> {
+        let ontology = build_ontology();
+        let results = [1, 2]
+            .into_iter()
+            .flat_map(|term_count| {
+                super::simulate(
+                    &ontology,
+                    super::SimulationParams {
+                        term_count,
+                        n_simulations: 10,
+                        seed: super::DEFAULT_SEED,
+                    },
+                )
+            })
+            .collect::<Vec<_>>();
+        let bytes = super::encode_artifact("2023-06-06", 10, 7, &[1, 2], &results);
+
+        let (seed, distributions) = super::decode_artifact(&bytes, "2023-06-06")?;
+        assert_eq!(seed, 7);
+        assert_eq!(
+            distributions.keys().copied().collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+        assert_eq!(distributions[&2].scores[&20], results[3].scores);
+        assert!(super::decode_artifact(&bytes, "2024-01-16").is_err());
+
+        // Artifacts without header are refused but can be migrated.
+        let (header, payload) = crate::data::artifact::ArtifactHeader::decode(&bytes)?
+            .expect("artifact must have a header");
+        assert!(super::decode_artifact(payload, "2023-06-06").is_err());
+        let (migrated, migrated_bytes) =
+            crate::data::artifact::migrate(payload)?.expect("legacy artifact must be migrated");
+        assert_eq!(migrated, header);
+        assert_eq!(migrated_bytes, bytes);
+
+        // Precomputed distributions are used instead of simulating them.
+        let cache =
+            super::SimulationCache::new(None, 1000).with_precomputed(seed, distributions.clone());
+        let (precomputed, cache_hit) = cache.get(&ontology, 2, seed)?;
+        assert!(cache_hit);
+        assert_eq!(precomputed.scores, distributions[&2].scores);
+        assert!(cache.is_allowed_seed(seed));
+        assert!(!cache.is_allowed_seed(super::DEFAULT_SEED));
+
+        Ok(())
+    }
+
+    #[test]
+    fn simulation_cache_allowed_seeds() {
+        let cache = super::SimulationCache::new(None, 20);
+        assert!(cache.is_allowed_seed(super::DEFAULT_SEED));
+        assert!(!cache.is_allowed_seed(7));
+
+        let cache = cache.with_allowed_seeds([7, 8]);
+        assert!(cache.is_allowed_seed(super::DEFAULT_SEED));
+        assert!(cache.is_allowed_seed(7));
+        assert!(!cache.is_allowed_seed(9));
+    }
+
+    #[test]
+    fn simulation_cache_persists() -> Result<(), anyhow::Error> {
+        let ontology = build_ontology();
+        let tmp_dir = temp_testdir::TempDir::default();
+        let disk = crate::cache::DiskCache::new(&tmp_dir, 1024 * 1024)?;
+
+        let cache = super::SimulationCache::new(Some(disk.clone()), 20);
+        let (first, cache_hit) = cache.get(&ontology, 2, super::DEFAULT_SEED)?;
+        assert!(!cache_hit);
+        assert!(cache.get(&ontology, 2, super::DEFAULT_SEED)?.1);
+        assert_eq!(std::fs::read_dir(&tmp_dir)?.count(), 1);
+        // A new cache, e.g., after a restart, loads the same distributions from disk.
+        let (second, cache_hit) =
+            super::SimulationCache::new(Some(disk), 20).get(&ontology, 2, super::DEFAULT_SEED)?;
+
+        assert!(cache_hit);
+        assert_eq!(first.scores, second.scores);
+
+        Ok(())
+    }
+
+    #[test]
+    fn simulation_cache_seed_and_budget() -> Result<(), anyhow::Error> {
+        let ontology = build_ontology();
+        let (first, _) = super::SimulationCache::new(None, 20).get(&ontology, 2, 1)?;
+        // A budget for one set of distributions, a quarter of the memory budget.
+        let cache = super::SimulationCache::new(None, 20)
+            .with_memory_budget(Some(4 * first.memory_bytes() as u64));
+
+        assert!(!cache.get(&ontology, 2, 1)?.1);
+        assert!(cache.get(&ontology, 2, 1)?.1);
+        let (other_seed, _) = cache.get(&ontology, 2, 2)?;
+        assert_ne!(first.scores, other_seed.scores);
+
+        // The distributions of seed 1 were evicted for those of seed 2.
+        assert!(!cache.get(&ontology, 2, 1)?.1);
+        let (again, _) = cache.get(&ontology, 2, 1)?;
+        assert_eq!(first.scores, again.scores);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,68 @@
+//! Bitset index from HPO terms to annotated OMIM diseases.
+
+use std::collections::HashMap;
+
+use fixedbitset::FixedBitSet;
+use hpo::{
+    annotations::{Disease as _, OmimDiseaseId},
+    HpoTermId, Ontology,
+};
+
+use super::term_expr::TermBitsets;
+
+/// Index of the OMIM diseases annotated with each term or its descendants, computed at
+/// load time.
+///
+/// Diseases are numbered by ascending disease ID so the sets of diseases can be stored as
+/// bitsets.
+#[derive(Debug, Clone, Default)]
+pub struct TermDiseaseIndex {
+    /// The disease IDs, sorted, the position is the bit in the bitsets.
+    diseases: Vec<OmimDiseaseId>,
+    /// Diseases annotated with the term or any of its descendants.
+    closure: HashMap<HpoTermId, FixedBitSet>,
+}
+
+impl TermDiseaseIndex {
+    /// Build the index for the given ontology.
+    pub fn new(ontology: &Ontology) -> Self {
+        let mut diseases = ontology.omim_diseases().collect::<Vec<_>>();
+        diseases.sort_by_key(|disease| *disease.id());
+        let n_diseases = diseases.len();
+
+        let mut closure: HashMap<HpoTermId, FixedBitSet> = HashMap::new();
+        for (bit, disease) in diseases.iter().enumerate() {
+            for term_id in disease.hpo_terms() {
+                let Some(term) = ontology.hpo(term_id) else {
+                    continue;
+                };
+                for ancestor_id in std::iter::once(term_id).chain(term.all_parent_ids()) {
+                    closure
+                        .entry(ancestor_id)
+                        .or_insert_with(|| FixedBitSet::with_capacity(n_diseases))
+                        .insert(bit);
+                }
+            }
+        }
+
+        Self {
+            diseases: diseases.iter().map(|disease| *disease.id()).collect(),
+            closure,
+        }
+    }
+
+    /// Return the disease IDs, the position is the bit in the bitsets.
+    pub fn diseases(&self) -> &[OmimDiseaseId] {
+        &self.diseases
+    }
+}
+
+impl TermBitsets for TermDiseaseIndex {
+    fn n_items(&self) -> usize {
+        self.diseases.len()
+    }
+
+    fn term_bitset(&self, term_id: HpoTermId) -> Option<&FixedBitSet> {
+        self.closure.get(&term_id)
+    }
+}
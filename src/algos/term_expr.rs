@@ -0,0 +1,180 @@
+//! Boolean expressions over terms, evaluated against bitsets of annotated items.
+
+use std::collections::HashMap;
+
+use fixedbitset::FixedBitSet;
+use hpo::HpoTermId;
+
+/// Bitsets of the items, e.g., genes, annotated with each term or its descendants.
+pub trait TermBitsets {
+    /// Return the number of items.
+    fn n_items(&self) -> usize;
+
+    /// Return the items annotated with `term_id` or its descendants, if any.
+    fn term_bitset(&self, term_id: HpoTermId) -> Option<&FixedBitSet>;
+}
+
+/// A boolean expression over terms, e.g.,
+/// `{"and": [{"term": "HP:0001250"}, {"not": {"term": "HP:0001263"}}]}`.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+#[schema(no_recursion)]
+pub enum TermExpr {
+    /// The items annotated with the term or any of its descendants.
+    Term(String),
+    /// The items matching all of the expressions, all items if empty.
+    And(Vec<TermExpr>),
+    /// The items matching any of the expressions, no items if empty.
+    Or(Vec<TermExpr>),
+    /// The items not matching the expression.
+    Not(Box<TermExpr>),
+}
+
+impl TermExpr {
+    /// Return the terms of the expression in order of appearance.
+    pub fn terms(&self) -> Vec<&str> {
+        match self {
+            TermExpr::Term(term) => vec![term.as_str()],
+            TermExpr::And(exprs) | TermExpr::Or(exprs) => {
+                exprs.iter().flat_map(TermExpr::terms).collect()
+            }
+            TermExpr::Not(expr) => expr.terms(),
+        }
+    }
+
+    /// Return the number of nodes of the expression.
+    pub fn size(&self) -> usize {
+        match self {
+            TermExpr::Term(_) => 1,
+            TermExpr::And(exprs) | TermExpr::Or(exprs) => {
+                1 + exprs.iter().map(TermExpr::size).sum::<usize>()
+            }
+            TermExpr::Not(expr) => 1 + expr.size(),
+        }
+    }
+
+    /// Return the items of `index` matching the expression.
+    ///
+    /// `term_ids` maps each term of the expression to its term IDs, several for a local
+    /// term, which match the items of any of them.  Terms without IDs match no items.
+    pub fn evaluate(
+        &self,
+        index: &impl TermBitsets,
+        term_ids: &HashMap<String, Vec<HpoTermId>>,
+    ) -> FixedBitSet {
+        let n_items = index.n_items();
+        match self {
+            TermExpr::Term(term) => {
+                let mut result = FixedBitSet::with_capacity(n_items);
+                for term_id in term_ids.get(term).into_iter().flatten() {
+                    if let Some(bitset) = index.term_bitset(*term_id) {
+                        result.union_with(bitset);
+                    }
+                }
+                result
+            }
+            TermExpr::And(exprs) => {
+                let mut result = FixedBitSet::with_capacity(n_items);
+                result.insert_range(..);
+                for expr in exprs {
+                    result.intersect_with(&expr.evaluate(index, term_ids));
+                }
+                result
+            }
+            TermExpr::Or(exprs) => {
+                let mut result = FixedBitSet::with_capacity(n_items);
+                for expr in exprs {
+                    result.union_with(&expr.evaluate(index, term_ids));
+                }
+                result
+            }
+            TermExpr::Not(expr) => {
+                let mut result = expr.evaluate(index, term_ids);
+                result.toggle_range(..);
+                result
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use fixedbitset::FixedBitSet;
+    use hpo::HpoTermId;
+
+    use super::TermExpr;
+
+    /// Four items with item 0 and 1 on `HP:0000001` and item 1 and 2 on `HP:0000002`.
+    struct Index(HashMap<HpoTermId, FixedBitSet>);
+
+    impl super::TermBitsets for Index {
+        fn n_items(&self) -> usize {
+            4
+        }
+
+        fn term_bitset(&self, term_id: HpoTermId) -> Option<&FixedBitSet> {
+            self.0.get(&term_id)
+        }
+    }
+
+    fn index() -> Index {
+        let bitset = |bits: &[usize]| {
+            let mut result = FixedBitSet::with_capacity(4);
+            result.extend(bits.iter().copied());
+            result
+        };
+        Index(HashMap::from([
+            (HpoTermId::from_u32(1), bitset(&[0, 1])),
+            (HpoTermId::from_u32(2), bitset(&[1, 2])),
+        ]))
+    }
+
+    fn term_ids() -> HashMap<String, Vec<HpoTermId>> {
+        HashMap::from([
+            ("HP:0000001".to_string(), vec![HpoTermId::from_u32(1)]),
+            ("HP:0000002".to_string(), vec![HpoTermId::from_u32(2)]),
+            (
+                "HP:LAB:0001".to_string(),
+                vec![HpoTermId::from_u32(1), HpoTermId::from_u32(2)],
+            ),
+        ])
+    }
+
+    #[rstest::rstest]
+    #[case(r#"{"term": "HP:0000001"}"#, &[0, 1])]
+    #[case(r#"{"term": "HP:LAB:0001"}"#, &[0, 1, 2])]
+    #[case(r#"{"term": "HP:0000003"}"#, &[])]
+    #[case(r#"{"and": [{"term": "HP:0000001"}, {"term": "HP:0000002"}]}"#, &[1])]
+    #[case(r#"{"or": [{"term": "HP:0000001"}, {"term": "HP:0000002"}]}"#, &[0, 1, 2])]
+    #[case(r#"{"and": [{"term": "HP:0000001"}, {"not": {"term": "HP:0000002"}}]}"#, &[0])]
+    #[case(r#"{"not": {"term": "HP:0000001"}}"#, &[2, 3])]
+    #[case(r#"{"and": []}"#, &[0, 1, 2, 3])]
+    #[case(r#"{"or": []}"#, &[])]
+    fn evaluate(#[case] expr: &str, #[case] expected: &[usize]) -> Result<(), anyhow::Error> {
+        let expr: TermExpr = serde_json::from_str(expr)?;
+
+        assert_eq!(
+            expr.evaluate(&index(), &term_ids())
+                .ones()
+                .collect::<Vec<_>>(),
+            expected
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn terms_and_size() -> Result<(), anyhow::Error> {
+        let expr: TermExpr = serde_json::from_str(
+            r#"{"and": [{"term": "HP:0000001"}, {"not": {"term": "HP:0000002"}}]}"#,
+        )?;
+
+        assert_eq!(expr.terms(), vec!["HP:0000001", "HP:0000002"]);
+        assert_eq!(expr.size(), 4);
+        assert!(serde_json::from_str::<TermExpr>(r#"{"xor": []}"#).is_err());
+
+        Ok(())
+    }
+}
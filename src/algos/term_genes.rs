@@ -0,0 +1,202 @@
+//! Bitset index from HPO terms to annotated genes.
+
+use std::collections::HashMap;
+
+use fixedbitset::FixedBitSet;
+use hpo::{annotations::GeneId, HpoTermId, Ontology};
+
+/// Index of the genes annotated with each term, computed at load time.
+///
+/// Genes are numbered by ascending gene ID so the sets of genes can be stored as bitsets.
+#[derive(Debug, Clone, Default)]
+pub struct TermGeneIndex {
+    /// The gene IDs, sorted, the position is the bit in the bitsets.
+    genes: Vec<GeneId>,
+    /// Genes directly annotated with the term.
+    direct: HashMap<HpoTermId, FixedBitSet>,
+    /// Genes annotated with the term or any of its descendants.
+    closure: HashMap<HpoTermId, FixedBitSet>,
+}
+
+impl TermGeneIndex {
+    /// Build the index for the given ontology.
+    pub fn new(ontology: &Ontology) -> Self {
+        let mut genes = ontology.genes().collect::<Vec<_>>();
+        genes.sort_by_key(|gene| *gene.id());
+        let n_genes = genes.len();
+
+        let mut direct: HashMap<HpoTermId, FixedBitSet> = HashMap::new();
+        let mut closure: HashMap<HpoTermId, FixedBitSet> = HashMap::new();
+        for (bit, gene) in genes.iter().enumerate() {
+            for term_id in gene.hpo_terms() {
+                direct
+                    .entry(term_id)
+                    .or_insert_with(|| FixedBitSet::with_capacity(n_genes))
+                    .insert(bit);
+                let Some(term) = ontology.hpo(term_id) else {
+                    continue;
+                };
+                for ancestor_id in std::iter::once(term_id).chain(term.all_parent_ids()) {
+                    closure
+                        .entry(ancestor_id)
+                        .or_insert_with(|| FixedBitSet::with_capacity(n_genes))
+                        .insert(bit);
+                }
+            }
+        }
+
+        Self {
+            genes: genes.iter().map(|gene| *gene.id()).collect(),
+            direct,
+            closure,
+        }
+    }
+
+    /// Return the IDs of the genes annotated with `term_id`, sorted by gene ID.
+    ///
+    /// If `include_descendants` is set then genes annotated with any descendant of the
+    /// term are included as well.
+    pub fn gene_ids(&self, term_id: HpoTermId, include_descendants: bool) -> Vec<GeneId> {
+        let bitsets = if include_descendants {
+            &self.closure
+        } else {
+            &self.direct
+        };
+        bitsets
+            .get(&term_id)
+            .map(|bitset| bitset.ones().map(|bit| self.genes[bit]).collect())
+            .unwrap_or_default()
+    }
+
+    /// Return the gene IDs, the position is the bit in the bitsets.
+    pub fn genes(&self) -> &[GeneId] {
+        &self.genes
+    }
+
+    /// Return the genes annotated with at least `min_matches` of `term_ids`, sorted by
+    /// gene ID, each with the positions of the matched terms in `term_ids`.
+    ///
+    /// The genes are matched as by `gene_ids` with `include_descendants`.
+    pub fn genes_matching(
+        &self,
+        term_ids: &[HpoTermId],
+        min_matches: usize,
+        include_descendants: bool,
+    ) -> Vec<(GeneId, Vec<usize>)> {
+        let bitsets = if include_descendants {
+            &self.closure
+        } else {
+            &self.direct
+        };
+        let mut matched = vec![Vec::new(); self.genes.len()];
+        for (i, term_id) in term_ids.iter().enumerate() {
+            if let Some(bitset) = bitsets.get(term_id) {
+                for bit in bitset.ones() {
+                    matched[bit].push(i);
+                }
+            }
+        }
+        matched
+            .into_iter()
+            .enumerate()
+            .filter(|(_, matched)| !matched.is_empty() && matched.len() >= min_matches)
+            .map(|(bit, matched)| (self.genes[bit], matched))
+            .collect()
+    }
+}
+
+impl super::term_expr::TermBitsets for TermGeneIndex {
+    fn n_items(&self) -> usize {
+        self.genes.len()
+    }
+
+    fn term_bitset(&self, term_id: HpoTermId) -> Option<&FixedBitSet> {
+        self.closure.get(&term_id)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use hpo::{annotations::GeneId, builder::Builder, HpoTermId, Ontology};
+
+    /// Build a small ontology with the structure
+    ///
+    /// ```text
+    /// HP:0000001 (Seizure)
+    /// +-- HP:0000002 (Focal seizure)
+    /// |   +-- HP:0000004 (Focal motor seizure)
+    /// +-- HP:0000003 (Generalized seizure)
+    /// ```
+    ///
+    /// with gene 10 on `HP:0000004`, gene 20 on `HP:0000002`, and gene 30 on `HP:0000003`.
+    fn seizure_ontology() -> Ontology {
+        let mut builder = Builder::new();
+        builder.new_term("Seizure", 1u32);
+        builder.new_term("Focal seizure", 2u32);
+        builder.new_term("Generalized seizure", 3u32);
+        builder.new_term("Focal motor seizure", 4u32);
+        let mut builder = builder.terms_complete();
+        builder.add_parent(1u32, 2u32).expect("term must exist");
+        builder.add_parent(1u32, 3u32).expect("term must exist");
+        builder.add_parent(2u32, 4u32).expect("term must exist");
+        let mut builder = builder.connect_all_terms();
+        for (gene_id, name, term) in [
+            (10, "GENE10", 4u32),
+            (20, "GENE20", 2u32),
+            (30, "GENE30", 3u32),
+        ] {
+            builder
+                .annotate_gene(GeneId::from(gene_id), name, term.into())
+                .expect("term must exist");
+        }
+        builder
+            .calculate_information_content()
+            .expect("could not compute IC")
+            .build_minimal()
+    }
+
+    #[test]
+    fn gene_ids_direct() {
+        let index = super::TermGeneIndex::new(&seizure_ontology());
+
+        assert!(index.gene_ids(HpoTermId::from_u32(1), false).is_empty());
+        assert_eq!(
+            index.gene_ids(HpoTermId::from_u32(2), false),
+            vec![GeneId::from(20)]
+        );
+    }
+
+    #[test]
+    fn gene_ids_with_descendants() {
+        let index = super::TermGeneIndex::new(&seizure_ontology());
+
+        assert_eq!(
+            index.gene_ids(HpoTermId::from_u32(1), true),
+            vec![GeneId::from(10), GeneId::from(20), GeneId::from(30)]
+        );
+        assert_eq!(
+            index.gene_ids(HpoTermId::from_u32(2), true),
+            vec![GeneId::from(10), GeneId::from(20)]
+        );
+    }
+
+    #[test]
+    fn genes_matching() {
+        let index = super::TermGeneIndex::new(&seizure_ontology());
+        let term_ids = [2u32, 3, 4].map(HpoTermId::from_u32);
+
+        assert_eq!(
+            index.genes_matching(&term_ids, 1, false),
+            vec![
+                (GeneId::from(10), vec![2]),
+                (GeneId::from(20), vec![0]),
+                (GeneId::from(30), vec![1]),
+            ]
+        );
+        assert_eq!(
+            index.genes_matching(&term_ids, 2, true),
+            vec![(GeneId::from(10), vec![0, 2])]
+        );
+        assert!(index.genes_matching(&term_ids, 3, true).is_empty());
+    }
+}
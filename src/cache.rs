@@ -0,0 +1,238 @@
+//! Persistent cache directory for precomputed artifacts, e.g., the simulated score
+//! distributions for the p-values.
+//!
+//! Entries are stored in files named by a versioned key from the HPO release and the
+//! parameters they were computed with, so they are reused across restarts and never
+//! confused with artifacts from another release.  When the directory grows beyond its
+//! size limit, the least recently used entries are removed.
+
+use std::{
+    fmt::Write as _,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use sha2::Digest as _;
+
+/// Version of the layout of the cache entries, part of every key.
+const CACHE_FORMAT_VERSION: u32 = 1;
+
+/// Suffix of the cache entry files.
+const ENTRY_SUFFIX: &str = ".bin";
+
+/// Managed cache directory with least recently used eviction.
+#[derive(Debug, Clone)]
+pub struct DiskCache {
+    /// The cache directory.
+    dir: PathBuf,
+    /// The maximal total size of the entries in bytes.
+    max_bytes: u64,
+}
+
+impl DiskCache {
+    /// Open the cache in `dir` with at most `max_bytes`, creating the directory if needed.
+    ///
+    /// # Errors
+    ///
+    /// In the case that the directory cannot be created.
+    pub fn new<P: AsRef<Path>>(dir: P, max_bytes: u64) -> Result<Self, anyhow::Error> {
+        let dir = dir.as_ref().to_path_buf();
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| anyhow::anyhow!("could not create cache directory {:?}: {}", dir, e))?;
+        Ok(Self { dir, max_bytes })
+    }
+
+    /// Return the key of the `kind` of artifact for `hpo_release` and `params`.
+    ///
+    /// The key is readable in directory listings, e.g.,
+    /// `score-dists-2023-06-06-0123456789abcdef`, the parameters are hashed.
+    ///
+    /// # Errors
+    ///
+    /// In the case that `params` cannot be serialized.
+    pub fn key<T: serde::Serialize>(
+        kind: &str,
+        hpo_release: &str,
+        params: &T,
+    ) -> Result<String, anyhow::Error> {
+        let mut hasher = sha2::Sha256::new();
+        hasher.update(CACHE_FORMAT_VERSION.to_le_bytes());
+        hasher.update(serde_json::to_vec(params)?);
+        let hash = hasher
+            .finalize()
+            .iter()
+            .take(8)
+            .fold(String::new(), |mut hash, byte| {
+                write!(hash, "{byte:02x}").expect("writing to string cannot fail");
+                hash
+            });
+        let release = hpo_release
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+            .collect::<String>();
+        Ok(format!("{kind}-{release}-{hash}"))
+    }
+
+    /// Return the path of the entry with `key`.
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{key}{ENTRY_SUFFIX}"))
+    }
+
+    /// Return the entry with `key`, marking it as recently used, if any.
+    ///
+    /// # Errors
+    ///
+    /// In the case that the entry exists but cannot be read.
+    pub fn get(&self, key: &str) -> Result<Option<Vec<u8>>, anyhow::Error> {
+        let path = self.path(key);
+        let bytes = match std::fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => anyhow::bail!("could not read cache entry {:?}: {}", path, e),
+        };
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .and_then(|file| file.set_modified(SystemTime::now()))
+            .map_err(|e| anyhow::anyhow!("could not touch cache entry {:?}: {}", path, e))?;
+        Ok(Some(bytes))
+    }
+
+    /// Store `bytes` as the entry with `key` and evict the least recently used entries
+    /// beyond the size limit.
+    ///
+    /// # Errors
+    ///
+    /// In the case that the entry cannot be written.
+    pub fn put(&self, key: &str, bytes: &[u8]) -> Result<(), anyhow::Error> {
+        let path = self.path(key);
+        // Write to a temporary file first so that readers never see partial entries.
+        let tmp_path = self.dir.join(format!(".{key}.tmp"));
+        std::fs::write(&tmp_path, bytes)
+            .and_then(|()| std::fs::rename(&tmp_path, &path))
+            .map_err(|e| anyhow::anyhow!("could not write cache entry {:?}: {}", path, e))?;
+        self.evict(&path)
+    }
+
+    /// Return the entry with `key` or build, store, and return it.
+    ///
+    /// # Errors
+    ///
+    /// In the case that `build` fails or the entry cannot be read or written.
+    pub fn get_or_build(
+        &self,
+        key: &str,
+        build: impl FnOnce() -> Result<Vec<u8>, anyhow::Error>,
+    ) -> Result<Vec<u8>, anyhow::Error> {
+        if let Some(bytes) = self.get(key)? {
+            tracing::debug!(key, "cache hit");
+            return Ok(bytes);
+        }
+        tracing::info!(key, "building cache entry");
+        let bytes = build()?;
+        self.put(key, &bytes)?;
+        Ok(bytes)
+    }
+
+    /// Remove the least recently used entries until the total size is within the limit.
+    ///
+    /// The entry at `keep` is never removed, even if it exceeds the limit on its own.
+    fn evict(&self, keep: &Path) -> Result<(), anyhow::Error> {
+        let mut entries = Vec::new();
+        for entry in std::fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            if !path.to_string_lossy().ends_with(ENTRY_SUFFIX) {
+                continue;
+            }
+            let metadata = entry.metadata()?;
+            entries.push((metadata.modified()?, metadata.len(), path));
+        }
+
+        let mut total = entries.iter().map(|(_, len, _)| len).sum::<u64>();
+        entries.sort();
+        for (_, len, path) in entries {
+            if total <= self.max_bytes {
+                break;
+            }
+            if path == keep {
+                continue;
+            }
+            tracing::info!(?path, "evicting cache entry");
+            std::fs::remove_file(&path)?;
+            total -= len;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, SystemTime};
+
+    #[test]
+    fn key() -> Result<(), anyhow::Error> {
+        let key = super::DiskCache::key("score-dists", "2023-06-06", &(3, 1000))?;
+
+        assert!(key.starts_with("score-dists-2023-06-06-"));
+        assert_eq!(
+            key,
+            super::DiskCache::key("score-dists", "2023-06-06", &(3, 1000))?
+        );
+        assert_ne!(
+            key,
+            super::DiskCache::key("score-dists", "2023-06-06", &(4, 1000))?
+        );
+        assert_ne!(
+            key,
+            super::DiskCache::key("score-dists", "2024-01-16", &(3, 1000))?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn get_or_build() -> Result<(), anyhow::Error> {
+        let tmp_dir = temp_testdir::TempDir::default();
+        let cache = super::DiskCache::new(tmp_dir.join("cache"), 1024)?;
+
+        assert_eq!(
+            cache.get_or_build("a", || Ok(vec![1, 2, 3]))?,
+            vec![1, 2, 3]
+        );
+        // Reopening the cache, e.g., after a restart, reuses the entry.
+        let cache = super::DiskCache::new(tmp_dir.join("cache"), 1024)?;
+        assert_eq!(
+            cache.get_or_build("a", || anyhow::bail!("must not be built again"))?,
+            vec![1, 2, 3]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn evict_least_recently_used() -> Result<(), anyhow::Error> {
+        let tmp_dir = temp_testdir::TempDir::default();
+        let cache = super::DiskCache::new(&tmp_dir, 10)?;
+
+        cache.put("a", &[0; 4])?;
+        cache.put("b", &[0; 4])?;
+        // Make `a` older than `b` before using it so that `b` is the least recently used.
+        let past = SystemTime::now() - Duration::from_secs(60);
+        for (key, age) in [("a", 2), ("b", 1)] {
+            std::fs::File::options()
+                .write(true)
+                .open(cache.path(key))?
+                .set_modified(past - Duration::from_secs(age))?;
+        }
+        assert!(cache.get("a")?.is_some());
+        cache.put("c", &[0; 4])?;
+
+        assert!(cache.get("a")?.is_some());
+        assert!(cache.get("b")?.is_none());
+        assert!(cache.get("c")?.is_some());
+
+        Ok(())
+    }
+}
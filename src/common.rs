@@ -10,6 +10,10 @@ use hpo::{
 };
 use strum::{EnumIter, IntoEnumIterator};
 
+pub use viguno_core::parse_term_id;
+
+use crate::algos::ic::{IcTables, PairwiseSimilarity, TableSimilarity};
+
 /// Shared command line arguments.
 #[derive(Parser, Debug)]
 pub struct Args {
@@ -18,6 +22,63 @@ pub struct Args {
     pub verbose: Verbosity<InfoLevel>,
 }
 
+/// Errors about the HPO data directory and the files in it.
+#[derive(Debug, thiserror::Error)]
+pub enum DataDirError {
+    /// The data directory does not exist.
+    #[error("HPO data directory {} does not exist", .0.display())]
+    MissingDir(std::path::PathBuf),
+    /// The path of the data directory is not a directory.
+    #[error("HPO data directory {} is not a directory", .0.display())]
+    NotADir(std::path::PathBuf),
+    /// A required file is missing.
+    #[error("missing {what} file {}", .path.display())]
+    MissingFile {
+        /// Description of the file, e.g., "HPO OBO".
+        what: &'static str,
+        /// The path of the missing file.
+        path: std::path::PathBuf,
+    },
+    /// The path cannot be represented as UTF-8, as required by the `hpo` crate.
+    #[error("path {} is not valid UTF-8", .0.display())]
+    NonUtf8Path(std::path::PathBuf),
+}
+
+/// Check that `path_hpo_dir` is an existing directory.
+///
+/// # Errors
+///
+/// In the case that the directory does not exist or is no directory.
+pub fn check_data_dir<P: AsRef<std::path::Path>>(path_hpo_dir: P) -> Result<(), DataDirError> {
+    let path_hpo_dir = path_hpo_dir.as_ref();
+    if !path_hpo_dir.exists() {
+        Err(DataDirError::MissingDir(path_hpo_dir.to_path_buf()))
+    } else if !path_hpo_dir.is_dir() {
+        Err(DataDirError::NotADir(path_hpo_dir.to_path_buf()))
+    } else {
+        Ok(())
+    }
+}
+
+/// Return the path of the required file `file_name` in the data directory `path_hpo_dir`.
+///
+/// # Errors
+///
+/// In the case that the directory or the file does not exist.
+pub fn data_file<P: AsRef<std::path::Path>>(
+    path_hpo_dir: P,
+    file_name: &str,
+    what: &'static str,
+) -> Result<std::path::PathBuf, DataDirError> {
+    check_data_dir(&path_hpo_dir)?;
+    let path = path_hpo_dir.as_ref().join(file_name);
+    if path.is_file() {
+        Ok(path)
+    } else {
+        Err(DataDirError::MissingFile { what, path })
+    }
+}
+
 /// Load HPO either from binary `$path_hpo/hpo.bin` if it exist, otherwise load as
 /// standard directory from `$path_hpo`.
 ///
@@ -25,24 +86,47 @@ pub struct Args {
 ///
 /// In the case of loading failure.
 pub fn load_hpo<P: AsRef<std::path::Path>>(path: P) -> Result<hpo::Ontology, anyhow::Error> {
-    if path.as_ref().join("hpo.bin").exists() {
+    let path = path.as_ref();
+    check_data_dir(path)?;
+    if path.join("hpo.bin").exists() {
         tracing::info!(
             "  attempting to load binary HPO file from {}",
-            path.as_ref().display()
+            path.display()
         );
-        Ok(hpo::Ontology::from_binary(path.as_ref().join("hpo.bin"))?)
+        Ok(hpo::Ontology::from_binary(path.join("hpo.bin"))?)
     } else {
         tracing::info!(
             "  attempting to load HPO from standard file {}",
-            path.as_ref().display()
+            path.display()
         );
-        Ok(hpo::Ontology::from_standard(&format!(
-            "{}",
-            path.as_ref().display()
-        ))?)
+        data_file(path, "hp.obo", "HPO OBO")?;
+        data_file(path, "genes_to_phenotype.txt", "gene annotation")?;
+        data_file(path, "phenotype.hpoa", "disease annotation")?;
+        Ok(hpo::Ontology::from_standard(path.to_str().ok_or_else(
+            || DataDirError::NonUtf8Path(path.to_path_buf()),
+        )?)?)
     }
 }
 
+/// Read the build date of the annotations from the header of `$path_hpo/phenotype.hpoa`.
+///
+/// Newer releases give the date in the `#version:` header line, older ones in `#date:`.
+/// Returns `None` if the file cannot be read or has neither header line.
+pub fn load_annotation_date<P: AsRef<std::path::Path>>(path: P) -> Option<String> {
+    use std::io::BufRead as _;
+
+    let file = std::fs::File::open(path.as_ref().join("phenotype.hpoa")).ok()?;
+    std::io::BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .take_while(|line| line.starts_with('#'))
+        .find_map(|line| {
+            line.strip_prefix("#version:")
+                .or_else(|| line.strip_prefix("#date:"))
+                .map(|date| date.trim().to_string())
+        })
+}
+
 /// Enum for representing the information content kind.
 ///
 /// We replicate what is in the `hpo` create so we can put them on the command line and use
@@ -57,6 +141,7 @@ pub fn load_hpo<P: AsRef<std::path::Path>>(path: P) -> Result<hpo::Ontology, any
     Eq,
     PartialOrd,
     Ord,
+    Hash,
     derive_more::Display,
     serde::Serialize,
     serde::Deserialize,
@@ -71,6 +156,27 @@ pub enum IcBasedOn {
     /// Compute information content based on OMIM disease.
     #[display("omim")]
     Omim,
+    /// Compute information content based on ORPHA disease.
+    #[display("orpha")]
+    Orpha,
+    /// Compute information content based on the union of OMIM and ORPHA diseases.
+    #[display("combined")]
+    Combined,
+    /// Use externally computed information content loaded at startup.
+    #[display("custom")]
+    Custom,
+}
+
+impl IcBasedOn {
+    /// Return the corresponding kind of the `hpo` crate, if any.
+    pub fn to_kind(self) -> Option<InformationContentKind> {
+        match self {
+            IcBasedOn::Gene => Some(InformationContentKind::Gene),
+            IcBasedOn::Omim => Some(InformationContentKind::Omim),
+            IcBasedOn::Orpha => Some(InformationContentKind::Orpha),
+            IcBasedOn::Combined | IcBasedOn::Custom => None,
+        }
+    }
 }
 
 impl FromStr for IcBasedOn {
@@ -83,6 +189,162 @@ impl FromStr for IcBasedOn {
     }
 }
 
+/// Enum for representing whether annotations propagate up the ontology.
+///
+/// With `Ancestors`, the true-path rule applies and a gene or disease annotated with
+/// a term is also considered annotated with all of the term's ancestors.
+#[derive(
+    Default,
+    Debug,
+    Clone,
+    Copy,
+    EnumIter,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    derive_more::Display,
+    serde::Serialize,
+    serde::Deserialize,
+    utoipa::ToSchema,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum AnnotationPropagation {
+    /// Only consider direct annotations.
+    #[display("none")]
+    None,
+    /// Propagate annotations to all ancestors (true-path rule).
+    #[default]
+    #[display("ancestors")]
+    Ancestors,
+}
+
+impl AnnotationPropagation {
+    /// Return whether annotations propagate to ancestors.
+    pub fn is_propagated(self) -> bool {
+        self == AnnotationPropagation::Ancestors
+    }
+}
+
+impl FromStr for AnnotationPropagation {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        AnnotationPropagation::iter()
+            .find(|m| m.to_string().as_str().eq(s))
+            .ok_or(anyhow::anyhow!("unknown annotation propagation: {}", s))
+    }
+}
+
+/// Experimental scoring pipelines that can be enabled per request.
+///
+/// Features must be allowed with the server's `--experimental-allowlist` before they
+/// can be requested with the `experimental` query parameter.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    EnumIter,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    derive_more::Display,
+    serde::Serialize,
+    serde::Deserialize,
+    utoipa::ToSchema,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum ExperimentalFeature {
+    /// Diffuse scores over the network by random walk with restart when boosting.
+    #[display("rwr-network")]
+    RwrNetwork,
+    /// Weight the best term matches by the terms' information content.
+    #[display("weighted-combiner")]
+    WeightedCombiner,
+}
+
+impl FromStr for ExperimentalFeature {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ExperimentalFeature::iter()
+            .find(|m| m.to_string().as_str().eq(s))
+            .ok_or(anyhow::anyhow!("unknown experimental feature: {}", s))
+    }
+}
+
+/// External terminologies that HPO terms are mapped to by their xrefs.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    EnumIter,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    derive_more::Display,
+    serde::Serialize,
+    serde::Deserialize,
+    utoipa::ToSchema,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum MappingSystem {
+    /// SNOMED CT, xref prefix `SNOMEDCT_US`.
+    #[display("snomed")]
+    Snomed,
+    /// The UMLS Metathesaurus, xref prefix `UMLS`.
+    #[display("umls")]
+    Umls,
+    /// Medical Subject Headings, xref prefix `MSH`.
+    #[display("mesh")]
+    Mesh,
+}
+
+impl MappingSystem {
+    /// Return the xref prefixes of the system, the canonical one first.
+    pub fn prefixes(self) -> &'static [&'static str] {
+        match self {
+            MappingSystem::Snomed => &["SNOMEDCT_US", "SNOMEDCT", "SCTID"],
+            MappingSystem::Umls => &["UMLS"],
+            MappingSystem::Mesh => &["MSH", "MESH"],
+        }
+    }
+
+    /// Split `xref`, e.g., `UMLS:C0036572`, into the system and the code, if known.
+    pub fn split_xref(xref: &str) -> Option<(Self, &str)> {
+        let (prefix, code) = xref.split_once(':')?;
+        MappingSystem::iter()
+            .find(|system| system.prefixes().contains(&prefix))
+            .map(|system| (system, code))
+    }
+
+    /// Return `code` without any of the system's xref prefixes.
+    pub fn strip_prefix(self, code: &str) -> &str {
+        code.split_once(':')
+            .filter(|(prefix, _)| {
+                self.prefixes()
+                    .iter()
+                    .any(|known| known.eq_ignore_ascii_case(prefix))
+            })
+            .map_or(code, |(_, code)| code)
+    }
+}
+
+impl FromStr for MappingSystem {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        MappingSystem::iter()
+            .find(|m| m.to_string().as_str().eq(s))
+            .ok_or(anyhow::anyhow!("unknown mapping system: {}", s))
+    }
+}
+
 /// Enum for representing similarity method to use.
 ///
 /// We replicate what is in the `hpo` create so we can put them on the command line and use
@@ -132,11 +394,27 @@ pub enum SimilarityMethod {
 }
 
 /// Convert to pairwise similarity.
-pub fn to_pairwise_sim(sim: SimilarityMethod, ic_based_on: IcBasedOn) -> Builtins {
-    let kind = match ic_based_on {
-        IcBasedOn::Gene => InformationContentKind::Gene,
-        IcBasedOn::Omim => InformationContentKind::Omim,
-    };
+///
+/// Information content bases that are not built into the `hpo` crate, as well as all
+/// information content from direct annotations only, are looked up in `ic_tables`.
+pub fn to_pairwise_sim(
+    sim: SimilarityMethod,
+    ic_based_on: IcBasedOn,
+    propagation: AnnotationPropagation,
+    ic_tables: &IcTables,
+) -> PairwiseSimilarity<'_> {
+    if let Some(table) = ic_tables.table(ic_based_on, propagation) {
+        PairwiseSimilarity::Table(TableSimilarity::new(sim, table))
+    } else {
+        let kind = ic_based_on
+            .to_kind()
+            .unwrap_or(InformationContentKind::Gene);
+        PairwiseSimilarity::Builtin(to_builtin_sim(sim, kind))
+    }
+}
+
+/// Convert to pairwise similarity built into the `hpo` crate.
+pub fn to_builtin_sim(sim: SimilarityMethod, kind: InformationContentKind) -> Builtins {
     match sim {
         SimilarityMethod::DistanceGene => Builtins::Distance(kind),
         SimilarityMethod::GraphIc => Builtins::GraphIc(kind),
@@ -212,6 +490,148 @@ impl FromStr for ScoreCombiner {
     }
 }
 
+/// Calibration of similarity scores into the range from 0 to 1.
+#[derive(
+    Default,
+    Debug,
+    Clone,
+    Copy,
+    EnumIter,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    derive_more::Display,
+    serde::Serialize,
+    serde::Deserialize,
+    utoipa::ToSchema,
+)]
+pub enum ScoreNormalization {
+    /// Report the raw scores.
+    #[default]
+    #[display("none")]
+    #[serde(rename = "none")]
+    None,
+    /// Divide by the maximal possible score, for Resnik the maximal information content.
+    #[display("max_ic")]
+    #[serde(rename = "max_ic")]
+    MaxIc,
+    /// Scale from the minimal to the maximal possible score, for Resnik from the
+    /// information content of the root to the maximal information content.
+    #[display("minmax")]
+    #[serde(rename = "minmax")]
+    MinMax,
+}
+
+impl FromStr for ScoreNormalization {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ScoreNormalization::iter()
+            .find(|m| m.to_string().as_str().eq(s))
+            .ok_or(anyhow::anyhow!("unknown score normalization: {}", s))
+    }
+}
+
+/// Method for scoring genes in the term-gene similarity query.
+#[derive(
+    Default,
+    Debug,
+    Clone,
+    Copy,
+    EnumIter,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    derive_more::Display,
+    serde::Serialize,
+    serde::Deserialize,
+    utoipa::ToSchema,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum TermGeneMethod {
+    /// The Phenomizer score.
+    #[default]
+    #[display("phenomizer")]
+    Phenomizer,
+    /// Reciprocal rank fusion of the configured ensemble methods.
+    #[display("ensemble")]
+    Ensemble,
+}
+
+impl FromStr for TermGeneMethod {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        TermGeneMethod::iter()
+            .find(|m| m.to_string().as_str().eq(s))
+            .ok_or(anyhow::anyhow!("unknown term-gene method: {}", s))
+    }
+}
+
+/// The constants of a score normalization, reported together with the normalized scores.
+///
+/// Scores are mapped to `(score - min) / (max - min)`, clamped to the range from 0 to 1.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct NormalizationConstants {
+    /// The applied normalization.
+    pub normalization: ScoreNormalization,
+    /// The score mapped to 0.
+    pub min: f32,
+    /// The score mapped to 1.
+    pub max: f32,
+}
+
+impl NormalizationConstants {
+    /// Return the constants for scores of `method` given the information content `range`.
+    ///
+    /// All methods but Resnik already yield scores from 0 to 1.  Returns `None` for
+    /// `ScoreNormalization::None`.
+    pub fn new(
+        normalization: ScoreNormalization,
+        method: SimilarityMethod,
+        range: crate::algos::ic::IcRange,
+    ) -> Option<Self> {
+        let (min, max) = if method == SimilarityMethod::Resnik {
+            (range.min, range.max)
+        } else {
+            (0.0, 1.0)
+        };
+        match normalization {
+            ScoreNormalization::None => None,
+            ScoreNormalization::MaxIc => Some(Self {
+                normalization,
+                min: 0.0,
+                max,
+            }),
+            ScoreNormalization::MinMax => Some(Self {
+                normalization,
+                min,
+                max,
+            }),
+        }
+    }
+
+    /// Normalize `score`, zero if the range is empty.
+    pub fn apply(&self, score: f32) -> f32 {
+        if self.max <= self.min {
+            0.0
+        } else {
+            ((score - self.min) / (self.max - self.min)).clamp(0.0, 1.0)
+        }
+    }
+
+    /// Normalize the difference `delta` between two scores, zero if the range is empty.
+    pub fn scale(&self, delta: f32) -> f32 {
+        if self.max <= self.min {
+            0.0
+        } else {
+            delta / (self.max - self.min)
+        }
+    }
+}
+
 /// The version of `viguno` package.
 #[cfg(not(test))]
 const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -247,67 +667,404 @@ impl Version {
     }
 }
 
-/// Code related to the HGNC xlink table.
-pub mod hgnc_xlink {
-    use std::collections::HashMap;
+/// Interning of strings as `u32` handles into an arena.
+///
+/// Interning saves memory for repeated strings and lets tables refer to them by handle.
+/// Strings are hashed with FNV-1a, which is faster than the default `SipHash` for short keys
+/// such as gene symbols.  It is used for the HGNC gene IDs of the `HgncXlink` and, by
+/// `SymbolMap`, for the gene symbols of the constraint, expression, and panel tables, see
+/// `benches/interner.rs`.
+pub mod interner {
+    use std::{
+        collections::HashMap,
+        hash::{BuildHasherDefault, Hasher},
+    };
+
+    /// Hasher for the keys of `Interner::by_hash`, which are hashes already.
+    #[derive(Debug, Clone, Copy, Default)]
+    struct PrehashedHasher(u64);
+
+    impl Hasher for PrehashedHasher {
+        fn finish(&self) -> u64 {
+            self.0
+        }
 
-    /// Data structure for representing an entry of the table.
-    #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
-    #[serde_with::skip_serializing_none]
-    pub struct Entry {
-        /// HGNC gene ID.
-        pub hgnc_id: String,
-        /// Ensembl gene ID.
-        pub ensembl_gene_id: Option<String>,
-        /// Entrez gene ID.
-        #[serde(alias = "entrez_id")]
-        pub ncgi_gene_id: Option<u32>,
-        /// Gene symbol.
-        pub gene_symbol: String,
+        fn write(&mut self, bytes: &[u8]) {
+            for byte in bytes {
+                self.0 = self.0.rotate_left(8) ^ u64::from(*byte);
+            }
+        }
+
+        fn write_u64(&mut self, value: u64) {
+            self.0 = value;
+        }
     }
 
-    /// Read the `hgnc_xlink.tsv` file using the `csv` crate via serde.
-    ///
-    /// # Errors
-    ///
-    /// In the case that the file could not be read.
-    pub fn load_entries<P: AsRef<std::path::Path>>(path: &P) -> Result<Vec<Entry>, anyhow::Error> {
-        let mut rdr = csv::ReaderBuilder::new()
-            .delimiter(b'\t')
-            .has_headers(true)
-            .from_path(path.as_ref())?;
-        let mut entries = Vec::new();
-        for result in rdr.deserialize() {
-            let entry: Entry = result?;
-            entries.push(entry);
+    /// Handle of an interned string.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+    pub struct Symbol(u32);
+
+    impl Symbol {
+        /// Return the position of the string in the order of interning.
+        pub fn index(self) -> usize {
+            self.0 as usize
         }
-        Ok(entries)
     }
 
-    /// Read the `hgnc_xlink.tsv` into a map from NCBI gene ID to HGNC gene ID.
-    ///
-    /// # Errors
+    /// Interner storing each distinct string once in an arena.
+    #[derive(Debug, Clone, Default)]
+    pub struct Interner {
+        /// The concatenated strings.
+        arena: String,
+        /// The end of each string in `arena`, indexed by handle.
+        ends: Vec<usize>,
+        /// Map from the hash of a string to the handles of the strings with that hash.
+        by_hash: HashMap<u64, Vec<Symbol>, BuildHasherDefault<PrehashedHasher>>,
+    }
+
+    impl Interner {
+        /// Return the FNV-1a hash of `value` for the lookup.
+        fn hash(value: &str) -> u64 {
+            value.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+                (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+            })
+        }
+
+        /// Intern `value` and return its handle, the existing one if already interned.
+        ///
+        /// # Panics
+        ///
+        /// If more than `u32::MAX` strings are interned.
+        pub fn intern(&mut self, value: &str) -> Symbol {
+            let hash = Self::hash(value);
+            if let Some(symbol) = self.lookup(hash, value) {
+                return symbol;
+            }
+            let symbol = Symbol(u32::try_from(self.ends.len()).expect("too many strings"));
+            self.arena.push_str(value);
+            self.ends.push(self.arena.len());
+            self.by_hash.entry(hash).or_default().push(symbol);
+            symbol
+        }
+
+        /// Return the handle of `value`, if interned.
+        pub fn get(&self, value: &str) -> Option<Symbol> {
+            self.lookup(Self::hash(value), value)
+        }
+
+        /// Return the handle of `value` with the given `hash`, if interned.
+        fn lookup(&self, hash: u64, value: &str) -> Option<Symbol> {
+            self.by_hash
+                .get(&hash)?
+                .iter()
+                .copied()
+                .find(|symbol| self.resolve(*symbol) == value)
+        }
+
+        /// Return the string of `symbol`.
+        pub fn resolve(&self, symbol: Symbol) -> &str {
+            let start = match symbol.index() {
+                0 => 0,
+                index => self.ends[index - 1],
+            };
+            &self.arena[start..self.ends[symbol.index()]]
+        }
+
+        /// Return the interned strings in the order of interning.
+        pub fn strings(&self) -> impl Iterator<Item = &str> {
+            std::iter::once(0)
+                .chain(self.ends.iter().copied())
+                .zip(&self.ends)
+                .map(|(start, end)| &self.arena[start..*end])
+        }
+
+        /// Return the number of interned strings.
+        pub fn len(&self) -> usize {
+            self.ends.len()
+        }
+
+        /// Return whether no strings are interned.
+        pub fn is_empty(&self) -> bool {
+            self.ends.is_empty()
+        }
+
+        /// Return the approximate number of bytes allocated by the interner.
+        pub fn memory_bytes(&self) -> usize {
+            self.arena.capacity()
+                + self.ends.capacity() * std::mem::size_of::<usize>()
+                + self.by_hash.capacity() * std::mem::size_of::<(u64, Vec<Symbol>)>()
+                + self
+                    .by_hash
+                    .values()
+                    .map(|symbols| symbols.capacity() * std::mem::size_of::<Symbol>())
+                    .sum::<usize>()
+        }
+    }
+
+    /// Map from strings to values, with the keys interned and the values stored by handle.
     ///
-    /// In the case that the file could not be read.
-    pub fn load_ncbi_to_hgnc<P: AsRef<std::path::Path>>(
-        path: P,
-    ) -> Result<HashMap<u32, String>, anyhow::Error> {
-        let mut map = HashMap::new();
-        for entry in load_entries(&path)? {
-            if let Some(ncbi_gene_id) = entry.ncgi_gene_id {
-                map.insert(ncbi_gene_id, entry.hgnc_id);
+    /// This replaces `HashMap<String, V>` for the tables keyed by gene symbol, so each key
+    /// is stored once in the arena and the values need no second hash map.
+    #[derive(Debug, Clone)]
+    pub struct SymbolMap<V> {
+        /// The interned keys.
+        keys: Interner,
+        /// The values, indexed by the handles of their keys.
+        values: Vec<V>,
+    }
+
+    impl<V> Default for SymbolMap<V> {
+        fn default() -> Self {
+            Self {
+                keys: Interner::default(),
+                values: Vec::new(),
             }
         }
-        Ok(map)
     }
 
-    /// Uility function to make the inverse of a `HashMap`.
-    pub fn inverse_hashmap<K, V, S>(map: &HashMap<K, V, S>) -> HashMap<V, K, S>
-    where
-        K: std::hash::Hash + Eq + Clone,
-        V: std::hash::Hash + Eq + Clone,
-        S: std::hash::BuildHasher + Default,
-    {
-        map.iter().map(|(k, v)| (v.clone(), k.clone())).collect()
+    impl<V> SymbolMap<V> {
+        /// Insert `value` for `key` unless `key` has a value already, i.e., the first value
+        /// is kept.
+        pub fn insert_first(&mut self, key: &str, value: V) {
+            let symbol = self.keys.intern(key);
+            if symbol.index() == self.values.len() {
+                self.values.push(value);
+            }
+        }
+
+        /// Return the value of `key`, the default value is inserted first if missing.
+        pub fn get_or_default_mut(&mut self, key: &str) -> &mut V
+        where
+            V: Default,
+        {
+            let symbol = self.keys.intern(key);
+            if symbol.index() == self.values.len() {
+                self.values.push(V::default());
+            }
+            &mut self.values[symbol.index()]
+        }
+
+        /// Return the value of `key`, if any.
+        pub fn get(&self, key: &str) -> Option<&V> {
+            self.values.get(self.keys.get(key)?.index())
+        }
+
+        /// Return the keys with their values in the order of insertion.
+        pub fn iter(&self) -> impl Iterator<Item = (&str, &V)> {
+            self.keys.strings().zip(&self.values)
+        }
+
+        /// Return the number of keys.
+        pub fn len(&self) -> usize {
+            self.values.len()
+        }
+
+        /// Return whether there are no keys.
+        pub fn is_empty(&self) -> bool {
+            self.values.is_empty()
+        }
+
+        /// Return the approximate number of bytes allocated for the keys and the values,
+        /// without the heap memory owned by the values.
+        pub fn memory_bytes(&self) -> usize {
+            self.keys.memory_bytes() + self.values.capacity() * std::mem::size_of::<V>()
+        }
+    }
+
+    #[cfg(test)]
+    mod test {
+        use super::{Interner, SymbolMap};
+
+        #[test]
+        fn intern() {
+            let mut interner = Interner::default();
+            let a = interner.intern("HGNC:5");
+            let b = interner.intern("");
+            let c = interner.intern("HGNC:37133");
+
+            assert_eq!(interner.intern("HGNC:5"), a);
+            assert_eq!(interner.len(), 3);
+            assert_eq!(interner.resolve(a), "HGNC:5");
+            assert_eq!(interner.resolve(b), "");
+            assert_eq!(interner.resolve(c), "HGNC:37133");
+            assert_eq!(interner.get("HGNC:37133"), Some(c));
+            assert_eq!(interner.get("HGNC:6"), None);
+            assert_eq!(
+                interner.strings().collect::<Vec<_>>(),
+                vec!["HGNC:5", "", "HGNC:37133"]
+            );
+            assert!(interner.memory_bytes() >= "HGNC:5HGNC:37133".len());
+        }
+
+        #[test]
+        fn symbol_map() {
+            let mut map = SymbolMap::default();
+            map.insert_first("FBN1", 1);
+            map.insert_first("TGDS", 2);
+            map.insert_first("FBN1", 3);
+            *map.get_or_default_mut("TGDS") += 10;
+            *map.get_or_default_mut("SCN1A") += 5;
+
+            assert_eq!(map.len(), 3);
+            assert_eq!(map.get("FBN1"), Some(&1));
+            assert_eq!(map.get("TGDS"), Some(&12));
+            assert_eq!(map.get("SCN1A"), Some(&5));
+            assert_eq!(map.get("fbn1"), None);
+            assert_eq!(
+                map.iter().collect::<Vec<_>>(),
+                vec![("FBN1", &1), ("TGDS", &12), ("SCN1A", &5)]
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn data_file() -> Result<(), anyhow::Error> {
+        let tmp_dir = temp_testdir::TempDir::default();
+        std::fs::write(tmp_dir.join("hp.obo"), "format-version: 1.2\n")?;
+
+        assert_eq!(
+            super::data_file(&*tmp_dir, "hp.obo", "HPO OBO")?,
+            tmp_dir.join("hp.obo")
+        );
+        assert!(matches!(
+            super::data_file(&*tmp_dir, "phenotype.hpoa", "disease annotation"),
+            Err(super::DataDirError::MissingFile {
+                what: "disease annotation",
+                ..
+            })
+        ));
+        assert!(matches!(
+            super::data_file(tmp_dir.join("missing"), "hp.obo", "HPO OBO"),
+            Err(super::DataDirError::MissingDir(_))
+        ));
+        assert!(matches!(
+            super::data_file(tmp_dir.join("hp.obo"), "hp.obo", "HPO OBO"),
+            Err(super::DataDirError::NotADir(_))
+        ));
+
+        let err = super::load_hpo(&*tmp_dir).expect_err("must fail");
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "missing gene annotation file {}",
+                tmp_dir.join("genes_to_phenotype.txt").display()
+            )
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn load_annotation_date() -> Result<(), anyhow::Error> {
+        let tmp_dir = temp_testdir::TempDir::default();
+        assert_eq!(super::load_annotation_date(&*tmp_dir), None);
+
+        std::fs::write(
+            tmp_dir.join("phenotype.hpoa"),
+            "#description: \"HPO annotations for rare diseases\"\n\
+             #version: 2023-06-06\n\
+             #tracker: https://github.com/obophenotype/human-phenotype-ontology/issues\n\
+             database_id\tdisease_name\n",
+        )?;
+
+        assert_eq!(
+            super::load_annotation_date(&*tmp_dir),
+            Some("2023-06-06".to_string())
+        );
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case("HP:0001250")]
+    #[case("HP_0001250")]
+    #[case("hp:0001250")]
+    #[case(" Hp_0001250 ")]
+    #[case("0001250")]
+    #[case("1250")]
+    fn parse_term_id(#[case] value: &str) -> Result<(), anyhow::Error> {
+        assert_eq!(super::parse_term_id(value)?.to_string(), "HP:0001250");
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case("")]
+    #[case("HP:")]
+    #[case("HP:12345678")]
+    #[case("HP:00012x0")]
+    #[case("MONDO:0001250")]
+    #[case("HP-0001250")]
+    fn parse_term_id_malformed(#[case] value: &str) {
+        assert!(super::parse_term_id(value).is_err());
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn parse_term_id_fuzz(value in crate::fuzz::term_id()) {
+            if let Ok(term_id) = super::parse_term_id(&value) {
+                // Accepted term IDs survive the round trip through the canonical form.
+                proptest::prop_assert_eq!(
+                    super::parse_term_id(&term_id.to_string()).ok(),
+                    Some(term_id),
+                    "{:?}",
+                    value
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn mapping_system() {
+        use super::MappingSystem;
+
+        assert_eq!(
+            MappingSystem::split_xref("SNOMEDCT_US:91175000"),
+            Some((MappingSystem::Snomed, "91175000"))
+        );
+        assert_eq!(
+            MappingSystem::split_xref("MSH:D012640"),
+            Some((MappingSystem::Mesh, "D012640"))
+        );
+        assert_eq!(MappingSystem::split_xref("Fyler:4197"), None);
+        assert_eq!(MappingSystem::split_xref("C0036572"), None);
+        assert_eq!(
+            MappingSystem::Umls.strip_prefix("umls:C0036572"),
+            "C0036572"
+        );
+        assert_eq!(
+            MappingSystem::Umls.strip_prefix("MSH:D012640"),
+            "MSH:D012640"
+        );
+    }
+
+    #[test]
+    fn normalization_constants() {
+        let range = crate::algos::ic::IcRange { min: 1.0, max: 5.0 };
+        let resnik = super::SimilarityMethod::Resnik;
+
+        assert!(
+            super::NormalizationConstants::new(super::ScoreNormalization::None, resnik, range)
+                .is_none()
+        );
+        let max_ic =
+            super::NormalizationConstants::new(super::ScoreNormalization::MaxIc, resnik, range)
+                .expect("must be some");
+        assert!((max_ic.apply(2.5) - 0.5).abs() < 1e-6);
+        let minmax =
+            super::NormalizationConstants::new(super::ScoreNormalization::MinMax, resnik, range)
+                .expect("must be some");
+        assert!((minmax.apply(3.0) - 0.5).abs() < 1e-6);
+        assert!(minmax.apply(0.0).abs() < 1e-6);
+        let lin = super::NormalizationConstants::new(
+            super::ScoreNormalization::MinMax,
+            super::SimilarityMethod::Lin,
+            range,
+        )
+        .expect("must be some");
+        assert!((lin.apply(0.25) - 0.25).abs() < 1e-6);
     }
 }
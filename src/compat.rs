@@ -0,0 +1,242 @@
+//! Compatibility with the command line of `varfish-server-worker`.
+//!
+//! Viguno started out as the `pheno` and `server pheno` commands of
+//! `varfish-server-worker`.  Its legacy flag names, sub command names, and environment
+//! variables are rewritten to the current ones before parsing, each with a deprecation
+//! warning.
+
+use std::ffi::OsString;
+
+/// Legacy flags and their replacements.
+const LEGACY_FLAGS: &[(&str, &str)] = &[
+    ("--path-hpo", "--path-hpo-dir"),
+    ("--host", "--listen-host"),
+    ("--port", "--listen-port"),
+    ("--no-hints", "--suppress-hints"),
+];
+
+/// Legacy sub commands and their replacements.
+const LEGACY_COMMANDS: &[(&[&str], &[&str])] = &[
+    (&["server", "pheno"], &["server", "run"]),
+    (&["pheno", "query"], &["query"]),
+];
+
+/// Legacy environment variables, their replacements, and the `server run` flags they set.
+const LEGACY_ENV_VARS: &[(&str, &str, &str)] = &[
+    (
+        "VARFISH_SERVER_WORKER_PATH_HPO_DIR",
+        "SEAHORSE_PATH_HPO_DIR",
+        "--path-hpo-dir",
+    ),
+    (
+        "VARFISH_SERVER_WORKER_LISTEN_HOST",
+        "SEAHORSE_LISTEN_HOST",
+        "--listen-host",
+    ),
+    (
+        "VARFISH_SERVER_WORKER_LISTEN_PORT",
+        "SEAHORSE_LISTEN_PORT",
+        "--listen-port",
+    ),
+];
+
+/// The command line after rewriting legacy names.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rewritten {
+    /// The command line arguments, including the program name.
+    pub args: Vec<OsString>,
+    /// The deprecation warnings to log once logging is set up.
+    pub warnings: Vec<String>,
+}
+
+/// Rewrite the legacy names in `args`, including the program name.
+///
+/// The environment variables are looked up with `env` and only apply to `server run`
+/// if neither the corresponding flag nor the current environment variable is given.
+/// Arguments after `--` are left as they are.
+pub fn rewrite<I, F>(args: I, env: F) -> Rewritten
+where
+    I: IntoIterator<Item = OsString>,
+    F: Fn(&str) -> Option<String>,
+{
+    let mut args = args.into_iter().collect::<Vec<_>>();
+    let mut warnings = Vec::new();
+
+    // Rewrite the sub command following the program name.
+    let command = args
+        .iter()
+        .skip(1)
+        .map(|arg| arg.to_str().unwrap_or_default())
+        .collect::<Vec<_>>();
+    if let Some((legacy, current)) = LEGACY_COMMANDS
+        .iter()
+        .find(|(legacy, _)| command.starts_with(legacy))
+    {
+        warnings.push(format!(
+            "sub command `{}` is deprecated, use `{}` instead",
+            legacy.join(" "),
+            current.join(" ")
+        ));
+        args.splice(
+            1..=legacy.len(),
+            current.iter().map(|arg| OsString::from(*arg)),
+        );
+    }
+
+    // Rewrite the flags, as `--flag value` or `--flag=value`.
+    for arg in args.iter_mut().skip(1) {
+        let Some(value) = arg.to_str() else {
+            continue;
+        };
+        if value == "--" {
+            break;
+        }
+        let (flag, rest) = value
+            .split_once('=')
+            .map_or((value, None), |(flag, rest)| (flag, Some(rest)));
+        if let Some((legacy, current)) = LEGACY_FLAGS.iter().find(|(legacy, _)| *legacy == flag) {
+            warnings.push(format!(
+                "flag `{legacy}` is deprecated, use `{current}` instead"
+            ));
+            *arg = match rest {
+                Some(rest) => OsString::from(format!("{current}={rest}")),
+                None => OsString::from(*current),
+            };
+        }
+    }
+
+    // Set the flags of `server run` from the environment variables.
+    let is_server_run = args.get(1).is_some_and(|arg| arg == "server")
+        && args.get(2).is_some_and(|arg| arg == "run");
+    if is_server_run {
+        for (name, current, flag) in LEGACY_ENV_VARS {
+            let is_given = args.iter().any(|arg| {
+                arg.to_str().is_some_and(|arg| {
+                    arg == *flag || arg.strip_prefix(*flag).is_some_and(|s| s.starts_with('='))
+                })
+            });
+            if let Some(value) = env(name).filter(|_| !is_given && env(current).is_none()) {
+                warnings.push(format!(
+                    "environment variable `{name}` is deprecated, use `{current}` instead"
+                ));
+                args.push(OsString::from(format!("{flag}={value}")));
+            }
+        }
+    }
+
+    Rewritten { args, warnings }
+}
+
+#[cfg(test)]
+mod test {
+    use std::ffi::OsString;
+
+    use super::rewrite;
+
+    /// Convert `args` into `OsString`s.
+    fn os_args(args: &[&str]) -> Vec<OsString> {
+        args.iter().map(OsString::from).collect()
+    }
+
+    #[test]
+    fn rewrite_current() {
+        let args = os_args(&["viguno", "server", "run", "--path-hpo-dir", "hpo"]);
+
+        let rewritten = rewrite(args.clone(), |_| None);
+
+        assert_eq!(rewritten.args, args);
+        assert!(rewritten.warnings.is_empty());
+    }
+
+    #[test]
+    fn rewrite_legacy_flags() {
+        let rewritten = rewrite(
+            os_args(&[
+                "viguno",
+                "server",
+                "pheno",
+                "--path-hpo",
+                "hpo",
+                "--port=8081",
+                "--no-hints",
+                "--",
+                "--host",
+            ]),
+            |_| None,
+        );
+
+        assert_eq!(
+            rewritten.args,
+            os_args(&[
+                "viguno",
+                "server",
+                "run",
+                "--path-hpo-dir",
+                "hpo",
+                "--listen-port=8081",
+                "--suppress-hints",
+                "--",
+                "--host",
+            ])
+        );
+        assert_eq!(rewritten.warnings.len(), 4);
+        assert_eq!(
+            rewritten.warnings[0],
+            "sub command `server pheno` is deprecated, use `server run` instead"
+        );
+    }
+
+    #[test]
+    fn rewrite_legacy_commands() {
+        let rewritten = rewrite(os_args(&["viguno", "pheno", "query", "-v"]), |_| None);
+
+        assert_eq!(rewritten.args, os_args(&["viguno", "query", "-v"]));
+    }
+
+    #[test]
+    fn rewrite_legacy_env_vars() {
+        let env = |name: &str| match name {
+            "VARFISH_SERVER_WORKER_PATH_HPO_DIR" => Some("hpo".to_string()),
+            "VARFISH_SERVER_WORKER_LISTEN_PORT" => Some("8081".to_string()),
+            _ => None,
+        };
+
+        let rewritten = rewrite(os_args(&["viguno", "server", "run"]), env);
+        assert_eq!(
+            rewritten.args,
+            os_args(&[
+                "viguno",
+                "server",
+                "run",
+                "--path-hpo-dir=hpo",
+                "--listen-port=8081"
+            ])
+        );
+        assert_eq!(rewritten.warnings.len(), 2);
+
+        // Flags take precedence and other sub commands are not affected.
+        let rewritten = rewrite(
+            os_args(&[
+                "viguno",
+                "server",
+                "run",
+                "--listen-port=8082",
+                "--path-hpo-dir",
+                "x",
+            ]),
+            env,
+        );
+        assert!(rewritten.warnings.is_empty());
+        let rewritten = rewrite(os_args(&["viguno", "server", "schema"]), env);
+        assert_eq!(rewritten.args.len(), 3);
+
+        // The current environment variables take precedence.
+        let rewritten = rewrite(os_args(&["viguno", "server", "run"]), |name| {
+            env(name).or_else(|| (name == "SEAHORSE_LISTEN_PORT").then(|| "8082".to_string()))
+        });
+        assert_eq!(
+            rewritten.args,
+            os_args(&["viguno", "server", "run", "--path-hpo-dir=hpo"])
+        );
+    }
+}
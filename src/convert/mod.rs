@@ -10,10 +10,10 @@ use clap::Parser;
 pub struct Args {
     /// Path to the directory with the HPO files.
     #[arg(long, required = true)]
-    pub path_hpo_dir: String,
+    pub path_hpo_dir: std::path::PathBuf,
     /// Path to the output binary file.
     #[arg(long, required = true)]
-    pub path_out_bin: String,
+    pub path_out_bin: std::path::PathBuf,
 }
 
 /// Main entry point for `convert` command.
@@ -34,16 +34,21 @@ pub fn run(args_common: &crate::common::Args, args: &Args) -> Result<(), anyhow:
         env_logger::init_from_env(env_logger::Env::new().default_filter_or("info"));
     }
 
-    tracing::info!("Loading HPO...");
-    let before_loading = std::time::Instant::now();
+    let progress = crate::progress::Progress::new(crate::progress::Mode::cli());
+    let stage = progress.stage("HPO");
     let hpo = crate::common::load_hpo(&args.path_hpo_dir)?;
-    tracing::info!("...done loading HPO in {:?}", before_loading.elapsed());
+    stage.finish();
     tracing::info!("Ontology [{}] with {} terms", hpo.hpo_version(), hpo.len());
 
     tracing::info!("Writing binary file...");
     let before_writing = std::time::Instant::now();
-    let filename = &args.path_out_bin;
-    let mut fh = std::fs::File::create(filename).expect("Cannot create file");
+    let mut fh = std::fs::File::create(&args.path_out_bin).map_err(|e| {
+        anyhow::anyhow!(
+            "cannot create output file {}: {}",
+            args.path_out_bin.display(),
+            e
+        )
+    })?;
     fh.write_all(&hpo.as_bytes())?;
     tracing::info!("...done writing binary in {:?}", before_writing.elapsed());
 
@@ -0,0 +1,316 @@
+//! Self-describing header of the binary artifacts, e.g., the score distributions from
+//! `precompute sim-pvalues`.
+//!
+//! Each artifact starts with the magic bytes, the kind of the artifact, the version of its
+//! schema, and the HPO release it was computed for, followed by the payload.  Artifacts
+//! of an older schema are refused on loading and can be forward-ported with
+//! `data migrate` where feasible.
+
+use std::path::Path;
+
+/// The magic bytes at the start of every artifact.
+pub const MAGIC: &[u8; 8] = b"SEAHORSE";
+
+/// The kind of an artifact.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, derive_more::Display)]
+pub enum ArtifactKind {
+    /// The simulated score distributions for the p-values.
+    #[display("sim-pvalues")]
+    SimPvalues,
+}
+
+impl ArtifactKind {
+    /// All kinds of artifacts.
+    const ALL: &'static [Self] = &[Self::SimPvalues];
+
+    /// Return the current version of the schema of the payload.
+    ///
+    /// Version 0 are the artifacts without header from before the header was introduced.
+    pub fn schema_version(self) -> u32 {
+        match self {
+            Self::SimPvalues => 1,
+        }
+    }
+
+    /// Return the command that computes the artifact.
+    fn command(self) -> &'static str {
+        match self {
+            Self::SimPvalues => "precompute sim-pvalues",
+        }
+    }
+
+    /// Parse the kind from its name in the header.
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|kind| kind.to_string() == name)
+    }
+}
+
+/// The header of an artifact.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArtifactHeader {
+    /// The kind of the artifact.
+    pub kind: ArtifactKind,
+    /// The version of the schema of the payload.
+    pub schema_version: u32,
+    /// The HPO release the artifact was computed for.
+    pub hpo_release: String,
+}
+
+/// Append the length-prefixed `value` to `buf`.
+fn write_str(buf: &mut Vec<u8>, value: &str) {
+    let len = u16::try_from(value.len()).unwrap_or(u16::MAX);
+    buf.extend(len.to_le_bytes());
+    buf.extend(&value.as_bytes()[..usize::from(len)]);
+}
+
+/// Split the first `len` bytes off `bytes`.
+fn take<'a>(bytes: &mut &'a [u8], len: usize) -> Result<&'a [u8], anyhow::Error> {
+    if bytes.len() < len {
+        anyhow::bail!("artifact header is truncated");
+    }
+    let (head, tail) = bytes.split_at(len);
+    *bytes = tail;
+    Ok(head)
+}
+
+/// Split the length-prefixed string written by `write_str` off `bytes`.
+fn read_str(bytes: &mut &[u8]) -> Result<String, anyhow::Error> {
+    let len = u16::from_le_bytes(take(bytes, 2)?.try_into()?);
+    Ok(std::str::from_utf8(take(bytes, usize::from(len))?)?.to_string())
+}
+
+impl ArtifactHeader {
+    /// Construct the header of the current schema of `kind` for `hpo_release`.
+    pub fn new(kind: ArtifactKind, hpo_release: &str) -> Self {
+        Self {
+            kind,
+            schema_version: kind.schema_version(),
+            hpo_release: hpo_release.to_string(),
+        }
+    }
+
+    /// Encode the header followed by `payload`.
+    pub fn encode(&self, payload: &[u8]) -> Vec<u8> {
+        let mut buf = MAGIC.to_vec();
+        write_str(&mut buf, &self.kind.to_string());
+        buf.extend(self.schema_version.to_le_bytes());
+        write_str(&mut buf, &self.hpo_release);
+        buf.extend(payload);
+        buf
+    }
+
+    /// Decode the header of `bytes` and return it together with the payload, `None` if
+    /// `bytes` does not start with the magic bytes.
+    ///
+    /// # Errors
+    ///
+    /// In the case that the header is malformed or of an unknown kind.
+    pub fn decode(bytes: &[u8]) -> Result<Option<(Self, &[u8])>, anyhow::Error> {
+        let Some(mut bytes) = bytes.strip_prefix(MAGIC.as_slice()) else {
+            return Ok(None);
+        };
+        let name = read_str(&mut bytes)?;
+        let kind = ArtifactKind::from_name(&name)
+            .ok_or_else(|| anyhow::anyhow!("unknown kind of artifact: {}", name))?;
+        let schema_version = u32::from_le_bytes(take(&mut bytes, 4)?.try_into()?);
+        let hpo_release = read_str(&mut bytes)?;
+        Ok(Some((
+            Self {
+                kind,
+                schema_version,
+                hpo_release,
+            },
+            bytes,
+        )))
+    }
+}
+
+/// Check the header of the artifact `bytes` of `kind` and return its payload.
+///
+/// # Errors
+///
+/// In the case that the artifact is of another kind, of another schema version than the
+/// current one, or computed for another release than `hpo_release`.  The errors explain
+/// how to migrate or recompute the artifact.
+pub fn open<'a>(
+    bytes: &'a [u8],
+    kind: ArtifactKind,
+    hpo_release: &str,
+) -> Result<&'a [u8], anyhow::Error> {
+    let Some((header, payload)) = ArtifactHeader::decode(bytes)? else {
+        anyhow::bail!(
+            "the {} artifact has no header, it was written by an older version; \
+             convert it with `data migrate`",
+            kind
+        );
+    };
+    if header.kind != kind {
+        anyhow::bail!("expected a {} artifact but found {}", kind, header.kind);
+    }
+    if header.schema_version < kind.schema_version() {
+        anyhow::bail!(
+            "the {} artifact has schema version {} but version {} is required; convert it \
+             with `data migrate`",
+            kind,
+            header.schema_version,
+            kind.schema_version()
+        );
+    }
+    if header.schema_version > kind.schema_version() {
+        anyhow::bail!(
+            "the {} artifact has schema version {} but this version only reads up to {}; \
+             upgrade or recompute it with `{}`",
+            kind,
+            header.schema_version,
+            kind.schema_version(),
+            kind.command()
+        );
+    }
+    if header.hpo_release != hpo_release {
+        anyhow::bail!(
+            "the {} artifact was computed for HPO release {} but {} is loaded; recompute \
+             it with `{}`",
+            kind,
+            header.hpo_release,
+            hpo_release,
+            kind.command()
+        );
+    }
+    Ok(payload)
+}
+
+/// Forward-port the artifact `bytes` to the current schema.
+///
+/// Returns the header of the migrated artifact and its bytes, `None` if the artifact
+/// already has the current schema.
+///
+/// # Errors
+///
+/// In the case that the artifact is malformed or cannot be migrated, e.g., because it
+/// was written by a newer version.
+pub fn migrate(bytes: &[u8]) -> Result<Option<(ArtifactHeader, Vec<u8>)>, anyhow::Error> {
+    let Some((header, _)) = ArtifactHeader::decode(bytes)? else {
+        // Without header, only the score distributions were written and their payload
+        // is unchanged in schema version 1.
+        let hpo_release = crate::algos::simulation::artifact_release(bytes).map_err(|e| {
+            anyhow::anyhow!(
+                "not an artifact and not convertible score distributions: {}",
+                e
+            )
+        })?;
+        let header = ArtifactHeader::new(ArtifactKind::SimPvalues, &hpo_release);
+        let bytes = header.encode(bytes);
+        return Ok(Some((header, bytes)));
+    };
+    if header.schema_version > header.kind.schema_version() {
+        anyhow::bail!(
+            "the {} artifact has schema version {} but this version only reads up to {}",
+            header.kind,
+            header.schema_version,
+            header.kind.schema_version()
+        );
+    }
+    if header.schema_version == header.kind.schema_version() {
+        return Ok(None);
+    }
+    anyhow::bail!(
+        "migration of {} artifacts from schema version {} is not supported; recompute it \
+         with `{}`",
+        header.kind,
+        header.schema_version,
+        header.kind.command()
+    )
+}
+
+/// Command line arguments for `data migrate` sub command.
+#[derive(clap::Parser, Debug)]
+#[command(author, version, about = "Convert a binary artifact to the current schema", long_about = None)]
+pub struct Args {
+    /// Path to the artifact to convert, e.g., from `precompute sim-pvalues`.
+    #[arg(long, required = true)]
+    pub path_artifact: std::path::PathBuf,
+    /// Path to the output file, may be the same as the input.
+    #[arg(long, required = true)]
+    pub out: std::path::PathBuf,
+}
+
+/// Forward-port the artifact at `path` to `out`, copying it if it is current.
+///
+/// # Errors
+///
+/// In the case that the artifact cannot be read, migrated, or written.
+fn migrate_file(path: &Path, out: &Path) -> Result<(), anyhow::Error> {
+    let bytes =
+        std::fs::read(path).map_err(|e| anyhow::anyhow!("could not read {:?}: {}", path, e))?;
+    let bytes = if let Some((header, migrated)) = migrate(&bytes)? {
+        tracing::info!(
+            "Migrated {} artifact for HPO release {} to schema version {}",
+            header.kind,
+            header.hpo_release,
+            header.schema_version
+        );
+        migrated
+    } else {
+        tracing::info!("Artifact already has the current schema");
+        bytes
+    };
+    std::fs::write(out, bytes).map_err(|e| anyhow::anyhow!("could not write {:?}: {}", out, e))
+}
+
+/// Main entry point for `data migrate` sub command.
+///
+/// # Errors
+///
+/// In the case that the artifact cannot be read, migrated, or written.
+pub fn run(args_common: &crate::common::Args, args: &Args) -> Result<(), anyhow::Error> {
+    tracing::info!("args_common = {:?}", &args_common);
+    tracing::info!("args = {:?}", &args);
+
+    migrate_file(&args.path_artifact, &args.out)?;
+    tracing::info!("Wrote artifact to {:?}", args.out);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ArtifactHeader, ArtifactKind};
+
+    #[test]
+    fn open() -> Result<(), anyhow::Error> {
+        let bytes = ArtifactHeader::new(ArtifactKind::SimPvalues, "2023-06-06").encode(b"data");
+        assert_eq!(
+            super::open(&bytes, ArtifactKind::SimPvalues, "2023-06-06")?,
+            b"data"
+        );
+
+        let err = super::open(&bytes, ArtifactKind::SimPvalues, "2024-01-16")
+            .expect_err("release must match");
+        assert!(err.to_string().contains("precompute sim-pvalues"));
+        let err = super::open(b"data", ArtifactKind::SimPvalues, "2023-06-06")
+            .expect_err("header must be present");
+        assert!(err.to_string().contains("data migrate"));
+
+        let newer = ArtifactHeader {
+            schema_version: 2,
+            ..ArtifactHeader::new(ArtifactKind::SimPvalues, "2023-06-06")
+        }
+        .encode(b"data");
+        assert!(super::open(&newer, ArtifactKind::SimPvalues, "2023-06-06").is_err());
+        assert!(super::migrate(&newer).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn migrate() -> Result<(), anyhow::Error> {
+        let current = ArtifactHeader::new(ArtifactKind::SimPvalues, "2023-06-06").encode(b"data");
+        assert!(super::migrate(&current)?.is_none());
+        assert!(super::migrate(b"\xff\xff\xff").is_err());
+
+        Ok(())
+    }
+}
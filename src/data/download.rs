@@ -0,0 +1,273 @@
+//! Download and assembly of the HPO data directory.
+//!
+//! The files are fetched with `curl`, which must be on the `PATH`.
+
+use std::io::{BufRead, Write};
+
+/// Default base URL of the HPO releases.
+pub const DEFAULT_URL_HPO: &str =
+    "https://github.com/obophenotype/human-phenotype-ontology/releases/download";
+/// Default URL of the HGNC complete set in JSON format.
+pub const DEFAULT_URL_HGNC: &str =
+    "https://ftp.ebi.ac.uk/pub/databases/genenames/hgnc/json/hgnc_complete_set.json";
+
+/// The HPO release files that are downloaded as they are.
+const HPO_FILES: &[&str] = &[
+    "phenotype.hpoa",
+    "phenotype_to_genes.txt",
+    "genes_to_phenotype.txt",
+];
+
+/// Command line arguments for `data download` sub command.
+#[derive(clap::Parser, Debug)]
+#[command(author, version, about = "Download and assemble the HPO data directory", long_about = None)]
+pub struct Args {
+    /// The HPO release as tagged on GitHub without the leading `v`, e.g., `2024-03-06`.
+    #[arg(long, required = true)]
+    pub release: String,
+    /// Path to the output directory, created if missing.
+    #[arg(long, required = true)]
+    pub out: std::path::PathBuf,
+    /// Base URL of the HPO releases, e.g., of a mirror.
+    #[arg(long, default_value = DEFAULT_URL_HPO)]
+    pub url_hpo: String,
+    /// URL of the HGNC complete set in JSON format.
+    #[arg(long, default_value = DEFAULT_URL_HGNC)]
+    pub url_hgnc: String,
+}
+
+/// Download `url` to `path` with `curl`.
+///
+/// The file is written to `<path>.part` first and only renamed on success.
+///
+/// # Errors
+///
+/// In the case that `curl` could not be run or the download failed.
+pub fn fetch<P: AsRef<std::path::Path>>(url: &str, path: P) -> Result<(), anyhow::Error> {
+    let path = path.as_ref();
+    let path_part = path.with_extension("part");
+    tracing::info!("  fetching {}", url);
+    let output = std::process::Command::new("curl")
+        .args([
+            "--fail",
+            "--location",
+            "--silent",
+            "--show-error",
+            "--output",
+        ])
+        .arg(&path_part)
+        .arg(url)
+        .output()
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => {
+                anyhow::anyhow!("could not run curl, please install it and put it on the PATH")
+            }
+            _ => anyhow::anyhow!("could not run curl: {}", e),
+        })?;
+    if !output.status.success() {
+        let _ = std::fs::remove_file(&path_part);
+        anyhow::bail!(
+            "could not download {}: {}",
+            url,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    std::fs::rename(&path_part, path)?;
+    Ok(())
+}
+
+/// Copy the OBO file `hp-base.obo` from `reader` to `writer` as `hp.obo`.
+///
+/// This removes the `/hp-base.owl` suffix from the ontology IRI so that the version is
+/// parsed properly.
+///
+/// # Errors
+///
+/// In the case that reading or writing failed.
+pub fn write_hp_obo<R: BufRead, W: Write>(reader: R, mut writer: W) -> Result<(), anyhow::Error> {
+    for line in reader.lines() {
+        writeln!(writer, "{}", line?.replace("/hp-base.owl", ""))?;
+    }
+    Ok(())
+}
+
+/// The relevant part of the HGNC complete set.
+#[derive(Debug, serde::Deserialize)]
+struct HgncCompleteSet {
+    /// The response of the query.
+    response: HgncResponse,
+}
+
+/// The response of the HGNC complete set.
+#[derive(Debug, serde::Deserialize)]
+struct HgncResponse {
+    /// The genes.
+    docs: Vec<HgncDoc>,
+}
+
+/// A gene of the HGNC complete set.
+#[derive(Debug, serde::Deserialize)]
+struct HgncDoc {
+    /// The HGNC ID, e.g., `HGNC:5`.
+    hgnc_id: String,
+    /// The Ensembl gene ID, if any.
+    ensembl_gene_id: Option<String>,
+    /// The NCBI gene ID, if any.
+    entrez_id: Option<String>,
+    /// The gene symbol.
+    symbol: String,
+}
+
+/// Derive `hgnc_xlink.tsv` from the HGNC complete set JSON in `reader`.
+///
+/// Genes without NCBI gene ID are skipped, the rows are sorted by NCBI gene ID.
+///
+/// # Errors
+///
+/// In the case that the JSON is malformed or writing failed.
+pub fn write_hgnc_xlink<R: std::io::Read, W: Write>(
+    reader: R,
+    mut writer: W,
+) -> Result<usize, anyhow::Error> {
+    let complete_set: HgncCompleteSet = serde_json::from_reader(reader)
+        .map_err(|e| anyhow::anyhow!("invalid HGNC complete set: {}", e))?;
+    let mut docs = complete_set
+        .response
+        .docs
+        .into_iter()
+        .filter_map(|doc| {
+            let entrez_id = doc.entrez_id.as_deref()?.parse::<u32>().ok()?;
+            Some((entrez_id, doc))
+        })
+        .collect::<Vec<_>>();
+    docs.sort_by_key(|(entrez_id, _)| *entrez_id);
+
+    writeln!(writer, "hgnc_id\tensembl_gene_id\tentrez_id\tgene_symbol")?;
+    for (entrez_id, doc) in &docs {
+        writeln!(
+            writer,
+            "{}\t{}\t{}\t{}",
+            doc.hgnc_id,
+            doc.ensembl_gene_id.as_deref().unwrap_or_default(),
+            entrez_id,
+            doc.symbol
+        )?;
+    }
+    Ok(docs.len())
+}
+
+/// Main entry point for `data download` sub command.
+///
+/// # Errors
+///
+/// In the case that a download or conversion failed.
+pub fn run(args_common: &crate::common::Args, args: &Args) -> Result<(), anyhow::Error> {
+    tracing::info!("args_common = {:?}", &args_common);
+    tracing::info!("args = {:?}", &args);
+
+    let out = args.out.as_path();
+    std::fs::create_dir_all(out)?;
+    let tmp_dir = tempdir::TempDir::new_in(out, "download")?;
+    let url_release = format!(
+        "{}/v{}",
+        args.url_hpo.trim_end_matches('/'),
+        args.release.trim_start_matches('v')
+    );
+
+    tracing::info!("Downloading HPO release {}...", &args.release);
+    let before_download = std::time::Instant::now();
+    fetch(
+        &format!("{url_release}/hp-base.obo"),
+        tmp_dir.path().join("hp-base.obo"),
+    )?;
+    write_hp_obo(
+        std::io::BufReader::new(std::fs::File::open(tmp_dir.path().join("hp-base.obo"))?),
+        std::io::BufWriter::new(std::fs::File::create(out.join("hp.obo"))?),
+    )?;
+    for name in HPO_FILES {
+        fetch(&format!("{url_release}/{name}"), out.join(name))?;
+    }
+    tracing::info!("... done downloading in {:?}", before_download.elapsed());
+
+    tracing::info!("Deriving hgnc_xlink.tsv from HGNC...");
+    let before_hgnc = std::time::Instant::now();
+    fetch(
+        &args.url_hgnc,
+        tmp_dir.path().join("hgnc_complete_set.json"),
+    )?;
+    let count = write_hgnc_xlink(
+        std::io::BufReader::new(std::fs::File::open(
+            tmp_dir.path().join("hgnc_complete_set.json"),
+        )?),
+        std::io::BufWriter::new(std::fs::File::create(out.join("hgnc_xlink.tsv"))?),
+    )?;
+    tracing::info!(
+        "... done writing {} genes in {:?}",
+        count,
+        before_hgnc.elapsed()
+    );
+
+    let manifest = super::manifest::Manifest::compute(out)?;
+    manifest.save(out)?;
+    tracing::info!(
+        "Wrote {} checksums to {}",
+        manifest.checksums.len(),
+        out.join(super::manifest::MANIFEST_FILE).display()
+    );
+
+    tracing::info!("All done. Have a nice day!");
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn write_hp_obo() -> Result<(), anyhow::Error> {
+        let mut buf = Vec::new();
+        super::write_hp_obo(
+            "format-version: 1.2\n\
+             data-version: hp/releases/2024-03-06/hp-base.owl\n\
+             ontology: hp/hp-base\n"
+                .as_bytes(),
+            &mut buf,
+        )?;
+
+        assert_eq!(
+            String::from_utf8(buf)?,
+            "format-version: 1.2\n\
+             data-version: hp/releases/2024-03-06\n\
+             ontology: hp/hp-base\n"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn write_hgnc_xlink() -> Result<(), anyhow::Error> {
+        let json = serde_json::json!({
+            "responseHeader": {"status": 0},
+            "response": {"numFound": 3, "docs": [
+                {"hgnc_id": "HGNC:24086", "ensembl_gene_id": "ENSG00000148584",
+                 "entrez_id": "144568", "symbol": "A1CF"},
+                {"hgnc_id": "HGNC:5", "ensembl_gene_id": "ENSG00000121410",
+                 "entrez_id": "1", "symbol": "A1BG"},
+                {"hgnc_id": "HGNC:37133", "symbol": "A1BG-AS1"},
+                {"hgnc_id": "HGNC:7", "entrez_id": "2", "symbol": "A2M"}
+            ]}
+        });
+        let mut buf = Vec::new();
+
+        let count = super::write_hgnc_xlink(json.to_string().as_bytes(), &mut buf)?;
+
+        assert_eq!(count, 3);
+        assert_eq!(
+            String::from_utf8(buf)?,
+            "hgnc_id\tensembl_gene_id\tentrez_id\tgene_symbol\n\
+             HGNC:5\tENSG00000121410\t1\tA1BG\n\
+             HGNC:7\t\t2\tA2M\n\
+             HGNC:24086\tENSG00000148584\t144568\tA1CF\n"
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,317 @@
+//! Manifest with the SHA256 checksums of the files in the HPO data directory.
+//!
+//! The manifest is written to `MANIFEST.sha256` in the format of `sha256sum`, so it can
+//! also be checked with `sha256sum -c MANIFEST.sha256`.
+
+use std::{collections::BTreeMap, fmt::Write as _, path::Path};
+
+use sha2::Digest as _;
+
+/// Name of the manifest file in the data directory.
+pub const MANIFEST_FILE: &str = "MANIFEST.sha256";
+
+/// Files that must be in the data directory.
+pub const EXPECTED_FILES: &[&str] = &[
+    "hp.obo",
+    "phenotype.hpoa",
+    "phenotype_to_genes.txt",
+    "genes_to_phenotype.txt",
+    "hgnc_xlink.tsv",
+];
+
+/// Files that are covered by the manifest if they are in the data directory.
+pub const OPTIONAL_FILES: &[&str] = &["hpo.bin"];
+
+/// Command line arguments for `data manifest` sub command.
+#[derive(clap::Parser, Debug)]
+#[command(author, version, about = "Write the checksum manifest of the HPO data directory", long_about = None)]
+pub struct Args {
+    /// Path to the directory with the HPO files.
+    #[arg(long, required = true)]
+    pub path_hpo_dir: std::path::PathBuf,
+}
+
+/// A problem found when verifying the data directory against the manifest.
+#[derive(Debug, Clone, PartialEq, Eq, derive_more::Display)]
+pub enum Problem {
+    /// A file of the manifest is missing.
+    #[display("{file}: missing")]
+    Missing {
+        /// The file name.
+        file: String,
+    },
+    /// A file of the manifest could not be read.
+    #[display("{file}: could not be read: {error}")]
+    Unreadable {
+        /// The file name.
+        file: String,
+        /// The error message.
+        error: String,
+    },
+    /// The checksum of a file differs from the manifest.
+    #[display("{file}: checksum mismatch, expected {expected} but found {actual}")]
+    Corrupted {
+        /// The file name.
+        file: String,
+        /// The checksum in the manifest.
+        expected: String,
+        /// The checksum of the file.
+        actual: String,
+    },
+    /// A data file is not covered by the manifest.
+    #[display("{file}: not listed in {MANIFEST_FILE}")]
+    Unlisted {
+        /// The file name.
+        file: String,
+    },
+}
+
+/// Compute the hex-encoded SHA256 checksum of the file at `path`.
+///
+/// # Errors
+///
+/// In the case that the file could not be read.
+pub fn sha256_file<P: AsRef<Path>>(path: P) -> Result<String, anyhow::Error> {
+    let mut file = std::fs::File::open(path.as_ref())?;
+    let mut hasher = sha2::Sha256::new();
+    std::io::copy(&mut file, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// The checksums of the files in the data directory.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Manifest {
+    /// The hex-encoded SHA256 checksums by file name.
+    pub checksums: BTreeMap<String, String>,
+}
+
+impl Manifest {
+    /// Compute the manifest of the data directory at `path`.
+    ///
+    /// # Errors
+    ///
+    /// In the case that an expected file is missing or a file could not be read.
+    pub fn compute<P: AsRef<Path>>(path: P) -> Result<Self, anyhow::Error> {
+        let path = path.as_ref();
+        let missing = EXPECTED_FILES
+            .iter()
+            .filter(|file| !path.join(file).exists())
+            .copied()
+            .collect::<Vec<_>>();
+        if !missing.is_empty() {
+            anyhow::bail!(
+                "missing files in data directory {}: {}",
+                path.display(),
+                missing.join(", ")
+            );
+        }
+
+        let mut checksums = BTreeMap::new();
+        for file in EXPECTED_FILES.iter().chain(
+            OPTIONAL_FILES
+                .iter()
+                .filter(|file| path.join(file).exists()),
+        ) {
+            let checksum = sha256_file(path.join(file))
+                .map_err(|e| anyhow::anyhow!("could not read {}: {}", file, e))?;
+            checksums.insert((*file).to_string(), checksum);
+        }
+        Ok(Self { checksums })
+    }
+
+    /// Parse the manifest from the lines `<checksum>  <file name>` of `reader`.
+    ///
+    /// # Errors
+    ///
+    /// In the case that a line is malformed.
+    pub fn from_reader<R: std::io::BufRead>(reader: R) -> Result<Self, anyhow::Error> {
+        let mut checksums = BTreeMap::new();
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let (checksum, file) = line
+                .split_once(' ')
+                .map(|(checksum, file)| (checksum, file.trim_start_matches([' ', '*'])))
+                .filter(|(checksum, file)| {
+                    checksum.len() == 64
+                        && checksum.bytes().all(|c| c.is_ascii_hexdigit())
+                        && !file.is_empty()
+                })
+                .ok_or_else(|| anyhow::anyhow!("malformed manifest line {}: {}", i + 1, line))?;
+            checksums.insert(file.to_string(), checksum.to_lowercase());
+        }
+        Ok(Self { checksums })
+    }
+
+    /// Read the manifest of the data directory at `path`.
+    ///
+    /// # Errors
+    ///
+    /// In the case that the manifest could not be read or is malformed.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, anyhow::Error> {
+        let path = path.as_ref().join(MANIFEST_FILE);
+        let file = std::fs::File::open(&path)
+            .map_err(|e| anyhow::anyhow!("could not open {}: {}", path.display(), e))?;
+        Self::from_reader(std::io::BufReader::new(file))
+            .map_err(|e| anyhow::anyhow!("invalid {}: {}", path.display(), e))
+    }
+
+    /// Write the manifest to the data directory at `path`.
+    ///
+    /// # Errors
+    ///
+    /// In the case that the manifest could not be written.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), anyhow::Error> {
+        let mut contents = String::new();
+        for (file, checksum) in &self.checksums {
+            writeln!(contents, "{checksum}  {file}")?;
+        }
+        std::fs::write(path.as_ref().join(MANIFEST_FILE), contents)?;
+        Ok(())
+    }
+
+    /// Verify the data directory at `path` against the manifest.
+    ///
+    /// Returns the problems, sorted by file name, empty if the directory is intact.
+    pub fn verify<P: AsRef<Path>>(&self, path: P) -> Vec<Problem> {
+        let path = path.as_ref();
+        let mut problems = Vec::new();
+        for (file, expected) in &self.checksums {
+            let file_path = path.join(file);
+            if !file_path.exists() {
+                problems.push(Problem::Missing { file: file.clone() });
+                continue;
+            }
+            match sha256_file(&file_path) {
+                Ok(actual) if &actual == expected => (),
+                Ok(actual) => problems.push(Problem::Corrupted {
+                    file: file.clone(),
+                    expected: expected.clone(),
+                    actual,
+                }),
+                Err(e) => problems.push(Problem::Unreadable {
+                    file: file.clone(),
+                    error: e.to_string(),
+                }),
+            }
+        }
+        for file in EXPECTED_FILES.iter().chain(OPTIONAL_FILES) {
+            if !self.checksums.contains_key(*file) && path.join(file).exists() {
+                problems.push(Problem::Unlisted {
+                    file: (*file).to_string(),
+                });
+            }
+        }
+        problems.sort_by_key(ToString::to_string);
+        problems
+    }
+}
+
+/// Main entry point for `data manifest` sub command.
+///
+/// # Errors
+///
+/// In the case that an expected file is missing or the manifest could not be written.
+pub fn run(args_common: &crate::common::Args, args: &Args) -> Result<(), anyhow::Error> {
+    tracing::info!("args_common = {:?}", &args_common);
+    tracing::info!("args = {:?}", &args);
+
+    tracing::info!("Computing checksums...");
+    let before_compute = std::time::Instant::now();
+    let manifest = Manifest::compute(&args.path_hpo_dir)?;
+    manifest.save(&args.path_hpo_dir)?;
+    tracing::info!(
+        "... done writing {} checksums to {} in {:?}",
+        manifest.checksums.len(),
+        args.path_hpo_dir.join(MANIFEST_FILE).display(),
+        before_compute.elapsed()
+    );
+
+    tracing::info!("All done. Have a nice day!");
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::{Manifest, Problem, EXPECTED_FILES};
+
+    /// Write the expected files with their names as contents into `path`.
+    pub fn write_data_dir(path: &std::path::Path) -> Result<(), anyhow::Error> {
+        for file in EXPECTED_FILES {
+            std::fs::write(path.join(file), file)?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn sha256_file() -> Result<(), anyhow::Error> {
+        let tmp_dir = temp_testdir::TempDir::default();
+        std::fs::write(tmp_dir.join("abc.txt"), "abc")?;
+
+        assert_eq!(
+            super::sha256_file(tmp_dir.join("abc.txt"))?,
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn compute_save_load() -> Result<(), anyhow::Error> {
+        let tmp_dir = temp_testdir::TempDir::default();
+        assert!(Manifest::compute(&*tmp_dir).is_err());
+        write_data_dir(&tmp_dir)?;
+
+        let manifest = Manifest::compute(&*tmp_dir)?;
+        manifest.save(&*tmp_dir)?;
+
+        assert_eq!(manifest.checksums.len(), EXPECTED_FILES.len());
+        assert_eq!(Manifest::load(&*tmp_dir)?, manifest);
+        assert!(manifest.verify(&*tmp_dir).is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn from_reader() -> Result<(), anyhow::Error> {
+        let checksum = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+
+        let manifest = Manifest::from_reader(format!("{checksum}  hp.obo\n\n").as_bytes())?;
+        assert_eq!(manifest.checksums["hp.obo"], checksum);
+        let manifest = Manifest::from_reader(format!("{checksum} *hp.obo\n").as_bytes())?;
+        assert_eq!(manifest.checksums["hp.obo"], checksum);
+        assert!(Manifest::from_reader("abc  hp.obo\n".as_bytes()).is_err());
+        assert!(Manifest::from_reader(format!("{checksum}\n").as_bytes()).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn verify() -> Result<(), anyhow::Error> {
+        let tmp_dir = temp_testdir::TempDir::default();
+        write_data_dir(&tmp_dir)?;
+        let manifest = Manifest::compute(&*tmp_dir)?;
+
+        std::fs::write(tmp_dir.join("hp.obo"), "corrupted")?;
+        std::fs::remove_file(tmp_dir.join("hgnc_xlink.tsv"))?;
+        std::fs::write(tmp_dir.join("hpo.bin"), "binary")?;
+
+        let problems = manifest.verify(&*tmp_dir);
+        assert_eq!(problems.len(), 3);
+        assert_eq!(
+            problems[0],
+            Problem::Missing {
+                file: "hgnc_xlink.tsv".to_string()
+            }
+        );
+        assert!(matches!(&problems[1], Problem::Corrupted { file, .. } if file == "hp.obo"));
+        assert_eq!(
+            problems[2].to_string(),
+            "hpo.bin: not listed in MANIFEST.sha256"
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,7 @@
+//! Management of the HPO data directory.
+
+pub mod artifact;
+pub mod download;
+pub mod manifest;
+pub mod panelapp;
+pub mod verify;
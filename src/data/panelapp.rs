@@ -0,0 +1,295 @@
+//! Sync of gene panels from the `PanelApp` API into the TSV file of `--path-gene-panels`.
+//!
+//! The panels are fetched with `curl` as in `data download`.  The responses are cached
+//! in a directory and requests to the API are spaced out by a minimal interval.
+
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+/// Default base URL of the `PanelApp` API.
+pub const DEFAULT_URL_PANELAPP: &str = "https://panelapp.genomicsengland.co.uk/api/v1";
+
+/// Command line arguments for `data sync-panelapp` sub command.
+#[derive(clap::Parser, Debug)]
+#[command(author, version, about = "Fetch gene panels from PanelApp into a TSV file", long_about = None)]
+pub struct Args {
+    /// Comma-separated IDs of the panels to fetch, e.g., `245,90`.
+    #[arg(long, required = true, value_delimiter = ',')]
+    pub panels: Vec<u32>,
+    /// Path to the output TSV file for `server run --path-gene-panels`.
+    #[arg(long, required = true)]
+    pub out: PathBuf,
+    /// Base URL of the `PanelApp` API, e.g., of `PanelApp` Australia.
+    #[arg(long, default_value = DEFAULT_URL_PANELAPP)]
+    pub url_panelapp: String,
+    /// Directory for caching the API responses, next to the output file if missing.
+    #[arg(long)]
+    pub cache_dir: Option<PathBuf>,
+    /// Age in hours from which cached responses are fetched again.
+    #[arg(long, default_value_t = 24)]
+    pub max_age_hours: u64,
+    /// Minimal interval between two requests to the API in milliseconds.
+    #[arg(long, default_value_t = 1000)]
+    pub min_interval_ms: u64,
+    /// Minimal confidence level of the genes, 3 for green, 2 for amber, and 1 for red.
+    #[arg(long, default_value_t = 3)]
+    pub min_confidence: u8,
+}
+
+/// A panel as returned by `/panels/{id}/`.
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct Panel {
+    /// The panel ID, e.g., `245`.
+    pub id: u32,
+    /// The panel name, e.g., `Intellectual disability`.
+    pub name: String,
+    /// The panel version, e.g., `5.123`, if given.
+    #[serde(default)]
+    pub version: Option<String>,
+    /// The genes on the panel.
+    #[serde(default)]
+    pub genes: Vec<PanelGene>,
+}
+
+/// A gene of a panel.
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct PanelGene {
+    /// The gene information.
+    pub gene_data: GeneData,
+    /// The confidence level as a number string, e.g., `3` for green.
+    #[serde(default)]
+    pub confidence_level: Option<String>,
+}
+
+/// The gene information of a panel gene.
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct GeneData {
+    /// The gene symbol.
+    pub gene_symbol: String,
+}
+
+impl PanelGene {
+    /// Return the confidence level, 0 if missing or malformed.
+    pub fn confidence(&self) -> u8 {
+        self.confidence_level
+            .as_deref()
+            .and_then(|level| level.trim().parse().ok())
+            .unwrap_or_default()
+    }
+}
+
+/// Spacing of requests by a minimal interval.
+#[derive(Debug)]
+struct RateLimiter {
+    /// The minimal interval between two requests.
+    interval: Duration,
+    /// The time of the last request, if any.
+    last: Option<Instant>,
+}
+
+impl RateLimiter {
+    /// Sleep until the interval since the last request has passed and record the request.
+    fn wait(&mut self) {
+        if let Some(elapsed) = self.last.map(|last| last.elapsed()) {
+            if let Some(remaining) = self.interval.checked_sub(elapsed) {
+                std::thread::sleep(remaining);
+            }
+        }
+        self.last = Some(Instant::now());
+    }
+}
+
+/// Return whether the file at `path` exists and was modified less than `max_age` ago.
+fn is_fresh(path: &Path, max_age: Duration) -> bool {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| modified.elapsed().ok())
+        .is_some_and(|age| age < max_age)
+}
+
+/// Parse the `PanelApp` response in `reader` into a panel.
+///
+/// # Errors
+///
+/// In the case that the JSON is malformed.
+pub fn read_panel<R: std::io::Read>(reader: R) -> Result<Panel, anyhow::Error> {
+    serde_json::from_reader(reader).map_err(|e| anyhow::anyhow!("invalid PanelApp panel: {}", e))
+}
+
+/// Write the genes of `panels` with at least `min_confidence` as TSV to `writer`.
+///
+/// The columns are `panel_id`, e.g., `PanelApp:245`, `panel_name`, and `gene_symbol`, as
+/// read by `GenePanels`.  Tabs and line breaks in the panel names are replaced by spaces.
+/// Returns the number of rows written.
+///
+/// # Errors
+///
+/// In the case that writing failed.
+pub fn write_panels<W: Write>(
+    panels: &[Panel],
+    min_confidence: u8,
+    mut writer: W,
+) -> Result<usize, anyhow::Error> {
+    writeln!(writer, "panel_id\tpanel_name\tgene_symbol")?;
+    let mut count = 0;
+    for panel in panels {
+        let panel_name = panel.name.replace(['\t', '\n', '\r'], " ");
+        let mut gene_symbols = panel
+            .genes
+            .iter()
+            .filter(|gene| gene.confidence() >= min_confidence)
+            .map(|gene| gene.gene_data.gene_symbol.as_str())
+            .collect::<Vec<_>>();
+        gene_symbols.sort_unstable();
+        gene_symbols.dedup();
+        for gene_symbol in gene_symbols {
+            writeln!(
+                writer,
+                "PanelApp:{}\t{}\t{}",
+                panel.id, panel_name, gene_symbol
+            )?;
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+/// Main entry point for `data sync-panelapp` sub command.
+///
+/// # Errors
+///
+/// In the case that a panel could not be fetched or the output could not be written.
+pub fn run(args_common: &crate::common::Args, args: &Args) -> Result<(), anyhow::Error> {
+    tracing::info!("args_common = {:?}", &args_common);
+    tracing::info!("args = {:?}", &args);
+
+    let cache_dir = args.cache_dir.clone().unwrap_or_else(|| {
+        args.out
+            .parent()
+            .unwrap_or_else(|| Path::new("."))
+            .join("panelapp-cache")
+    });
+    std::fs::create_dir_all(&cache_dir)?;
+    let max_age = Duration::from_hours(args.max_age_hours);
+    let mut rate_limiter = RateLimiter {
+        interval: Duration::from_millis(args.min_interval_ms),
+        last: None,
+    };
+
+    tracing::info!("Fetching {} panels from PanelApp...", args.panels.len());
+    let before_fetch = Instant::now();
+    let mut panels = Vec::new();
+    for panel_id in &args.panels {
+        let path = cache_dir.join(format!("panel-{panel_id}.json"));
+        if is_fresh(&path, max_age) {
+            tracing::info!("  using cached panel {}", panel_id);
+        } else {
+            rate_limiter.wait();
+            super::download::fetch(
+                &format!(
+                    "{}/panels/{}/",
+                    args.url_panelapp.trim_end_matches('/'),
+                    panel_id
+                ),
+                &path,
+            )?;
+        }
+        let panel = read_panel(std::io::BufReader::new(std::fs::File::open(&path)?))
+            .map_err(|e| anyhow::anyhow!("panel {}: {}", panel_id, e))?;
+        tracing::info!(
+            "  panel {} ({}, version {}) has {} genes",
+            panel.id,
+            panel.name,
+            panel.version.as_deref().unwrap_or("unknown"),
+            panel.genes.len()
+        );
+        panels.push(panel);
+    }
+    tracing::info!("... done fetching in {:?}", before_fetch.elapsed());
+
+    let count = write_panels(
+        &panels,
+        args.min_confidence,
+        std::io::BufWriter::new(std::fs::File::create(&args.out)?),
+    )?;
+    tracing::info!("Wrote {} panel genes to {}", count, args.out.display());
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    #[test]
+    fn write_panels() -> Result<(), anyhow::Error> {
+        let json = serde_json::json!({
+            "id": 245,
+            "name": "Intellectual disability",
+            "version": "5.123",
+            "genes": [
+                {"gene_data": {"gene_symbol": "SCN1A"}, "confidence_level": "3"},
+                {"gene_data": {"gene_symbol": "ARID1B"}, "confidence_level": "3"},
+                {"gene_data": {"gene_symbol": "TGDS"}, "confidence_level": "2"},
+                {"gene_data": {"gene_symbol": "TTN"}}
+            ]
+        });
+        let panel = super::read_panel(json.to_string().as_bytes())?;
+        assert_eq!(panel.genes.len(), 4);
+        let mut buf = Vec::new();
+
+        let count = super::write_panels(&[panel], 2, &mut buf)?;
+
+        assert_eq!(count, 3);
+        assert_eq!(
+            String::from_utf8(buf.clone())?,
+            "panel_id\tpanel_name\tgene_symbol\n\
+             PanelApp:245\tIntellectual disability\tARID1B\n\
+             PanelApp:245\tIntellectual disability\tSCN1A\n\
+             PanelApp:245\tIntellectual disability\tTGDS\n"
+        );
+        let panels = crate::sources::panels::GenePanels::from_reader(buf.as_slice())?;
+        assert_eq!(panels.panels_of("TGDS").count(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn write_panels_name_whitespace() -> Result<(), anyhow::Error> {
+        let json = serde_json::json!({
+            "id": 90,
+            "name": "Cardiac\tarrhythmias\r\nand cardiomyopathies",
+            "genes": [{"gene_data": {"gene_symbol": "TTN"}, "confidence_level": "3"}]
+        });
+        let panel = super::read_panel(json.to_string().as_bytes())?;
+        let mut buf = Vec::new();
+
+        super::write_panels(&[panel], 3, &mut buf)?;
+
+        assert_eq!(
+            String::from_utf8(buf)?,
+            "panel_id\tpanel_name\tgene_symbol\n\
+             PanelApp:90\tCardiac arrhythmias  and cardiomyopathies\tTTN\n"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn is_fresh() -> Result<(), anyhow::Error> {
+        let tmp_dir = temp_testdir::TempDir::default();
+        let path = tmp_dir.join("panel-245.json");
+        assert!(!super::is_fresh(&path, Duration::from_mins(1)));
+
+        std::fs::write(&path, "{}")?;
+
+        assert!(super::is_fresh(&path, Duration::from_mins(1)));
+        assert!(!super::is_fresh(&path, Duration::ZERO));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,174 @@
+//! Verification of the HPO data directory against its checksum manifest.
+
+use std::str::FromStr;
+
+use strum::{EnumIter, IntoEnumIterator};
+
+use super::manifest::{Manifest, Problem, MANIFEST_FILE};
+
+/// Command line arguments for `data verify` sub command.
+#[derive(clap::Parser, Debug)]
+#[command(author, version, about = "Verify the HPO data directory against its manifest", long_about = None)]
+pub struct Args {
+    /// Path to the directory with the HPO files.
+    #[arg(long, required = true)]
+    pub path_hpo_dir: std::path::PathBuf,
+}
+
+/// How the server verifies the data directory on startup.
+#[derive(
+    Default,
+    Debug,
+    Clone,
+    Copy,
+    EnumIter,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    derive_more::Display,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum DataVerification {
+    /// Do not verify the data directory.
+    #[display("off")]
+    Off,
+    /// Log a warning for each problem and start anyway.
+    #[default]
+    #[display("warn")]
+    Warn,
+    /// Refuse to start on problems or without manifest.
+    #[display("strict")]
+    Strict,
+}
+
+impl FromStr for DataVerification {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        DataVerification::iter()
+            .find(|m| m.to_string().as_str().eq(s))
+            .ok_or(anyhow::anyhow!("unknown data verification: {}", s))
+    }
+}
+
+/// Verify the data directory at `path` against its manifest.
+///
+/// # Errors
+///
+/// In the case that the manifest could not be read.
+pub fn verify<P: AsRef<std::path::Path>>(path: P) -> Result<Vec<Problem>, anyhow::Error> {
+    Ok(Manifest::load(path.as_ref())?.verify(path.as_ref()))
+}
+
+/// Verify the data directory at `path` on server startup according to `mode`.
+///
+/// # Errors
+///
+/// In the case of `DataVerification::Strict` and a missing manifest or any problem.
+pub fn check_on_startup<P: AsRef<std::path::Path>>(
+    path: P,
+    mode: DataVerification,
+) -> Result<(), anyhow::Error> {
+    let path = path.as_ref();
+    if mode == DataVerification::Off {
+        return Ok(());
+    }
+    if mode == DataVerification::Warn && !path.join(MANIFEST_FILE).exists() {
+        tracing::info!(
+            "  no {} in {}, skipping verification (create with `viguno data manifest`)",
+            MANIFEST_FILE,
+            path.display()
+        );
+        return Ok(());
+    }
+
+    tracing::info!("Verifying data directory {}...", path.display());
+    let before_verify = std::time::Instant::now();
+    let problems = verify(path)?;
+    if problems.is_empty() {
+        tracing::info!("... done verifying in {:?}", before_verify.elapsed());
+        return Ok(());
+    }
+
+    for problem in &problems {
+        tracing::warn!("  {}", problem);
+    }
+    if mode == DataVerification::Strict {
+        anyhow::bail!(
+            "data directory {} failed verification, {} problem(s): {}",
+            path.display(),
+            problems.len(),
+            problems
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join("; ")
+        );
+    }
+    tracing::warn!(
+        "... data directory failed verification with {} problem(s), starting anyway",
+        problems.len()
+    );
+    Ok(())
+}
+
+/// Main entry point for `data verify` sub command.
+///
+/// # Errors
+///
+/// In the case that the manifest could not be read or the data directory has problems.
+pub fn run(args_common: &crate::common::Args, args: &Args) -> Result<(), anyhow::Error> {
+    tracing::info!("args_common = {:?}", &args_common);
+    tracing::info!("args = {:?}", &args);
+
+    check_on_startup(&args.path_hpo_dir, DataVerification::Strict)?;
+
+    tracing::info!("All done. Have a nice day!");
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{check_on_startup, DataVerification};
+    use crate::data::manifest::{test::write_data_dir, Manifest};
+
+    #[test]
+    fn data_verification() -> Result<(), anyhow::Error> {
+        for mode in [
+            DataVerification::Off,
+            DataVerification::Warn,
+            DataVerification::Strict,
+        ] {
+            assert_eq!(mode.to_string().parse::<DataVerification>()?, mode);
+        }
+        assert!("lenient".parse::<DataVerification>().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn check_on_startup_modes() -> Result<(), anyhow::Error> {
+        let tmp_dir = temp_testdir::TempDir::default();
+        write_data_dir(&tmp_dir)?;
+
+        check_on_startup(&*tmp_dir, DataVerification::Warn)?;
+        assert!(check_on_startup(&*tmp_dir, DataVerification::Strict).is_err());
+
+        Manifest::compute(&*tmp_dir)?.save(&*tmp_dir)?;
+        check_on_startup(&*tmp_dir, DataVerification::Strict)?;
+
+        std::fs::write(tmp_dir.join("phenotype.hpoa"), "corrupted")?;
+        check_on_startup(&*tmp_dir, DataVerification::Off)?;
+        check_on_startup(&*tmp_dir, DataVerification::Warn)?;
+        let err = check_on_startup(&*tmp_dir, DataVerification::Strict)
+            .expect_err("must fail")
+            .to_string();
+        assert!(err.contains("phenotype.hpoa: checksum mismatch"), "{err}");
+
+        Ok(())
+    }
+}
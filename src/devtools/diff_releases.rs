@@ -0,0 +1,387 @@
+//! Compare two HPO data directories before upgrading servers.
+
+use std::{
+    fs::File,
+    io::{self, Write},
+};
+
+use hpo::{
+    annotations::Disease as _,
+    comparison::{AnnotationDelta, Comparison},
+    HpoTermId, Ontology,
+};
+
+use crate::common::load_hpo;
+
+/// Command line arguments for `devtools diff-releases` sub command.
+#[derive(clap::Parser, Debug)]
+#[command(author, version, about = "Compare two HPO releases", long_about = None)]
+pub struct Args {
+    /// Path to the directory with the old HPO files.
+    #[arg(long, required = true)]
+    pub old: String,
+    /// Path to the directory with the new HPO files.
+    #[arg(long, required = true)]
+    pub new: String,
+    /// Path to the output JSON file.  Use stdout if missing.
+    #[arg(long)]
+    pub output_file: Option<String>,
+}
+
+impl Args {
+    /// Get writeable output file or stdout.
+    fn get_output(&self) -> Result<Box<dyn Write>, io::Error> {
+        match self.output_file {
+            Some(ref path) => File::create(path).map(|f| Box::new(f) as Box<dyn Write>),
+            None => Ok(Box::new(io::stdout())),
+        }
+    }
+}
+
+/// An HPO term in the report.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, serde::Serialize, serde::Deserialize)]
+pub struct Term {
+    /// The HPO term ID.
+    pub term_id: String,
+    /// The term name.
+    pub name: String,
+}
+
+/// A term that became obsolete in the new release.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, serde::Serialize, serde::Deserialize)]
+pub struct ObsoletedTerm {
+    /// The HPO term ID.
+    pub term_id: String,
+    /// The term name.
+    pub name: String,
+    /// The ID of the replacing term, if any.
+    pub replaced_by: Option<String>,
+}
+
+/// A term whose name changed.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, serde::Serialize, serde::Deserialize)]
+pub struct RenamedTerm {
+    /// The HPO term ID.
+    pub term_id: String,
+    /// The name in the old release.
+    pub old_name: String,
+    /// The name in the new release.
+    pub new_name: String,
+}
+
+/// A term whose direct parents changed.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, serde::Serialize, serde::Deserialize)]
+pub struct MovedTerm {
+    /// The HPO term ID.
+    pub term_id: String,
+    /// Parents in the new release only.
+    pub added_parents: Vec<String>,
+    /// Parents in the old release only.
+    pub removed_parents: Vec<String>,
+}
+
+/// A gene or disease in the report.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, serde::Serialize, serde::Deserialize)]
+pub struct Annotation {
+    /// The gene or disease ID.
+    pub id: String,
+    /// The gene symbol or disease name.
+    pub name: String,
+}
+
+impl Annotation {
+    /// Construct from the ID and name of a gene or disease.
+    fn new(id: &impl std::fmt::Display, name: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            name: name.to_string(),
+        }
+    }
+}
+
+/// A gene or disease whose annotation changed.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, serde::Serialize, serde::Deserialize)]
+pub struct ChangedAnnotation {
+    /// The gene or disease ID.
+    pub id: String,
+    /// The old and new name, if changed.
+    pub renamed: Option<(String, String)>,
+    /// Directly annotated terms in the new release only.
+    pub added_terms: Vec<String>,
+    /// Directly annotated terms in the old release only.
+    pub removed_terms: Vec<String>,
+}
+
+/// Changes of genes or diseases between the releases.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct AnnotationChanges {
+    /// Genes or diseases in the new release only.
+    pub added: Vec<Annotation>,
+    /// Genes or diseases in the old release only.
+    pub removed: Vec<Annotation>,
+    /// Genes or diseases with changed annotations.
+    pub changed: Vec<ChangedAnnotation>,
+}
+
+/// The report of differences between two releases.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ReleaseDiff {
+    /// Version of the old release.
+    pub old_version: String,
+    /// Version of the new release.
+    pub new_version: String,
+    /// Terms in the new release only.
+    pub added_terms: Vec<Term>,
+    /// Terms in the old release only.
+    pub removed_terms: Vec<Term>,
+    /// Terms that were marked obsolete in the new release.
+    pub obsoleted_terms: Vec<ObsoletedTerm>,
+    /// Terms that were renamed.
+    pub renamed_terms: Vec<RenamedTerm>,
+    /// Terms whose direct parents changed.
+    pub moved_terms: Vec<MovedTerm>,
+    /// Changes of gene annotations.
+    pub genes: AnnotationChanges,
+    /// Changes of OMIM disease annotations.
+    pub omim_diseases: AnnotationChanges,
+    /// Changes of ORPHA disease annotations.
+    pub orpha_diseases: AnnotationChanges,
+}
+
+/// Convert term IDs to sorted strings.
+fn to_sorted_strings(term_ids: Option<&Vec<HpoTermId>>) -> Vec<String> {
+    let mut term_ids = term_ids.cloned().unwrap_or_default();
+    term_ids.sort();
+    term_ids.iter().map(ToString::to_string).collect()
+}
+
+/// Build the sorted annotation changes.
+fn annotation_changes(
+    added: impl Iterator<Item = Annotation>,
+    removed: impl Iterator<Item = Annotation>,
+    changed: &[AnnotationDelta],
+) -> AnnotationChanges {
+    let mut result = AnnotationChanges {
+        added: added.collect(),
+        removed: removed.collect(),
+        changed: changed
+            .iter()
+            .map(|delta| ChangedAnnotation {
+                id: delta.id().to_string(),
+                renamed: delta.changed_name().cloned(),
+                added_terms: to_sorted_strings(delta.added_terms()),
+                removed_terms: to_sorted_strings(delta.removed_terms()),
+            })
+            .collect(),
+    };
+    result.added.sort();
+    result.removed.sort();
+    result.changed.sort();
+    result
+}
+
+/// Collect the obsoleted, renamed, and moved terms into `result`.
+fn collect_term_changes(result: &mut ReleaseDiff, comparison: &Comparison, new: &Ontology) {
+    for delta in comparison.changed_hpo_terms() {
+        let term_id = delta.id().to_string();
+        if let (Some((false, true)), Some(term)) = (delta.changed_obsolete(), new.hpo(*delta.id()))
+        {
+            result.obsoleted_terms.push(ObsoletedTerm {
+                term_id: term_id.clone(),
+                name: term.name().to_string(),
+                replaced_by: term.replacement_id().map(|id| id.to_string()),
+            });
+        }
+        if let Some((old_name, new_name)) = delta.changed_name() {
+            result.renamed_terms.push(RenamedTerm {
+                term_id: term_id.clone(),
+                old_name: old_name.clone(),
+                new_name: new_name.clone(),
+            });
+        }
+        if delta.added_parents().is_some() || delta.removed_parents().is_some() {
+            result.moved_terms.push(MovedTerm {
+                term_id,
+                added_parents: to_sorted_strings(delta.added_parents()),
+                removed_parents: to_sorted_strings(delta.removed_parents()),
+            });
+        }
+    }
+    result.obsoleted_terms.sort();
+    result.renamed_terms.sort();
+    result.moved_terms.sort();
+}
+
+/// Compute the differences between the `old` and the `new` release.
+pub fn diff(old: &Ontology, new: &Ontology) -> ReleaseDiff {
+    let comparison = Comparison::new(old, new);
+
+    let to_terms = |terms: Vec<hpo::HpoTerm>| {
+        let mut result = terms
+            .iter()
+            .map(|term| Term {
+                term_id: term.id().to_string(),
+                name: term.name().to_string(),
+            })
+            .collect::<Vec<_>>();
+        result.sort();
+        result
+    };
+
+    let mut result = ReleaseDiff {
+        old_version: old.hpo_version(),
+        new_version: new.hpo_version(),
+        added_terms: to_terms(comparison.added_hpo_terms()),
+        removed_terms: to_terms(comparison.removed_hpo_terms()),
+        ..Default::default()
+    };
+
+    collect_term_changes(&mut result, &comparison, new);
+
+    result.genes = annotation_changes(
+        comparison
+            .added_genes()
+            .iter()
+            .map(|gene| Annotation::new(gene.id(), gene.name())),
+        comparison
+            .removed_genes()
+            .iter()
+            .map(|gene| Annotation::new(gene.id(), gene.name())),
+        &comparison.changed_genes(),
+    );
+    result.omim_diseases = annotation_changes(
+        comparison
+            .added_omim_diseases()
+            .iter()
+            .map(|disease| Annotation::new(disease.id(), disease.name())),
+        comparison
+            .removed_omim_diseases()
+            .iter()
+            .map(|disease| Annotation::new(disease.id(), disease.name())),
+        &comparison.changed_omim_diseases(),
+    );
+    result.orpha_diseases = annotation_changes(
+        comparison
+            .added_orpha_diseases()
+            .iter()
+            .map(|disease| Annotation::new(disease.id(), disease.name())),
+        comparison
+            .removed_orpha_diseases()
+            .iter()
+            .map(|disease| Annotation::new(disease.id(), disease.name())),
+        &comparison.changed_orpha_diseases(),
+    );
+
+    result
+}
+
+/// Main entry point for `devtools diff-releases` sub command.
+///
+/// # Errors
+///
+/// In the case that the releases could not be loaded or the report could not be written.
+pub fn run(args_common: &crate::common::Args, args: &Args) -> Result<(), anyhow::Error> {
+    tracing::info!("args_common = {:?}", &args_common);
+    tracing::info!("args = {:?}", &args);
+
+    tracing::info!("Loading old HPO...");
+    let before_loading = std::time::Instant::now();
+    let old = load_hpo(&args.old)?;
+    tracing::info!("...done loading old HPO in {:?}", before_loading.elapsed());
+
+    tracing::info!("Loading new HPO...");
+    let before_loading = std::time::Instant::now();
+    let new = load_hpo(&args.new)?;
+    tracing::info!("...done loading new HPO in {:?}", before_loading.elapsed());
+
+    let report = diff(&old, &new);
+    tracing::info!(
+        "{} added, {} removed, {} obsoleted, {} renamed terms",
+        report.added_terms.len(),
+        report.removed_terms.len(),
+        report.obsoleted_terms.len(),
+        report.renamed_terms.len()
+    );
+
+    let mut output = args
+        .get_output()
+        .map_err(|e| anyhow::anyhow!("Failed to open output file: {}", e))?;
+    serde_json::to_writer_pretty(&mut output, &report)
+        .map_err(|e| anyhow::anyhow!("Failed to write output: {}", e))?;
+    writeln!(output).map_err(|e| anyhow::anyhow!("Failed to write output: {}", e))?;
+
+    tracing::info!("All done. Have a nice day!");
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use hpo::{
+        annotations::{GeneId, OmimDiseaseId},
+        builder::Builder,
+        Ontology,
+    };
+
+    /// Build an ontology with three terms below the root where `third` names the third term.
+    fn build_ontology(third: &str, gene_term: u32) -> Ontology {
+        let mut builder = Builder::new();
+        builder.new_term("All", 1u32);
+        builder.new_term("Left", 2u32);
+        builder.new_term(third, 3u32);
+        let mut builder = builder.terms_complete();
+        builder.add_parent(1u32, 2u32).expect("term must exist");
+        builder.add_parent(1u32, 3u32).expect("term must exist");
+        let mut builder = builder.connect_all_terms();
+        builder
+            .annotate_gene(GeneId::from(10), "GENE1", gene_term.into())
+            .expect("term must exist");
+        builder
+            .annotate_omim_disease(OmimDiseaseId::from(1), "Disease 1", 2u32.into())
+            .expect("term must exist");
+        builder
+            .calculate_information_content()
+            .expect("could not compute IC")
+            .build_minimal()
+    }
+
+    #[test]
+    fn diff_identical() {
+        let old = build_ontology("Right", 2);
+        let new = build_ontology("Right", 2);
+
+        let report = super::diff(&old, &new);
+
+        assert!(report.added_terms.is_empty());
+        assert!(report.removed_terms.is_empty());
+        assert!(report.renamed_terms.is_empty());
+        assert_eq!(report.genes, super::AnnotationChanges::default());
+        assert_eq!(report.omim_diseases, super::AnnotationChanges::default());
+    }
+
+    #[test]
+    fn diff_renamed_and_reannotated() {
+        let old = build_ontology("Right", 2);
+        let new = build_ontology("Right side", 3);
+
+        let report = super::diff(&old, &new);
+
+        assert_eq!(
+            report.renamed_terms,
+            vec![super::RenamedTerm {
+                term_id: "HP:0000003".into(),
+                old_name: "Right".into(),
+                new_name: "Right side".into(),
+            }]
+        );
+        assert_eq!(
+            report.genes.changed,
+            vec![super::ChangedAnnotation {
+                id: "NCBI-GeneID:10".into(),
+                renamed: None,
+                added_terms: vec!["HP:0000003".into()],
+                removed_terms: vec!["HP:0000002".into()],
+            }]
+        );
+        assert!(report.omim_diseases.changed.is_empty());
+    }
+}
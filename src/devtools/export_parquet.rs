@@ -0,0 +1,553 @@
+//! Export the annotation edge tables as partitioned Parquet files, e.g., for Spark.
+
+use std::{
+    collections::BTreeSet,
+    fs::File,
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use hpo::{
+    annotations::{AnnotationId as _, Disease as _},
+    Ontology,
+};
+use parquet::{
+    data_type::{ByteArray, ByteArrayType, DoubleType, Int64Type},
+    file::{metadata::KeyValue, properties::WriterProperties, writer::SerializedFileWriter},
+    schema::parser::parse_message_type,
+};
+
+use crate::common::load_hpo;
+
+/// Version of the schema of the exported tables, stored in the file metadata.
+pub const SCHEMA_VERSION: &str = "1";
+
+/// Command line arguments for `devtools export-parquet` sub command.
+#[derive(clap::Parser, Debug)]
+#[command(author, version, about = "Export HPO annotations as Parquet", long_about = None)]
+pub struct Args {
+    /// Path to the directory with the HPO files.
+    #[arg(long, required = true)]
+    pub path_hpo_dir: std::path::PathBuf,
+    /// Path to the output directory.
+    #[arg(long, required = true)]
+    pub output_dir: std::path::PathBuf,
+    /// Maximal number of rows per Parquet file.
+    #[arg(long, default_value_t = 1_000_000)]
+    pub rows_per_file: usize,
+}
+
+/// The values of a table column.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Column {
+    /// UTF-8 strings.
+    Utf8(Vec<String>),
+    /// 64-bit integers.
+    Int64(Vec<i64>),
+    /// 64-bit floating point numbers.
+    Double(Vec<f64>),
+}
+
+impl Column {
+    /// Return the number of values.
+    pub fn len(&self) -> usize {
+        match self {
+            Column::Utf8(values) => values.len(),
+            Column::Int64(values) => values.len(),
+            Column::Double(values) => values.len(),
+        }
+    }
+
+    /// Return whether the column is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Return the Parquet type for the message type definition.
+    fn parquet_type(&self) -> &'static str {
+        match self {
+            Column::Utf8(_) => "BYTE_ARRAY",
+            Column::Int64(_) => "INT64",
+            Column::Double(_) => "DOUBLE",
+        }
+    }
+
+    /// Return the logical type annotation for the message type definition.
+    fn annotation(&self) -> &'static str {
+        match self {
+            Column::Utf8(_) => " (UTF8)",
+            Column::Int64(_) | Column::Double(_) => "",
+        }
+    }
+}
+
+/// A table with named columns of equal length.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Table {
+    /// The named columns.
+    pub columns: Vec<(&'static str, Column)>,
+}
+
+impl Table {
+    /// Return the number of rows.
+    pub fn len(&self) -> usize {
+        self.columns.first().map_or(0, |(_, column)| column.len())
+    }
+
+    /// Return whether the table has no rows.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Return the Parquet message type of the table.
+    fn message_type(&self, name: &str) -> String {
+        let fields = self
+            .columns
+            .iter()
+            .map(|(column_name, column)| {
+                format!(
+                    "REQUIRED {} {}{};",
+                    column.parquet_type(),
+                    column_name,
+                    column.annotation()
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        format!("message {name} {{ {fields} }}")
+    }
+
+    /// Write the rows in `rows` to a Parquet file at `path` with the key/value `metadata`.
+    fn write_file(
+        &self,
+        name: &str,
+        path: &Path,
+        rows: Range<usize>,
+        metadata: &[KeyValue],
+    ) -> Result<(), anyhow::Error> {
+        let schema = Arc::new(parse_message_type(&self.message_type(name))?);
+        let props = Arc::new(
+            WriterProperties::builder()
+                .set_key_value_metadata(Some(metadata.to_vec()))
+                .build(),
+        );
+        let mut writer = SerializedFileWriter::new(File::create(path)?, schema, props)?;
+        let mut row_group = writer.next_row_group()?;
+        for (_, column) in &self.columns {
+            let mut column_writer = row_group
+                .next_column()?
+                .ok_or_else(|| anyhow::anyhow!("schema has fewer columns than table"))?;
+            match column {
+                Column::Utf8(values) => {
+                    let values = values[rows.clone()]
+                        .iter()
+                        .map(|value| ByteArray::from(value.as_str()))
+                        .collect::<Vec<_>>();
+                    column_writer
+                        .typed::<ByteArrayType>()
+                        .write_batch(&values, None, None)?;
+                }
+                Column::Int64(values) => {
+                    column_writer.typed::<Int64Type>().write_batch(
+                        &values[rows.clone()],
+                        None,
+                        None,
+                    )?;
+                }
+                Column::Double(values) => {
+                    column_writer.typed::<DoubleType>().write_batch(
+                        &values[rows.clone()],
+                        None,
+                        None,
+                    )?;
+                }
+            }
+            column_writer.close()?;
+        }
+        row_group.close()?;
+        writer.close()?;
+        Ok(())
+    }
+
+    /// Write the table into `dir` as `part-00000.parquet`, ... with `rows_per_file` rows each.
+    ///
+    /// Returns the paths of the written files.
+    ///
+    /// # Errors
+    ///
+    /// In the case that the files could not be written.
+    pub fn write_parts(
+        &self,
+        name: &str,
+        dir: &Path,
+        rows_per_file: usize,
+        metadata: &[KeyValue],
+    ) -> Result<Vec<PathBuf>, anyhow::Error> {
+        std::fs::create_dir_all(dir)?;
+        let mut result = Vec::new();
+        let mut start = 0;
+        while start < self.len() || result.is_empty() {
+            let end = (start + rows_per_file.max(1)).min(self.len());
+            let path = dir.join(format!("part-{:05}.parquet", result.len()));
+            self.write_file(name, &path, start..end, metadata)?;
+            result.push(path);
+            start = end;
+        }
+        Ok(result)
+    }
+}
+
+/// Build the table of direct term-gene annotations with the gene-based term IC.
+pub fn term_gene_table(ontology: &Ontology) -> Table {
+    let mut rows = ontology
+        .genes()
+        .flat_map(|gene| {
+            gene.hpo_terms()
+                .iter()
+                .map(|term_id| (term_id, gene.id().as_u32(), gene.name()))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    rows.sort_unstable();
+
+    let mut term_ids = Vec::new();
+    let mut gene_ids = Vec::new();
+    let mut gene_symbols = Vec::new();
+    let mut information_contents = Vec::new();
+    for (term_id, gene_id, gene_symbol) in rows {
+        term_ids.push(term_id.to_string());
+        gene_ids.push(i64::from(gene_id));
+        gene_symbols.push(gene_symbol.to_string());
+        information_contents.push(
+            ontology
+                .hpo(term_id)
+                .map_or(0.0, |term| f64::from(term.information_content().gene())),
+        );
+    }
+    Table {
+        columns: vec![
+            ("term_id", Column::Utf8(term_ids)),
+            ("ncbi_gene_id", Column::Int64(gene_ids)),
+            ("gene_symbol", Column::Utf8(gene_symbols)),
+            ("term_ic", Column::Double(information_contents)),
+        ],
+    }
+}
+
+/// Build the tables of direct term-disease annotations for OMIM and ORPHA.
+///
+/// The term IC is based on the diseases of the same source.
+pub fn term_disease_tables(ontology: &Ontology) -> Vec<(&'static str, Table)> {
+    let omim = ontology
+        .omim_diseases()
+        .map(|disease| {
+            (
+                disease.id().to_string(),
+                disease.name().to_string(),
+                disease.hpo_terms().iter().collect::<Vec<_>>(),
+            )
+        })
+        .collect::<Vec<_>>();
+    let orpha = ontology
+        .orpha_diseases()
+        .map(|disease| {
+            (
+                disease.id().to_string(),
+                disease.name().to_string(),
+                disease.hpo_terms().iter().collect::<Vec<_>>(),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    [("OMIM", omim), ("ORPHA", orpha)]
+        .into_iter()
+        .map(|(source, diseases)| {
+            let mut rows = diseases
+                .into_iter()
+                .flat_map(|(disease_id, name, term_ids)| {
+                    term_ids
+                        .into_iter()
+                        .map(move |term_id| (term_id, disease_id.clone(), name.clone()))
+                })
+                .collect::<Vec<_>>();
+            rows.sort_unstable();
+
+            let mut term_ids = Vec::new();
+            let mut disease_ids = Vec::new();
+            let mut disease_names = Vec::new();
+            let mut information_contents = Vec::new();
+            for (term_id, disease_id, name) in rows {
+                term_ids.push(term_id.to_string());
+                disease_ids.push(disease_id);
+                disease_names.push(name);
+                information_contents.push(ontology.hpo(term_id).map_or(0.0, |term| {
+                    let ic = term.information_content();
+                    f64::from(if source == "OMIM" {
+                        ic.omim_disease()
+                    } else {
+                        ic.orpha_disease()
+                    })
+                }));
+            }
+            let table = Table {
+                columns: vec![
+                    ("term_id", Column::Utf8(term_ids)),
+                    ("disease_id", Column::Utf8(disease_ids)),
+                    ("disease_name", Column::Utf8(disease_names)),
+                    ("term_ic", Column::Double(information_contents)),
+                ],
+            };
+            (source, table)
+        })
+        .collect()
+}
+
+/// Record of the `genes_to_phenotype.txt` file with the columns needed for gene-disease edges.
+#[derive(Debug, Clone, serde::Deserialize)]
+struct GenesToPhenotypeEntry {
+    /// The NCBI gene ID.
+    ncbi_gene_id: u32,
+    /// The gene symbol.
+    gene_symbol: String,
+    /// The disease ID, e.g., `OMIM:619340`.
+    disease_id: String,
+}
+
+/// Build the table of gene-disease edges from a `genes_to_phenotype.txt` file.
+///
+/// # Errors
+///
+/// In the case that the file could not be read or lacks the `disease_id` column.
+pub fn gene_disease_table<P: AsRef<Path>>(path: P) -> Result<Table, anyhow::Error> {
+    let mut rdr = csv::ReaderBuilder::new()
+        .delimiter(b'\t')
+        .has_headers(true)
+        .quoting(false)
+        .from_path(path.as_ref())?;
+    let mut rows = BTreeSet::new();
+    for record in rdr.deserialize() {
+        let entry: GenesToPhenotypeEntry = record?;
+        rows.insert((entry.ncbi_gene_id, entry.gene_symbol, entry.disease_id));
+    }
+
+    let mut gene_ids = Vec::new();
+    let mut gene_symbols = Vec::new();
+    let mut disease_ids = Vec::new();
+    for (gene_id, gene_symbol, disease_id) in rows {
+        gene_ids.push(i64::from(gene_id));
+        gene_symbols.push(gene_symbol);
+        disease_ids.push(disease_id);
+    }
+    Ok(Table {
+        columns: vec![
+            ("ncbi_gene_id", Column::Int64(gene_ids)),
+            ("gene_symbol", Column::Utf8(gene_symbols)),
+            ("disease_id", Column::Utf8(disease_ids)),
+        ],
+    })
+}
+
+/// Main entry point for `devtools export-parquet` sub command.
+///
+/// Writes `term_gene/`, `term_disease/source={OMIM,ORPHA}/`, and, if the HPO directory
+/// has a `genes_to_phenotype.txt` file, `gene_disease/` below the output directory.
+///
+/// # Errors
+///
+/// In the case that the HPO could not be loaded or the files could not be written.
+pub fn run(args_common: &crate::common::Args, args: &Args) -> Result<(), anyhow::Error> {
+    tracing::info!("args_common = {:?}", &args_common);
+    tracing::info!("args = {:?}", &args);
+
+    tracing::info!("Loading HPO...");
+    let before_loading = std::time::Instant::now();
+    let ontology = load_hpo(&args.path_hpo_dir)?;
+    tracing::info!("...done loading HPO in {:?}", before_loading.elapsed());
+
+    let metadata = vec![
+        KeyValue::new(
+            "viguno.schema_version".to_string(),
+            SCHEMA_VERSION.to_string(),
+        ),
+        KeyValue::new("viguno.hpo_release".to_string(), ontology.hpo_version()),
+    ];
+    let output_dir = Path::new(&args.output_dir);
+
+    let table = term_gene_table(&ontology);
+    tracing::info!("Writing {} term-gene edges...", table.len());
+    table.write_parts(
+        "term_gene",
+        &output_dir.join("term_gene"),
+        args.rows_per_file,
+        &metadata,
+    )?;
+
+    for (source, table) in term_disease_tables(&ontology) {
+        tracing::info!(
+            "Writing {} term-disease edges for {}...",
+            table.len(),
+            source
+        );
+        table.write_parts(
+            "term_disease",
+            &output_dir
+                .join("term_disease")
+                .join(format!("source={source}")),
+            args.rows_per_file,
+            &metadata,
+        )?;
+    }
+
+    let path_genes_to_phenotype = args.path_hpo_dir.join("genes_to_phenotype.txt");
+    if path_genes_to_phenotype.exists() {
+        let table = gene_disease_table(&path_genes_to_phenotype)?;
+        tracing::info!("Writing {} gene-disease edges...", table.len());
+        table.write_parts(
+            "gene_disease",
+            &output_dir.join("gene_disease"),
+            args.rows_per_file,
+            &metadata,
+        )?;
+    } else {
+        tracing::warn!(
+            "{} not found, skipping gene-disease edges",
+            path_genes_to_phenotype.display()
+        );
+    }
+
+    tracing::info!("All done. Have a nice day!");
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use hpo::{
+        annotations::{GeneId, OmimDiseaseId},
+        builder::Builder,
+        Ontology,
+    };
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+
+    use super::{Column, Table};
+
+    /// Build an ontology with two terms below the root, a gene, and a disease.
+    fn build_ontology() -> Ontology {
+        let mut builder = Builder::new();
+        builder.new_term("All", 1u32);
+        builder.new_term("Left", 2u32);
+        builder.new_term("Right", 3u32);
+        let mut builder = builder.terms_complete();
+        builder.add_parent(1u32, 2u32).expect("term must exist");
+        builder.add_parent(1u32, 3u32).expect("term must exist");
+        let mut builder = builder.connect_all_terms();
+        builder
+            .annotate_gene(GeneId::from(10), "GENE1", 2u32.into())
+            .expect("term must exist");
+        builder
+            .annotate_gene(GeneId::from(11), "GENE2", 3u32.into())
+            .expect("term must exist");
+        builder
+            .annotate_omim_disease(OmimDiseaseId::from(1), "Disease 1", 3u32.into())
+            .expect("term must exist");
+        builder
+            .calculate_information_content()
+            .expect("could not compute IC")
+            .build_minimal()
+    }
+
+    #[test]
+    fn term_gene_table() {
+        let table = super::term_gene_table(&build_ontology());
+
+        assert_eq!(table.len(), 2);
+        assert_eq!(
+            table.columns[0],
+            (
+                "term_id",
+                Column::Utf8(vec!["HP:0000002".into(), "HP:0000003".into()])
+            )
+        );
+        assert_eq!(
+            table.columns[1],
+            ("ncbi_gene_id", Column::Int64(vec![10, 11]))
+        );
+    }
+
+    #[test]
+    fn term_disease_tables() {
+        let tables = super::term_disease_tables(&build_ontology());
+
+        assert_eq!(tables.len(), 2);
+        assert_eq!(tables[0].0, "OMIM");
+        assert_eq!(
+            tables[0].1.columns[1],
+            ("disease_id", Column::Utf8(vec!["OMIM:1".into()]))
+        );
+        assert!(tables[1].1.is_empty());
+    }
+
+    #[test]
+    fn gene_disease_table() -> Result<(), anyhow::Error> {
+        let tmp_dir = temp_testdir::TempDir::default();
+        let path = tmp_dir.join("genes_to_phenotype.txt");
+        std::fs::write(
+            &path,
+            "ncbi_gene_id\tgene_symbol\thpo_id\thpo_name\tfrequency\tdisease_id\n\
+             10\tNAT2\tHP:0000007\tAutosomal recessive inheritance\t-\tOMIM:243400\n\
+             10\tNAT2\tHP:0001939\tAbnormality of metabolism/homeostasis\t-\tOMIM:243400\n",
+        )?;
+
+        let table = super::gene_disease_table(&path)?;
+
+        assert_eq!(table.len(), 1);
+        assert_eq!(
+            table.columns[2],
+            ("disease_id", Column::Utf8(vec!["OMIM:243400".into()]))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn write_parts() -> Result<(), anyhow::Error> {
+        let tmp_dir = temp_testdir::TempDir::default();
+        let table = super::term_gene_table(&build_ontology());
+        let metadata = vec![parquet::file::metadata::KeyValue::new(
+            "viguno.schema_version".to_string(),
+            super::SCHEMA_VERSION.to_string(),
+        )];
+
+        let paths = table.write_parts("term_gene", &tmp_dir.join("term_gene"), 1, &metadata)?;
+
+        assert_eq!(paths.len(), 2);
+        assert!(paths[1].ends_with("term_gene/part-00001.parquet"));
+        let reader = SerializedFileReader::new(std::fs::File::open(&paths[0])?)?;
+        let file_metadata = reader.metadata().file_metadata();
+        assert_eq!(file_metadata.num_rows(), 1);
+        assert_eq!(file_metadata.schema_descr().num_columns(), 4);
+        assert_eq!(
+            file_metadata
+                .key_value_metadata()
+                .and_then(|kvs| kvs.iter().find(|kv| kv.key == "viguno.schema_version"))
+                .and_then(|kv| kv.value.as_deref()),
+            Some(super::SCHEMA_VERSION)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn write_parts_empty() -> Result<(), anyhow::Error> {
+        let tmp_dir = temp_testdir::TempDir::default();
+        let table = Table {
+            columns: vec![("term_id", Column::Utf8(Vec::new()))],
+        };
+
+        let paths = table.write_parts("empty", &tmp_dir.join("empty"), 10, &[])?;
+
+        assert_eq!(paths.len(), 1);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,259 @@
+//! Export the loaded HPO and annotations as an SQL script for `SQLite` or `DuckDB`.
+
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+};
+
+use hpo::{
+    annotations::{AnnotationId as _, Disease as _},
+    Ontology,
+};
+
+use crate::common::load_hpo;
+
+/// Command line arguments for `devtools export-sql` sub command.
+#[derive(clap::Parser, Debug)]
+#[command(author, version, about = "Export HPO annotations as SQL", long_about = None)]
+pub struct Args {
+    /// Path to the directory with the HPO files.
+    #[arg(long, required = true)]
+    pub path_hpo_dir: std::path::PathBuf,
+    /// Path to the output SQL file.  Use stdout if missing.
+    #[arg(long)]
+    pub output_file: Option<String>,
+}
+
+impl Args {
+    /// Get writeable output file or stdout.
+    fn get_output(&self) -> Result<Box<dyn Write>, io::Error> {
+        match self.output_file {
+            Some(ref path) => {
+                File::create(path).map(|f| Box::new(BufWriter::new(f)) as Box<dyn Write>)
+            }
+            None => Ok(Box::new(io::stdout())),
+        }
+    }
+}
+
+/// The table definitions, shared by `SQLite` and `DuckDB`.
+const SCHEMA: &str = "\
+CREATE TABLE term (
+    term_id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    obsolete BOOLEAN NOT NULL,
+    ic_gene REAL NOT NULL,
+    ic_omim REAL NOT NULL,
+    ic_orpha REAL NOT NULL
+);
+CREATE TABLE term_parent (
+    term_id TEXT NOT NULL,
+    parent_id TEXT NOT NULL
+);
+CREATE TABLE gene (
+    ncbi_gene_id INTEGER PRIMARY KEY,
+    gene_symbol TEXT NOT NULL
+);
+CREATE TABLE disease (
+    disease_id TEXT PRIMARY KEY,
+    source TEXT NOT NULL,
+    name TEXT NOT NULL
+);
+CREATE TABLE gene_term (
+    ncbi_gene_id INTEGER NOT NULL,
+    term_id TEXT NOT NULL
+);
+CREATE TABLE disease_term (
+    disease_id TEXT NOT NULL,
+    term_id TEXT NOT NULL
+);
+";
+
+/// Quote `value` as an SQL string literal.
+fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+/// Write the SQL script with the terms, genes, diseases, and direct annotations.
+///
+/// Rows are sorted by their IDs so that the output is reproducible.
+///
+/// # Errors
+///
+/// In the case that writing to `out` fails.
+pub fn write_sql<W: Write>(ontology: &Ontology, out: &mut W) -> Result<(), io::Error> {
+    writeln!(out, "-- HPO release {}", ontology.hpo_version())?;
+    writeln!(out, "BEGIN TRANSACTION;")?;
+    write!(out, "{SCHEMA}")?;
+
+    let mut terms = ontology.hpos().collect::<Vec<_>>();
+    terms.sort_by_key(hpo::HpoTerm::id);
+    for term in &terms {
+        let ic = term.information_content();
+        writeln!(
+            out,
+            "INSERT INTO term VALUES ({}, {}, {}, {}, {}, {});",
+            quote(&term.id().to_string()),
+            quote(term.name()),
+            if term.is_obsolete() { "TRUE" } else { "FALSE" },
+            ic.gene(),
+            ic.omim_disease(),
+            ic.orpha_disease()
+        )?;
+    }
+    for term in &terms {
+        let mut parent_ids = term.parent_ids().iter().collect::<Vec<_>>();
+        parent_ids.sort();
+        for parent_id in parent_ids {
+            writeln!(
+                out,
+                "INSERT INTO term_parent VALUES ({}, {});",
+                quote(&term.id().to_string()),
+                quote(&parent_id.to_string())
+            )?;
+        }
+    }
+
+    let mut genes = ontology.genes().collect::<Vec<_>>();
+    genes.sort_by_key(|gene| gene.id().as_u32());
+    for gene in &genes {
+        writeln!(
+            out,
+            "INSERT INTO gene VALUES ({}, {});",
+            gene.id().as_u32(),
+            quote(gene.name())
+        )?;
+        let mut term_ids = gene.hpo_terms().iter().collect::<Vec<_>>();
+        term_ids.sort();
+        for term_id in term_ids {
+            writeln!(
+                out,
+                "INSERT INTO gene_term VALUES ({}, {});",
+                gene.id().as_u32(),
+                quote(&term_id.to_string())
+            )?;
+        }
+    }
+
+    let mut diseases = ontology
+        .omim_diseases()
+        .map(|disease| {
+            (
+                disease.id().to_string(),
+                "OMIM",
+                disease.name(),
+                disease.hpo_terms(),
+            )
+        })
+        .chain(ontology.orpha_diseases().map(|disease| {
+            (
+                disease.id().to_string(),
+                "ORPHA",
+                disease.name(),
+                disease.hpo_terms(),
+            )
+        }))
+        .collect::<Vec<_>>();
+    diseases.sort_by(|a, b| a.0.cmp(&b.0));
+    for (disease_id, source, name, hpo_terms) in &diseases {
+        writeln!(
+            out,
+            "INSERT INTO disease VALUES ({}, {}, {});",
+            quote(disease_id),
+            quote(source),
+            quote(name)
+        )?;
+        let mut term_ids = hpo_terms.iter().collect::<Vec<_>>();
+        term_ids.sort();
+        for term_id in term_ids {
+            writeln!(
+                out,
+                "INSERT INTO disease_term VALUES ({}, {});",
+                quote(disease_id),
+                quote(&term_id.to_string())
+            )?;
+        }
+    }
+
+    writeln!(out, "COMMIT;")?;
+    Ok(())
+}
+
+/// Main entry point for `devtools export-sql` sub command.
+///
+/// # Errors
+///
+/// In the case that the HPO could not be loaded or the script could not be written.
+pub fn run(args_common: &crate::common::Args, args: &Args) -> Result<(), anyhow::Error> {
+    tracing::info!("args_common = {:?}", &args_common);
+    tracing::info!("args = {:?}", &args);
+
+    tracing::info!("Loading HPO...");
+    let before_loading = std::time::Instant::now();
+    let ontology = load_hpo(&args.path_hpo_dir)?;
+    tracing::info!("...done loading HPO in {:?}", before_loading.elapsed());
+
+    let mut output = args
+        .get_output()
+        .map_err(|e| anyhow::anyhow!("Failed to open output file: {}", e))?;
+    write_sql(&ontology, &mut output)
+        .and_then(|()| output.flush())
+        .map_err(|e| anyhow::anyhow!("Failed to write output: {}", e))?;
+
+    tracing::info!("All done. Have a nice day!");
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use hpo::{
+        annotations::{GeneId, OmimDiseaseId},
+        builder::Builder,
+        Ontology,
+    };
+
+    /// Build an ontology with two terms below the root, a gene, and a disease.
+    fn build_ontology() -> Ontology {
+        let mut builder = Builder::new();
+        builder.new_term("All", 1u32);
+        builder.new_term("Left", 2u32);
+        builder.new_term("Parkinson's", 3u32);
+        let mut builder = builder.terms_complete();
+        builder.add_parent(1u32, 2u32).expect("term must exist");
+        builder.add_parent(1u32, 3u32).expect("term must exist");
+        let mut builder = builder.connect_all_terms();
+        builder
+            .annotate_gene(GeneId::from(10), "GENE1", 2u32.into())
+            .expect("term must exist");
+        builder
+            .annotate_omim_disease(OmimDiseaseId::from(1), "Disease 1", 3u32.into())
+            .expect("term must exist");
+        builder
+            .calculate_information_content()
+            .expect("could not compute IC")
+            .build_minimal()
+    }
+
+    #[test]
+    fn quote() {
+        assert_eq!(super::quote("Parkinson's"), "'Parkinson''s'");
+    }
+
+    #[test]
+    fn write_sql() -> Result<(), anyhow::Error> {
+        let mut out = Vec::new();
+        super::write_sql(&build_ontology(), &mut out)?;
+        let sql = String::from_utf8(out)?;
+
+        assert!(sql.contains("BEGIN TRANSACTION;\nCREATE TABLE term ("));
+        assert!(sql.contains("INSERT INTO term VALUES ('HP:0000003', 'Parkinson''s', FALSE,"));
+        assert!(sql.contains("INSERT INTO term_parent VALUES ('HP:0000002', 'HP:0000001');"));
+        assert!(sql.contains("INSERT INTO gene VALUES (10, 'GENE1');"));
+        assert!(sql.contains("INSERT INTO gene_term VALUES (10, 'HP:0000002');"));
+        assert!(sql.contains("INSERT INTO disease VALUES ('OMIM:1', 'OMIM', 'Disease 1');"));
+        assert!(sql.contains("INSERT INTO disease_term VALUES ('OMIM:1', 'HP:0000003');"));
+        assert!(sql.ends_with("COMMIT;\n"));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,6 @@
+//! Tools for developers and operators, e.g., for preparing HPO upgrades.
+
+pub mod diff_releases;
+pub mod export_parquet;
+pub mod export_sql;
+pub mod rank_delta;
@@ -0,0 +1,408 @@
+//! Compare the gene ranking of a phenotype set between two HPO releases, e.g., to trigger
+//! the reanalysis of cases after an upgrade.
+
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{self, Write},
+};
+
+use hpo::{annotations::AnnotationId as _, term::HpoGroup, HpoTermId, Ontology};
+
+use crate::{
+    algos::phenomizer::{self, TermWeights},
+    common::{load_hpo, parse_term_id},
+    query::{run_query, GeneScorer},
+    sources::hgnc_xlink::HgncXlink,
+};
+
+/// Command line arguments for `devtools rank-delta` sub command.
+#[derive(clap::Parser, Debug)]
+#[command(
+    author,
+    version,
+    about = "Compare the gene ranking of a phenotype set between two HPO releases",
+    long_about = None
+)]
+pub struct Args {
+    /// Path to the directory with the old HPO files.
+    #[arg(long, required = true)]
+    pub old: std::path::PathBuf,
+    /// Path to the directory with the new HPO files.
+    #[arg(long, required = true)]
+    pub new: std::path::PathBuf,
+    /// The phenotype set, e.g., `HP:0001250,HP:0001263`.
+    #[arg(long, required = true, value_delimiter = ',')]
+    pub terms: Vec<String>,
+    /// Restrict the ranking to these gene symbols, all annotated genes if missing.
+    #[arg(long, value_delimiter = ',')]
+    pub gene_symbols: Option<Vec<String>>,
+    /// Only report genes ranked within this many genes in either release.
+    #[arg(long, default_value_t = 100)]
+    pub top: usize,
+    /// Minimal change of the rank that is reported.
+    #[arg(long, default_value_t = 10)]
+    pub min_rank_change: usize,
+    /// Path to the output JSON file.  Use stdout if missing.
+    #[arg(long)]
+    pub output_file: Option<std::path::PathBuf>,
+}
+
+impl Args {
+    /// Get writeable output file or stdout.
+    fn get_output(&self) -> Result<Box<dyn Write>, io::Error> {
+        match self.output_file {
+            Some(ref path) => File::create(path).map(|f| Box::new(f) as Box<dyn Write>),
+            None => Ok(Box::new(io::stdout())),
+        }
+    }
+}
+
+/// Parameters of the comparison.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Thresholds {
+    /// Only report genes ranked within this many genes in either release.
+    pub top: usize,
+    /// Minimal change of the rank that is reported.
+    pub min_rank_change: usize,
+}
+
+/// The rank and score of a gene in one release.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Ranked {
+    /// The rank, genes with equal scores share the best rank.
+    pub rank: usize,
+    /// The Phenomizer score.
+    pub score: f32,
+}
+
+/// A gene whose rank changed materially.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct RankChange {
+    /// The gene symbol.
+    pub gene_symbol: String,
+    /// The NCBI gene ID.
+    pub ncbi_gene_id: u32,
+    /// The rank in the old release, if annotated there.
+    pub old: Option<Ranked>,
+    /// The rank in the new release, if annotated there.
+    pub new: Option<Ranked>,
+}
+
+/// The report of rank changes between two releases.
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct RankDelta {
+    /// Version of the old release.
+    pub old_version: String,
+    /// Version of the new release.
+    pub new_version: String,
+    /// Query terms that are not in the old release and were skipped there.
+    pub old_missing_terms: Vec<String>,
+    /// Query terms that are not in the new release and were skipped there.
+    pub new_missing_terms: Vec<String>,
+    /// The genes whose rank changed materially, by best rank.
+    pub changes: Vec<RankChange>,
+}
+
+impl RankChange {
+    /// Return whether the change is material according to `thresholds`.
+    fn is_material(&self, thresholds: Thresholds) -> bool {
+        match (self.old, self.new) {
+            (Some(old), Some(new)) => {
+                old.rank.min(new.rank) <= thresholds.top
+                    && old.rank.abs_diff(new.rank) >= thresholds.min_rank_change
+            }
+            (Some(ranked), None) | (None, Some(ranked)) => ranked.rank <= thresholds.top,
+            (None, None) => false,
+        }
+    }
+
+    /// Return the best rank in either release.
+    fn best_rank(&self) -> usize {
+        [self.old, self.new]
+            .iter()
+            .flatten()
+            .map(|ranked| ranked.rank)
+            .min()
+            .unwrap_or(usize::MAX)
+    }
+}
+
+/// The gene symbols and ranks by NCBI gene ID.
+type Ranks = BTreeMap<u32, (String, Ranked)>;
+
+/// Rank the genes of `hpo` (or `gene_symbols` only) by their similarity to `terms`.
+///
+/// Returns the ranks by NCBI gene ID with the gene symbols, and the terms that are not in
+/// `hpo`.
+///
+/// # Errors
+///
+/// In the case that the query fails.
+fn rank(
+    hpo: &Ontology,
+    terms: &[HpoTermId],
+    gene_symbols: Option<&[String]>,
+) -> Result<(Ranks, Vec<String>), anyhow::Error> {
+    let (present, missing): (Vec<_>, Vec<_>) = terms
+        .iter()
+        .partition(|term_id| hpo.hpo(**term_id).is_some());
+    let patient = present.into_iter().copied().collect::<HpoGroup>();
+    let genes = match gene_symbols {
+        Some(gene_symbols) => gene_symbols
+            .iter()
+            .filter_map(|gene_symbol| hpo.gene_by_name(gene_symbol))
+            .collect(),
+        None => hpo.genes().collect(),
+    };
+
+    let result = run_query(
+        &patient,
+        &genes,
+        hpo,
+        &HgncXlink::default(),
+        &GeneScorer {
+            score: phenomizer::score_modified,
+            sex: None,
+            route: None,
+            weights: &TermWeights::new(),
+            direct_ic: None,
+        },
+    )?;
+
+    let mut ranks = BTreeMap::new();
+    let mut rank = 0;
+    let mut previous = f32::NAN;
+    for (i, entry) in result.result.iter().enumerate() {
+        if entry.raw_score.total_cmp(&previous).is_ne() {
+            rank = i + 1;
+            previous = entry.raw_score;
+        }
+        if let Some(gene) = hpo.gene_by_name(&entry.gene_symbol) {
+            ranks.insert(
+                gene.id().as_u32(),
+                (
+                    entry.gene_symbol.clone(),
+                    Ranked {
+                        rank,
+                        score: entry.raw_score,
+                    },
+                ),
+            );
+        }
+    }
+
+    Ok((ranks, missing.iter().map(ToString::to_string).collect()))
+}
+
+/// Compare the ranking of the genes for `terms` between the `old` and the `new` release.
+///
+/// # Errors
+///
+/// In the case that the query fails.
+pub fn rank_delta(
+    old: &Ontology,
+    new: &Ontology,
+    terms: &[HpoTermId],
+    gene_symbols: Option<&[String]>,
+    thresholds: Thresholds,
+) -> Result<RankDelta, anyhow::Error> {
+    let (mut old_ranks, old_missing_terms) = rank(old, terms, gene_symbols)?;
+    let (new_ranks, new_missing_terms) = rank(new, terms, gene_symbols)?;
+
+    let mut changes = Vec::new();
+    for (ncbi_gene_id, (new_symbol, new_ranked)) in new_ranks {
+        let old_ranked = old_ranks.remove(&ncbi_gene_id).map(|(_, ranked)| ranked);
+        changes.push(RankChange {
+            gene_symbol: new_symbol,
+            ncbi_gene_id,
+            old: old_ranked,
+            new: Some(new_ranked),
+        });
+    }
+    for (ncbi_gene_id, (old_symbol, old_ranked)) in old_ranks {
+        changes.push(RankChange {
+            gene_symbol: old_symbol,
+            ncbi_gene_id,
+            old: Some(old_ranked),
+            new: None,
+        });
+    }
+    changes.retain(|change| change.is_material(thresholds));
+    changes.sort_by(|a, b| {
+        a.best_rank()
+            .cmp(&b.best_rank())
+            .then_with(|| a.gene_symbol.cmp(&b.gene_symbol))
+    });
+
+    Ok(RankDelta {
+        old_version: old.hpo_version(),
+        new_version: new.hpo_version(),
+        old_missing_terms,
+        new_missing_terms,
+        changes,
+    })
+}
+
+/// Main entry point for `devtools rank-delta` sub command.
+///
+/// # Errors
+///
+/// In the case that the releases could not be loaded or the report could not be written.
+pub fn run(args_common: &crate::common::Args, args: &Args) -> Result<(), anyhow::Error> {
+    tracing::info!("args_common = {:?}", &args_common);
+    tracing::info!("args = {:?}", &args);
+
+    let terms = args
+        .terms
+        .iter()
+        .map(|term_id| parse_term_id(term_id))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    tracing::info!("Loading old HPO...");
+    let before_loading = std::time::Instant::now();
+    let old = load_hpo(&args.old)?;
+    tracing::info!("...done loading old HPO in {:?}", before_loading.elapsed());
+
+    tracing::info!("Loading new HPO...");
+    let before_loading = std::time::Instant::now();
+    let new = load_hpo(&args.new)?;
+    tracing::info!("...done loading new HPO in {:?}", before_loading.elapsed());
+
+    let report = rank_delta(
+        &old,
+        &new,
+        &terms,
+        args.gene_symbols.as_deref(),
+        Thresholds {
+            top: args.top,
+            min_rank_change: args.min_rank_change,
+        },
+    )?;
+    tracing::info!(
+        "{} genes changed their rank materially",
+        report.changes.len()
+    );
+
+    let mut output = args
+        .get_output()
+        .map_err(|e| anyhow::anyhow!("Failed to open output file: {}", e))?;
+    serde_json::to_writer_pretty(&mut output, &report)
+        .map_err(|e| anyhow::anyhow!("Failed to write output: {}", e))?;
+    writeln!(output).map_err(|e| anyhow::anyhow!("Failed to write output: {}", e))?;
+
+    tracing::info!("All done. Have a nice day!");
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use hpo::{annotations::GeneId, builder::Builder, HpoTermId, Ontology};
+
+    /// Build an ontology with three terms below the root and `genes` annotated with a term.
+    fn build_ontology(genes: &[(u32, &str, u32)]) -> Ontology {
+        let mut builder = Builder::new();
+        builder.new_term("All", 1u32);
+        builder.new_term("Left", 2u32);
+        builder.new_term("Right", 3u32);
+        builder.new_term("Right upper", 4u32);
+        let mut builder = builder.terms_complete();
+        builder.add_parent(1u32, 2u32).expect("term must exist");
+        builder.add_parent(1u32, 3u32).expect("term must exist");
+        builder.add_parent(3u32, 4u32).expect("term must exist");
+        let mut builder = builder.connect_all_terms();
+        for (gene_id, symbol, term) in genes {
+            builder
+                .annotate_gene(GeneId::from(*gene_id), symbol, (*term).into())
+                .expect("term must exist");
+        }
+        builder
+            .calculate_information_content()
+            .expect("could not compute IC")
+            .build_minimal()
+    }
+
+    const THRESHOLDS: super::Thresholds = super::Thresholds {
+        top: 10,
+        min_rank_change: 1,
+    };
+
+    #[test]
+    fn rank_delta_unchanged() -> Result<(), anyhow::Error> {
+        let genes = [(10, "GENE1", 4), (20, "GENE2", 2)];
+        let report = super::rank_delta(
+            &build_ontology(&genes),
+            &build_ontology(&genes),
+            &[HpoTermId::from(4u32)],
+            None,
+            THRESHOLDS,
+        )?;
+
+        assert!(report.changes.is_empty(), "{report:?}");
+        assert!(report.old_missing_terms.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn rank_delta_swapped_and_added() -> Result<(), anyhow::Error> {
+        let old = build_ontology(&[(10, "GENE1", 4), (20, "GENE2", 2)]);
+        let new = build_ontology(&[(10, "GENE1", 2), (20, "GENE2", 4), (30, "GENE3", 3)]);
+
+        let report = super::rank_delta(&old, &new, &[HpoTermId::from(4u32)], None, THRESHOLDS)?;
+
+        let ranks = report
+            .changes
+            .iter()
+            .map(|change| {
+                (
+                    change.gene_symbol.as_str(),
+                    change.old.map(|ranked| ranked.rank),
+                    change.new.map(|ranked| ranked.rank),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ranks,
+            vec![
+                ("GENE1", Some(1), Some(3)),
+                ("GENE2", Some(2), Some(1)),
+                ("GENE3", None, Some(2)),
+            ]
+        );
+
+        // With a higher threshold, only the new gene is reported.
+        let report = super::rank_delta(
+            &old,
+            &new,
+            &[HpoTermId::from(4u32)],
+            None,
+            super::Thresholds {
+                top: 10,
+                min_rank_change: 5,
+            },
+        )?;
+        assert_eq!(report.changes.len(), 1);
+        assert_eq!(report.changes[0].gene_symbol, "GENE3");
+
+        Ok(())
+    }
+
+    #[test]
+    fn rank_delta_missing_terms() -> Result<(), anyhow::Error> {
+        let genes = [(10, "GENE1", 4), (20, "GENE2", 2)];
+        let report = super::rank_delta(
+            &build_ontology(&genes),
+            &build_ontology(&genes),
+            &[HpoTermId::from(4u32), HpoTermId::from(99u32)],
+            Some(&["GENE1".to_string()]),
+            THRESHOLDS,
+        )?;
+
+        assert_eq!(report.old_missing_terms, vec!["HP:0000099".to_string()]);
+        assert_eq!(report.new_missing_terms, vec!["HP:0000099".to_string()]);
+        assert!(report.changes.is_empty());
+
+        Ok(())
+    }
+}
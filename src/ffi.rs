@@ -0,0 +1,291 @@
+//! C ABI of the scoring, built with the `ffi` feature, e.g., for calling it via JNI.
+//!
+//! The header `include/viguno.h` is generated from this file with `cbindgen` by
+//! `build.rs`.  Build the shared library with
+//!
+//! ```text
+//! cargo rustc --lib --release --features ffi --crate-type cdylib
+//! ```
+//!
+//! Functions that fail or panic return `NULL` or a negative value and leave the error
+//! message for `viguno_last_error`.  Strings returned by the library must be released with
+//! `viguno_string_free`.
+
+use std::{
+    cell::RefCell,
+    ffi::{c_char, CStr, CString},
+    panic::{catch_unwind, AssertUnwindSafe},
+    sync::Arc,
+};
+
+use clap::Parser as _;
+
+use crate::{
+    query::query_result::HpoSimTermGeneResult,
+    server::run::{hpo_sim::term_gene, WebServerData},
+};
+
+thread_local! {
+    /// The message of the last error on this thread.
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// The loaded data, opaque to C.
+pub struct Viguno {
+    /// The data as loaded for `server run`.
+    data: Arc<WebServerData>,
+}
+
+/// Remember `err` as the last error of this thread.
+fn set_last_error(err: &anyhow::Error) {
+    let message = CString::new(format!("{err:#}").replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|last_error| *last_error.borrow_mut() = Some(message));
+}
+
+/// Run `f` and return its result, or remember its error or panic as the last error of this
+/// thread and return `on_error`.
+///
+/// Panics must not unwind into the calling C code.
+fn guard<T>(on_error: T, f: impl FnOnce() -> Result<T, anyhow::Error>) -> T {
+    let err = match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(value)) => return value,
+        Ok(Err(err)) => err,
+        Err(payload) => {
+            let message = payload
+                .downcast_ref::<&str>()
+                .map(ToString::to_string)
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_default();
+            anyhow::anyhow!("panic in viguno: {}", message)
+        }
+    };
+    set_last_error(&err);
+    on_error
+}
+
+/// Convert the C string `value` into a string slice.
+///
+/// # Safety
+///
+/// `value` must be `NULL` or point to a NUL-terminated string that outlives the result.
+unsafe fn to_str<'a>(value: *const c_char, name: &str) -> Result<&'a str, anyhow::Error> {
+    if value.is_null() {
+        anyhow::bail!("{} must not be NULL", name);
+    }
+    CStr::from_ptr(value)
+        .to_str()
+        .map_err(|e| anyhow::anyhow!("{} is no valid UTF-8: {}", name, e))
+}
+
+/// Prioritize the genes `gene_symbols` (all genes if empty) for the comma-separated `terms`.
+fn sim_term_gene(
+    viguno: &Viguno,
+    terms: &str,
+    gene_symbols: &str,
+) -> Result<HpoSimTermGeneResult, anyhow::Error> {
+    let gene_symbols = if gene_symbols.is_empty() {
+        itertools::Itertools::join(
+            &mut viguno
+                .data
+                .ontology
+                .genes()
+                .map(hpo::annotations::Gene::name),
+            ",",
+        )
+    } else {
+        gene_symbols.to_string()
+    };
+    let query = serde_json::from_value(serde_json::json!({
+        "terms": terms,
+        "gene_symbols": gene_symbols,
+    }))?;
+    term_gene::compute_blocking(&viguno.data, &query)
+}
+
+/// Load the data from the directory `path_hpo_dir` as `viguno server run` does.
+///
+/// Returns `NULL` on failure.  Release the result with `viguno_free`.
+///
+/// # Safety
+///
+/// `path_hpo_dir` must point to a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn viguno_init(path_hpo_dir: *const c_char) -> *mut Viguno {
+    guard(std::ptr::null_mut(), || {
+        let path_hpo_dir = to_str(path_hpo_dir, "path_hpo_dir")?;
+        let args =
+            crate::server::run::Args::try_parse_from(["viguno", "--path-hpo-dir", path_hpo_dir])?;
+        let data = crate::server::run::load_web_server_data(&args, crate::progress::Mode::Logs)?;
+        Ok(Box::into_raw(Box::new(Viguno {
+            data: Arc::new(data),
+        })))
+    })
+}
+
+/// Release the data loaded with `viguno_init`.
+///
+/// # Safety
+///
+/// `viguno` must be `NULL` or returned by `viguno_init` and not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn viguno_free(viguno: *mut Viguno) {
+    guard((), || {
+        if !viguno.is_null() {
+            drop(Box::from_raw(viguno));
+        }
+        Ok(())
+    });
+}
+
+/// Compute the score of the gene `gene_symbol` for the comma-separated `terms` and store it
+/// in `score`.
+///
+/// Returns 0 on success and -1 on failure.
+///
+/// # Safety
+///
+/// `viguno` must be returned by `viguno_init`, `terms` and `gene_symbol` must point to
+/// NUL-terminated strings, and `score` must point to a writeable float.
+#[no_mangle]
+pub unsafe extern "C" fn viguno_score_gene(
+    viguno: *const Viguno,
+    terms: *const c_char,
+    gene_symbol: *const c_char,
+    score: *mut f32,
+) -> i32 {
+    guard(-1, || {
+        let viguno = viguno
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("viguno must not be NULL"))?;
+        if score.is_null() {
+            anyhow::bail!("score must not be NULL");
+        }
+        let gene_symbol = to_str(gene_symbol, "gene_symbol")?;
+        let result = sim_term_gene(viguno, to_str(terms, "terms")?, gene_symbol)?;
+        let entry = result
+            .result
+            .first()
+            .ok_or_else(|| anyhow::anyhow!("unknown gene: {}", gene_symbol))?;
+        score.write(entry.raw_score);
+        Ok(0)
+    })
+}
+
+/// Prioritize the comma-separated `gene_symbols` (all genes if `NULL` or empty) for the
+/// comma-separated `terms` as `/api/v1/hpo/sim/term-gene` does.
+///
+/// Returns the JSON result without the `meta` object or `NULL` on failure.  Release the
+/// result with `viguno_string_free`.
+///
+/// # Safety
+///
+/// `viguno` must be returned by `viguno_init`, `terms` must point to a NUL-terminated
+/// string, and `gene_symbols` must be `NULL` or point to a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn viguno_sim_term_gene(
+    viguno: *const Viguno,
+    terms: *const c_char,
+    gene_symbols: *const c_char,
+) -> *mut c_char {
+    guard(std::ptr::null_mut(), || {
+        let viguno = viguno
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("viguno must not be NULL"))?;
+        let gene_symbols = if gene_symbols.is_null() {
+            ""
+        } else {
+            to_str(gene_symbols, "gene_symbols")?
+        };
+        let result = sim_term_gene(viguno, to_str(terms, "terms")?, gene_symbols)?;
+        Ok(CString::new(serde_json::to_string(&result)?)?.into_raw())
+    })
+}
+
+/// Release a string returned by the library.
+///
+/// # Safety
+///
+/// `value` must be `NULL` or returned by the library and not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn viguno_string_free(value: *mut c_char) {
+    guard((), || {
+        if !value.is_null() {
+            drop(CString::from_raw(value));
+        }
+        Ok(())
+    });
+}
+
+/// Return the message of the last error on the calling thread or `NULL`.
+///
+/// The message is owned by the library and valid until the next failing call on the thread.
+#[no_mangle]
+pub extern "C" fn viguno_last_error() -> *const c_char {
+    guard(std::ptr::null(), || {
+        Ok(LAST_ERROR.with(|last_error| {
+            last_error
+                .borrow()
+                .as_ref()
+                .map_or(std::ptr::null(), |message| message.as_ptr())
+        }))
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::ffi::{CStr, CString};
+
+    #[test]
+    fn header_up_to_date() {
+        assert_eq!(
+            include_str!("../include/viguno.h"),
+            include_str!(concat!(env!("OUT_DIR"), "/viguno.h")),
+            "include/viguno.h is outdated, copy it from the build directory"
+        );
+    }
+
+    #[test]
+    fn init_error() {
+        let path = CString::new("/nonexistent").expect("no NUL");
+
+        // SAFETY: the path is a valid C string.
+        let viguno = unsafe { super::viguno_init(path.as_ptr()) };
+
+        assert!(viguno.is_null());
+        // SAFETY: the error is set by the failed call on this thread.
+        let message = unsafe { CStr::from_ptr(super::viguno_last_error()) };
+        assert!(message.to_string_lossy().contains("/nonexistent"));
+    }
+
+    #[test]
+    fn null_arguments() {
+        let mut score = 0f32;
+
+        // SAFETY: all pointers are `NULL`, which the functions check.
+        let status = unsafe {
+            super::viguno_score_gene(
+                std::ptr::null(),
+                std::ptr::null(),
+                std::ptr::null(),
+                &raw mut score,
+            )
+        };
+        // SAFETY: `NULL` is allowed.
+        unsafe {
+            super::viguno_free(std::ptr::null_mut());
+            super::viguno_string_free(std::ptr::null_mut());
+        }
+
+        assert_eq!(status, -1);
+    }
+
+    #[test]
+    fn panic_as_error() {
+        let status = super::guard(-1, || -> Result<i32, anyhow::Error> { panic!("boom") });
+
+        assert_eq!(status, -1);
+        // SAFETY: the error is set by the failed call on this thread.
+        let message = unsafe { CStr::from_ptr(super::viguno_last_error()) };
+        assert_eq!(message.to_string_lossy(), "panic in viguno: boom");
+    }
+}
@@ -0,0 +1,49 @@
+//! Strategies for the property-based tests of the parsers.
+//!
+//! The tests use `proptest`, which shrinks failing inputs and records their seeds in
+//! `proptest-regressions/`.  Set `PROPTEST_CASES` to run more cases.
+
+use proptest::prelude::*;
+
+/// Characters the inputs are drawn from, biased to the syntax of the parsed formats.
+const ALPHABET: &[char] = &[
+    'H', 'P', 'h', 'p', 'O', 'M', 'I', ':', '_', '0', '1', '2', '5', '9', ',', ';', '[', ']', '\t',
+    ' ', '\n', '#', '-', 'é', 'ß', '€', '😀', '\u{0}',
+];
+
+/// Return a strategy for strings of up to `max_len` characters from the alphabet.
+pub fn string(max_len: usize) -> impl Strategy<Value = String> {
+    proptest::collection::vec(proptest::sample::select(ALPHABET), 0..=max_len)
+        .prop_map(|chars| chars.into_iter().collect())
+}
+
+/// Return a strategy for term IDs, well-formed or not.
+pub fn term_id() -> impl Strategy<Value = String> {
+    prop_oneof![
+        1 => (0..10_000_000u32).prop_map(|number| format!("HP:{number:07}")),
+        1 => (string(3), any::<u32>()).prop_map(|(prefix, number)| format!("{prefix}{number}")),
+        2 => string(12),
+    ]
+}
+
+/// Return a strategy for tab-separated lines of up to `max_columns` columns, e.g., of
+/// HPOA files, whose values are term IDs, `words`, or strings from the alphabet.
+pub fn tsv_line(
+    max_columns: usize,
+    words: &'static [&'static str],
+) -> impl Strategy<Value = String> {
+    let value = prop_oneof![
+        term_id(),
+        proptest::sample::select(words).prop_map(ToString::to_string),
+        string(8),
+    ];
+    proptest::collection::vec(value, 0..=max_columns).prop_map(|values| values.join("\t"))
+}
+
+/// Return a strategy for up to `max_lines` lines of `line`, joined by newlines.
+pub fn lines<S>(line: S, max_lines: usize) -> impl Strategy<Value = String>
+where
+    S: Strategy<Value = String>,
+{
+    proptest::collection::vec(line, 0..max_lines).prop_map(|lines| lines.join("\n"))
+}
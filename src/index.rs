@@ -1,6 +1,132 @@
 //! Full text index for OBO documents using tantivy.
 
-use tantivy::schema::Schema;
+use std::{collections::HashMap, ops::Range, path::Path};
+
+use hpo::HpoTermId;
+use tantivy::schema::{Field, Schema};
+
+use crate::sources::{local_terms::LocalTerms, translations::Translations};
+
+/// Value of the `namespace` field for terms of the HPO.
+pub const NAMESPACE_HPO: &str = "hpo";
+/// Value of the `namespace` field for local extension terms.
+pub const NAMESPACE_LOCAL: &str = "local";
+/// Name of the tokenizer of the text fields, see `normalizing_analyzer`.
+pub const TOKENIZER_NORMALIZED: &str = "normalized";
+
+/// Name of the tokenizer of the stemmed copies of the text fields, see `stemming_analyzer`.
+pub const TOKENIZER_STEMMED: &str = "normalized_stemmed";
+/// Suffix of the stemmed copies of the `name`, `def`, and `synonym` fields.
+pub const STEMMED_SUFFIX: &str = "_stemmed";
+
+/// Return the analyzer of the text fields.
+///
+/// The text is split into words and each word is lowercased and folded to ASCII, e.g.,
+/// "Généralisée" to "generalisee", "ß" to "ss", and "ﬁ" to "fi".  This amounts to NFKD
+/// with case folding and removal of the diacritics for Latin scripts.  The normalized
+/// `stopwords` are dropped.  As the query parser uses the analyzer of the fields, queries
+/// are normalized in the same way.
+pub fn normalizing_analyzer(stopwords: &[String]) -> tantivy::tokenizer::TextAnalyzer {
+    use tantivy::tokenizer::{
+        AsciiFoldingFilter, LowerCaser, RemoveLongFilter, SimpleTokenizer, StopWordFilter,
+        TextAnalyzer,
+    };
+
+    TextAnalyzer::builder(SimpleTokenizer::default())
+        .filter(RemoveLongFilter::limit(40))
+        .filter(LowerCaser)
+        .filter(AsciiFoldingFilter)
+        .filter(StopWordFilter::remove(stopwords.iter().cloned()))
+        .build()
+}
+
+/// Return the analyzer of the stemmed copies of the text fields.
+///
+/// The words are normalized as by `normalizing_analyzer` and then reduced to their
+/// English stem, e.g., "seizures" and "Seizure" both to "seizur".
+pub fn stemming_analyzer(stopwords: &[String]) -> tantivy::tokenizer::TextAnalyzer {
+    use tantivy::tokenizer::{
+        AsciiFoldingFilter, Language, LowerCaser, RemoveLongFilter, SimpleTokenizer, Stemmer,
+        StopWordFilter, TextAnalyzer,
+    };
+
+    TextAnalyzer::builder(SimpleTokenizer::default())
+        .filter(RemoveLongFilter::limit(40))
+        .filter(LowerCaser)
+        .filter(AsciiFoldingFilter)
+        .filter(StopWordFilter::remove(stopwords.iter().cloned()))
+        .filter(Stemmer::new(Language::English))
+        .build()
+}
+
+/// Add `value` to the text field `name` of `doc` and to its stemmed copy.
+fn add_text(
+    doc: &mut tantivy::TantivyDocument,
+    schema: &Schema,
+    name: &str,
+    value: &str,
+) -> Result<(), anyhow::Error> {
+    doc.add_field_value(schema.get_field(name)?, value);
+    doc.add_field_value(schema.get_field(&format!("{name}{STEMMED_SUFFIX}"))?, value);
+    Ok(())
+}
+
+/// Normalize `text` with `normalizing_analyzer` and return the words separated by spaces.
+fn normalize(text: &str) -> String {
+    let mut analyzer = normalizing_analyzer(&[]);
+    let mut stream = analyzer.token_stream(text);
+    let mut words = Vec::new();
+    while stream.advance() {
+        words.push(stream.token().text.clone());
+    }
+    words.join(" ")
+}
+
+/// Return `query` with an optional clause for `text` as a phrase in `fields`, so that
+/// exact phrase hits rank above hits of the words in any order.
+///
+/// The query is returned unchanged for single words and for texts with quotes, which
+/// are phrase queries already.
+pub fn with_phrase_boost(
+    index: &tantivy::Index,
+    query: Box<dyn tantivy::query::Query>,
+    text: &str,
+    fields: Vec<Field>,
+) -> Box<dyn tantivy::query::Query> {
+    use tantivy::query::{BooleanQuery, BoostQuery, Occur, QueryParser};
+
+    if text.contains('"') || text.split_whitespace().nth(1).is_none() {
+        return query;
+    }
+    let Ok(phrase) = QueryParser::for_index(index, fields).parse_query(&format!("\"{text}\""))
+    else {
+        return query;
+    };
+    Box::new(BooleanQuery::new(vec![
+        (Occur::Must, query),
+        (Occur::Should, Box::new(BoostQuery::new(phrase, 2.0))),
+    ]))
+}
+
+/// Read the stopwords from the file at `path` with one word per line, normalized.
+///
+/// Empty lines and lines starting with `#` are skipped.
+///
+/// # Errors
+///
+/// In the case that the file cannot be read.
+pub fn read_stopwords<P: AsRef<Path>>(path: P) -> Result<Vec<String>, anyhow::Error> {
+    let path = path.as_ref();
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("could not read {:?}: {}", path, e))?;
+    Ok(text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(normalize)
+        .filter(|word| !word.is_empty())
+        .collect())
+}
 
 /// Encapsulation of a Tantivy index.
 ///
@@ -17,10 +143,100 @@ pub struct Index {
     schema: tantivy::schema::Schema,
     /// The single reader.
     reader: tantivy::IndexReader,
+    /// Map from alternative term ID to the primary ID of the merged term.
+    alt_ids: HashMap<HpoTermId, HpoTermId>,
+    /// Map from term ID to the term's xrefs, e.g., `SNOMEDCT_US:91175000`.
+    xrefs: HashMap<HpoTermId, Vec<String>>,
+    /// Map from xref to the IDs of the terms having it.
+    xref_terms: HashMap<String, Vec<HpoTermId>>,
+    /// Map from term ID to the term's creation metadata and property values.
+    history: HashMap<HpoTermId, TermHistory>,
+    /// The text of the OBO file and the byte offsets of the term stanzas in it, if known.
+    stanzas: Option<(String, HashMap<HpoTermId, Range<u64>>)>,
+}
+
+/// A property value of a term, e.g., `IAO:0000233` with the URL of the request issue.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct TermPropertyValue {
+    /// The property, e.g., `IAO:0000233`.
+    pub property: String,
+    /// The value, an ID or the literal text.
+    pub value: String,
+}
+
+/// The creation metadata and property values of a term from the OBO document.
+#[derive(
+    Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize, utoipa::ToSchema,
+)]
+pub struct TermHistory {
+    /// The creation date as written in the OBO, e.g., `2008-02-27T02:20:00Z`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub creation_date: Option<String>,
+    /// The creator, e.g., an ORCID URL.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_by: Option<String>,
+    /// The property values in the order of the OBO.
+    #[serde(default)]
+    pub property_values: Vec<TermPropertyValue>,
+}
+
+impl TermHistory {
+    /// Return the day of the creation date, e.g., `2008-02-27` for `2008-02-27T02:20:00Z`.
+    ///
+    /// Returns `None` if the term has no creation date or it is malformed.
+    pub fn creation_day(&self) -> Option<chrono::NaiveDate> {
+        let creation_date = self.creation_date.as_deref()?.trim();
+        chrono::DateTime::parse_from_rfc3339(creation_date)
+            .map(|date_time| date_time.date_naive())
+            .or_else(|_| chrono::NaiveDate::parse_from_str(creation_date, "%Y-%m-%d"))
+            .ok()
+    }
+
+    /// Return whether the term was created after the `date`.
+    ///
+    /// Terms without a well-formed creation date are never created after a date.
+    pub fn created_after(&self, date: chrono::NaiveDate) -> bool {
+        self.creation_day()
+            .is_some_and(|creation_day| creation_day > date)
+    }
+}
+
+/// Return the byte offsets of the `[Term]` stanzas of the HPO terms in the OBO `text`.
+///
+/// A stanza runs from its header line to the next header line, without the trailing
+/// blank lines.
+pub fn stanza_offsets(text: &str) -> HashMap<HpoTermId, Range<u64>> {
+    let mut result = HashMap::new();
+    let mut finish = |current: Option<(bool, Option<HpoTermId>, u64)>, end: u64| {
+        if let Some((true, Some(term_id), start)) = current {
+            result.insert(term_id, start..end);
+        }
+    };
+    // The current stanza as whether it is a term, its ID, and its start.
+    let mut current = None;
+    let mut offset = 0;
+    let mut content_end = 0;
+    for line in text.split_inclusive('\n') {
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') {
+            finish(current.take(), content_end);
+            current = Some((trimmed == "[Term]", None, offset));
+        } else if let Some((_, term_id @ None, _)) = current.as_mut() {
+            *term_id = trimmed
+                .strip_prefix("id:")
+                .and_then(|id| crate::common::parse_term_id(id.trim()).ok());
+        }
+        offset += line.len() as u64;
+        if !trimmed.is_empty() {
+            content_end = offset;
+        }
+    }
+    finish(current, content_end);
+    result
 }
 
 /// Convert ident to String.
-fn ident_to_string(ident: &fastobo::ast::Ident) -> String {
+pub fn ident_to_string(ident: &fastobo::ast::Ident) -> String {
     match ident {
         fastobo::ast::Ident::Prefixed(val) => format!("{}:{}", val.prefix(), val.local()),
         fastobo::ast::Ident::Unprefixed(val) => val.as_str().to_string(),
@@ -30,19 +246,58 @@ fn ident_to_string(ident: &fastobo::ast::Ident) -> String {
 
 // Code for creating an `Index`.
 impl Index {
-    /// Create a new index from an OBO document.
+    /// Create a new index from the HPO OBO document.
+    ///
+    /// The translated names and synonyms are indexed in the per-language fields
+    /// `name_{lang}` and `synonym_{lang}`.
     ///
     /// # Args
     ///
     /// * `hpo_doc` - The OBO document to index.
+    /// * `translations` - The translations of the term labels.
     ///
     /// # Errors
     ///
     /// In the case that the index cannot be created.
-    pub fn new(hpo_doc: fastobo::ast::OboDoc) -> Result<Self, anyhow::Error> {
-        let schema = Self::build_schema();
+    pub fn new(
+        hpo_doc: fastobo::ast::OboDoc,
+        translations: &Translations,
+    ) -> Result<Self, anyhow::Error> {
+        Self::with_namespace(hpo_doc, translations, NAMESPACE_HPO)
+    }
+
+    /// Create a new index from an arbitrary OBO document, e.g., MAXO.
+    ///
+    /// The terms are indexed as for the HPO but with `namespace` as the value of the
+    /// `namespace` field.
+    ///
+    /// # Errors
+    ///
+    /// In the case that the index cannot be created.
+    pub fn with_namespace(
+        hpo_doc: fastobo::ast::OboDoc,
+        translations: &Translations,
+        namespace: &str,
+    ) -> Result<Self, anyhow::Error> {
+        Self::build(hpo_doc, translations, namespace, &[])
+    }
+
+    /// Create a new index as `with_namespace` whose text fields drop the `stopwords`.
+    fn build(
+        hpo_doc: fastobo::ast::OboDoc,
+        translations: &Translations,
+        namespace: &str,
+        stopwords: &[String],
+    ) -> Result<Self, anyhow::Error> {
+        let schema = Self::build_schema(&translations.languages());
         let tmpdir = tempdir::TempDir::new("viguno")?;
         let index = tantivy::Index::create_in_dir(tmpdir.path(), schema.clone())?;
+        index
+            .tokenizers()
+            .register(TOKENIZER_NORMALIZED, normalizing_analyzer(stopwords));
+        index
+            .tokenizers()
+            .register(TOKENIZER_STEMMED, stemming_analyzer(stopwords));
 
         let mut index_writer = index.writer(100_000_000).map_err(|e| {
             anyhow::anyhow!(
@@ -51,7 +306,14 @@ impl Index {
                 e
             )
         })?;
-        Self::write_hpo_index(&hpo_doc, &schema, &mut index_writer).map_err(|e| {
+        Self::write_hpo_index(
+            &hpo_doc,
+            translations,
+            namespace,
+            &schema,
+            &mut index_writer,
+        )
+        .map_err(|e| {
             anyhow::anyhow!(
                 "Error writing HPO index for directory {:?}: {}",
                 tmpdir.path(),
@@ -64,8 +326,24 @@ impl Index {
             .reload_policy(tantivy::ReloadPolicy::Manual)
             .try_into()?;
 
+        let xrefs = Self::collect_xrefs(&hpo_doc);
+        let mut xref_terms = HashMap::<_, Vec<_>>::new();
+        for (term_id, term_xrefs) in &xrefs {
+            for xref in term_xrefs {
+                xref_terms.entry(xref.clone()).or_default().push(*term_id);
+            }
+        }
+        for term_ids in xref_terms.values_mut() {
+            term_ids.sort();
+        }
+
         Ok(Self {
             tmpdir,
+            alt_ids: Self::collect_alt_ids(&hpo_doc),
+            xrefs,
+            xref_terms,
+            history: Self::collect_history(&hpo_doc),
+            stanzas: None,
             hpo_doc,
             index,
             schema,
@@ -73,23 +351,174 @@ impl Index {
         })
     }
 
-    /// Build the tantivy schema for the HPO.
-    fn build_schema() -> Schema {
-        use tantivy::schema::{STORED, STRING, TEXT};
+    /// Create a new index from the HPO OBO file at `path`, see `new`.
+    ///
+    /// In contrast to `new`, the text of the file is kept in memory for `stanza`, and
+    /// the `stopwords`, e.g., from `read_stopwords`, are dropped from the text fields and
+    /// the queries.
+    ///
+    /// # Errors
+    ///
+    /// In the case that the file cannot be read or parsed, or the index cannot be created.
+    pub fn from_path<P: AsRef<Path>>(
+        path: P,
+        translations: &Translations,
+        stopwords: &[String],
+    ) -> Result<Self, anyhow::Error> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("could not read {:?}: {}", path, e))?;
+        let hpo_doc = fastobo::from_str(&text)
+            .map_err(|e| anyhow::anyhow!("Error loading HPO OBO: {}", e))?;
+        let offsets = stanza_offsets(&text);
+        Ok(Self {
+            stanzas: Some((text, offsets)),
+            ..Self::build(hpo_doc, translations, NAMESPACE_HPO, stopwords)?
+        })
+    }
+
+    /// Collect the `alt_id` clauses of the HPO document by alternative ID.
+    fn collect_alt_ids(hpo_doc: &fastobo::ast::OboDoc) -> HashMap<HpoTermId, HpoTermId> {
+        let mut result = HashMap::new();
+        for term_frame in hpo_doc
+            .entities()
+            .iter()
+            .filter_map(fastobo::ast::EntityFrame::as_term)
+        {
+            let Ok(term_id) =
+                crate::common::parse_term_id(&ident_to_string(term_frame.id().as_inner().as_ref()))
+            else {
+                continue;
+            };
+            for line in term_frame.clauses() {
+                if let fastobo::ast::TermClause::AltId(alt_id) = line.as_inner() {
+                    if let Ok(alt_id) = crate::common::parse_term_id(&ident_to_string(alt_id)) {
+                        result.insert(alt_id, term_id);
+                    }
+                }
+            }
+        }
+        result
+    }
+
+    /// Collect the `xref` clauses of the HPO document by term ID.
+    fn collect_xrefs(hpo_doc: &fastobo::ast::OboDoc) -> HashMap<HpoTermId, Vec<String>> {
+        let mut result = HashMap::new();
+        for term_frame in hpo_doc
+            .entities()
+            .iter()
+            .filter_map(fastobo::ast::EntityFrame::as_term)
+        {
+            let Ok(term_id) =
+                crate::common::parse_term_id(&ident_to_string(term_frame.id().as_inner().as_ref()))
+            else {
+                continue;
+            };
+            let xrefs = term_frame
+                .clauses()
+                .iter()
+                .filter_map(|line| match line.as_inner() {
+                    fastobo::ast::TermClause::Xref(xref) => Some(ident_to_string(xref.id())),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+            if !xrefs.is_empty() {
+                result.insert(term_id, xrefs);
+            }
+        }
+        result
+    }
+
+    /// Collect the `creation_date`, `created_by`, and `property_value` clauses of the HPO
+    /// document by term ID.
+    fn collect_history(hpo_doc: &fastobo::ast::OboDoc) -> HashMap<HpoTermId, TermHistory> {
+        let mut result = HashMap::new();
+        for term_frame in hpo_doc
+            .entities()
+            .iter()
+            .filter_map(fastobo::ast::EntityFrame::as_term)
+        {
+            let Ok(term_id) =
+                crate::common::parse_term_id(&ident_to_string(term_frame.id().as_inner().as_ref()))
+            else {
+                continue;
+            };
+            let mut history = TermHistory::default();
+            for line in term_frame.clauses() {
+                match line.as_inner() {
+                    fastobo::ast::TermClause::CreationDate(date) => {
+                        history.creation_date = Some(date.to_string());
+                    }
+                    fastobo::ast::TermClause::CreatedBy(creator) => {
+                        history.created_by = Some(creator.as_str().to_string());
+                    }
+                    fastobo::ast::TermClause::PropertyValue(pv) => {
+                        let value = match pv.as_ref() {
+                            fastobo::ast::PropertyValue::Resource(pv) => {
+                                ident_to_string(pv.target())
+                            }
+                            fastobo::ast::PropertyValue::Literal(pv) => {
+                                pv.literal().as_str().to_string()
+                            }
+                        };
+                        history.property_values.push(TermPropertyValue {
+                            property: pv.property().to_string(),
+                            value,
+                        });
+                    }
+                    _ => (),
+                }
+            }
+            if history != TermHistory::default() {
+                result.insert(term_id, history);
+            }
+        }
+        result
+    }
+
+    /// Build the tantivy schema for the HPO with fields for the given `languages`.
+    ///
+    /// The text fields use the `TOKENIZER_NORMALIZED` tokenizer.  The `name`, `def`, and
+    /// `synonym` fields have an unstored copy with the `TOKENIZER_STEMMED` tokenizer.
+    fn build_schema(languages: &[&str]) -> Schema {
+        use tantivy::schema::{IndexRecordOption, TextFieldIndexing, TextOptions, STORED, STRING};
+
+        let text = TextOptions::default()
+            .set_indexing_options(
+                TextFieldIndexing::default()
+                    .set_tokenizer(TOKENIZER_NORMALIZED)
+                    .set_index_option(IndexRecordOption::WithFreqsAndPositions),
+            )
+            .set_stored();
+        let stemmed = TextOptions::default().set_indexing_options(
+            TextFieldIndexing::default()
+                .set_tokenizer(TOKENIZER_STEMMED)
+                .set_index_option(IndexRecordOption::WithFreqsAndPositions),
+        );
 
         let mut schema_builder = Schema::builder();
         schema_builder.add_text_field("term_id", STRING | STORED);
         schema_builder.add_text_field("alt_id", STRING | STORED);
-        schema_builder.add_text_field("name", TEXT | STORED);
-        schema_builder.add_text_field("def", TEXT | STORED);
-        schema_builder.add_text_field("synonym", TEXT | STORED);
+        schema_builder.add_text_field("name", text.clone());
+        schema_builder.add_text_field("def", text.clone());
+        schema_builder.add_text_field("synonym", text.clone());
         schema_builder.add_text_field("xref", STRING | STORED);
+        schema_builder.add_text_field("namespace", STRING | STORED);
+        for name in ["name", "def", "synonym"] {
+            schema_builder.add_text_field(&format!("{name}{STEMMED_SUFFIX}"), stemmed.clone());
+        }
+        for lang in languages {
+            schema_builder.add_text_field(&format!("name_{lang}"), text.clone());
+            schema_builder.add_text_field(&format!("synonym_{lang}"), text.clone());
+        }
         schema_builder.build()
     }
 
     /// Index the HPO document.
     fn write_hpo_index(
         hpo_doc: &fastobo::ast::OboDoc,
+        translations: &Translations,
+        namespace: &str,
         schema: &tantivy::schema::Schema,
         index_writer: &mut tantivy::IndexWriter,
     ) -> Result<(), anyhow::Error> {
@@ -100,10 +529,25 @@ impl Index {
         {
             let mut doc = tantivy::TantivyDocument::default();
 
-            doc.add_field_value(
-                schema.get_field("term_id")?,
-                ident_to_string(term_frame.id().as_inner().as_ref()),
-            );
+            let term_id = ident_to_string(term_frame.id().as_inner().as_ref());
+            for lang in translations.languages() {
+                if let Some(translation) = translations.get(lang, &term_id) {
+                    if let Some(name) = &translation.name {
+                        doc.add_field_value(
+                            schema.get_field(&format!("name_{lang}"))?,
+                            name.as_str(),
+                        );
+                    }
+                    for synonym in &translation.synonyms {
+                        doc.add_field_value(
+                            schema.get_field(&format!("synonym_{lang}"))?,
+                            synonym.as_str(),
+                        );
+                    }
+                }
+            }
+            doc.add_field_value(schema.get_field("term_id")?, term_id);
+            doc.add_field_value(schema.get_field("namespace")?, namespace);
 
             for line in term_frame
                 .clauses()
@@ -112,7 +556,7 @@ impl Index {
             {
                 match line {
                     fastobo::ast::TermClause::Name(name) => {
-                        doc.add_field_value(schema.get_field("name")?, name.as_str());
+                        add_text(&mut doc, schema, "name", name.as_str())?;
                     }
                     fastobo::ast::TermClause::AltId(alt_id) => {
                         doc.add_field_value(
@@ -121,13 +565,10 @@ impl Index {
                         );
                     }
                     fastobo::ast::TermClause::Def(def) => {
-                        doc.add_field_value(schema.get_field("def")?, def.text().as_str());
+                        add_text(&mut doc, schema, "def", def.text().as_str())?;
                     }
                     fastobo::ast::TermClause::Synonym(synonym) => {
-                        doc.add_field_value(
-                            schema.get_field("synonym")?,
-                            synonym.description().as_str(),
-                        );
+                        add_text(&mut doc, schema, "synonym", synonym.description().as_str())?;
                     }
                     fastobo::ast::TermClause::Xref(xref) => {
                         doc.add_field_value(schema.get_field("xref")?, ident_to_string(xref.id()));
@@ -167,6 +608,41 @@ impl Index {
     }
 }
 
+// Code for updating an `Index`.
+impl Index {
+    /// Add the local extension terms to the index in the `local` namespace.
+    ///
+    /// # Errors
+    ///
+    /// In the case that the index cannot be written.
+    pub fn add_local_terms(&self, local_terms: &LocalTerms) -> Result<(), anyhow::Error> {
+        let mut index_writer = self
+            .index
+            .writer(15_000_000)
+            .map_err(|e| anyhow::anyhow!("Error creating tantivy index writer: {}", e))?;
+        for term in local_terms.iter() {
+            let mut doc = tantivy::TantivyDocument::default();
+            doc.add_field_value(self.schema.get_field("term_id")?, term.id.as_str());
+            doc.add_field_value(self.schema.get_field("namespace")?, NAMESPACE_LOCAL);
+            add_text(&mut doc, &self.schema, "name", term.name.as_str())?;
+            if let Some(definition) = &term.definition {
+                add_text(&mut doc, &self.schema, "def", definition.as_str())?;
+            }
+            for synonym in &term.synonyms {
+                add_text(&mut doc, &self.schema, "synonym", synonym.as_str())?;
+            }
+            index_writer.add_document(doc).map_err(|e| {
+                anyhow::anyhow!("Error adding document to tantivy index writer: {}", e)
+            })?;
+        }
+        index_writer
+            .commit()
+            .map_err(|e| anyhow::anyhow!("Error committing tantivy index writer: {}", e))?;
+        self.reader.reload()?;
+        Ok(())
+    }
+}
+
 // Accessor code.
 impl Index {
     /// Get the HPO document.
@@ -188,4 +664,542 @@ impl Index {
     pub fn reader(&self) -> &tantivy::IndexReader {
         &self.reader
     }
+
+    /// Get the translated name and synonym fields for `lang`, if indexed.
+    pub fn translated_fields(&self, lang: &str) -> Option<(Field, Field)> {
+        let name = self.schema.get_field(&format!("name_{lang}")).ok()?;
+        let synonym = self.schema.get_field(&format!("synonym_{lang}")).ok()?;
+        Some((name, synonym))
+    }
+
+    /// Return the byte ranges of the words in `text` that match a word of `query`.
+    ///
+    /// The words are analyzed as in the text fields, or their stemmed copies if `stem`, and
+    /// a word matches if it starts with a query word, e.g., "hernia" for "hern".  Matches
+    /// by the fuzzy search with typos are not highlighted.
+    pub fn highlight(&self, query: &str, text: &str, stem: bool) -> Vec<Range<usize>> {
+        let tokenizer = if stem {
+            TOKENIZER_STEMMED
+        } else {
+            TOKENIZER_NORMALIZED
+        };
+        let Some(mut analyzer) = self.index.tokenizers().get(tokenizer) else {
+            return Vec::new();
+        };
+        let mut words = Vec::new();
+        {
+            let mut stream = analyzer.token_stream(query);
+            while stream.advance() {
+                words.push(stream.token().text.clone());
+            }
+        }
+        let mut result = Vec::new();
+        let mut stream = analyzer.token_stream(text);
+        while stream.advance() {
+            let token = stream.token();
+            if words
+                .iter()
+                .any(|word| token.text.starts_with(word.as_str()))
+            {
+                result.push(token.offset_from..token.offset_to);
+            }
+        }
+        result
+    }
+
+    /// Get the stemmed copies of the `name`, `def`, and `synonym` fields.
+    pub fn stemmed_fields(&self) -> Option<(Field, Field, Field)> {
+        let field = |name: &str| {
+            self.schema
+                .get_field(&format!("{name}{STEMMED_SUFFIX}"))
+                .ok()
+        };
+        Some((field("name")?, field("def")?, field("synonym")?))
+    }
+
+    /// Get the primary ID of the term that `alt_id` was merged into, if any.
+    pub fn primary_id(&self, alt_id: HpoTermId) -> Option<HpoTermId> {
+        self.alt_ids.get(&alt_id).copied()
+    }
+
+    /// Get the xrefs of the term with `term_id`, e.g., `UMLS:C0036572`.
+    pub fn xrefs(&self, term_id: HpoTermId) -> &[String] {
+        self.xrefs.get(&term_id).map_or(&[], Vec::as_slice)
+    }
+
+    /// Get the IDs of the terms having the xref `xref`, sorted.
+    pub fn terms_with_xref(&self, xref: &str) -> &[HpoTermId] {
+        self.xref_terms.get(xref).map_or(&[], Vec::as_slice)
+    }
+
+    /// Get the creation metadata and property values of the term with `term_id`, if any.
+    pub fn history(&self, term_id: HpoTermId) -> Option<&TermHistory> {
+        self.history.get(&term_id)
+    }
+
+    /// Get the IDs of the terms created after `date`, sorted.
+    pub fn terms_created_after(&self, date: chrono::NaiveDate) -> Vec<HpoTermId> {
+        let mut result = self
+            .history
+            .iter()
+            .filter(|(_, history)| history.created_after(date))
+            .map(|(term_id, _)| *term_id)
+            .collect::<Vec<_>>();
+        result.sort();
+        result
+    }
+
+    /// Get the byte offsets of the stanza of the term with `term_id` in the OBO file, if
+    /// the index was created with `from_path`.
+    pub fn stanza_offsets(&self, term_id: HpoTermId) -> Option<Range<u64>> {
+        let (_, stanzas) = self.stanzas.as_ref()?;
+        stanzas.get(&term_id).cloned()
+    }
+
+    /// Get the original stanza text of the term with `term_id` as read from the OBO file.
+    ///
+    /// Returns `None` if the term has no stanza or the index was not created with
+    /// `from_path`.
+    pub fn stanza(&self, term_id: HpoTermId) -> Option<&str> {
+        let (text, stanzas) = self.stanzas.as_ref()?;
+        let range = stanzas.get(&term_id)?;
+        text.get(usize::try_from(range.start).ok()?..usize::try_from(range.end).ok()?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use tantivy::schema::Value as _;
+
+    use crate::sources::translations::Translations;
+
+    #[test]
+    fn search_translated() -> Result<(), anyhow::Error> {
+        let hpo_doc = fastobo::from_str(
+            "format-version: 1.2\n\n\
+             [Term]\n\
+             id: HP:0001250\n\
+             name: Seizure\n",
+        )?;
+        let tmp_dir = temp_testdir::TempDir::default();
+        let path = tmp_dir.join("hp-de.babelon.tsv");
+        std::fs::write(
+            &path,
+            "source_language\ttranslation_language\tsubject_id\tpredicate_id\t\
+             source_value\ttranslation_value\ttranslation_status\n\
+             en\tde\tHP:0001250\trdfs:label\tSeizure\tKrampfanfall\tOFFICIAL\n",
+        )?;
+        let translations = Translations::from_paths(&[&path])?;
+
+        let index = super::Index::new(hpo_doc, &translations)?;
+
+        assert!(index.translated_fields("es").is_none());
+        let (field_name_de, field_synonym_de) =
+            index.translated_fields("de").expect("must be indexed");
+        let searcher = index.reader().searcher();
+        let query = tantivy::query::QueryParser::for_index(
+            index.index(),
+            vec![field_name_de, field_synonym_de],
+        )
+        .parse_query("krampfanfall")?;
+        let top_docs = searcher.search(&query, &tantivy::collector::TopDocs::with_limit(1))?;
+        assert_eq!(top_docs.len(), 1);
+        let doc = searcher.doc::<tantivy::TantivyDocument>(top_docs[0].1)?;
+        let field_term_id = index.schema().get_field("term_id")?;
+        assert_eq!(
+            doc.get_first(field_term_id)
+                .and_then(|value| value.as_value().as_str()),
+            Some("HP:0001250")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn normalize() {
+        assert_eq!(
+            super::normalize("Épilepsie GÉNÉRALISÉE"),
+            "epilepsie generalisee"
+        );
+        assert_eq!(super::normalize("Straße, ﬁbrosis"), "strasse fibrosis");
+    }
+
+    #[test]
+    fn search_normalized() -> Result<(), anyhow::Error> {
+        let hpo_doc = fastobo::from_str(
+            "format-version: 1.2\n\n\
+             [Term]\n\
+             id: HP:0001250\n\
+             name: Seizure\n",
+        )?;
+        let tmp_dir = temp_testdir::TempDir::default();
+        let path = tmp_dir.join("hp-fr.babelon.tsv");
+        std::fs::write(
+            &path,
+            "source_language\ttranslation_language\tsubject_id\tpredicate_id\t\
+             source_value\ttranslation_value\ttranslation_status\n\
+             en\tfr\tHP:0001250\trdfs:label\tSeizure\tÉpilepsie généralisée\tOFFICIAL\n",
+        )?;
+        let translations = Translations::from_paths(&[&path])?;
+
+        let index = super::Index::new(hpo_doc, &translations)?;
+
+        let (field_name_fr, _) = index.translated_fields("fr").expect("must be indexed");
+        let field_name = index.schema().get_field("name")?;
+        let searcher = index.reader().searcher();
+        let parser =
+            tantivy::query::QueryParser::for_index(index.index(), vec![field_name, field_name_fr]);
+        for query in ["SEIZURE", "Epilepsie généralisée", "epilepsie GENERALISEE"] {
+            let query = parser.parse_query(query)?;
+            let top_docs = searcher.search(&query, &tantivy::collector::TopDocs::with_limit(1))?;
+            assert_eq!(top_docs.len(), 1);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn search_stemmed() -> Result<(), anyhow::Error> {
+        let hpo_doc = fastobo::from_str(
+            "format-version: 1.2\n\n\
+             [Term]\n\
+             id: HP:0001250\n\
+             name: Seizure\n",
+        )?;
+
+        let index = super::Index::new(hpo_doc, &Translations::default())?;
+
+        let field_name = index.schema().get_field("name")?;
+        let (field_name_stemmed, _, _) = index.stemmed_fields().expect("must be indexed");
+        let searcher = index.reader().searcher();
+        let search = |field| -> Result<usize, anyhow::Error> {
+            let query = tantivy::query::QueryParser::for_index(index.index(), vec![field])
+                .parse_query("SEIZURES")?;
+            Ok(searcher
+                .search(&query, &tantivy::collector::TopDocs::with_limit(1))?
+                .len())
+        };
+        assert_eq!(search(field_name)?, 0);
+        assert_eq!(search(field_name_stemmed)?, 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn phrase_boost() -> Result<(), anyhow::Error> {
+        let hpo_doc = fastobo::from_str(
+            "format-version: 1.2\n\n\
+             [Term]\n\
+             id: HP:0001344\n\
+             name: Absent speech and delayed motor development\n\n\
+             [Term]\n\
+             id: HP:0002167\n\
+             name: Speech absent\n",
+        )?;
+
+        let index = super::Index::new(hpo_doc, &Translations::default())?;
+
+        let field_name = index.schema().get_field("name")?;
+        let field_term_id = index.schema().get_field("term_id")?;
+        let searcher = index.reader().searcher();
+        let first = |query: &dyn tantivy::query::Query| -> Result<String, anyhow::Error> {
+            let top_docs = searcher.search(query, &tantivy::collector::TopDocs::with_limit(2))?;
+            assert_eq!(top_docs.len(), 2);
+            let doc = searcher.doc::<tantivy::TantivyDocument>(top_docs[0].1)?;
+            Ok(doc
+                .get_first(field_term_id)
+                .and_then(|value| value.as_value().as_str())
+                .unwrap_or_default()
+                .to_string())
+        };
+        let query = tantivy::query::QueryParser::for_index(index.index(), vec![field_name])
+            .parse_query("absent speech")?;
+        assert_eq!(first(query.as_ref())?, "HP:0002167");
+        let query =
+            super::with_phrase_boost(index.index(), query, "absent speech", vec![field_name]);
+        assert_eq!(first(query.as_ref())?, "HP:0001344");
+
+        Ok(())
+    }
+
+    #[test]
+    fn highlight() -> Result<(), anyhow::Error> {
+        let hpo_doc = fastobo::from_str("format-version: 1.2\n")?;
+
+        let index = super::Index::new(hpo_doc, &Translations::default())?;
+
+        assert_eq!(
+            index.highlight("inguinal HERN", "Inguinal hernia, Hérnia", false),
+            vec![0..8, 9..15, 17..24]
+        );
+        assert!(index.highlight("seizures", "Seizure", false).is_empty());
+        assert_eq!(index.highlight("seizures", "Seizure", true), vec![0..7]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn stopwords() -> Result<(), anyhow::Error> {
+        let tmp_dir = temp_testdir::TempDir::default();
+        let path_obo = tmp_dir.join("hp.obo");
+        std::fs::write(
+            &path_obo,
+            "format-version: 1.2\n\n\
+             [Term]\n\
+             id: HP:0000598\n\
+             name: Abnormality of the ear\n",
+        )?;
+        let path_stopwords = tmp_dir.join("stopwords.txt");
+        std::fs::write(&path_stopwords, "# English\nOf\n\nThe\n")?;
+        let stopwords = super::read_stopwords(&path_stopwords)?;
+        assert_eq!(stopwords, vec!["of", "the"]);
+
+        for (stopwords, expected) in [(vec![], 1), (stopwords, 0)] {
+            let index = super::Index::from_path(&path_obo, &Translations::default(), &stopwords)?;
+            let field_name = index.schema().get_field("name")?;
+            let query = tantivy::query::QueryParser::for_index(index.index(), vec![field_name])
+                .parse_query("the")?;
+            let top_docs = index
+                .reader()
+                .searcher()
+                .search(&query, &tantivy::collector::TopDocs::with_limit(1))?;
+            assert_eq!(top_docs.len(), expected);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn primary_id() -> Result<(), anyhow::Error> {
+        let hpo_doc = fastobo::from_str(
+            "format-version: 1.2\n\n\
+             [Term]\n\
+             id: HP:0001250\n\
+             name: Seizure\n\
+             alt_id: HP:0002279\n\
+             alt_id: HP:0002391\n",
+        )?;
+
+        let index = super::Index::new(hpo_doc, &Translations::default())?;
+
+        assert_eq!(
+            index
+                .primary_id(hpo::HpoTermId::from_u32(2279))
+                .map(|term_id| term_id.to_string()),
+            Some("HP:0001250".to_string())
+        );
+        assert!(index.primary_id(hpo::HpoTermId::from_u32(1250)).is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn xrefs() -> Result<(), anyhow::Error> {
+        let hpo_doc = fastobo::from_str(
+            "format-version: 1.2\n\n\
+             [Term]\n\
+             id: HP:0001250\n\
+             name: Seizure\n\
+             xref: SNOMEDCT_US:91175000\n\
+             xref: UMLS:C0036572\n\n\
+             [Term]\n\
+             id: HP:0002069\n\
+             name: Bilateral tonic-clonic seizure\n\
+             xref: UMLS:C0036572\n",
+        )?;
+
+        let index = super::Index::new(hpo_doc, &Translations::default())?;
+
+        assert_eq!(
+            index.xrefs(hpo::HpoTermId::from_u32(1250)),
+            ["SNOMEDCT_US:91175000", "UMLS:C0036572"]
+        );
+        assert!(index.xrefs(hpo::HpoTermId::from_u32(1)).is_empty());
+        assert_eq!(
+            index.terms_with_xref("UMLS:C0036572"),
+            [
+                hpo::HpoTermId::from_u32(1250),
+                hpo::HpoTermId::from_u32(2069)
+            ]
+        );
+        assert!(index.terms_with_xref("UMLS:C0000000").is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn add_local_terms() -> Result<(), anyhow::Error> {
+        let hpo_doc = fastobo::from_str(
+            "format-version: 1.2\n\n\
+             [Term]\n\
+             id: HP:0001250\n\
+             name: Seizure\n",
+        )?;
+        let mut builder = hpo::builder::Builder::new();
+        builder.new_term("All", 1u32);
+        builder.new_term("Seizure", 1250u32);
+        let mut builder = builder.terms_complete();
+        builder.add_parent(1u32, 1250u32)?;
+        let ontology = builder
+            .connect_all_terms()
+            .calculate_information_content()?
+            .build_minimal();
+        let local_terms = crate::sources::local_terms::LocalTerms::from_doc(
+            &fastobo::from_str(
+                "format-version: 1.2\n\n\
+                 [Term]\n\
+                 id: HP:LAB:0001\n\
+                 name: Lab seizure\n\
+                 is_a: HP:0001250\n",
+            )?,
+            &ontology,
+        )?;
+
+        let index = super::Index::new(hpo_doc, &Translations::default())?;
+        index.add_local_terms(&local_terms)?;
+
+        let searcher = index.reader().searcher();
+        let field_name = index.schema().get_field("name")?;
+        let query = tantivy::query::QueryParser::for_index(index.index(), vec![field_name])
+            .parse_query("seizure")?;
+        let top_docs = searcher.search(&query, &tantivy::collector::TopDocs::with_limit(10))?;
+        let namespaces = top_docs
+            .iter()
+            .map(|(_, address)| {
+                let doc = searcher.doc::<tantivy::TantivyDocument>(*address)?;
+                Ok(doc
+                    .get_first(index.schema().get_field("namespace")?)
+                    .and_then(|value| value.as_value().as_str())
+                    .map(ToString::to_string))
+            })
+            .collect::<Result<std::collections::BTreeSet<_>, anyhow::Error>>()?;
+        assert_eq!(
+            namespaces,
+            [Some("hpo".to_string()), Some("local".to_string())].into()
+        );
+
+        Ok(())
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn collect_alt_ids_fuzz(id in crate::fuzz::term_id(), alt_id in crate::fuzz::term_id()) {
+            let Ok(hpo_doc) = fastobo::from_str(format!(
+                "format-version: 1.2\n\n[Term]\nid: {id}\nalt_id: {alt_id}\n"
+            )) else {
+                return Ok(());
+            };
+
+            // Only well-formed pairs of IDs are collected.
+            let alt_ids = super::Index::collect_alt_ids(&hpo_doc);
+            proptest::prop_assert!(alt_ids.len() <= 1);
+            for (alt_id, id) in alt_ids {
+                proptest::prop_assert!(crate::common::parse_term_id(&alt_id.to_string()).is_ok());
+                proptest::prop_assert!(crate::common::parse_term_id(&id.to_string()).is_ok());
+            }
+        }
+    }
+
+    #[test]
+    fn stanza() -> Result<(), anyhow::Error> {
+        let text = "format-version: 1.2\n\n\
+                    [Term]\n\
+                    id: HP:0001250\n\
+                    name: Seizure\n\n\
+                    [Typedef]\n\
+                    id: part_of\n\n\
+                    [Term]\n\
+                    id: HP:0002616\n\
+                    name: Aortic root aneurysm\n";
+        let tmp_dir = temp_testdir::TempDir::default();
+        let path = tmp_dir.join("hp.obo");
+        std::fs::write(&path, text)?;
+
+        let index = super::Index::from_path(&path, &Translations::default(), &[])?;
+        // Changes to the file after loading do not affect the stanzas.
+        std::fs::write(&path, "format-version: 1.2\n")?;
+
+        assert_eq!(
+            index.stanza(hpo::HpoTermId::from_u32(1250)),
+            Some("[Term]\nid: HP:0001250\nname: Seizure\n")
+        );
+        assert_eq!(
+            index.stanza(hpo::HpoTermId::from_u32(2616)),
+            Some("[Term]\nid: HP:0002616\nname: Aortic root aneurysm\n")
+        );
+        assert_eq!(index.stanza(hpo::HpoTermId::from_u32(118)), None);
+        assert_eq!(super::stanza_offsets(text).len(), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn history() -> Result<(), anyhow::Error> {
+        let hpo_doc = fastobo::from_str(
+            "format-version: 1.2\n\n\
+             [Term]\n\
+             id: HP:0001250\n\
+             name: Seizure\n\
+             property_value: IAO:0000233 \"https://github.com/obophenotype/issues/1\" xsd:anyURI\n\
+             created_by: https://orcid.org/0000-0001-5208-3432\n\
+             creation_date: 2021-05-20T12:00:00Z\n\n\
+             [Term]\n\
+             id: HP:0002616\n\
+             name: Aortic root aneurysm\n\
+             creation_date: 2008-02-27T02:20:00Z\n\n\
+             [Term]\n\
+             id: HP:0000118\n\
+             name: Phenotypic abnormality\n",
+        )?;
+        let index = super::Index::new(hpo_doc, &Translations::default())?;
+
+        let history = index
+            .history(hpo::HpoTermId::from_u32(1250))
+            .expect("term must have history");
+        assert_eq!(
+            history.creation_date.as_deref(),
+            Some("2021-05-20T12:00:00Z")
+        );
+        assert_eq!(
+            history.created_by.as_deref(),
+            Some("https://orcid.org/0000-0001-5208-3432")
+        );
+        assert_eq!(
+            history.property_values,
+            vec![super::TermPropertyValue {
+                property: "IAO:0000233".to_string(),
+                value: "https://github.com/obophenotype/issues/1".to_string(),
+            }]
+        );
+        assert!(index.history(hpo::HpoTermId::from_u32(118)).is_none());
+
+        let date = |date: &str| chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d");
+        assert_eq!(
+            index.terms_created_after(date("2020-01-01")?),
+            vec![hpo::HpoTermId::from_u32(1250)]
+        );
+        assert_eq!(index.terms_created_after(date("2008-02-27")?).len(), 1);
+        assert_eq!(index.terms_created_after(date("2008-02-26")?).len(), 2);
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case(Some("2008-02-27T02:20:00Z"), Some("2008-02-27"))]
+    #[case(Some("2008-02-27T23:20:00-05:00"), Some("2008-02-27"))]
+    #[case(Some("2008-02-27"), Some("2008-02-27"))]
+    #[case(Some("2008-02-30"), None)]
+    #[case(Some("20080227"), None)]
+    #[case(None, None)]
+    fn creation_day(#[case] creation_date: Option<&str>, #[case] expected: Option<&str>) {
+        let history = super::TermHistory {
+            creation_date: creation_date.map(str::to_string),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            history.creation_day().map(|day| day.to_string()),
+            expected.map(str::to_string)
+        );
+    }
 }
@@ -0,0 +1,30 @@
+//! This is the `viguno` library with the ontology loading, search, and scoring behind the
+//! `viguno` app.
+#![deny(clippy::pedantic)]
+#![allow(clippy::must_use_candidate)]
+#![allow(clippy::module_name_repetitions)]
+#![warn(missing_docs)]
+
+pub mod algos;
+#[cfg(test)]
+mod alloc_count;
+pub mod cache;
+pub mod common;
+pub mod compat;
+pub mod convert;
+pub mod data;
+pub mod devtools;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(test)]
+mod fuzz;
+pub mod index;
+pub mod network;
+pub mod pbs;
+pub mod precompute;
+pub mod progress;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod query;
+pub mod server;
+pub mod sources;
@@ -4,15 +4,8 @@
 #![allow(clippy::module_name_repetitions)]
 #![warn(missing_docs)]
 
-pub mod algos;
-pub mod common;
-pub mod convert;
-pub mod index;
-pub mod pbs;
-pub mod query;
-pub mod server;
-
 use clap::{Parser, Subcommand};
+use viguno::{common, compat, convert, data, devtools, precompute, query, server};
 
 /// CLI parser based on clap.
 #[derive(Debug, Parser)]
@@ -38,9 +31,12 @@ struct Cli {
 #[allow(clippy::large_enum_variant)]
 #[derive(Debug, Subcommand)]
 enum Commands {
-    Convert(crate::convert::Args),
-    Query(crate::query::Args),
+    Convert(viguno::convert::Args),
+    Query(viguno::query::Args),
     Server(Server),
+    Data(Data),
+    Devtools(Devtools),
+    Precompute(Precompute),
 }
 
 /// Clap sub command below "server".
@@ -51,17 +47,82 @@ struct Server {
     pub command: ServerSubCommands,
 }
 
+/// Clap sub command below "data".
+#[derive(Debug, Parser)]
+struct Data {
+    /// The sub command to run
+    #[clap(subcommand)]
+    pub command: DataSubCommands,
+}
+
+/// Sub commands for "data".
+#[derive(Debug, Subcommand)]
+enum DataSubCommands {
+    /// Download and assemble the HPO data directory.
+    Download(viguno::data::download::Args),
+    /// Write the checksum manifest of the HPO data directory.
+    Manifest(viguno::data::manifest::Args),
+    /// Verify the HPO data directory against its checksum manifest.
+    Verify(viguno::data::verify::Args),
+    /// Convert a binary artifact to the current schema.
+    Migrate(viguno::data::artifact::Args),
+    /// Fetch gene panels from `PanelApp` into a TSV file.
+    SyncPanelapp(viguno::data::panelapp::Args),
+}
+
+/// Clap sub command below "devtools".
+#[derive(Debug, Parser)]
+struct Devtools {
+    /// The sub command to run
+    #[clap(subcommand)]
+    pub command: DevtoolsSubCommands,
+}
+
+/// Sub commands for "devtools".
+#[derive(Debug, Subcommand)]
+enum DevtoolsSubCommands {
+    /// Compare two HPO releases.
+    DiffReleases(viguno::devtools::diff_releases::Args),
+    /// Export the HPO and annotations as SQL for `SQLite` or `DuckDB`.
+    ExportSql(viguno::devtools::export_sql::Args),
+    /// Export the annotation edge tables as partitioned Parquet files.
+    ExportParquet(viguno::devtools::export_parquet::Args),
+    /// Compare the gene ranking of a phenotype set between two HPO releases.
+    RankDelta(viguno::devtools::rank_delta::Args),
+}
+
+/// Clap sub command below "precompute".
+#[derive(Debug, Parser)]
+struct Precompute {
+    /// The sub command to run
+    #[clap(subcommand)]
+    pub command: PrecomputeSubCommands,
+}
+
+/// Sub commands for "precompute".
+#[derive(Debug, Subcommand)]
+enum PrecomputeSubCommands {
+    /// Precompute the score distributions for the p-values.
+    SimPvalues(viguno::precompute::sim_pvalues::Args),
+}
+
 /// Sub commands for "server".
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Subcommand)]
 enum ServerSubCommands {
     /// Run the server.
-    Run(crate::server::run::Args),
+    Run(viguno::server::run::Args),
     /// Dump the schema.
-    Schema(crate::server::schema::Args),
+    Schema(viguno::server::schema::Args),
+    /// Load the data and run canary queries.
+    Selftest(viguno::server::selftest::Args),
+    /// Replay recorded requests and diff the responses.
+    Replay(viguno::server::replay::Args),
 }
 
 fn main() -> Result<(), anyhow::Error> {
-    let cli = Cli::parse();
+    let compat = compat::rewrite(std::env::args_os(), |name| std::env::var(name).ok());
+    let cli = Cli::parse_from(&compat.args);
 
     // Build a tracing subscriber according to the configuration in `cli.common`.
     let collector = tracing_subscriber::fmt()
@@ -81,6 +142,9 @@ fn main() -> Result<(), anyhow::Error> {
 
     // Install collector and go into sub commands.
     tracing::subscriber::with_default(collector, || {
+        for warning in &compat.warnings {
+            tracing::warn!("{}", warning);
+        }
         match &cli.command {
             Commands::Convert(args) => {
                 convert::run(&cli.common, args)?;
@@ -95,6 +159,48 @@ fn main() -> Result<(), anyhow::Error> {
                 ServerSubCommands::Schema(args) => {
                     server::schema::run(&cli.common, args)?;
                 }
+                ServerSubCommands::Selftest(args) => {
+                    server::selftest::run(&cli.common, args)?;
+                }
+                ServerSubCommands::Replay(args) => {
+                    server::replay::run(&cli.common, args)?;
+                }
+            },
+            Commands::Data(cmd_data) => match &cmd_data.command {
+                DataSubCommands::Download(args) => {
+                    data::download::run(&cli.common, args)?;
+                }
+                DataSubCommands::Manifest(args) => {
+                    data::manifest::run(&cli.common, args)?;
+                }
+                DataSubCommands::Verify(args) => {
+                    data::verify::run(&cli.common, args)?;
+                }
+                DataSubCommands::Migrate(args) => {
+                    data::artifact::run(&cli.common, args)?;
+                }
+                DataSubCommands::SyncPanelapp(args) => {
+                    data::panelapp::run(&cli.common, args)?;
+                }
+            },
+            Commands::Devtools(cmd_devtools) => match &cmd_devtools.command {
+                DevtoolsSubCommands::DiffReleases(args) => {
+                    devtools::diff_releases::run(&cli.common, args)?;
+                }
+                DevtoolsSubCommands::ExportSql(args) => {
+                    devtools::export_sql::run(&cli.common, args)?;
+                }
+                DevtoolsSubCommands::ExportParquet(args) => {
+                    devtools::export_parquet::run(&cli.common, args)?;
+                }
+                DevtoolsSubCommands::RankDelta(args) => {
+                    devtools::rank_delta::run(&cli.common, args)?;
+                }
+            },
+            Commands::Precompute(cmd_precompute) => match &cmd_precompute.command {
+                PrecomputeSubCommands::SimPvalues(args) => {
+                    precompute::sim_pvalues::run(&cli.common, args)?;
+                }
             },
         }
 
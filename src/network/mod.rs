@@ -0,0 +1,177 @@
+//! Gene interaction networks for network-boosted prioritization.
+
+pub mod rwr;
+
+use std::collections::HashMap;
+
+/// Record of the network edge list TSV file.
+#[derive(Debug, Clone, serde::Deserialize)]
+struct EdgeEntry {
+    /// NCBI gene ID of the first interaction partner.
+    ncbi_gene_id_a: u32,
+    /// NCBI gene ID of the second interaction partner.
+    ncbi_gene_id_b: u32,
+    /// The edge weight, e.g., the STRING combined score.
+    #[serde(default = "_default_weight")]
+    weight: f32,
+}
+
+/// Default edge weight if the file has no `weight` column.
+fn _default_weight() -> f32 {
+    1.0
+}
+
+/// Undirected weighted network between genes, e.g., protein-protein interactions.
+#[derive(Debug, Clone, Default)]
+pub struct Network {
+    /// The NCBI gene IDs of the nodes.
+    genes: Vec<u32>,
+    /// Map from NCBI gene ID to node index.
+    index: HashMap<u32, usize>,
+    /// Weighted neighbors of each node by node index.
+    neighbors: Vec<Vec<(usize, f32)>>,
+}
+
+impl Network {
+    /// Build the network from `(gene_a, gene_b, weight)` edges.
+    ///
+    /// Edges are undirected, self loops and edges without positive weight are ignored.
+    pub fn from_edges<I: IntoIterator<Item = (u32, u32, f32)>>(edges: I) -> Self {
+        let mut result = Self::default();
+        for (gene_a, gene_b, weight) in edges {
+            if gene_a == gene_b || weight <= 0.0 {
+                continue;
+            }
+            let a = result.node(gene_a);
+            let b = result.node(gene_b);
+            result.neighbors[a].push((b, weight));
+            result.neighbors[b].push((a, weight));
+        }
+        result
+    }
+
+    /// Return the index of the node for `gene`, adding it if necessary.
+    fn node(&mut self, gene: u32) -> usize {
+        *self.index.entry(gene).or_insert_with(|| {
+            self.genes.push(gene);
+            self.neighbors.push(Vec::new());
+            self.genes.len() - 1
+        })
+    }
+
+    /// Load the network from a TSV edge list.
+    ///
+    /// The file must have a header and the columns `ncbi_gene_id_a` and
+    /// `ncbi_gene_id_b`, and optionally `weight`.
+    ///
+    /// # Errors
+    ///
+    /// In the case that the file could not be read.
+    pub fn from_path<P: AsRef<std::path::Path>>(path: P) -> Result<Self, anyhow::Error> {
+        let mut rdr = csv::ReaderBuilder::new()
+            .delimiter(b'\t')
+            .has_headers(true)
+            .from_path(path.as_ref())?;
+        let mut edges = Vec::new();
+        for result in rdr.deserialize() {
+            let entry: EdgeEntry = result?;
+            edges.push((entry.ncbi_gene_id_a, entry.ncbi_gene_id_b, entry.weight));
+        }
+        Ok(Self::from_edges(edges))
+    }
+
+    /// Return the number of genes in the network.
+    pub fn len(&self) -> usize {
+        self.genes.len()
+    }
+
+    /// Return whether the network is empty.
+    pub fn is_empty(&self) -> bool {
+        self.genes.is_empty()
+    }
+
+    /// Return the neighbors of `gene` with the edge weights.
+    pub fn neighbors(&self, gene: u32) -> impl Iterator<Item = (u32, f32)> + '_ {
+        self.index
+            .get(&gene)
+            .map(|&node| self.neighbors[node].as_slice())
+            .unwrap_or_default()
+            .iter()
+            .map(|&(neighbor, weight)| (self.genes[neighbor], weight))
+    }
+
+    /// Diffuse `scores` by one step to the neighbors of `gene`.
+    ///
+    /// Returns the weighted mean of the neighbors' scores, zero for genes without
+    /// neighbors.
+    pub fn neighbor_score<F: FnMut(u32) -> f32>(&self, gene: u32, mut scores: F) -> f32 {
+        let (weighted_sum, weight_sum) = self.neighbors(gene).fold(
+            (0.0, 0.0),
+            |(weighted_sum, weight_sum), (neighbor, weight)| {
+                (
+                    weighted_sum + weight * scores(neighbor),
+                    weight_sum + weight,
+                )
+            },
+        );
+        if weight_sum > 0.0 {
+            weighted_sum / weight_sum
+        } else {
+            0.0
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Network;
+
+    /// Network with the edges 1-2 (weight 1), 1-3 (weight 3), and 4-4 (ignored).
+    fn small_network() -> Network {
+        Network::from_edges([(1, 2, 1.0), (1, 3, 3.0), (4, 4, 1.0)])
+    }
+
+    #[test]
+    fn neighbors() {
+        let network = small_network();
+
+        assert_eq!(network.len(), 3);
+        assert_eq!(
+            network.neighbors(1).collect::<Vec<_>>(),
+            vec![(2, 1.0), (3, 3.0)]
+        );
+        assert_eq!(network.neighbors(2).collect::<Vec<_>>(), vec![(1, 1.0)]);
+        assert_eq!(network.neighbors(4).count(), 0);
+    }
+
+    #[test]
+    fn neighbor_score() {
+        let network = small_network();
+        let scores = |gene| match gene {
+            2 => 1.0,
+            3 => 2.0,
+            _ => 0.0,
+        };
+
+        assert!((network.neighbor_score(1, scores) - 7.0 / 4.0).abs() < 1e-6);
+        assert!(network.neighbor_score(2, scores).abs() < 1e-6);
+        assert!(network.neighbor_score(5, scores).abs() < 1e-6);
+    }
+
+    #[test]
+    fn from_path() -> Result<(), anyhow::Error> {
+        let tmp_dir = temp_testdir::TempDir::default();
+        let path = tmp_dir.join("network.tsv");
+        std::fs::write(&path, "ncbi_gene_id_a\tncbi_gene_id_b\n1\t2\n2\t3\n")?;
+
+        let network = Network::from_path(&path)?;
+
+        assert_eq!(network.len(), 3);
+        assert_eq!(
+            network.neighbors(2).collect::<Vec<_>>(),
+            vec![(1, 1.0), (3, 1.0)]
+        );
+
+        Ok(())
+    }
+}
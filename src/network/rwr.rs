@@ -0,0 +1,233 @@
+//! Random walk with restart on sparse matrices for score diffusion.
+
+use std::collections::BTreeMap;
+
+use super::Network;
+
+/// Square sparse matrix in compressed sparse row (CSR) format.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SparseMatrix {
+    /// The number of rows and columns.
+    n: usize,
+    /// Offsets of each row's entries in `cols` and `values`, of length `n + 1`.
+    row_offsets: Vec<usize>,
+    /// The column index of each entry.
+    cols: Vec<usize>,
+    /// The value of each entry.
+    values: Vec<f32>,
+}
+
+impl SparseMatrix {
+    /// Build an `n x n` matrix from `(row, col, value)` triplets.
+    ///
+    /// Values of duplicate triplets are summed.
+    ///
+    /// # Panics
+    ///
+    /// If a row or column index is not below `n`.
+    pub fn from_triplets(n: usize, triplets: &[(usize, usize, f32)]) -> Self {
+        let mut rows: Vec<Vec<(usize, f32)>> = vec![Vec::new(); n];
+        for &(row, col, value) in triplets {
+            assert!(row < n && col < n, "index ({row}, {col}) out of bounds");
+            rows[row].push((col, value));
+        }
+
+        let mut result = Self {
+            n,
+            row_offsets: vec![0],
+            ..Default::default()
+        };
+        for mut row in rows {
+            row.sort_by_key(|(col, _)| *col);
+            for (col, value) in row {
+                if result.cols.len() > *result.row_offsets.last().expect("never empty")
+                    && result.cols.last() == Some(&col)
+                {
+                    *result.values.last_mut().expect("checked above") += value;
+                } else {
+                    result.cols.push(col);
+                    result.values.push(value);
+                }
+            }
+            result.row_offsets.push(result.cols.len());
+        }
+        result
+    }
+
+    /// Return the number of rows and columns.
+    pub fn dim(&self) -> usize {
+        self.n
+    }
+
+    /// Compute the matrix-vector product `self * x`.
+    ///
+    /// # Panics
+    ///
+    /// If the length of `x` does not match the dimension of the matrix.
+    pub fn mul_vec(&self, x: &[f32]) -> Vec<f32> {
+        assert_eq!(x.len(), self.n, "dimension mismatch");
+        self.row_offsets
+            .windows(2)
+            .map(|range| {
+                (range[0]..range[1])
+                    .map(|i| self.values[i] * x[self.cols[i]])
+                    .sum()
+            })
+            .collect()
+    }
+}
+
+/// Parameters of the random walk with restart.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RwrParams {
+    /// Probability of restarting at the seeds in each step.
+    pub restart: f32,
+    /// The walk has converged when the L1 distance between steps is below this value.
+    pub tolerance: f32,
+    /// Maximal number of iterations.
+    pub max_iterations: usize,
+}
+
+impl Default for RwrParams {
+    fn default() -> Self {
+        Self {
+            restart: 0.7,
+            tolerance: 1e-6,
+            max_iterations: 100,
+        }
+    }
+}
+
+/// Compute the stationary distribution of a random walk with restart.
+///
+/// Iterates `p = (1 - restart) * transition * p + restart * p0` where `p0` are the
+/// `seeds` normalized to sum to one.  The `transition` matrix must be column-stochastic.
+/// Returns all zeroes if the seeds sum to zero.
+///
+/// # Panics
+///
+/// If the length of `seeds` does not match the dimension of `transition`.
+pub fn random_walk_with_restart(
+    transition: &SparseMatrix,
+    seeds: &[f32],
+    params: &RwrParams,
+) -> Vec<f32> {
+    assert_eq!(seeds.len(), transition.dim(), "dimension mismatch");
+    let total: f32 = seeds.iter().sum();
+    if total <= 0.0 {
+        return vec![0.0; seeds.len()];
+    }
+    let p0 = seeds.iter().map(|seed| seed / total).collect::<Vec<_>>();
+
+    let mut p = p0.clone();
+    for _ in 0..params.max_iterations {
+        let next = transition
+            .mul_vec(&p)
+            .iter()
+            .zip(&p0)
+            .map(|(walk, restart)| (1.0 - params.restart) * walk + params.restart * restart)
+            .collect::<Vec<_>>();
+        let delta: f32 = next.iter().zip(&p).map(|(a, b)| (a - b).abs()).sum();
+        p = next;
+        if delta < params.tolerance {
+            break;
+        }
+    }
+    p
+}
+
+impl Network {
+    /// Return the column-stochastic transition matrix of the network by node index.
+    ///
+    /// The weights of the edges of each node are normalized to sum to one.
+    pub fn transition_matrix(&self) -> SparseMatrix {
+        let mut triplets = Vec::new();
+        for (col, neighbors) in self.neighbors.iter().enumerate() {
+            let degree: f32 = neighbors.iter().map(|(_, weight)| weight).sum();
+            for &(row, weight) in neighbors {
+                triplets.push((row, col, weight / degree));
+            }
+        }
+        SparseMatrix::from_triplets(self.len(), &triplets)
+    }
+
+    /// Diffuse the seed scores of genes over the network by random walk with restart.
+    ///
+    /// Seeds for genes that are not in the network are ignored.  Returns the
+    /// stationary probability of each gene in the network.
+    pub fn random_walk_with_restart(
+        &self,
+        seeds: &BTreeMap<u32, f32>,
+        params: &RwrParams,
+    ) -> BTreeMap<u32, f32> {
+        let mut p0 = vec![0.0; self.len()];
+        for (gene, score) in seeds {
+            if let Some(&node) = self.index.get(gene) {
+                p0[node] = *score;
+            }
+        }
+        let p = random_walk_with_restart(&self.transition_matrix(), &p0, params);
+        self.genes.iter().copied().zip(p).collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+
+    use super::{RwrParams, SparseMatrix};
+    use crate::network::Network;
+
+    /// Parameters with restart probability 1/2 and tight tolerance.
+    fn params() -> RwrParams {
+        RwrParams {
+            restart: 0.5,
+            tolerance: 1e-9,
+            max_iterations: 1000,
+        }
+    }
+
+    #[test]
+    fn sparse_mul_vec() {
+        // [[1, 0, 2], [0, 0, 0], [0, 3, 0]] with the duplicate (0, 2) summed up.
+        let matrix =
+            SparseMatrix::from_triplets(3, &[(2, 1, 3.0), (0, 0, 1.0), (0, 2, 1.5), (0, 2, 0.5)]);
+
+        assert_eq!(matrix.mul_vec(&[1.0, 2.0, 3.0]), vec![7.0, 0.0, 6.0]);
+    }
+
+    #[test]
+    fn rwr_two_nodes() {
+        // p1 = 1/2 p2 + 1/2 and p2 = 1/2 p1, so p1 = 2/3 and p2 = 1/3.
+        let network = Network::from_edges([(1, 2, 1.0)]);
+        let seeds = BTreeMap::from([(1, 1.0)]);
+
+        let p = network.random_walk_with_restart(&seeds, &params());
+
+        assert!((p[&1] - 2.0 / 3.0).abs() < 1e-5);
+        assert!((p[&2] - 1.0 / 3.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn rwr_path() {
+        // For the path 1-2-3 with seed 1, p1 = 7/12, p2 = 4/12, and p3 = 1/12.
+        let network = Network::from_edges([(1, 2, 1.0), (2, 3, 1.0)]);
+        let seeds = BTreeMap::from([(1, 5.0), (4, 1.0)]);
+
+        let p = network.random_walk_with_restart(&seeds, &params());
+
+        assert!((p[&1] - 7.0 / 12.0).abs() < 1e-5);
+        assert!((p[&2] - 4.0 / 12.0).abs() < 1e-5);
+        assert!((p[&3] - 1.0 / 12.0).abs() < 1e-5);
+        assert!(!p.contains_key(&4));
+    }
+
+    #[test]
+    fn rwr_no_seeds() {
+        let network = Network::from_edges([(1, 2, 1.0)]);
+
+        let p = network.random_walk_with_restart(&BTreeMap::new(), &params());
+
+        assert!(p.values().all(|score| *score == 0.0));
+    }
+}
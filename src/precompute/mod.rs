@@ -0,0 +1,4 @@
+//! Precomputation of expensive artifacts on a build machine, e.g., to mount them read-only
+//! into the server.
+
+pub mod sim_pvalues;
@@ -0,0 +1,126 @@
+//! Precompute the simulated score distributions for the p-values of
+//! `/api/v1/hpo/sim/term-gene` into an artifact file for `--path-sim-pvalues`.
+
+use std::str::FromStr;
+
+use crate::{
+    algos::simulation::{self, SimulationParams, MAX_TERM_COUNT},
+    common::load_hpo,
+};
+
+/// Numbers of query terms to simulate, e.g., `1..10` or `3`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TermCounts(pub Vec<usize>);
+
+impl FromStr for TermCounts {
+    type Err = anyhow::Error;
+
+    /// Parse an inclusive range `first..last` or a single number.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse = |value: &str| {
+            value
+                .trim()
+                .parse::<usize>()
+                .map_err(|e| anyhow::anyhow!("invalid term count {:?}: {}", value, e))
+        };
+        let (first, last) = match s.split_once("..") {
+            Some((first, last)) => (parse(first)?, parse(last)?),
+            None => (parse(s)?, parse(s)?),
+        };
+        if first == 0 || first > last || last > MAX_TERM_COUNT {
+            anyhow::bail!("term counts must be from 1 to {}: {}", MAX_TERM_COUNT, s);
+        }
+        Ok(Self((first..=last).collect()))
+    }
+}
+
+/// Command line arguments for `precompute sim-pvalues` sub command.
+#[derive(clap::Parser, Debug)]
+#[command(
+    author,
+    version,
+    about = "Precompute the score distributions for p-values",
+    long_about = None
+)]
+pub struct Args {
+    /// Path to the directory with the HPO files.
+    #[arg(long, required = true)]
+    pub path_hpo_dir: std::path::PathBuf,
+    /// Number of random queries per number of terms.
+    #[arg(long, default_value_t = simulation::DEFAULT_SIMULATIONS)]
+    pub n_samples: usize,
+    /// Numbers of query terms to simulate, an inclusive range, e.g., `1..10`.
+    #[arg(long, default_value = "1..10")]
+    pub term_counts: TermCounts,
+    /// Seed of the random queries, the default seed of the server when loaded.
+    #[arg(long, default_value_t = simulation::DEFAULT_SEED)]
+    pub seed: u64,
+    /// Path to the output file.
+    #[arg(long, required = true)]
+    pub out: std::path::PathBuf,
+}
+
+/// Main entry point for `precompute sim-pvalues` sub command.
+///
+/// # Errors
+///
+/// In the case that the HPO cannot be loaded or the output cannot be written.
+pub fn run(args_common: &crate::common::Args, args: &Args) -> Result<(), anyhow::Error> {
+    tracing::info!("args_common = {:?}", &args_common);
+    tracing::info!("args = {:?}", &args);
+
+    tracing::info!("Loading HPO...");
+    let before_loading = std::time::Instant::now();
+    let ontology = load_hpo(&args.path_hpo_dir)?;
+    tracing::info!("...done loading HPO in {:?}", before_loading.elapsed());
+
+    let mut results = Vec::new();
+    for term_count in &args.term_counts.0 {
+        tracing::info!(
+            "Simulating {} queries of {} terms...",
+            args.n_samples,
+            term_count
+        );
+        let before_simulating = std::time::Instant::now();
+        results.extend(simulation::simulate(
+            &ontology,
+            SimulationParams {
+                term_count: *term_count,
+                n_simulations: args.n_samples,
+                seed: args.seed,
+            },
+        ));
+        tracing::info!("...done in {:?}", before_simulating.elapsed());
+    }
+
+    let bytes = simulation::encode_artifact(
+        &ontology.hpo_version(),
+        args.n_samples,
+        args.seed,
+        &args.term_counts.0,
+        &results,
+    );
+    std::fs::write(&args.out, bytes)
+        .map_err(|e| anyhow::anyhow!("could not write {:?}: {}", args.out, e))?;
+    tracing::info!("Wrote score distributions to {:?}", args.out);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    #[rstest::rstest]
+    #[case("1..10", Some(vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10]))]
+    #[case("3", Some(vec![3]))]
+    #[case("2..4", Some(vec![2, 3, 4]))]
+    #[case("0..3", None)]
+    #[case("4..2", None)]
+    #[case("1..11", None)]
+    #[case("x", None)]
+    fn term_counts(#[case] s: &str, #[case] expected: Option<Vec<usize>>) {
+        assert_eq!(
+            s.parse::<super::TermCounts>().ok().map(|counts| counts.0),
+            expected
+        );
+    }
+}
@@ -0,0 +1,336 @@
+//! Progress reporting for the loading stages.
+//!
+//! On the command line, each stage is shown as a spinner below an overall bar.  In
+//! server mode, or if stderr is not a terminal, the start and end of each stage is logged
+//! with structured fields (`stage`, `completed`, `elapsed_ms`).
+
+use std::{
+    io::IsTerminal as _,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+
+/// How to report the progress.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// Progress bars on stderr.
+    Bars,
+    /// Structured log messages.
+    Logs,
+}
+
+impl Mode {
+    /// Return the mode for command line use, bars if stderr is a terminal.
+    pub fn cli() -> Self {
+        if std::io::stderr().is_terminal() {
+            Mode::Bars
+        } else {
+            Mode::Logs
+        }
+    }
+}
+
+/// Progress of a number of loading stages, possibly running concurrently.
+#[derive(Debug)]
+pub struct Progress {
+    /// The bars of the stages, for `Mode::Bars`.
+    multi: MultiProgress,
+    /// The overall bar, for `Mode::Bars`.
+    overall: Option<ProgressBar>,
+    /// The number of completed stages.
+    completed: AtomicUsize,
+}
+
+impl Progress {
+    /// Create new progress reporting in `mode`.
+    ///
+    /// # Panics
+    ///
+    /// In the case that the bar template is invalid.
+    pub fn new(mode: Mode) -> Self {
+        let multi = MultiProgress::new();
+        let overall = (mode == Mode::Bars).then(|| {
+            multi.add(
+                ProgressBar::new(0).with_style(
+                    ProgressStyle::with_template("{wide_bar} {pos}/{len} stages [{elapsed}]")
+                        .expect("invalid template"),
+                ),
+            )
+        });
+        Self {
+            multi,
+            overall,
+            completed: AtomicUsize::new(0),
+        }
+    }
+
+    /// Start the stage `what`.
+    ///
+    /// # Panics
+    ///
+    /// In the case that the spinner template is invalid.
+    pub fn stage(&self, what: &'static str) -> Stage<'_> {
+        let bar = if let Some(overall) = &self.overall {
+            overall.inc_length(1);
+            let bar = self.multi.add(
+                ProgressBar::new_spinner()
+                    .with_style(
+                        ProgressStyle::with_template("  {spinner} {msg} [{elapsed}]")
+                            .expect("invalid template"),
+                    )
+                    .with_message(format!("Loading {what}...")),
+            );
+            bar.enable_steady_tick(Duration::from_millis(100));
+            Some(bar)
+        } else {
+            tracing::info!(stage = what, "Loading {}...", what);
+            None
+        };
+        Stage {
+            progress: self,
+            what,
+            bar,
+            started: Instant::now(),
+        }
+    }
+
+    /// Return the number of completed stages, including the failed ones.
+    pub fn completed(&self) -> usize {
+        self.completed.load(Ordering::Relaxed)
+    }
+
+    /// Mark one more stage as completed, returning the number of completed stages.
+    fn complete(&self) -> usize {
+        if let Some(overall) = &self.overall {
+            overall.inc(1);
+        }
+        self.completed.fetch_add(1, Ordering::Relaxed) + 1
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        if let Some(overall) = &self.overall {
+            overall.finish();
+        }
+    }
+}
+
+/// A running loading stage, see `Progress::stage`.
+///
+/// Dropping the stage without finishing it marks it as aborted.
+#[derive(Debug)]
+pub struct Stage<'a> {
+    /// The progress that the stage belongs to.
+    progress: &'a Progress,
+    /// The name of the stage.
+    what: &'static str,
+    /// The spinner, for `Mode::Bars`.
+    bar: Option<ProgressBar>,
+    /// When the stage started.
+    started: Instant,
+}
+
+impl Stage<'_> {
+    /// Finish the stage successfully.
+    pub fn finish(self) {
+        let completed = self.progress.complete();
+        let elapsed = self.started.elapsed();
+        if let Some(bar) = &self.bar {
+            bar.finish_with_message(format!("Loaded {}", self.what));
+        } else {
+            tracing::info!(
+                stage = self.what,
+                completed,
+                elapsed_ms = u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX),
+                "... done loading {} in {:?}",
+                self.what,
+                elapsed
+            );
+        }
+    }
+
+    /// Finish the stage with the recoverable `error`, continuing without its data.
+    pub fn fail(self, error: &anyhow::Error) {
+        let completed = self.progress.complete();
+        let elapsed = self.started.elapsed();
+        if let Some(bar) = &self.bar {
+            bar.abandon_with_message(format!("Failed loading {}, continuing without", self.what));
+        }
+        tracing::warn!(
+            stage = self.what,
+            completed,
+            elapsed_ms = u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX),
+            "... failed loading {}, continuing without: {:#}",
+            self.what,
+            error
+        );
+    }
+}
+
+impl Drop for Stage<'_> {
+    fn drop(&mut self) {
+        if let Some(bar) = self.bar.as_ref().filter(|bar| !bar.is_finished()) {
+            bar.abandon_with_message(format!("Aborted loading {}", self.what));
+        }
+    }
+}
+
+/// An optional loading stage that failed, the data is running without it.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct Degraded {
+    /// The name of the stage, e.g., "translations".
+    pub stage: String,
+    /// The error that the stage failed with.
+    pub error: String,
+}
+
+/// Loads stages with progress reporting, recovering from failures of optional stages.
+#[derive(Debug)]
+pub struct Loader {
+    /// The progress of the stages.
+    pub progress: Progress,
+    /// Whether failures of optional stages are errors.
+    require_optional: bool,
+    /// The failed optional stages.
+    degraded: Mutex<Vec<Degraded>>,
+}
+
+impl Loader {
+    /// Create a new loader reporting in `mode`, failing on optional stages if
+    /// `require_optional`.
+    pub fn new(mode: Mode, require_optional: bool) -> Self {
+        Self {
+            progress: Progress::new(mode),
+            require_optional,
+            degraded: Mutex::default(),
+        }
+    }
+
+    /// Load the required stage `what` with `load`.
+    ///
+    /// # Errors
+    ///
+    /// In the case that `load` fails.
+    pub fn required<T, F>(&self, what: &'static str, load: F) -> Result<T, anyhow::Error>
+    where
+        F: FnOnce() -> Result<T, anyhow::Error>,
+    {
+        let stage = self.progress.stage(what);
+        let result = load()?;
+        stage.finish();
+        Ok(result)
+    }
+
+    /// Load the optional stage `what` from `path` with `load`, if `path` is given.
+    ///
+    /// A failure is recorded as degraded and yields `None` unless optional stages are
+    /// required.
+    ///
+    /// # Errors
+    ///
+    /// In the case that `load` fails and optional stages are required.
+    ///
+    /// # Panics
+    ///
+    /// In the case that the lock on the degraded stages is poisoned.
+    pub fn optional<'a, P, T, F>(
+        &self,
+        what: &'static str,
+        path: Option<&'a P>,
+        load: F,
+    ) -> Result<Option<T>, anyhow::Error>
+    where
+        P: ?Sized,
+        F: FnOnce(&'a P) -> Result<T, anyhow::Error>,
+    {
+        let Some(path) = path else {
+            return Ok(None);
+        };
+        let stage = self.progress.stage(what);
+        match load(path) {
+            Ok(result) => {
+                stage.finish();
+                Ok(Some(result))
+            }
+            Err(e) if self.require_optional => {
+                Err(e.context(format!("failed loading {what} (optional data is required)")))
+            }
+            Err(e) => {
+                stage.fail(&e);
+                self.degraded.lock().expect("poisoned lock").push(Degraded {
+                    stage: what.to_string(),
+                    error: format!("{e:#}"),
+                });
+                Ok(None)
+            }
+        }
+    }
+
+    /// Return the failed optional stages.
+    ///
+    /// # Panics
+    ///
+    /// In the case that the lock on the degraded stages is poisoned.
+    pub fn into_degraded(self) -> Vec<Degraded> {
+        self.degraded.into_inner().expect("poisoned lock")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Degraded, Loader, Mode};
+
+    #[test]
+    fn loader_optional() -> Result<(), anyhow::Error> {
+        let loader = Loader::new(Mode::Logs, false);
+
+        assert_eq!(loader.required("required", || Ok(1))?, 1);
+        assert_eq!(loader.optional("unset", None::<&str>, |_| Ok(2))?, None);
+        assert_eq!(loader.optional("set", Some("x"), |_| Ok(3))?, Some(3));
+        assert_eq!(
+            loader.optional("broken", Some("x"), |path| -> Result<(), _> {
+                anyhow::bail!("cannot read {}", path)
+            })?,
+            None
+        );
+        assert!(loader
+            .required("broken required", || -> Result<(), _> {
+                anyhow::bail!("fails")
+            })
+            .is_err());
+
+        assert_eq!(loader.progress.completed(), 3);
+        assert_eq!(
+            loader.into_degraded(),
+            vec![Degraded {
+                stage: "broken".to_string(),
+                error: "cannot read x".to_string(),
+            }]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn loader_optional_required() {
+        let loader = Loader::new(Mode::Logs, true);
+
+        let err = loader
+            .optional("broken", Some("x"), |_| -> Result<(), _> {
+                anyhow::bail!("cannot read x")
+            })
+            .expect_err("must fail");
+
+        assert_eq!(
+            format!("{err:#}"),
+            "failed loading broken (optional data is required): cannot read x"
+        );
+        assert!(loader.into_degraded().is_empty());
+    }
+}
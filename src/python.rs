@@ -0,0 +1,184 @@
+//! Python bindings of the query engine with `pyo3`, built with the `python` feature.
+//!
+//! The `viguno` extension module loads the data as `server run` does and answers queries with
+//! the query functions behind the REST API, so that notebook users get the same results as
+//! from the server, without the `meta` of the responses and without running it, e.g.,
+//!
+//! ```text
+//! >>> import viguno
+//! >>> engine = viguno.Engine("/data/hpo")
+//! >>> engine.search_terms("seizure", max_results=5)
+//! >>> engine.sim_term_gene(["HP:0001250"], gene_symbols=["SCN1A", "TGDS"])
+//! ```
+
+// The code generated by `pyo3` for `PyResult` return values converts the error into itself.
+#![allow(clippy::useless_conversion)]
+
+use std::{collections::BTreeMap, path::PathBuf, sync::Arc};
+
+use clap::Parser as _;
+use pyo3::{exceptions::PyValueError, prelude::*};
+
+use crate::server::run::{
+    hpo_sim::{term_gene, term_term},
+    hpo_terms, strict_query, WebServerData,
+};
+
+/// Convert `err` into a Python `ValueError`.
+fn to_py_err(err: impl std::fmt::Display) -> PyErr {
+    PyValueError::new_err(err.to_string())
+}
+
+/// The query engine with the loaded data.
+#[pyclass(frozen, module = "viguno")]
+pub struct Engine {
+    /// The data as loaded for `server run`.
+    data: Arc<WebServerData>,
+}
+
+impl Engine {
+    /// Parse the query `params` as the REST API does, run `compute` on the result without the
+    /// GIL, and convert the result into Python objects.
+    fn compute_py<Q, R>(
+        &self,
+        py: Python<'_>,
+        params: &BTreeMap<String, String>,
+        compute: impl FnOnce(&Arc<WebServerData>, Q) -> Result<R, anyhow::Error> + Send,
+    ) -> PyResult<PyObject>
+    where
+        Q: serde::de::DeserializeOwned + utoipa::IntoParams + Send,
+        R: serde::Serialize,
+    {
+        let query =
+            strict_query::from_query::<Q>(&serde_urlencoded::to_string(params).map_err(to_py_err)?)
+                .map_err(to_py_err)?;
+        let result = py
+            .allow_threads(|| {
+                compute(&self.data, query).and_then(|result| Ok(serde_json::to_string(&result)?))
+            })
+            .map_err(to_py_err)?;
+        Ok(py
+            .import_bound("json")?
+            .call_method1("loads", (result,))?
+            .unbind())
+    }
+}
+
+/// Insert the optional parameter `name` into `params`.
+fn set_param(params: &mut BTreeMap<String, String>, name: &str, value: Option<String>) {
+    if let Some(value) = value {
+        params.insert(name.to_string(), value);
+    }
+}
+
+// Arguments are extracted from Python objects and thus passed by value.
+#[allow(clippy::needless_pass_by_value)]
+#[pymethods]
+impl Engine {
+    /// Load the data from `path_hpo_dir` with the further `server run` arguments in `args`,
+    /// e.g., `["--path-network", "string.tsv"]`.
+    #[new]
+    #[pyo3(signature = (path_hpo_dir, args = None))]
+    fn new(py: Python<'_>, path_hpo_dir: PathBuf, args: Option<Vec<String>>) -> PyResult<Self> {
+        let mut command_line = vec![
+            "viguno".to_string(),
+            "--path-hpo-dir".to_string(),
+            path_hpo_dir.display().to_string(),
+        ];
+        command_line.extend(args.unwrap_or_default());
+        let args = crate::server::run::Args::try_parse_from(command_line).map_err(to_py_err)?;
+        let data = py
+            .allow_threads(|| {
+                crate::server::run::load_web_server_data(&args, crate::progress::Mode::Logs)
+            })
+            .map_err(to_py_err)?;
+        Ok(Self {
+            data: Arc::new(data),
+        })
+    }
+
+    /// The HPO release of the loaded data.
+    #[getter]
+    fn hpo_version(&self) -> String {
+        self.data.ontology.hpo_version()
+    }
+
+    /// Search terms by `name` or by `term_id` as `/api/v1/hpo/terms` does.
+    #[pyo3(signature = (name = None, term_id = None, max_results = 100))]
+    fn search_terms(
+        &self,
+        py: Python<'_>,
+        name: Option<String>,
+        term_id: Option<String>,
+        max_results: usize,
+    ) -> PyResult<PyObject> {
+        let mut params = BTreeMap::from([("max_results".to_string(), max_results.to_string())]);
+        set_param(&mut params, "name", name);
+        set_param(&mut params, "term_id", term_id);
+        self.compute_py(py, &params, |data, query| {
+            hpo_terms::compute_blocking(data, query)
+        })
+    }
+
+    /// Compute the pairwise similarity of the `lhs` and `rhs` terms as
+    /// `/api/v1/hpo/sim/term-term` does.
+    #[pyo3(signature = (lhs, rhs, similarity = None, ic_base = None, normalize = None))]
+    fn sim_term_term(
+        &self,
+        py: Python<'_>,
+        lhs: Vec<String>,
+        rhs: Vec<String>,
+        similarity: Option<String>,
+        ic_base: Option<String>,
+        normalize: Option<String>,
+    ) -> PyResult<PyObject> {
+        let mut params = BTreeMap::from([
+            ("lhs".to_string(), lhs.join(",")),
+            ("rhs".to_string(), rhs.join(",")),
+        ]);
+        set_param(&mut params, "similarity", similarity);
+        set_param(&mut params, "ic_base", ic_base);
+        set_param(&mut params, "normalize", normalize);
+        self.compute_py(py, &params, |data, query| {
+            term_term::compute_blocking(data, &data.ic_tables, &query)
+        })
+    }
+
+    /// Prioritize genes for the `terms` as `/api/v1/hpo/sim/term-gene` does, all genes if
+    /// `gene_symbols` is not given.
+    #[pyo3(signature = (terms, gene_symbols = None, method = None, normalize = None))]
+    fn sim_term_gene(
+        &self,
+        py: Python<'_>,
+        terms: Vec<String>,
+        gene_symbols: Option<Vec<String>>,
+        method: Option<String>,
+        normalize: Option<String>,
+    ) -> PyResult<PyObject> {
+        let gene_symbols = gene_symbols.map_or_else(
+            || {
+                itertools::Itertools::join(
+                    &mut self.data.ontology.genes().map(hpo::annotations::Gene::name),
+                    ",",
+                )
+            },
+            |symbols| symbols.join(","),
+        );
+        let mut params = BTreeMap::from([
+            ("terms".to_string(), terms.join(",")),
+            ("gene_symbols".to_string(), gene_symbols),
+        ]);
+        set_param(&mut params, "method", method);
+        set_param(&mut params, "normalize", normalize);
+        self.compute_py(py, &params, |data, query| {
+            term_gene::compute_blocking(data, &query)
+        })
+    }
+}
+
+/// The `viguno` extension module.
+#[pymodule]
+fn viguno(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<Engine>()?;
+    Ok(())
+}
@@ -2,13 +2,20 @@
 
 use hpo::similarity::Builtins;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::time::Instant;
 
 use clap::Parser;
 use hpo::{annotations::AnnotationId, term::HpoGroup, HpoTermId, Ontology};
 
-use crate::algos::phenomizer;
+use crate::algos::{
+    ensemble::EnsembleMethod,
+    ic::{IcTable, PairwiseSimilarity, TableSimilarity},
+    phenomizer::{self, TermWeights},
+    routes::{AnnotationRoute, RouteFilter, RouteTerms},
+    sex::SexFilter,
+};
+use crate::common::SimilarityMethod;
 use crate::query::query_result::HpoSimTermGeneTermDetails;
 
 /// Command line arguments for `query` command.
@@ -17,14 +24,14 @@ use crate::query::query_result::HpoSimTermGeneTermDetails;
 pub struct Args {
     /// Path to the directory with the HPO files.
     #[arg(long, required = true)]
-    pub path_hpo_dir: String,
+    pub path_hpo_dir: std::path::PathBuf,
 
     /// Path to JSON file with the genes to rank.
     #[arg(long)]
-    pub path_genes_json: String,
+    pub path_genes_json: std::path::PathBuf,
     /// Path to JSON file with HPO IDs of patient.
     #[arg(long)]
-    pub path_terms_json: String,
+    pub path_terms_json: std::path::PathBuf,
 }
 
 /// Struct for loading a gene from JSON.
@@ -46,6 +53,8 @@ pub struct HpoTerm {
 
 /// Query result records.
 pub mod query_result {
+    use std::collections::BTreeMap;
+
     use crate::common::Version;
 
     use super::HpoTerm;
@@ -70,6 +79,9 @@ pub mod query_result {
         pub gene_symbol: String,
         /// The HGNC ID.
         pub hgnc_id: Option<String>,
+        /// The gene's NCBI gene information, if requested and available.
+        #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+        pub gene_info: Option<crate::sources::gene_info::GeneInfo>,
     }
 
     /// The performed query.
@@ -90,6 +102,32 @@ pub mod query_result {
         pub query: HpoSimTermGeneQuery,
         /// The resulting records for the scored genes.
         pub result: Vec<HpoSimTermGeneResultEntry>,
+        /// The experimental features that were applied, if any.
+        #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+        pub experimental: Option<Vec<crate::common::ExperimentalFeature>>,
+        /// The constants of the score normalization, if any.
+        #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+        pub normalization: Option<crate::common::NormalizationConstants>,
+        /// The entries of `result` grouped by the gene panels they are on, if requested.
+        #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+        pub panels: Option<Vec<HpoSimTermGenePanel>>,
+    }
+
+    /// The entries of the genes on a gene panel.
+    #[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+    pub struct HpoSimTermGenePanel {
+        /// The panel ID, e.g., `PanelApp:285`.
+        pub panel_id: String,
+        /// The panel name.
+        pub panel_name: String,
+        /// The number of genes on the panel, including those not in the result.
+        pub panel_size: usize,
+        /// The highest ranking score of the panel's genes in the result.
+        pub max_score: f32,
+        /// The mean ranking score of the panel's genes in the result.
+        pub mean_score: f32,
+        /// The entries of the panel's genes in the order of the result.
+        pub genes: Vec<HpoSimTermGeneResultEntry>,
     }
 
     /// Store score for a record with information on individual terms.
@@ -102,6 +140,93 @@ pub mod query_result {
         /// Details on individual terms.
         #[serde(default = "Option::default")]
         pub terms: Option<Vec<HpoSimTermGeneTermDetails>>,
+        /// Partial scores by top-level category, if requested.
+        #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+        pub categories: Option<Vec<HpoSimTermGeneCategoryScore>>,
+        /// The direct phenotype similarity score, if network boosting was requested.
+        #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+        pub direct_score: Option<f32>,
+        /// The score diffused from the network neighbors, if network boosting was requested.
+        #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+        pub network_score: Option<f32>,
+        /// The combination of direct and network score, if network boosting was requested.
+        #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+        pub combined_score: Option<f32>,
+        /// The amount subtracted from the raw score as the gene's age of onset is
+        /// incompatible with the patient's, if any.
+        #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+        pub onset_penalty: Option<f32>,
+        /// Supplementary association scores by name, if requested.
+        #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+        pub association_scores: Option<BTreeMap<String, f32>>,
+        /// The raw scores of the ensemble methods by name, if the ensemble was requested.
+        #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+        pub method_scores: Option<BTreeMap<String, f32>>,
+        /// The reciprocal rank fusion of the ensemble methods, if the ensemble was
+        /// requested.
+        #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+        pub ensemble_score: Option<f32>,
+        /// The p-value of the raw score against the simulated scores of the gene, if
+        /// requested.
+        #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+        pub p_value: Option<f32>,
+        /// The gene's constraint score from 0 (unconstrained) to 1 (constrained), if
+        /// re-ranking by constraint was requested.
+        #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+        pub constraint_score: Option<f32>,
+        /// The phenotype score blended with the constraint score, if re-ranking by
+        /// constraint was requested.
+        #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+        pub reranked_score: Option<f32>,
+        /// The number of pathogenic or likely pathogenic `ClinVar` variants of the gene, if
+        /// the `ClinVar` summary is loaded.
+        #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+        pub clinvar_pathogenic: Option<u32>,
+        /// The maximal median TPM of the gene in the requested tissue, if a tissue was
+        /// requested and the gene is in the expression matrix.
+        #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+        pub expression_tpm: Option<f32>,
+        /// The amount subtracted from the raw score as the gene is not expressed in the
+        /// requested tissue, if any.
+        #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+        pub expression_penalty: Option<f32>,
+    }
+
+    impl HpoSimTermGeneResultEntry {
+        /// Return the score the result is sorted by, i.e., the ensemble score, the
+        /// re-ranked score, the combined score, or the raw score, whichever is present
+        /// first.
+        pub fn ranking_score(&self) -> f32 {
+            self.ensemble_score
+                .or(self.reranked_score)
+                .or(self.combined_score)
+                .unwrap_or(self.raw_score)
+        }
+
+        /// Normalize the scores of the entry and its term details with `constants`.
+        pub fn normalize(&mut self, constants: &crate::common::NormalizationConstants) {
+            self.raw_score = constants.apply(self.raw_score);
+            for score in [
+                &mut self.direct_score,
+                &mut self.network_score,
+                &mut self.combined_score,
+                &mut self.reranked_score,
+            ]
+            .into_iter()
+            .flatten()
+            {
+                *score = constants.apply(*score);
+            }
+            for details in self.terms.iter_mut().flatten() {
+                details.score = constants.apply(details.score);
+            }
+            for penalty in [&mut self.onset_penalty, &mut self.expression_penalty]
+                .into_iter()
+                .flatten()
+            {
+                *penalty = constants.scale(*penalty);
+            }
+        }
     }
 
     /// Detailed term scores.
@@ -113,7 +238,494 @@ pub mod query_result {
         pub term_gene: HpoTerm,
         /// The similarity score.
         pub score: f32,
+        /// The route of the gene's annotation with the term, if a route was requested.
+        #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+        pub route: Option<crate::algos::routes::AnnotationRoute>,
+    }
+
+    /// Partial score of the gene's terms below one top-level category.
+    #[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+    pub struct HpoSimTermGeneCategoryScore {
+        /// The top-level category (organ system), `None` for uncategorized terms.
+        pub category: Option<HpoTerm>,
+        /// The number of the gene's terms in the category.
+        pub n_terms: usize,
+        /// The sum of the best-match scores of the gene's terms in the category.
+        pub score: f32,
+    }
+}
+
+/// Group the best-matching term pairs by the top-level category of the gene term.
+///
+/// Gene terms below more than one category contribute to each of them.  The result is
+/// sorted by descending score.
+pub fn category_scores(
+    hpo: &Ontology,
+    terms: &[HpoSimTermGeneTermDetails],
+) -> Vec<query_result::HpoSimTermGeneCategoryScore> {
+    let mut scores: BTreeMap<Option<HpoTermId>, (usize, f32)> = BTreeMap::new();
+    for details in terms {
+        let categories = HpoTermId::try_from(details.term_gene.term_id.as_str())
+            .ok()
+            .and_then(|term_id| hpo.hpo(term_id))
+            .map(|term| term.categories())
+            .unwrap_or_default();
+        let keys = if categories.is_empty() {
+            vec![None]
+        } else {
+            categories.into_iter().map(Some).collect()
+        };
+        for key in keys {
+            let entry = scores.entry(key).or_default();
+            entry.0 += 1;
+            entry.1 += details.score;
+        }
+    }
+
+    let mut result = scores
+        .into_iter()
+        .map(
+            |(category, (n_terms, score))| query_result::HpoSimTermGeneCategoryScore {
+                category: category
+                    .and_then(|category| hpo.hpo(category))
+                    .map(|term| HpoTerm {
+                        term_id: term.id().to_string(),
+                        term_name: Some(term.name().to_string()),
+                    }),
+                n_terms,
+                score,
+            },
+        )
+        .collect::<Vec<_>>();
+    result.sort_by(|a, b| b.score.total_cmp(&a.score));
+    result
+}
+
+/// Function for scoring a set of patient terms against a set of gene terms.
+pub type ScoreFn = fn(&HpoGroup, &HpoGroup, &Ontology, &TermWeights) -> f32;
+
+/// Scoring of the patient terms against the terms of genes.
+#[derive(Debug, Clone, Copy)]
+pub struct GeneScorer<'a> {
+    /// The function for scoring the terms.
+    pub score: ScoreFn,
+    /// Exclusion of the annotations restricted to the other sex, if any.
+    pub sex: Option<SexFilter<'a>>,
+    /// Selection of the annotations by route, the direct annotations if missing.
+    pub route: Option<RouteFilter<'a>>,
+    /// Weights of the patient terms from their clinical modifiers.
+    pub weights: &'a TermWeights,
+    /// Information content from direct annotations to score with if annotations do not
+    /// propagate to ancestors, the built-in propagated one if missing.
+    pub direct_ic: Option<&'a IcTable>,
+}
+
+impl<'a> GeneScorer<'a> {
+    /// Return the annotations of `gene` by route, if a route other than the direct
+    /// annotations was requested.
+    pub fn route_terms(&self, gene: &hpo::annotations::Gene, hpo: &Ontology) -> Option<RouteTerms> {
+        self.route
+            .filter(|route| route.route != AnnotationRoute::Direct)
+            .map(|route| route.route_terms(gene, self.direct_terms(gene), hpo, self.sex.as_ref()))
+    }
+
+    /// Return the direct annotations of `gene`, without those restricted to the other sex.
+    fn direct_terms(&self, gene: &hpo::annotations::Gene) -> HpoGroup {
+        match &self.sex {
+            Some(sex) => sex.gene_terms(gene),
+            None => gene.hpo_terms().clone(),
+        }
+    }
+
+    /// Return the most specific terms of `gene` that are scored, without modifiers.
+    pub fn gene_terms(&self, gene: &hpo::annotations::Gene, hpo: &Ontology) -> HpoGroup {
+        self.select_terms(gene, hpo, self.route_terms(gene, hpo).as_ref())
+    }
+
+    /// Return the most specific terms of `gene` that are scored, given its `route_terms`
+    /// from `route_terms`.
+    pub fn select_terms(
+        &self,
+        gene: &hpo::annotations::Gene,
+        hpo: &Ontology,
+        route_terms: Option<&RouteTerms>,
+    ) -> HpoGroup {
+        let terms = match (self.route, route_terms) {
+            (Some(route), Some(terms)) => terms.terms(route.route),
+            _ => self.direct_terms(gene),
+        };
+        let terms = hpo::HpoSet::new(hpo, terms);
+        // Without propagation, the terms are not implied by their more specific ones.
+        let terms = if self.direct_ic.is_some() {
+            terms
+        } else {
+            terms.child_nodes()
+        };
+        terms.without_modifier().into_iter().collect()
+    }
+
+    /// Return the pairwise similarity of the terms, Resnik with the gene-based information
+    /// content.
+    pub fn similarity(&self) -> PairwiseSimilarity<'a> {
+        match self.direct_ic {
+            Some(table) => {
+                PairwiseSimilarity::Table(TableSimilarity::new(SimilarityMethod::Resnik, table))
+            }
+            None => PairwiseSimilarity::Builtin(Builtins::Resnik(
+                hpo::term::InformationContentKind::Gene,
+            )),
+        }
+    }
+
+    /// Compute the score between the patient terms and the selected `gene_terms`.
+    pub fn score_terms(&self, patient: &HpoGroup, gene_terms: &HpoGroup, hpo: &Ontology) -> f32 {
+        match self.direct_ic {
+            Some(_) => phenomizer::score_modified_with(
+                patient,
+                gene_terms,
+                hpo,
+                self.weights,
+                &self.similarity(),
+            ),
+            None => (self.score)(patient, gene_terms, hpo, self.weights),
+        }
+    }
+
+    /// Compute the score between the patient terms and the terms of `gene`.
+    pub fn gene_score(
+        &self,
+        patient: &HpoGroup,
+        gene: &hpo::annotations::Gene,
+        hpo: &Ontology,
+    ) -> f32 {
+        self.score_terms(patient, &self.gene_terms(gene, hpo), hpo)
+    }
+}
+
+/// Penalize the genes in `result` whose age of onset is incompatible with `patient`.
+///
+/// The raw score of such a gene is reduced by the fraction `penalty` of it, the amount is
+/// reported in the entry's `onset_penalty`, and the result is sorted again.
+pub fn onset_penalize(
+    result: &mut query_result::HpoSimTermGeneResult,
+    onset_index: &crate::algos::onset::OnsetIndex,
+    hpo: &Ontology,
+    patient: crate::algos::onset::OnsetRange,
+    penalty: f32,
+) {
+    for entry in &mut result.result {
+        if let Some(gene) = hpo.gene_by_name(&entry.gene_symbol) {
+            if onset_index.gene_incompatible(gene.id(), patient) {
+                let amount = entry.raw_score * penalty;
+                entry.raw_score -= amount;
+                entry.onset_penalty = Some(amount);
+            }
+        }
+    }
+    result
+        .result
+        .sort_by(|a, b| b.raw_score.total_cmp(&a.raw_score));
+}
+
+/// Report the expression of the genes in `result` in the tissue `columns` of `matrix`.
+///
+/// With `penalty`, the raw score of genes expressed below `min_tpm` is reduced by the
+/// fraction `penalty` and the result is sorted by raw score again.  Genes missing from
+/// the matrix are not penalized.
+pub fn add_expression(
+    result: &mut query_result::HpoSimTermGeneResult,
+    matrix: &crate::sources::expression::ExpressionMatrix,
+    columns: &[usize],
+    min_tpm: f32,
+    penalty: Option<f32>,
+) {
+    for entry in &mut result.result {
+        entry.expression_tpm = matrix.max_tpm(&entry.gene_symbol, columns);
+        if let (Some(tpm), Some(penalty)) = (entry.expression_tpm, penalty) {
+            if tpm < min_tpm {
+                let amount = entry.raw_score * penalty;
+                entry.raw_score -= amount;
+                entry.expression_penalty = Some(amount);
+            }
+        }
+    }
+    if penalty.is_some() {
+        result
+            .result
+            .sort_by(|a, b| b.raw_score.total_cmp(&a.raw_score));
+    }
+}
+
+/// Report the supplementary association scores of the genes in `result` for `patient`.
+///
+/// The scores are reported next to the raw score and do not change the order.
+pub fn add_association_scores(
+    patient: &HpoGroup,
+    result: &mut query_result::HpoSimTermGeneResult,
+    hpo: &Ontology,
+    association_scores: &[(&str, &crate::sources::associations::AssociationScores)],
+) {
+    for entry in &mut result.result {
+        if let Some(gene) = hpo.gene_by_name(&entry.gene_symbol) {
+            entry.association_scores = Some(
+                association_scores
+                    .iter()
+                    .map(|(name, scores)| {
+                        (
+                            name.to_string(),
+                            scores.gene_score(patient, gene.id().as_u32()),
+                        )
+                    })
+                    .collect(),
+            );
+        }
+    }
+}
+
+/// Report the numbers of pathogenic or likely pathogenic `ClinVar` variants of the genes in
+/// `result`.
+///
+/// The counts are reported next to the raw score and do not change the order.
+pub fn add_clinvar_counts(
+    result: &mut query_result::HpoSimTermGeneResult,
+    hpo: &Ontology,
+    clinvar: &crate::sources::clinvar::ClinvarCounts,
+) {
+    for entry in &mut result.result {
+        if let Some(gene) = hpo.gene_by_name(&entry.gene_symbol) {
+            entry.clinvar_pathogenic = Some(clinvar.get(gene.id().as_u32()));
+        }
+    }
+}
+
+/// Group the entries of `result` by the gene panels of `panels` they are on.
+///
+/// A gene on several panels is reported under each of them, genes on no panel are only
+/// reported in the result.  The groups are sorted by descending maximal ranking score.
+pub fn group_by_panel(
+    result: &mut query_result::HpoSimTermGeneResult,
+    panels: &crate::sources::panels::GenePanels,
+) {
+    let mut groups = BTreeMap::<&str, query_result::HpoSimTermGenePanel>::new();
+    for entry in &result.result {
+        for panel in panels.panels_of(&entry.gene_symbol) {
+            groups
+                .entry(panel.id.as_str())
+                .or_insert_with(|| query_result::HpoSimTermGenePanel {
+                    panel_id: panel.id.clone(),
+                    panel_name: panel.name.clone(),
+                    panel_size: panel.gene_symbols.len(),
+                    max_score: f32::NEG_INFINITY,
+                    mean_score: 0.0,
+                    genes: Vec::new(),
+                })
+                .genes
+                .push(entry.clone());
+        }
+    }
+    let mut groups = groups
+        .into_values()
+        .map(|mut group| {
+            let scores = group
+                .genes
+                .iter()
+                .map(query_result::HpoSimTermGeneResultEntry::ranking_score);
+            group.max_score = scores.clone().fold(f32::NEG_INFINITY, f32::max);
+            #[allow(clippy::cast_precision_loss)]
+            let mean_score = scores.sum::<f32>() / group.genes.len() as f32;
+            group.mean_score = mean_score;
+            group
+        })
+        .collect::<Vec<_>>();
+    groups.sort_by(|a, b| b.max_score.total_cmp(&a.max_score));
+    result.panels = Some(groups);
+}
+
+/// Score the genes in `result` with the ensemble `methods` and fuse their ranks.
+///
+/// The raw scores of the methods are reported in the entry's `method_scores` and the
+/// supplementary association scores reported so far are fused as further methods.  With
+/// `clinvar_prior`, the `ClinVar` counts reported so far are fused as a prior, too.  The
+/// reciprocal rank fusion is reported in the entry's `ensemble_score` and the result is
+/// sorted by it.
+pub fn ensemble(
+    patient: &HpoGroup,
+    result: &mut query_result::HpoSimTermGeneResult,
+    hpo: &Ontology,
+    scorer: &GeneScorer,
+    methods: &[EnsembleMethod],
+    clinvar_prior: bool,
+) {
+    for entry in &mut result.result {
+        if let Some(gene) = hpo.gene_by_name(&entry.gene_symbol) {
+            let terms = scorer.gene_terms(gene, hpo);
+            entry.method_scores = Some(
+                methods
+                    .iter()
+                    .map(|method| {
+                        (
+                            method.to_string(),
+                            method.score(patient, &terms, hpo, scorer.weights),
+                        )
+                    })
+                    .collect(),
+            );
+        }
+    }
+
+    let mut columns = result
+        .result
+        .iter()
+        .flat_map(|entry| {
+            entry
+                .method_scores
+                .iter()
+                .chain(&entry.association_scores)
+                .flat_map(BTreeMap::keys)
+        })
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .map(|name| {
+            result
+                .result
+                .iter()
+                .map(|entry| {
+                    entry
+                        .method_scores
+                        .iter()
+                        .chain(&entry.association_scores)
+                        .find_map(|scores| scores.get(name))
+                        .copied()
+                        .unwrap_or_default()
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    if clinvar_prior {
+        #[allow(clippy::cast_precision_loss)]
+        columns.push(
+            result
+                .result
+                .iter()
+                .map(|entry| entry.clinvar_pathogenic.unwrap_or_default() as f32)
+                .collect(),
+        );
+    }
+    let fused = crate::algos::ensemble::reciprocal_rank_fusion(&columns);
+    for (entry, score) in result.result.iter_mut().zip(fused) {
+        entry.ensemble_score = Some(score);
+    }
+    result.result.sort_by(|a, b| {
+        b.ensemble_score
+            .unwrap_or_default()
+            .total_cmp(&a.ensemble_score.unwrap_or_default())
+    });
+}
+
+/// Boost the scores in `result` by the scores of the genes' network neighbors.
+///
+/// The direct scores of the network neighbors are computed with `scorer`.  The network
+/// score of a gene is the weighted mean of the direct scores of its neighbors or, if
+/// `rwr` is given, the result of a random walk with restart seeded with the direct
+/// scores of the genes and their neighbors.  The combined score is
+/// `(1 - network_weight) * direct_score + network_weight * network_score` and the
+/// result is sorted by it.
+pub fn network_boost(
+    patient: &HpoGroup,
+    result: &mut query_result::HpoSimTermGeneResult,
+    network: &crate::network::Network,
+    hpo: &Ontology,
+    network_weight: f32,
+    rwr: Option<&crate::network::rwr::RwrParams>,
+    scorer: &GeneScorer,
+) {
+    let mut direct_scores: HashMap<u32, f32> = HashMap::new();
+    let mut direct_score = |ncbi_gene_id: u32| {
+        *direct_scores.entry(ncbi_gene_id).or_insert_with(|| {
+            hpo.gene(&hpo::annotations::GeneId::from(ncbi_gene_id))
+                .map(|gene| scorer.gene_score(patient, gene, hpo))
+                .unwrap_or_default()
+        })
+    };
+
+    let ncbi_gene_ids = result
+        .result
+        .iter()
+        .map(|entry| {
+            hpo.gene_by_name(&entry.gene_symbol)
+                .map(|gene| gene.id().as_u32())
+        })
+        .collect::<Vec<_>>();
+    // Scale the stationary distribution back to the range of the scores.
+    let rwr_scores = rwr.map(|params| {
+        let mut seeds = BTreeMap::new();
+        for &gene in ncbi_gene_ids.iter().flatten() {
+            for neighbor in std::iter::once(gene).chain(network.neighbors(gene).map(|(n, _)| n)) {
+                seeds.insert(neighbor, direct_score(neighbor));
+            }
+        }
+        // Sum in the order of the genes so that the total does not depend on hashing.
+        let total: f32 = seeds.values().sum();
+        network
+            .random_walk_with_restart(&seeds, params)
+            .into_iter()
+            .map(|(gene, p)| (gene, p * total))
+            .collect::<BTreeMap<_, _>>()
+    });
+
+    for (entry, ncbi_gene_id) in result.result.iter_mut().zip(ncbi_gene_ids) {
+        let network_score = ncbi_gene_id
+            .map(|gene| match &rwr_scores {
+                Some(rwr_scores) => rwr_scores.get(&gene).copied().unwrap_or_default(),
+                None => network.neighbor_score(gene, &mut direct_score),
+            })
+            .unwrap_or_default();
+        entry.direct_score = Some(entry.raw_score);
+        entry.network_score = Some(network_score);
+        entry.combined_score =
+            Some((1.0 - network_weight) * entry.raw_score + network_weight * network_score);
     }
+
+    result.result.sort_by(|a, b| {
+        let (a, b) = (
+            a.combined_score.unwrap_or_default(),
+            b.combined_score.unwrap_or_default(),
+        );
+        b.total_cmp(&a)
+    });
+}
+
+/// Re-rank the genes in `result` by blending their phenotype score with their constraint.
+///
+/// The phenotype score is the combined score if network boosting was applied and the raw
+/// score otherwise.  It is scaled by `1 - constraint_weight + constraint_weight * c` with
+/// the constraint score `c` of the gene, so that genes without phenotype match stay at 0.
+/// Genes missing from `constraint` count with `DEFAULT_CONSTRAINT_SCORE`.  The result is
+/// sorted by the re-ranked score.
+pub fn constraint_rerank(
+    result: &mut query_result::HpoSimTermGeneResult,
+    constraint: &crate::sources::constraint::ConstraintTable,
+    constraint_weight: f32,
+) {
+    for entry in &mut result.result {
+        let constraint_score = constraint
+            .get(&entry.gene_symbol)
+            .and_then(crate::sources::constraint::GeneConstraint::score);
+        let phenotype_score = entry.combined_score.unwrap_or(entry.raw_score);
+        let factor = 1.0 - constraint_weight
+            + constraint_weight
+                * constraint_score.unwrap_or(crate::sources::constraint::DEFAULT_CONSTRAINT_SCORE);
+        entry.constraint_score = constraint_score;
+        entry.reranked_score = Some(phenotype_score * factor);
+    }
+
+    result.result.sort_by(|a, b| {
+        let (a, b) = (
+            a.reranked_score.unwrap_or_default(),
+            b.reranked_score.unwrap_or_default(),
+        );
+        b.total_cmp(&a)
+    });
 }
 
 /// Run the actual phenotypic similarity query for patient terms and list of
@@ -125,6 +737,7 @@ pub mod query_result {
 /// * `genes`: The list of genes to score.
 /// * `hpo`: The HPO ontology.
 /// * `db`: The `RocksDB` instance for the Resnik P-values.
+/// * `scorer`: The scoring of the patient terms against the terms of the genes.
 ///
 /// # Returns
 ///
@@ -140,24 +753,24 @@ pub mod query_result {
 #[allow(clippy::cast_possible_truncation)]
 #[allow(clippy::cast_precision_loss)]
 #[allow(clippy::too_many_lines)]
-pub fn run_query<S>(
+pub fn run_query(
     patient: &HpoGroup,
     genes: &Vec<&hpo::annotations::Gene>,
     hpo: &Ontology,
-    ncbi_to_hgnc: &HashMap<u32, String, S>,
-) -> Result<query_result::HpoSimTermGeneResult, anyhow::Error>
-where
-    S: std::hash::BuildHasher,
-{
+    hgnc_xlink: &crate::sources::hgnc_xlink::HgncXlink,
+    scorer: &GeneScorer,
+) -> Result<query_result::HpoSimTermGeneResult, anyhow::Error> {
+    // The patient terms are resolved once and their entries cloned into the term details.
+    let patient_terms = patient
+        .iter()
+        .map(|t| hpo.hpo(t).expect("could not resolve HPO term"))
+        .collect::<Vec<_>>();
     let query = query_result::HpoSimTermGeneQuery {
-        terms: patient
+        terms: patient_terms
             .iter()
-            .map(|t| {
-                let term = hpo.hpo(t).expect("could not resolve HPO term");
-                HpoTerm {
-                    term_id: term.id().to_string(),
-                    term_name: Some(term.name().to_string()),
-                }
+            .map(|term| HpoTerm {
+                term_id: term.id().to_string(),
+                term_name: Some(term.name().to_string()),
             })
             .collect(),
         genes: Vec::new(),
@@ -166,52 +779,31 @@ where
         version: crate::common::Version::new(&hpo.hpo_version()),
         query,
         result: Vec::new(),
+        experimental: None,
+        normalization: None,
+        panels: None,
     };
     for gene in genes {
         let ncbi_gene_id = gene.id().as_u32();
         tracing::debug!("gene = {:?}", gene);
-        let raw_score = phenomizer::score(
-            patient,
-            &gene
-                .to_hpo_set(hpo)
-                .child_nodes()
-                .without_modifier()
-                .into_iter()
-                .collect::<HpoGroup>(),
-            hpo,
-        );
+        let route_terms = scorer.route_terms(gene, hpo);
+        let gene_terms = scorer.select_terms(gene, hpo, route_terms.as_ref());
+        let raw_score = scorer.score_terms(patient, &gene_terms, hpo);
+        let similarity = scorer.similarity();
 
         // For each term in the gene, provide query term with the highest similarity.
-        let mut terms = gene
-            .to_hpo_set(hpo)
-            .child_nodes()
-            .without_modifier()
-            .into_iter()
-            .collect::<HpoGroup>()
+        let mut terms = gene_terms
             .iter()
             .map(|gene_term_id| {
                 let gene_term = hpo.hpo(gene_term_id).expect("gene HPO term not found");
-                let (best_term, best_score) = patient
+                let (best_index, best_score) = patient_terms
                     .iter()
-                    .map(|query_term_id| {
-                        let query_term = hpo.hpo(query_term_id).expect("query HPO term not found");
-                        let score = gene_term.similarity_score(
-                            &query_term,
-                            &Builtins::Resnik(hpo::term::InformationContentKind::Gene),
-                        );
-                        (query_term, score)
-                    })
+                    .map(|query_term| gene_term.similarity_score(query_term, &similarity))
+                    .enumerate()
                     .max_by(|(_, score1), (_, score2)| score1.partial_cmp(score2).unwrap())
                     .expect("could not determine best query term");
 
-                let term_query = if best_score > 0.0 {
-                    Some(HpoTerm {
-                        term_id: best_term.id().to_string(),
-                        term_name: Some(best_term.name().to_string()),
-                    })
-                } else {
-                    None
-                };
+                let term_query = (best_score > 0.0).then(|| result.query.terms[best_index].clone());
 
                 HpoSimTermGeneTermDetails {
                     term_query,
@@ -220,6 +812,10 @@ where
                         term_name: Some(gene_term.name().to_string()),
                     },
                     score: best_score,
+                    route: match &route_terms {
+                        Some(route_terms) => route_terms.route_of(gene_term_id),
+                        None => scorer.route.map(|_| AnnotationRoute::Direct),
+                    },
                 }
             })
             .collect::<Vec<_>>();
@@ -228,13 +824,28 @@ where
         result.query.genes.push(query_result::Gene {
             entrez_id: ncbi_gene_id,
             gene_symbol: gene.name().to_string(),
-            hgnc_id: ncbi_to_hgnc.get(&ncbi_gene_id).cloned(),
+            hgnc_id: hgnc_xlink.hgnc_id(ncbi_gene_id).map(str::to_string),
+            gene_info: None,
         });
 
         result.result.push(query_result::HpoSimTermGeneResultEntry {
             gene_symbol: gene.name().to_string(),
             raw_score,
             terms: Some(terms),
+            categories: None,
+            direct_score: None,
+            network_score: None,
+            combined_score: None,
+            onset_penalty: None,
+            association_scores: None,
+            method_scores: None,
+            ensemble_score: None,
+            p_value: None,
+            constraint_score: None,
+            reranked_score: None,
+            clinvar_pathogenic: None,
+            expression_tpm: None,
+            expression_penalty: None,
         });
     }
 
@@ -267,10 +878,10 @@ pub fn run(args_common: &crate::common::Args, args: &Args) -> Result<(), anyhow:
         env_logger::init_from_env(env_logger::Env::new().default_filter_or("info"));
     }
 
-    tracing::info!("Loading HPO...");
-    let before_loading = Instant::now();
+    let progress = crate::progress::Progress::new(crate::progress::Mode::cli());
+    let stage = progress.stage("HPO");
     let hpo = crate::common::load_hpo(&args.path_hpo_dir)?;
-    tracing::info!("...done loading HPO in {:?}", before_loading.elapsed());
+    stage.finish();
 
     tracing::info!("Loading genes...");
     let before_load_genes = Instant::now();
@@ -295,11 +906,8 @@ pub fn run(args_common: &crate::common::Args, args: &Args) -> Result<(), anyhow:
     let query: Vec<HpoTerm> = serde_json::from_str(&query_json)?;
     let query = query
         .iter()
-        .map(|t| {
-            HpoTermId::try_from(t.term_id.as_str())
-                .unwrap_or_else(|_| panic!("term {} no valid HPO term ID", &t.term_id))
-        })
-        .collect::<Vec<_>>();
+        .map(|t| crate::common::parse_term_id(&t.term_id))
+        .collect::<Result<Vec<_>, _>>()?;
     let query = {
         let mut group = HpoGroup::new();
         for term in query {
@@ -314,8 +922,9 @@ pub fn run(args_common: &crate::common::Args, args: &Args) -> Result<(), anyhow:
 
     tracing::info!("Loading HGNC xlink...");
     let before_load_xlink = Instant::now();
-    let path_hgnc_xlink = format!("{}/hgnc_xlink.tsv", args.path_hpo_dir);
-    let ncbi_to_hgnc = crate::common::hgnc_xlink::load_ncbi_to_hgnc(path_hgnc_xlink)?;
+    let path_hgnc_xlink =
+        crate::common::data_file(&args.path_hpo_dir, "hgnc_xlink.tsv", "HGNC xlink")?;
+    let hgnc_xlink = crate::sources::hgnc_xlink::HgncXlink::load(path_hgnc_xlink)?;
     tracing::info!(
         "... done loading HGNC xlink in {:?}",
         before_load_xlink.elapsed()
@@ -323,7 +932,19 @@ pub fn run(args_common: &crate::common::Args, args: &Args) -> Result<(), anyhow:
 
     tracing::info!("Starting priorization...");
     let before_priorization = Instant::now();
-    let result = run_query(&query, &genes, &hpo, &ncbi_to_hgnc)?;
+    let result = run_query(
+        &query,
+        &genes,
+        &hpo,
+        &hgnc_xlink,
+        &GeneScorer {
+            score: phenomizer::score_modified,
+            sex: None,
+            route: None,
+            weights: &TermWeights::new(),
+            direct_ic: None,
+        },
+    )?;
     tracing::info!(
         "... done with prioritization in {:?}",
         before_priorization.elapsed()
@@ -351,3 +972,420 @@ pub fn run(args_common: &crate::common::Args, args: &Args) -> Result<(), anyhow:
     tracing::info!("All done. Have a nice day!");
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use hpo::{annotations::GeneId, builder::Builder, term::HpoGroup, HpoTermId, Ontology};
+
+    use super::{query_result::HpoSimTermGeneTermDetails, HpoTerm};
+
+    /// Build a small ontology with the categories `HP:0000002` and `HP:0000003` below
+    /// "Phenotypic abnormality" and the term `HP:0000004` below `HP:0000002`.
+    ///
+    /// Genes 10 and 30 are annotated with `HP:0000004`, gene 20 with `HP:0000003`.
+    fn category_ontology() -> Ontology {
+        let mut builder = Builder::new();
+        builder.new_term("All", 1u32);
+        builder.new_term("Head", 2u32);
+        builder.new_term("Heart", 3u32);
+        builder.new_term("Eye", 4u32);
+        builder.new_term("Phenotypic abnormality", 118u32);
+        let mut builder = builder.terms_complete();
+        builder.add_parent(1u32, 118u32).expect("term must exist");
+        builder.add_parent(118u32, 2u32).expect("term must exist");
+        builder.add_parent(118u32, 3u32).expect("term must exist");
+        builder.add_parent(2u32, 4u32).expect("term must exist");
+        let mut builder = builder.connect_all_terms();
+        for (gene_id, name, term) in [
+            (10, "GENE10", 4u32),
+            (20, "GENE20", 3u32),
+            (30, "GENE30", 4u32),
+        ] {
+            builder
+                .annotate_gene(GeneId::from(gene_id), name, term.into())
+                .expect("term must exist");
+        }
+        builder
+            .calculate_information_content()
+            .expect("could not compute IC")
+            .build_with_defaults()
+            .expect("could not build ontology")
+    }
+
+    fn details(term_gene: &str, score: f32) -> HpoSimTermGeneTermDetails {
+        HpoSimTermGeneTermDetails {
+            term_query: None,
+            term_gene: HpoTerm {
+                term_id: term_gene.to_string(),
+                term_name: None,
+            },
+            score,
+            route: None,
+        }
+    }
+
+    #[test]
+    fn category_scores() {
+        let hpo = category_ontology();
+        let terms = vec![
+            details("HP:0000004", 2.0),
+            details("HP:0000002", 1.0),
+            details("HP:0000003", 0.5),
+        ];
+
+        let scores = super::category_scores(&hpo, &terms);
+
+        assert_eq!(
+            scores
+                .iter()
+                .map(|s| (
+                    s.category.as_ref().map(|t| t.term_id.as_str()),
+                    s.n_terms,
+                    s.score
+                ))
+                .collect::<Vec<_>>(),
+            vec![(Some("HP:0000002"), 2, 3.0), (Some("HP:0000003"), 1, 0.5)]
+        );
+    }
+
+    #[test]
+    fn network_boost() -> Result<(), anyhow::Error> {
+        let hpo = category_ontology();
+        let patient = HpoGroup::from(vec![HpoTermId::from_u32(4)]);
+        let genes = vec![hpo.gene_by_name("GENE20").expect("gene must exist")];
+        let network = crate::network::Network::from_edges([(20, 30, 1.0)]);
+        let weights = crate::algos::phenomizer::TermWeights::new();
+        let scorer = super::GeneScorer {
+            score: crate::algos::phenomizer::score_modified,
+            sex: None,
+            route: None,
+            weights: &weights,
+            direct_ic: None,
+        };
+        let mut result = super::run_query(
+            &patient,
+            &genes,
+            &hpo,
+            &crate::sources::hgnc_xlink::HgncXlink::default(),
+            &scorer,
+        )?;
+        let eye_ic = hpo
+            .hpo(4u32)
+            .expect("term must exist")
+            .information_content()
+            .gene();
+
+        super::network_boost(&patient, &mut result, &network, &hpo, 0.5, None, &scorer);
+
+        let entry = &result.result[0];
+        assert!(entry.direct_score.expect("must be set").abs() < 1e-6);
+        assert!((entry.network_score.expect("must be set") - eye_ic).abs() < 1e-6);
+        assert!((entry.combined_score.expect("must be set") - 0.5 * eye_ic).abs() < 1e-6);
+
+        Ok(())
+    }
+
+    #[test]
+    fn run_query_direct_ic() -> Result<(), anyhow::Error> {
+        let hpo = category_ontology();
+        let patient = HpoGroup::from(vec![HpoTermId::from_u32(2)]);
+        let genes = vec![hpo.gene_by_name("GENE10").expect("gene must exist")];
+        let weights = crate::algos::phenomizer::TermWeights::new();
+        let direct_ic = crate::algos::ic::IcTable::from_direct_annotations(
+            &hpo,
+            crate::common::IcBasedOn::Gene,
+        )
+        .expect("gene IC must exist");
+        let score = |direct_ic| -> Result<f32, anyhow::Error> {
+            let scorer = super::GeneScorer {
+                score: crate::algos::phenomizer::score_modified,
+                sex: None,
+                route: None,
+                weights: &weights,
+                direct_ic,
+            };
+            let result = super::run_query(
+                &patient,
+                &genes,
+                &hpo,
+                &crate::sources::hgnc_xlink::HgncXlink::default(),
+                &scorer,
+            )?;
+            Ok(result.result[0].raw_score)
+        };
+
+        // With propagation, "Head" is annotated with the genes of "Eye", without, with none.
+        assert!((score(None)? - 1.5f32.ln()).abs() < 1e-6);
+        assert!(score(Some(&direct_ic))?.abs() < 1e-6);
+
+        Ok(())
+    }
+
+    #[test]
+    fn constraint_rerank() -> Result<(), anyhow::Error> {
+        let hpo = category_ontology();
+        let patient = HpoGroup::from(vec![HpoTermId::from_u32(4)]);
+        let genes = ["GENE10", "GENE30"]
+            .iter()
+            .map(|name| hpo.gene_by_name(name).expect("gene must exist"))
+            .collect::<Vec<_>>();
+        let weights = crate::algos::phenomizer::TermWeights::new();
+        let scorer = super::GeneScorer {
+            score: crate::algos::phenomizer::score_modified,
+            sex: None,
+            route: None,
+            weights: &weights,
+            direct_ic: None,
+        };
+        let mut result = super::run_query(
+            &patient,
+            &genes,
+            &hpo,
+            &crate::sources::hgnc_xlink::HgncXlink::default(),
+            &scorer,
+        )?;
+        let constraint = crate::sources::constraint::ConstraintTable::from_reader(
+            "gene_symbol\tpli\tloeuf\nGENE10\t0.0\t2.0\nGENE30\t1.0\t0.0\n".as_bytes(),
+        )?;
+        let raw_score = result.result[0].raw_score;
+
+        super::constraint_rerank(&mut result, &constraint, 0.5);
+
+        // Both genes match equally well, the constrained one is ranked first.
+        assert_eq!(result.result[0].gene_symbol, "GENE30");
+        assert_eq!(result.result[0].constraint_score, Some(1.0));
+        assert!((result.result[0].reranked_score.expect("must be set") - raw_score).abs() < 1e-6);
+        assert_eq!(result.result[1].constraint_score, Some(0.0));
+        assert!(
+            (result.result[1].reranked_score.expect("must be set") - 0.5 * raw_score).abs() < 1e-6
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn group_by_panel() -> Result<(), anyhow::Error> {
+        let hpo = category_ontology();
+        let patient = HpoGroup::from(vec![HpoTermId::from_u32(4)]);
+        let genes = ["GENE10", "GENE20", "GENE30"]
+            .iter()
+            .map(|name| hpo.gene_by_name(name).expect("gene must exist"))
+            .collect::<Vec<_>>();
+        let weights = crate::algos::phenomizer::TermWeights::new();
+        let scorer = super::GeneScorer {
+            score: crate::algos::phenomizer::score_modified,
+            sex: None,
+            route: None,
+            weights: &weights,
+            direct_ic: None,
+        };
+        let mut result = super::run_query(
+            &patient,
+            &genes,
+            &hpo,
+            &crate::sources::hgnc_xlink::HgncXlink::default(),
+            &scorer,
+        )?;
+        let panels = crate::sources::panels::GenePanels::from_reader(
+            "panel_id\tpanel_name\tgene_symbol\n\
+             heart\tHeart\tGENE20\n\
+             heart\tHeart\tGENE99\n\
+             eye\tEye\tGENE10\n\
+             eye\tEye\tGENE20\n"
+                .as_bytes(),
+        )?;
+        let score = |symbol: &str| {
+            result
+                .result
+                .iter()
+                .find(|entry| entry.gene_symbol == symbol)
+                .map(super::query_result::HpoSimTermGeneResultEntry::ranking_score)
+                .unwrap_or_default()
+        };
+        let (score10, score20) = (score("GENE10"), score("GENE20"));
+
+        super::group_by_panel(&mut result, &panels);
+
+        let groups = result.panels.as_ref().expect("must be grouped");
+        assert_eq!(
+            groups
+                .iter()
+                .map(|group| (group.panel_id.as_str(), group.panel_size, group.genes.len()))
+                .collect::<Vec<_>>(),
+            vec![("eye", 2, 2), ("heart", 2, 1)]
+        );
+        assert!((groups[0].max_score - score10).abs() < 1e-6);
+        assert!((groups[0].mean_score - f32::midpoint(score10, score20)).abs() < 1e-6);
+        assert!((groups[1].max_score - score20).abs() < 1e-6);
+        assert_eq!(result.result.len(), 3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn ensemble_clinvar_prior() -> Result<(), anyhow::Error> {
+        let hpo = category_ontology();
+        let patient = HpoGroup::from(vec![HpoTermId::from_u32(4)]);
+        let genes = ["GENE10", "GENE30"]
+            .iter()
+            .map(|name| hpo.gene_by_name(name).expect("gene must exist"))
+            .collect::<Vec<_>>();
+        let weights = crate::algos::phenomizer::TermWeights::new();
+        let scorer = super::GeneScorer {
+            score: crate::algos::phenomizer::score_modified,
+            sex: None,
+            route: None,
+            weights: &weights,
+            direct_ic: None,
+        };
+        let clinvar = crate::sources::clinvar::ClinvarCounts::from_reader(
+            "ncbi_gene_id\tpathogenic_variants\n10\t1\n30\t25\n".as_bytes(),
+        )?;
+        let run = |clinvar_prior| -> Result<_, anyhow::Error> {
+            let mut result = super::run_query(
+                &patient,
+                &genes,
+                &hpo,
+                &crate::sources::hgnc_xlink::HgncXlink::default(),
+                &scorer,
+            )?;
+            super::add_clinvar_counts(&mut result, &hpo, &clinvar);
+            super::ensemble(
+                &patient,
+                &mut result,
+                &hpo,
+                &scorer,
+                &[crate::algos::ensemble::EnsembleMethod::Jaccard],
+                clinvar_prior,
+            );
+            Ok(result)
+        };
+
+        // Both genes match equally well, the prior ranks the one with more variants first.
+        let result = run(false)?;
+        assert_eq!(
+            result.result[0].ensemble_score,
+            result.result[1].ensemble_score
+        );
+        let result = run(true)?;
+        assert_eq!(result.result[0].gene_symbol, "GENE30");
+        assert_eq!(result.result[0].clinvar_pathogenic, Some(25));
+        assert!(result.result[0].ensemble_score > result.result[1].ensemble_score);
+
+        Ok(())
+    }
+
+    #[test]
+    fn add_expression() -> Result<(), anyhow::Error> {
+        let hpo = category_ontology();
+        let patient = HpoGroup::from(vec![HpoTermId::from_u32(4)]);
+        let genes = ["GENE10", "GENE20", "GENE30"]
+            .iter()
+            .map(|name| hpo.gene_by_name(name).expect("gene must exist"))
+            .collect::<Vec<_>>();
+        let weights = crate::algos::phenomizer::TermWeights::new();
+        let scorer = super::GeneScorer {
+            score: crate::algos::phenomizer::score_modified,
+            sex: None,
+            route: None,
+            weights: &weights,
+            direct_ic: None,
+        };
+        let mut result = super::run_query(
+            &patient,
+            &genes,
+            &hpo,
+            &crate::sources::hgnc_xlink::HgncXlink::default(),
+            &scorer,
+        )?;
+        let matrix = crate::sources::expression::ExpressionMatrix::from_reader(
+            "Name\tDescription\tBrain - Cortex\tLiver\n\
+             ENSG10\tGENE10\t0.1\t9.0\n\
+             ENSG30\tGENE30\t5.0\t0.0\n"
+                .as_bytes(),
+        )?;
+        let raw_score = |result: &super::query_result::HpoSimTermGeneResult, name: &str| {
+            result
+                .result
+                .iter()
+                .find(|entry| entry.gene_symbol == name)
+                .map(|entry| entry.raw_score)
+                .unwrap_or_default()
+        };
+        let before = raw_score(&result, "GENE10");
+
+        super::add_expression(
+            &mut result,
+            &matrix,
+            &matrix.tissue_columns("brain"),
+            1.0,
+            Some(0.5),
+        );
+
+        // Both genes match equally well, the one expressed in the brain is ranked first.
+        assert_eq!(result.result[0].gene_symbol, "GENE30");
+        assert_eq!(result.result[0].expression_tpm, Some(5.0));
+        assert!(result.result[0].expression_penalty.is_none());
+        assert!((raw_score(&result, "GENE10") - 0.5 * before).abs() < 1e-6);
+        // Genes missing from the matrix are not penalized.
+        let gene20 = result
+            .result
+            .iter()
+            .find(|entry| entry.gene_symbol == "GENE20")
+            .expect("gene must exist");
+        assert!(gene20.expression_tpm.is_none() && gene20.expression_penalty.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn deterministic() -> Result<(), anyhow::Error> {
+        let hpo = category_ontology();
+        let patient = HpoGroup::from(vec![HpoTermId::from_u32(4)]);
+        let genes = ["GENE10", "GENE20", "GENE30"]
+            .iter()
+            .map(|name| hpo.gene_by_name(name).expect("gene must exist"))
+            .collect::<Vec<_>>();
+        let network = crate::network::Network::from_edges([(10, 20, 1.0), (20, 30, 2.0)]);
+        let weights = crate::algos::phenomizer::TermWeights::new();
+        let scorer = super::GeneScorer {
+            score: crate::algos::phenomizer::score_modified,
+            sex: None,
+            route: None,
+            weights: &weights,
+            direct_ic: None,
+        };
+        let methods = crate::algos::ensemble::DEFAULT_METHODS
+            .split(',')
+            .map(str::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Identical inputs must give bitwise identical outputs, regardless of hashing.
+        let run = || -> Result<serde_json::Value, anyhow::Error> {
+            let mut result = super::run_query(
+                &patient,
+                &genes,
+                &hpo,
+                &crate::sources::hgnc_xlink::HgncXlink::default(),
+                &scorer,
+            )?;
+            super::network_boost(
+                &patient,
+                &mut result,
+                &network,
+                &hpo,
+                0.5,
+                Some(&crate::network::rwr::RwrParams::default()),
+                &scorer,
+            );
+            super::ensemble(&patient, &mut result, &hpo, &scorer, &methods, false);
+            Ok(serde_json::to_value(&result)?)
+        };
+        let expected = run()?;
+        for _ in 0..10 {
+            assert_eq!(run()?, expected);
+        }
+
+        Ok(())
+    }
+}
@@ -1,4 +1,6 @@
 //! Code for the REST API server.
 
+pub mod replay;
 pub mod run;
 pub mod schema;
+pub mod selftest;
@@ -0,0 +1,306 @@
+//! Replay of requests recorded with `server run --record-requests`.
+//!
+//! Loads the data as `server run` does, re-executes the recorded requests against the
+//! current build without HTTP, and reports the responses that differ from the recorded
+//! ones, e.g., when upgrading the `hpo` crate.
+
+use std::{
+    fs::File,
+    io::{self, Write},
+    path::PathBuf,
+    sync::Arc,
+};
+
+use crate::server::run::record::{self, Recording};
+
+/// Command line arguments for `server replay` sub command.
+#[derive(clap::Parser, Debug)]
+#[command(author, version, about = "Replay recorded requests and diff the responses", long_about = None)]
+#[group(id = "replay")]
+pub struct Args {
+    /// The arguments for loading the data as for `server run`.
+    #[command(flatten)]
+    pub server: crate::server::run::Args,
+    /// Path to the directory with the recordings.
+    pub dir: PathBuf,
+    /// JSON pointers of response fields that are expected to differ and not compared.
+    #[arg(
+        long,
+        value_delimiter = ',',
+        default_value = "/meta/compute_time_ms,/meta/server_version,/meta/cache_hit"
+    )]
+    pub ignore: Vec<String>,
+    /// Path to the output JSON file with the differences.  Use stdout if missing.
+    #[arg(long)]
+    pub output_file: Option<PathBuf>,
+}
+
+impl Args {
+    /// Get writeable output file or stdout.
+    fn get_output(&self) -> Result<Box<dyn Write>, io::Error> {
+        match self.output_file {
+            Some(ref path) => File::create(path).map(|f| Box::new(f) as Box<dyn Write>),
+            None => Ok(Box::new(io::stdout())),
+        }
+    }
+}
+
+/// A replayed request whose response differs from the recorded one.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Difference {
+    /// The file of the recording.
+    pub file: String,
+    /// The HTTP method.
+    pub method: String,
+    /// The path.
+    pub path: String,
+    /// The query string.
+    pub query: String,
+    /// The recorded and the replayed status code, if they differ.
+    pub status: Option<(u16, u16)>,
+    /// JSON pointers of the differing response fields.
+    pub fields: Vec<String>,
+}
+
+/// The report of a replay.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ReplayReport {
+    /// The number of replayed requests.
+    pub n_replayed: usize,
+    /// The number of replayed requests with identical responses.
+    pub n_identical: usize,
+    /// The replayed requests whose responses differ.
+    pub differences: Vec<Difference>,
+}
+
+/// Remove the fields at the JSON pointers in `ignore` from `value`.
+fn remove_ignored(value: &mut serde_json::Value, ignore: &[String]) {
+    for pointer in ignore {
+        if let Some((parent, key)) = pointer.rsplit_once('/') {
+            let key = key.replace("~1", "/").replace("~0", "~");
+            match value.pointer_mut(parent) {
+                Some(serde_json::Value::Object(object)) => {
+                    object.remove(&key);
+                }
+                Some(serde_json::Value::Array(array)) => {
+                    if let Ok(index) = key.parse::<usize>() {
+                        if index < array.len() {
+                            array.remove(index);
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+}
+
+/// Collect the JSON pointers below `pointer` where `lhs` and `rhs` differ into `fields`.
+fn diff_values(
+    lhs: &serde_json::Value,
+    rhs: &serde_json::Value,
+    pointer: &str,
+    fields: &mut Vec<String>,
+) {
+    match (lhs, rhs) {
+        (serde_json::Value::Object(lhs), serde_json::Value::Object(rhs)) => {
+            let keys = lhs
+                .keys()
+                .chain(rhs.keys())
+                .collect::<std::collections::BTreeSet<_>>();
+            for key in keys {
+                let child = format!("{pointer}/{}", key.replace('~', "~0").replace('/', "~1"));
+                match (lhs.get(key), rhs.get(key)) {
+                    (Some(lhs), Some(rhs)) => diff_values(lhs, rhs, &child, fields),
+                    _ => fields.push(child),
+                }
+            }
+        }
+        (serde_json::Value::Array(lhs), serde_json::Value::Array(rhs))
+            if lhs.len() == rhs.len() =>
+        {
+            for (i, (lhs, rhs)) in lhs.iter().zip(rhs).enumerate() {
+                diff_values(lhs, rhs, &format!("{pointer}/{i}"), fields);
+            }
+        }
+        (lhs, rhs) => {
+            if lhs != rhs {
+                fields.push(pointer.to_string());
+            }
+        }
+    }
+}
+
+/// Compare the `replayed` responses with the `recordings` ignoring the fields in `ignore`.
+///
+/// Only the status is compared for the recordings without response body.
+pub fn compare(
+    recordings: &[(PathBuf, Recording)],
+    replayed: Vec<(u16, serde_json::Value)>,
+    ignore: &[String],
+) -> ReplayReport {
+    let mut report = ReplayReport {
+        n_replayed: recordings.len(),
+        ..Default::default()
+    };
+    for ((file, recording), (status, mut response)) in recordings.iter().zip(replayed) {
+        if recording.response.is_null() {
+            response = serde_json::Value::Null;
+        }
+        let mut recorded = recording.response.clone();
+        remove_ignored(&mut recorded, ignore);
+        remove_ignored(&mut response, ignore);
+        let mut fields = Vec::new();
+        diff_values(&recorded, &response, "", &mut fields);
+        if status == recording.status && fields.is_empty() {
+            report.n_identical += 1;
+        } else {
+            report.differences.push(Difference {
+                file: file.display().to_string(),
+                method: recording.method.clone(),
+                path: recording.path.clone(),
+                query: recording.query.clone(),
+                status: (status != recording.status).then_some((recording.status, status)),
+                fields,
+            });
+        }
+    }
+    report
+}
+
+/// Main entry point for `server replay` sub command.
+///
+/// # Errors
+///
+/// In the case that loading the data or the recordings failed, or a response differs.
+pub fn run(args_common: &crate::common::Args, args: &Args) -> Result<(), anyhow::Error> {
+    tracing::info!("args_common = {:?}", &args_common);
+    tracing::info!("args = {:?}", &args);
+
+    let data =
+        crate::server::run::load_web_server_data(&args.server, crate::progress::Mode::cli())?;
+    let recordings = record::load(&args.dir)?;
+
+    tracing::info!("Replaying {} requests...", recordings.len());
+    let replayed = actix_web::rt::System::new().block_on(record::replay(
+        Arc::new(data),
+        &recordings
+            .iter()
+            .map(|(_, recording)| recording.clone())
+            .collect::<Vec<_>>(),
+    ))?;
+    let report = compare(&recordings, replayed, &args.ignore);
+
+    let mut output = args
+        .get_output()
+        .map_err(|e| anyhow::anyhow!("Failed to open output file: {}", e))?;
+    serde_json::to_writer_pretty(&mut output, &report)
+        .map_err(|e| anyhow::anyhow!("Failed to write output: {}", e))?;
+    writeln!(output).map_err(|e| anyhow::anyhow!("Failed to write output: {}", e))?;
+
+    if !report.differences.is_empty() {
+        anyhow::bail!(
+            "{} of {} replayed responses differ",
+            report.differences.len(),
+            report.n_replayed
+        );
+    }
+
+    tracing::info!("All done. Have a nice day!");
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use clap::Parser as _;
+
+    use crate::server::run::record::Recording;
+
+    #[test]
+    fn args() {
+        let args = super::Args::parse_from([
+            "replay",
+            "--path-hpo-dir",
+            "tests/data/hpo",
+            "/tmp/recordings",
+        ]);
+
+        assert_eq!(args.dir, PathBuf::from("/tmp/recordings"));
+        assert_eq!(
+            args.ignore,
+            vec![
+                "/meta/compute_time_ms",
+                "/meta/server_version",
+                "/meta/cache_hit"
+            ]
+        );
+    }
+
+    #[test]
+    fn diff_values() {
+        let mut fields = Vec::new();
+        super::diff_values(
+            &serde_json::json!({"result": [{"score": 1.0}, {"score": 2.0}], "a/b": 1, "x": 1}),
+            &serde_json::json!({"result": [{"score": 1.0}, {"score": 2.5}], "a/b": 2, "y": 1}),
+            "",
+            &mut fields,
+        );
+
+        assert_eq!(fields, vec!["/a~1b", "/result/1/score", "/x", "/y"]);
+    }
+
+    #[test]
+    fn compare() {
+        let recording = |response: serde_json::Value| Recording {
+            method: "GET".to_string(),
+            path: "/api/v1/hpo/genes".to_string(),
+            query: "gene_symbol=TGDS".to_string(),
+            tenant: None,
+            content_type: None,
+            body: None,
+            status: 200,
+            response,
+        };
+        let recordings = vec![
+            (
+                PathBuf::from("1.json"),
+                recording(serde_json::json!({"result": [1], "meta": {"compute_time_ms": 1.0}})),
+            ),
+            (
+                PathBuf::from("2.json"),
+                recording(serde_json::json!({"result": [1]})),
+            ),
+            // Streamed and non-JSON responses are not recorded.
+            (PathBuf::from("3.json"), recording(serde_json::Value::Null)),
+        ];
+
+        let report = super::compare(
+            &recordings,
+            vec![
+                (
+                    200,
+                    serde_json::json!({"result": [1], "meta": {"compute_time_ms": 2.0}}),
+                ),
+                (404, serde_json::json!({"result": [2]})),
+                (200, serde_json::json!("format-version: 1.2")),
+            ],
+            &["/meta/compute_time_ms".to_string()],
+        );
+
+        assert_eq!(report.n_replayed, 3);
+        assert_eq!(report.n_identical, 2);
+        assert_eq!(
+            report.differences,
+            vec![super::Difference {
+                file: "2.json".to_string(),
+                method: "GET".to_string(),
+                path: "/api/v1/hpo/genes".to_string(),
+                query: "gene_symbol=TGDS".to_string(),
+                status: Some((200, 404)),
+                fields: vec!["/result/0".to_string()],
+            }]
+        );
+    }
+}
@@ -0,0 +1,142 @@
+//! Writer of the Apache Arrow IPC streaming format.
+//!
+//! Matrix-sized results can be returned as a single record batch of non-nullable UTF-8 and
+//! 32-bit float columns that Python and R clients read directly into data frames, e.g., with
+//! `pyarrow.ipc.open_stream()` or `arrow::read_ipc_stream()`.
+
+use std::sync::Arc;
+
+use arrow_array::{ArrayRef, Float32Array, RecordBatch, StringArray};
+use arrow_ipc::writer::StreamWriter;
+use arrow_schema::{DataType, Field, Schema};
+
+/// The media type of the Arrow IPC streaming format.
+pub const CONTENT_TYPE: &str = "application/vnd.apache.arrow.stream";
+
+/// A column of a record batch.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Column {
+    /// Non-nullable UTF-8 strings.
+    Utf8(Vec<String>),
+    /// Non-nullable 32-bit floats.
+    Float32(Vec<f32>),
+}
+
+impl Column {
+    /// Return the number of values.
+    pub fn len(&self) -> usize {
+        match self {
+            Column::Utf8(values) => values.len(),
+            Column::Float32(values) => values.len(),
+        }
+    }
+
+    /// Return whether the column has no values.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Return the Arrow data type of the column.
+    fn data_type(&self) -> DataType {
+        match self {
+            Column::Utf8(_) => DataType::Utf8,
+            Column::Float32(_) => DataType::Float32,
+        }
+    }
+
+    /// Convert the column into an Arrow array.
+    fn to_array(&self) -> ArrayRef {
+        match self {
+            Column::Utf8(values) => Arc::new(StringArray::from_iter_values(values)),
+            Column::Float32(values) => Arc::new(Float32Array::from(values.clone())),
+        }
+    }
+}
+
+/// Write `columns` with their names as an Arrow IPC stream with one record batch.
+///
+/// # Errors
+///
+/// In the case that the columns have different lengths.
+pub fn write_stream(columns: &[(&str, Column)]) -> Result<Vec<u8>, anyhow::Error> {
+    let schema = Arc::new(Schema::new(
+        columns
+            .iter()
+            .map(|(name, column)| Field::new(*name, column.data_type(), false))
+            .collect::<Vec<_>>(),
+    ));
+    let batch = RecordBatch::try_new(
+        schema.clone(),
+        columns
+            .iter()
+            .map(|(_, column)| column.to_array())
+            .collect(),
+    )?;
+
+    let mut writer = StreamWriter::try_new(Vec::new(), &schema)?;
+    writer.write(&batch)?;
+    writer.finish()?;
+    Ok(writer.into_inner()?)
+}
+
+#[cfg(test)]
+mod test {
+    use arrow_array::{Array as _, Float32Array, StringArray};
+    use arrow_ipc::reader::StreamReader;
+    use arrow_schema::DataType;
+
+    use super::Column;
+
+    #[test]
+    fn write_stream() -> Result<(), anyhow::Error> {
+        let stream = super::write_stream(&[
+            (
+                "lhs",
+                Column::Utf8(vec!["HP:0000001".to_string(), "HP:02".to_string()]),
+            ),
+            ("score", Column::Float32(vec![0.5, 1.25])),
+        ])?;
+
+        let reader = StreamReader::try_new(stream.as_slice(), None)?;
+        let schema = reader.schema();
+        assert_eq!(
+            schema
+                .fields()
+                .iter()
+                .map(|field| (field.name().as_str(), field.data_type().clone()))
+                .collect::<Vec<_>>(),
+            vec![("lhs", DataType::Utf8), ("score", DataType::Float32)]
+        );
+        let batches = reader.collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(batches.len(), 1);
+        let batch = &batches[0];
+        assert_eq!(batch.num_rows(), 2);
+        let lhs = batch
+            .column(0)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .expect("must be UTF-8");
+        assert_eq!(
+            lhs.iter().flatten().collect::<Vec<_>>(),
+            ["HP:0000001", "HP:02"]
+        );
+        let score = batch
+            .column(1)
+            .as_any()
+            .downcast_ref::<Float32Array>()
+            .expect("must be float");
+        assert_eq!(score.values().to_vec(), vec![0.5, 1.25]);
+        assert_eq!(score.null_count(), 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn write_stream_unequal_lengths() {
+        assert!(super::write_stream(&[
+            ("lhs", Column::Utf8(vec!["HP:0000001".to_string()])),
+            ("score", Column::Float32(vec![])),
+        ])
+        .is_err());
+    }
+}
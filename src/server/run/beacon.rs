@@ -0,0 +1,378 @@
+//! Implementation of `/beacon`, answering whether registered patients or diseases have
+//! phenotypes.
+
+use std::{str::FromStr, sync::Arc};
+
+use actix_web::{
+    get,
+    http::StatusCode,
+    web::{Data, Json, Path},
+};
+use hpo::{annotations::Disease as _, term::HpoGroup, HpoTermId, Ontology};
+use strum::{EnumIter, IntoEnumIterator as _};
+
+use crate::{common::Version, server::run::WebServerData};
+
+use super::{
+    middleware::{ComputeTimer, ResolvedFrom},
+    CustomError,
+};
+use crate::server::run::strict_query::StrictQuery;
+use crate::server::run::tenants::TenantScope;
+
+/// The records a beacon query is answered for.
+#[derive(
+    Default,
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    derive_more::Display,
+    serde::Serialize,
+    serde::Deserialize,
+    utoipa::ToSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum BeaconSource {
+    /// The OMIM diseases of the ontology.
+    #[default]
+    #[display("diseases")]
+    Diseases,
+    /// The patients of the patient registry.
+    #[display("patients")]
+    Patients,
+}
+
+/// How much a beacon answer reveals, from the least to the most.
+#[derive(
+    Default,
+    Debug,
+    Clone,
+    Copy,
+    EnumIter,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    derive_more::Display,
+    serde::Serialize,
+    serde::Deserialize,
+    utoipa::ToSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum BeaconGranularity {
+    /// Only whether there is a matching record.
+    #[default]
+    #[display("boolean")]
+    Boolean,
+    /// Also the number of matching records.
+    #[display("count")]
+    Count,
+}
+
+impl FromStr for BeaconGranularity {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        BeaconGranularity::iter()
+            .find(|m| m.to_string().as_str().eq(s))
+            .ok_or(anyhow::anyhow!("unknown beacon granularity: {}", s))
+    }
+}
+
+/// Settings for the beacon endpoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BeaconConfig {
+    /// The most revealing granularity answered for the diseases.
+    pub max_granularity_diseases: BeaconGranularity,
+    /// The most revealing granularity answered for the patients.
+    pub max_granularity_patients: BeaconGranularity,
+}
+
+impl Default for BeaconConfig {
+    fn default() -> Self {
+        Self {
+            max_granularity_diseases: BeaconGranularity::Count,
+            max_granularity_patients: BeaconGranularity::Boolean,
+        }
+    }
+}
+
+impl BeaconConfig {
+    /// Construct from the command line arguments of the server.
+    pub fn from_args(args: &super::Args) -> Self {
+        Self {
+            max_granularity_diseases: args.beacon_max_granularity_diseases,
+            max_granularity_patients: args.beacon_max_granularity_patients,
+        }
+    }
+
+    /// Return the most revealing granularity answered for `source`.
+    pub fn max_granularity(&self, source: BeaconSource) -> BeaconGranularity {
+        match source {
+            BeaconSource::Diseases => self.max_granularity_diseases,
+            BeaconSource::Patients => self.max_granularity_patients,
+        }
+    }
+}
+
+/// Parameters for `handle`.
+///
+/// - `terms` -- set of terms that a record must all have, or descendants of them
+/// - `source` -- whether to query the diseases or the registered patients
+/// - `granularity` -- whether to answer with yes/no only or also with the count
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::IntoParams)]
+#[serde(deny_unknown_fields)]
+#[into_params(parameter_in = Query)]
+pub struct RequestQuery {
+    /// The set of terms that a record must all have, or descendants of them.
+    #[serde(deserialize_with = "super::vec_str_deserialize")]
+    pub terms: Vec<String>,
+    /// Whether to query the diseases or the registered patients.
+    #[serde(default)]
+    pub source: BeaconSource,
+    /// Whether to answer with yes/no only or also with the count.
+    #[serde(default)]
+    pub granularity: BeaconGranularity,
+}
+
+/// Request as sent together with the response.
+///
+/// The difference is that the `terms` field is replaced by a vec.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct BeaconQuery {
+    /// The set of terms in canonical form, unknown terms are dropped.
+    pub terms: Vec<String>,
+    /// The terms of the query that were dropped, i.e., unknown terms and obsolete terms
+    /// without replacement.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dropped_terms: Vec<String>,
+    /// Whether the diseases or the registered patients were queried.
+    pub source: BeaconSource,
+    /// Whether the answer is yes/no only or also the count.
+    pub granularity: BeaconGranularity,
+}
+
+/// Container for the result.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct BeaconResult {
+    /// Version information.
+    pub version: Version,
+    /// The original query.
+    pub query: BeaconQuery,
+    /// Whether any record has all terms.
+    pub exists: bool,
+    /// The number of records with all terms, with count granularity only.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub count: Option<usize>,
+}
+
+/// Return whether `terms` contain each of `query` or a descendant of it.
+fn has_phenotypes(terms: &HpoGroup, query: &[HpoTermId], ontology: &Ontology) -> bool {
+    query.iter().all(|query_id| {
+        terms.iter().any(|term_id| {
+            term_id == *query_id
+                || ontology
+                    .hpo(term_id)
+                    .is_some_and(|term| term.all_parent_ids().contains(query_id))
+        })
+    })
+}
+
+/// Query whether any disease or registered patient has the given phenotypes.
+///
+/// A record matches if it has each of the terms or a descendant of it.  By default, only
+/// whether a record matches is revealed; with `granularity=count` also the number of
+/// matching records, if the server allows it for the source, by default for the diseases
+/// only.  Querying the patients requires the patient registry.
+///
+/// # Errors
+///
+/// In the case that there is an error running the server.
+#[utoipa::path(
+    get,
+    operation_id = "beacon",
+    params(RequestQuery),
+    responses(
+        (status = 200, description = "The query was successful.", body = BeaconResult),
+        (status = 400, description = "A term ID is malformed or no query term is known.", body = CustomError),
+        (status = 403, description = "The granularity is not allowed for the source.", body = CustomError),
+        (status = 404, description = "The patient registry is not enabled.", body = CustomError),
+        (status = 422, description = "The request exceeds a size or complexity limit.", body = CustomError),
+        (status = 500, description = "The server encountered an error.", body = CustomError),
+        (status = 503, description = "The server is low on memory.", body = CustomError)
+    )
+)]
+#[get("/api/v1/beacon")]
+async fn handle(
+    data: Data<Arc<WebServerData>>,
+    tenant: TenantScope,
+    _path: Path<()>,
+    query: StrictQuery<RequestQuery>,
+    resolved_from: ResolvedFrom,
+    _timer: ComputeTimer,
+) -> actix_web::Result<Json<BeaconResult>, CustomError> {
+    let ontology = &data.ontology;
+    let RequestQuery {
+        terms,
+        source,
+        granularity,
+    } = query.into_inner();
+
+    let max_granularity = data.beacon_config.max_granularity(source);
+    if granularity > max_granularity {
+        return Err(CustomError::with_status(
+            StatusCode::FORBIDDEN,
+            anyhow::anyhow!(
+                "granularity {} is not allowed for the {}, at most {}",
+                granularity,
+                source,
+                max_granularity
+            ),
+        ));
+    }
+
+    let parsed = super::parse_term_ids(&terms, &data, &resolved_from)?;
+    let dropped_terms = parsed
+        .iter()
+        .filter(|term_id| {
+            super::to_hpo_group([**term_id], &data, &ResolvedFrom::default()).is_empty()
+        })
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+    let term_ids = super::to_hpo_group(parsed, &data, &resolved_from)
+        .iter()
+        .collect::<Vec<_>>();
+    if term_ids.is_empty() {
+        return Err(CustomError::with_status(
+            StatusCode::BAD_REQUEST,
+            anyhow::anyhow!("no known query terms: {}", terms.join(",")),
+        ));
+    }
+
+    let count = match source {
+        BeaconSource::Diseases => ontology
+            .omim_diseases()
+            .filter(|disease| has_phenotypes(disease.hpo_terms(), &term_ids, ontology))
+            .count(),
+        BeaconSource::Patients => super::patients::registry(&data, &tenant)?
+            .count(|terms| has_phenotypes(terms, &term_ids, ontology)),
+    };
+
+    Ok(Json(BeaconResult {
+        version: Version::new(&ontology.hpo_version()),
+        query: BeaconQuery {
+            terms: term_ids.iter().map(ToString::to_string).collect(),
+            dropped_terms,
+            source,
+            granularity,
+        },
+        exists: count > 0,
+        count: (granularity == BeaconGranularity::Count).then_some(count),
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use crate::server::run::hpo_genes::test::web_server_data;
+
+    /// Helper function for running a query.
+    async fn run_query(
+        web_server_data: Arc<crate::server::run::WebServerData>,
+        uri: &str,
+    ) -> Result<super::BeaconResult, anyhow::Error> {
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(actix_web::web::Data::new(web_server_data))
+                .service(super::handle),
+        )
+        .await;
+        let req = actix_web::test::TestRequest::get().uri(uri).to_request();
+        let resp: super::BeaconResult = actix_web::test::call_and_read_body_json(&app, req).await;
+
+        Ok(resp)
+    }
+
+    #[rstest::rstest]
+    #[actix_web::test]
+    async fn beacon_diseases(
+        web_server_data: &Arc<crate::server::run::WebServerData>,
+    ) -> Result<(), anyhow::Error> {
+        // "Seizure" is matched by diseases annotated with its descendants.
+        let boolean = run_query(web_server_data.clone(), "/api/v1/beacon?terms=HP:0001250").await?;
+        assert!(boolean.exists);
+        assert!(boolean.count.is_none());
+
+        let count = run_query(
+            web_server_data.clone(),
+            "/api/v1/beacon?terms=HP:0001250&granularity=count",
+        )
+        .await?;
+        let narrower = run_query(
+            web_server_data.clone(),
+            "/api/v1/beacon?terms=HP:0001250,HP:0000347&granularity=count",
+        )
+        .await?;
+        assert!(count.count.expect("count requested") >= narrower.count.expect("count requested"));
+
+        let dropped = run_query(
+            web_server_data.clone(),
+            "/api/v1/beacon?terms=HP:0001250,HP:9999999",
+        )
+        .await?;
+        assert_eq!(dropped.query.terms, vec!["HP:0001250"]);
+        assert_eq!(dropped.query.dropped_terms, vec!["HP:9999999"]);
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case(
+        "/api/v1/beacon?terms=HP:9999999",
+        actix_web::http::StatusCode::BAD_REQUEST
+    )]
+    #[case("/api/v1/beacon?terms=", actix_web::http::StatusCode::BAD_REQUEST)]
+    #[case(
+        "/api/v1/beacon?terms=HP:0001250&source=patients&granularity=count",
+        actix_web::http::StatusCode::FORBIDDEN
+    )]
+    #[actix_web::test]
+    async fn beacon_rejected(
+        web_server_data: &Arc<crate::server::run::WebServerData>,
+        #[case] uri: &str,
+        #[case] expected: actix_web::http::StatusCode,
+    ) {
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(actix_web::web::Data::new(web_server_data.clone()))
+                .service(super::handle),
+        )
+        .await;
+        let req = actix_web::test::TestRequest::get().uri(uri).to_request();
+        let resp = actix_web::test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), expected);
+    }
+
+    #[test]
+    fn max_granularity() {
+        let config = super::BeaconConfig::default();
+
+        assert_eq!(
+            config.max_granularity(super::BeaconSource::Diseases),
+            super::BeaconGranularity::Count
+        );
+        assert_eq!(
+            config.max_granularity(super::BeaconSource::Patients),
+            super::BeaconGranularity::Boolean
+        );
+        assert_eq!(
+            "count".parse::<super::BeaconGranularity>().ok(),
+            Some(super::BeaconGranularity::Count)
+        );
+        assert!(super::BeaconGranularity::Boolean < super::BeaconGranularity::Count);
+    }
+}
@@ -0,0 +1,269 @@
+//! Default query parameters per route from the `[defaults]` section of the configuration
+//! file.
+//!
+//! The defaults are applied by `StrictQuery` to the parameters not given by the client and
+//! reported in the `applied_defaults` field of the response metadata, e.g.,
+//!
+//! ```toml
+//! [defaults.hpo-sim-term-gene]
+//! method = "ensemble"
+//! normalize = "max_ic"
+//! experimental = ["weighted-combiner"]
+//! ```
+
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashSet},
+    rc::Rc,
+};
+
+use utoipa::openapi::path::ParameterIn;
+
+use super::{routes::Route, strict_query::GLOBAL_PARAMS};
+
+/// Default query parameters applied by `StrictQuery`, shared with `add_response_meta`.
+///
+/// These are the configured defaults and the defaults of the query structs.
+pub type SharedAppliedDefaults = Rc<RefCell<BTreeMap<String, serde_json::Value>>>;
+
+/// Default query parameters by route.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QueryDefaults(BTreeMap<Route, BTreeMap<String, String>>);
+
+/// Convert a TOML value to the value of a query parameter, lists are comma-separated.
+fn query_value(value: &toml_edit::Value) -> Result<String, anyhow::Error> {
+    Ok(match value {
+        toml_edit::Value::String(value) => value.value().clone(),
+        toml_edit::Value::Integer(value) => value.value().to_string(),
+        toml_edit::Value::Float(value) => value.value().to_string(),
+        toml_edit::Value::Boolean(value) => value.value().to_string(),
+        toml_edit::Value::Array(values) => values
+            .iter()
+            .map(query_value)
+            .collect::<Result<Vec<_>, _>>()?
+            .join(","),
+        value => anyhow::bail!("unsupported {} value", value.type_name()),
+    })
+}
+
+impl QueryDefaults {
+    /// Parse the `[defaults]` section of the configuration file `content`.
+    ///
+    /// # Errors
+    ///
+    /// In the case that the content is no valid TOML, has other sections, or names an
+    /// unknown route or query parameter.
+    pub fn from_toml(content: &str) -> Result<Self, anyhow::Error> {
+        let document = content
+            .parse::<toml_edit::DocumentMut>()
+            .map_err(|e| anyhow::anyhow!("invalid configuration file: {}", e))?;
+        let openapi = <super::ApiDoc as utoipa::OpenApi>::openapi();
+
+        let mut result = BTreeMap::new();
+        for (section, item) in document.iter() {
+            if section != "defaults" {
+                anyhow::bail!("unknown configuration section: [{}]", section);
+            }
+            let routes = item
+                .as_table_like()
+                .ok_or_else(|| anyhow::anyhow!("[defaults] must be a table"))?;
+            for (route_name, item) in routes.iter() {
+                let route = route_name.parse::<Route>()?;
+                let params = item
+                    .as_table_like()
+                    .ok_or_else(|| anyhow::anyhow!("[defaults.{}] must be a table", route))?;
+                let known = openapi
+                    .paths
+                    .paths
+                    .get(route.path())
+                    .and_then(|path_item| path_item.get.as_ref())
+                    .and_then(|operation| operation.parameters.as_ref())
+                    .into_iter()
+                    .flatten()
+                    .filter(|param| {
+                        param.parameter_in == ParameterIn::Query
+                            && !GLOBAL_PARAMS.contains(&param.name.as_str())
+                    })
+                    .map(|param| param.name.clone())
+                    .collect::<Vec<_>>();
+                let mut defaults = BTreeMap::new();
+                for (name, item) in params.iter() {
+                    if !known.iter().any(|known| known == name) {
+                        anyhow::bail!(
+                            "unknown query parameter {} in [defaults.{}]{}",
+                            name,
+                            route,
+                            super::strict_query::closest(name, &known)
+                                .map(|suggestion| format!(" (did you mean {suggestion}?)"))
+                                .unwrap_or_default()
+                        );
+                    }
+                    let value = item
+                        .as_value()
+                        .ok_or_else(|| anyhow::anyhow!("{} must be a value", name))
+                        .and_then(query_value)
+                        .map_err(|e| anyhow::anyhow!("in [defaults.{}]: {}", route, e))?;
+                    defaults.insert(name.to_string(), value);
+                }
+                result.insert(route, defaults);
+            }
+        }
+
+        Ok(Self(result))
+    }
+
+    /// Load the `[defaults]` section of the configuration file at `path`.
+    ///
+    /// # Errors
+    ///
+    /// In the case that the file cannot be read or is invalid.
+    pub fn load<P: AsRef<std::path::Path>>(path: P) -> Result<Self, anyhow::Error> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            anyhow::anyhow!("cannot read configuration file {}: {}", path.display(), e)
+        })?;
+        Self::from_toml(&content)
+            .map_err(|e| anyhow::anyhow!("in configuration file {}: {}", path.display(), e))
+    }
+
+    /// Return the defaults of `route`.
+    pub fn get(&self, route: Route) -> Option<&BTreeMap<String, String>> {
+        self.0.get(&route)
+    }
+
+    /// Return the number of routes with defaults.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Return whether no defaults are configured.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Append the defaults of `route` that are not in `given` to `query_string`.
+    ///
+    /// Returns the query string and the applied defaults.
+    pub fn apply(
+        &self,
+        route: Route,
+        query_string: &str,
+        given: &HashSet<String>,
+    ) -> (String, BTreeMap<String, String>) {
+        let applied = self
+            .get(route)
+            .into_iter()
+            .flatten()
+            .filter(|(name, _)| !given.contains(*name))
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect::<BTreeMap<_, _>>();
+        if applied.is_empty() {
+            return (query_string.to_string(), applied);
+        }
+        let encoded = serde_urlencoded::to_string(&applied).unwrap_or_default();
+        let query_string = if query_string.is_empty() {
+            encoded
+        } else {
+            format!("{query_string}&{encoded}")
+        };
+        (query_string, applied)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::{BTreeMap, HashSet};
+
+    use super::QueryDefaults;
+    use crate::server::run::routes::Route;
+
+    #[test]
+    fn from_toml() -> Result<(), anyhow::Error> {
+        let defaults = QueryDefaults::from_toml(
+            r#"
+            [defaults.hpo-sim-term-gene]
+            method = "ensemble"
+            network = true
+            network_weight = 0.25
+            experimental = ["rwr-network", "weighted-combiner"]
+
+            [defaults]
+            hpo-genes = { max_results = 5 }
+            "#,
+        )?;
+
+        assert_eq!(defaults.len(), 2);
+        assert_eq!(
+            defaults.get(Route::HpoSimTermGene),
+            Some(&BTreeMap::from([
+                (
+                    "experimental".to_string(),
+                    "rwr-network,weighted-combiner".to_string()
+                ),
+                ("method".to_string(), "ensemble".to_string()),
+                ("network".to_string(), "true".to_string()),
+                ("network_weight".to_string(), "0.25".to_string()),
+            ]))
+        );
+        assert_eq!(
+            defaults.get(Route::HpoGenes),
+            Some(&BTreeMap::from([(
+                "max_results".to_string(),
+                "5".to_string()
+            )]))
+        );
+        assert!(QueryDefaults::from_toml("")?.is_empty());
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case("[server]\nport = 8080", "unknown configuration section: [server]")]
+    #[case("[defaults.jobs]\nx = 1", "unknown route: jobs")]
+    #[case(
+        "[defaults.hpo-sim-term-gene]\nmethd = \"ensemble\"",
+        "unknown query parameter methd in [defaults.hpo-sim-term-gene] (did you mean method?)"
+    )]
+    #[case(
+        "[defaults.hpo-genes]\nhpo_release = \"2023-06-06\"",
+        "unknown query parameter hpo_release in [defaults.hpo-genes]"
+    )]
+    #[case(
+        "[defaults.hpo-genes]\nmax_results = { n = 5 }",
+        "in [defaults.hpo-genes]: unsupported inline table value"
+    )]
+    fn from_toml_invalid(#[case] content: &str, #[case] expected: &str) {
+        let err = QueryDefaults::from_toml(content).expect_err("must fail");
+
+        assert_eq!(err.to_string(), expected);
+    }
+
+    #[test]
+    fn apply() -> Result<(), anyhow::Error> {
+        let defaults = QueryDefaults::from_toml(
+            "[defaults.hpo-sim-term-gene]\nmethod = \"ensemble\"\nexperimental = [\"a\", \"b\"]",
+        )?;
+        let given = HashSet::from(["terms".to_string(), "method".to_string()]);
+
+        let (query_string, applied) = defaults.apply(
+            Route::HpoSimTermGene,
+            "terms=HP:0001250&method=phenomizer",
+            &given,
+        );
+
+        assert_eq!(
+            query_string,
+            "terms=HP:0001250&method=phenomizer&experimental=a%2Cb"
+        );
+        assert_eq!(
+            applied,
+            BTreeMap::from([("experimental".to_string(), "a,b".to_string())])
+        );
+        assert_eq!(
+            defaults.apply(Route::HpoGenes, "gene_symbol=TGDS", &HashSet::new()),
+            ("gene_symbol=TGDS".to_string(), BTreeMap::new())
+        );
+
+        Ok(())
+    }
+}
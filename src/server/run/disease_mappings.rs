@@ -0,0 +1,265 @@
+//! Implementation of `/diseases/mappings/{system}/{code}`.
+
+use std::sync::Arc;
+
+use actix_web::{
+    get,
+    http::StatusCode,
+    web::{Data, Json, Path},
+};
+use hpo::annotations::{Disease, OrphaDiseaseId};
+
+use crate::{
+    common::Version,
+    server::run::WebServerData,
+    sources::{
+        disease_codes::{DiseaseCodes, Icd10Code},
+        prevalence::{DiseasePrevalences, Prevalence},
+    },
+};
+
+use super::{middleware::ComputeTimer, strict_query::StrictQuery, CustomError};
+
+/// The disease code systems that can be translated.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize, utoipa::ToSchema,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum DiseaseCodeSystem {
+    /// ICD-10 codes, e.g., `Q87.4`.
+    Icd10,
+    /// ORPHA codes, e.g., `ORPHA:558`.
+    Orpha,
+    /// OMIM IDs, e.g., `OMIM:154700`.
+    Omim,
+}
+
+/// Parameters for `handle`.
+///
+/// - `lang` -- language of the disease names, falls back to English
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize, utoipa::IntoParams)]
+#[serde(deny_unknown_fields)]
+#[into_params(parameter_in = Query)]
+pub struct RequestQuery {
+    /// Language of the disease names, e.g., `de`; untranslated names are in English.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub lang: Option<String>,
+}
+
+/// The disease code as sent together with the response.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct DiseaseMappingsQuery {
+    /// The code system.
+    pub system: DiseaseCodeSystem,
+    /// The code as given in the query.
+    pub code: String,
+    /// Language of the disease names.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub lang: Option<String>,
+}
+
+/// An Orphanet disease with its codes.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct DiseaseMappingsResultEntry {
+    /// The ORPHA code, e.g., `ORPHA:558`.
+    pub orpha_id: String,
+    /// The disease name, if the disease is annotated in the HPO or translated.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// The ICD-10 codes of the disease.
+    pub icd10_codes: Vec<Icd10Code>,
+    /// The OMIM IDs of the disease, e.g., `OMIM:154700`.
+    pub omim_ids: Vec<String>,
+    /// The most representative prevalence of the disease, if loaded with
+    /// `--path-orphanet-prevalence`.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub prevalence: Option<Prevalence>,
+}
+
+/// Container for the result.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct DiseaseMappingsResult {
+    /// Version information.
+    pub version: Version,
+    /// The original query.
+    pub query: DiseaseMappingsQuery,
+    /// The Orphanet diseases with the code, sorted by ORPHA code.
+    pub result: Vec<DiseaseMappingsResultEntry>,
+}
+
+/// Parse the `code` of `system` and return the ORPHA codes it maps to.
+fn orpha_codes(
+    system: DiseaseCodeSystem,
+    code: &str,
+    disease_codes: &DiseaseCodes,
+) -> Result<Vec<u32>, CustomError> {
+    let parse = |value: &str| {
+        value.trim().parse::<u32>().map_err(|e| {
+            CustomError::with_status(
+                StatusCode::BAD_REQUEST,
+                anyhow::anyhow!("invalid disease code {}: {}", code, e),
+            )
+        })
+    };
+    Ok(match system {
+        DiseaseCodeSystem::Icd10 => disease_codes.orpha_codes_for_icd10(code).to_vec(),
+        DiseaseCodeSystem::Orpha => {
+            let trimmed = code.trim();
+            let orpha_code = parse(
+                trimmed
+                    .get(..6)
+                    .filter(|prefix| prefix.eq_ignore_ascii_case("orpha:"))
+                    .map_or(trimmed, |_| &trimmed[6..]),
+            )?;
+            disease_codes
+                .orpha(orpha_code)
+                .map(|_| orpha_code)
+                .into_iter()
+                .collect()
+        }
+        DiseaseCodeSystem::Omim => {
+            let omim_id = parse(super::hpo_omims::strip_omim_prefix(code.trim()))?;
+            disease_codes.orpha_codes_for_omim(omim_id).to_vec()
+        }
+    })
+}
+
+/// Translate a disease code between ICD-10, ORPHA, and OMIM.
+///
+/// The code is translated by Orphanet's cross-references into the Orphanet diseases
+/// it belongs to, each with its ICD-10 codes and OMIM IDs.
+///
+/// # Errors
+///
+/// In the case that there is an error running the server.
+#[allow(clippy::unused_async)]
+#[utoipa::path(
+    get,
+    operation_id = "diseaseMappings",
+    params(
+        ("system" = DiseaseCodeSystem, Path, description = "The code system."),
+        ("code" = String, Path, description = "The disease code, e.g., `Q87.4`."),
+        RequestQuery,
+    ),
+    responses(
+        (status = 200, description = "The query was successful.", body = DiseaseMappingsResult),
+        (status = 400, description = "The code is malformed.", body = CustomError),
+        (status = 404, description = "The code system is unknown or no Orphanet mappings were loaded.", body = CustomError),
+        (status = 500, description = "The server encountered an error.", body = CustomError)
+    )
+)]
+#[get("/api/v1/diseases/mappings/{system}/{code}")]
+async fn handle(
+    data: Data<Arc<WebServerData>>,
+    path: Path<(DiseaseCodeSystem, String)>,
+    query: StrictQuery<RequestQuery>,
+    _timer: ComputeTimer,
+) -> actix_web::Result<Json<DiseaseMappingsResult>, CustomError> {
+    if data.disease_codes.is_empty() {
+        return Err(CustomError::with_status(
+            StatusCode::NOT_FOUND,
+            anyhow::anyhow!("no Orphanet mappings were loaded on this server"),
+        ));
+    }
+    let (system, code) = path.into_inner();
+    let RequestQuery { lang } = query.into_inner();
+
+    let result = orpha_codes(system, &code, &data.disease_codes)?
+        .into_iter()
+        .filter_map(|orpha_code| {
+            let codes = data.disease_codes.orpha(orpha_code)?;
+            Some(DiseaseMappingsResultEntry {
+                orpha_id: format!("ORPHA:{orpha_code}"),
+                name: lang
+                    .as_ref()
+                    .and_then(|lang| data.disease_translations.get(lang, orpha_code))
+                    .or_else(|| {
+                        data.ontology
+                            .orpha_disease(&OrphaDiseaseId::from(orpha_code))
+                            .map(Disease::name)
+                    })
+                    .map(str::to_string),
+                icd10_codes: codes.icd10_codes.clone(),
+                omim_ids: codes
+                    .omim_ids
+                    .iter()
+                    .map(|omim_id| format!("OMIM:{omim_id}"))
+                    .collect(),
+                prevalence: data
+                    .sources
+                    .get::<DiseasePrevalences>()
+                    .and_then(|prevalences| prevalences.best(orpha_code))
+                    .cloned(),
+            })
+        })
+        .collect();
+
+    Ok(Json(DiseaseMappingsResult {
+        version: Version::new(&data.ontology.hpo_version()),
+        query: DiseaseMappingsQuery { system, code, lang },
+        result,
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use crate::{
+        server::run::hpo_genes::test::web_server_data,
+        sources::disease_codes::{DiseaseCodes, Record},
+    };
+
+    use super::DiseaseCodeSystem;
+
+    #[rstest::rstest]
+    #[case(DiseaseCodeSystem::Icd10, "q87.4", &[558])]
+    #[case(DiseaseCodeSystem::Icd10, "G40", &[])]
+    #[case(DiseaseCodeSystem::Orpha, "ORPHA:558", &[558])]
+    #[case(DiseaseCodeSystem::Orpha, "558", &[558])]
+    #[case(DiseaseCodeSystem::Orpha, "1234", &[])]
+    #[case(DiseaseCodeSystem::Omim, "OMIM:154700", &[558])]
+    fn orpha_codes(
+        #[case] system: DiseaseCodeSystem,
+        #[case] code: &str,
+        #[case] expected: &[u32],
+    ) -> Result<(), anyhow::Error> {
+        let record = |source: &str, reference: &str| Record {
+            orpha_code: 558,
+            source: source.to_string(),
+            reference: reference.to_string(),
+            mapping_relation: None,
+        };
+        let disease_codes =
+            DiseaseCodes::from_records([record("ICD-10", "Q87.4"), record("OMIM", "154700")]);
+
+        assert_eq!(
+            super::orpha_codes(system, code, &disease_codes).map_err(|e| anyhow::anyhow!(e))?,
+            expected
+        );
+        assert!(super::orpha_codes(DiseaseCodeSystem::Omim, "MONDO:1", &disease_codes).is_err());
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[actix_web::test]
+    async fn disease_mappings_not_loaded(
+        web_server_data: &Arc<crate::server::run::WebServerData>,
+    ) -> Result<(), anyhow::Error> {
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(actix_web::web::Data::new(web_server_data.clone()))
+                .service(super::handle),
+        )
+        .await;
+        let req = actix_web::test::TestRequest::get()
+            .uri("/api/v1/diseases/mappings/icd10/Q87.4")
+            .to_request();
+        let resp = actix_web::test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
+
+        Ok(())
+    }
+}
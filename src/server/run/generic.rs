@@ -0,0 +1,840 @@
+//! Generic ontology mode for search-only use of arbitrary OBO ontologies, e.g., MAXO.
+//!
+//! With `server run --generic-obo foo.obo`, the server loads only the OBO file and serves
+//! the term search, detail, and hierarchy endpoints below `/api/v1/obo`.  The HPO-specific
+//! annotation features, e.g., genes and diseases, are not available in this mode.
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    sync::Arc,
+    time::Duration,
+};
+
+use actix_web::{
+    get,
+    http::{Method, StatusCode},
+    middleware::{from_fn, Logger},
+    web::{Data, Json, Path},
+    App, HttpServer,
+};
+use tantivy::schema::Value as _;
+use utoipa::OpenApi as _;
+
+use crate::{
+    common::version,
+    index::{ident_to_string, Index},
+    sources::translations::Translations,
+};
+
+use super::{
+    middleware::{DebugTimingsDoc, ExpectedReleaseDoc, ResponseMetaDoc},
+    strict_query::StrictQuery,
+    CustomError,
+};
+
+/// A term of the generic ontology.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GenericTerm {
+    /// The term ID, e.g., `MAXO:0000004`.
+    pub term_id: String,
+    /// The term's name.
+    pub name: String,
+    /// The term's definition, if any.
+    pub definition: Option<String>,
+    /// The term's synonyms.
+    pub synonyms: Vec<String>,
+    /// The term's xrefs.
+    pub xrefs: Vec<String>,
+    /// The IDs of the `is_a` parents.
+    pub parents: Vec<String>,
+    /// The IDs of the `is_a` children.
+    pub children: Vec<String>,
+    /// Whether the term is obsolete.
+    pub obsolete: bool,
+}
+
+/// An OBO ontology loaded in generic ontology mode with its full text index.
+pub struct GenericOntology {
+    /// The ontology name from the `ontology` header clause, e.g., `maxo`.
+    pub ontology: String,
+    /// The version from the `data-version` header clause, if any.
+    pub data_version: Option<String>,
+    /// The terms by term ID.
+    terms: BTreeMap<String, GenericTerm>,
+    /// The full text index of the terms.
+    index: Index,
+}
+
+impl GenericOntology {
+    /// Load the OBO file at `path` and build the full text index.
+    ///
+    /// # Errors
+    ///
+    /// In the case that the file cannot be parsed or the index cannot be built.
+    pub fn from_path<P: AsRef<std::path::Path>>(path: P) -> Result<Self, anyhow::Error> {
+        let doc = fastobo::from_file(path.as_ref())
+            .map_err(|e| anyhow::anyhow!("could not parse OBO file {:?}: {}", path.as_ref(), e))?;
+        Self::from_doc(doc)
+    }
+
+    /// Build from the OBO document `doc`.
+    ///
+    /// # Errors
+    ///
+    /// In the case that the index cannot be built.
+    pub fn from_doc(doc: fastobo::ast::OboDoc) -> Result<Self, anyhow::Error> {
+        let mut ontology = None;
+        let mut data_version = None;
+        for clause in doc.header() {
+            match clause {
+                fastobo::ast::HeaderClause::Ontology(name) => {
+                    ontology = Some(name.as_str().to_string());
+                }
+                fastobo::ast::HeaderClause::DataVersion(version) => {
+                    data_version = Some(version.as_str().to_string());
+                }
+                _ => (),
+            }
+        }
+        let ontology = ontology.unwrap_or_else(|| "obo".to_string());
+
+        let mut terms = BTreeMap::new();
+        for term_frame in doc
+            .entities()
+            .iter()
+            .filter_map(fastobo::ast::EntityFrame::as_term)
+        {
+            let mut term = GenericTerm {
+                term_id: ident_to_string(term_frame.id().as_inner().as_ref()),
+                name: String::new(),
+                definition: None,
+                synonyms: Vec::new(),
+                xrefs: Vec::new(),
+                parents: Vec::new(),
+                children: Vec::new(),
+                obsolete: false,
+            };
+            for line in term_frame.clauses() {
+                match line.as_inner() {
+                    fastobo::ast::TermClause::Name(name) => term.name = name.as_str().to_string(),
+                    fastobo::ast::TermClause::Def(def) => {
+                        term.definition = Some(def.text().as_str().to_string());
+                    }
+                    fastobo::ast::TermClause::Synonym(synonym) => {
+                        term.synonyms
+                            .push(synonym.description().as_str().to_string());
+                    }
+                    fastobo::ast::TermClause::Xref(xref) => {
+                        term.xrefs.push(ident_to_string(xref.id()));
+                    }
+                    fastobo::ast::TermClause::IsA(parent) => {
+                        term.parents.push(ident_to_string(parent.as_ref().as_ref()));
+                    }
+                    fastobo::ast::TermClause::IsObsolete(obsolete) => term.obsolete = *obsolete,
+                    _ => (),
+                }
+            }
+            terms.insert(term.term_id.clone(), term);
+        }
+
+        let mut children = HashMap::<_, Vec<_>>::new();
+        for term in terms.values() {
+            for parent in &term.parents {
+                children
+                    .entry(parent.clone())
+                    .or_default()
+                    .push(term.term_id.clone());
+            }
+        }
+        for (parent, mut term_children) in children {
+            if let Some(term) = terms.get_mut(&parent) {
+                term_children.sort();
+                term.children = term_children;
+            }
+        }
+
+        let index = Index::with_namespace(doc, &Translations::default(), &ontology)?;
+
+        Ok(Self {
+            ontology,
+            data_version,
+            terms,
+            index,
+        })
+    }
+
+    /// Return the term with `term_id`, if any.
+    pub fn term(&self, term_id: &str) -> Option<&GenericTerm> {
+        self.terms.get(term_id)
+    }
+
+    /// Return the number of terms.
+    pub fn len(&self) -> usize {
+        self.terms.len()
+    }
+
+    /// Return whether the ontology has no terms.
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    /// Return the IDs of the ancestors of `term_id` along `is_a` relations.
+    pub fn ancestors(&self, term_id: &str) -> BTreeSet<String> {
+        let mut result = BTreeSet::new();
+        let mut stack = self
+            .term(term_id)
+            .map(|term| term.parents.clone())
+            .unwrap_or_default();
+        while let Some(ancestor) = stack.pop() {
+            if result.insert(ancestor.clone()) {
+                if let Some(term) = self.term(&ancestor) {
+                    stack.extend(term.parents.iter().cloned());
+                }
+            }
+        }
+        result
+    }
+
+    /// Search the names, synonyms, definitions, and IDs of the terms for `name`.
+    ///
+    /// Returns at most `max_results` terms, best matches first.
+    ///
+    /// # Errors
+    ///
+    /// In the case that `name` cannot be parsed or searching the index fails.
+    pub fn search(
+        &self,
+        name: &str,
+        max_results: usize,
+    ) -> Result<Vec<&GenericTerm>, anyhow::Error> {
+        let schema = self.index.schema();
+        let field_term_id = schema.get_field("term_id")?;
+        let field_name = schema.get_field("name")?;
+        let field_def = schema.get_field("def")?;
+        let field_synonym = schema.get_field("synonym")?;
+        let mut query_parser = tantivy::query::QueryParser::for_index(
+            self.index.index(),
+            vec![
+                field_term_id,
+                schema.get_field("alt_id")?,
+                field_name,
+                field_def,
+                field_synonym,
+                schema.get_field("xref")?,
+            ],
+        );
+        query_parser.set_conjunction_by_default();
+        query_parser.set_field_boost(field_name, 3.0);
+        query_parser.set_field_boost(field_synonym, 0.8);
+        query_parser.set_field_boost(field_def, 0.6);
+        query_parser.set_field_fuzzy(field_name, true, 1, true);
+        query_parser.set_field_fuzzy(field_def, true, 1, true);
+        query_parser.set_field_fuzzy(field_synonym, true, 1, true);
+
+        let name = if name.contains(':') {
+            format!("\"{name}\"")
+        } else {
+            name.to_string()
+        };
+        let index_query = query_parser
+            .parse_query(&name)
+            .map_err(|e| anyhow::anyhow!("Error parsing query: {}", e))?;
+        let index_query = crate::index::with_phrase_boost(
+            self.index.index(),
+            index_query,
+            &name,
+            vec![field_name, field_synonym],
+        );
+        let searcher = self.index.reader().searcher();
+        // The collector requires a positive limit.
+        let top_docs = searcher.search(
+            &index_query,
+            &tantivy::collector::TopDocs::with_limit(max_results.max(1)),
+        )?;
+
+        let mut result = Vec::new();
+        for (_score, doc_address) in top_docs.into_iter().take(max_results) {
+            let doc = searcher.doc::<tantivy::TantivyDocument>(doc_address)?;
+            let term_id = doc
+                .get_first(field_term_id)
+                .and_then(|value| value.as_value().as_str())
+                .ok_or_else(|| anyhow::anyhow!("Document has no `term_id` field"))?;
+            if let Some(term) = self.term(term_id) {
+                result.push(term);
+            }
+        }
+        Ok(result)
+    }
+
+    /// Return the version information.
+    fn version(&self) -> GenericVersion {
+        GenericVersion {
+            ontology: self.ontology.clone(),
+            data_version: self.data_version.clone(),
+            viguno: version().to_string(),
+        }
+    }
+
+    /// Return the ID and name of the term with `term_id`.
+    fn term_ref(&self, term_id: &str) -> OboTermRef {
+        OboTermRef {
+            term_id: term_id.to_string(),
+            name: self
+                .term(term_id)
+                .map(|term| term.name.clone())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Return the term with `term_id` or 404.
+    fn term_or_not_found(&self, term_id: &str) -> Result<&GenericTerm, CustomError> {
+        self.term(term_id).ok_or_else(|| {
+            CustomError::with_status(
+                StatusCode::NOT_FOUND,
+                anyhow::anyhow!("term not found: {}", term_id),
+            )
+        })
+    }
+}
+
+/// Version information in generic ontology mode.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct GenericVersion {
+    /// Name of the ontology, e.g., `maxo`.
+    pub ontology: String,
+    /// Version of the ontology, if given in the OBO file.
+    pub data_version: Option<String>,
+    /// Version of the `viguno` package.
+    pub viguno: String,
+}
+
+/// A term of the generic ontology.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct OboTerm {
+    /// The term ID.
+    pub term_id: String,
+    /// The term's name.
+    pub name: String,
+    /// The term's definition, if any.
+    pub definition: Option<String>,
+    /// The term's synonyms.
+    pub synonyms: Vec<String>,
+    /// The term's xrefs.
+    pub xrefs: Vec<String>,
+    /// The IDs of the `is_a` parents.
+    pub parents: Vec<String>,
+    /// The IDs of the `is_a` children.
+    pub children: Vec<String>,
+    /// Whether the term is obsolete.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub obsolete: bool,
+}
+
+impl From<&GenericTerm> for OboTerm {
+    fn from(term: &GenericTerm) -> Self {
+        Self {
+            term_id: term.term_id.clone(),
+            name: term.name.clone(),
+            definition: term.definition.clone(),
+            synonyms: term.synonyms.clone(),
+            xrefs: term.xrefs.clone(),
+            parents: term.parents.clone(),
+            children: term.children.clone(),
+            obsolete: term.obsolete,
+        }
+    }
+}
+
+/// The ID and name of a term.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct OboTermRef {
+    /// The term ID.
+    pub term_id: String,
+    /// The term's name.
+    pub name: String,
+}
+
+/// Parameters for `handle_terms`.
+///
+/// - `name` -- the text to search the term names, synonyms, and definitions for
+/// - `max_results` -- the maximum number of records to return
+#[derive(
+    Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema, utoipa::IntoParams,
+)]
+#[serde(deny_unknown_fields)]
+#[into_params(parameter_in = Query)]
+pub struct OboTermsQuery {
+    /// The text to search for.
+    pub name: String,
+    /// Maximal number of results to return.
+    #[serde(default = "_default_max_results")]
+    pub max_results: usize,
+}
+
+/// Return default of `OboTermsQuery::max_results`.
+fn _default_max_results() -> usize {
+    100
+}
+
+/// Result of `handle_terms`.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct OboTermsResult {
+    /// Version information.
+    pub version: GenericVersion,
+    /// The original query.
+    pub query: OboTermsQuery,
+    /// The matching terms, best matches first.
+    pub result: Vec<OboTerm>,
+}
+
+/// Result of `handle_term`.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct OboTermResult {
+    /// Version information.
+    pub version: GenericVersion,
+    /// The term.
+    pub result: OboTerm,
+}
+
+/// The hierarchy around a term.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct OboTermHierarchy {
+    /// The term.
+    pub term: OboTermRef,
+    /// The `is_a` parents of the term.
+    pub parents: Vec<OboTermRef>,
+    /// The `is_a` children of the term.
+    pub children: Vec<OboTermRef>,
+    /// All ancestors of the term along `is_a` relations, ordered by term ID.
+    pub ancestors: Vec<OboTermRef>,
+}
+
+/// Result of `handle_hierarchy`.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct OboTermHierarchyResult {
+    /// Version information.
+    pub version: GenericVersion,
+    /// The hierarchy around the term.
+    pub result: OboTermHierarchy,
+}
+
+/// Search the terms of the generic ontology by name.
+///
+/// # Errors
+///
+/// In the case that there is an error running the server.
+#[allow(clippy::unused_async)]
+#[utoipa::path(
+    get,
+    operation_id = "oboTerms",
+    params(OboTermsQuery),
+    responses(
+        (status = 200, description = "The query was successful.", body = OboTermsResult),
+        (status = 400, description = "The query could not be parsed.", body = CustomError),
+        (status = 500, description = "The server encountered an error.", body = CustomError)
+    )
+)]
+#[get("/api/v1/obo/terms")]
+async fn handle_terms(
+    data: Data<Arc<GenericOntology>>,
+    query: StrictQuery<OboTermsQuery>,
+) -> actix_web::Result<Json<OboTermsResult>, CustomError> {
+    let result = data
+        .search(&query.name, query.max_results)
+        .map_err(|e| CustomError::with_status(StatusCode::BAD_REQUEST, e))?
+        .into_iter()
+        .map(OboTerm::from)
+        .collect();
+
+    Ok(Json(OboTermsResult {
+        version: data.version(),
+        query: query.into_inner(),
+        result,
+    }))
+}
+
+/// Return the details of a term of the generic ontology.
+///
+/// # Errors
+///
+/// In the case that there is an error running the server.
+#[allow(clippy::unused_async)]
+#[utoipa::path(
+    get,
+    operation_id = "oboTerm",
+    params(
+        ("term_id" = String, Path, description = "The term ID, e.g., `MAXO:0000004`."),
+    ),
+    responses(
+        (status = 200, description = "The query was successful.", body = OboTermResult),
+        (status = 404, description = "The term could not be found.", body = CustomError),
+        (status = 500, description = "The server encountered an error.", body = CustomError)
+    )
+)]
+#[get("/api/v1/obo/terms/{term_id}")]
+async fn handle_term(
+    data: Data<Arc<GenericOntology>>,
+    path: Path<String>,
+) -> actix_web::Result<Json<OboTermResult>, CustomError> {
+    let term = data.term_or_not_found(&path)?;
+
+    Ok(Json(OboTermResult {
+        version: data.version(),
+        result: OboTerm::from(term),
+    }))
+}
+
+/// Return the parents, children, and ancestors of a term of the generic ontology.
+///
+/// # Errors
+///
+/// In the case that there is an error running the server.
+#[allow(clippy::unused_async)]
+#[utoipa::path(
+    get,
+    operation_id = "oboTermHierarchy",
+    params(
+        ("term_id" = String, Path, description = "The term ID, e.g., `MAXO:0000004`."),
+    ),
+    responses(
+        (status = 200, description = "The query was successful.", body = OboTermHierarchyResult),
+        (status = 404, description = "The term could not be found.", body = CustomError),
+        (status = 500, description = "The server encountered an error.", body = CustomError)
+    )
+)]
+#[get("/api/v1/obo/terms/{term_id}/hierarchy")]
+async fn handle_hierarchy(
+    data: Data<Arc<GenericOntology>>,
+    path: Path<String>,
+) -> actix_web::Result<Json<OboTermHierarchyResult>, CustomError> {
+    let term = data.term_or_not_found(&path)?;
+    let refs = |term_ids: &mut dyn Iterator<Item = &String>| {
+        term_ids
+            .map(|term_id| data.term_ref(term_id))
+            .collect::<Vec<_>>()
+    };
+
+    Ok(Json(OboTermHierarchyResult {
+        version: data.version(),
+        result: OboTermHierarchy {
+            term: data.term_ref(&term.term_id),
+            parents: refs(&mut term.parents.iter()),
+            children: refs(&mut term.children.iter()),
+            ancestors: refs(&mut data.ancestors(&term.term_id).iter()),
+        },
+    }))
+}
+
+pub use api_doc::GenericApiDoc;
+
+// The code generated by `utoipa::OpenApi` applies the modifiers with `for_each`.
+#[allow(clippy::needless_for_each)]
+mod api_doc {
+    use super::{
+        CustomError, DebugTimingsDoc, ExpectedReleaseDoc, GenericVersion, OboTerm,
+        OboTermHierarchy, OboTermHierarchyResult, OboTermRef, OboTermResult, OboTermsQuery,
+        OboTermsResult, ResponseMetaDoc,
+    };
+
+    /// Utoipa-based `OpenAPI` generation helper for the generic ontology mode.
+    #[derive(utoipa::OpenApi)]
+    #[openapi(
+        modifiers(&ExpectedReleaseDoc, &DebugTimingsDoc, &ResponseMetaDoc),
+        paths(super::handle_terms, super::handle_term, super::handle_hierarchy),
+        components(schemas(
+            CustomError,
+            crate::server::run::middleware::ReleaseMismatch,
+            crate::server::run::middleware::ResponseMeta,
+            crate::server::run::middleware::DebugTimings,
+            crate::server::run::middleware::StageTiming,
+            crate::server::run::middleware::QueryNormalization,
+            crate::server::run::middleware::QueryNormalizationAction,
+            GenericVersion,
+            OboTerm,
+            OboTermRef,
+            OboTermsQuery,
+            OboTermsResult,
+            OboTermResult,
+            OboTermHierarchy,
+            OboTermHierarchyResult,
+        ))
+    )]
+    pub struct GenericApiDoc;
+}
+
+/// Return the settings of the middleware for `ontology` from the command line arguments.
+///
+/// # Errors
+///
+/// In the case that the tenants configuration cannot be loaded.
+pub fn settings(
+    args: &super::Args,
+    ontology: &GenericOntology,
+) -> Result<super::middleware::GenericSettings, anyhow::Error> {
+    Ok(super::middleware::GenericSettings {
+        release: ontology
+            .data_version
+            .clone()
+            .unwrap_or_else(|| ontology.ontology.clone()),
+        slow_request_threshold: args
+            .slow_request_threshold_ms
+            .map(std::time::Duration::from_millis),
+        max_memory_bytes: args.max_memory_mb.map(|mb| mb * 1024 * 1024),
+        tenants: args
+            .path_tenants
+            .as_ref()
+            .map(super::tenants::Tenants::load_generic)
+            .transpose()?
+            .unwrap_or_default(),
+    })
+}
+
+/// Main entry point for running the REST server in generic ontology mode.
+///
+/// The routes are wrapped in the same middleware as in the HPO mode, except for the
+/// recording of requests, which replays against the HPO data.
+#[allow(clippy::unused_async)]
+#[actix_web::main]
+pub async fn main(
+    args: &super::Args,
+    data: Data<Arc<GenericOntology>>,
+    settings: Data<super::middleware::GenericSettings>,
+) -> std::io::Result<()> {
+    use super::{limits, middleware, tenants};
+
+    let openapi = GenericApiDoc::openapi();
+    let max_body_bytes = limits::Limits::from_args(args).max_body_bytes;
+    let server = HttpServer::new(move || {
+        App::new()
+            .app_data(data.clone())
+            .app_data(settings.clone())
+            .app_data(
+                actix_web::web::JsonConfig::default()
+                    .limit(max_body_bytes)
+                    .error_handler(limits::json_error),
+            )
+            .app_data(actix_web::web::PayloadConfig::new(max_body_bytes))
+            .service(handle_terms)
+            .service(handle_hierarchy)
+            .service(handle_term)
+            .configure(|cfg| {
+                for path in [
+                    "/api/v1/obo/terms",
+                    "/api/v1/obo/terms/{term_id}",
+                    "/api/v1/obo/terms/{term_id}/hierarchy",
+                ] {
+                    super::routes::register_options(cfg, path, &[Method::GET, Method::HEAD]);
+                }
+            })
+            .service(
+                utoipa_swagger_ui::SwaggerUi::new("/swagger-ui/{_:.*}")
+                    .url("/api-docs/openapi.json", openapi.clone()),
+            )
+            .wrap(from_fn(middleware::add_response_meta))
+            .wrap(from_fn(middleware::answer_head))
+            .wrap(from_fn(middleware::check_expected_release))
+            .wrap(from_fn(middleware::shed_load))
+            .wrap(from_fn(middleware::log_slow_requests))
+            .wrap(from_fn(tenants::scope_tenant))
+            .wrap(from_fn(super::routes::add_cors_headers))
+            .wrap(Logger::default())
+    })
+    .keep_alive(super::keep_alive(args.keep_alive_secs))
+    .client_request_timeout(Duration::from_millis(args.client_request_timeout_ms))
+    .client_disconnect_timeout(Duration::from_millis(args.client_disconnect_timeout_ms))
+    .max_connections(args.max_connections);
+
+    let addr = (args.listen_host.as_str(), args.listen_port);
+    let server = if args.http2_cleartext {
+        server.bind_auto_h2c(addr)?
+    } else {
+        server.bind(addr)?
+    };
+    server.run().await
+}
+
+/// Load the OBO file at `path` and run the server in generic ontology mode.
+///
+/// # Errors
+///
+/// In the case that the OBO file cannot be loaded or there is an error running the server.
+pub fn run(args: &super::Args, path: &std::path::Path) -> Result<(), anyhow::Error> {
+    tracing::info!("Loading generic ontology from {:?}...", path);
+    let before_loading = std::time::Instant::now();
+    let ontology = GenericOntology::from_path(path)?;
+    tracing::info!(
+        "...done loading {} terms of ontology {} in {:?}",
+        ontology.len(),
+        ontology.ontology,
+        before_loading.elapsed()
+    );
+
+    let settings = settings(args, &ontology)?;
+
+    super::print_hints(args);
+    main(args, Data::new(Arc::new(ontology)), Data::new(settings))?;
+
+    tracing::info!("All done. Have a nice day!");
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    /// A small MAXO-like ontology.
+    const OBO: &str = "format-version: 1.2\n\
+        data-version: maxo/releases/2024-01-01/maxo.obo\n\
+        ontology: maxo\n\n\
+        [Term]\n\
+        id: MAXO:0000001\n\
+        name: medical action\n\n\
+        [Term]\n\
+        id: MAXO:0000004\n\
+        name: surgical procedure\n\
+        def: \"A procedure that involves an incision.\" []\n\
+        synonym: \"operation\" EXACT []\n\
+        is_a: MAXO:0000001\n\n\
+        [Term]\n\
+        id: MAXO:0000005\n\
+        name: cardiac surgery\n\
+        xref: NCIT:C18034\n\
+        is_a: MAXO:0000004\n\n\
+        [Term]\n\
+        id: MAXO:0000006\n\
+        name: therapeutic procedure\n\
+        is_a: MAXO:0000001\n";
+
+    fn ontology() -> Result<super::GenericOntology, anyhow::Error> {
+        super::GenericOntology::from_doc(fastobo::from_str(OBO)?)
+    }
+
+    #[test]
+    fn from_doc() -> Result<(), anyhow::Error> {
+        let ontology = ontology()?;
+
+        assert_eq!(ontology.ontology, "maxo");
+        assert_eq!(
+            ontology.data_version.as_deref(),
+            Some("maxo/releases/2024-01-01/maxo.obo")
+        );
+        assert_eq!(ontology.len(), 4);
+        let term = ontology.term("MAXO:0000004").expect("term must exist");
+        assert_eq!(term.name, "surgical procedure");
+        assert_eq!(
+            term.definition.as_deref(),
+            Some("A procedure that involves an incision.")
+        );
+        assert_eq!(term.synonyms, vec!["operation"]);
+        assert_eq!(term.parents, vec!["MAXO:0000001"]);
+        assert_eq!(term.children, vec!["MAXO:0000005"]);
+        assert_eq!(
+            ontology
+                .ancestors("MAXO:0000005")
+                .into_iter()
+                .collect::<Vec<_>>(),
+            vec!["MAXO:0000001", "MAXO:0000004"]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn search() -> Result<(), anyhow::Error> {
+        let ontology = ontology()?;
+
+        let term_ids = |name: &str| -> Result<Vec<String>, anyhow::Error> {
+            Ok(ontology
+                .search(name, 10)?
+                .into_iter()
+                .map(|term| term.term_id.clone())
+                .collect())
+        };
+        assert_eq!(term_ids("cardiac")?, vec!["MAXO:0000005"]);
+        assert_eq!(term_ids("operation")?, vec!["MAXO:0000004"]);
+        assert_eq!(term_ids("MAXO:0000006")?, vec!["MAXO:0000006"]);
+        assert!(ontology.search("cardiac", 0)?.is_empty());
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn middleware() -> Result<(), anyhow::Error> {
+        use actix_web::middleware::from_fn;
+
+        use crate::server::run::middleware;
+
+        let ontology = ontology()?;
+        let settings = super::settings(
+            &<crate::server::run::Args as clap::Parser>::parse_from([
+                "server",
+                "--generic-obo",
+                "maxo.obo",
+            ]),
+            &ontology,
+        )?;
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(actix_web::web::Data::new(Arc::new(ontology)))
+                .app_data(actix_web::web::Data::new(settings))
+                .service(super::handle_terms)
+                .wrap(from_fn(middleware::add_response_meta))
+                .wrap(from_fn(middleware::check_expected_release)),
+        )
+        .await;
+
+        let req = actix_web::test::TestRequest::get()
+            .uri("/api/v1/obo/terms?name=cardiac&max_results=0")
+            .to_request();
+        let resp: serde_json::Value = actix_web::test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp["result"], serde_json::json!([]));
+        assert_eq!(
+            resp["meta"]["hpo_release"],
+            "maxo/releases/2024-01-01/maxo.obo"
+        );
+
+        let req = actix_web::test::TestRequest::get()
+            .uri("/api/v1/obo/terms?name=cardiac")
+            .insert_header((middleware::EXPECTED_RELEASE_HEADER, "other"))
+            .to_request();
+        let resp = actix_web::test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::CONFLICT);
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn handle_hierarchy() -> Result<(), anyhow::Error> {
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(actix_web::web::Data::new(Arc::new(ontology()?)))
+                .service(super::handle_hierarchy)
+                .service(super::handle_term),
+        )
+        .await;
+
+        let req = actix_web::test::TestRequest::get()
+            .uri("/api/v1/obo/terms/MAXO:0000005/hierarchy")
+            .to_request();
+        let resp: super::OboTermHierarchyResult =
+            actix_web::test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp.version.ontology, "maxo");
+        assert_eq!(
+            resp.result
+                .ancestors
+                .iter()
+                .map(|term| term.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["medical action", "surgical procedure"]
+        );
+        assert!(resp.result.children.is_empty());
+
+        let req = actix_web::test::TestRequest::get()
+            .uri("/api/v1/obo/terms/MAXO:9999999")
+            .to_request();
+        let resp = actix_web::test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,242 @@
+//! Implementation of `/hpo/expr`.
+
+use std::{collections::HashMap, sync::Arc};
+
+use actix_web::{
+    post,
+    web::{Data, Json, Path},
+};
+use hpo::annotations::{AnnotationId as _, Disease as _};
+
+use crate::{
+    algos::term_expr::TermExpr,
+    common::Version,
+    server::run::{middleware::QueryNormalizationAction, WebServerData},
+};
+
+use super::{
+    middleware::{ComputeTimer, ResolvedFrom},
+    CustomError, ResultGene,
+};
+
+/// Request body for `handle`.
+///
+/// - `expr` -- the boolean expression over terms
+/// - `max_results` -- the maximum number of genes and of diseases to return
+/// - `lang` -- language of the disease names, falls back to English
+/// - `include_gene_info` -- whether to include `"gene_info"` in the genes
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct HpoExprRequest {
+    /// The boolean expression over terms, e.g.,
+    /// `{"and": [{"term": "HP:0001250"}, {"not": {"term": "HP:0001263"}}]}`.
+    pub expr: TermExpr,
+    /// Maximal number of genes and of diseases to return.
+    #[serde(default = "_default_max_results")]
+    pub max_results: usize,
+    /// Language of the disease names, e.g., `de`; untranslated names are in English.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub lang: Option<String>,
+    /// Whether to include the NCBI gene information.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub include_gene_info: bool,
+}
+
+/// Return default of `HpoExprRequest::max_results`.
+fn _default_max_results() -> usize {
+    100
+}
+
+/// An OMIM disease matching the expression.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct HpoExprDisease {
+    /// The OMIM ID, e.g., `OMIM:154700`.
+    pub omim_id: String,
+    /// The disease name.
+    pub name: String,
+}
+
+/// The genes and diseases matching the expression.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct HpoExprMatches {
+    /// The number of matching genes before truncation to `max_results`.
+    pub n_genes: usize,
+    /// The matching genes, sorted by NCBI gene ID.
+    pub genes: Vec<ResultGene>,
+    /// The number of matching diseases before truncation to `max_results`.
+    pub n_diseases: usize,
+    /// The matching OMIM diseases, sorted by OMIM ID.
+    pub diseases: Vec<HpoExprDisease>,
+}
+
+/// Container for the result.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct HpoExprResult {
+    /// Version information.
+    pub version: Version,
+    /// The original request.
+    pub query: HpoExprRequest,
+    /// The matching genes and diseases.
+    pub result: HpoExprMatches,
+}
+
+/// Query for the genes and diseases matching a boolean expression over terms.
+///
+/// A term matches the genes and diseases annotated with it or any of its descendants,
+/// `and`, `or`, and `not` combine the matches of the nested expressions.
+///
+/// # Errors
+///
+/// In the case that there is an error running the server.
+#[allow(clippy::unused_async)]
+#[utoipa::path(
+    post,
+    operation_id = "hpoExpr",
+    request_body = HpoExprRequest,
+    responses(
+        (status = 200, description = "The query was successful.", body = HpoExprResult),
+        (status = 400, description = "The request is invalid.", body = CustomError),
+        (status = 413, description = "The request body exceeds the size limit.", body = CustomError),
+        (status = 422, description = "The request exceeds a size or complexity limit.", body = CustomError),
+        (status = 500, description = "The server encountered an error.", body = CustomError)
+    )
+)]
+#[post("/api/v1/hpo/expr")]
+async fn handle(
+    data: Data<Arc<WebServerData>>,
+    _path: Path<()>,
+    request: Json<HpoExprRequest>,
+    resolved_from: ResolvedFrom,
+    _timer: ComputeTimer,
+) -> actix_web::Result<Json<HpoExprResult>, CustomError> {
+    let ontology = &data.ontology;
+    let request = request.into_inner();
+    data.limits.check_terms(request.expr.size())?;
+
+    // Resolve each distinct term once so that the changes are recorded once.
+    let mut term_ids = HashMap::new();
+    for term in request.expr.terms() {
+        if term_ids.contains_key(term) {
+            continue;
+        }
+        let ids = super::parse_term_id(term, &data, &resolved_from)?
+            .into_iter()
+            .filter(|term_id| ontology.hpo(*term_id).is_some())
+            .collect::<Vec<_>>();
+        if ids.is_empty() {
+            resolved_from.record_change(QueryNormalizationAction::UnknownDropped, term, &[]);
+        }
+        term_ids.insert(term.to_string(), ids);
+    }
+
+    let genes = request.expr.evaluate(&data.term_gene_index, &term_ids);
+    let diseases = request.expr.evaluate(&data.term_disease_index, &term_ids);
+    let result = HpoExprMatches {
+        n_genes: genes.count_ones(..),
+        genes: genes
+            .ones()
+            .take(request.max_results)
+            .map(|bit| data.term_gene_index.genes()[bit])
+            .filter_map(|gene_id| ontology.gene(&gene_id))
+            .map(|gene| {
+                ResultGene {
+                    ncbi_gene_id: gene.id().as_u32(),
+                    gene_symbol: gene.name().to_string(),
+                    hgnc_id: data
+                        .hgnc_xlink
+                        .hgnc_id(gene.id().as_u32())
+                        .map(str::to_string),
+                    gene_info: None,
+                }
+                .with_gene_info(request.include_gene_info.then_some(&data.gene_info))
+            })
+            .collect(),
+        n_diseases: diseases.count_ones(..),
+        diseases: diseases
+            .ones()
+            .take(request.max_results)
+            .map(|bit| data.term_disease_index.diseases()[bit])
+            .filter_map(|omim_id| ontology.omim_disease(&omim_id))
+            .map(|disease| HpoExprDisease {
+                omim_id: disease.id().to_string(),
+                name: super::hpo_omims::omim_name(disease, &data, request.lang.as_deref()),
+            })
+            .collect(),
+    };
+
+    Ok(Json(HpoExprResult {
+        version: Version::new(&ontology.hpo_version()),
+        query: request,
+        result,
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use crate::server::run::hpo_genes::test::web_server_data;
+
+    /// Helper function for running a query.
+    async fn run_query(
+        web_server_data: Arc<crate::server::run::WebServerData>,
+        body: serde_json::Value,
+    ) -> actix_web::dev::ServiceResponse {
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(actix_web::web::Data::new(web_server_data))
+                .service(super::handle),
+        )
+        .await;
+        let req = actix_web::test::TestRequest::post()
+            .uri("/api/v1/hpo/expr")
+            .set_json(body)
+            .to_request();
+        actix_web::test::call_service(&app, req).await
+    }
+
+    #[rstest::rstest]
+    #[actix_web::test]
+    async fn hpo_expr_and_not(
+        web_server_data: &Arc<crate::server::run::WebServerData>,
+    ) -> Result<(), anyhow::Error> {
+        let term = |term_id: &str| serde_json::json!({ "term": term_id });
+        let count = |body: serde_json::Value| {
+            let web_server_data = web_server_data.clone();
+            async move {
+                let resp = run_query(web_server_data, body).await;
+                assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+                let result: super::HpoExprResult = actix_web::test::read_body_json(resp).await;
+                (result.result.n_genes, result.result.n_diseases)
+            }
+        };
+
+        let seizure = count(serde_json::json!({ "expr": term("HP:0001250") })).await;
+        let both = count(serde_json::json!({
+            "expr": { "and": [term("HP:0001250"), term("HP:0001263")] }
+        }))
+        .await;
+        let without = count(serde_json::json!({
+            "expr": { "and": [term("HP:0001250"), { "not": term("HP:0001263") }] }
+        }))
+        .await;
+
+        assert!(seizure.0 > 0 && seizure.1 > 0);
+        assert_eq!(both.0 + without.0, seizure.0);
+        assert_eq!(both.1 + without.1, seizure.1);
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[actix_web::test]
+    async fn hpo_expr_invalid(web_server_data: &Arc<crate::server::run::WebServerData>) {
+        let resp = run_query(
+            web_server_data.clone(),
+            serde_json::json!({ "expr": { "xor": [] } }),
+        )
+        .await;
+
+        assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+    }
+}
@@ -4,16 +4,22 @@ use std::{collections::HashMap, sync::Arc};
 
 use actix_web::{
     get,
-    web::{self, Data, Json, Path},
+    web::{Data, Json, Path},
 };
 use hpo::{
     annotations::{AnnotationId, Gene, GeneId},
-    Ontology,
+    HpoSet, Ontology,
 };
 
-use crate::{common::Version, server::run::WebServerData};
+use crate::{
+    algos::sex::{Sex, SexFilter},
+    common::{AnnotationPropagation, Version},
+    server::run::WebServerData,
+    sources::{clinvar::ClinvarCounts, gene_info::GeneInfo, hgnc_xlink::HgncXlink},
+};
 
-use super::{CustomError, Match, ResultHpoTerm};
+use super::{middleware::ComputeTimer, CustomError, Match, ResultHpoTerm};
+use crate::server::run::strict_query::StrictQuery;
 
 /// Parameters for `fetch_hpo_genes`.
 ///
@@ -24,6 +30,11 @@ use super::{CustomError, Match, ResultHpoTerm};
 /// - `gene_symbol` -- specify the gene symbol
 /// - `max_results` -- the maximnum number of records to return
 /// - `hpo_terms` -- whether to include `"hpo_terms"` in result
+/// - `include_gene_info` -- whether to include `"gene_info"` in result
+/// - `region` -- restrict to genes overlapping the region, e.g., `chr7:100000-5000000`
+/// - `sex` -- exclude `"hpo_terms"` restricted to the other sex
+/// - `annotation_propagation` -- whether `"hpo_terms"` leaves out the terms implied by more
+///   specific ones (`ancestors`) or lists all direct annotations (`none`)
 ///
 /// The following propery defines how matches are performed:
 ///
@@ -31,6 +42,8 @@ use super::{CustomError, Match, ResultHpoTerm};
 #[derive(
     Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema, utoipa::IntoParams,
 )]
+#[serde(deny_unknown_fields)]
+#[into_params(parameter_in = Query)]
 pub struct HpoGenesQuery {
     /// The gene ID to search for.
     pub gene_id: Option<String>,
@@ -45,6 +58,18 @@ pub struct HpoGenesQuery {
     /// Whether to include HPO terms.
     #[serde(default = "_default_hpo_terms")]
     pub hpo_terms: bool,
+    /// Whether to include the NCBI gene information.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub include_gene_info: bool,
+    /// Restrict to genes overlapping the region, e.g., `chr7:100000-5000000`.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+    /// Exclude HPO terms restricted to the other sex.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub sex: Option<Sex>,
+    /// Whether annotations propagate to ancestors, server default if missing.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub annotation_propagation: Option<AnnotationPropagation>,
 }
 
 /// Return default of `Request::max_results`.
@@ -79,20 +104,40 @@ pub struct HpoGenesResultEntry {
     /// The gene's associated HPO terms.
     #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
     pub hpo_terms: Option<Vec<ResultHpoTerm>>,
+    /// The gene's NCBI gene information, if requested and available.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub gene_info: Option<GeneInfo>,
+    /// The number of pathogenic or likely pathogenic `ClinVar` variants of the gene, if the
+    /// `ClinVar` summary is loaded.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub clinvar_pathogenic: Option<u32>,
 }
 
 impl HpoGenesResultEntry {
     /// Create a `ResultEntry` from a `Gene` with an `Ontology`.
+    ///
+    /// The terms restricted to the other sex are left out if `sex` is given.  With
+    /// propagation, the terms implied by a more specific annotated term are left out.
     pub fn from_gene_with_ontology(
         gene: &Gene,
         ontology: &Ontology,
         hpo_terms: bool,
-        ncbi_to_hgnc: &HashMap<u32, String>,
+        hgnc_xlink: &HgncXlink,
+        sex: Option<SexFilter>,
+        propagation: AnnotationPropagation,
     ) -> Self {
         let hpo_terms = if hpo_terms {
-            let mut terms = gene
-                .to_hpo_set(ontology)
-                .child_nodes()
+            let terms = match sex {
+                Some(sex) => sex.gene_terms(gene),
+                None => gene.hpo_terms().clone(),
+            };
+            let terms = HpoSet::new(ontology, terms);
+            let terms = if propagation.is_propagated() {
+                terms.child_nodes()
+            } else {
+                terms
+            };
+            let mut terms = terms
                 .into_iter()
                 .map(|term| ResultHpoTerm {
                     term_id: term.id().to_string(),
@@ -107,8 +152,28 @@ impl HpoGenesResultEntry {
         HpoGenesResultEntry {
             gene_ncbi_id: gene.id().as_u32(),
             gene_symbol: gene.name().to_string(),
-            hgnc_id: ncbi_to_hgnc.get(&gene.id().as_u32()).cloned(),
+            hgnc_id: hgnc_xlink.hgnc_id(gene.id().as_u32()).map(str::to_string),
             hpo_terms,
+            gene_info: None,
+            clinvar_pathogenic: None,
+        }
+    }
+
+    /// Set the number of pathogenic `ClinVar` variants from `clinvar`.
+    #[must_use]
+    pub fn with_clinvar(self, clinvar: &ClinvarCounts) -> Self {
+        Self {
+            clinvar_pathogenic: Some(clinvar.get(self.gene_ncbi_id)),
+            ..self
+        }
+    }
+
+    /// Set the gene information from `gene_info`, if available.
+    #[must_use]
+    pub fn with_gene_info(self, gene_info: &HashMap<u32, GeneInfo>) -> Self {
+        Self {
+            gene_info: gene_info.get(&self.gene_ncbi_id).cloned(),
+            ..self
         }
     }
 }
@@ -124,6 +189,15 @@ pub struct HpoGenesResult {
     pub result: Vec<HpoGenesResultEntry>,
 }
 
+/// Parse a gene ID that is either an NCBI or an HGNC gene ID.
+pub fn parse_gene_id(gene_id: &str, hgnc_xlink: &HgncXlink) -> Option<GeneId> {
+    if let Ok(ncbi_gene_id) = gene_id.parse::<u32>() {
+        Some(GeneId::from(ncbi_gene_id))
+    } else {
+        hgnc_xlink.ncbi_gene_id(gene_id).map(GeneId::from)
+    }
+}
+
 /// Query for genes in the HPO database.
 ///
 /// # Errors
@@ -135,6 +209,7 @@ pub struct HpoGenesResult {
     params(HpoGenesQuery),
     responses(
         (status = 200, description = "The query was successful.", body = HpoGenesResult),
+        (status = 400, description = "The query parameters are invalid.", body = CustomError),
         (status = 500, description = "The server encountered an error.", body = CustomError)
     )
 )]
@@ -142,33 +217,44 @@ pub struct HpoGenesResult {
 async fn handle(
     data: Data<Arc<WebServerData>>,
     _path: Path<()>,
-    query: web::Query<HpoGenesQuery>,
+    query: StrictQuery<HpoGenesQuery>,
+    _timer: ComputeTimer,
 ) -> actix_web::Result<Json<HpoGenesResult>, CustomError> {
     let ontology = &data.ontology;
     let match_ = query.match_.unwrap_or_default();
     let mut result: Vec<HpoGenesResultEntry> = Vec::new();
+    let region = super::parse_region(query.region.as_deref(), &data)?;
+    let sex = query.sex.map(|sex| SexFilter {
+        index: &data.sex_index,
+        sex,
+    });
+    let propagation = query
+        .annotation_propagation
+        .unwrap_or(data.annotation_propagation);
+    let in_region = |gene: &Gene| {
+        region
+            .as_ref()
+            .is_none_or(|region| region.contains_gene(gene.id().as_u32(), &data.gene_coords))
+    };
 
     if match_ == Match::Exact {
         let gene = if let Some(gene_id) = &query.gene_id {
-            let gene_id = if let Ok(ncbi_gene_id) = gene_id.parse::<u32>() {
-                Ok(GeneId::from(ncbi_gene_id))
-            } else if let Some(ncbi_gene_id) = data.hgnc_to_ncbi.get(gene_id) {
-                Ok(GeneId::from(*ncbi_gene_id))
-            } else {
-                Err(CustomError::new(anyhow::anyhow!("could not parse gene ID")))
-            }?;
+            let gene_id = parse_gene_id(gene_id, &data.hgnc_xlink)
+                .ok_or_else(|| CustomError::new(anyhow::anyhow!("could not parse gene ID")))?;
             ontology.gene(&gene_id)
         } else if let Some(gene_symbol) = &query.gene_symbol {
             ontology.gene_by_name(gene_symbol)
         } else {
             None
         };
-        if let Some(gene) = gene {
+        if let Some(gene) = gene.filter(|gene| in_region(gene)) {
             result.push(HpoGenesResultEntry::from_gene_with_ontology(
                 gene,
                 ontology,
                 query.hpo_terms,
-                &data.ncbi_to_hgnc,
+                &data.hgnc_xlink,
+                sex,
+                propagation,
             ));
         }
     } else if let Some(gene_symbol) = &query.gene_symbol {
@@ -182,12 +268,14 @@ async fn handle(
                 Match::Contains => symbol.contains(gene_symbol),
                 Match::Exact => panic!("cannot happen here"),
             };
-            if is_match {
+            if is_match && in_region(gene.expect("checked above")) {
                 result.push(HpoGenesResultEntry::from_gene_with_ontology(
                     gene.expect("checked above"),
                     ontology,
                     query.hpo_terms,
-                    &data.ncbi_to_hgnc,
+                    &data.hgnc_xlink,
+                    sex,
+                    propagation,
                 ));
             }
 
@@ -195,6 +283,18 @@ async fn handle(
         }
     }
 
+    if query.include_gene_info {
+        result = result
+            .into_iter()
+            .map(|entry| entry.with_gene_info(&data.gene_info))
+            .collect();
+    }
+    if let Some(clinvar) = data.sources.get::<ClinvarCounts>() {
+        result = result
+            .into_iter()
+            .map(|entry| entry.with_clinvar(clinvar))
+            .collect();
+    }
     result.sort();
 
     let result = HpoGenesResult {
@@ -214,20 +314,76 @@ pub(crate) mod test {
     #[rstest::fixture]
     #[once]
     pub fn web_server_data() -> Arc<crate::server::run::WebServerData> {
+        Arc::new(load_web_server_data())
+    }
+
+    /// Load the default ``crate::server::run::WebServerData``, e.g., for modifying it.
+    pub fn load_web_server_data() -> crate::server::run::WebServerData {
         let ontology = crate::common::load_hpo("tests/data/hpo").expect("could not load HPO");
-        let ncbi_to_hgnc =
-            crate::common::hgnc_xlink::load_ncbi_to_hgnc("tests/data/hpo/hgnc_xlink.tsv")
+        let hgnc_xlink =
+            crate::sources::hgnc_xlink::HgncXlink::load("tests/data/hpo/hgnc_xlink.tsv")
                 .expect("could not HGNC xlink");
-        let hgnc_to_ncbi = crate::common::hgnc_xlink::inverse_hashmap(&ncbi_to_hgnc);
-        let hpo_doc = fastobo::from_file("tests/data/hpo/hp.obo").expect("could not load HPO OBO");
-
-        Arc::new(crate::server::run::WebServerData {
+        let ic_tables = crate::algos::ic::IcTables::new(&ontology);
+        let term_gene_index = crate::algos::term_genes::TermGeneIndex::new(&ontology);
+        let term_disease_index = crate::algos::term_diseases::TermDiseaseIndex::new(&ontology);
+        let inheritance_index = crate::algos::inheritance::InheritanceIndex::new(&ontology);
+        let sex_index = crate::algos::sex::SexIndex::from_hpo_dir("tests/data/hpo")
+            .expect("could not load sex-specific annotations");
+        let disease_gene_index =
+            crate::algos::routes::DiseaseGeneIndex::from_hpo_dir("tests/data/hpo")
+                .expect("could not load disease-gene links");
+        let onset_index = crate::algos::onset::OnsetIndex::new(&ontology);
+
+        crate::server::run::WebServerData {
             ontology,
-            ncbi_to_hgnc,
-            hgnc_to_ncbi,
-            full_text_index: crate::index::Index::new(hpo_doc)
-                .expect("could not create full text index"),
-        })
+            hgnc_xlink,
+            gene_info: std::collections::HashMap::new(),
+            gene_coords: std::collections::HashMap::new(),
+            network: None,
+            sources: crate::sources::AnnotationSources::default(),
+            expression_penalty: 0.5,
+            disease_codes: crate::sources::disease_codes::DiseaseCodes::default(),
+            lab_inference: Box::new(crate::algos::labs::LabRules::default()),
+            translations: crate::sources::translations::Translations::default(),
+            disease_translations: crate::sources::translations::DiseaseTranslations::default(),
+            association_scores: std::collections::BTreeMap::new(),
+            experimental_allowlist: Vec::new(),
+            local_terms: crate::sources::local_terms::LocalTerms::default(),
+            full_text_index: crate::index::Index::from_path(
+                "tests/data/hpo/hp.obo",
+                &crate::sources::translations::Translations::default(),
+                &[],
+            )
+            .expect("could not create full text index"),
+            annotation_date: crate::common::load_annotation_date("tests/data/hpo"),
+            ic_tables,
+            term_gene_index,
+            term_disease_index,
+            inheritance_index,
+            onset_index,
+            onset_penalty: 0.5,
+            ensemble_methods: crate::algos::ensemble::DEFAULT_METHODS
+                .split(',')
+                .map(str::parse)
+                .collect::<Result<_, _>>()
+                .expect("invalid default ensemble methods"),
+            patient_registry: Some(crate::server::run::patients::PatientRegistry::default()),
+            mme_config: crate::server::run::patients::mme::MmeConfig::default(),
+            beacon_config: crate::server::run::beacon::BeaconConfig::default(),
+            limits: crate::server::run::limits::Limits::default(),
+            sex_index,
+            disease_gene_index,
+            annotation_propagation: crate::common::AnnotationPropagation::default(),
+            export_config: crate::server::run::hpo_ontology::ExportConfig::default(),
+            slow_request_threshold: None,
+            max_memory_bytes: None,
+            degraded: Vec::new(),
+            tenants: crate::server::run::tenants::Tenants::default(),
+            report_signing_key: None,
+            query_defaults: crate::server::run::defaults::QueryDefaults::default(),
+            record_requests: None,
+            simulation_cache: crate::algos::simulation::SimulationCache::default(),
+        }
     }
 
     /// Helper function for running a query.
@@ -411,4 +567,155 @@ pub(crate) mod test {
             .await?
         ))
     }
+
+    /// Return gene coordinates with TGDS on chromosome 13 and FGD1 on chromosome X.
+    pub fn gene_coords() -> std::collections::HashMap<u32, crate::sources::gene_coords::Entry> {
+        [(23483, "13", 94_566_000), (2245, "X", 54_445_000)]
+            .into_iter()
+            .map(|(ncbi_gene_id, chrom, start)| {
+                (
+                    ncbi_gene_id,
+                    crate::sources::gene_coords::Entry {
+                        ncbi_gene_id,
+                        chrom: chrom.to_string(),
+                        start,
+                        end: start + 30_000,
+                    },
+                )
+            })
+            .collect()
+    }
+
+    #[actix_web::test]
+    async fn hpo_genes_region() -> Result<(), anyhow::Error> {
+        let data = Arc::new(crate::server::run::WebServerData {
+            gene_coords: gene_coords(),
+            ..load_web_server_data()
+        });
+        let gene_ids = |result: super::HpoGenesResult| {
+            result
+                .result
+                .into_iter()
+                .map(|entry| entry.gene_ncbi_id)
+                .collect::<Vec<_>>()
+        };
+
+        let unfiltered = run_query(
+            data.clone(),
+            "/api/v1/hpo/genes?gene_symbol=GD&match=contains",
+        )
+        .await?;
+        assert!(gene_ids(unfiltered).len() > 2);
+        let chr13 = run_query(
+            data.clone(),
+            "/api/v1/hpo/genes?gene_symbol=GD&match=contains&region=chr13:94,000,000-95,000,000",
+        )
+        .await?;
+        assert_eq!(gene_ids(chr13), vec![23483]);
+        let chr_x = run_query(
+            data.clone(),
+            "/api/v1/hpo/genes?gene_symbol=FGD1&region=X:54000000-55000000",
+        )
+        .await?;
+        assert_eq!(gene_ids(chr_x), vec![2245]);
+        let elsewhere = run_query(
+            data.clone(),
+            "/api/v1/hpo/genes?gene_id=23483&region=chr13:1-1000",
+        )
+        .await?;
+        assert!(gene_ids(elsewhere).is_empty());
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[actix_web::test]
+    async fn hpo_genes_region_invalid(
+        web_server_data: &Arc<crate::server::run::WebServerData>,
+    ) -> Result<(), anyhow::Error> {
+        let status = |data: Arc<crate::server::run::WebServerData>, uri: &'static str| async move {
+            let app = actix_web::test::init_service(
+                actix_web::App::new()
+                    .app_data(actix_web::web::Data::new(data))
+                    .service(super::handle),
+            )
+            .await;
+            let req = actix_web::test::TestRequest::get().uri(uri).to_request();
+            actix_web::test::call_service(&app, req).await.status()
+        };
+        let with_coords = Arc::new(crate::server::run::WebServerData {
+            gene_coords: gene_coords(),
+            ..load_web_server_data()
+        });
+
+        // No gene coordinates loaded.
+        assert_eq!(
+            status(
+                web_server_data.clone(),
+                "/api/v1/hpo/genes?gene_id=23483&region=chr13:1-1000"
+            )
+            .await,
+            actix_web::http::StatusCode::BAD_REQUEST
+        );
+        // Malformed region.
+        assert_eq!(
+            status(
+                with_coords,
+                "/api/v1/hpo/genes?gene_id=23483&region=chr13:1000-1"
+            )
+            .await,
+            actix_web::http::StatusCode::BAD_REQUEST
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn from_gene_with_ontology_propagation() {
+        use hpo::annotations::GeneId;
+
+        let mut builder = hpo::builder::Builder::new();
+        builder.new_term("All", 1u32);
+        builder.new_term("Head", 2u32);
+        builder.new_term("Eye", 3u32);
+        let mut builder = builder.terms_complete();
+        builder.add_parent(1u32, 2u32).expect("term must exist");
+        builder.add_parent(2u32, 3u32).expect("term must exist");
+        let mut builder = builder.connect_all_terms();
+        for term in [2u32, 3] {
+            builder
+                .annotate_gene(GeneId::from(10), "GENE10", term.into())
+                .expect("term must exist");
+        }
+        let ontology = builder
+            .calculate_information_content()
+            .expect("could not compute IC")
+            .build_minimal();
+        let gene = ontology.gene(&GeneId::from(10)).expect("gene must exist");
+        let term_ids = |propagation| {
+            super::HpoGenesResultEntry::from_gene_with_ontology(
+                gene,
+                &ontology,
+                true,
+                &crate::sources::hgnc_xlink::HgncXlink::default(),
+                None,
+                propagation,
+            )
+            .hpo_terms
+            .expect("terms must be included")
+            .into_iter()
+            .map(|term| term.term_id)
+            .collect::<Vec<_>>()
+        };
+
+        // "Head" follows from "Eye" with propagation only.
+        assert_eq!(
+            term_ids(crate::common::AnnotationPropagation::Ancestors),
+            vec!["HP:0000003"]
+        );
+        assert_eq!(
+            term_ids(crate::common::AnnotationPropagation::None),
+            vec!["HP:0000002", "HP:0000003"]
+        );
+    }
 }
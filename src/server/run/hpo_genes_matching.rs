@@ -0,0 +1,272 @@
+//! Implementation of `/hpo/genes/matching`.
+
+use std::sync::Arc;
+
+use actix_web::{
+    get,
+    http::StatusCode,
+    web::{Data, Json, Path},
+};
+use hpo::annotations::AnnotationId as _;
+
+use crate::{common::Version, server::run::WebServerData};
+
+use super::{
+    middleware::{ComputeTimer, ResolvedFrom},
+    CustomError, ResultGene, ResultHpoTerm,
+};
+use crate::server::run::strict_query::StrictQuery;
+
+/// Parameters for `handle`.
+///
+/// - `terms` -- set of query terms
+/// - `min_matches` -- minimal number of query terms a gene must be annotated with
+/// - `include_descendants` -- whether genes annotated with descendant terms match
+/// - `max_results` -- the maximum number of records to return
+/// - `include_gene_info` -- whether to include `"gene_info"` in the genes
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::IntoParams)]
+#[serde(deny_unknown_fields)]
+#[into_params(parameter_in = Query)]
+pub struct RequestQuery {
+    /// The set of HPO terms to match the genes with.
+    #[serde(deserialize_with = "super::vec_str_deserialize")]
+    pub terms: Vec<String>,
+    /// The minimal number of query terms a gene must match, all terms if missing.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub min_matches: Option<usize>,
+    /// Whether genes annotated with descendants of a term match the term.
+    #[serde(default = "_default_include_descendants")]
+    pub include_descendants: bool,
+    /// Maximal number of results to return.
+    #[serde(default = "_default_max_results")]
+    pub max_results: usize,
+    /// Whether to include the NCBI gene information.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub include_gene_info: bool,
+}
+
+/// Return default of `RequestQuery::include_descendants`.
+fn _default_include_descendants() -> bool {
+    true
+}
+
+/// Return default of `RequestQuery::max_results`.
+fn _default_max_results() -> usize {
+    100
+}
+
+/// Request as sent together with the response.
+///
+/// The difference is that the `terms` field is replaced by the sorted known terms without
+/// duplicates and `min_matches` is always given.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct HpoGenesMatchingQuery {
+    /// The set of HPO terms to match the genes with.
+    pub terms: Vec<String>,
+    /// The minimal number of query terms a gene must match.
+    pub min_matches: usize,
+    /// Whether genes annotated with descendants of a term match the term.
+    pub include_descendants: bool,
+    /// Maximal number of results to return.
+    pub max_results: usize,
+    /// Whether to include the NCBI gene information.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub include_gene_info: bool,
+}
+
+/// Result entry for `handle`.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct HpoGenesMatchingResultEntry {
+    /// The gene.
+    pub gene: ResultGene,
+    /// The number of matched query terms.
+    pub n_matches: usize,
+    /// The matched query terms, sorted by term ID.
+    pub matched_terms: Vec<ResultHpoTerm>,
+}
+
+/// Container for the result.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct HpoGenesMatchingResult {
+    /// Version information.
+    pub version: Version,
+    /// The original query.
+    pub query: HpoGenesMatchingQuery,
+    /// The number of matching genes before truncation to `max_results`.
+    pub n_genes: usize,
+    /// The matching genes by descending number of matches and ascending NCBI gene ID.
+    pub result: Vec<HpoGenesMatchingResultEntry>,
+}
+
+/// Query for the genes annotated with at least `min_matches` of the terms.
+///
+/// This is a boolean alternative to the similarity for triaging gene panels, e.g.,
+/// `min_matches=2` for three terms returns the genes annotated with any two of them
+/// together with the matched terms.
+///
+/// # Errors
+///
+/// In the case that there is an error running the server.
+#[utoipa::path(
+    get,
+    operation_id = "hpoGenesMatching",
+    params(RequestQuery),
+    responses(
+        (status = 200, description = "The query was successful.", body = HpoGenesMatchingResult),
+        (status = 400, description = "A term ID is malformed or `min_matches` is out of range.", body = CustomError),
+        (status = 422, description = "The request exceeds a size or complexity limit.", body = CustomError),
+        (status = 500, description = "The server encountered an error.", body = CustomError)
+    )
+)]
+#[get("/api/v1/hpo/genes/matching")]
+async fn handle(
+    data: Data<Arc<WebServerData>>,
+    _path: Path<()>,
+    query: StrictQuery<RequestQuery>,
+    resolved_from: ResolvedFrom,
+    _timer: ComputeTimer,
+) -> actix_web::Result<Json<HpoGenesMatchingResult>, CustomError> {
+    let ontology = &data.ontology;
+    let RequestQuery {
+        terms,
+        min_matches,
+        include_descendants,
+        max_results,
+        include_gene_info,
+    } = query.into_inner();
+
+    let term_ids = super::to_hpo_group(
+        super::parse_term_ids(&terms, &data, &resolved_from)?,
+        &data,
+        &resolved_from,
+    )
+    .iter()
+    .collect::<Vec<_>>();
+    let min_matches = min_matches.unwrap_or(term_ids.len());
+    if min_matches == 0 || min_matches > term_ids.len() {
+        return Err(CustomError::with_status(
+            StatusCode::BAD_REQUEST,
+            anyhow::anyhow!(
+                "min_matches must be from 1 to the number of known query terms ({}): {}",
+                term_ids.len(),
+                min_matches
+            ),
+        ));
+    }
+
+    let mut matches =
+        data.term_gene_index
+            .genes_matching(&term_ids, min_matches, include_descendants);
+    matches.sort_by(|(lhs_id, lhs), (rhs_id, rhs)| {
+        rhs.len().cmp(&lhs.len()).then_with(|| lhs_id.cmp(rhs_id))
+    });
+    let n_genes = matches.len();
+    let gene_info = include_gene_info.then_some(&data.gene_info);
+    let result = matches
+        .into_iter()
+        .take(max_results)
+        .filter_map(|(gene_id, matched)| {
+            let gene = ontology.gene(&gene_id)?;
+            Some(HpoGenesMatchingResultEntry {
+                gene: ResultGene {
+                    ncbi_gene_id: gene_id.as_u32(),
+                    gene_symbol: gene.name().to_string(),
+                    hgnc_id: data
+                        .hgnc_xlink
+                        .hgnc_id(gene_id.as_u32())
+                        .map(str::to_string),
+                    gene_info: None,
+                }
+                .with_gene_info(gene_info),
+                n_matches: matched.len(),
+                matched_terms: matched
+                    .iter()
+                    .filter_map(|i| ontology.hpo(term_ids[*i]))
+                    .map(|term| ResultHpoTerm {
+                        term_id: term.id().to_string(),
+                        name: term.name().to_string(),
+                    })
+                    .collect(),
+            })
+        })
+        .collect();
+
+    Ok(Json(HpoGenesMatchingResult {
+        version: Version::new(&ontology.hpo_version()),
+        query: HpoGenesMatchingQuery {
+            terms: term_ids.iter().map(ToString::to_string).collect(),
+            min_matches,
+            include_descendants,
+            max_results,
+            include_gene_info,
+        },
+        n_genes,
+        result,
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use crate::server::run::hpo_genes::test::web_server_data;
+
+    /// Helper function for running a query.
+    async fn run_query(
+        web_server_data: Arc<crate::server::run::WebServerData>,
+        uri: &str,
+    ) -> actix_web::dev::ServiceResponse {
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(actix_web::web::Data::new(web_server_data))
+                .service(super::handle),
+        )
+        .await;
+        let req = actix_web::test::TestRequest::get().uri(uri).to_request();
+        actix_web::test::call_service(&app, req).await
+    }
+
+    #[rstest::rstest]
+    #[actix_web::test]
+    async fn hpo_genes_matching(
+        web_server_data: &Arc<crate::server::run::WebServerData>,
+    ) -> Result<(), anyhow::Error> {
+        let resp = run_query(
+            web_server_data.clone(),
+            "/api/v1/hpo/genes/matching?terms=HP:0001166,HP:0001250,HP:0000252&min_matches=2",
+        )
+        .await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+        let result: super::HpoGenesMatchingResult = actix_web::test::read_body_json(resp).await;
+
+        assert_eq!(result.query.min_matches, 2);
+        assert!(!result.result.is_empty());
+        assert!(result
+            .result
+            .iter()
+            .all(|entry| entry.n_matches >= 2 && entry.matched_terms.len() == entry.n_matches));
+        assert!(result
+            .result
+            .windows(2)
+            .all(|pair| pair[0].n_matches >= pair[1].n_matches));
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case("terms=HP:0001166,HP:0001250&min_matches=3")]
+    #[case("terms=HP:0001166&min_matches=0")]
+    #[actix_web::test]
+    async fn hpo_genes_matching_min_matches_out_of_range(
+        web_server_data: &Arc<crate::server::run::WebServerData>,
+        #[case] query: &str,
+    ) {
+        let resp = run_query(
+            web_server_data.clone(),
+            &format!("/api/v1/hpo/genes/matching?{query}"),
+        )
+        .await;
+
+        assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,361 @@
+//! Implementation of `/hpo/genes/{gene}/profile`.
+
+use std::{collections::BTreeMap, sync::Arc};
+
+use actix_web::{
+    get,
+    http::StatusCode,
+    web::{Data, Json, Path},
+};
+use hpo::{
+    annotations::{AnnotationId as _, Gene},
+    HpoTermId, Ontology,
+};
+
+use crate::{
+    algos::ic::IcTables,
+    common::{AnnotationPropagation, IcBasedOn, Version},
+    server::run::WebServerData,
+};
+
+use super::{
+    hpo_genes::parse_gene_id, middleware::ComputeTimer, CustomError, ResultGene, ResultHpoTerm,
+};
+use crate::server::run::strict_query::StrictQuery;
+use crate::server::run::tenants::TenantScope;
+
+/// Parameters for `handle`.
+///
+/// - `ic_base` -- what the information content should be based on
+/// - `annotation_propagation` -- whether annotations propagate to ancestors
+/// - `include_gene_info` -- whether to include `"gene_info"` in the gene
+#[derive(
+    Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema, utoipa::IntoParams,
+)]
+#[serde(deny_unknown_fields)]
+#[into_params(parameter_in = Query)]
+pub struct HpoGenesProfileQuery {
+    /// What should information content be based on.
+    #[serde(default = "IcBasedOn::default")]
+    pub ic_base: IcBasedOn,
+    /// Whether annotations propagate to ancestors, server default if missing.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub annotation_propagation: Option<AnnotationPropagation>,
+    /// Whether to include the NCBI gene information.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub include_gene_info: bool,
+}
+
+/// An annotated term of the gene with its information content.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct HpoGenesProfileTerm {
+    /// The HPO term ID.
+    pub term_id: String,
+    /// The term name.
+    pub name: String,
+    /// The information content of the term.
+    pub ic: f32,
+}
+
+/// The gene's terms within one top-level category.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct HpoGenesProfileCategory {
+    /// The top-level category, `None` for uncategorized terms.
+    pub category: Option<ResultHpoTerm>,
+    /// The number of terms in the category.
+    pub n_terms: usize,
+    /// The sum of the information content of the terms in the category.
+    pub ic_sum: f32,
+    /// The terms in the category, sorted by term ID.
+    pub terms: Vec<HpoGenesProfileTerm>,
+}
+
+/// The phenotype profile of a gene.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct HpoGenesProfile {
+    /// The gene.
+    pub gene: ResultGene,
+    /// The number of annotated terms.
+    pub n_terms: usize,
+    /// The sum of the information content of all annotated terms.
+    pub ic_sum: f32,
+    /// The annotated terms by top-level category, sorted by descending information content.
+    ///
+    /// Terms below more than one category are listed in each of them.
+    pub categories: Vec<HpoGenesProfileCategory>,
+}
+
+/// Container for the result.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct HpoGenesProfileResult {
+    /// Version information.
+    pub version: Version,
+    /// The gene as given in the path.
+    pub gene: String,
+    /// The original query.
+    pub query: HpoGenesProfileQuery,
+    /// The gene's phenotype profile.
+    pub result: HpoGenesProfile,
+}
+
+/// Compute the phenotype profile of `gene`.
+pub fn gene_profile(
+    ontology: &Ontology,
+    ic_tables: &IcTables,
+    ic_based_on: IcBasedOn,
+    propagation: AnnotationPropagation,
+    gene: &Gene,
+    hgnc_id: Option<String>,
+) -> HpoGenesProfile {
+    let mut categories: BTreeMap<Option<HpoTermId>, Vec<HpoGenesProfileTerm>> = BTreeMap::new();
+    let mut ic_sum = 0f32;
+    for term in &gene.to_hpo_set(ontology) {
+        let profile_term = HpoGenesProfileTerm {
+            term_id: term.id().to_string(),
+            name: term.name().to_string(),
+            ic: ic_tables.term_ic(&term, ic_based_on, propagation),
+        };
+        ic_sum += profile_term.ic;
+        let term_categories = term.categories();
+        if term_categories.is_empty() {
+            categories.entry(None).or_default().push(profile_term);
+        } else {
+            for category in term_categories {
+                categories
+                    .entry(Some(category))
+                    .or_default()
+                    .push(profile_term.clone());
+            }
+        }
+    }
+
+    let mut categories = categories
+        .into_iter()
+        .map(|(category, mut terms)| {
+            terms.sort_by(|a, b| a.term_id.cmp(&b.term_id));
+            HpoGenesProfileCategory {
+                category: category
+                    .and_then(|category| ontology.hpo(category))
+                    .map(|category| ResultHpoTerm {
+                        term_id: category.id().to_string(),
+                        name: category.name().to_string(),
+                    }),
+                n_terms: terms.len(),
+                ic_sum: terms.iter().map(|term| term.ic).sum(),
+                terms,
+            }
+        })
+        .collect::<Vec<_>>();
+    categories.sort_by(|a, b| b.ic_sum.total_cmp(&a.ic_sum));
+
+    HpoGenesProfile {
+        gene: ResultGene {
+            ncbi_gene_id: gene.id().as_u32(),
+            gene_symbol: gene.name().to_string(),
+            hgnc_id,
+            gene_info: None,
+        },
+        n_terms: gene.hpo_terms().len(),
+        ic_sum,
+        categories,
+    }
+}
+
+/// Query for the phenotype profile of a gene.
+///
+/// The gene can be given as NCBI gene ID, HGNC gene ID, or gene symbol.  The
+/// gene's annotated terms are grouped by top-level HPO category (organ system)
+/// to render phenotype "fingerprints".
+///
+/// # Errors
+///
+/// In the case that there is an error running the server.
+#[utoipa::path(
+    get,
+    operation_id = "hpoGenesProfile",
+    params(
+        ("gene" = String, Path, description = "NCBI gene ID, HGNC gene ID, or gene symbol."),
+        HpoGenesProfileQuery,
+    ),
+    responses(
+        (status = 200, description = "The query was successful.", body = HpoGenesProfileResult),
+        (status = 400, description = "The requested information content is not available.", body = CustomError),
+        (status = 404, description = "The gene could not be found.", body = CustomError),
+        (status = 500, description = "The server encountered an error.", body = CustomError)
+    )
+)]
+#[get("/api/v1/hpo/genes/{gene}/profile")]
+async fn handle(
+    data: Data<Arc<WebServerData>>,
+    tenant: TenantScope,
+    path: Path<String>,
+    query: StrictQuery<HpoGenesProfileQuery>,
+    _timer: ComputeTimer,
+) -> actix_web::Result<Json<HpoGenesProfileResult>, CustomError> {
+    let ic_tables = tenant.ic_tables(&data);
+    let ontology = &data.ontology;
+    ic_tables
+        .ensure_available(query.ic_base)
+        .map_err(|e| CustomError::with_status(StatusCode::BAD_REQUEST, e))?;
+
+    let gene_str = path.into_inner();
+    let gene = parse_gene_id(&gene_str, &data.hgnc_xlink)
+        .and_then(|gene_id| ontology.gene(&gene_id))
+        .or_else(|| ontology.gene_by_name(&gene_str))
+        .ok_or_else(|| {
+            CustomError::with_status(
+                StatusCode::NOT_FOUND,
+                anyhow::anyhow!("gene not found: {}", &gene_str),
+            )
+        })?;
+
+    let mut result = gene_profile(
+        ontology,
+        ic_tables,
+        query.ic_base,
+        query
+            .annotation_propagation
+            .unwrap_or(data.annotation_propagation),
+        gene,
+        data.hgnc_xlink
+            .hgnc_id(gene.id().as_u32())
+            .map(str::to_string),
+    );
+    result.gene = result
+        .gene
+        .with_gene_info(query.include_gene_info.then_some(&data.gene_info));
+
+    Ok(Json(HpoGenesProfileResult {
+        version: Version::new(&data.ontology.hpo_version()),
+        gene: gene_str,
+        query: query.into_inner(),
+        result,
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use hpo::{
+        annotations::{GeneId, OmimDiseaseId},
+        builder::Builder,
+        Ontology,
+    };
+
+    use crate::server::run::hpo_genes::test::web_server_data;
+    use crate::{
+        algos::ic::IcTables,
+        common::{AnnotationPropagation, IcBasedOn},
+    };
+
+    /// Build a small ontology with the structure
+    ///
+    /// ```text
+    /// HP:0000001 (All)
+    /// +-- HP:0000005 (Mode of inheritance)
+    /// +-- HP:0000118 (Phenotypic abnormality)
+    ///     +-- HP:0000002 (Head)
+    ///     |   +-- HP:0000004 (Eye)
+    ///     +-- HP:0000003 (Heart)
+    /// ```
+    ///
+    /// with gene 1 on `HP:0000004`, `HP:0000003`, and `HP:0000005`, and gene 2
+    /// on `HP:0000002`.
+    fn profile_ontology() -> Ontology {
+        let mut builder = Builder::new();
+        builder.new_term("All", 1u32);
+        builder.new_term("Head", 2u32);
+        builder.new_term("Heart", 3u32);
+        builder.new_term("Eye", 4u32);
+        builder.new_term("Mode of inheritance", 5u32);
+        builder.new_term("Phenotypic abnormality", 118u32);
+        let mut builder = builder.terms_complete();
+        builder.add_parent(1u32, 5u32).expect("term must exist");
+        builder.add_parent(1u32, 118u32).expect("term must exist");
+        builder.add_parent(118u32, 2u32).expect("term must exist");
+        builder.add_parent(118u32, 3u32).expect("term must exist");
+        builder.add_parent(2u32, 4u32).expect("term must exist");
+        let mut builder = builder.connect_all_terms();
+        for term in [4u32, 3u32, 5u32] {
+            builder
+                .annotate_gene(GeneId::from(1), "GENE1", term.into())
+                .expect("term must exist");
+        }
+        builder
+            .annotate_gene(GeneId::from(2), "GENE2", 2u32.into())
+            .expect("term must exist");
+        builder
+            .annotate_omim_disease(OmimDiseaseId::from(1), "Disease 1", 4u32.into())
+            .expect("term must exist");
+        builder
+            .calculate_information_content()
+            .expect("could not compute IC")
+            .build_with_defaults()
+            .expect("could not build ontology")
+    }
+
+    #[test]
+    fn gene_profile() {
+        let ontology = profile_ontology();
+        let ic_tables = IcTables::new(&ontology);
+        let gene = ontology.gene(&GeneId::from(1)).expect("gene must exist");
+
+        let profile = super::gene_profile(
+            &ontology,
+            &ic_tables,
+            IcBasedOn::Gene,
+            AnnotationPropagation::Ancestors,
+            gene,
+            None,
+        );
+
+        assert_eq!(profile.gene.gene_symbol, "GENE1");
+        assert_eq!(profile.n_terms, 3);
+        assert!((profile.ic_sum - 3f32 * 2f32.ln()).abs() < 1e-6);
+        assert_eq!(
+            profile
+                .categories
+                .iter()
+                .map(|c| (c.category.as_ref().map(|t| t.term_id.as_str()), c.n_terms))
+                .collect::<Vec<_>>(),
+            vec![
+                (Some("HP:0000002"), 1),
+                (Some("HP:0000003"), 1),
+                (Some("HP:0000005"), 1),
+            ]
+        );
+        assert_eq!(profile.categories[0].terms[0].term_id, "HP:0000004");
+    }
+
+    #[rstest::rstest]
+    #[actix_web::test]
+    async fn hpo_genes_profile_hgnc_id(
+        web_server_data: &Arc<crate::server::run::WebServerData>,
+    ) -> Result<(), anyhow::Error> {
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(actix_web::web::Data::new(web_server_data.clone()))
+                .service(super::handle),
+        )
+        .await;
+        let req = actix_web::test::TestRequest::get()
+            .uri("/api/v1/hpo/genes/HGNC:3791/profile")
+            .to_request();
+        let resp: super::HpoGenesProfileResult =
+            actix_web::test::call_and_read_body_json(&app, req).await;
+
+        assert_eq!(resp.result.gene.ncbi_gene_id, 2348);
+        assert!(!resp.result.categories.is_empty());
+        assert!(
+            resp.result
+                .categories
+                .iter()
+                .map(|c| c.n_terms)
+                .sum::<usize>()
+                >= resp.result.n_terms
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,142 @@
+//! Implementation of `/hpo/infer/labs`.
+
+use std::sync::Arc;
+
+use actix_web::{
+    post,
+    web::{Data, Json, Path},
+};
+
+use crate::{algos::labs::LabObservation, common::Version, server::run::WebServerData};
+
+use super::{
+    middleware::{ComputeTimer, ResolvedFrom},
+    CustomError, ResultHpoTerm,
+};
+
+/// Request body for `handle`.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, utoipa::ToSchema)]
+pub struct HpoInferLabsRequest {
+    /// The lab observations with abnormal values.
+    pub observations: Vec<LabObservation>,
+}
+
+/// The terms inferred from one observation.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, utoipa::ToSchema)]
+pub struct HpoInferLabsResultEntry {
+    /// The observation.
+    pub observation: LabObservation,
+    /// The inferred terms, sorted by term ID.
+    pub terms: Vec<ResultHpoTerm>,
+}
+
+/// Result for `handle`.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, utoipa::ToSchema)]
+pub struct HpoInferLabsResult {
+    /// Version information.
+    pub version: Version,
+    /// The inferred terms by observation, in the order of the request.
+    pub result: Vec<HpoInferLabsResultEntry>,
+    /// The union of the inferred terms, sorted by term ID, e.g., to pass on as `terms`.
+    pub terms: Vec<ResultHpoTerm>,
+}
+
+/// Infer HPO terms from structured lab observations.
+///
+/// Each observation names the analyte by LOINC code or name and the direction of the
+/// abnormal value.  The terms are inferred by the rules passed with `--path-lab-rules`;
+/// observations without rule yield no terms.
+#[allow(clippy::unused_async)]
+#[utoipa::path(
+    post,
+    operation_id = "hpoInferLabs",
+    request_body = HpoInferLabsRequest,
+    responses(
+        (status = 200, description = "The terms were inferred.", body = HpoInferLabsResult),
+        (status = 413, description = "The request body exceeds the size limit.", body = CustomError),
+        (status = 422, description = "The request exceeds a size or complexity limit.", body = CustomError),
+        (status = 500, description = "The server encountered an error.", body = CustomError)
+    )
+)]
+#[post("/api/v1/hpo/infer/labs")]
+async fn handle(
+    data: Data<Arc<WebServerData>>,
+    _path: Path<()>,
+    request: Json<HpoInferLabsRequest>,
+    resolved_from: ResolvedFrom,
+    _timer: ComputeTimer,
+) -> actix_web::Result<Json<HpoInferLabsResult>, CustomError> {
+    data.limits.check_batch(request.observations.len())?;
+
+    let to_result_terms = |term_ids: &mut Vec<hpo::HpoTermId>| {
+        term_ids.sort();
+        term_ids.dedup();
+        term_ids
+            .iter()
+            .filter_map(|term_id| data.ontology.hpo(*term_id))
+            .map(|term| ResultHpoTerm {
+                term_id: term.id().to_string(),
+                name: term.name().to_string(),
+            })
+            .collect::<Vec<_>>()
+    };
+    let mut all_term_ids = Vec::new();
+    let result = request
+        .into_inner()
+        .observations
+        .into_iter()
+        .map(|observation| {
+            let mut term_ids = data
+                .lab_inference
+                .infer(&observation)
+                .into_iter()
+                .map(|term_id| super::resolve_alt_id(term_id, &data, &resolved_from))
+                .collect::<Vec<_>>();
+            all_term_ids.extend_from_slice(&term_ids);
+            HpoInferLabsResultEntry {
+                observation,
+                terms: to_result_terms(&mut term_ids),
+            }
+        })
+        .collect();
+
+    Ok(Json(HpoInferLabsResult {
+        version: Version::new(&data.ontology.hpo_version()),
+        result,
+        terms: to_result_terms(&mut all_term_ids),
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use crate::server::run::hpo_genes::test::web_server_data;
+
+    #[rstest::rstest]
+    #[actix_web::test]
+    async fn hpo_infer_labs_without_rules(
+        web_server_data: &Arc<crate::server::run::WebServerData>,
+    ) -> Result<(), anyhow::Error> {
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(actix_web::web::Data::new(web_server_data.clone()))
+                .service(super::handle),
+        )
+        .await;
+        let req = actix_web::test::TestRequest::post()
+            .uri("/api/v1/hpo/infer/labs")
+            .set_json(serde_json::json!({
+                "observations": [{"analyte": "ALT", "direction": "high"}]
+            }))
+            .to_request();
+        let resp: super::HpoInferLabsResult =
+            actix_web::test::call_and_read_body_json(&app, req).await;
+
+        assert_eq!(resp.result.len(), 1);
+        assert!(resp.result[0].terms.is_empty());
+        assert!(resp.terms.is_empty());
+
+        Ok(())
+    }
+}
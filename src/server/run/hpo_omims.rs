@@ -4,17 +4,26 @@ use std::sync::Arc;
 
 use actix_web::{
     get,
-    web::{self, Data, Json, Path},
+    web::{Data, Json, Path},
 };
 use hpo::{
-    annotations::{Disease as _, OmimDisease, OmimDiseaseId},
+    annotations::{AnnotationId as _, Disease as _, OmimDisease, OmimDiseaseId},
     term::HpoGroup,
-    Ontology,
+    HpoSet, Ontology,
 };
 
-use crate::{common::Version, server::run::WebServerData};
+use crate::{
+    algos::sex::{Sex, SexFilter},
+    common::Version,
+    server::run::WebServerData,
+    sources::{
+        disease_codes::{DiseaseCodes, Icd10Code},
+        prevalence::{DiseasePrevalences, Prevalence},
+    },
+};
 
-use super::{CustomError, Match, ResultHpoTerm};
+use super::{middleware::ComputeTimer, CustomError, Match, ResultHpoTerm};
+use crate::server::run::strict_query::StrictQuery;
 
 /// Parameters for `handle`.
 ///
@@ -25,6 +34,9 @@ use super::{CustomError, Match, ResultHpoTerm};
 /// - `name` -- specify the name to query for
 /// - `max_results` -- the maximum number of records to return
 /// - `hpo_terms` -- whether to include `"hpo_terms"` in result
+/// - `inheritance` -- restrict to diseases with any of the modes of inheritance
+/// - `sex` -- exclude `"hpo_terms"` restricted to the other sex
+/// - `lang` -- language of the disease names, falls back to English
 ///
 /// The following propery defines how matches are performed:
 ///
@@ -32,6 +44,8 @@ use super::{CustomError, Match, ResultHpoTerm};
 #[derive(
     Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema, utoipa::IntoParams,
 )]
+#[serde(deny_unknown_fields)]
+#[into_params(parameter_in = Query)]
 pub struct HpoOmimsQuery {
     /// The OMIM ID to search for.
     pub omim_id: Option<String>,
@@ -47,27 +61,62 @@ pub struct HpoOmimsQuery {
     /// Whether to include HPO terms.
     #[serde(default = "_default_hpo_terms")]
     pub hpo_terms: bool,
+    /// Restrict to diseases with any of the modes of inheritance, e.g., `AR,AD,XL`.
+    #[serde(
+        default = "Option::default",
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "super::option_vec_str_deserialize"
+    )]
+    pub inheritance: Option<Vec<String>>,
+    /// Exclude HPO terms restricted to the other sex.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub sex: Option<Sex>,
+    /// Language of the disease names, e.g., `de`; untranslated names are in English.
+    ///
+    /// The `name` search always matches the English names.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub lang: Option<String>,
 }
 
 impl HpoOmimsQuery {
     /// Strip "OMIM:" prefix from `omim_id`, if any.
     fn with_stripped_prefix(self) -> Self {
         Self {
-            omim_id: self.omim_id.map(|omim_id| {
-                let lower_omim_id = omim_id.to_lowercase();
-                if lower_omim_id.starts_with("omim:") {
-                    omim_id[5..].to_string()
-                } else if lower_omim_id.starts_with("mim:") {
-                    omim_id[4..].to_string()
-                } else {
-                    omim_id
-                }
-            }),
+            omim_id: self
+                .omim_id
+                .map(|omim_id| strip_omim_prefix(&omim_id).to_string()),
             ..self
         }
     }
 }
 
+/// Strip "OMIM:" or "MIM:" prefix from `omim_id`, if any, ignoring case.
+pub(super) fn strip_omim_prefix(omim_id: &str) -> &str {
+    let lower_omim_id = omim_id.to_lowercase();
+    if lower_omim_id.starts_with("omim:") {
+        &omim_id[5..]
+    } else if lower_omim_id.starts_with("mim:") {
+        &omim_id[4..]
+    } else {
+        omim_id
+    }
+}
+
+/// Return the name of `omim_disease` in `lang` by the Orphanet translations, falling back
+/// to English.
+pub(super) fn omim_name(
+    omim_disease: &OmimDisease,
+    data: &WebServerData,
+    lang: Option<&str>,
+) -> String {
+    lang.and_then(|lang| {
+        data.disease_translations
+            .get_omim(lang, omim_disease.id().as_u32(), &data.disease_codes)
+    })
+    .unwrap_or(omim_disease.name())
+    .to_string()
+}
+
 /// Return default of `Request::max_results`.
 fn _default_max_results() -> usize {
     100
@@ -88,6 +137,16 @@ pub struct HpoOmimsResultEntry {
     /// The gene's associated HPO terms.
     #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
     pub hpo_terms: Option<Vec<ResultHpoTerm>>,
+    /// The ORPHA codes of the disease by Orphanet's mappings, e.g., `ORPHA:558`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub orpha_ids: Vec<String>,
+    /// The ICD-10 codes of the disease by Orphanet's mappings.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub icd10_codes: Vec<Icd10Code>,
+    /// The prevalence of the disease by way of its Orphanet diseases, if loaded with
+    /// `--path-orphanet-prevalence`.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub prevalence: Option<Prevalence>,
 }
 
 impl PartialEq for HpoOmimsResultEntry {
@@ -116,14 +175,24 @@ impl Ord for HpoOmimsResultEntry {
 
 impl HpoOmimsResultEntry {
     /// Create a `ResultEntry` from an `OmimDisease`.
+    ///
+    /// The terms restricted to the other sex are left out if `sex` is given.  The
+    /// disease is annotated with its ORPHA and ICD-10 codes from `disease_codes` and with
+    /// the prevalence of its Orphanet diseases from `prevalences`, if given.
     pub fn from_omim_disease_with_ontology(
         omim_disease: &OmimDisease,
         ontology: &Ontology,
         hpo_terms: bool,
+        sex: Option<SexFilter>,
+        disease_codes: &DiseaseCodes,
+        prevalences: Option<&DiseasePrevalences>,
     ) -> Self {
         let hpo_terms = if hpo_terms {
-            let mut result = omim_disease
-                .to_hpo_set(ontology)
+            let terms = match sex {
+                Some(sex) => sex.omim_terms(omim_disease),
+                None => omim_disease.hpo_terms().clone(),
+            };
+            let mut result = HpoSet::new(ontology, terms)
                 .child_nodes()
                 .into_iter()
                 .collect::<HpoGroup>()
@@ -139,10 +208,22 @@ impl HpoOmimsResultEntry {
         } else {
             None
         };
+        let omim_id = omim_disease.id().as_u32();
         HpoOmimsResultEntry {
             omim_id: omim_disease.id().to_string(),
             name: omim_disease.name().to_string(),
             hpo_terms,
+            orpha_ids: disease_codes
+                .orpha_codes_for_omim(omim_id)
+                .iter()
+                .map(|orpha_code| format!("ORPHA:{orpha_code}"))
+                .collect(),
+            icd10_codes: disease_codes.icd10_codes_for_omim(omim_id),
+            prevalence: prevalences
+                .and_then(|prevalences| {
+                    prevalences.best_of(disease_codes.orpha_codes_for_omim(omim_id))
+                })
+                .cloned(),
         }
     }
 }
@@ -166,6 +247,7 @@ pub struct HpoOmimsResult {
     params(HpoOmimsQuery),
     responses(
         (status = 200, description = "The query was successful.", body = HpoOmimsResult),
+        (status = 400, description = "A mode of inheritance is unknown.", body = CustomError),
         (status = 500, description = "The server encountered an error.", body = CustomError)
     )
 )]
@@ -173,7 +255,8 @@ pub struct HpoOmimsResult {
 async fn handle(
     data: Data<Arc<WebServerData>>,
     _path: Path<()>,
-    query: web::Query<HpoOmimsQuery>,
+    query: StrictQuery<HpoOmimsQuery>,
+    _timer: ComputeTimer,
 ) -> actix_web::Result<Json<HpoOmimsResult>, CustomError> {
     let ontology = &data.ontology;
     let match_ = query.r#match.unwrap_or_default();
@@ -181,6 +264,28 @@ async fn handle(
 
     // Strip "OMIM:" and "MIM:" prefix from `query.omim_id` if given.
     let query = query.into_inner().with_stripped_prefix();
+    let inheritance = super::parse_inheritance(query.inheritance.as_deref())?;
+    let sex = query.sex.map(|sex| SexFilter {
+        index: &data.sex_index,
+        sex,
+    });
+    let has_inheritance = |omim_disease: &OmimDisease| {
+        inheritance.as_ref().is_none_or(|modes| {
+            data.inheritance_index
+                .omim_matches(omim_disease.id(), modes)
+        })
+    };
+    let to_entry = |omim_disease: &OmimDisease| HpoOmimsResultEntry {
+        name: omim_name(omim_disease, &data, query.lang.as_deref()),
+        ..HpoOmimsResultEntry::from_omim_disease_with_ontology(
+            omim_disease,
+            ontology,
+            query.hpo_terms,
+            sex,
+            &data.disease_codes,
+            data.sources.get::<DiseasePrevalences>(),
+        )
+    };
 
     if match_ == Match::Exact {
         let omim_disease = if let Some(omim_id) = &query.omim_id {
@@ -212,12 +317,10 @@ async fn handle(
         } else {
             None
         };
-        if let Some(omim_disease) = &omim_disease {
-            result.push(HpoOmimsResultEntry::from_omim_disease_with_ontology(
-                omim_disease,
-                ontology,
-                query.hpo_terms,
-            ));
+        if let Some(omim_disease) =
+            omim_disease.filter(|omim_disease| has_inheritance(omim_disease))
+        {
+            result.push(to_entry(omim_disease));
         }
     } else if let Some(name) = &query.name {
         let mut it = ontology.omim_diseases();
@@ -240,12 +343,8 @@ async fn handle(
                 Match::Contains => omim_name.contains(&name),
                 Match::Exact => panic!("cannot happen here"),
             };
-            if is_match {
-                result.push(HpoOmimsResultEntry::from_omim_disease_with_ontology(
-                    omim_disease.as_ref().expect("checked above"),
-                    ontology,
-                    query.hpo_terms,
-                ));
+            if is_match && has_inheritance(omim_disease.as_ref().expect("checked above")) {
+                result.push(to_entry(omim_disease.as_ref().expect("checked above")));
             }
 
             omim_disease = it.next();
@@ -267,7 +366,7 @@ async fn handle(
 mod test {
     use std::sync::Arc;
 
-    use crate::server::run::hpo_genes::test::web_server_data;
+    use crate::server::run::hpo_genes::test::{load_web_server_data, web_server_data};
 
     /// Helper function for running a query.
     #[allow(dead_code)]
@@ -422,4 +521,46 @@ mod test {
             .await?
         ))
     }
+
+    #[actix_web::test]
+    async fn hpo_omims_lang() -> Result<(), anyhow::Error> {
+        let tmp_dir = temp_testdir::TempDir::default();
+        let path = tmp_dir.join("orphanet-names.tsv");
+        std::fs::write(
+            &path,
+            "language\torpha_code\tname\nde\t1388\tCatel-Manzke-Syndrom\n",
+        )?;
+        let data = Arc::new(crate::server::run::WebServerData {
+            disease_translations: crate::sources::translations::DiseaseTranslations::from_paths(
+                &[&path],
+            )?,
+            disease_codes: crate::sources::disease_codes::DiseaseCodes::from_records([
+                crate::sources::disease_codes::Record {
+                    orpha_code: 1388,
+                    source: "OMIM".to_string(),
+                    reference: "616145".to_string(),
+                    mapping_relation: None,
+                },
+            ]),
+            ..load_web_server_data()
+        });
+        let name = |lang: &'static str| {
+            let data = data.clone();
+            async move {
+                let uri = format!("/api/v1/hpo/omims?omim_id=616145{lang}");
+                run_query(data, &uri)
+                    .await
+                    .map(|result| result.result[0].name.clone())
+            }
+        };
+
+        let english = name("").await?;
+
+        assert_eq!(name("&lang=de").await?, "Catel-Manzke-Syndrom");
+        assert_ne!(english, "Catel-Manzke-Syndrom");
+        // Untranslated names fall back to English.
+        assert_eq!(name("&lang=fr").await?, english);
+
+        Ok(())
+    }
 }
@@ -0,0 +1,496 @@
+//! Implementation of `/hpo/ontology/export`.
+
+use std::{
+    convert::Infallible,
+    io::Write,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use actix_web::{
+    body::{BodySize, MessageBody},
+    get,
+    http::{header, StatusCode},
+    web::{self, Bytes, Data, Path},
+    HttpRequest, HttpResponse,
+};
+use hpo::Ontology;
+use subtle::ConstantTimeEq as _;
+
+use crate::server::run::WebServerData;
+
+use super::{middleware::ComputeTimer, CustomError};
+use crate::server::run::strict_query::StrictQuery;
+
+/// Prefix of the OBO PURLs used in OBO Graphs.
+const OBO_PURL_PREFIX: &str = "http://purl.obolibrary.org/obo/";
+
+/// Number of bytes sent per chunk of the response body.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Settings for the ontology export.
+#[derive(Debug, Clone)]
+pub struct ExportConfig {
+    /// Bearer token required for export, the export is refused without.
+    pub token: Option<String>,
+    /// Maximal size of an export in bytes.
+    pub max_bytes: usize,
+}
+
+impl Default for ExportConfig {
+    fn default() -> Self {
+        Self {
+            token: None,
+            max_bytes: 100_000_000,
+        }
+    }
+}
+
+/// The format to export the ontology in.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    serde::Serialize,
+    serde::Deserialize,
+    utoipa::ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    /// The original OBO document.
+    #[default]
+    Obo,
+    /// OBO Graphs JSON.
+    Obographs,
+    /// Tab-separated list of `is_a` edges.
+    TsvEdges,
+}
+
+/// Parameters for `handle`.
+///
+/// - `format` -- the format to export the ontology in
+#[derive(
+    Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema, utoipa::IntoParams,
+)]
+#[serde(deny_unknown_fields)]
+#[into_params(parameter_in = Query)]
+pub struct HpoOntologyExportQuery {
+    /// The export format.
+    #[serde(default = "ExportFormat::default")]
+    pub format: ExportFormat,
+}
+
+/// A node in an OBO Graphs document.
+#[derive(Debug, serde::Serialize)]
+struct ObographsNode {
+    /// The node's IRI.
+    id: String,
+    /// The node's label.
+    lbl: String,
+    /// The node type.
+    #[serde(rename = "type")]
+    type_: &'static str,
+}
+
+/// An edge in an OBO Graphs document.
+#[derive(Debug, serde::Serialize)]
+struct ObographsEdge {
+    /// The subject's IRI.
+    sub: String,
+    /// The predicate.
+    pred: &'static str,
+    /// The object's IRI.
+    obj: String,
+}
+
+/// Graph metadata in an OBO Graphs document.
+#[derive(Debug, serde::Serialize)]
+struct ObographsMeta {
+    /// The version of the ontology.
+    version: String,
+}
+
+/// A graph in an OBO Graphs document.
+#[derive(Debug, serde::Serialize)]
+struct ObographsGraph {
+    /// The ontology IRI.
+    id: String,
+    /// Graph metadata.
+    meta: ObographsMeta,
+    /// The graph's nodes.
+    nodes: Vec<ObographsNode>,
+    /// The graph's edges.
+    edges: Vec<ObographsEdge>,
+}
+
+/// An OBO Graphs document.
+#[derive(Debug, serde::Serialize)]
+struct ObographsDoc {
+    /// The graphs in the document.
+    graphs: Vec<ObographsGraph>,
+}
+
+/// Convert a term ID such as `HP:0000001` to an OBO PURL.
+fn to_purl(term_id: &str) -> String {
+    format!("{}{}", OBO_PURL_PREFIX, term_id.replace(':', "_"))
+}
+
+/// Return all `(term, parent)` pairs of the ontology, sorted by term ID.
+fn is_a_edges(ontology: &Ontology) -> Vec<(hpo::HpoTermId, hpo::HpoTermId)> {
+    let mut result = ontology
+        .hpos()
+        .flat_map(|term| {
+            term.parent_ids()
+                .iter()
+                .map(|parent_id| (term.id(), parent_id))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    result.sort();
+    result
+}
+
+/// Write the ontology as OBO Graphs JSON to `writer`.
+fn write_obographs<W: Write>(ontology: &Ontology, writer: W) -> Result<(), std::io::Error> {
+    let mut nodes = ontology
+        .hpos()
+        .map(|term| ObographsNode {
+            id: to_purl(&term.id().to_string()),
+            lbl: term.name().to_string(),
+            type_: "CLASS",
+        })
+        .collect::<Vec<_>>();
+    nodes.sort_by(|a, b| a.id.cmp(&b.id));
+    let edges = is_a_edges(ontology)
+        .into_iter()
+        .map(|(term_id, parent_id)| ObographsEdge {
+            sub: to_purl(&term_id.to_string()),
+            pred: "is_a",
+            obj: to_purl(&parent_id.to_string()),
+        })
+        .collect();
+    let doc = ObographsDoc {
+        graphs: vec![ObographsGraph {
+            id: format!("{OBO_PURL_PREFIX}hp.json"),
+            meta: ObographsMeta {
+                version: ontology.hpo_version(),
+            },
+            nodes,
+            edges,
+        }],
+    };
+    Ok(serde_json::to_writer(writer, &doc)?)
+}
+
+/// Write the ontology `is_a` edges as TSV to `writer`.
+fn write_tsv_edges<W: Write>(ontology: &Ontology, mut writer: W) -> Result<(), std::io::Error> {
+    writeln!(writer, "term_id\tparent_id")?;
+    for (term_id, parent_id) in is_a_edges(ontology) {
+        writeln!(writer, "{term_id}\t{parent_id}")?;
+    }
+    Ok(())
+}
+
+/// Buffer that fails writes beyond `max_bytes`, so that an export is aborted as soon as it
+/// exceeds the limit instead of being rendered completely.
+#[derive(Debug)]
+struct LimitedBuffer {
+    /// The bytes written so far.
+    buf: Vec<u8>,
+    /// The maximal number of bytes.
+    max_bytes: usize,
+    /// Whether a write exceeded the limit.
+    exceeded: bool,
+}
+
+impl Write for LimitedBuffer {
+    fn write(&mut self, data: &[u8]) -> std::io::Result<usize> {
+        if self.buf.len() + data.len() > self.max_bytes {
+            self.exceeded = true;
+            return Err(std::io::Error::other("export size limit exceeded"));
+        }
+        self.buf.extend_from_slice(data);
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Render the ontology with the OBO document `hpo_doc` in `format`.
+///
+/// # Errors
+///
+/// With 413 as soon as the export exceeds `max_bytes`.
+fn render(
+    ontology: &Ontology,
+    hpo_doc: &impl std::fmt::Display,
+    format: ExportFormat,
+    max_bytes: usize,
+) -> Result<Vec<u8>, CustomError> {
+    let mut buffer = LimitedBuffer {
+        buf: Vec::new(),
+        max_bytes,
+        exceeded: false,
+    };
+    let written = match format {
+        ExportFormat::Obo => write!(buffer, "{hpo_doc}"),
+        ExportFormat::Obographs => write_obographs(ontology, &mut buffer),
+        ExportFormat::TsvEdges => write_tsv_edges(ontology, &mut buffer),
+    };
+    match written {
+        Ok(()) => Ok(buffer.buf),
+        Err(_) if buffer.exceeded => Err(CustomError::with_status(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            anyhow::anyhow!("export exceeds limit of {} bytes", max_bytes),
+        )),
+        Err(e) => Err(CustomError::new(e.into())),
+    }
+}
+
+/// Response body that sends the rendered export chunk by chunk.
+struct ChunkedBody {
+    /// The rendered export.
+    bytes: Bytes,
+    /// Offset of the next chunk.
+    pos: usize,
+}
+
+impl MessageBody for ChunkedBody {
+    type Error = Infallible;
+
+    fn size(&self) -> BodySize {
+        BodySize::Stream
+    }
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, Self::Error>>> {
+        let this = self.get_mut();
+        if this.pos >= this.bytes.len() {
+            return Poll::Ready(None);
+        }
+
+        let end = (this.pos + CHUNK_SIZE).min(this.bytes.len());
+        let chunk = this.bytes.slice(this.pos..end);
+        this.pos = end;
+
+        Poll::Ready(Some(Ok(chunk)))
+    }
+}
+
+/// Check the `Authorization` header against the configured token.
+///
+/// The token is compared in constant time.  Without a configured token, the export is
+/// refused; the server does not register the route then.
+fn check_token(req: &HttpRequest, config: &ExportConfig) -> Result<(), CustomError> {
+    let Some(token) = &config.token else {
+        return Err(CustomError::with_status(
+            StatusCode::FORBIDDEN,
+            anyhow::anyhow!("the ontology export requires --export-token"),
+        ));
+    };
+    let given = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    if given.is_some_and(|given| bool::from(given.as_bytes().ct_eq(token.as_bytes()))) {
+        Ok(())
+    } else {
+        Err(CustomError::with_status(
+            StatusCode::UNAUTHORIZED,
+            anyhow::anyhow!("missing or invalid bearer token"),
+        ))
+    }
+}
+
+/// Export the loaded ontology.
+///
+/// This allows clients to interpret term IDs against exactly the HPO release that
+/// the server uses.  The export is rendered on the blocking thread pool and sent in
+/// chunks.  The route is only served with `--export-token`.
+///
+/// # Errors
+///
+/// In the case that there is an error running the server.
+#[utoipa::path(
+    get,
+    operation_id = "hpoOntologyExport",
+    params(HpoOntologyExportQuery),
+    responses(
+        (status = 200, description = "The ontology in the requested format.", body = String),
+        (status = 401, description = "Missing or invalid bearer token.", body = CustomError),
+        (status = 403, description = "No --export-token is configured.", body = CustomError),
+        (status = 413, description = "The export exceeds the size limit.", body = CustomError),
+        (status = 500, description = "The server encountered an error.", body = CustomError),
+        (status = 503, description = "The server is low on memory.", body = CustomError)
+    )
+)]
+#[get("/api/v1/hpo/ontology/export")]
+async fn handle(
+    req: HttpRequest,
+    data: Data<Arc<WebServerData>>,
+    _path: Path<()>,
+    query: StrictQuery<HpoOntologyExportQuery>,
+    _timer: ComputeTimer,
+) -> actix_web::Result<HttpResponse, CustomError> {
+    check_token(&req, &data.export_config)?;
+
+    let content_type = match query.format {
+        ExportFormat::Obo => "text/plain; charset=utf-8",
+        ExportFormat::Obographs => "application/json",
+        ExportFormat::TsvEdges => "text/tab-separated-values; charset=utf-8",
+    };
+    let format = query.format;
+    let data = data.get_ref().clone();
+    let body = web::block(move || {
+        render(
+            &data.ontology,
+            data.full_text_index.hpo_doc(),
+            format,
+            data.export_config.max_bytes,
+        )
+    })
+    .await
+    .map_err(|e| CustomError::new(anyhow::anyhow!(e)))??;
+
+    Ok(HttpResponse::Ok()
+        .content_type(content_type)
+        .body(ChunkedBody {
+            bytes: Bytes::from(body),
+            pos: 0,
+        }))
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use actix_web::{http::StatusCode, ResponseError as _};
+
+    use super::ExportFormat;
+
+    /// Helper function for running a query with the bearer token `secret`.
+    async fn run_query(uri: &str) -> actix_web::dev::ServiceResponse {
+        let web_server_data = crate::server::run::WebServerData {
+            export_config: super::ExportConfig {
+                token: Some("secret".to_string()),
+                ..Default::default()
+            },
+            ..crate::server::run::hpo_genes::test::load_web_server_data()
+        };
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(actix_web::web::Data::new(Arc::new(web_server_data)))
+                .service(super::handle),
+        )
+        .await;
+        let req = actix_web::test::TestRequest::get()
+            .uri(uri)
+            .insert_header(("Authorization", "Bearer secret"))
+            .to_request();
+        actix_web::test::call_service(&app, req).await
+    }
+
+    #[test]
+    fn to_purl() {
+        assert_eq!(
+            super::to_purl("HP:0000118"),
+            "http://purl.obolibrary.org/obo/HP_0000118"
+        );
+    }
+
+    #[rstest::rstest]
+    #[case(None, false)]
+    #[case(Some("Bearer secret"), true)]
+    #[case(Some("Bearer secreT"), false)]
+    #[case(Some("Bearer secret2"), false)]
+    #[case(Some("secret"), false)]
+    fn check_token(#[case] authorization: Option<&str>, #[case] expected_ok: bool) {
+        let mut req = actix_web::test::TestRequest::default();
+        if let Some(authorization) = authorization {
+            req = req.insert_header(("Authorization", authorization));
+        }
+        let req = req.to_http_request();
+        let config = super::ExportConfig {
+            token: Some("secret".to_string()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            super::check_token(&req, &super::ExportConfig::default())
+                .map_err(|err| err.status_code()),
+            Err(StatusCode::FORBIDDEN)
+        );
+        match super::check_token(&req, &config) {
+            Ok(()) => assert!(expected_ok),
+            Err(err) => {
+                assert!(!expected_ok);
+                assert_eq!(err.status_code(), StatusCode::UNAUTHORIZED);
+            }
+        }
+    }
+
+    #[rstest::rstest]
+    #[case(ExportFormat::Obo)]
+    #[case(ExportFormat::Obographs)]
+    #[case(ExportFormat::TsvEdges)]
+    fn render_max_bytes(#[case] format: ExportFormat) -> Result<(), anyhow::Error> {
+        let ontology = crate::algos::ic::test::small_ontology();
+        let hpo_doc = &"format-version: 1.2\n";
+
+        let body = super::render(&ontology, hpo_doc, format, 100_000)
+            .map_err(|err| anyhow::anyhow!("{}", err.err))?;
+        assert!(!body.is_empty());
+
+        let err = super::render(&ontology, hpo_doc, format, body.len() - 1)
+            .expect_err("export must exceed the limit");
+        assert_eq!(err.status_code(), StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(
+            super::render(&ontology, hpo_doc, format, body.len())
+                .map_err(|err| anyhow::anyhow!("{}", err.err))?,
+            body
+        );
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn hpo_ontology_export_tsv_edges() -> Result<(), anyhow::Error> {
+        let resp = run_query("/api/v1/hpo/ontology/export?format=tsv_edges").await;
+        assert!(resp.status().is_success());
+        let body = actix_web::test::read_body(resp).await;
+        let body = std::str::from_utf8(&body)?;
+        assert!(body.starts_with("term_id\tparent_id\n"));
+        assert!(body.contains("HP:0000118\tHP:0000001\n"));
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn chunked_body() -> Result<(), anyhow::Error> {
+        let bytes = actix_web::web::Bytes::from(vec![b'x'; super::CHUNK_SIZE * 2 + 1]);
+        let body = super::ChunkedBody {
+            bytes: bytes.clone(),
+            pos: 0,
+        };
+
+        assert_eq!(
+            actix_web::body::MessageBody::size(&body),
+            actix_web::body::BodySize::Stream
+        );
+        let sent = actix_web::body::to_bytes(body)
+            .await
+            .map_err(|e| anyhow::anyhow!("{}", e))?;
+        assert_eq!(sent, bytes);
+
+        Ok(())
+    }
+}
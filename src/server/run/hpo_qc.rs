@@ -0,0 +1,190 @@
+//! Implementation of `/hpo/qc`.
+
+use std::sync::Arc;
+
+use actix_web::{
+    get,
+    http::StatusCode,
+    web::{Data, Json, Path},
+};
+
+use crate::algos::qc::{check_terms, QcReport};
+use crate::common::{AnnotationPropagation, IcBasedOn, Version};
+use crate::server::run::strict_query::StrictQuery;
+use crate::server::run::tenants::TenantScope;
+use crate::server::{
+    run::middleware::{ComputeTimer, ResolvedFrom},
+    run::CustomError,
+    run::WebServerData,
+};
+
+/// Default minimal information content below which terms are flagged.
+fn _default_min_ic() -> f32 {
+    1.0
+}
+
+/// Parameters for `handle`.
+///
+/// - `terms` -- set of terms to check
+/// - `ic_base` -- what the information content should be based on
+/// - `min_ic` -- terms with lower information content are flagged
+/// - `annotation_propagation` -- whether annotations propagate to ancestors
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::IntoParams)]
+#[serde(deny_unknown_fields)]
+#[into_params(parameter_in = Query)]
+pub struct RequestQuery {
+    /// The set of HPO terms to check.
+    #[serde(deserialize_with = "super::vec_str_deserialize")]
+    pub terms: Vec<String>,
+    /// What should information content be based on.
+    #[serde(default = "IcBasedOn::default")]
+    pub ic_base: IcBasedOn,
+    /// Terms with lower information content are flagged.
+    #[serde(default = "_default_min_ic")]
+    pub min_ic: f32,
+    /// Whether annotations propagate to ancestors, server default if missing.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub annotation_propagation: Option<AnnotationPropagation>,
+}
+
+/// Request as sent together with the response.
+///
+/// The difference is that the `terms` field is replaced by a vec.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct HpoQcQuery {
+    /// The set of HPO terms to check.
+    pub terms: Vec<String>,
+    /// What should information content be based on.
+    pub ic_base: IcBasedOn,
+    /// Terms with lower information content are flagged.
+    pub min_ic: f32,
+    /// Whether annotations propagate to ancestors, server default if missing.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub annotation_propagation: Option<AnnotationPropagation>,
+}
+
+/// Result container.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct HpoQcResult {
+    /// Version information.
+    pub version: Version,
+    /// The original query.
+    pub query: HpoQcQuery,
+    /// The diagnostics for the term set.
+    pub result: QcReport,
+}
+
+/// Check a set of terms before prioritization.
+///
+/// Reports duplicate, unknown, obsolete, and non-phenotype terms, terms with low
+/// information content, redundant ancestor/descendant pairs, and an overall
+/// informativeness score.
+///
+/// # Errors
+///
+/// In the case that there is an error running the server.
+#[utoipa::path(
+    get,
+    operation_id = "hpoQc",
+    params(RequestQuery),
+    responses(
+        (status = 200, description = "The query was successful.", body = HpoQcResult),
+        (status = 400, description = "The requested information content is not available.", body = CustomError),
+        (status = 500, description = "The server encountered an error.", body = CustomError)
+    )
+)]
+#[get("/api/v1/hpo/qc")]
+async fn handle(
+    data: Data<Arc<WebServerData>>,
+    tenant: TenantScope,
+    _path: Path<()>,
+    query: StrictQuery<RequestQuery>,
+    resolved_from: ResolvedFrom,
+    _timer: ComputeTimer,
+) -> actix_web::Result<Json<HpoQcResult>, CustomError> {
+    let ic_tables = tenant.ic_tables(&data);
+    ic_tables
+        .ensure_available(query.ic_base)
+        .map_err(|e| CustomError::with_status(StatusCode::BAD_REQUEST, e))?;
+
+    let RequestQuery {
+        terms,
+        ic_base,
+        min_ic,
+        annotation_propagation,
+    } = query.into_inner();
+    // Resolve alternative IDs, malformed IDs are reported as unknown.
+    let resolved_terms = terms
+        .iter()
+        .map(|term| match crate::common::parse_term_id(term) {
+            Ok(term_id) => super::resolve_alt_id(term_id, &data, &resolved_from).to_string(),
+            Err(_) => term.clone(),
+        })
+        .collect::<Vec<_>>();
+    let result = check_terms(
+        &data.ontology,
+        ic_tables,
+        ic_base,
+        annotation_propagation.unwrap_or(data.annotation_propagation),
+        min_ic,
+        &resolved_terms,
+    );
+
+    Ok(Json(HpoQcResult {
+        version: Version::new(&data.ontology.hpo_version()),
+        query: HpoQcQuery {
+            terms,
+            ic_base,
+            min_ic,
+            annotation_propagation,
+        },
+        result,
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use crate::server::run::hpo_genes::test::web_server_data;
+
+    /// Helper function for running a query.
+    async fn run_query(
+        web_server_data: Arc<crate::server::run::WebServerData>,
+        uri: &str,
+    ) -> Result<super::HpoQcResult, anyhow::Error> {
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(actix_web::web::Data::new(web_server_data))
+                .service(super::handle),
+        )
+        .await;
+        let req = actix_web::test::TestRequest::get().uri(uri).to_request();
+        let resp: super::HpoQcResult = actix_web::test::call_and_read_body_json(&app, req).await;
+
+        Ok(resp)
+    }
+
+    #[rstest::rstest]
+    #[actix_web::test]
+    async fn hpo_qc_redundant(
+        web_server_data: &Arc<crate::server::run::WebServerData>,
+    ) -> Result<(), anyhow::Error> {
+        let result = run_query(
+            web_server_data.clone(),
+            "/api/v1/hpo/qc?terms=HP:0000118,HP:0000252,HP:0000252,HP:0000006",
+        )
+        .await?;
+
+        assert_eq!(result.result.duplicates, vec!["HP:0000252"]);
+        assert_eq!(result.result.non_phenotype.len(), 1);
+        assert_eq!(result.result.non_phenotype[0].term_id, "HP:0000006");
+        assert!(result
+            .result
+            .redundant
+            .iter()
+            .any(|p| p.ancestor == "HP:0000118" && p.descendant == "HP:0000252"));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,307 @@
+//! Entry point `/hpo/sim/family-gene` that scores genes for a proband and down-weights
+//! the genes that match unaffected relatives.
+
+use std::sync::Arc;
+
+use actix_web::{
+    http::StatusCode,
+    post,
+    web::{Data, Json, Path},
+};
+use hpo::{annotations::AnnotationId as _, term::HpoGroup};
+
+use super::super::{
+    middleware::{ComputeTimer, ResolvedFrom},
+    CustomError,
+};
+use crate::{
+    algos::phenomizer::{self, TermWeights},
+    common::Version,
+    query::GeneScorer,
+    server::run::WebServerData,
+    sources::gene_info::GeneInfo,
+};
+
+/// A relative of the proband.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, utoipa::ToSchema)]
+pub struct Relative {
+    /// Name of the relative, e.g., `father`.
+    pub name: String,
+    /// Whether the relative is affected.
+    pub affected: bool,
+    /// The relative's terms, optionally with clinical modifiers.
+    pub terms: Vec<String>,
+}
+
+/// Request body for `handle`.
+///
+/// - `proband` -- the proband's terms, optionally with clinical modifiers, e.g.,
+///   `HP:0001249[HP:0012828]`
+/// - `relatives` -- the affected and unaffected relatives with their terms
+/// - `gene_ids` -- set of ids for genes to score, can be NCBI or HGNC gene ID
+/// - `gene_symbols` -- set of symbols for genes to score
+/// - `unaffected_weight` -- how strongly genes matching unaffected relatives are
+///   down-weighted
+/// - `include_gene_info` -- whether to include `"gene_info"` in result
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, utoipa::ToSchema)]
+pub struct HpoSimFamilyGeneRequest {
+    /// The proband's terms, optionally with clinical modifiers.
+    pub proband: Vec<String>,
+    /// The relatives of the proband.
+    #[serde(default)]
+    pub relatives: Vec<Relative>,
+    /// The set of ids for genes to score.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub gene_ids: Option<Vec<String>>,
+    /// The set of symbols for genes to score.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub gene_symbols: Option<Vec<String>>,
+    /// How strongly genes matching unaffected relatives are down-weighted, between 0
+    /// and 1.
+    #[serde(default = "_default_unaffected_weight")]
+    pub unaffected_weight: f32,
+    /// Whether to include the NCBI gene information.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub include_gene_info: bool,
+}
+
+/// Return default of `HpoSimFamilyGeneRequest::unaffected_weight`.
+fn _default_unaffected_weight() -> f32 {
+    0.5
+}
+
+/// Score of a gene for a relative.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, utoipa::ToSchema)]
+pub struct HpoSimFamilyGeneRelativeScore {
+    /// Name of the relative.
+    pub name: String,
+    /// Whether the relative is affected.
+    pub affected: bool,
+    /// The Phenomizer score of the relative's terms.
+    pub score: f32,
+}
+
+/// Result entry for `handle`.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, utoipa::ToSchema)]
+pub struct HpoSimFamilyGeneResultEntry {
+    /// The gene symbol.
+    pub gene_symbol: String,
+    /// The NCBI gene ID.
+    pub ncbi_gene_id: u32,
+    /// The HGNC gene ID, if known.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub hgnc_id: Option<String>,
+    /// The gene's NCBI gene information, if requested and available.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub gene_info: Option<GeneInfo>,
+    /// The proband score, down-weighted by the best score of an unaffected relative.
+    pub score: f32,
+    /// The Phenomizer score of the proband's terms.
+    pub proband_score: f32,
+    /// The scores of the relatives, in the order of the request.
+    pub relatives: Vec<HpoSimFamilyGeneRelativeScore>,
+}
+
+/// Result container for `handle`.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, utoipa::ToSchema)]
+pub struct HpoSimFamilyGeneResult {
+    /// Version information.
+    pub version: Version,
+    /// The original request.
+    pub query: HpoSimFamilyGeneRequest,
+    /// The scored genes, best first.
+    pub result: Vec<HpoSimFamilyGeneResultEntry>,
+}
+
+/// Down-weight the `proband` score by the best score of an unaffected relative.
+///
+/// The proband score is multiplied by `1 - weight * min(1, unaffected / proband)`, so a
+/// gene that matches an unaffected relative at least as well as the proband loses the
+/// fraction `weight` of its score.
+fn family_score(proband: f32, unaffected: Option<f32>, weight: f32) -> f32 {
+    match unaffected {
+        Some(unaffected) if proband > 0.0 => {
+            proband * (1.0 - weight * (unaffected / proband).min(1.0))
+        }
+        _ => proband,
+    }
+}
+
+/// Translate the terms with modifiers into an `HpoGroup` for scoring and the weights.
+fn parse_phenotype(
+    values: &[String],
+    data: &WebServerData,
+    resolved_from: &ResolvedFrom,
+) -> Result<(HpoGroup, TermWeights), CustomError> {
+    let (term_ids, weights) = super::super::parse_modified_term_ids(values, data, resolved_from)?;
+    Ok((
+        super::super::to_hpo_group(term_ids, data, resolved_from),
+        weights,
+    ))
+}
+
+/// Score genes for a proband given the phenotypes of affected and unaffected relatives.
+///
+/// The genes are scored with the Phenomizer score for the proband's terms and the
+/// score is down-weighted for genes that also match an unaffected relative.  The scores
+/// of all relatives are reported per gene.
+#[allow(clippy::unused_async)]
+#[utoipa::path(
+    post,
+    operation_id = "hpoSimFamilyGene",
+    request_body = HpoSimFamilyGeneRequest,
+    responses(
+        (status = 200, description = "The query was successful.", body = HpoSimFamilyGeneResult),
+        (status = 400, description = "The request is invalid.", body = CustomError),
+        (status = 413, description = "The request body exceeds the size limit.", body = CustomError),
+        (status = 422, description = "The request exceeds a size or complexity limit.", body = CustomError),
+        (status = 500, description = "The server encountered an error.", body = CustomError),
+        (status = 503, description = "The server is low on memory.", body = CustomError)
+    )
+)]
+#[post("/api/v1/hpo/sim/family-gene")]
+async fn handle(
+    data: Data<Arc<WebServerData>>,
+    _path: Path<()>,
+    request: Json<HpoSimFamilyGeneRequest>,
+    resolved_from: ResolvedFrom,
+    _timer: ComputeTimer,
+) -> actix_web::Result<Json<HpoSimFamilyGeneResult>, CustomError> {
+    let hpo = &data.ontology;
+    if !(0.0..=1.0).contains(&request.unaffected_weight) {
+        return Err(CustomError::with_status(
+            StatusCode::BAD_REQUEST,
+            anyhow::anyhow!("unaffected_weight must be between 0 and 1"),
+        ));
+    }
+
+    data.limits.check_batch(request.relatives.len())?;
+    let proband = parse_phenotype(&request.proband, &data, &resolved_from)?;
+    let relatives = request
+        .relatives
+        .iter()
+        .map(|relative| parse_phenotype(&relative.terms, &data, &resolved_from))
+        .collect::<Result<Vec<_>, _>>()?;
+    let genes = super::term_gene::lookup_genes(
+        request.gene_ids.as_deref(),
+        request.gene_symbols.as_deref(),
+        &data,
+    )?;
+    data.limits.check_complexity(
+        proband.0.len()
+            + relatives
+                .iter()
+                .map(|(terms, _)| terms.len())
+                .sum::<usize>(),
+        genes.iter().map(|gene| gene.hpo_terms().len()).sum(),
+    )?;
+
+    let score = |(terms, weights): &(HpoGroup, TermWeights), gene| {
+        GeneScorer {
+            score: phenomizer::score_modified,
+            sex: None,
+            route: None,
+            weights,
+            direct_ic: None,
+        }
+        .gene_score(terms, gene, hpo)
+    };
+    let mut result = genes
+        .into_iter()
+        .map(|gene| {
+            let proband_score = score(&proband, gene);
+            let relatives = request
+                .relatives
+                .iter()
+                .zip(&relatives)
+                .map(|(relative, phenotype)| HpoSimFamilyGeneRelativeScore {
+                    name: relative.name.clone(),
+                    affected: relative.affected,
+                    score: score(phenotype, gene),
+                })
+                .collect::<Vec<_>>();
+            let unaffected = relatives
+                .iter()
+                .filter(|relative| !relative.affected)
+                .map(|relative| relative.score)
+                .max_by(f32::total_cmp);
+            HpoSimFamilyGeneResultEntry {
+                gene_symbol: gene.name().to_string(),
+                ncbi_gene_id: gene.id().as_u32(),
+                hgnc_id: data
+                    .hgnc_xlink
+                    .hgnc_id(gene.id().as_u32())
+                    .map(str::to_string),
+                gene_info: request
+                    .include_gene_info
+                    .then(|| data.gene_info.get(&gene.id().as_u32()).cloned())
+                    .flatten(),
+                score: family_score(proband_score, unaffected, request.unaffected_weight),
+                proband_score,
+                relatives,
+            }
+        })
+        .collect::<Vec<_>>();
+    result.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| a.gene_symbol.cmp(&b.gene_symbol))
+    });
+
+    Ok(Json(HpoSimFamilyGeneResult {
+        version: Version::new(&hpo.hpo_version()),
+        query: request.into_inner(),
+        result,
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use crate::server::run::hpo_genes::test::web_server_data;
+
+    #[rstest::rstest]
+    #[case(2.0, None, 2.0)]
+    #[case(2.0, Some(0.0), 2.0)]
+    #[case(2.0, Some(1.0), 1.5)]
+    #[case(2.0, Some(4.0), 1.0)]
+    #[case(0.0, Some(1.0), 0.0)]
+    fn family_score(#[case] proband: f32, #[case] unaffected: Option<f32>, #[case] expected: f32) {
+        assert!((super::family_score(proband, unaffected, 0.5) - expected).abs() < 1e-6);
+    }
+
+    #[rstest::rstest]
+    #[actix_web::test]
+    async fn hpo_sim_family_gene(
+        web_server_data: &Arc<crate::server::run::WebServerData>,
+    ) -> Result<(), anyhow::Error> {
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(actix_web::web::Data::new(web_server_data.clone()))
+                .service(super::handle),
+        )
+        .await;
+        let req = actix_web::test::TestRequest::post()
+            .uri("/api/v1/hpo/sim/family-gene")
+            .set_json(serde_json::json!({
+                "proband": ["HP:0010442", "HP:0000347"],
+                "relatives": [
+                    {"name": "father", "affected": false, "terms": ["HP:0000347"]},
+                ],
+                "gene_symbols": ["TGDS", "TTN"],
+            }))
+            .to_request();
+        let resp: super::HpoSimFamilyGeneResult =
+            actix_web::test::call_and_read_body_json(&app, req).await;
+
+        assert_eq!(resp.result.len(), 2);
+        for entry in &resp.result {
+            assert_eq!(entry.relatives.len(), 1);
+            assert!(entry.score <= entry.proband_score);
+        }
+        assert!(resp.result[0].score >= resp.result[1].score);
+
+        Ok(())
+    }
+}
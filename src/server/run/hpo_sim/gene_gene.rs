@@ -0,0 +1,373 @@
+//! Entry point `/hpo/sim/gene-gene` that allows the similarity computation between genes
+//! via their phenotype profiles.
+
+use std::sync::Arc;
+
+use actix_web::{
+    get,
+    http::StatusCode,
+    web::{Data, Json, Path},
+};
+use hpo::{
+    annotations::{AnnotationId as _, Gene, GeneId},
+    similarity::{GroupSimilarity, StandardCombiner},
+    HpoSet, Ontology,
+};
+use itertools::Itertools;
+
+use crate::common::{
+    to_pairwise_sim, AnnotationPropagation, IcBasedOn, NormalizationConstants, ScoreCombiner,
+    ScoreNormalization, SimilarityMethod, Version,
+};
+use crate::server::run::strict_query::StrictQuery;
+use crate::server::run::tenants::TenantScope;
+use crate::server::{run::middleware::ComputeTimer, run::CustomError, run::WebServerData};
+
+/// Parameters for `handle`.
+///
+/// This allows to compute similarities between
+///
+/// - `lhs` -- first set of genes, as NCBI gene ID, HGNC gene ID, or symbol
+/// - `rhs` -- second set of genes, all other annotated genes if missing
+/// - `max_results` -- maximal number of gene pairs to return
+#[derive(Default, Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::IntoParams)]
+#[serde(deny_unknown_fields)]
+#[into_params(parameter_in = Query)]
+pub struct RequestQuery {
+    /// The one set of genes to compute similarity for.
+    #[serde(deserialize_with = "super::super::vec_str_deserialize")]
+    pub lhs: Vec<String>,
+    /// The second set of genes to compute similarity for, all other annotated genes if
+    /// missing.
+    #[serde(
+        default = "Option::default",
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "super::super::option_vec_str_deserialize"
+    )]
+    pub rhs: Option<Vec<String>>,
+    /// Maximal number of gene pairs to return.
+    #[serde(default = "_default_max_results")]
+    pub max_results: usize,
+    /// What should information content be based on.
+    #[serde(default = "IcBasedOn::default")]
+    pub ic_base: IcBasedOn,
+    /// The similarity method to use.
+    #[serde(default = "SimilarityMethod::default")]
+    pub similarity: SimilarityMethod,
+    /// The score combiner.
+    #[serde(default = "ScoreCombiner::default")]
+    pub combiner: ScoreCombiner,
+    /// Whether annotations propagate to ancestors, server default if missing.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub annotation_propagation: Option<AnnotationPropagation>,
+    /// Calibration of the scores into the range from 0 to 1, raw scores if missing.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub normalize: Option<ScoreNormalization>,
+}
+
+/// Return default of `RequestQuery::max_results`.
+fn _default_max_results() -> usize {
+    100
+}
+
+/// Request as sent together with the response.
+///
+/// The difference is that the `lhs` and `rhs` fields are replaced by vecs of symbols.
+#[derive(Default, Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct HpoSimGeneGeneQuery {
+    /// The one set of genes to compute similarity for.
+    pub lhs: Vec<String>,
+    /// The second set of genes to compute similarity for, all other annotated genes if
+    /// missing.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub rhs: Option<Vec<String>>,
+    /// Maximal number of gene pairs to return.
+    #[serde(default = "_default_max_results")]
+    pub max_results: usize,
+    /// What should information content be based on.
+    #[serde(default = "IcBasedOn::default")]
+    pub ic_base: IcBasedOn,
+    /// The similarity method to use.
+    #[serde(default = "SimilarityMethod::default")]
+    pub similarity: SimilarityMethod,
+    /// The score combiner.
+    #[serde(default = "ScoreCombiner::default")]
+    pub combiner: ScoreCombiner,
+    /// Whether annotations propagate to ancestors, server default if missing.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub annotation_propagation: Option<AnnotationPropagation>,
+    /// Calibration of the scores into the range from 0 to 1, raw scores if missing.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub normalize: Option<ScoreNormalization>,
+}
+
+/// Result container.
+#[derive(Default, Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct HpoSimGeneGeneResult {
+    /// Version information.
+    pub version: Version,
+    /// The original query records.
+    pub query: HpoSimGeneGeneQuery,
+    /// The resulting records for the scored gene pairs.
+    pub result: Vec<HpoSimGeneGeneResultEntry>,
+    /// The constants of the score normalization, if any.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub normalization: Option<NormalizationConstants>,
+}
+
+/// Result entry for `handle`.
+#[derive(
+    Default,
+    Debug,
+    Clone,
+    PartialEq,
+    PartialOrd,
+    serde::Serialize,
+    serde::Deserialize,
+    utoipa::ToSchema,
+)]
+pub struct HpoSimGeneGeneResultEntry {
+    /// The lhs gene symbol.
+    pub lhs: String,
+    /// The NCBI gene ID of the lhs gene.
+    pub lhs_ncbi_gene_id: u32,
+    /// The rhs gene symbol.
+    pub rhs: String,
+    /// The NCBI gene ID of the rhs gene.
+    pub rhs_ncbi_gene_id: u32,
+    /// The similarity score.
+    pub score: f32,
+}
+
+/// Translate the NCBI gene IDs, HGNC gene IDs, or symbols from the query into genes.
+///
+/// Genes that are not annotated in the ontology are skipped.
+fn lookup_genes<'a>(
+    values: &[String],
+    data: &'a WebServerData,
+) -> Result<Vec<&'a Gene>, CustomError> {
+    data.limits.check_batch(values.len())?;
+    let hpo = &data.ontology;
+    Ok(values
+        .iter()
+        .filter_map(|value| {
+            if let Ok(gene_id) = value.parse::<u32>() {
+                hpo.gene(&GeneId::from(gene_id))
+            } else if let Some(gene_id) = data.hgnc_xlink.ncbi_gene_id(value) {
+                hpo.gene(&GeneId::from(gene_id))
+            } else {
+                hpo.gene_by_name(value)
+            }
+        })
+        .unique_by(|gene| gene.id())
+        .collect())
+}
+
+/// Score each gene of `lhs` against each gene of `rhs` and return the `max_results` best
+/// pairs first.
+///
+/// The term sets are built once per gene and result entries only for the returned pairs.
+fn score_pairs<S: hpo::similarity::Similarity>(
+    lhs: &[&Gene],
+    rhs: &[&Gene],
+    ontology: &Ontology,
+    group_sim: &GroupSimilarity<S, StandardCombiner>,
+    normalization: Option<&NormalizationConstants>,
+    max_results: usize,
+) -> Vec<HpoSimGeneGeneResultEntry> {
+    let to_sets = |genes: &[&Gene]| {
+        genes
+            .iter()
+            .map(|gene| HpoSet::new(ontology, gene.hpo_terms().clone()))
+            .collect::<Vec<_>>()
+    };
+    let (lhs_sets, rhs_sets) = (to_sets(lhs), to_sets(rhs));
+    let mut scores = (0..lhs.len())
+        .cartesian_product(0..rhs.len())
+        .map(|(i, j)| {
+            let mut score = group_sim.calculate(&lhs_sets[i], &rhs_sets[j]);
+            if let Some(normalization) = normalization {
+                score = normalization.apply(score);
+            }
+            (i, j, score)
+        })
+        .collect::<Vec<_>>();
+    scores.sort_by(|(_, a_j, a_score), (_, b_j, b_score)| {
+        b_score
+            .total_cmp(a_score)
+            .then_with(|| rhs[*a_j].name().cmp(rhs[*b_j].name()))
+    });
+    scores
+        .into_iter()
+        .take(max_results)
+        .map(|(i, j, score)| HpoSimGeneGeneResultEntry {
+            lhs: lhs[i].name().to_string(),
+            lhs_ncbi_gene_id: lhs[i].id().as_u32(),
+            rhs: rhs[j].name().to_string(),
+            rhs_ncbi_gene_id: rhs[j].id().as_u32(),
+            score,
+        })
+        .collect()
+}
+
+/// Query for the similarity of genes by their phenotype profiles.
+///
+/// Each gene of `lhs` is compared to each gene of `rhs` by combining the pairwise
+/// similarities of their terms with the score combiner.  Without `rhs`, the genes are
+/// compared to all other annotated genes, e.g., to expand a candidate list from a seed
+/// gene.
+///
+/// # Errors
+///
+/// In the case that there is an error running the server.
+#[utoipa::path(
+    get,
+    operation_id = "hpoSimGeneGene",
+    params(RequestQuery),
+    responses(
+        (status = 200, description = "The query was successful.", body = HpoSimGeneGeneResult),
+        (status = 400, description = "The requested information content is not available.", body = CustomError),
+        (status = 422, description = "The request exceeds a size or complexity limit.", body = CustomError),
+        (status = 500, description = "The server encountered an error.", body = CustomError),
+        (status = 503, description = "The server is low on memory.", body = CustomError)
+    )
+)]
+#[get("/api/v1/hpo/sim/gene-gene")]
+async fn handle(
+    data: Data<Arc<WebServerData>>,
+    tenant: TenantScope,
+    _path: Path<()>,
+    query: StrictQuery<RequestQuery>,
+    _timer: ComputeTimer,
+) -> actix_web::Result<Json<HpoSimGeneGeneResult>, CustomError> {
+    let ic_tables = tenant.ic_tables(&data);
+    let ontology: &Ontology = &data.ontology;
+
+    ic_tables
+        .ensure_available(query.ic_base)
+        .map_err(|e| CustomError::with_status(StatusCode::BAD_REQUEST, e))?;
+    let propagation = query
+        .annotation_propagation
+        .unwrap_or(data.annotation_propagation);
+    let group_sim = GroupSimilarity::new(
+        StandardCombiner::from(query.combiner),
+        to_pairwise_sim(query.similarity, query.ic_base, propagation, ic_tables),
+    );
+    let normalization = NormalizationConstants::new(
+        query.normalize.unwrap_or_default(),
+        query.similarity,
+        ic_tables.range(query.ic_base, propagation),
+    );
+
+    // Translate strings from the query into genes, all other genes if `rhs` is missing.
+    let lhs = lookup_genes(&query.lhs, &data)?;
+    let rhs = match &query.rhs {
+        Some(rhs) => lookup_genes(rhs, &data)?,
+        None => ontology
+            .genes()
+            .filter(|gene| lhs.iter().all(|lhs| lhs.id() != gene.id()))
+            .collect(),
+    };
+    let n_terms = |genes: &[&Gene]| genes.iter().map(|gene| gene.hpo_terms().len()).sum();
+    data.limits.check_complexity(n_terms(&lhs), n_terms(&rhs))?;
+
+    let result = score_pairs(
+        &lhs,
+        &rhs,
+        ontology,
+        &group_sim,
+        normalization.as_ref(),
+        query.max_results,
+    );
+
+    // The genes are echoed as symbols.
+    let RequestQuery {
+        rhs: query_rhs,
+        max_results,
+        ic_base,
+        similarity,
+        combiner,
+        annotation_propagation,
+        normalize,
+        ..
+    } = query.into_inner();
+
+    Ok(Json(HpoSimGeneGeneResult {
+        version: Version::new(&ontology.hpo_version()),
+        query: HpoSimGeneGeneQuery {
+            lhs: lhs.iter().map(|gene| gene.name().to_string()).collect(),
+            rhs: query_rhs.map(|_| rhs.iter().map(|gene| gene.name().to_string()).collect()),
+            max_results,
+            ic_base,
+            similarity,
+            combiner,
+            annotation_propagation,
+            normalize,
+        },
+        result,
+        normalization,
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use crate::server::run::hpo_genes::test::web_server_data;
+
+    /// Helper function for running a query.
+    pub async fn run_query(
+        web_server_data: Arc<crate::server::run::WebServerData>,
+        uri: &str,
+    ) -> Result<super::HpoSimGeneGeneResult, anyhow::Error> {
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(actix_web::web::Data::new(web_server_data))
+                .service(super::handle),
+        )
+        .await;
+        let req = actix_web::test::TestRequest::get().uri(uri).to_request();
+        let resp: super::HpoSimGeneGeneResult =
+            actix_web::test::call_and_read_body_json(&app, req).await;
+
+        Ok(resp)
+    }
+
+    #[rstest::rstest]
+    #[actix_web::test]
+    async fn hpo_sim_gene_gene_pairs(
+        web_server_data: &Arc<crate::server::run::WebServerData>,
+    ) -> Result<(), anyhow::Error> {
+        let result = run_query(
+            web_server_data.clone(),
+            "/api/v1/hpo/sim/gene-gene?lhs=TGDS&rhs=23483,HGNC:12403",
+        )
+        .await?;
+
+        assert_eq!(result.query.lhs, vec!["TGDS"]);
+        assert_eq!(result.result.len(), 2);
+        // A gene is at least as similar to itself as to any other gene.
+        assert_eq!(result.result[0].rhs, "TGDS");
+        assert!(result.result[0].score >= result.result[1].score);
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[actix_web::test]
+    async fn hpo_sim_gene_gene_seed(
+        web_server_data: &Arc<crate::server::run::WebServerData>,
+    ) -> Result<(), anyhow::Error> {
+        let result = run_query(
+            web_server_data.clone(),
+            "/api/v1/hpo/sim/gene-gene?lhs=TGDS&max_results=5",
+        )
+        .await?;
+
+        assert!(result.query.rhs.is_none());
+        assert_eq!(result.result.len(), 5);
+        assert!(result.result.iter().all(|entry| entry.rhs != "TGDS"));
+
+        Ok(())
+    }
+}
@@ -1,4 +1,8 @@
-//! Implementation of `/hpo/sim/{term-term,term-gene}` endpoints.
+//! Implementation of `/hpo/sim/{term-term,term-gene,term-gene/report,family-gene,omim-omim,gene-gene}` endpoints.
 
+pub mod family_gene;
+pub mod gene_gene;
+pub mod omim_omim;
 pub mod term_gene;
+pub mod term_gene_report;
 pub mod term_term;
@@ -0,0 +1,329 @@
+//! Entry point `/hpo/sim/omim-omim` that allows the similarity computation between
+//! diseases via their phenotype profiles.
+
+use std::sync::Arc;
+
+use actix_web::{
+    get,
+    http::StatusCode,
+    web::{Data, Json, Path},
+};
+use hpo::{
+    annotations::{Disease as _, OmimDisease, OmimDiseaseId},
+    similarity::{GroupSimilarity, StandardCombiner},
+    HpoSet, Ontology,
+};
+use itertools::Itertools;
+
+use crate::common::{
+    to_pairwise_sim, AnnotationPropagation, IcBasedOn, NormalizationConstants, ScoreCombiner,
+    ScoreNormalization, SimilarityMethod, Version,
+};
+use crate::server::run::hpo_omims::omim_name;
+use crate::server::run::strict_query::StrictQuery;
+use crate::server::run::tenants::TenantScope;
+use crate::server::{run::middleware::ComputeTimer, run::CustomError, run::WebServerData};
+
+/// Parameters for `handle`.
+///
+/// This allows to compute similarities between
+///
+/// - `lhs` -- first set of OMIM disease IDs, e.g., `OMIM:616145`
+/// - `rhs` -- second set of OMIM disease IDs
+/// - `lang` -- language of the disease names, falls back to English
+#[derive(Default, Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::IntoParams)]
+#[serde(deny_unknown_fields)]
+#[into_params(parameter_in = Query)]
+pub struct RequestQuery {
+    /// The one set of OMIM disease IDs to compute similarity for.
+    #[serde(deserialize_with = "super::super::vec_str_deserialize")]
+    pub lhs: Vec<String>,
+    /// The second set of OMIM disease IDs to compute similarity for.
+    #[serde(deserialize_with = "super::super::vec_str_deserialize")]
+    pub rhs: Vec<String>,
+    /// What should information content be based on.
+    #[serde(default = "IcBasedOn::default")]
+    pub ic_base: IcBasedOn,
+    /// The similarity method to use.
+    #[serde(default = "SimilarityMethod::default")]
+    pub similarity: SimilarityMethod,
+    /// The score combiner.
+    #[serde(default = "ScoreCombiner::default")]
+    pub combiner: ScoreCombiner,
+    /// Whether annotations propagate to ancestors, server default if missing.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub annotation_propagation: Option<AnnotationPropagation>,
+    /// Calibration of the scores into the range from 0 to 1, raw scores if missing.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub normalize: Option<ScoreNormalization>,
+    /// Language of the disease names, e.g., `de`; untranslated names are in English.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub lang: Option<String>,
+}
+
+/// Request as sent together with the response.
+///
+/// The difference is that the `lhs` and `rhs` fields are replaced by vecs.
+#[derive(Default, Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct HpoSimOmimOmimQuery {
+    /// The one set of OMIM disease IDs to compute similarity for.
+    pub lhs: Vec<String>,
+    /// The second set of OMIM disease IDs to compute similarity for.
+    pub rhs: Vec<String>,
+    /// What should information content be based on.
+    #[serde(default = "IcBasedOn::default")]
+    pub ic_base: IcBasedOn,
+    /// The similarity method to use.
+    #[serde(default = "SimilarityMethod::default")]
+    pub similarity: SimilarityMethod,
+    /// The score combiner.
+    #[serde(default = "ScoreCombiner::default")]
+    pub combiner: ScoreCombiner,
+    /// Whether annotations propagate to ancestors, server default if missing.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub annotation_propagation: Option<AnnotationPropagation>,
+    /// Calibration of the scores into the range from 0 to 1, raw scores if missing.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub normalize: Option<ScoreNormalization>,
+    /// Language of the disease names, e.g., `de`; untranslated names are in English.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub lang: Option<String>,
+}
+
+/// Result container.
+#[derive(Default, Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct HpoSimOmimOmimResult {
+    /// Version information.
+    pub version: Version,
+    /// The original query records.
+    pub query: HpoSimOmimOmimQuery,
+    /// The resulting records for the scored disease pairs.
+    pub result: Vec<HpoSimOmimOmimResultEntry>,
+    /// The constants of the score normalization, if any.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub normalization: Option<NormalizationConstants>,
+}
+
+/// Result entry for `handle`.
+#[derive(
+    Default,
+    Debug,
+    Clone,
+    PartialEq,
+    PartialOrd,
+    serde::Serialize,
+    serde::Deserialize,
+    utoipa::ToSchema,
+)]
+pub struct HpoSimOmimOmimResultEntry {
+    /// The lhs OMIM disease ID.
+    pub lhs: String,
+    /// The name of the lhs disease.
+    pub lhs_name: String,
+    /// The rhs OMIM disease ID.
+    pub rhs: String,
+    /// The name of the rhs disease.
+    pub rhs_name: String,
+    /// The similarity score.
+    pub score: f32,
+}
+
+/// Translate the OMIM disease IDs from the query into diseases.
+///
+/// Diseases that are not annotated in the ontology are skipped.
+fn lookup_omims<'a>(
+    omim_ids: &[String],
+    data: &'a WebServerData,
+) -> Result<Vec<&'a OmimDisease>, CustomError> {
+    data.limits.check_batch(omim_ids.len())?;
+    let ontology: &Ontology = &data.ontology;
+    let mut result = Vec::new();
+    for omim_id in omim_ids {
+        let id = OmimDiseaseId::try_from(super::super::hpo_omims::strip_omim_prefix(omim_id))
+            .map_err(|e| {
+                CustomError::with_status(
+                    StatusCode::BAD_REQUEST,
+                    anyhow::anyhow!("invalid OMIM disease ID {}: {}", omim_id, e),
+                )
+            })?;
+        if let Some(omim_disease) = ontology.omim_disease(&id) {
+            result.push(omim_disease);
+        }
+    }
+    Ok(result)
+}
+
+/// Query for the similarity of diseases by their phenotype profiles.
+///
+/// Each disease of `lhs` is compared to each disease of `rhs` by combining the pairwise
+/// similarities of their terms with the score combiner.
+///
+/// # Errors
+///
+/// In the case that there is an error running the server.
+#[utoipa::path(
+    get,
+    operation_id = "hpoSimOmimOmim",
+    params(RequestQuery),
+    responses(
+        (status = 200, description = "The query was successful.", body = HpoSimOmimOmimResult),
+        (status = 400, description = "A disease ID is malformed or the requested information content is not available.", body = CustomError),
+        (status = 422, description = "The request exceeds a size or complexity limit.", body = CustomError),
+        (status = 500, description = "The server encountered an error.", body = CustomError),
+        (status = 503, description = "The server is low on memory.", body = CustomError)
+    )
+)]
+#[get("/api/v1/hpo/sim/omim-omim")]
+async fn handle(
+    data: Data<Arc<WebServerData>>,
+    tenant: TenantScope,
+    _path: Path<()>,
+    query: StrictQuery<RequestQuery>,
+    _timer: ComputeTimer,
+) -> actix_web::Result<Json<HpoSimOmimOmimResult>, CustomError> {
+    let ic_tables = tenant.ic_tables(&data);
+    let ontology: &Ontology = &data.ontology;
+
+    ic_tables
+        .ensure_available(query.ic_base)
+        .map_err(|e| CustomError::with_status(StatusCode::BAD_REQUEST, e))?;
+    let propagation = query
+        .annotation_propagation
+        .unwrap_or(data.annotation_propagation);
+    let group_sim = GroupSimilarity::new(
+        StandardCombiner::from(query.combiner),
+        to_pairwise_sim(query.similarity, query.ic_base, propagation, ic_tables),
+    );
+    let normalization = NormalizationConstants::new(
+        query.normalize.unwrap_or_default(),
+        query.similarity,
+        ic_tables.range(query.ic_base, propagation),
+    );
+
+    // Translate strings from the query into diseases.
+    let lhs = lookup_omims(&query.lhs, &data)?;
+    let rhs = lookup_omims(&query.rhs, &data)?;
+    let n_terms = |omims: &[&OmimDisease]| omims.iter().map(|omim| omim.hpo_terms().len()).sum();
+    data.limits.check_complexity(n_terms(&lhs), n_terms(&rhs))?;
+
+    // Compute the similarity for each pair, building the term sets once per disease.
+    let to_sets = |omims: &[&OmimDisease]| {
+        omims
+            .iter()
+            .map(|omim| HpoSet::new(ontology, omim.hpo_terms().clone()))
+            .collect::<Vec<_>>()
+    };
+    let (lhs_sets, rhs_sets) = (to_sets(&lhs), to_sets(&rhs));
+    let mut result = lhs
+        .iter()
+        .zip(&lhs_sets)
+        .cartesian_product(rhs.iter().zip(&rhs_sets))
+        .map(|((lhs, lhs_set), (rhs, rhs_set))| {
+            let mut score = group_sim.calculate(lhs_set, rhs_set);
+            if let Some(normalization) = &normalization {
+                score = normalization.apply(score);
+            }
+            HpoSimOmimOmimResultEntry {
+                lhs: lhs.id().to_string(),
+                lhs_name: omim_name(lhs, &data, query.lang.as_deref()),
+                rhs: rhs.id().to_string(),
+                rhs_name: omim_name(rhs, &data, query.lang.as_deref()),
+                score,
+            }
+        })
+        .collect::<Vec<_>>();
+    result.sort_by(|lhs, rhs| rhs.score.total_cmp(&lhs.score));
+
+    // The disease IDs are echoed in canonical form.
+    let RequestQuery {
+        ic_base,
+        similarity,
+        combiner,
+        annotation_propagation,
+        normalize,
+        lang,
+        ..
+    } = query.into_inner();
+
+    Ok(Json(HpoSimOmimOmimResult {
+        version: Version::new(&ontology.hpo_version()),
+        query: HpoSimOmimOmimQuery {
+            lhs: lhs.iter().map(|omim| omim.id().to_string()).collect(),
+            rhs: rhs.iter().map(|omim| omim.id().to_string()).collect(),
+            ic_base,
+            similarity,
+            combiner,
+            annotation_propagation,
+            normalize,
+            lang,
+        },
+        result,
+        normalization,
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use crate::server::run::hpo_genes::test::web_server_data;
+
+    /// Helper function for running a query.
+    pub async fn run_query(
+        web_server_data: Arc<crate::server::run::WebServerData>,
+        uri: &str,
+    ) -> Result<super::HpoSimOmimOmimResult, anyhow::Error> {
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(actix_web::web::Data::new(web_server_data))
+                .service(super::handle),
+        )
+        .await;
+        let req = actix_web::test::TestRequest::get().uri(uri).to_request();
+        let resp: super::HpoSimOmimOmimResult =
+            actix_web::test::call_and_read_body_json(&app, req).await;
+
+        Ok(resp)
+    }
+
+    #[rstest::rstest]
+    #[actix_web::test]
+    async fn hpo_sim_omim_omim_self(
+        web_server_data: &Arc<crate::server::run::WebServerData>,
+    ) -> Result<(), anyhow::Error> {
+        let result = run_query(
+            web_server_data.clone(),
+            "/api/v1/hpo/sim/omim-omim?lhs=OMIM:616145&rhs=616145,MIM:154700&normalize=max_ic",
+        )
+        .await?;
+
+        assert_eq!(result.query.lhs, vec!["OMIM:616145"]);
+        assert_eq!(result.result.len(), 2);
+        // A disease is at least as similar to itself as to any other disease.
+        assert_eq!(result.result[0].rhs, "OMIM:616145");
+        assert!(result.result[0].score >= result.result[1].score);
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[actix_web::test]
+    async fn hpo_sim_omim_omim_malformed_id(
+        web_server_data: &Arc<crate::server::run::WebServerData>,
+    ) -> Result<(), anyhow::Error> {
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(actix_web::web::Data::new(web_server_data.clone()))
+                .service(super::handle),
+        )
+        .await;
+        let req = actix_web::test::TestRequest::get()
+            .uri("/api/v1/hpo/sim/omim-omim?lhs=OMIM:616145&rhs=ORPHA:123")
+            .to_request();
+        let resp = actix_web::test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+
+        Ok(())
+    }
+}
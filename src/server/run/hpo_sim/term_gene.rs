@@ -5,93 +5,314 @@ use std::sync::Arc;
 
 use actix_web::{
     get,
-    web::{self, Data, Json, Path},
+    http::StatusCode,
+    web::{self, Data, Path},
+    HttpResponse,
 };
 
-use hpo::{annotations::GeneId, term::HpoGroup, HpoTermId, Ontology};
+use hpo::{
+    annotations::{AnnotationId as _, Gene, GeneId},
+    Ontology,
+};
+use serde_with::{DisplayFromStr, PickFirst};
 
-use super::super::CustomError;
+use super::super::{
+    middleware::{CacheHit, ComputeTimer, RandomSeed, ResolvedFrom},
+    CustomError,
+};
+use crate::server::run::strict_query::StrictQuery;
+use crate::server::run::CommaSeparated;
 use crate::{
+    algos::{
+        ic::IcTable,
+        onset::{self, OnsetRange},
+        phenomizer,
+        routes::{AnnotationRoute, RouteFilter},
+        sex::{Sex, SexFilter},
+    },
+    common::{
+        parse_term_id, AnnotationPropagation, ExperimentalFeature, IcBasedOn,
+        NormalizationConstants, ScoreNormalization, SimilarityMethod, TermGeneMethod,
+    },
+    network::rwr::RwrParams,
     query::{self, query_result::HpoSimTermGeneResult},
     server::run::WebServerData,
+    sources::{
+        associations::AssociationScores,
+        clinvar::ClinvarCounts,
+        constraint::ConstraintTable,
+        expression::{ExpressionMatrix, ExpressionMode},
+        panels::GenePanels,
+    },
 };
 
 /// Parameters for `handle`.
 ///
-/// This allows to compute differences between
+/// The parameters are split into two structs as the derive of `utoipa::IntoParams` builds
+/// all parameters of a struct in an array on the stack.  The values of flattened structs
+/// are strings in the query, so their numbers and flags are also parsed from strings.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct HpoSimTermGeneQuery {
+    /// The query terms and genes, the scoring method, and the output.
+    #[serde(flatten)]
+    pub params: TermGeneParams,
+    /// The adjustments of the scores by network, constraint, `ClinVar`, and expression.
+    #[serde(flatten)]
+    pub adjustments: TermGeneAdjustments,
+}
+
+impl utoipa::IntoParams for HpoSimTermGeneQuery {
+    fn into_params(
+        parameter_in_provider: impl Fn() -> Option<utoipa::openapi::path::ParameterIn>,
+    ) -> Vec<utoipa::openapi::path::Parameter> {
+        let mut params = TermGeneParams::into_params(&parameter_in_provider);
+        params.extend(TermGeneAdjustments::into_params(&parameter_in_provider));
+        params
+    }
+}
+
+/// Parameters of `HpoSimTermGeneQuery` for the query terms and genes, the scoring method,
+/// and the output.
 ///
-/// - `terms` -- set of terms to use as query
+/// - `terms` -- set of terms to use as query, optionally with clinical modifiers, e.g.,
+///   `HP:0001249[HP:0012828]`
 /// - `gene_ids` -- set of ids for genes to use as "database", can be NCBI\
-///                 gene ID or HGNC gene ID.
+///   gene ID or HGNC gene ID.
 /// - `gene_symbols` -- set of symbols for genes to use as
 ///   "database"
-#[derive(serde::Deserialize, Debug, Clone, utoipa::ToSchema, utoipa::IntoParams)]
-pub struct HpoSimTermGeneQuery {
-    /// Set of terms to use as query.
-    #[serde(deserialize_with = "super::super::vec_str_deserialize")]
-    pub terms: Vec<String>,
+/// - `categories` -- whether to report partial scores by top-level category
+/// - `region` -- restrict to genes overlapping the region, e.g., `chr7:100000-5000000`
+/// - `experimental` -- experimental features to enable, must be allowed by the server
+/// - `normalize` -- calibration of the scores into the range from 0 to 1
+/// - `patient_age_onset` -- onset term of the patient, e.g., `HP:0003577`, genes with
+///   incompatible onset are penalized
+/// - `sex` -- sex of the patient, gene annotations restricted to the other sex are
+///   excluded
+/// - `associations` -- names of supplementary association scores to report
+/// - `method` -- `phenomizer` or `ensemble` for the reciprocal rank fusion of the
+///   configured methods and the requested association scores
+/// - `p_values` -- whether to report the p-values of the raw scores
+/// - `seed` -- seed of the random queries for the p-values, the default or one allowed by
+///   the server, reported in the metadata
+/// - `group_by` -- `panel` to also report the genes grouped by the loaded gene panels
+/// - `annotation_route` -- whether genes are scored with their `direct` annotations, those
+///   `via_disease`, or `both`, reporting the route of each matched gene term
+/// - `include_gene_info` -- whether to include `"gene_info"` in the genes of the query
+/// - `annotation_propagation` -- whether gene annotations propagate to ancestors, scoring
+///   with the information content of the direct annotations for `none`
+#[serde_with::serde_as]
+#[derive(
+    serde::Serialize, serde::Deserialize, Debug, Clone, utoipa::ToSchema, utoipa::IntoParams,
+)]
+#[into_params(parameter_in = Query)]
+pub struct TermGeneParams {
+    /// Set of terms to use as query, optionally with clinical modifiers in brackets
+    /// separated by `;`, e.g., `HP:0001249[HP:0012828]`.
+    #[schema(value_type = Vec<String>)]
+    #[param(value_type = Vec<String>)]
+    pub terms: CommaSeparated,
     /// The set of ids for genes to use as "database".
     #[serde(
         default = "Option::default",
         skip_serializing_if = "Option::is_none",
-        deserialize_with = "super::super::option_vec_str_deserialize"
+        deserialize_with = "crate::server::run::option_vec_str_deserialize"
     )]
     pub gene_ids: Option<Vec<String>>,
     /// The set of symbols for genes to use as "database".
     #[serde(
         default = "Option::default",
         skip_serializing_if = "Option::is_none",
-        deserialize_with = "super::super::option_vec_str_deserialize"
+        deserialize_with = "crate::server::run::option_vec_str_deserialize"
     )]
     pub gene_symbols: Option<Vec<String>>,
+    /// Whether to report partial scores by top-level category.
+    #[serde(default = "_default_categories")]
+    #[serde_as(as = "PickFirst<(_, DisplayFromStr)>")]
+    pub categories: bool,
+    /// Restrict to genes overlapping the region, e.g., `chr7:100000-5000000`.
+    pub region: Option<String>,
+    /// Experimental features to enable, e.g., `rwr-network,weighted-combiner`.
+    #[serde(
+        default = "Option::default",
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "crate::server::run::option_vec_str_deserialize"
+    )]
+    pub experimental: Option<Vec<String>>,
+    /// Calibration of the scores into the range from 0 to 1, raw scores if missing.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub normalize: Option<ScoreNormalization>,
+    /// Onset term of the patient, e.g., `HP:0003577`, genes with incompatible onset are
+    /// penalized.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub patient_age_onset: Option<String>,
+    /// Sex of the patient, gene annotations restricted to the other sex are excluded.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub sex: Option<Sex>,
+    /// Names of supplementary association scores loaded by the server to report, e.g.,
+    /// `cada`.
+    #[serde(
+        default = "Option::default",
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "crate::server::run::option_vec_str_deserialize"
+    )]
+    pub associations: Option<Vec<String>>,
+    /// Method for scoring the genes, `ensemble` fuses the ranks of the methods configured
+    /// by the server and of the requested association scores.
+    #[serde(default)]
+    pub method: TermGeneMethod,
+    /// Whether to report the p-values of the raw scores against simulated random queries
+    /// with the same number of terms.
+    #[serde(default = "_default_p_values")]
+    #[serde_as(as = "PickFirst<(_, DisplayFromStr)>")]
+    pub p_values: bool,
+    /// Seed of the random queries that the p-values are computed against, the seed of the
+    /// precomputed distributions if missing.  Other seeds must be allowed by the server.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    #[serde_as(as = "Option<PickFirst<(_, DisplayFromStr)>>")]
+    pub seed: Option<u64>,
+    /// How to group the genes in addition to the flat result, e.g., `panel`.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub group_by: Option<TermGeneGroupBy>,
+    /// Route of the gene annotations to score with, e.g., `via_disease` for the annotations
+    /// of the OMIM diseases linked to the genes, the direct annotations if missing.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub annotation_route: Option<AnnotationRoute>,
+    /// Whether to include the NCBI gene information in the genes of the query.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    #[serde_as(as = "PickFirst<(_, DisplayFromStr)>")]
+    pub include_gene_info: bool,
+    /// Whether annotations propagate to ancestors, server default if missing.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub annotation_propagation: Option<AnnotationPropagation>,
 }
 
-/// Query for similarity between a set of terms to each entry in a
-/// list of genes.
-#[allow(clippy::unused_async)]
-#[utoipa::path(
-    get,
-    operation_id = "hpoSimTermGene",
-    params(HpoSimTermGeneQuery),
-    responses(
-        (status = 200, description = "The query was successful.", body = HpoSimTermGeneResult),
-        (status = 500, description = "The server encountered an error.", body = CustomError)
-    )
+/// Parameters of `HpoSimTermGeneQuery` for adjusting the scores.
+///
+/// - `network` -- whether to boost scores by the scores of network neighbors
+/// - `network_weight` -- weight of the network score in the combined score
+/// - `rerank_by_constraint` -- whether to blend the scores with the genes' constraint
+/// - `constraint_weight` -- weight of the constraint score in the blended score
+/// - `clinvar_prior` -- whether to fuse the genes' `ClinVar` counts into the ensemble
+/// - `expressed_in` -- tissue the phenotype is localized to, e.g., `brain`
+/// - `min_tpm` -- median TPM from which a gene counts as expressed in the tissue
+/// - `expression_mode` -- whether genes not expressed in the tissue are excluded or
+///   penalized
+#[serde_with::serde_as]
+#[derive(
+    serde::Serialize, serde::Deserialize, Debug, Clone, utoipa::ToSchema, utoipa::IntoParams,
 )]
-#[get("/api/v1/hpo/sim/term-gene")]
-async fn handle(
-    data: Data<Arc<WebServerData>>,
-    _path: Path<()>,
-    query: web::Query<HpoSimTermGeneQuery>,
-) -> actix_web::Result<Json<HpoSimTermGeneResult>, CustomError> {
-    let hpo: &Ontology = &data.ontology;
+#[into_params(parameter_in = Query)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct TermGeneAdjustments {
+    /// Whether to boost scores by the scores of network neighbors.
+    #[serde(default = "_default_network")]
+    #[serde_as(as = "PickFirst<(_, DisplayFromStr)>")]
+    pub network: bool,
+    /// Weight of the network score in the combined score, between 0 and 1.
+    #[serde(default = "_default_network_weight")]
+    #[serde_as(as = "PickFirst<(_, DisplayFromStr)>")]
+    pub network_weight: f32,
+    /// Whether to re-rank the genes by blending their scores with their constraint, e.g.,
+    /// gnomAD pLI and LOEUF, for de novo scenarios, not with `method=ensemble`.
+    #[serde(default = "_default_rerank_by_constraint")]
+    #[serde_as(as = "PickFirst<(_, DisplayFromStr)>")]
+    pub rerank_by_constraint: bool,
+    /// Weight of the constraint score in the blended score, between 0 and 1.
+    #[serde(default = "_default_constraint_weight")]
+    #[serde_as(as = "PickFirst<(_, DisplayFromStr)>")]
+    pub constraint_weight: f32,
+    /// Whether to fuse the numbers of pathogenic or likely pathogenic `ClinVar` variants of
+    /// the genes into the ensemble as a prior, requires `method=ensemble`.
+    #[serde(default = "_default_clinvar_prior")]
+    #[serde_as(as = "PickFirst<(_, DisplayFromStr)>")]
+    pub clinvar_prior: bool,
+    /// Tissue the phenotype is localized to, e.g., `brain` or `Brain - Cortex`, genes not
+    /// expressed there are excluded or penalized.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub expressed_in: Option<String>,
+    /// Median TPM in the tissue from which a gene counts as expressed.
+    #[serde(default = "_default_min_tpm")]
+    #[serde_as(as = "PickFirst<(_, DisplayFromStr)>")]
+    pub min_tpm: f32,
+    /// Whether genes not expressed in the tissue are excluded or penalized.
+    #[serde(default)]
+    pub expression_mode: ExpressionMode,
+}
 
-    // Translate strings from the query into an `HpoGroup`.
-    let query_terms = {
-        let mut query_terms = HpoGroup::new();
-        for term in &query.terms {
-            if let Some(term) = hpo.hpo(HpoTermId::from(term.clone())) {
-                query_terms.insert(term.id());
-            }
-        }
-        query_terms
-    };
+/// Return default of `TermGeneParams::categories`.
+fn _default_categories() -> bool {
+    false
+}
 
-    // Translate strings from the query into genes via symbol or gene ID.
-    let genes = if let Some(gene_ids) = &query.gene_ids {
+/// Return default of `TermGeneAdjustments::network`.
+fn _default_network() -> bool {
+    false
+}
+
+/// Return default of `TermGeneParams::p_values`.
+fn _default_p_values() -> bool {
+    false
+}
+
+/// Return default of `TermGeneAdjustments::network_weight`.
+fn _default_network_weight() -> f32 {
+    0.5
+}
+
+/// Return default of `TermGeneAdjustments::rerank_by_constraint`.
+fn _default_rerank_by_constraint() -> bool {
+    false
+}
+
+/// Return default of `TermGeneAdjustments::clinvar_prior`.
+fn _default_clinvar_prior() -> bool {
+    false
+}
+
+/// Return default of `TermGeneAdjustments::min_tpm`.
+fn _default_min_tpm() -> f32 {
+    1.0
+}
+
+/// Return default of `TermGeneAdjustments::constraint_weight`.
+fn _default_constraint_weight() -> f32 {
+    0.3
+}
+
+/// How the genes of the term-gene result are grouped.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize, utoipa::ToSchema,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum TermGeneGroupBy {
+    /// By the gene panels loaded with `--path-gene-panels`, with per-panel scores.
+    Panel,
+}
+
+/// Translate the gene IDs or symbols from the query into genes.
+pub(super) fn lookup_genes<'a>(
+    gene_ids: Option<&[String]>,
+    gene_symbols: Option<&[String]>,
+    data: &'a WebServerData,
+) -> Result<Vec<&'a Gene>, CustomError> {
+    let hpo: &Ontology = &data.ontology;
+    if let Some(gene_ids) = gene_ids {
+        data.limits.check_batch(gene_ids.len())?;
         Ok(gene_ids
             .iter()
             .filter_map(|gene_id| {
                 if let Ok(gene_id) = gene_id.parse::<u32>() {
                     hpo.gene(&GeneId::from(gene_id))
-                } else if let Some(gene_id) = data.hgnc_to_ncbi.get(gene_id) {
-                    hpo.gene(&GeneId::from(*gene_id))
+                } else if let Some(gene_id) = data.hgnc_xlink.ncbi_gene_id(gene_id) {
+                    hpo.gene(&GeneId::from(gene_id))
                 } else {
                     None
                 }
             })
             .collect::<Vec<_>>())
-    } else if let Some(gene_symbols) = &query.gene_symbols {
+    } else if let Some(gene_symbols) = gene_symbols {
+        data.limits.check_batch(gene_symbols.len())?;
         Ok(gene_symbols
             .iter()
             .filter_map(|gene_symbol| hpo.gene_by_name(gene_symbol))
@@ -100,20 +321,540 @@ async fn handle(
         Err(CustomError::new(anyhow::anyhow!(
             "either `gene_ids` or `gene_symbols` must be given"
         )))
-    }?;
+    }
+}
+
+/// Look up the supplementary association scores requested by name.
+fn lookup_associations<'a>(
+    query: &HpoSimTermGeneQuery,
+    data: &'a WebServerData,
+) -> Result<Vec<(&'a str, &'a AssociationScores)>, CustomError> {
+    query
+        .params
+        .associations
+        .iter()
+        .flatten()
+        .map(|name| {
+            data.association_scores
+                .get_key_value(name)
+                .map(|(name, scores)| (name.as_str(), scores))
+                .ok_or_else(|| {
+                    CustomError::with_status(
+                        StatusCode::BAD_REQUEST,
+                        anyhow::anyhow!("unknown association scores: {}", name),
+                    )
+                })
+        })
+        .collect()
+}
+
+/// Return the network for boosting, if requested, and check its weight.
+fn lookup_network<'a>(
+    query: &HpoSimTermGeneQuery,
+    data: &'a WebServerData,
+) -> Result<Option<&'a crate::network::Network>, CustomError> {
+    if !query.adjustments.network {
+        return Ok(None);
+    }
+    let network = data.network.as_ref().ok_or_else(|| {
+        CustomError::with_status(
+            StatusCode::BAD_REQUEST,
+            anyhow::anyhow!("network boosting requested but no network was loaded"),
+        )
+    })?;
+    if !(0.0..=1.0).contains(&query.adjustments.network_weight) {
+        return Err(CustomError::with_status(
+            StatusCode::BAD_REQUEST,
+            anyhow::anyhow!("network_weight must be between 0 and 1"),
+        ));
+    }
+    Ok(Some(network))
+}
+
+/// Return the constraint table for re-ranking, if requested, and check the weight.
+fn lookup_constraint<'a>(
+    query: &HpoSimTermGeneQuery,
+    data: &'a WebServerData,
+) -> Result<Option<&'a ConstraintTable>, CustomError> {
+    if !query.adjustments.rerank_by_constraint {
+        return Ok(None);
+    }
+    // The ensemble re-sorts by the fused ranks, which would ignore the re-ranking.
+    if query.params.method == TermGeneMethod::Ensemble {
+        return Err(CustomError::with_status(
+            StatusCode::BAD_REQUEST,
+            anyhow::anyhow!("rerank_by_constraint cannot be combined with method=ensemble"),
+        ));
+    }
+    let constraint = data.sources.get::<ConstraintTable>().ok_or_else(|| {
+        CustomError::with_status(
+            StatusCode::BAD_REQUEST,
+            anyhow::anyhow!(
+                "re-ranking by constraint requested but no constraint table was loaded"
+            ),
+        )
+    })?;
+    if !(0.0..=1.0).contains(&query.adjustments.constraint_weight) {
+        return Err(CustomError::with_status(
+            StatusCode::BAD_REQUEST,
+            anyhow::anyhow!("constraint_weight must be between 0 and 1"),
+        ));
+    }
+    Ok(Some(constraint))
+}
+
+/// Return the expression matrix and the columns of the requested tissue, if any.
+fn lookup_expression<'a>(
+    query: &HpoSimTermGeneQuery,
+    data: &'a WebServerData,
+) -> Result<Option<(&'a ExpressionMatrix, Vec<usize>)>, CustomError> {
+    let Some(tissue) = query.adjustments.expressed_in.as_deref() else {
+        return Ok(None);
+    };
+    let matrix = data.sources.get::<ExpressionMatrix>().ok_or_else(|| {
+        CustomError::with_status(
+            StatusCode::BAD_REQUEST,
+            anyhow::anyhow!("tissue requested but no expression matrix was loaded"),
+        )
+    })?;
+    let columns = matrix.tissue_columns(tissue);
+    if columns.is_empty() {
+        return Err(CustomError::with_status(
+            StatusCode::BAD_REQUEST,
+            anyhow::anyhow!("unknown tissue: {}", tissue),
+        ));
+    }
+    Ok(Some((matrix, columns)))
+}
+
+/// Return the gene panels for grouping, if requested.
+fn lookup_panels<'a>(
+    query: &HpoSimTermGeneQuery,
+    data: &'a WebServerData,
+) -> Result<Option<&'a GenePanels>, CustomError> {
+    let Some(TermGeneGroupBy::Panel) = query.params.group_by else {
+        return Ok(None);
+    };
+    data.sources.get::<GenePanels>().map(Some).ok_or_else(|| {
+        CustomError::with_status(
+            StatusCode::BAD_REQUEST,
+            anyhow::anyhow!("grouping by panel requested but no gene panels were loaded"),
+        )
+    })
+}
+
+/// Return the selection of the gene annotations by route, if requested.
+fn lookup_route<'a>(
+    query: &HpoSimTermGeneQuery,
+    data: &'a WebServerData,
+) -> Result<Option<RouteFilter<'a>>, CustomError> {
+    let Some(route) = query.params.annotation_route else {
+        return Ok(None);
+    };
+    if route != AnnotationRoute::Direct && data.disease_gene_index.is_empty() {
+        return Err(CustomError::with_status(
+            StatusCode::BAD_REQUEST,
+            anyhow::anyhow!("scoring via diseases requested but no disease-gene links were loaded"),
+        ));
+    }
+    Ok(Some(RouteFilter {
+        index: &data.disease_gene_index,
+        route,
+    }))
+}
+
+/// Return the information content of the direct gene annotations to score with, if
+/// annotations do not propagate to ancestors.
+///
+/// The simulated p-values, the weighted combiner, and the ensemble are based on the
+/// propagated information content and are rejected without propagation.
+fn lookup_direct_ic<'a>(
+    query: &HpoSimTermGeneQuery,
+    data: &'a WebServerData,
+    experimental: &[ExperimentalFeature],
+) -> Result<Option<&'a IcTable>, CustomError> {
+    let propagation = query
+        .params
+        .annotation_propagation
+        .unwrap_or(data.annotation_propagation);
+    if propagation.is_propagated() {
+        return Ok(None);
+    }
+    let unsupported = if query.params.p_values {
+        Some("p_values")
+    } else if query.params.method == TermGeneMethod::Ensemble {
+        Some("method=ensemble")
+    } else if experimental.contains(&ExperimentalFeature::WeightedCombiner) {
+        Some("weighted-combiner")
+    } else {
+        None
+    };
+    if let Some(unsupported) = unsupported {
+        return Err(CustomError::with_status(
+            StatusCode::BAD_REQUEST,
+            anyhow::anyhow!("annotation_propagation=none is not supported with {unsupported}"),
+        ));
+    }
+    data.ic_tables
+        .table(IcBasedOn::Gene, propagation)
+        .map(Some)
+        .ok_or_else(|| {
+            CustomError::new(anyhow::anyhow!(
+                "no information content of the direct gene annotations"
+            ))
+        })
+}
+
+/// Check that the `ClinVar` prior, if requested, can be applied.
+fn check_clinvar_prior(
+    query: &HpoSimTermGeneQuery,
+    data: &WebServerData,
+) -> Result<(), CustomError> {
+    if !query.adjustments.clinvar_prior {
+        return Ok(());
+    }
+    if query.params.method != TermGeneMethod::Ensemble {
+        return Err(CustomError::with_status(
+            StatusCode::BAD_REQUEST,
+            anyhow::anyhow!("clinvar_prior requires method=ensemble"),
+        ));
+    }
+    if data.sources.get::<ClinvarCounts>().is_none() {
+        return Err(CustomError::with_status(
+            StatusCode::BAD_REQUEST,
+            anyhow::anyhow!("ClinVar prior requested but no ClinVar summary was loaded"),
+        ));
+    }
+    Ok(())
+}
+
+/// Parse the patient's onset term from the query into its onset range.
+fn parse_patient_onset(
+    patient_age_onset: Option<&str>,
+    data: &WebServerData,
+) -> Result<Option<OnsetRange>, CustomError> {
+    let Some(value) = patient_age_onset else {
+        return Ok(None);
+    };
+    let term_id = parse_term_id(value).map_err(|err| CustomError::invalid_term_id(value, &err))?;
+    onset::onset_range(&data.ontology, term_id)
+        .map(Some)
+        .ok_or_else(|| {
+            CustomError::with_status(
+                StatusCode::BAD_REQUEST,
+                anyhow::anyhow!("not an onset term: {}", term_id),
+            )
+        })
+}
+
+/// Calibrate the scores of `result` and group them by top-level category, if requested.
+fn calibrate_and_group(
+    result: &mut HpoSimTermGeneResult,
+    query: &HpoSimTermGeneQuery,
+    data: &WebServerData,
+) {
+    // Calibrate the scores, the Phenomizer score is based on Resnik with gene IC.
+    result.normalization = NormalizationConstants::new(
+        query.params.normalize.unwrap_or_default(),
+        SimilarityMethod::Resnik,
+        data.ic_tables.range(
+            IcBasedOn::Gene,
+            query
+                .params
+                .annotation_propagation
+                .unwrap_or(data.annotation_propagation),
+        ),
+    );
+    if let Some(constants) = &result.normalization {
+        for entry in &mut result.result {
+            entry.normalize(constants);
+        }
+    }
+
+    // Group the best-matching term pairs by top-level category, if requested.
+    if query.params.categories {
+        for entry in &mut result.result {
+            entry.categories = entry
+                .terms
+                .as_ref()
+                .map(|terms| query::category_scores(&data.ontology, terms));
+        }
+    }
+}
+
+/// The seed of the random queries that the p-values were computed against.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct Simulated {
+    /// The seed of the random queries.
+    pub seed: u64,
+    /// Whether the score distributions were cached in memory or on disk.
+    pub cache_hit: bool,
+}
+
+/// Query for similarity between a set of terms to each entry in a
+/// list of genes.
+#[utoipa::path(
+    get,
+    operation_id = "hpoSimTermGene",
+    params(HpoSimTermGeneQuery),
+    responses(
+        (status = 200, description = "The query was successful.", body = HpoSimTermGeneResult),
+        (status = 400, description = "The query parameters are invalid.", body = CustomError),
+        (status = 422, description = "The request exceeds a size or complexity limit.", body = CustomError),
+        (status = 500, description = "The server encountered an error.", body = CustomError),
+        (status = 503, description = "The server is low on memory.", body = CustomError)
+    )
+)]
+#[get("/api/v1/hpo/sim/term-gene")]
+async fn handle(
+    data: Data<Arc<WebServerData>>,
+    _path: Path<()>,
+    query: StrictQuery<HpoSimTermGeneQuery>,
+    resolved_from: ResolvedFrom,
+    timer: ComputeTimer,
+) -> actix_web::Result<HttpResponse, CustomError> {
+    let (result, simulated) = compute(&data, &query, &resolved_from, &timer).await?;
+    let mut response = HttpResponse::Ok().json(result);
+    if let Some(simulated) = simulated {
+        response.extensions_mut().insert(RandomSeed(simulated.seed));
+        if simulated.cache_hit {
+            response.extensions_mut().insert(CacheHit);
+        }
+    }
+    Ok(response)
+}
+
+/// Compute the similarity between the terms and the genes of `query` outside of the server,
+/// e.g., for the Python bindings and the C ABI, on a runtime of the calling thread.
+///
+/// # Errors
+///
+/// In the case that the query is invalid or exceeds a limit.
+pub fn compute_blocking(
+    data: &Arc<WebServerData>,
+    query: &HpoSimTermGeneQuery,
+) -> Result<HpoSimTermGeneResult, anyhow::Error> {
+    actix_web::rt::System::new().block_on(async {
+        compute(
+            data,
+            query,
+            &ResolvedFrom::default(),
+            &ComputeTimer::default(),
+        )
+        .await
+        .map(|(result, _)| result)
+        .map_err(|err| anyhow::anyhow!("{}", err.err))
+    })
+}
+
+/// Compute the similarity between the terms and the genes of `query`.
+///
+/// The `ontology_lookup` and `scoring` stages are marked on `timer`.  The score
+/// distributions for the p-values are simulated on the blocking thread pool, if needed.
+/// Returns the seed of the simulation as well, if p-values were computed.
+///
+/// # Errors
+///
+/// In the case that the query is invalid or exceeds a limit.
+#[allow(clippy::too_many_lines)]
+pub(super) async fn compute(
+    data: &Arc<WebServerData>,
+    query: &HpoSimTermGeneQuery,
+    resolved_from: &ResolvedFrom,
+    timer: &ComputeTimer,
+) -> Result<(HpoSimTermGeneResult, Option<Simulated>), CustomError> {
+    let hpo: &Ontology = &data.ontology;
+    let experimental =
+        super::super::parse_experimental(query.params.experimental.as_deref(), data)?;
+    let rwr = experimental
+        .contains(&ExperimentalFeature::RwrNetwork)
+        .then(RwrParams::default);
+    let network = lookup_network(query, data)?;
+    let constraint = lookup_constraint(query, data)?;
+    check_clinvar_prior(query, data)?;
+    let expression = lookup_expression(query, data)?;
+    let panels = lookup_panels(query, data)?;
+    let route = lookup_route(query, data)?;
+    let direct_ic = lookup_direct_ic(query, data, &experimental)?;
+    let associations = lookup_associations(query, data)?;
+
+    let patient_onset = parse_patient_onset(query.params.patient_age_onset.as_deref(), data)?;
+
+    // Translate strings from the query into an `HpoGroup` and the modifier weights.
+    let (term_ids, weights) =
+        super::super::parse_modified_term_ids(&query.params.terms.to_strs(), data, resolved_from)?;
+    let query_terms = super::super::to_hpo_group(term_ids, data, resolved_from);
+
+    // Translate strings from the query into genes via symbol or gene ID.
+    let genes = lookup_genes(
+        query.params.gene_ids.as_deref(),
+        query.params.gene_symbols.as_deref(),
+        data,
+    )?;
+
+    // Restrict genes to the region, if any.
+    let genes =
+        if let Some(region) = super::super::parse_region(query.params.region.as_deref(), data)? {
+            genes
+                .into_iter()
+                .filter(|gene| region.contains_gene(gene.id().as_u32(), &data.gene_coords))
+                .collect()
+        } else {
+            genes
+        };
+
+    // Exclude genes not expressed in the tissue, if requested.
+    let genes = match &expression {
+        Some((matrix, columns)) if query.adjustments.expression_mode == ExpressionMode::Filter => {
+            genes
+                .into_iter()
+                .filter(|gene| {
+                    matrix
+                        .max_tpm(gene.name(), columns)
+                        .is_none_or(|tpm| tpm >= query.adjustments.min_tpm)
+                })
+                .collect()
+        }
+        _ => genes,
+    };
+    data.limits.check_complexity(
+        query_terms.len(),
+        genes.iter().map(|gene| gene.hpo_terms().len()).sum(),
+    )?;
+    timer.stage("ontology_lookup");
 
     // Perform similarity computation.
-    let result = query::run_query(&query_terms, &genes, hpo, &data.ncbi_to_hgnc)
+    let scorer = query::GeneScorer {
+        score: if experimental.contains(&ExperimentalFeature::WeightedCombiner) {
+            phenomizer::score_weighted_modified
+        } else {
+            phenomizer::score_modified
+        },
+        sex: query.params.sex.map(|sex| SexFilter {
+            index: &data.sex_index,
+            sex,
+        }),
+        route,
+        weights: &weights,
+        direct_ic,
+    };
+    let mut result = query::run_query(&query_terms, &genes, hpo, &data.hgnc_xlink, &scorer)
+        .map_err(CustomError::new)?;
+    if query.params.include_gene_info {
+        for gene in &mut result.query.genes {
+            gene.gene_info = data.gene_info.get(&gene.entrez_id).cloned();
+        }
+    }
+
+    // Compare the raw scores to the simulated ones, if requested.
+    let mut simulated = None;
+    if query.params.p_values && !query_terms.is_empty() {
+        let seed = query
+            .params
+            .seed
+            .unwrap_or(data.simulation_cache.default_seed());
+        if !data.simulation_cache.is_allowed_seed(seed) {
+            return Err(CustomError::with_status(
+                StatusCode::BAD_REQUEST,
+                anyhow::anyhow!(
+                    "seed {} is not allowed, use the default seed {} or one of --p-value-seeds",
+                    seed,
+                    data.simulation_cache.default_seed()
+                ),
+            ));
+        }
+        let term_count = query_terms.len();
+        let shared = Arc::clone(data);
+        let (distributions, cache_hit) = web::block(move || {
+            shared
+                .simulation_cache
+                .get(&shared.ontology, term_count, seed)
+        })
+        .await
+        .map_err(|e| CustomError::new(e.into()))?
         .map_err(CustomError::new)?;
+        for entry in &mut result.result {
+            entry.p_value = hpo
+                .gene_by_name(&entry.gene_symbol)
+                .and_then(|gene| distributions.p_value(gene.id().as_u32(), entry.raw_score));
+        }
+        simulated = Some(Simulated { seed, cache_hit });
+    }
+
+    // Penalize genes with incompatible age of onset, if the patient's is given.
+    if let Some(patient_onset) = patient_onset {
+        query::onset_penalize(
+            &mut result,
+            &data.onset_index,
+            hpo,
+            patient_onset,
+            data.onset_penalty,
+        );
+    }
+
+    // Report the expression in the tissue and penalize genes not expressed, if requested.
+    if let Some((matrix, columns)) = &expression {
+        query::add_expression(
+            &mut result,
+            matrix,
+            columns,
+            query.adjustments.min_tpm,
+            (query.adjustments.expression_mode == ExpressionMode::Penalize)
+                .then_some(data.expression_penalty),
+        );
+    }
 
-    Ok(Json(result))
+    // Diffuse the scores over the network, if requested.
+    if let Some(network) = network {
+        query::network_boost(
+            &query_terms,
+            &mut result,
+            network,
+            hpo,
+            query.adjustments.network_weight,
+            rwr.as_ref(),
+            &scorer,
+        );
+    }
+    // Blend the scores with the genes' constraint, if requested.
+    if let Some(constraint) = constraint {
+        query::constraint_rerank(&mut result, constraint, query.adjustments.constraint_weight);
+    }
+    if !experimental.is_empty() {
+        result.experimental = Some(experimental);
+    }
+    if !associations.is_empty() {
+        query::add_association_scores(&query_terms, &mut result, hpo, &associations);
+    }
+    if let Some(clinvar) = data.sources.get::<ClinvarCounts>() {
+        query::add_clinvar_counts(&mut result, hpo, clinvar);
+    }
+    if query.params.method == TermGeneMethod::Ensemble {
+        query::ensemble(
+            &query_terms,
+            &mut result,
+            hpo,
+            &scorer,
+            &data.ensemble_methods,
+            query.adjustments.clinvar_prior,
+        );
+    }
+
+    calibrate_and_group(&mut result, query, data);
+    if let Some(panels) = panels {
+        query::group_by_panel(&mut result, panels);
+    }
+    timer.stage("scoring");
+
+    Ok((result, simulated))
 }
 
 #[cfg(test)]
 mod test {
     use std::sync::Arc;
 
-    use crate::server::run::hpo_genes::test::web_server_data;
+    use crate::algos::routes::{AnnotationRoute, DiseaseGeneIndex};
+    use crate::server::run::hpo_genes::test::{load_web_server_data, web_server_data};
 
     /// Helper function for running a query.
     #[allow(dead_code)]
@@ -134,6 +875,38 @@ mod test {
         Ok(resp)
     }
 
+    #[test]
+    fn query_params() -> Result<(), actix_web::Error> {
+        use utoipa::IntoParams as _;
+
+        use crate::server::run::strict_query::from_query;
+
+        // The numbers and flags of the flattened structs are parsed from strings.
+        let query = from_query::<super::HpoSimTermGeneQuery>(
+            "terms=HP:0001250&p_values=true&seed=5&network=true&network_weight=0.7&min_tpm=2",
+        )?;
+        assert_eq!(query.params.terms.to_strs().as_slice(), ["HP:0001250"]);
+        assert!(query.params.p_values);
+        assert_eq!(query.params.seed, Some(5));
+        assert!(query.adjustments.network);
+        assert!((query.adjustments.network_weight - 0.7).abs() < f32::EPSILON);
+        assert!((query.adjustments.min_tpm - 2.0).abs() < f32::EPSILON);
+        assert!((query.adjustments.constraint_weight - 0.3).abs() < f32::EPSILON);
+
+        // Unknown parameters are still rejected.
+        assert!(from_query::<super::HpoSimTermGeneQuery>("terms=HP:0001250&netwrk=true").is_err());
+
+        let names = super::HpoSimTermGeneQuery::into_params(|| None)
+            .into_iter()
+            .map(|param| param.name)
+            .collect::<Vec<_>>();
+        assert_eq!(names.len(), 25);
+        assert!(names.contains(&"terms".to_string()));
+        assert!(names.contains(&"expression_mode".to_string()));
+
+        Ok(())
+    }
+
     #[rstest::rstest]
     #[actix_web::test]
     async fn hpo_sim_term_gene_terms_ncbi_gene_ids(
@@ -175,4 +948,314 @@ mod test {
             .await?
         ))
     }
+
+    #[rstest::rstest]
+    #[actix_web::test]
+    async fn hpo_sim_term_gene_categories(
+        web_server_data: &Arc<crate::server::run::WebServerData>,
+    ) -> Result<(), anyhow::Error> {
+        let result = run_query(
+            web_server_data.clone(),
+            "/api/v1/hpo/sim/term-gene?terms=HP:0010442,HP:0000347&gene_ids=23483&categories=true",
+        )
+        .await?;
+
+        let entry = &result.result[0];
+        let categories = entry.categories.as_ref().expect("categories requested");
+        assert!(!categories.is_empty());
+        assert!(categories
+            .windows(2)
+            .all(|pair| pair[0].score >= pair[1].score));
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[actix_web::test]
+    async fn hpo_sim_term_gene_patient_age_onset(
+        web_server_data: &Arc<crate::server::run::WebServerData>,
+    ) -> Result<(), anyhow::Error> {
+        let uri = "/api/v1/hpo/sim/term-gene?terms=HP:0010442,HP:0000347&gene_symbols=TGDS,TTN";
+        let plain = run_query(web_server_data.clone(), uri).await?;
+        let result = run_query(
+            web_server_data.clone(),
+            &format!("{uri}&patient_age_onset=HP:0003581"),
+        )
+        .await?;
+
+        assert_eq!(result.result.len(), plain.result.len());
+        for entry in &result.result {
+            let plain_entry = plain
+                .result
+                .iter()
+                .find(|e| e.gene_symbol == entry.gene_symbol)
+                .expect("same genes");
+            let penalty = entry.onset_penalty.unwrap_or_default();
+            assert!((entry.raw_score + penalty - plain_entry.raw_score).abs() < 1e-5);
+        }
+        assert!(result
+            .result
+            .windows(2)
+            .all(|pair| pair[0].raw_score >= pair[1].raw_score));
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[actix_web::test]
+    async fn hpo_sim_term_gene_modifiers(
+        web_server_data: &Arc<crate::server::run::WebServerData>,
+    ) -> Result<(), anyhow::Error> {
+        let uri = |terms: &str| format!("/api/v1/hpo/sim/term-gene?terms={terms}&gene_ids=23483");
+        let score =
+            |result: crate::query::query_result::HpoSimTermGeneResult| result.result[0].raw_score;
+        let mild = score(
+            run_query(
+                web_server_data.clone(),
+                &uri("HP:0010442[HP:0012825],HP:0000347"),
+            )
+            .await?,
+        );
+        let severe = score(
+            run_query(
+                web_server_data.clone(),
+                &uri("HP:0010442[HP:0012828],HP:0000347"),
+            )
+            .await?,
+        );
+        let plain = score(run_query(web_server_data.clone(), &uri("HP:0010442,HP:0000347")).await?);
+
+        // Mild and severe shift the weight between the two terms in opposite directions.
+        assert!((mild - plain) * (severe - plain) <= 0.0);
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[actix_web::test]
+    async fn hpo_sim_term_gene_ensemble(
+        web_server_data: &Arc<crate::server::run::WebServerData>,
+    ) -> Result<(), anyhow::Error> {
+        let result = run_query(
+            web_server_data.clone(),
+            "/api/v1/hpo/sim/term-gene?terms=HP:0010442,HP:0000347&gene_symbols=TGDS,TTN&method=ensemble",
+        )
+        .await?;
+
+        assert_eq!(result.result.len(), 2);
+        for entry in &result.result {
+            let method_scores = entry.method_scores.as_ref().expect("ensemble requested");
+            assert_eq!(
+                method_scores.keys().collect::<Vec<_>>(),
+                vec!["jaccard", "jc", "lin", "resnik"]
+            );
+        }
+        let scores = result
+            .result
+            .iter()
+            .map(|entry| entry.ensemble_score.expect("ensemble requested"))
+            .collect::<Vec<_>>();
+        assert!(scores[0] >= scores[1]);
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[actix_web::test]
+    async fn hpo_sim_term_gene_ensemble_rerank_by_constraint(
+        web_server_data: &Arc<crate::server::run::WebServerData>,
+    ) -> Result<(), anyhow::Error> {
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(actix_web::web::Data::new(web_server_data.clone()))
+                .service(super::handle),
+        )
+        .await;
+        let req = actix_web::test::TestRequest::get()
+            .uri(
+                "/api/v1/hpo/sim/term-gene?terms=HP:0010442&gene_symbols=TGDS\
+                 &method=ensemble&rerank_by_constraint=true",
+            )
+            .to_request();
+
+        let resp = actix_web::test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+        let body = actix_web::test::read_body(resp).await;
+        assert!(String::from_utf8_lossy(&body).contains("method=ensemble"));
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[actix_web::test]
+    async fn hpo_sim_term_gene_annotation_route_via_disease(
+        web_server_data: &Arc<crate::server::run::WebServerData>,
+    ) -> Result<(), anyhow::Error> {
+        let uri = "/api/v1/hpo/sim/term-gene?terms=HP:0010442,HP:0000347&gene_symbols=TGDS\
+                   &annotation_route=via_disease";
+        let result = run_query(web_server_data.clone(), uri).await?;
+
+        let terms = result.result[0].terms.as_ref().expect("terms are reported");
+        assert!(!terms.is_empty());
+        assert!(terms.iter().all(|term| matches!(
+            term.route,
+            Some(AnnotationRoute::ViaDisease | AnnotationRoute::Both)
+        )));
+        let direct = run_query(
+            web_server_data.clone(),
+            &uri.replace("via_disease", "direct"),
+        )
+        .await?;
+        let direct_terms = direct.result[0].terms.as_ref().expect("terms are reported");
+        assert!(direct_terms
+            .iter()
+            .all(|term| term.route == Some(AnnotationRoute::Direct)));
+
+        // Without disease-gene links, scoring via diseases is rejected.
+        let mut data = load_web_server_data();
+        data.disease_gene_index = DiseaseGeneIndex::default();
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(actix_web::web::Data::new(Arc::new(data)))
+                .service(super::handle),
+        )
+        .await;
+        let req = actix_web::test::TestRequest::get().uri(uri).to_request();
+
+        let resp = actix_web::test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+        let body = actix_web::test::read_body(resp).await;
+        assert!(String::from_utf8_lossy(&body).contains("no disease-gene links"));
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn hpo_sim_term_gene_sex() -> Result<(), anyhow::Error> {
+        // Restrict "Micrognathia" of TGDS to female patients.
+        let sex_index = crate::algos::sex::SexIndex::from_readers(
+            "OMIM:616145\tCatel-Manzke syndrome\t\tHP:0000347\tOMIM:616145\tTAS\t\t\tFEMALE\n"
+                .as_bytes(),
+            "23483\tTGDS\tHP:0000347\tMicrognathia\t-\tOMIM:616145\n".as_bytes(),
+        )?;
+        let data = Arc::new(crate::server::run::WebServerData {
+            sex_index,
+            ..load_web_server_data()
+        });
+        let uri = "/api/v1/hpo/sim/term-gene?terms=HP:0010442,HP:0000347&gene_symbols=TGDS";
+        let gene_term_ids = |result: &crate::query::query_result::HpoSimTermGeneResult| {
+            result.result[0]
+                .terms
+                .iter()
+                .flatten()
+                .map(|term| term.term_gene.term_id.clone())
+                .collect::<Vec<_>>()
+        };
+
+        let unfiltered = run_query(data.clone(), uri).await?;
+        let female = run_query(data.clone(), &format!("{uri}&sex=female")).await?;
+        let male = run_query(data.clone(), &format!("{uri}&sex=male")).await?;
+
+        assert!(gene_term_ids(&female).contains(&"HP:0000347".to_string()));
+        assert_eq!(female.result[0].raw_score, unfiltered.result[0].raw_score);
+        assert!(!gene_term_ids(&male).contains(&"HP:0000347".to_string()));
+        assert!(male.result[0].raw_score < female.result[0].raw_score);
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn hpo_sim_term_gene_region() -> Result<(), anyhow::Error> {
+        let data = Arc::new(crate::server::run::WebServerData {
+            gene_coords: crate::server::run::hpo_genes::test::gene_coords(),
+            ..load_web_server_data()
+        });
+
+        let result = run_query(
+            data,
+            "/api/v1/hpo/sim/term-gene?terms=HP:0010442,HP:0000347&gene_ids=23483,7273\
+             &region=chr13:94000000-95000000",
+        )
+        .await?;
+
+        // TTN has no coordinates and is never in the region.
+        assert_eq!(
+            result
+                .result
+                .iter()
+                .map(|entry| entry.gene_symbol.as_str())
+                .collect::<Vec<_>>(),
+            vec!["TGDS"]
+        );
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn hpo_sim_term_gene_p_values_seed() -> Result<(), anyhow::Error> {
+        let data = crate::server::run::WebServerData {
+            simulation_cache: crate::algos::simulation::SimulationCache::new(None, 20)
+                .with_allowed_seeds([7]),
+            ..load_web_server_data()
+        };
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(actix_web::web::Data::new(Arc::new(data)))
+                .wrap(actix_web::middleware::from_fn(
+                    crate::server::run::middleware::add_response_meta,
+                ))
+                .service(super::handle),
+        )
+        .await;
+        let call = |seed: Option<u64>| {
+            let uri = format!(
+                "/api/v1/hpo/sim/term-gene?terms=HP:0010442,HP:0000347&gene_symbols=TGDS,TTN&p_values=true{}",
+                seed.map(|seed| format!("&seed={seed}")).unwrap_or_default()
+            );
+            actix_web::test::call_and_read_body_json::<_, _, serde_json::Value>(
+                &app,
+                actix_web::test::TestRequest::get().uri(&uri).to_request(),
+            )
+        };
+        let p_values = |resp: &serde_json::Value| {
+            resp["result"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .map(|entry| (entry["gene_symbol"].clone(), entry["p_value"].clone()))
+                .collect::<Vec<_>>()
+        };
+
+        let first = call(Some(7)).await;
+        let second = call(Some(7)).await;
+        let default = call(None).await;
+
+        // The same seed gives the same p-values and is reported in the metadata.
+        assert_eq!(first["meta"]["seed"], 7);
+        assert!(!first["meta"]["cache_hit"].as_bool().unwrap_or_default());
+        assert_eq!(second["meta"]["seed"], 7);
+        assert_eq!(second["meta"]["cache_hit"], true);
+        assert!(p_values(&first)
+            .iter()
+            .all(|(_, p_value)| p_value.is_number()));
+        assert_eq!(p_values(&first), p_values(&second));
+        assert_eq!(
+            default["meta"]["seed"],
+            crate::algos::simulation::DEFAULT_SEED
+        );
+
+        // Seeds not allowed by the server are rejected instead of simulated.
+        let resp = actix_web::test::call_service(
+            &app,
+            actix_web::test::TestRequest::get()
+                .uri("/api/v1/hpo/sim/term-gene?terms=HP:0010442&gene_symbols=TGDS&p_values=true&seed=8")
+                .to_request(),
+        )
+        .await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+
+        Ok(())
+    }
 }
@@ -0,0 +1,463 @@
+//! Entry point `/hpo/sim/term-gene/report` that renders the gene prioritization of
+//! `/hpo/sim/term-gene` as a signed, human-readable HTML report for audit purposes.
+//!
+//! The report embeds the data release, the method parameters, and the result as JSON
+//! together with its SHA-256 hash, or its HMAC-SHA256 with `--report-signing-key`.  A PDF
+//! can be obtained by printing the report from the browser.
+
+use std::{collections::HashMap, fmt::Write as _, sync::Arc};
+
+use actix_web::{
+    get,
+    http::header::ContentType,
+    web::{Data, Path},
+    HttpResponse,
+};
+use hmac::{Hmac, Mac as _};
+use sha2::{Digest as _, Sha256};
+
+use super::super::{
+    middleware::{ComputeTimer, ResolvedFrom},
+    CustomError,
+};
+use super::term_gene::{HpoSimTermGeneQuery, TermGeneParams};
+use crate::server::run::strict_query::StrictQuery;
+use crate::{
+    common::Version, query::query_result::HpoSimTermGeneResult, server::run::WebServerData,
+};
+
+/// The template of the report, with `{{name}}` placeholders.
+const TEMPLATE: &str = include_str!("term_gene_report.html");
+
+/// Name of the response header carrying the signature, as `<algorithm>=<hex value>`.
+pub const SIGNATURE_HEADER: &str = "X-Report-Signature";
+
+/// The signed content of a report.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ReportPayload {
+    /// Time of generation in RFC 3339 format.
+    pub generated_at: String,
+    /// Version information.
+    pub version: Version,
+    /// Date of the HPO annotation files, if known.
+    pub annotation_date: Option<String>,
+    /// The method parameters, including the defaults.
+    pub parameters: HpoSimTermGeneQuery,
+    /// The prioritization result.
+    pub result: HpoSimTermGeneResult,
+}
+
+/// The signature of a report.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signature {
+    /// The algorithm, "sha256" or "hmac-sha256".
+    pub algorithm: &'static str,
+    /// The hex-encoded value.
+    pub value: String,
+}
+
+/// Compute the HMAC-SHA256 of `message` with `key` (RFC 2104).
+fn hmac_sha256(key: &[u8], message: &[u8]) -> Vec<u8> {
+    Hmac::<Sha256>::new_from_slice(key)
+        .expect("HMAC accepts keys of any length")
+        .chain_update(message)
+        .finalize()
+        .into_bytes()
+        .to_vec()
+}
+
+/// Sign `payload` with `key`, or hash it without a key.
+pub fn sign(payload: &str, key: Option<&str>) -> Signature {
+    let (algorithm, digest) = match key {
+        Some(key) => (
+            "hmac-sha256",
+            hmac_sha256(key.as_bytes(), payload.as_bytes()),
+        ),
+        None => ("sha256", Sha256::digest(payload.as_bytes()).to_vec()),
+    };
+    Signature {
+        algorithm,
+        value: digest.iter().fold(String::new(), |mut value, byte| {
+            write!(value, "{byte:02x}").expect("writing to string cannot fail");
+            value
+        }),
+    }
+}
+
+/// Escape `value` for use in HTML text and attribute values.
+fn escape_html(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => result.push_str("&amp;"),
+            '<' => result.push_str("&lt;"),
+            '>' => result.push_str("&gt;"),
+            '"' => result.push_str("&quot;"),
+            '\'' => result.push_str("&#39;"),
+            _ => result.push(c),
+        }
+    }
+    result
+}
+
+/// Serialize `payload` as it is signed and embedded into the report.
+///
+/// `<` is escaped as `\u003c` so that the JSON can be embedded in a `<script>` element
+/// as it is.
+///
+/// # Errors
+///
+/// In the case that the payload cannot be serialized.
+pub fn payload_json(payload: &ReportPayload) -> Result<String, serde_json::Error> {
+    Ok(serde_json::to_string(payload)?.replace('<', "\\u003c"))
+}
+
+/// Render the table rows of the method parameters in `payload`.
+fn parameter_rows(payload: &ReportPayload) -> String {
+    let parameters = serde_json::to_value(&payload.parameters).unwrap_or_default();
+    let mut rows = String::new();
+    for (name, value) in parameters.as_object().into_iter().flatten() {
+        let value = match value {
+            serde_json::Value::Null => continue,
+            serde_json::Value::String(value) => value.clone(),
+            serde_json::Value::Array(values) => values
+                .iter()
+                .map(|value| {
+                    value
+                        .as_str()
+                        .map_or_else(|| value.to_string(), str::to_string)
+                })
+                .collect::<Vec<_>>()
+                .join(", "),
+            value => value.to_string(),
+        };
+        writeln!(
+            rows,
+            "    <tr><td>{}</td><td>{}</td></tr>",
+            escape_html(name),
+            escape_html(&value)
+        )
+        .expect("writing to string cannot fail");
+    }
+    rows
+}
+
+/// Render the table rows of the ranked genes in `payload`.
+fn gene_rows(payload: &ReportPayload) -> String {
+    let mut rows = String::new();
+    for (rank, entry) in payload.result.result.iter().enumerate() {
+        let gene = payload
+            .result
+            .query
+            .genes
+            .iter()
+            .find(|gene| gene.gene_symbol == entry.gene_symbol);
+        let mut ids = Vec::new();
+        if let Some(gene) = gene {
+            ids.push(format!("NCBI {}", gene.entrez_id));
+            ids.extend(gene.hgnc_id.clone());
+        }
+        let mut scores = vec![format!("{:.3}", entry.raw_score)];
+        for (name, score) in [
+            ("combined", entry.combined_score),
+            ("ensemble", entry.ensemble_score),
+        ] {
+            if let Some(score) = score {
+                scores.push(format!("{name} {score:.3}"));
+            }
+        }
+        let terms = entry
+            .terms
+            .iter()
+            .flatten()
+            .filter_map(|details| {
+                details.term_query.as_ref().map(|term_query| {
+                    escape_html(&format!(
+                        "{} ~ {} ({:.2})",
+                        term_query.term_id, details.term_gene.term_id, details.score
+                    ))
+                })
+            })
+            .collect::<Vec<_>>()
+            .join("<br>");
+        writeln!(
+            rows,
+            "    <tr><td>{}</td><td>{}<br><small>{}</small></td><td>{}</td><td>{}</td></tr>",
+            rank + 1,
+            escape_html(&entry.gene_symbol),
+            escape_html(&ids.join(", ")),
+            escape_html(&scores.join(", ")),
+            terms
+        )
+        .expect("writing to string cannot fail");
+    }
+    rows
+}
+
+/// Replace the `{{name}}` placeholders of `template` by their `values` in a single pass.
+///
+/// The values are not scanned for placeholders, so that values from the query cannot
+/// inject other values.  Unknown placeholders are kept as they are.
+fn fill_template(template: &str, values: &HashMap<&str, String>) -> String {
+    let mut result = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        result.push_str(&rest[..start]);
+        let placeholder = &rest[start..];
+        if let Some((end, value)) = placeholder
+            .find("}}")
+            .and_then(|end| values.get(&placeholder[2..end]).map(|value| (end, value)))
+        {
+            result.push_str(value);
+            rest = &placeholder[end + 2..];
+        } else {
+            result.push_str("{{");
+            rest = &placeholder[2..];
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Render the report of `payload`, with its JSON as signed by `signature`.
+pub fn render(payload: &ReportPayload, payload_json: &str, signature: &Signature) -> String {
+    let terms = payload
+        .result
+        .query
+        .terms
+        .iter()
+        .fold(String::new(), |mut rows, term| {
+            writeln!(
+                rows,
+                "    <tr><td>{}</td><td>{}</td></tr>",
+                escape_html(&term.term_id),
+                escape_html(term.term_name.as_deref().unwrap_or_default())
+            )
+            .expect("writing to string cannot fail");
+            rows
+        });
+    let values = HashMap::from([
+        ("generated_at", escape_html(&payload.generated_at)),
+        ("hpo_version", escape_html(&payload.version.hpo)),
+        (
+            "annotation_date",
+            escape_html(payload.annotation_date.as_deref().unwrap_or("unknown")),
+        ),
+        ("viguno_version", escape_html(&payload.version.viguno)),
+        ("terms", terms),
+        ("parameters", parameter_rows(payload)),
+        ("genes", gene_rows(payload)),
+        ("signature_algorithm", signature.algorithm.to_string()),
+        ("signature", signature.value.clone()),
+        ("payload", payload_json.to_string()),
+    ]);
+    fill_template(TEMPLATE, &values)
+}
+
+/// Query for a signed HTML report of the similarity between a set of terms and genes.
+///
+/// Takes the parameters of `/api/v1/hpo/sim/term-gene`.  The signature is also returned
+/// in the `X-Report-Signature` header.
+///
+/// # Errors
+///
+/// In the case that there is an error running the server.
+#[utoipa::path(
+    get,
+    operation_id = "hpoSimTermGeneReport",
+    params(HpoSimTermGeneQuery),
+    responses(
+        (status = 200, description = "The report was generated.", body = String, content_type = "text/html"),
+        (status = 400, description = "The query parameters are invalid.", body = CustomError),
+        (status = 422, description = "The request exceeds a size or complexity limit.", body = CustomError),
+        (status = 500, description = "The server encountered an error.", body = CustomError),
+        (status = 503, description = "The server is low on memory.", body = CustomError)
+    )
+)]
+#[get("/api/v1/hpo/sim/term-gene/report")]
+async fn handle(
+    data: Data<Arc<WebServerData>>,
+    _path: Path<()>,
+    query: StrictQuery<HpoSimTermGeneQuery>,
+    resolved_from: ResolvedFrom,
+    timer: ComputeTimer,
+) -> actix_web::Result<HttpResponse, CustomError> {
+    let (result, simulated) =
+        super::term_gene::compute(&data, &query, &resolved_from, &timer).await?;
+    let query = query.into_inner();
+    let payload = ReportPayload {
+        generated_at: humantime::format_rfc3339_seconds(std::time::SystemTime::now()).to_string(),
+        version: result.version.clone(),
+        annotation_date: data.annotation_date.clone(),
+        // The seed of the p-values is signed with the parameters, also if defaulted.
+        parameters: HpoSimTermGeneQuery {
+            params: TermGeneParams {
+                seed: simulated
+                    .map(|simulated| simulated.seed)
+                    .or(query.params.seed),
+                ..query.params
+            },
+            ..query
+        },
+        result,
+    };
+    let payload_json = payload_json(&payload).map_err(|e| CustomError::new(e.into()))?;
+    let signature = sign(&payload_json, data.report_signing_key.as_deref());
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::html())
+        .insert_header((
+            SIGNATURE_HEADER,
+            format!("{}={}", signature.algorithm, signature.value),
+        ))
+        .body(render(&payload, &payload_json, &signature)))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::query::query_result::{
+        Gene, HpoSimTermGeneQuery, HpoSimTermGeneResult, HpoSimTermGeneResultEntry,
+        HpoSimTermGeneTermDetails,
+    };
+    use crate::query::HpoTerm;
+
+    /// Return a payload with one gene.
+    fn payload() -> super::ReportPayload {
+        let term = |term_id: &str, term_name: &str| HpoTerm {
+            term_id: term_id.to_string(),
+            term_name: Some(term_name.to_string()),
+        };
+        super::ReportPayload {
+            generated_at: "2023-06-06T12:00:00Z".to_string(),
+            version: crate::common::Version {
+                hpo: "2023-06-06".to_string(),
+                viguno: "0.0.0".to_string(),
+            },
+            annotation_date: None,
+            parameters: serde_json::from_value(serde_json::json!({
+                "terms": "HP:0001250",
+                "gene_symbols": "TGDS",
+            }))
+            .expect("valid query"),
+            result: HpoSimTermGeneResult {
+                version: crate::common::Version {
+                    hpo: "2023-06-06".to_string(),
+                    viguno: "0.0.0".to_string(),
+                },
+                query: HpoSimTermGeneQuery {
+                    terms: vec![term("HP:0001250", "Seizure")],
+                    genes: vec![Gene {
+                        entrez_id: 23483,
+                        gene_symbol: "TGDS".to_string(),
+                        hgnc_id: Some("HGNC:20324".to_string()),
+                        gene_info: None,
+                    }],
+                },
+                result: vec![HpoSimTermGeneResultEntry {
+                    gene_symbol: "TGDS".to_string(),
+                    raw_score: 1.5,
+                    terms: Some(vec![HpoSimTermGeneTermDetails {
+                        term_query: Some(term("HP:0001250", "Seizure")),
+                        term_gene: term("HP:0007359", "Focal-onset seizure"),
+                        score: 1.5,
+                        route: None,
+                    }]),
+                    categories: None,
+                    direct_score: None,
+                    network_score: None,
+                    combined_score: None,
+                    onset_penalty: None,
+                    association_scores: None,
+                    method_scores: None,
+                    ensemble_score: None,
+                    p_value: None,
+                    constraint_score: None,
+                    reranked_score: None,
+                    clinvar_pathogenic: None,
+                    expression_tpm: None,
+                    expression_penalty: None,
+                }],
+                experimental: None,
+                normalization: None,
+                panels: None,
+            },
+        }
+    }
+
+    #[test]
+    fn sign() {
+        // Test case 2 of RFC 4231.
+        assert_eq!(
+            super::sign("what do ya want for nothing?", Some("Jefe")),
+            super::Signature {
+                algorithm: "hmac-sha256",
+                value: "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+                    .to_string(),
+            }
+        );
+        assert_eq!(
+            super::sign("abc", None),
+            super::Signature {
+                algorithm: "sha256",
+                value: "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+                    .to_string(),
+            }
+        );
+        // Keys longer than the block size are hashed first.
+        assert_eq!(super::sign("abc", Some(&"k".repeat(100))).value.len(), 64);
+    }
+
+    #[test]
+    fn fill_template() {
+        let values =
+            std::collections::HashMap::from([("a", "{{b}}".to_string()), ("b", "B".to_string())]);
+
+        assert_eq!(
+            super::fill_template("<{{a}}|{{b}}|{{c}}|{{", &values),
+            "<{{b}}|B|{{c}}|{{"
+        );
+    }
+
+    #[test]
+    fn escape_html() {
+        assert_eq!(
+            super::escape_html(r#"<a href="x">&'</a>"#),
+            "&lt;a href=&quot;x&quot;&gt;&amp;&#39;&lt;/a&gt;"
+        );
+    }
+
+    #[test]
+    fn render() -> Result<(), anyhow::Error> {
+        let mut payload = payload();
+        payload.result.query.terms[0].term_name = Some("</script><b>".to_string());
+        let payload_json = super::payload_json(&payload)?;
+        let signature = super::sign(&payload_json, Some("secret"));
+
+        let html = super::render(&payload, &payload_json, &signature);
+
+        assert!(!html.contains("{{"), "{html}");
+        assert!(html.contains("<td>2023-06-06</td>"));
+        assert!(html.contains("<td>TGDS<br><small>NCBI 23483, HGNC:20324</small></td>"));
+        assert!(html.contains("HP:0001250 ~ HP:0007359 (1.50)"));
+        assert!(html.contains("<tr><td>gene_symbols</td><td>TGDS</td></tr>"));
+        assert!(html.contains(&signature.value));
+        assert!(html.contains("&lt;/script&gt;&lt;b&gt;"));
+        assert_eq!(html.matches("</script>").count(), 1);
+
+        // The embedded JSON is the signed one and parses to the payload.
+        let embedded = html
+            .split_once(r#"<script type="application/json" id="report-payload">"#)
+            .and_then(|(_, rest)| rest.split_once("</script>"))
+            .map(|(embedded, _)| embedded)
+            .expect("must be embedded");
+        assert_eq!(embedded, payload_json);
+        assert_eq!(super::sign(embedded, Some("secret")), signature);
+        let parsed: serde_json::Value = serde_json::from_str(embedded)?;
+        assert_eq!(
+            parsed["result"]["query"]["terms"][0]["term_name"],
+            "</script><b>"
+        );
+
+        Ok(())
+    }
+}
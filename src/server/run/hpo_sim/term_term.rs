@@ -5,16 +5,51 @@ use std::sync::Arc;
 
 use actix_web::{
     get,
-    web::{self, Data, Json, Path},
-};
-use hpo::{
-    similarity::{Builtins, Similarity},
-    HpoTermId, Ontology,
+    http::StatusCode,
+    web::{Data, Json, Path},
+    Either, HttpResponse,
 };
+use hpo::{similarity::Similarity, Ontology};
 use itertools::Itertools;
 
-use crate::common::{to_pairwise_sim, IcBasedOn, ScoreCombiner, SimilarityMethod, Version};
-use crate::server::{run::CustomError, run::WebServerData};
+use crate::algos::ic::IcTables;
+use crate::common::{
+    to_pairwise_sim, AnnotationPropagation, IcBasedOn, NormalizationConstants, ScoreCombiner,
+    ScoreNormalization, SimilarityMethod, Version,
+};
+use crate::server::run::arrow_ipc;
+use crate::server::run::strict_query::StrictQuery;
+use crate::server::run::tenants::TenantScope;
+use crate::server::run::CommaSeparated;
+use crate::server::{
+    run::middleware::{ComputeTimer, ResolvedFrom},
+    run::CustomError,
+    run::WebServerData,
+};
+
+/// Layout of the result of `handle`.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    serde::Serialize,
+    serde::Deserialize,
+    utoipa::ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum TermTermLayout {
+    /// A list of pairs, sorted by descending score.
+    #[default]
+    Pairs,
+    /// The full `lhs` by `rhs` matrix, in the order of the query.
+    Matrix,
+    /// The full matrix as an Arrow IPC stream with the columns `lhs`, `rhs`, and `score` in
+    /// row-major order.
+    Arrow,
+}
 
 /// Parameters for `handle`.
 ///
@@ -23,13 +58,15 @@ use crate::server::{run::CustomError, run::WebServerData};
 /// - `lhs` -- first set of terms to compute similarity for
 /// - `rhs` -- econd set of terms to compute similarity for
 #[derive(Default, Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::IntoParams)]
+#[serde(deny_unknown_fields)]
+#[into_params(parameter_in = Query)]
 pub struct RequestQuery {
     /// The one set of HPO terms to compute similarity for.
-    #[serde(deserialize_with = "super::super::vec_str_deserialize")]
-    pub lhs: Vec<String>,
+    #[param(value_type = Vec<String>)]
+    pub lhs: CommaSeparated,
     /// The second set of HPO terms to compute similarity for.
-    #[serde(deserialize_with = "super::super::vec_str_deserialize")]
-    pub rhs: Vec<String>,
+    #[param(value_type = Vec<String>)]
+    pub rhs: CommaSeparated,
     /// What should information content be based on.
     #[serde(default = "IcBasedOn::default")]
     pub ic_base: IcBasedOn,
@@ -39,6 +76,15 @@ pub struct RequestQuery {
     /// The score combiner.
     #[serde(default = "ScoreCombiner::default")]
     pub combiner: ScoreCombiner,
+    /// Whether annotations propagate to ancestors, server default if missing.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub annotation_propagation: Option<AnnotationPropagation>,
+    /// Calibration of the scores into the range from 0 to 1, raw scores if missing.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub normalize: Option<ScoreNormalization>,
+    /// The layout of the result, `pairs` if missing.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub layout: Option<TermTermLayout>,
 }
 
 /// Request as sent together with the response.
@@ -59,6 +105,15 @@ pub struct HpoSimTermTermQuery {
     /// The score combiner.
     #[serde(default = "ScoreCombiner::default")]
     pub combiner: ScoreCombiner,
+    /// Whether annotations propagate to ancestors, server default if missing.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub annotation_propagation: Option<AnnotationPropagation>,
+    /// Calibration of the scores into the range from 0 to 1, raw scores if missing.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub normalize: Option<ScoreNormalization>,
+    /// The layout of the result, `pairs` if missing.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub layout: Option<TermTermLayout>,
 }
 
 /// Result container.
@@ -70,6 +125,26 @@ pub struct HpoSimTermTermResult {
     pub query: HpoSimTermTermQuery,
     /// The resulting records for the scored genes.
     pub result: Vec<HpoSimTermTermResultEntry>,
+    /// The constants of the score normalization, if any.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub normalization: Option<NormalizationConstants>,
+    /// The full similarity matrix with `layout=matrix`, in which case `result` is empty.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub matrix: Option<HpoSimTermTermMatrix>,
+}
+
+/// The similarity matrix of all pairs of `lhs` and `rhs` terms.
+#[derive(
+    Default, Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, utoipa::ToSchema,
+)]
+pub struct HpoSimTermTermMatrix {
+    /// The row headers, i.e., the `lhs` terms.
+    pub rows: Vec<String>,
+    /// The column headers, i.e., the `rhs` terms.
+    pub cols: Vec<String>,
+    /// The scores in row-major order, i.e., the score of row `i` and column `j` is at
+    /// `i * cols.len() + j`.
+    pub scores: Vec<f32>,
 }
 
 /// Result entry for `handle`.
@@ -92,9 +167,31 @@ pub struct HpoSimTermTermResultEntry {
     pub score: f32,
 }
 
+/// Return the `scores` of all pairs of `lhs` and `rhs` terms as an Arrow IPC stream.
+fn arrow_response(matrix: HpoSimTermTermMatrix) -> Result<HttpResponse, CustomError> {
+    let (lhs, rhs): (Vec<_>, Vec<_>) = matrix
+        .rows
+        .iter()
+        .cartesian_product(matrix.cols.iter())
+        .map(|(lhs, rhs)| (lhs.clone(), rhs.clone()))
+        .unzip();
+    let body = arrow_ipc::write_stream(&[
+        ("lhs", arrow_ipc::Column::Utf8(lhs)),
+        ("rhs", arrow_ipc::Column::Utf8(rhs)),
+        ("score", arrow_ipc::Column::Float32(matrix.scores)),
+    ])
+    .map_err(CustomError::new)?;
+    Ok(HttpResponse::Ok()
+        .content_type(arrow_ipc::CONTENT_TYPE)
+        .body(body))
+}
+
 /// Query for pairwise term similarity.
 ///
-/// In the case of Resnik, this corresponds to `IC(MICA(t_1, t_2))`.
+/// In the case of Resnik, this corresponds to `IC(MICA(t_1, t_2))`.  With `layout=matrix`,
+/// the scores of all pairs are returned as a compact matrix with row and column headers,
+/// e.g., for statistical analyses.  With `layout=arrow`, the matrix is returned as an Arrow
+/// IPC stream that Python and R clients can read into data frames without parsing JSON.
 ///
 /// # Errors
 ///
@@ -104,75 +201,158 @@ pub struct HpoSimTermTermResultEntry {
     operation_id = "hpoSimTermTerm",
     params(RequestQuery),
     responses(
-        (status = 200, description = "The query was successful.", body = HpoSimTermTermResult),
-        (status = 500, description = "The server encountered an error.", body = CustomError)
+        (status = 200, description = "The query was successful.", content(
+            (HpoSimTermTermResult = "application/json"),
+            (Vec<u8> = "application/vnd.apache.arrow.stream"),
+        )),
+        (status = 400, description = "A term ID is malformed or the requested information content is not available.", body = CustomError),
+        (status = 422, description = "The request exceeds a size or complexity limit.", body = CustomError),
+        (status = 500, description = "The server encountered an error.", body = CustomError),
+        (status = 503, description = "The server is low on memory.", body = CustomError)
     )
 )]
 #[get("/api/v1/hpo/sim/term-term")]
 async fn handle(
     data: Data<Arc<WebServerData>>,
+    tenant: TenantScope,
     _path: Path<()>,
-    query: web::Query<RequestQuery>,
-) -> actix_web::Result<Json<HpoSimTermTermResult>, CustomError> {
+    query: StrictQuery<RequestQuery>,
+    resolved_from: ResolvedFrom,
+    _timer: ComputeTimer,
+) -> actix_web::Result<Either<Json<HpoSimTermTermResult>, HttpResponse>, CustomError> {
+    let mut result = compute(&data, tenant.ic_tables(&data), &query, &resolved_from)?;
+    match (
+        result.query.layout.unwrap_or_default(),
+        result.matrix.take(),
+    ) {
+        (TermTermLayout::Arrow, Some(matrix)) => Ok(Either::Right(arrow_response(matrix)?)),
+        (_, matrix) => {
+            result.matrix = matrix;
+            Ok(Either::Left(Json(result)))
+        }
+    }
+}
+
+/// Compute the pairwise similarity of the terms of `query` with the information content of
+/// `ic_tables`, without the `meta` of the response.
+///
+/// # Errors
+///
+/// In the case that a term ID is malformed or the information content is not available.
+pub fn compute_blocking(
+    data: &WebServerData,
+    ic_tables: &IcTables,
+    query: &RequestQuery,
+) -> Result<HpoSimTermTermResult, anyhow::Error> {
+    compute(data, ic_tables, query, &ResolvedFrom::default())
+        .map_err(|err| anyhow::anyhow!("{}", err.err))
+}
+
+/// Compute the pairwise similarity of the terms of `query`, see `handle`.
+///
+/// With `layout=arrow`, the scores are returned as the matrix.
+fn compute(
+    data: &WebServerData,
+    ic_tables: &IcTables,
+    query: &RequestQuery,
+    resolved_from: &ResolvedFrom,
+) -> Result<HpoSimTermTermResult, CustomError> {
     let ontology: &Ontology = &data.ontology;
     let mut result = Vec::new();
 
-    let ic: Builtins = to_pairwise_sim(query.similarity, query.ic_base);
+    ic_tables
+        .ensure_available(query.ic_base)
+        .map_err(|e| CustomError::with_status(StatusCode::BAD_REQUEST, e))?;
+    let propagation = query
+        .annotation_propagation
+        .unwrap_or(data.annotation_propagation);
+    let ic = to_pairwise_sim(query.similarity, query.ic_base, propagation, ic_tables);
+    let normalization = NormalizationConstants::new(
+        query.normalize.unwrap_or_default(),
+        query.similarity,
+        ic_tables.range(query.ic_base, propagation),
+    );
 
     // Translate strings from the query into HPO terms.
-    let lhs = query
-        .lhs
+    let lhs_ids = super::super::parse_term_ids(&query.lhs.to_strs(), data, resolved_from)?;
+    let rhs_ids = super::super::parse_term_ids(&query.rhs.to_strs(), data, resolved_from)?;
+    let lhs = lhs_ids
         .iter()
-        .filter_map(|lhs| ontology.hpo(HpoTermId::from(lhs.clone())))
+        .filter_map(|lhs| ontology.hpo(*lhs))
         .collect::<Vec<_>>();
-    let rhs = query
-        .rhs
+    let rhs = rhs_ids
         .iter()
-        .filter_map(|rhs| ontology.hpo(HpoTermId::from(rhs.clone())))
+        .filter_map(|rhs| ontology.hpo(*rhs))
         .collect::<Vec<_>>();
+    data.limits.check_complexity(lhs.len(), rhs.len())?;
 
     // Compute the similarity for each pair.
-    for (lhs, rhs) in lhs.iter().cartesian_product(rhs.iter()) {
-        let similarity = ic.calculate(lhs, rhs);
-        let elem = HpoSimTermTermResultEntry {
-            lhs: lhs.id().to_string(),
-            rhs: rhs.id().to_string(),
-            score: similarity,
-        };
-        result.push(elem);
-    }
+    let scores = lhs
+        .iter()
+        .cartesian_product(rhs.iter())
+        .map(|(lhs, rhs)| {
+            let similarity = ic.calculate(lhs, rhs);
+            normalization
+                .as_ref()
+                .map_or(similarity, |normalization| normalization.apply(similarity))
+        })
+        .collect::<Vec<_>>();
 
-    result.sort_by(|lhs, rhs| {
-        rhs.score
-            .partial_cmp(&lhs.score)
-            .expect("could not sort by score")
-    });
+    let matrix = if query.layout.unwrap_or_default() == TermTermLayout::Pairs {
+        for ((lhs, rhs), score) in lhs.iter().cartesian_product(rhs.iter()).zip(scores) {
+            result.push(HpoSimTermTermResultEntry {
+                lhs: lhs.id().to_string(),
+                rhs: rhs.id().to_string(),
+                score,
+            });
+        }
+        result.sort_by(|lhs, rhs| {
+            rhs.score
+                .partial_cmp(&lhs.score)
+                .expect("could not sort by score")
+        });
+        None
+    } else {
+        Some(HpoSimTermTermMatrix {
+            rows: lhs.iter().map(|term| term.id().to_string()).collect(),
+            cols: rhs.iter().map(|term| term.id().to_string()).collect(),
+            scores,
+        })
+    };
 
     // We need to convert between Request and RequestResponse here so we can serialize the
-    // lhs and rhs as Vec (they must be strings to parse the GET).
+    // lhs and rhs as Vec (they must be strings to parse the GET).  The term IDs are echoed
+    // in canonical form.
     let RequestQuery {
-        lhs,
-        rhs,
         ic_base,
         similarity,
         combiner,
-    } = query.into_inner();
+        annotation_propagation,
+        normalize,
+        layout,
+        ..
+    } = *query;
 
     let result = HpoSimTermTermResult {
         version: Version::new(&data.ontology.hpo_version()),
         query: HpoSimTermTermQuery {
-            lhs,
-            rhs,
+            lhs: lhs_ids.iter().map(ToString::to_string).collect(),
+            rhs: rhs_ids.iter().map(ToString::to_string).collect(),
             ic_base,
             similarity,
             combiner,
+            annotation_propagation,
+            normalize,
+            layout,
         },
         result,
+        normalization,
+        matrix,
     };
 
     dbg!(&result);
 
-    Ok(Json(result))
+    Ok(result)
 }
 
 #[cfg(test)]
@@ -227,4 +407,145 @@ mod test {
             .await?
         ))
     }
+
+    #[rstest::rstest]
+    #[actix_web::test]
+    async fn hpo_sim_term_term_id_variants(
+        web_server_data: &Arc<crate::server::run::WebServerData>,
+    ) -> Result<(), anyhow::Error> {
+        let result = run_query(
+            web_server_data.clone(),
+            "/api/v1/hpo/sim/term-term?lhs=hp_0010442&rhs=1780",
+        )
+        .await?;
+
+        assert_eq!(result.query.lhs, vec!["HP:0010442"]);
+        assert_eq!(result.query.rhs, vec!["HP:0001780"]);
+        assert_eq!(result.result.len(), 1);
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[actix_web::test]
+    async fn hpo_sim_term_term_matrix(
+        web_server_data: &Arc<crate::server::run::WebServerData>,
+    ) -> Result<(), anyhow::Error> {
+        let pairs = run_query(
+            web_server_data.clone(),
+            "/api/v1/hpo/sim/term-term?lhs=HP:0010442,HP:0000347&rhs=HP:0001780,HP:0000252",
+        )
+        .await?;
+        let result = run_query(
+            web_server_data.clone(),
+            "/api/v1/hpo/sim/term-term?lhs=HP:0010442,HP:0000347&rhs=HP:0001780,HP:0000252&layout=matrix",
+        )
+        .await?;
+
+        assert!(result.result.is_empty());
+        let matrix = result.matrix.expect("matrix must be returned");
+        assert_eq!(matrix.rows, vec!["HP:0010442", "HP:0000347"]);
+        assert_eq!(matrix.cols, vec!["HP:0001780", "HP:0000252"]);
+        assert_eq!(matrix.scores.len(), 4);
+        for entry in &pairs.result {
+            let row = matrix.rows.iter().position(|term| *term == entry.lhs);
+            let col = matrix.cols.iter().position(|term| *term == entry.rhs);
+            let (Some(row), Some(col)) = (row, col) else {
+                anyhow::bail!("pair {} {} not in matrix", entry.lhs, entry.rhs);
+            };
+            assert!((matrix.scores[row * matrix.cols.len() + col] - entry.score).abs() < 1e-6);
+        }
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[actix_web::test]
+    async fn hpo_sim_term_term_arrow(
+        web_server_data: &Arc<crate::server::run::WebServerData>,
+    ) -> Result<(), anyhow::Error> {
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(actix_web::web::Data::new(web_server_data.clone()))
+                .service(super::handle),
+        )
+        .await;
+        let req = actix_web::test::TestRequest::get()
+            .uri("/api/v1/hpo/sim/term-term?lhs=HP:0010442,HP:0000347&rhs=HP:0001780&layout=arrow")
+            .to_request();
+        let resp = actix_web::test::call_service(&app, req).await;
+        assert_eq!(
+            resp.headers().get(actix_web::http::header::CONTENT_TYPE),
+            Some(&actix_web::http::header::HeaderValue::from_static(
+                crate::server::run::arrow_ipc::CONTENT_TYPE
+            ))
+        );
+        let body = actix_web::test::read_body(resp).await;
+
+        let reader = ::arrow_ipc::reader::StreamReader::try_new(&body[..], None)?;
+        let batches = reader.collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].num_rows(), 2);
+        assert_eq!(batches[0].num_columns(), 3);
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[actix_web::test]
+    async fn hpo_sim_term_term_malformed_id(
+        web_server_data: &Arc<crate::server::run::WebServerData>,
+    ) -> Result<(), anyhow::Error> {
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(actix_web::web::Data::new(web_server_data.clone()))
+                .service(super::handle),
+        )
+        .await;
+        let req = actix_web::test::TestRequest::get()
+            .uri("/api/v1/hpo/sim/term-term?lhs=HP:0010442&rhs=seizure")
+            .to_request();
+        let resp = actix_web::test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = actix_web::test::read_body_json(resp).await;
+
+        assert_eq!(body["term_id"], "seizure");
+        assert!(body["err"].as_str().is_some());
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[actix_web::test]
+    async fn hpo_sim_term_term_alt_id(
+        web_server_data: &Arc<crate::server::run::WebServerData>,
+    ) -> Result<(), anyhow::Error> {
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(actix_web::web::Data::new(web_server_data.clone()))
+                .wrap(actix_web::middleware::from_fn(
+                    crate::server::run::middleware::add_response_meta,
+                ))
+                .service(super::handle),
+        )
+        .await;
+        let req = actix_web::test::TestRequest::get()
+            .uri("/api/v1/hpo/sim/term-term?lhs=HP:0002279&rhs=HP:0001250")
+            .to_request();
+
+        let resp: serde_json::Value = actix_web::test::call_and_read_body_json(&app, req).await;
+
+        // The alternative ID is scored as the primary term.
+        assert_eq!(resp["result"][0]["lhs"], "HP:0001250");
+        assert_eq!(resp["result"][0]["rhs"], "HP:0001250");
+        assert_eq!(resp["meta"]["resolved_from"]["HP:0002279"], "HP:0001250");
+        assert_eq!(
+            resp["meta"]["resolved_from"]
+                .as_object()
+                .map(|ids| ids.len()),
+            Some(1)
+        );
+
+        Ok(())
+    }
 }
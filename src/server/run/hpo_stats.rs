@@ -0,0 +1,223 @@
+//! Implementation of `/hpo/stats/categories`.
+
+use std::{collections::HashMap, sync::Arc};
+
+use actix_web::{
+    get,
+    web::{Data, Json, Path},
+};
+use hpo::{HpoTermId, Ontology};
+
+use crate::{common::Version, server::run::WebServerData};
+
+use super::{middleware::ComputeTimer, CustomError, ResultHpoTerm};
+
+/// The counts of one top-level branch of the ontology.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct HpoStatsCategory {
+    /// The top-level term of the branch, e.g., `HP:0000152` (Abnormality of head or neck).
+    pub category: ResultHpoTerm,
+    /// The number of terms in the branch, including the top-level term.
+    pub n_terms: usize,
+    /// The number of genes annotated with a term of the branch.
+    pub n_genes: usize,
+    /// The number of OMIM diseases annotated with a term of the branch.
+    pub n_omim_diseases: usize,
+    /// The number of ORPHA diseases annotated with a term of the branch.
+    pub n_orpha_diseases: usize,
+}
+
+/// Container for the result.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct HpoStatsCategoriesResult {
+    /// Version information.
+    pub version: Version,
+    /// The number of non-obsolete terms in the ontology.
+    pub n_terms: usize,
+    /// The number of annotated genes.
+    pub n_genes: usize,
+    /// The number of annotated OMIM diseases.
+    pub n_omim_diseases: usize,
+    /// The number of annotated ORPHA diseases.
+    pub n_orpha_diseases: usize,
+    /// The counts by top-level branch, sorted by term ID.
+    ///
+    /// Terms below more than one branch are counted in each of them.
+    pub result: Vec<HpoStatsCategory>,
+}
+
+/// Count the terms and annotations of the top-level branches of `ontology`.
+///
+/// The branches are the ontology's categories, i.e., the children of "Phenotypic
+/// abnormality" and the other children of "All".
+pub fn category_stats(ontology: &Ontology) -> Vec<HpoStatsCategory> {
+    let mut n_terms = HashMap::<HpoTermId, usize>::new();
+    for term in ontology.hpos().filter(|term| !term.is_obsolete()) {
+        for category in term.categories() {
+            *n_terms.entry(category).or_default() += 1;
+        }
+    }
+
+    ontology
+        .categories()
+        .iter()
+        .filter_map(|category| ontology.hpo(category))
+        .map(|category| HpoStatsCategory {
+            category: ResultHpoTerm {
+                term_id: category.id().to_string(),
+                name: category.name().to_string(),
+            },
+            n_terms: n_terms.get(&category.id()).copied().unwrap_or_default(),
+            n_genes: category.gene_ids().len(),
+            n_omim_diseases: category.omim_disease_ids().len(),
+            n_orpha_diseases: category.orpha_disease_ids().len(),
+        })
+        .collect()
+}
+
+/// Query for the number of terms, genes, and diseases by top-level branch.
+///
+/// Annotations propagate to the ancestors, so a gene or disease annotated with any term
+/// of a branch is counted for the branch.  This allows to sanity-check new releases.
+///
+/// # Errors
+///
+/// In the case that there is an error running the server.
+#[allow(clippy::unused_async)]
+#[utoipa::path(
+    get,
+    operation_id = "hpoStatsCategories",
+    responses(
+        (status = 200, description = "The query was successful.", body = HpoStatsCategoriesResult),
+        (status = 500, description = "The server encountered an error.", body = CustomError)
+    )
+)]
+#[get("/api/v1/hpo/stats/categories")]
+async fn handle(
+    data: Data<Arc<WebServerData>>,
+    _path: Path<()>,
+    _timer: ComputeTimer,
+) -> actix_web::Result<Json<HpoStatsCategoriesResult>, CustomError> {
+    let ontology = &data.ontology;
+
+    Ok(Json(HpoStatsCategoriesResult {
+        version: Version::new(&ontology.hpo_version()),
+        n_terms: ontology.hpos().filter(|term| !term.is_obsolete()).count(),
+        n_genes: ontology.genes().count(),
+        n_omim_diseases: ontology.omim_diseases().count(),
+        n_orpha_diseases: ontology.orpha_diseases().count(),
+        result: category_stats(ontology),
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use hpo::{
+        annotations::{GeneId, OmimDiseaseId},
+        builder::Builder,
+        Ontology,
+    };
+
+    use crate::server::run::hpo_genes::test::web_server_data;
+
+    /// Build a small ontology with the structure
+    ///
+    /// ```text
+    /// HP:0000001 (All)
+    /// +-- HP:0000005 (Mode of inheritance)
+    /// +-- HP:0000118 (Phenotypic abnormality)
+    ///     +-- HP:0000002 (Head)
+    ///     |   +-- HP:0000004 (Eye)
+    ///     +-- HP:0000003 (Heart)
+    /// ```
+    ///
+    /// with gene 1 on `HP:0000004` and `HP:0000003`, gene 2 on `HP:0000002`, and
+    /// disease 1 on `HP:0000004`.
+    fn build_ontology() -> Ontology {
+        let mut builder = Builder::new();
+        builder.new_term("All", 1u32);
+        builder.new_term("Head", 2u32);
+        builder.new_term("Heart", 3u32);
+        builder.new_term("Eye", 4u32);
+        builder.new_term("Mode of inheritance", 5u32);
+        builder.new_term("Phenotypic abnormality", 118u32);
+        let mut builder = builder.terms_complete();
+        builder.add_parent(1u32, 5u32).expect("term must exist");
+        builder.add_parent(1u32, 118u32).expect("term must exist");
+        builder.add_parent(118u32, 2u32).expect("term must exist");
+        builder.add_parent(118u32, 3u32).expect("term must exist");
+        builder.add_parent(2u32, 4u32).expect("term must exist");
+        let mut builder = builder.connect_all_terms();
+        for term in [4u32, 3u32] {
+            builder
+                .annotate_gene(GeneId::from(1), "GENE1", term.into())
+                .expect("term must exist");
+        }
+        builder
+            .annotate_gene(GeneId::from(2), "GENE2", 2u32.into())
+            .expect("term must exist");
+        builder
+            .annotate_omim_disease(OmimDiseaseId::from(1), "Disease 1", 4u32.into())
+            .expect("term must exist");
+        builder
+            .calculate_information_content()
+            .expect("could not compute IC")
+            .build_with_defaults()
+            .expect("could not build ontology")
+    }
+
+    #[test]
+    fn category_stats() {
+        let ontology = build_ontology();
+
+        let stats = super::category_stats(&ontology);
+
+        assert_eq!(
+            stats
+                .iter()
+                .map(|category| (
+                    category.category.term_id.as_str(),
+                    category.n_terms,
+                    category.n_genes,
+                    category.n_omim_diseases
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                ("HP:0000002", 2, 2, 1),
+                ("HP:0000003", 1, 1, 0),
+                ("HP:0000005", 1, 0, 0),
+            ]
+        );
+    }
+
+    #[rstest::rstest]
+    #[actix_web::test]
+    async fn hpo_stats_categories(
+        web_server_data: &Arc<crate::server::run::WebServerData>,
+    ) -> Result<(), anyhow::Error> {
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(actix_web::web::Data::new(web_server_data.clone()))
+                .service(super::handle),
+        )
+        .await;
+        let req = actix_web::test::TestRequest::get()
+            .uri("/api/v1/hpo/stats/categories")
+            .to_request();
+        let resp: super::HpoStatsCategoriesResult =
+            actix_web::test::call_and_read_body_json(&app, req).await;
+
+        assert!(resp
+            .result
+            .iter()
+            .any(|category| category.category.term_id == "HP:0000152"));
+        assert!(resp
+            .result
+            .iter()
+            .all(|category| category.n_terms <= resp.n_terms && category.n_genes <= resp.n_genes));
+
+        Ok(())
+    }
+}
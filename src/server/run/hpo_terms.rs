@@ -4,14 +4,27 @@ use std::{collections::HashMap, sync::Arc};
 
 use actix_web::{
     get,
-    web::{self, Data, Json, Path},
+    http::StatusCode,
+    web::{Data, Json, Path},
 };
-use hpo::{annotations::AnnotationId, HpoTerm, HpoTermId, Ontology};
+use hpo::{
+    annotations::{AnnotationId, GeneId},
+    HpoTerm, Ontology,
+};
+use itertools::Itertools as _;
 use tantivy::schema::Value as _;
 
-use crate::{common::Version, server::run::WebServerData};
+use crate::{
+    common::{AnnotationPropagation, Version},
+    server::run::WebServerData,
+    sources::{gene_info::GeneInfo, local_terms::LocalTerm, translations::Translation},
+};
 
-use super::{CustomError, ResultGene};
+use super::{
+    middleware::{ComputeTimer, ResolvedFrom},
+    CustomError, ResultGene,
+};
+use crate::server::run::strict_query::StrictQuery;
 
 /// Parameters for `handle`.
 ///
@@ -22,9 +35,20 @@ use super::{CustomError, ResultGene};
 /// - `gene_symbol` -- specify the gene symbol
 /// - `max_results` -- the maximum number of records to return
 /// - `genes` -- whether to include `"genes"` in result
+/// - `include_gene_info` -- whether to include `"gene_info"` in the genes
+/// - `annotation_propagation` -- whether genes annotated with descendants are included
+/// - `lang` -- language of the term labels and of the name search, falls back to English
+/// - `created_after` -- only terms created after the date, e.g., the previous release;
+///   without `term_id` and `name`, all such terms are returned
+/// - `stem` -- whether the name search matches English word stems, e.g., "seizures" for
+///   "Seizure"
+/// - `highlight` -- whether the matches of the name search are highlighted in the results
 #[derive(
     Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema, utoipa::IntoParams,
 )]
+#[serde(deny_unknown_fields)]
+#[into_params(parameter_in = Query)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct HpoTermsQuery {
     /// The term ID to search for.
     pub term_id: Option<String>,
@@ -36,6 +60,29 @@ pub struct HpoTermsQuery {
     /// Whether to include genes.
     #[serde(default = "_default_genes")]
     pub genes: bool,
+    /// Whether to include the NCBI gene information of the genes.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub include_gene_info: bool,
+    /// Whether annotations propagate to ancestors, server default if missing.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub annotation_propagation: Option<AnnotationPropagation>,
+    /// Language of the term labels, e.g., `de`; untranslated labels are in English.
+    ///
+    /// Name searches also match the translated names and synonyms.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub lang: Option<String>,
+    /// Only return terms created after this date as `YYYY-MM-DD`, e.g., `2023-06-06`.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub created_after: Option<String>,
+    /// Whether the name search reduces English words to their stems.
+    ///
+    /// Off by default as stemming may mangle acronyms, e.g., `ALS` matches "al".
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub stem: bool,
+    /// Whether to return the matches of the name search in the name, definition, and
+    /// synonyms of the results as `highlights`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub highlight: bool,
 }
 
 /// Return default of `Request::max_results`.
@@ -48,6 +95,90 @@ fn _default_genes() -> bool {
     false
 }
 
+/// Parse `date` as `YYYY-MM-DD`, e.g., the date of an HPO release.
+fn check_date(date: &str) -> Result<chrono::NaiveDate, CustomError> {
+    chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .ok()
+        // The format also accepts months and days without leading zeros.
+        .filter(|parsed| parsed.format("%Y-%m-%d").to_string() == date)
+        .ok_or_else(|| {
+            CustomError::with_status(
+                StatusCode::BAD_REQUEST,
+                anyhow::anyhow!("created_after must be a date as YYYY-MM-DD: {}", date),
+            )
+        })
+}
+
+/// A range of highlighted characters in a text.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct HighlightRange {
+    /// The offset of the first character, counted in Unicode characters.
+    pub start: usize,
+    /// The offset after the last character, counted in Unicode characters.
+    pub end: usize,
+}
+
+/// The highlighted matches in a text of a result entry.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct Highlight {
+    /// The HTML-escaped text with the matches wrapped in `<em>` tags.
+    pub html: String,
+    /// The ranges of the matches in the text, empty if there are none.
+    pub ranges: Vec<HighlightRange>,
+}
+
+impl Highlight {
+    /// Construct the highlight of the byte `ranges` of `text`, e.g., from
+    /// `Index::highlight`.
+    pub fn new(text: &str, ranges: &[std::ops::Range<usize>]) -> Self {
+        /// Append `text` to `html` with the HTML special characters escaped.
+        fn escape(html: &mut String, text: &str) {
+            for c in text.chars() {
+                match c {
+                    '&' => html.push_str("&amp;"),
+                    '<' => html.push_str("&lt;"),
+                    '>' => html.push_str("&gt;"),
+                    '"' => html.push_str("&quot;"),
+                    _ => html.push(c),
+                }
+            }
+        }
+
+        let chars = |end: usize| text[..end].chars().count();
+        let mut html = String::new();
+        let mut last = 0;
+        for range in ranges {
+            escape(&mut html, &text[last..range.start]);
+            html.push_str("<em>");
+            escape(&mut html, &text[range.clone()]);
+            html.push_str("</em>");
+            last = range.end;
+        }
+        escape(&mut html, &text[last..]);
+        Self {
+            html,
+            ranges: ranges
+                .iter()
+                .map(|range| HighlightRange {
+                    start: chars(range.start),
+                    end: chars(range.end),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// The highlighted matches of the name search in a result entry.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct HpoTermsHighlights {
+    /// The highlighted name.
+    pub name: Highlight,
+    /// The highlighted definition, if any.
+    pub definition: Option<Highlight>,
+    /// The highlighted synonyms in the order of `synonyms`.
+    pub synonyms: Vec<Highlight>,
+}
+
 /// Result entry for `fetch_hpo_genes`.
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
 pub struct HpoTermsResultEntry {
@@ -64,6 +195,12 @@ pub struct HpoTermsResultEntry {
     /// The gene's associated HPO terms.
     #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
     pub genes: Option<Vec<ResultGene>>,
+    /// Whether the term is a local extension term rather than an HPO term.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub local: bool,
+    /// The matches of the name search, if requested with `highlight`.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub highlights: Option<HpoTermsHighlights>,
 }
 
 impl PartialEq for HpoTermsResultEntry {
@@ -93,6 +230,8 @@ impl Ord for HpoTermsResultEntry {
 impl HpoTermsResultEntry {
     /// Create a `ResultEntry` from an `HpoTerm`.
     ///
+    /// The genes with `gene_ids` are included in the result if given.
+    ///
     /// # Errors
     ///
     /// In the case that there is an error parsing the term.
@@ -100,8 +239,8 @@ impl HpoTermsResultEntry {
     pub fn from_term_with_ontology(
         term: &HpoTerm,
         ontology: &Ontology,
-        genes: bool,
-        ncbi_to_hgnc: &HashMap<u32, String>,
+        gene_ids: Option<Vec<GeneId>>,
+        hgnc_xlink: &crate::sources::hgnc_xlink::HgncXlink,
         index: &crate::index::Index,
         doc: Option<&tantivy::TantivyDocument>,
     ) -> Result<Self, anyhow::Error> {
@@ -163,22 +302,20 @@ impl HpoTermsResultEntry {
             .collect::<Vec<_>>();
         let xrefs = if xrefs.is_empty() { None } else { Some(xrefs) };
 
-        let genes = if genes {
-            let mut genes = term
-                .gene_ids()
+        let genes = gene_ids.map(|gene_ids| {
+            let mut genes = gene_ids
                 .iter()
                 .filter_map(|gene_id| ontology.gene(gene_id))
                 .map(|gene| ResultGene {
                     ncbi_gene_id: gene.id().as_u32(),
                     gene_symbol: gene.name().to_string(),
-                    hgnc_id: ncbi_to_hgnc.get(&gene.id().as_u32()).cloned(),
+                    hgnc_id: hgnc_xlink.hgnc_id(gene.id().as_u32()).map(str::to_string),
+                    gene_info: None,
                 })
                 .collect::<Vec<_>>();
             genes.sort();
-            Some(genes)
-        } else {
-            None
-        };
+            genes
+        });
         Ok(HpoTermsResultEntry {
             term_id: term.id().to_string(),
             name: term.name().to_string(),
@@ -186,8 +323,99 @@ impl HpoTermsResultEntry {
             definition,
             synonyms,
             xrefs,
+            local: false,
+            highlights: None,
         })
     }
+
+    /// Create a `ResultEntry` from a local extension term.
+    ///
+    /// The genes are those of the HPO terms the local term stands for, if requested.
+    pub fn from_local_term(
+        term: &LocalTerm,
+        ontology: &Ontology,
+        gene_ids: Option<Vec<GeneId>>,
+        hgnc_xlink: &crate::sources::hgnc_xlink::HgncXlink,
+    ) -> Self {
+        let genes = gene_ids.map(|gene_ids| {
+            gene_ids
+                .iter()
+                .filter_map(|gene_id| ontology.gene(gene_id))
+                .map(|gene| ResultGene {
+                    ncbi_gene_id: gene.id().as_u32(),
+                    gene_symbol: gene.name().to_string(),
+                    hgnc_id: hgnc_xlink.hgnc_id(gene.id().as_u32()).map(str::to_string),
+                    gene_info: None,
+                })
+                .sorted()
+                .dedup()
+                .collect()
+        });
+        HpoTermsResultEntry {
+            term_id: term.id.clone(),
+            name: term.name.clone(),
+            definition: term.definition.clone(),
+            synonyms: Some(term.synonyms.clone()).filter(|synonyms| !synonyms.is_empty()),
+            xrefs: None,
+            genes,
+            local: true,
+            highlights: None,
+        }
+    }
+
+    /// Set the `highlights` of the words of the name search `query` in the name,
+    /// definition, and synonyms, see `Index::highlight`.
+    #[must_use]
+    pub fn with_highlights(self, index: &crate::index::Index, query: &str, stem: bool) -> Self {
+        let highlight = |text: &str| Highlight::new(text, &index.highlight(query, text, stem));
+        Self {
+            highlights: Some(HpoTermsHighlights {
+                name: highlight(&self.name),
+                definition: self.definition.as_deref().map(highlight),
+                synonyms: self
+                    .synonyms
+                    .iter()
+                    .flatten()
+                    .map(|synonym| highlight(synonym))
+                    .collect(),
+            }),
+            ..self
+        }
+    }
+
+    /// Replace the name, definition, and synonyms by the `translation`, if any.
+    ///
+    /// Labels without translation are kept.
+    #[must_use]
+    pub fn with_translation(self, translation: Option<&Translation>) -> Self {
+        let Some(translation) = translation else {
+            return self;
+        };
+        Self {
+            name: translation.name.clone().unwrap_or(self.name),
+            definition: translation.definition.clone().or(self.definition),
+            synonyms: if translation.synonyms.is_empty() {
+                self.synonyms
+            } else {
+                Some(translation.synonyms.clone())
+            },
+            ..self
+        }
+    }
+
+    /// Set the gene information of the genes from `gene_info`, if available.
+    #[must_use]
+    pub fn with_gene_info(self, gene_info: &HashMap<u32, GeneInfo>) -> Self {
+        Self {
+            genes: self.genes.map(|genes| {
+                genes
+                    .into_iter()
+                    .map(|gene| gene.with_gene_info(Some(gene_info)))
+                    .collect()
+            }),
+            ..self
+        }
+    }
 }
 
 /// Container for the result.
@@ -214,6 +442,7 @@ pub struct HpoTermsResult {
     params(HpoTermsQuery),
     responses(
         (status = 200, description = "The query was successful.", body = HpoTermsResult),
+        (status = 400, description = "The term ID or date is malformed.", body = CustomError),
         (status = 500, description = "The server encountered an error.", body = CustomError)
     )
 )]
@@ -221,10 +450,70 @@ pub struct HpoTermsResult {
 async fn handle(
     data: Data<Arc<WebServerData>>,
     _path: Path<()>,
-    query: web::Query<HpoTermsQuery>,
+    query: StrictQuery<HpoTermsQuery>,
+    resolved_from: ResolvedFrom,
+    _timer: ComputeTimer,
 ) -> actix_web::Result<Json<HpoTermsResult>, CustomError> {
+    Ok(Json(compute(&data, query.into_inner(), &resolved_from)?))
+}
+
+/// Search the terms of `query` in `data`, without the `meta` of the response.
+///
+/// # Errors
+///
+/// In the case that the query is invalid or the search fails.
+pub fn compute_blocking(
+    data: &WebServerData,
+    query: HpoTermsQuery,
+) -> Result<HpoTermsResult, anyhow::Error> {
+    compute(data, query, &ResolvedFrom::default()).map_err(|err| anyhow::anyhow!("{}", err.err))
+}
+
+/// Search the terms of `query` in `data`, see `handle`.
+#[allow(clippy::too_many_lines)]
+fn compute(
+    data: &WebServerData,
+    query: HpoTermsQuery,
+    resolved_from: &ResolvedFrom,
+) -> Result<HpoTermsResult, CustomError> {
     let ontology = &data.ontology;
     let mut result: Vec<HpoTermsResultEntry> = Vec::new();
+    let created_after = query.created_after.as_deref().map(check_date).transpose()?;
+    let local_term = query
+        .term_id
+        .as_deref()
+        .and_then(|term_id| data.local_terms.get(term_id.trim()));
+    let term_id = query
+        .term_id
+        .as_deref()
+        .filter(|_| local_term.is_none())
+        .map(|term_id| {
+            crate::common::parse_term_id(term_id)
+                .map(|term_id| super::resolve_alt_id(term_id, data, resolved_from))
+                .map_err(|e| CustomError::invalid_term_id(term_id, &e))
+        })
+        .transpose()?;
+    let propagation = query
+        .annotation_propagation
+        .unwrap_or(data.annotation_propagation);
+    let gene_ids = |term: &HpoTerm| {
+        query.genes.then(|| {
+            data.term_gene_index
+                .gene_ids(term.id(), propagation.is_propagated())
+        })
+    };
+    let local_entry = |term: &LocalTerm| {
+        let gene_ids = query.genes.then(|| {
+            term.parents
+                .iter()
+                .flat_map(|parent| {
+                    data.term_gene_index
+                        .gene_ids(*parent, propagation.is_propagated())
+                })
+                .collect()
+        });
+        HpoTermsResultEntry::from_local_term(term, ontology, gene_ids, &data.hgnc_xlink)
+    };
 
     let field_term_id = data
         .full_text_index
@@ -261,17 +550,15 @@ async fn handle(
         .schema()
         .get_field("xref")
         .expect("field must exist");
+    let field_namespace = data
+        .full_text_index
+        .schema()
+        .get_field("namespace")
+        .expect("field must exist");
 
-    if let Some(term_id) = &query.term_id {
-        let re = regex::Regex::new(r"^hp:\d+$").unwrap();
-        if !re.is_match(&term_id.to_lowercase()) {
-            return Err(CustomError::new(anyhow::anyhow!(
-                "Invalid term ID: {}",
-                term_id
-            )));
-        }
-
-        let term_id = HpoTermId::from(term_id.clone());
+    if let Some(local_term) = local_term {
+        result.push(local_entry(local_term));
+    } else if let Some(term_id) = term_id {
         let term = ontology.hpo(term_id).ok_or_else(|| {
             CustomError::new(anyhow::anyhow!("Term ID {} not found in HPO", term_id))
         })?;
@@ -279,8 +566,8 @@ async fn handle(
             HpoTermsResultEntry::from_term_with_ontology(
                 &term,
                 ontology,
-                query.genes,
-                &data.ncbi_to_hgnc,
+                gene_ids(&term),
+                &data.hgnc_xlink,
                 &data.full_text_index,
                 None,
             )
@@ -288,18 +575,33 @@ async fn handle(
         );
     } else if let Some(name) = &query.name {
         let searcher = data.full_text_index.reader().searcher();
+        let translated_fields = query
+            .lang
+            .as_deref()
+            .and_then(|lang| data.full_text_index.translated_fields(lang));
+        let (field_name, field_def, field_synonym) = if query.stem {
+            data.full_text_index
+                .stemmed_fields()
+                .unwrap_or((field_name, field_def, field_synonym))
+        } else {
+            (field_name, field_def, field_synonym)
+        };
         let query_parser = {
-            let mut query_parser = tantivy::query::QueryParser::for_index(
-                data.full_text_index.index(),
-                vec![
-                    field_term_id,
-                    field_alt_id,
-                    field_name,
-                    field_def,
-                    field_synonym,
-                    field_xref,
-                ],
+            let mut fields = vec![
+                field_term_id,
+                field_alt_id,
+                field_name,
+                field_def,
+                field_synonym,
+                field_xref,
+            ];
+            fields.extend(
+                translated_fields
+                    .iter()
+                    .flat_map(|(name, synonym)| [*name, *synonym]),
             );
+            let mut query_parser =
+                tantivy::query::QueryParser::for_index(data.full_text_index.index(), fields);
             query_parser.set_conjunction_by_default();
             query_parser.set_field_boost(field_name, 3.0);
             query_parser.set_field_boost(field_synonym, 0.8);
@@ -307,6 +609,12 @@ async fn handle(
             query_parser.set_field_fuzzy(field_name, true, 1, true);
             query_parser.set_field_fuzzy(field_def, true, 1, true);
             query_parser.set_field_fuzzy(field_synonym, true, 1, true);
+            if let Some((field_name_lang, field_synonym_lang)) = translated_fields {
+                query_parser.set_field_boost(field_name_lang, 3.0);
+                query_parser.set_field_boost(field_synonym_lang, 0.8);
+                query_parser.set_field_fuzzy(field_name_lang, true, 1, true);
+                query_parser.set_field_fuzzy(field_synonym_lang, true, 1, true);
+            }
             query_parser
         };
         let name = if name.contains(':') {
@@ -317,10 +625,29 @@ async fn handle(
         let index_query = query_parser
             .parse_query(&name)
             .map_err(|e| CustomError::new(anyhow::anyhow!("Error parsing query: {}", e)))?;
+        let index_query = crate::index::with_phrase_boost(
+            data.full_text_index.index(),
+            index_query,
+            &name,
+            [field_name, field_synonym]
+                .into_iter()
+                .chain(
+                    translated_fields
+                        .iter()
+                        .flat_map(|(name, synonym)| [*name, *synonym]),
+                )
+                .collect(),
+        );
+        // Terms not created after the date are removed below, so search all of them.
+        let limit = if created_after.is_some() {
+            usize::try_from(searcher.num_docs()).unwrap_or(usize::MAX)
+        } else {
+            query.max_results
+        };
         let top_docs = searcher
             .search(
                 &index_query,
-                &tantivy::collector::TopDocs::with_limit(query.max_results),
+                &tantivy::collector::TopDocs::with_limit(limit.max(1)),
             )
             .map_err(|e| CustomError::new(anyhow::anyhow!("Error searching index: {}", e)))?;
 
@@ -338,7 +665,16 @@ async fn handle(
                 .as_value()
                 .as_str()
                 .unwrap_or_default();
-            let term_id = HpoTermId::from(term_id.to_string());
+            let namespace = retrieved_doc
+                .get_first(field_namespace)
+                .and_then(|value| value.as_value().as_str());
+            if namespace == Some(crate::index::NAMESPACE_LOCAL) {
+                if let Some(local_term) = data.local_terms.get(term_id) {
+                    result.push(local_entry(local_term));
+                }
+                continue;
+            }
+            let term_id = crate::common::parse_term_id(term_id).map_err(CustomError::new)?;
             let term = ontology.hpo(term_id).ok_or_else(|| {
                 CustomError::new(anyhow::anyhow!("Term ID {} not found in HPO", term_id))
             })?;
@@ -347,30 +683,89 @@ async fn handle(
                 HpoTermsResultEntry::from_term_with_ontology(
                     &term,
                     ontology,
-                    query.genes,
-                    &data.ncbi_to_hgnc,
+                    gene_ids(&term),
+                    &data.hgnc_xlink,
                     &data.full_text_index,
                     Some(&retrieved_doc),
                 )
                 .map_err(|e| CustomError::new(anyhow::anyhow!("Problem parsing term: {}", e)))?,
             );
         }
+    } else if let Some(date) = created_after {
+        for term_id in data
+            .full_text_index
+            .terms_created_after(date)
+            .into_iter()
+            .take(query.max_results)
+        {
+            let Some(term) = ontology.hpo(term_id) else {
+                continue;
+            };
+            result.push(
+                HpoTermsResultEntry::from_term_with_ontology(
+                    &term,
+                    ontology,
+                    gene_ids(&term),
+                    &data.hgnc_xlink,
+                    &data.full_text_index,
+                    None,
+                )
+                .map_err(|e| CustomError::new(anyhow::anyhow!("Problem parsing term: {}", e)))?,
+            );
+        }
     };
 
-    let result = HpoTermsResult {
+    if let Some(date) = created_after {
+        result.retain(|entry| {
+            crate::common::parse_term_id(&entry.term_id)
+                .ok()
+                .and_then(|term_id| data.full_text_index.history(term_id))
+                .is_some_and(|history| history.created_after(date))
+        });
+        result.truncate(query.max_results);
+    }
+
+    if let Some(lang) = &query.lang {
+        result = result
+            .into_iter()
+            .map(|entry| {
+                let translation = data.translations.get(lang, &entry.term_id);
+                entry.with_translation(translation)
+            })
+            .collect();
+    }
+
+    if query.include_gene_info {
+        result = result
+            .into_iter()
+            .map(|entry| entry.with_gene_info(&data.gene_info))
+            .collect();
+    }
+
+    if let Some(name) = query.name.as_ref().filter(|_| query.highlight) {
+        result = result
+            .into_iter()
+            .map(|entry| entry.with_highlights(&data.full_text_index, name, query.stem))
+            .collect();
+    }
+
+    Ok(HpoTermsResult {
         version: Version::new(&data.ontology.hpo_version()),
-        query: query.into_inner(),
+        query: HpoTermsQuery {
+            term_id: term_id
+                .map(|term_id| term_id.to_string())
+                .or_else(|| local_term.map(|local_term| local_term.id.clone())),
+            ..query
+        },
         result,
-    };
-
-    Ok(Json(result))
+    })
 }
 
 #[cfg(test)]
 mod test {
     use std::sync::Arc;
 
-    use crate::server::run::hpo_genes::test::web_server_data;
+    use crate::server::run::hpo_genes::test::{load_web_server_data, web_server_data};
 
     /// Helper function for running a query.
     #[allow(dead_code)]
@@ -390,6 +785,79 @@ mod test {
         Ok(resp)
     }
 
+    #[test]
+    fn from_local_term() {
+        let term = crate::sources::local_terms::LocalTerm {
+            id: "HP:LAB:0001".to_string(),
+            name: "Lab seizure".to_string(),
+            definition: None,
+            synonyms: Vec::new(),
+            parents: vec![hpo::HpoTermId::from_u32(1250)],
+        };
+
+        let entry = super::HpoTermsResultEntry::from_local_term(
+            &term,
+            &hpo::Ontology::default(),
+            None,
+            &crate::sources::hgnc_xlink::HgncXlink::default(),
+        );
+
+        assert_eq!(entry.term_id, "HP:LAB:0001");
+        assert!(entry.local);
+        assert!(entry.synonyms.is_none());
+        assert!(serde_json::to_value(&entry)
+            .expect("must serialize")
+            .get("local")
+            .is_some());
+    }
+
+    #[test]
+    fn highlight() {
+        let highlight = super::Highlight::new("Épilepsie <généralisée>", &[12..26]);
+
+        assert_eq!(highlight.html, "Épilepsie &lt;<em>généralisée</em>&gt;");
+        assert_eq!(
+            highlight.ranges,
+            vec![super::HighlightRange { start: 11, end: 22 }]
+        );
+    }
+
+    #[rstest::rstest]
+    #[case("2023-06-06", true)]
+    #[case("2023-02-30", false)]
+    #[case("2023-6-6", false)]
+    #[case("2023/06/06", false)]
+    #[case("v2023-06-06", false)]
+    fn check_date(#[case] date: &str, #[case] valid: bool) {
+        assert_eq!(super::check_date(date).is_ok(), valid);
+    }
+
+    #[test]
+    fn with_translation() {
+        let entry = super::HpoTermsResultEntry {
+            term_id: "HP:0001250".to_string(),
+            name: "Seizure".to_string(),
+            definition: Some("A seizure is ...".to_string()),
+            synonyms: Some(vec!["Seizures".to_string()]),
+            xrefs: None,
+            genes: None,
+            local: false,
+            highlights: None,
+        };
+        let translation = crate::sources::translations::Translation {
+            name: Some("Krampfanfall".to_string()),
+            definition: None,
+            synonyms: vec!["Anfall".to_string()],
+        };
+
+        let translated = entry.clone().with_translation(Some(&translation));
+
+        assert_eq!(translated.name, "Krampfanfall");
+        assert_eq!(translated.definition.as_deref(), Some("A seizure is ..."));
+        assert_eq!(translated.synonyms, Some(vec!["Anfall".to_string()]));
+        assert_eq!(entry.clone().with_translation(None).name, "Seizure");
+    }
+
     #[rstest::rstest]
     #[actix_web::test]
     async fn hpo_terms_term_id_exact_no_genes(
@@ -473,4 +941,95 @@ mod test {
             .await?
         ))
     }
+
+    #[rstest::rstest]
+    #[actix_web::test]
+    async fn hpo_terms_term_id_alt_id(
+        web_server_data: &Arc<crate::server::run::WebServerData>,
+    ) -> Result<(), anyhow::Error> {
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(actix_web::web::Data::new(web_server_data.clone()))
+                .wrap(actix_web::middleware::from_fn(
+                    crate::server::run::middleware::add_response_meta,
+                ))
+                .service(super::handle),
+        )
+        .await;
+        let req = actix_web::test::TestRequest::get()
+            .uri("/api/v1/hpo/terms?term_id=HP:0002279")
+            .to_request();
+
+        let resp: serde_json::Value = actix_web::test::call_and_read_body_json(&app, req).await;
+
+        // HP:0002279 is an alternative ID of "Seizure".
+        assert_eq!(resp["result"][0]["term_id"], "HP:0001250");
+        assert_eq!(resp["result"][0]["name"], "Seizure");
+        assert_eq!(resp["meta"]["resolved_from"]["HP:0002279"], "HP:0001250");
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn hpo_terms_created_after() -> Result<(), anyhow::Error> {
+        let hpo_doc = fastobo::from_str(
+            "format-version: 1.2\n\n\
+             [Term]\n\
+             id: HP:0001250\n\
+             name: Seizure\n\
+             creation_date: 2021-05-20T12:00:00Z\n\n\
+             [Term]\n\
+             id: HP:0002616\n\
+             name: Aortic root aneurysm\n\
+             creation_date: 2008-02-27T02:20:00Z\n\n\
+             [Term]\n\
+             id: HP:0002617\n\
+             name: Vascular dilatation\n\
+             creation_date: 2023-07-01\n",
+        )?;
+        let data = Arc::new(crate::server::run::WebServerData {
+            full_text_index: crate::index::Index::new(
+                hpo_doc,
+                &crate::sources::translations::Translations::default(),
+            )?,
+            ..load_web_server_data()
+        });
+        let term_ids = |uri: &'static str| {
+            let data = data.clone();
+            async move {
+                run_query(data, uri).await.map(|result| {
+                    result
+                        .result
+                        .into_iter()
+                        .map(|entry| entry.term_id)
+                        .collect::<Vec<_>>()
+                })
+            }
+        };
+
+        assert_eq!(
+            term_ids("/api/v1/hpo/terms?created_after=2020-01-01").await?,
+            vec!["HP:0001250", "HP:0002617"]
+        );
+        assert_eq!(
+            term_ids("/api/v1/hpo/terms?created_after=2021-05-20").await?,
+            vec!["HP:0002617"]
+        );
+        assert_eq!(
+            term_ids("/api/v1/hpo/terms?created_after=2020-01-01&max_results=1").await?,
+            vec!["HP:0001250"]
+        );
+        // Combined with the name search.
+        assert_eq!(
+            term_ids("/api/v1/hpo/terms?created_after=2008-01-01&name=aneurysm").await?,
+            vec!["HP:0002616"]
+        );
+        assert!(
+            term_ids("/api/v1/hpo/terms?created_after=2020-01-01&name=aneurysm")
+                .await?
+                .is_empty()
+        );
+
+        Ok(())
+    }
 }
@@ -0,0 +1,351 @@
+//! Implementation of `/hpo/terms/distance`.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+};
+
+use actix_web::{
+    get,
+    http::StatusCode,
+    web::{Data, Json},
+};
+use hpo::{HpoTermId, Ontology};
+
+use crate::{common::Version, server::run::WebServerData};
+
+use super::{
+    middleware::{ComputeTimer, ResolvedFrom},
+    CustomError, ResultHpoTerm,
+};
+use crate::server::run::strict_query::StrictQuery;
+
+/// Parameters for `handle`.
+///
+/// - `lhs` -- the first term
+/// - `rhs` -- the second term
+#[derive(
+    Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema, utoipa::IntoParams,
+)]
+#[serde(deny_unknown_fields)]
+#[into_params(parameter_in = Query)]
+pub struct HpoTermsDistanceQuery {
+    /// The first term.
+    pub lhs: String,
+    /// The second term.
+    pub rhs: String,
+}
+
+/// Graph distance between two terms.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct HpoTermsDistanceResultEntry {
+    /// The first term in canonical form.
+    pub lhs: String,
+    /// The second term in canonical form.
+    pub rhs: String,
+    /// The number of `is_a` edges on the shortest path, ignoring their direction.
+    pub distance: usize,
+    /// The terms on the shortest path, starting with `lhs` and ending with `rhs`.
+    pub path: Vec<ResultHpoTerm>,
+    /// The lowest common ancestor, i.e., the common ancestor (or the term itself) with the
+    /// fewest edges to both terms.
+    pub lca: ResultHpoTerm,
+    /// The number of edges on the shortest path from the lowest common ancestor to the root.
+    pub lca_depth: usize,
+}
+
+/// Container for the result.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct HpoTermsDistanceResult {
+    /// Version information.
+    pub version: Version,
+    /// The original query.
+    pub query: HpoTermsDistanceQuery,
+    /// The resulting distance.
+    pub result: HpoTermsDistanceResultEntry,
+}
+
+/// Return the shortest path from `lhs` to `rhs` along `is_a` relations in either direction.
+///
+/// Neighbours are visited in ascending order of their term IDs so that the path is
+/// deterministic.  Returns `None` if there is no path.
+pub fn shortest_path(
+    ontology: &Ontology,
+    lhs: HpoTermId,
+    rhs: HpoTermId,
+) -> Option<Vec<HpoTermId>> {
+    let mut previous = HashMap::from([(lhs, lhs)]);
+    let mut queue = VecDeque::from([lhs]);
+    while let Some(current) = queue.pop_front() {
+        if current == rhs {
+            let mut path = vec![rhs];
+            while let Some(last) = path.last().copied().filter(|last| *last != lhs) {
+                path.push(previous[&last]);
+            }
+            path.reverse();
+            return Some(path);
+        }
+        let Some(term) = ontology.hpo(current) else {
+            continue;
+        };
+        let mut neighbours = term
+            .parent_ids()
+            .iter()
+            .chain(term.children_ids().iter())
+            .collect::<Vec<_>>();
+        neighbours.sort();
+        for neighbour in neighbours {
+            if let std::collections::hash_map::Entry::Vacant(entry) = previous.entry(neighbour) {
+                entry.insert(current);
+                queue.push_back(neighbour);
+            }
+        }
+    }
+    None
+}
+
+/// Return the number of edges from `term_id` to each of its ancestors and itself.
+fn ancestor_distances(ontology: &Ontology, term_id: HpoTermId) -> HashMap<HpoTermId, usize> {
+    let mut result = HashMap::from([(term_id, 0)]);
+    let mut queue = VecDeque::from([term_id]);
+    while let Some(current) = queue.pop_front() {
+        let distance = result[&current];
+        if let Some(term) = ontology.hpo(current) {
+            for parent in term.parent_ids() {
+                result.entry(parent).or_insert_with(|| {
+                    queue.push_back(parent);
+                    distance + 1
+                });
+            }
+        }
+    }
+    result
+}
+
+/// Return the number of edges on the shortest path from `term_id` to the root.
+pub fn depth(ontology: &Ontology, term_id: HpoTermId) -> usize {
+    ancestor_distances(ontology, term_id)
+        .into_iter()
+        .filter(|(ancestor, _)| {
+            ontology
+                .hpo(*ancestor)
+                .is_some_and(|term| term.parent_ids().is_empty())
+        })
+        .map(|(_, distance)| distance)
+        .min()
+        .unwrap_or_default()
+}
+
+/// Return the lowest common ancestor of `lhs` and `rhs`.
+///
+/// This is the common ancestor with the fewest edges to both terms, ties are broken by the
+/// larger depth and then the lower term ID.  Returns `None` if the terms have no common
+/// ancestor.
+pub fn lowest_common_ancestor(
+    ontology: &Ontology,
+    lhs: HpoTermId,
+    rhs: HpoTermId,
+) -> Option<HpoTermId> {
+    let lhs_distances = ancestor_distances(ontology, lhs);
+    let rhs_distances = ancestor_distances(ontology, rhs);
+    lhs_distances
+        .iter()
+        .filter_map(|(ancestor, lhs_distance)| {
+            rhs_distances
+                .get(ancestor)
+                .map(|rhs_distance| (*ancestor, lhs_distance + rhs_distance))
+        })
+        .min_by_key(|(ancestor, distance)| {
+            (
+                *distance,
+                std::cmp::Reverse(depth(ontology, *ancestor)),
+                *ancestor,
+            )
+        })
+        .map(|(ancestor, _)| ancestor)
+}
+
+/// Return the name of `term_id` as a result term.
+fn result_term(ontology: &Ontology, term_id: HpoTermId) -> ResultHpoTerm {
+    ResultHpoTerm {
+        term_id: term_id.to_string(),
+        name: ontology
+            .hpo(term_id)
+            .map(|term| term.name().to_string())
+            .unwrap_or_default(),
+    }
+}
+
+/// Query for the graph distance between two terms.
+///
+/// Returns the length of the shortest path between the terms along `is_a` relations in
+/// either direction, the path itself, and the lowest common ancestor with its depth.  These
+/// are cheap structural metrics, e.g., for quality control besides the information
+/// content-based similarity.
+///
+/// # Errors
+///
+/// In the case that there is an error running the server.
+#[allow(clippy::unused_async)]
+#[utoipa::path(
+    get,
+    operation_id = "hpoTermsDistance",
+    params(HpoTermsDistanceQuery),
+    responses(
+        (status = 200, description = "The query was successful.", body = HpoTermsDistanceResult),
+        (status = 400, description = "A term ID is malformed.", body = CustomError),
+        (status = 404, description = "A term could not be found.", body = CustomError),
+        (status = 500, description = "The server encountered an error.", body = CustomError)
+    )
+)]
+#[get("/api/v1/hpo/terms/distance")]
+async fn handle(
+    data: Data<Arc<WebServerData>>,
+    query: StrictQuery<HpoTermsDistanceQuery>,
+    resolved_from: ResolvedFrom,
+    _timer: ComputeTimer,
+) -> actix_web::Result<Json<HpoTermsDistanceResult>, CustomError> {
+    let parse = |value: &str| {
+        let term_id = crate::common::parse_term_id(value)
+            .map(|term_id| super::resolve_alt_id(term_id, &data, &resolved_from))
+            .map_err(|e| CustomError::invalid_term_id(value, &e))?;
+        if data.ontology.hpo(term_id).is_none() {
+            return Err(CustomError::with_status(
+                StatusCode::NOT_FOUND,
+                anyhow::anyhow!("term not found: {}", term_id),
+            ));
+        }
+        Ok(term_id)
+    };
+    let lhs = parse(&query.lhs)?;
+    let rhs = parse(&query.rhs)?;
+
+    let path = shortest_path(&data.ontology, lhs, rhs)
+        .ok_or_else(|| CustomError::new(anyhow::anyhow!("no path from {} to {}", lhs, rhs)))?;
+    let lca = lowest_common_ancestor(&data.ontology, lhs, rhs).ok_or_else(|| {
+        CustomError::new(anyhow::anyhow!("no common ancestor of {} and {}", lhs, rhs))
+    })?;
+
+    Ok(Json(HpoTermsDistanceResult {
+        version: Version::new(&data.ontology.hpo_version()),
+        query: query.into_inner(),
+        result: HpoTermsDistanceResultEntry {
+            lhs: lhs.to_string(),
+            rhs: rhs.to_string(),
+            distance: path.len() - 1,
+            path: path
+                .iter()
+                .map(|term_id| result_term(&data.ontology, *term_id))
+                .collect(),
+            lca: result_term(&data.ontology, lca),
+            lca_depth: depth(&data.ontology, lca),
+        },
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use hpo::{builder::Builder, HpoTermId, Ontology};
+
+    use crate::server::run::hpo_genes::test::web_server_data;
+
+    /// Build a small ontology with the structure
+    ///
+    /// ```text
+    /// HP:0000001
+    /// +-- HP:0000002
+    /// |   +-- HP:0000004
+    /// |   |   +-- HP:0000006
+    /// |   +-- HP:0000005
+    /// +-- HP:0000003
+    ///     +-- HP:0000006
+    /// ```
+    fn ontology() -> Ontology {
+        let mut builder = Builder::new();
+        builder.new_term("All", 1u32);
+        builder.new_term("Left", 2u32);
+        builder.new_term("Right", 3u32);
+        builder.new_term("Left left", 4u32);
+        builder.new_term("Left right", 5u32);
+        builder.new_term("Left left and right", 6u32);
+        let mut builder = builder.terms_complete();
+        builder.add_parent(1u32, 2u32).expect("term must exist");
+        builder.add_parent(1u32, 3u32).expect("term must exist");
+        builder.add_parent(2u32, 4u32).expect("term must exist");
+        builder.add_parent(2u32, 5u32).expect("term must exist");
+        builder.add_parent(4u32, 6u32).expect("term must exist");
+        builder.add_parent(3u32, 6u32).expect("term must exist");
+        builder
+            .connect_all_terms()
+            .calculate_information_content()
+            .expect("could not compute IC")
+            .build_minimal()
+    }
+
+    #[rstest::rstest]
+    #[case(5, 6, vec![5, 2, 4, 6])]
+    #[case(3, 5, vec![3, 1, 2, 5])]
+    #[case(3, 4, vec![3, 6, 4])]
+    #[case(6, 6, vec![6])]
+    fn shortest_path(#[case] lhs: u32, #[case] rhs: u32, #[case] expected: Vec<u32>) {
+        let ontology = ontology();
+
+        let path = super::shortest_path(&ontology, HpoTermId::from(lhs), HpoTermId::from(rhs));
+
+        assert_eq!(
+            path,
+            Some(expected.into_iter().map(HpoTermId::from).collect())
+        );
+    }
+
+    #[rstest::rstest]
+    #[case(5, 6, 2, 1)]
+    #[case(3, 4, 1, 0)]
+    #[case(4, 6, 4, 2)]
+    #[case(6, 6, 6, 2)]
+    fn lowest_common_ancestor(
+        #[case] lhs: u32,
+        #[case] rhs: u32,
+        #[case] expected: u32,
+        #[case] expected_depth: usize,
+    ) {
+        let ontology = ontology();
+
+        let lca =
+            super::lowest_common_ancestor(&ontology, HpoTermId::from(lhs), HpoTermId::from(rhs));
+
+        assert_eq!(lca, Some(HpoTermId::from(expected)));
+        assert_eq!(
+            super::depth(&ontology, HpoTermId::from(expected)),
+            expected_depth
+        );
+    }
+
+    #[rstest::rstest]
+    #[actix_web::test]
+    async fn hpo_terms_distance(
+        web_server_data: &Arc<crate::server::run::WebServerData>,
+    ) -> Result<(), anyhow::Error> {
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(actix_web::web::Data::new(web_server_data.clone()))
+                .service(super::handle),
+        )
+        .await;
+        let req = actix_web::test::TestRequest::get()
+            .uri("/api/v1/hpo/terms/distance?lhs=HP:0001250&rhs=HP:0001166")
+            .to_request();
+        let resp: super::HpoTermsDistanceResult =
+            actix_web::test::call_and_read_body_json(&app, req).await;
+
+        assert_eq!(resp.result.distance + 1, resp.result.path.len());
+        assert_eq!(resp.result.path[0].term_id, "HP:0001250");
+        assert_eq!(resp.result.path[resp.result.distance].term_id, "HP:0001166");
+        assert_eq!(resp.result.lca.term_id, "HP:0000118");
+        assert_eq!(resp.result.lca_depth, 1);
+
+        Ok(())
+    }
+}
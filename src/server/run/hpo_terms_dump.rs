@@ -0,0 +1,224 @@
+//! Implementation of `/hpo/terms/dump`.
+
+use std::{
+    collections::HashMap,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use actix_web::{
+    body::{BodySize, MessageBody},
+    get,
+    web::{Bytes, Data, Path},
+    HttpResponse,
+};
+use hpo::{HpoTerm, HpoTermId};
+
+use crate::server::run::WebServerData;
+
+use super::{middleware::ComputeTimer, CustomError};
+
+/// Number of terms serialized per chunk of the response body.
+const CHUNK_SIZE: usize = 1000;
+
+/// One line of the NDJSON term dump.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct HpoTermsDumpRecord {
+    /// The HPO term's ID.
+    pub term_id: String,
+    /// The HPO term's name.
+    pub name: String,
+    /// The term's definition, if any.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub definition: Option<String>,
+    /// The IDs of the direct parents.
+    pub parents: Vec<String>,
+    /// Number of genes annotated with the term or its descendants.
+    pub gene_count: usize,
+    /// Number of OMIM diseases annotated with the term or its descendants.
+    pub omim_disease_count: usize,
+    /// Number of ORPHA diseases annotated with the term or its descendants.
+    pub orpha_disease_count: usize,
+}
+
+impl HpoTermsDumpRecord {
+    /// Create a record for `term` with the `definitions` by term ID.
+    pub fn from_term(term: &HpoTerm, definitions: &HashMap<String, String>) -> Self {
+        let term_id = term.id().to_string();
+        Self {
+            definition: definitions.get(&term_id).cloned(),
+            name: term.name().to_string(),
+            parents: term.parent_ids().iter().map(|id| id.to_string()).collect(),
+            gene_count: term.gene_ids().len(),
+            omim_disease_count: term.omim_disease_ids().len(),
+            orpha_disease_count: term.orpha_disease_ids().len(),
+            term_id,
+        }
+    }
+}
+
+/// Return the definitions of the terms in the OBO document by term ID.
+fn definitions(hpo_doc: &fastobo::ast::OboDoc) -> HashMap<String, String> {
+    hpo_doc
+        .entities()
+        .iter()
+        .filter_map(fastobo::ast::EntityFrame::as_term)
+        .filter_map(|frame| {
+            frame
+                .clauses()
+                .iter()
+                .find_map(|line| match line.as_inner() {
+                    fastobo::ast::TermClause::Def(def) => Some((
+                        frame.id().as_inner().to_string(),
+                        def.text().as_str().to_string(),
+                    )),
+                    _ => None,
+                })
+        })
+        .collect()
+}
+
+/// Response body that serializes the terms chunk by chunk while it is sent.
+struct DumpBody {
+    /// The server data with the ontology.
+    data: Arc<WebServerData>,
+    /// The definitions by term ID.
+    definitions: HashMap<String, String>,
+    /// The IDs of the terms to dump, sorted.
+    term_ids: Vec<HpoTermId>,
+    /// Index of the next term to serialize.
+    pos: usize,
+}
+
+impl MessageBody for DumpBody {
+    type Error = serde_json::Error;
+
+    fn size(&self) -> BodySize {
+        BodySize::Stream
+    }
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, Self::Error>>> {
+        let this = self.get_mut();
+        if this.pos >= this.term_ids.len() {
+            return Poll::Ready(None);
+        }
+
+        let end = (this.pos + CHUNK_SIZE).min(this.term_ids.len());
+        let mut buf = Vec::new();
+        for term_id in &this.term_ids[this.pos..end] {
+            if let Some(term) = this.data.ontology.hpo(*term_id) {
+                let record = HpoTermsDumpRecord::from_term(&term, &this.definitions);
+                if let Err(e) = serde_json::to_writer(&mut buf, &record) {
+                    return Poll::Ready(Some(Err(e)));
+                }
+                buf.push(b'\n');
+            }
+        }
+        this.pos = end;
+
+        Poll::Ready(Some(Ok(Bytes::from(buf))))
+    }
+}
+
+/// Dump all terms as newline-delimited JSON, one `HpoTermsDumpRecord` per line.
+///
+/// The terms are sorted by ID.  This allows warehouses to ingest the server's exact
+/// view of the ontology without parsing OBO.
+///
+/// # Errors
+///
+/// In the case that there is an error running the server.
+#[utoipa::path(
+    get,
+    operation_id = "hpoTermsDump",
+    responses(
+        (status = 200, description = "One term per line.", body = HpoTermsDumpRecord, content_type = "application/x-ndjson"),
+        (status = 500, description = "The server encountered an error.", body = CustomError),
+        (status = 503, description = "The server is low on memory.", body = CustomError)
+    )
+)]
+#[get("/api/v1/hpo/terms/dump")]
+async fn handle(
+    data: Data<Arc<WebServerData>>,
+    _path: Path<()>,
+    _timer: ComputeTimer,
+) -> actix_web::Result<HttpResponse, CustomError> {
+    let mut term_ids = data
+        .ontology
+        .hpos()
+        .map(|term| term.id())
+        .collect::<Vec<_>>();
+    term_ids.sort();
+
+    let body = DumpBody {
+        definitions: definitions(data.full_text_index.hpo_doc()),
+        data: data.get_ref().clone(),
+        term_ids,
+        pos: 0,
+    };
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/x-ndjson")
+        .body(body))
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use crate::server::run::hpo_genes::test::web_server_data;
+
+    #[test]
+    fn definitions() -> Result<(), anyhow::Error> {
+        let hpo_doc = fastobo::from_str(
+            "format-version: 1.2\n\n\
+             [Term]\n\
+             id: HP:0000001\n\
+             name: All\n\n\
+             [Term]\n\
+             id: HP:0000118\n\
+             name: Phenotypic abnormality\n\
+             def: \"A phenotypic abnormality.\" []\n",
+        )?;
+
+        let definitions = super::definitions(&hpo_doc);
+
+        assert_eq!(definitions.len(), 1);
+        assert_eq!(definitions["HP:0000118"], "A phenotypic abnormality.");
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[actix_web::test]
+    async fn hpo_terms_dump(
+        web_server_data: &Arc<crate::server::run::WebServerData>,
+    ) -> Result<(), anyhow::Error> {
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(actix_web::web::Data::new(web_server_data.clone()))
+                .service(super::handle),
+        )
+        .await;
+        let req = actix_web::test::TestRequest::get()
+            .uri("/api/v1/hpo/terms/dump")
+            .to_request();
+        let resp = actix_web::test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+        let body = actix_web::test::read_body(resp).await;
+        let records = std::str::from_utf8(&body)?
+            .lines()
+            .map(serde_json::from_str::<super::HpoTermsDumpRecord>)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        assert_eq!(records.len(), web_server_data.ontology.len());
+        assert_eq!(records[0].term_id, "HP:0000001");
+        assert!(records[0].parents.is_empty());
+
+        Ok(())
+    }
+}
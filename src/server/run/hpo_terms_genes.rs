@@ -0,0 +1,220 @@
+//! Implementation of `/hpo/terms/genes`.
+
+use std::sync::Arc;
+
+use actix_web::{
+    get,
+    web::{Data, Json, Path},
+};
+use hpo::annotations::AnnotationId as _;
+use rayon::prelude::*;
+
+use crate::{common::Version, server::run::WebServerData};
+
+use super::{
+    middleware::{ComputeTimer, ResolvedFrom},
+    CustomError, ResultGene,
+};
+use crate::server::run::strict_query::StrictQuery;
+
+/// Parameters for `handle`.
+///
+/// - `term_ids` -- set of terms to look up the annotated genes for
+/// - `include_descendants` -- whether to include genes annotated with descendant terms
+/// - `lang` -- language of the term names, falls back to English
+/// - `include_gene_info` -- whether to include `"gene_info"` in the genes
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::IntoParams)]
+#[serde(deny_unknown_fields)]
+#[into_params(parameter_in = Query)]
+pub struct RequestQuery {
+    /// The set of HPO terms to look up the genes for.
+    #[serde(deserialize_with = "super::vec_str_deserialize")]
+    pub term_ids: Vec<String>,
+    /// Whether to include genes annotated with descendants of the terms.
+    #[serde(default = "_default_include_descendants")]
+    pub include_descendants: bool,
+    /// Language of the term names, e.g., `de`; untranslated names are in English.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub lang: Option<String>,
+    /// Whether to include the NCBI gene information.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub include_gene_info: bool,
+}
+
+/// Return default of `RequestQuery::include_descendants`.
+fn _default_include_descendants() -> bool {
+    false
+}
+
+/// Request as sent together with the response.
+///
+/// The difference is that the `term_ids` field is replaced by a vec.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct HpoTermsGenesQuery {
+    /// The set of HPO terms to look up the genes for.
+    pub term_ids: Vec<String>,
+    /// Whether to include genes annotated with descendants of the terms.
+    pub include_descendants: bool,
+    /// Language of the term names.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub lang: Option<String>,
+    /// Whether to include the NCBI gene information.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub include_gene_info: bool,
+}
+
+/// Result entry for `handle`.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct HpoTermsGenesResultEntry {
+    /// The HPO term's ID.
+    pub term_id: String,
+    /// The HPO term's name.
+    pub name: String,
+    /// The genes annotated with the term, sorted by NCBI gene ID.
+    pub genes: Vec<ResultGene>,
+}
+
+/// Container for the result.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct HpoTermsGenesResult {
+    /// Version information.
+    pub version: Version,
+    /// The original query.
+    pub query: HpoTermsGenesQuery,
+    /// One entry for each known term of the query, in the order of the query.
+    pub result: Vec<HpoTermsGenesResultEntry>,
+}
+
+/// Query for the genes annotated with each of the given terms.
+///
+/// With `include_descendants=true`, a gene annotated with "Focal seizure" is also
+/// returned for "Seizure".
+///
+/// # Errors
+///
+/// In the case that there is an error running the server.
+#[utoipa::path(
+    get,
+    operation_id = "hpoTermsGenes",
+    params(RequestQuery),
+    responses(
+        (status = 200, description = "The query was successful.", body = HpoTermsGenesResult),
+        (status = 400, description = "A term ID is malformed.", body = CustomError),
+        (status = 422, description = "The request exceeds a size or complexity limit.", body = CustomError),
+        (status = 500, description = "The server encountered an error.", body = CustomError),
+        (status = 503, description = "The server is low on memory.", body = CustomError)
+    )
+)]
+#[get("/api/v1/hpo/terms/genes")]
+async fn handle(
+    data: Data<Arc<WebServerData>>,
+    _path: Path<()>,
+    query: StrictQuery<RequestQuery>,
+    resolved_from: ResolvedFrom,
+    _timer: ComputeTimer,
+) -> actix_web::Result<Json<HpoTermsGenesResult>, CustomError> {
+    let ontology = &data.ontology;
+    let RequestQuery {
+        term_ids,
+        include_descendants,
+        lang,
+        include_gene_info,
+    } = query.into_inner();
+
+    let term_ids = super::parse_term_ids(&term_ids, &data, &resolved_from)?;
+    let gene_info = include_gene_info.then_some(&data.gene_info);
+
+    let result = term_ids
+        .par_iter()
+        .filter_map(|term_id| ontology.hpo(*term_id))
+        .map(|term| HpoTermsGenesResultEntry {
+            term_id: term.id().to_string(),
+            name: lang
+                .as_ref()
+                .and_then(|lang| data.translations.get(lang, &term.id().to_string()))
+                .and_then(|translation| translation.name.clone())
+                .unwrap_or_else(|| term.name().to_string()),
+            genes: data
+                .term_gene_index
+                .gene_ids(term.id(), include_descendants)
+                .iter()
+                .filter_map(|gene_id| ontology.gene(gene_id))
+                .map(|gene| {
+                    ResultGene {
+                        ncbi_gene_id: gene.id().as_u32(),
+                        gene_symbol: gene.name().to_string(),
+                        hgnc_id: data
+                            .hgnc_xlink
+                            .hgnc_id(gene.id().as_u32())
+                            .map(str::to_string),
+                        gene_info: None,
+                    }
+                    .with_gene_info(gene_info)
+                })
+                .collect(),
+        })
+        .collect();
+
+    Ok(Json(HpoTermsGenesResult {
+        version: Version::new(&data.ontology.hpo_version()),
+        query: HpoTermsGenesQuery {
+            term_ids: term_ids.iter().map(ToString::to_string).collect(),
+            include_descendants,
+            lang,
+            include_gene_info,
+        },
+        result,
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use crate::server::run::hpo_genes::test::web_server_data;
+
+    /// Helper function for running a query.
+    async fn run_query(
+        web_server_data: Arc<crate::server::run::WebServerData>,
+        uri: &str,
+    ) -> Result<super::HpoTermsGenesResult, anyhow::Error> {
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(actix_web::web::Data::new(web_server_data))
+                .service(super::handle),
+        )
+        .await;
+        let req = actix_web::test::TestRequest::get().uri(uri).to_request();
+        let resp: super::HpoTermsGenesResult =
+            actix_web::test::call_and_read_body_json(&app, req).await;
+
+        Ok(resp)
+    }
+
+    #[rstest::rstest]
+    #[actix_web::test]
+    async fn hpo_terms_genes_include_descendants(
+        web_server_data: &Arc<crate::server::run::WebServerData>,
+    ) -> Result<(), anyhow::Error> {
+        let direct = run_query(
+            web_server_data.clone(),
+            "/api/v1/hpo/terms/genes?term_ids=HP:0001250",
+        )
+        .await?;
+        let closure = run_query(
+            web_server_data.clone(),
+            "/api/v1/hpo/terms/genes?term_ids=HP:0001250&include_descendants=true",
+        )
+        .await?;
+
+        assert_eq!(direct.result.len(), 1);
+        assert_eq!(closure.result.len(), 1);
+        assert!(closure.result[0].genes.len() > direct.result[0].genes.len());
+        assert!(direct.result[0]
+            .genes
+            .iter()
+            .all(|gene| closure.result[0].genes.contains(gene)));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,109 @@
+//! Implementation of `/hpo/terms/{term_id}/history`.
+
+use std::sync::Arc;
+
+use actix_web::{
+    get,
+    http::StatusCode,
+    web::{Data, Json, Path},
+};
+
+use crate::{common::Version, index::TermHistory, server::run::WebServerData};
+
+use super::{
+    middleware::{ComputeTimer, ResolvedFrom},
+    CustomError,
+};
+
+/// Container for the result.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct HpoTermsHistoryResult {
+    /// Version information.
+    pub version: Version,
+    /// The term ID in canonical form.
+    pub term_id: String,
+    /// The creation metadata and property values, empty if the OBO has none.
+    pub history: TermHistory,
+}
+
+/// Query for the creation metadata and property values of a term.
+///
+/// The `creation_date`, `created_by`, and `property_value` clauses are taken from the
+/// HPO OBO file, e.g., to see when and by whom a term was added.
+///
+/// # Errors
+///
+/// In the case that there is an error running the server.
+#[allow(clippy::unused_async)]
+#[utoipa::path(
+    get,
+    operation_id = "hpoTermsHistory",
+    params(
+        ("term_id" = String, Path, description = "The HPO term ID, e.g., `HP:0001250`."),
+    ),
+    responses(
+        (status = 200, description = "The query was successful.", body = HpoTermsHistoryResult),
+        (status = 400, description = "The term ID is malformed.", body = CustomError),
+        (status = 404, description = "The term could not be found.", body = CustomError),
+        (status = 500, description = "The server encountered an error.", body = CustomError)
+    )
+)]
+#[get("/api/v1/hpo/terms/{term_id}/history")]
+async fn handle(
+    data: Data<Arc<WebServerData>>,
+    path: Path<String>,
+    resolved_from: ResolvedFrom,
+    _timer: ComputeTimer,
+) -> actix_web::Result<Json<HpoTermsHistoryResult>, CustomError> {
+    let term_id = path.into_inner();
+    let term_id = crate::common::parse_term_id(&term_id)
+        .map(|term_id| super::resolve_alt_id(term_id, &data, &resolved_from))
+        .map_err(|e| CustomError::invalid_term_id(&term_id, &e))?;
+    if data.ontology.hpo(term_id).is_none() {
+        return Err(CustomError::with_status(
+            StatusCode::NOT_FOUND,
+            anyhow::anyhow!("term not found: {}", term_id),
+        ));
+    }
+
+    Ok(Json(HpoTermsHistoryResult {
+        version: Version::new(&data.ontology.hpo_version()),
+        term_id: term_id.to_string(),
+        history: data
+            .full_text_index
+            .history(term_id)
+            .cloned()
+            .unwrap_or_default(),
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use crate::server::run::hpo_genes::test::web_server_data;
+
+    #[rstest::rstest]
+    #[case("/api/v1/hpo/terms/HP:0001250/history", 200)]
+    #[case("/api/v1/hpo/terms/HP:00012x0/history", 400)]
+    #[case("/api/v1/hpo/terms/HP:9999999/history", 404)]
+    #[actix_web::test]
+    async fn hpo_terms_history(
+        #[case] uri: &str,
+        #[case] status: u16,
+        web_server_data: &Arc<crate::server::run::WebServerData>,
+    ) -> Result<(), anyhow::Error> {
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(actix_web::web::Data::new(web_server_data.clone()))
+                .service(super::handle),
+        )
+        .await;
+        let req = actix_web::test::TestRequest::get().uri(uri).to_request();
+        let resp = actix_web::test::call_service(&app, req).await;
+
+        assert_eq!(resp.status().as_u16(), status);
+
+        Ok(())
+    }
+}
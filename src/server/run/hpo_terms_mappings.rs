@@ -0,0 +1,202 @@
+//! Implementation of `/hpo/terms/{term_id}/mappings`.
+
+use std::sync::Arc;
+
+use actix_web::{
+    get,
+    http::StatusCode,
+    web::{Data, Json, Path},
+};
+
+use crate::{
+    common::{MappingSystem, Version},
+    server::run::WebServerData,
+};
+
+use super::{
+    middleware::{ComputeTimer, ResolvedFrom},
+    CustomError,
+};
+use crate::server::run::strict_query::StrictQuery;
+
+/// Parameters for `handle`.
+///
+/// - `system` -- restrict the mappings to one external terminology
+#[derive(
+    Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema, utoipa::IntoParams,
+)]
+#[serde(deny_unknown_fields)]
+#[into_params(parameter_in = Query)]
+pub struct HpoTermsMappingsQuery {
+    /// The external terminology, all if missing.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub system: Option<MappingSystem>,
+}
+
+/// A code of an external terminology that a term is mapped to.
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    serde::Serialize,
+    serde::Deserialize,
+    utoipa::ToSchema,
+)]
+pub struct HpoTermsMappingsResultEntry {
+    /// The external terminology.
+    pub system: MappingSystem,
+    /// The code without the terminology's prefix, e.g., `91175000`.
+    pub code: String,
+    /// The xref as given in the HPO OBO document, e.g., `SNOMEDCT_US:91175000`.
+    pub xref: String,
+}
+
+/// Container for the result.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct HpoTermsMappingsResult {
+    /// Version information.
+    pub version: Version,
+    /// The term ID in canonical form.
+    pub term_id: String,
+    /// The original query.
+    pub query: HpoTermsMappingsQuery,
+    /// The codes the term is mapped to, sorted by terminology and code.
+    pub result: Vec<HpoTermsMappingsResultEntry>,
+}
+
+/// Query for the codes of external terminologies that a term is mapped to.
+///
+/// The mappings are the term's SNOMED CT, UMLS, and `MeSH` xrefs in the HPO OBO document.
+///
+/// # Errors
+///
+/// In the case that there is an error running the server.
+#[allow(clippy::unused_async)]
+#[utoipa::path(
+    get,
+    operation_id = "hpoTermsMappings",
+    params(
+        ("term_id" = String, Path, description = "The HPO term ID, e.g., `HP:0001250`."),
+        HpoTermsMappingsQuery,
+    ),
+    responses(
+        (status = 200, description = "The query was successful.", body = HpoTermsMappingsResult),
+        (status = 400, description = "The term ID is malformed.", body = CustomError),
+        (status = 404, description = "The term could not be found.", body = CustomError),
+        (status = 500, description = "The server encountered an error.", body = CustomError)
+    )
+)]
+#[get("/api/v1/hpo/terms/{term_id}/mappings")]
+async fn handle(
+    data: Data<Arc<WebServerData>>,
+    path: Path<String>,
+    query: StrictQuery<HpoTermsMappingsQuery>,
+    resolved_from: ResolvedFrom,
+    _timer: ComputeTimer,
+) -> actix_web::Result<Json<HpoTermsMappingsResult>, CustomError> {
+    let term_id = path.into_inner();
+    let term_id = crate::common::parse_term_id(&term_id)
+        .map(|term_id| super::resolve_alt_id(term_id, &data, &resolved_from))
+        .map_err(|e| CustomError::invalid_term_id(&term_id, &e))?;
+    if data.ontology.hpo(term_id).is_none() {
+        return Err(CustomError::with_status(
+            StatusCode::NOT_FOUND,
+            anyhow::anyhow!("term not found: {}", term_id),
+        ));
+    }
+
+    let mut result = data
+        .full_text_index
+        .xrefs(term_id)
+        .iter()
+        .filter_map(|xref| {
+            let (system, code) = MappingSystem::split_xref(xref)?;
+            Some(HpoTermsMappingsResultEntry {
+                system,
+                code: code.to_string(),
+                xref: xref.clone(),
+            })
+        })
+        .filter(|entry| query.system.is_none_or(|system| system == entry.system))
+        .collect::<Vec<_>>();
+    result.sort();
+
+    Ok(Json(HpoTermsMappingsResult {
+        version: Version::new(&data.ontology.hpo_version()),
+        term_id: term_id.to_string(),
+        query: query.into_inner(),
+        result,
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use crate::server::run::hpo_genes::test::web_server_data;
+
+    /// Helper function for running a query.
+    pub async fn run_query(
+        web_server_data: Arc<crate::server::run::WebServerData>,
+        uri: &str,
+    ) -> Result<super::HpoTermsMappingsResult, anyhow::Error> {
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(actix_web::web::Data::new(web_server_data))
+                .service(super::handle),
+        )
+        .await;
+        let req = actix_web::test::TestRequest::get().uri(uri).to_request();
+        let resp: super::HpoTermsMappingsResult =
+            actix_web::test::call_and_read_body_json(&app, req).await;
+
+        Ok(resp)
+    }
+
+    #[rstest::rstest]
+    #[actix_web::test]
+    async fn hpo_terms_mappings_umls(
+        web_server_data: &Arc<crate::server::run::WebServerData>,
+    ) -> Result<(), anyhow::Error> {
+        let result = run_query(
+            web_server_data.clone(),
+            "/api/v1/hpo/terms/HP:0001250/mappings?system=umls",
+        )
+        .await?;
+
+        assert_eq!(result.term_id, "HP:0001250");
+        assert!(!result.result.is_empty());
+        assert!(result
+            .result
+            .iter()
+            .all(|entry| entry.system == crate::common::MappingSystem::Umls));
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case("/api/v1/hpo/terms/HP:00012x0/mappings", 400)]
+    #[case("/api/v1/hpo/terms/HP:9999999/mappings", 404)]
+    #[actix_web::test]
+    async fn hpo_terms_mappings_error(
+        #[case] uri: &str,
+        #[case] status: u16,
+        web_server_data: &Arc<crate::server::run::WebServerData>,
+    ) -> Result<(), anyhow::Error> {
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(actix_web::web::Data::new(web_server_data.clone()))
+                .service(super::handle),
+        )
+        .await;
+        let req = actix_web::test::TestRequest::get().uri(uri).to_request();
+        let resp = actix_web::test::call_service(&app, req).await;
+
+        assert_eq!(resp.status().as_u16(), status);
+
+        Ok(())
+    }
+}
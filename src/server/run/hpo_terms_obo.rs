@@ -0,0 +1,118 @@
+//! Implementation of `/hpo/terms/{term_id}/obo`.
+
+use std::sync::Arc;
+
+use actix_web::{
+    get,
+    http::StatusCode,
+    web::{Data, Json, Path},
+};
+
+use crate::{common::Version, server::run::WebServerData};
+
+use super::{
+    middleware::{ComputeTimer, ResolvedFrom},
+    CustomError,
+};
+
+/// Container for the result.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct HpoTermsOboResult {
+    /// Version information.
+    pub version: Version,
+    /// The term ID in canonical form.
+    pub term_id: String,
+    /// The byte offset of the stanza in the OBO file.
+    pub offset: u64,
+    /// The original text of the stanza, from the `[Term]` line to the last clause.
+    pub stanza: String,
+}
+
+/// Query for the original OBO stanza of a term.
+///
+/// The stanza is returned exactly as written in the HPO OBO file loaded at startup, e.g.,
+/// for curators to see what the OBO says or to debug differences to the parsed terms.
+///
+/// # Errors
+///
+/// In the case that there is an error running the server.
+#[allow(clippy::unused_async)]
+#[utoipa::path(
+    get,
+    operation_id = "hpoTermsObo",
+    params(
+        ("term_id" = String, Path, description = "The HPO term ID, e.g., `HP:0001250`."),
+    ),
+    responses(
+        (status = 200, description = "The query was successful.", body = HpoTermsOboResult),
+        (status = 400, description = "The term ID is malformed.", body = CustomError),
+        (status = 404, description = "The term has no stanza in the OBO file.", body = CustomError),
+        (status = 500, description = "The server encountered an error.", body = CustomError)
+    )
+)]
+#[get("/api/v1/hpo/terms/{term_id}/obo")]
+async fn handle(
+    data: Data<Arc<WebServerData>>,
+    path: Path<String>,
+    resolved_from: ResolvedFrom,
+    _timer: ComputeTimer,
+) -> actix_web::Result<Json<HpoTermsOboResult>, CustomError> {
+    let term_id = path.into_inner();
+    let term_id = crate::common::parse_term_id(&term_id)
+        .map(|term_id| super::resolve_alt_id(term_id, &data, &resolved_from))
+        .map_err(|e| CustomError::invalid_term_id(&term_id, &e))?;
+
+    let not_found = || {
+        CustomError::with_status(
+            StatusCode::NOT_FOUND,
+            anyhow::anyhow!("no OBO stanza for term: {}", term_id),
+        )
+    };
+    let offsets = data
+        .full_text_index
+        .stanza_offsets(term_id)
+        .ok_or_else(not_found)?;
+    let stanza = data
+        .full_text_index
+        .stanza(term_id)
+        .ok_or_else(not_found)?
+        .to_string();
+
+    Ok(Json(HpoTermsOboResult {
+        version: Version::new(&data.ontology.hpo_version()),
+        term_id: term_id.to_string(),
+        offset: offsets.start,
+        stanza,
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use crate::server::run::hpo_genes::test::web_server_data;
+
+    #[rstest::rstest]
+    #[case("/api/v1/hpo/terms/HP:0001250/obo", 200)]
+    #[case("/api/v1/hpo/terms/HP:00012x0/obo", 400)]
+    #[case("/api/v1/hpo/terms/HP:9999999/obo", 404)]
+    #[actix_web::test]
+    async fn hpo_terms_obo(
+        #[case] uri: &str,
+        #[case] status: u16,
+        web_server_data: &Arc<crate::server::run::WebServerData>,
+    ) -> Result<(), anyhow::Error> {
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(actix_web::web::Data::new(web_server_data.clone()))
+                .service(super::handle),
+        )
+        .await;
+        let req = actix_web::test::TestRequest::get().uri(uri).to_request();
+        let resp = actix_web::test::call_service(&app, req).await;
+
+        assert_eq!(resp.status().as_u16(), status);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,332 @@
+//! Implementation of `/hpo/terms/{term_id}/paths-to-root`.
+
+use std::sync::Arc;
+
+use actix_web::{
+    get,
+    http::StatusCode,
+    web::{Data, Json, Path},
+};
+use hpo::{HpoTermId, Ontology};
+
+use crate::{
+    algos::ic::IcTables,
+    common::{AnnotationPropagation, IcBasedOn, Version},
+    server::run::WebServerData,
+};
+
+use super::{
+    middleware::{ComputeTimer, ResolvedFrom},
+    CustomError,
+};
+use crate::server::run::strict_query::StrictQuery;
+use crate::server::run::tenants::TenantScope;
+
+/// Parameters for `handle`.
+///
+/// - `max_paths` -- the maximal number of paths to return
+/// - `ic_base` -- what the information content should be based on
+/// - `annotation_propagation` -- whether annotations propagate to ancestors
+#[derive(
+    Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema, utoipa::IntoParams,
+)]
+#[serde(deny_unknown_fields)]
+#[into_params(parameter_in = Query)]
+pub struct HpoTermsPathsQuery {
+    /// The maximal number of paths to return.
+    #[serde(default = "_default_max_paths")]
+    pub max_paths: usize,
+    /// What should information content be based on.
+    #[serde(default = "IcBasedOn::default")]
+    pub ic_base: IcBasedOn,
+    /// Whether annotations propagate to ancestors, server default if missing.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub annotation_propagation: Option<AnnotationPropagation>,
+}
+
+/// Return default of `HpoTermsPathsQuery::max_paths`.
+fn _default_max_paths() -> usize {
+    100
+}
+
+/// A term on a path with its information content.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct HpoTermsPathNode {
+    /// The HPO term ID.
+    pub term_id: String,
+    /// The term name.
+    pub name: String,
+    /// The information content of the term.
+    pub ic: f32,
+}
+
+/// A path from the term to the root along `is_a` relations.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct HpoTermsPath {
+    /// The terms on the path, starting with the term and ending with the root.
+    pub terms: Vec<HpoTermsPathNode>,
+}
+
+/// Container for the result.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct HpoTermsPathsResult {
+    /// Version information.
+    pub version: Version,
+    /// The term ID in canonical form.
+    pub term_id: String,
+    /// The original query.
+    pub query: HpoTermsPathsQuery,
+    /// Whether there are more than `max_paths` paths and the result is truncated.
+    pub truncated: bool,
+    /// The distinct paths to the root, depth-first by ascending parent term IDs.
+    pub result: Vec<HpoTermsPath>,
+}
+
+/// Enumerate the distinct paths from `term_id` to the root, at most `max_paths`.
+///
+/// The paths are enumerated depth-first with the parents in ascending order.  Returns the
+/// paths and whether there are more.
+pub fn paths_to_root(
+    ontology: &Ontology,
+    term_id: HpoTermId,
+    max_paths: usize,
+) -> (Vec<Vec<HpoTermId>>, bool) {
+    let mut paths = Vec::new();
+    let mut stack = vec![vec![term_id]];
+    while let Some(path) = stack.pop() {
+        let parents = path
+            .last()
+            .and_then(|last| ontology.hpo(*last))
+            .map(|term| term.parent_ids().iter().collect::<Vec<_>>())
+            .unwrap_or_default();
+        if parents.is_empty() {
+            if paths.len() == max_paths {
+                return (paths, true);
+            }
+            paths.push(path);
+        } else {
+            for parent in parents.into_iter().rev() {
+                let mut next = path.clone();
+                next.push(parent);
+                stack.push(next);
+            }
+        }
+    }
+    (paths, false)
+}
+
+/// Annotate the terms of `path` with their names and information content.
+fn path_nodes(
+    ontology: &Ontology,
+    ic_tables: &IcTables,
+    ic_based_on: IcBasedOn,
+    propagation: AnnotationPropagation,
+    path: &[HpoTermId],
+) -> HpoTermsPath {
+    HpoTermsPath {
+        terms: path
+            .iter()
+            .filter_map(|term_id| ontology.hpo(*term_id))
+            .map(|term| HpoTermsPathNode {
+                term_id: term.id().to_string(),
+                name: term.name().to_string(),
+                ic: ic_tables.term_ic(&term, ic_based_on, propagation),
+            })
+            .collect(),
+    }
+}
+
+/// Query for the distinct paths from a term to the root of the ontology.
+///
+/// Each term on a path is annotated with its information content, e.g., to draw the
+/// ontology context of a finding.  As terms can have several parents, the number of paths
+/// is limited by `max_paths`.
+///
+/// # Errors
+///
+/// In the case that there is an error running the server.
+#[allow(clippy::unused_async)]
+#[utoipa::path(
+    get,
+    operation_id = "hpoTermsPathsToRoot",
+    params(
+        ("term_id" = String, Path, description = "The HPO term ID, e.g., `HP:0001250`."),
+        HpoTermsPathsQuery,
+    ),
+    responses(
+        (status = 200, description = "The query was successful.", body = HpoTermsPathsResult),
+        (status = 400, description = "The term ID is malformed or the requested information content is not available.", body = CustomError),
+        (status = 404, description = "The term could not be found.", body = CustomError),
+        (status = 422, description = "The request exceeds a size or complexity limit.", body = CustomError),
+        (status = 500, description = "The server encountered an error.", body = CustomError)
+    )
+)]
+#[get("/api/v1/hpo/terms/{term_id}/paths-to-root")]
+async fn handle(
+    data: Data<Arc<WebServerData>>,
+    tenant: TenantScope,
+    path: Path<String>,
+    query: StrictQuery<HpoTermsPathsQuery>,
+    resolved_from: ResolvedFrom,
+    _timer: ComputeTimer,
+) -> actix_web::Result<Json<HpoTermsPathsResult>, CustomError> {
+    let ic_tables = tenant.ic_tables(&data);
+    ic_tables
+        .ensure_available(query.ic_base)
+        .map_err(|e| CustomError::with_status(StatusCode::BAD_REQUEST, e))?;
+    data.limits.check_batch(query.max_paths)?;
+
+    let term_id = path.into_inner();
+    let term_id = crate::common::parse_term_id(&term_id)
+        .map(|term_id| super::resolve_alt_id(term_id, &data, &resolved_from))
+        .map_err(|e| CustomError::invalid_term_id(&term_id, &e))?;
+    if data.ontology.hpo(term_id).is_none() {
+        return Err(CustomError::with_status(
+            StatusCode::NOT_FOUND,
+            anyhow::anyhow!("term not found: {}", term_id),
+        ));
+    }
+
+    let propagation = query
+        .annotation_propagation
+        .unwrap_or(data.annotation_propagation);
+    let (paths, truncated) = paths_to_root(&data.ontology, term_id, query.max_paths);
+    let result = paths
+        .iter()
+        .map(|path| path_nodes(&data.ontology, ic_tables, query.ic_base, propagation, path))
+        .collect();
+
+    Ok(Json(HpoTermsPathsResult {
+        version: Version::new(&data.ontology.hpo_version()),
+        term_id: term_id.to_string(),
+        query: query.into_inner(),
+        truncated,
+        result,
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use hpo::{annotations::GeneId, builder::Builder, HpoTermId, Ontology};
+
+    use crate::server::run::hpo_genes::test::web_server_data;
+    use crate::{
+        algos::ic::IcTables,
+        common::{AnnotationPropagation, IcBasedOn},
+    };
+
+    /// Build a small ontology with the structure
+    ///
+    /// ```text
+    /// HP:0000001
+    /// +-- HP:0000002
+    /// |   +-- HP:0000004
+    /// |       +-- HP:0000005
+    /// +-- HP:0000003
+    ///     +-- HP:0000004
+    /// ```
+    ///
+    /// with a gene on `HP:0000005` and one on `HP:0000003`.
+    fn diamond_ontology() -> Ontology {
+        let mut builder = Builder::new();
+        builder.new_term("All", 1u32);
+        builder.new_term("Left", 2u32);
+        builder.new_term("Right", 3u32);
+        builder.new_term("Both", 4u32);
+        builder.new_term("Below both", 5u32);
+        let mut builder = builder.terms_complete();
+        builder.add_parent(1u32, 2u32).expect("term must exist");
+        builder.add_parent(1u32, 3u32).expect("term must exist");
+        builder.add_parent(2u32, 4u32).expect("term must exist");
+        builder.add_parent(3u32, 4u32).expect("term must exist");
+        builder.add_parent(4u32, 5u32).expect("term must exist");
+        let mut builder = builder.connect_all_terms();
+        builder
+            .annotate_gene(GeneId::from(1), "GENE1", 5u32.into())
+            .expect("term must exist");
+        builder
+            .annotate_gene(GeneId::from(2), "GENE2", 3u32.into())
+            .expect("term must exist");
+        builder
+            .calculate_information_content()
+            .expect("could not compute IC")
+            .build_minimal()
+    }
+
+    #[test]
+    fn paths_to_root() {
+        let ontology = diamond_ontology();
+
+        let (paths, truncated) = super::paths_to_root(&ontology, HpoTermId::from(5u32), 10);
+        assert!(!truncated);
+        assert_eq!(
+            paths,
+            vec![
+                [5u32, 4, 2, 1].map(HpoTermId::from).to_vec(),
+                [5u32, 4, 3, 1].map(HpoTermId::from).to_vec(),
+            ]
+        );
+
+        let (paths, truncated) = super::paths_to_root(&ontology, HpoTermId::from(5u32), 1);
+        assert!(truncated);
+        assert_eq!(paths.len(), 1);
+
+        let (paths, truncated) = super::paths_to_root(&ontology, HpoTermId::from(1u32), 10);
+        assert!(!truncated);
+        assert_eq!(paths, vec![vec![HpoTermId::from(1u32)]]);
+    }
+
+    #[test]
+    fn path_nodes() {
+        let ontology = diamond_ontology();
+        let ic_tables = IcTables::new(&ontology);
+
+        let path = super::path_nodes(
+            &ontology,
+            &ic_tables,
+            IcBasedOn::Gene,
+            AnnotationPropagation::Ancestors,
+            &[5u32, 4, 3, 1].map(HpoTermId::from),
+        );
+
+        assert_eq!(
+            path.terms
+                .iter()
+                .map(|node| node.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["Below both", "Both", "Right", "All"]
+        );
+        // One of the two genes is below `HP:0000004`, both below `HP:0000003` and the root.
+        assert!((path.terms[1].ic - 2f32.ln()).abs() < 1e-6);
+        assert!(path.terms[2].ic.abs() < 1e-6);
+        assert!(path.terms[3].ic.abs() < 1e-6);
+    }
+
+    #[rstest::rstest]
+    #[actix_web::test]
+    async fn hpo_terms_paths_to_root(
+        web_server_data: &Arc<crate::server::run::WebServerData>,
+    ) -> Result<(), anyhow::Error> {
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(actix_web::web::Data::new(web_server_data.clone()))
+                .service(super::handle),
+        )
+        .await;
+        let req = actix_web::test::TestRequest::get()
+            .uri("/api/v1/hpo/terms/HP:0001250/paths-to-root?max_paths=5")
+            .to_request();
+        let resp: super::HpoTermsPathsResult =
+            actix_web::test::call_and_read_body_json(&app, req).await;
+
+        assert!(!resp.result.is_empty() && resp.result.len() <= 5);
+        for path in &resp.result {
+            assert_eq!(path.terms[0].term_id, "HP:0001250");
+            assert_eq!(path.terms[path.terms.len() - 1].term_id, "HP:0000001");
+        }
+
+        Ok(())
+    }
+}
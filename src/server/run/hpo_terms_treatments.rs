@@ -0,0 +1,144 @@
+//! Implementation of `/hpo/terms/{term_id}/treatments`.
+
+use std::sync::Arc;
+
+use actix_web::{
+    get,
+    http::StatusCode,
+    web::{Data, Json, Path},
+};
+
+use crate::{
+    common::Version,
+    server::run::WebServerData,
+    sources::treatments::{Treatment, Treatments},
+};
+
+use super::{
+    middleware::{ComputeTimer, ResolvedFrom},
+    CustomError,
+};
+use crate::server::run::strict_query::StrictQuery;
+
+/// Parameters for `handle`.
+///
+/// - `predicate` -- restrict the medical actions to one relation, e.g., `TREATS`
+#[derive(
+    Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema, utoipa::IntoParams,
+)]
+#[serde(deny_unknown_fields)]
+#[into_params(parameter_in = Query)]
+pub struct HpoTermsTreatmentsQuery {
+    /// The relation of the actions to the phenotype, case-insensitive, all if missing.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub predicate: Option<String>,
+}
+
+/// Container for the result.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct HpoTermsTreatmentsResult {
+    /// Version information.
+    pub version: Version,
+    /// The term ID in canonical form.
+    pub term_id: String,
+    /// The original query.
+    pub query: HpoTermsTreatmentsQuery,
+    /// The medical actions, sorted by MAXO term ID and predicate.
+    pub result: Vec<Treatment>,
+}
+
+/// Query for the medical actions relevant to a phenotype.
+///
+/// The actions are the MAXO annotations of the term loaded with
+/// `--path-maxo-annotations`, e.g., to show actionable follow-ups alongside the gene
+/// prioritization.  The result is empty if no annotations are loaded.
+///
+/// # Errors
+///
+/// In the case that there is an error running the server.
+#[allow(clippy::unused_async)]
+#[utoipa::path(
+    get,
+    operation_id = "hpoTermsTreatments",
+    params(
+        ("term_id" = String, Path, description = "The HPO term ID, e.g., `HP:0002616`."),
+        HpoTermsTreatmentsQuery,
+    ),
+    responses(
+        (status = 200, description = "The query was successful.", body = HpoTermsTreatmentsResult),
+        (status = 400, description = "The term ID is malformed.", body = CustomError),
+        (status = 404, description = "The term could not be found.", body = CustomError),
+        (status = 500, description = "The server encountered an error.", body = CustomError)
+    )
+)]
+#[get("/api/v1/hpo/terms/{term_id}/treatments")]
+async fn handle(
+    data: Data<Arc<WebServerData>>,
+    path: Path<String>,
+    query: StrictQuery<HpoTermsTreatmentsQuery>,
+    resolved_from: ResolvedFrom,
+    _timer: ComputeTimer,
+) -> actix_web::Result<Json<HpoTermsTreatmentsResult>, CustomError> {
+    let term_id = path.into_inner();
+    let term_id = crate::common::parse_term_id(&term_id)
+        .map(|term_id| super::resolve_alt_id(term_id, &data, &resolved_from))
+        .map_err(|e| CustomError::invalid_term_id(&term_id, &e))?;
+    if data.ontology.hpo(term_id).is_none() {
+        return Err(CustomError::with_status(
+            StatusCode::NOT_FOUND,
+            anyhow::anyhow!("term not found: {}", term_id),
+        ));
+    }
+
+    let result = data
+        .sources
+        .get::<Treatments>()
+        .map_or(&[][..], |treatments| treatments.get(term_id))
+        .iter()
+        .filter(|treatment| {
+            query
+                .predicate
+                .as_ref()
+                .is_none_or(|predicate| predicate.eq_ignore_ascii_case(&treatment.predicate))
+        })
+        .cloned()
+        .collect();
+
+    Ok(Json(HpoTermsTreatmentsResult {
+        version: Version::new(&data.ontology.hpo_version()),
+        term_id: term_id.to_string(),
+        query: query.into_inner(),
+        result,
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use crate::server::run::hpo_genes::test::web_server_data;
+
+    #[rstest::rstest]
+    #[case("/api/v1/hpo/terms/HP:0001250/treatments", 200)]
+    #[case("/api/v1/hpo/terms/HP:00012x0/treatments", 400)]
+    #[case("/api/v1/hpo/terms/HP:9999999/treatments", 404)]
+    #[actix_web::test]
+    async fn hpo_terms_treatments(
+        #[case] uri: &str,
+        #[case] status: u16,
+        web_server_data: &Arc<crate::server::run::WebServerData>,
+    ) -> Result<(), anyhow::Error> {
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(actix_web::web::Data::new(web_server_data.clone()))
+                .service(super::handle),
+        )
+        .await;
+        let req = actix_web::test::TestRequest::get().uri(uri).to_request();
+        let resp = actix_web::test::call_service(&app, req).await;
+
+        assert_eq!(resp.status().as_u16(), status);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,224 @@
+//! Limits protecting the server from pathological requests.
+
+use actix_web::http::StatusCode;
+
+use super::CustomError;
+
+/// The limit that a request exceeds.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    derive_more::Display,
+    serde::Serialize,
+    serde::Deserialize,
+    utoipa::ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum LimitReason {
+    /// The request body is too large.
+    #[display("payload_too_large")]
+    PayloadTooLarge,
+    /// A set of terms is too large.
+    #[display("too_many_terms")]
+    TooManyTerms,
+    /// A set of genes, diseases, or relatives is too large.
+    #[display("batch_too_large")]
+    BatchTooLarge,
+    /// The estimated number of term comparisons is too large.
+    #[display("too_complex")]
+    TooComplex,
+}
+
+/// Machine-readable description of an exceeded limit.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize, utoipa::ToSchema,
+)]
+pub struct LimitViolation {
+    /// The limit that was exceeded.
+    pub reason: LimitReason,
+    /// The configured limit.
+    pub limit: u64,
+    /// The size of the request, if known.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub actual: Option<u64>,
+}
+
+/// Limits on the size and complexity of requests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// Maximal size of a request body in bytes.
+    pub max_body_bytes: usize,
+    /// Maximal number of terms in a set of terms.
+    pub max_terms: usize,
+    /// Maximal number of genes, diseases, or relatives in a request.
+    pub max_batch_size: usize,
+    /// Maximal estimated number of pairwise term comparisons of a request.
+    pub max_complexity: u64,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_body_bytes: 2 * 1024 * 1024,
+            max_terms: 1_000,
+            max_batch_size: 10_000,
+            max_complexity: 100_000_000,
+        }
+    }
+}
+
+impl Limits {
+    /// Construct from the command line arguments of the server.
+    pub fn from_args(args: &super::Args) -> Self {
+        Self {
+            max_body_bytes: args.max_body_bytes,
+            max_terms: args.max_terms,
+            max_batch_size: args.max_batch_size,
+            max_complexity: args.max_complexity,
+        }
+    }
+
+    /// Check the number of terms in a set of terms.
+    pub(super) fn check_terms(&self, n_terms: usize) -> Result<(), CustomError> {
+        check(
+            LimitReason::TooManyTerms,
+            self.max_terms as u64,
+            n_terms as u64,
+        )
+    }
+
+    /// Check the number of genes, diseases, or relatives in a request.
+    pub(super) fn check_batch(&self, n_records: usize) -> Result<(), CustomError> {
+        check(
+            LimitReason::BatchTooLarge,
+            self.max_batch_size as u64,
+            n_records as u64,
+        )
+    }
+
+    /// Check the estimated number of pairwise term comparisons, `lhs * rhs` for sets of
+    /// `lhs` and `rhs` terms in total.
+    pub(super) fn check_complexity(&self, lhs: usize, rhs: usize) -> Result<(), CustomError> {
+        check(
+            LimitReason::TooComplex,
+            self.max_complexity,
+            (lhs as u64).saturating_mul(rhs as u64),
+        )
+    }
+}
+
+/// Return a 422 error for `reason` if `actual` exceeds `limit`.
+fn check(reason: LimitReason, limit: u64, actual: u64) -> Result<(), CustomError> {
+    if actual <= limit {
+        Ok(())
+    } else {
+        Err(CustomError::limit_exceeded(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            LimitViolation {
+                reason,
+                limit,
+                actual: Some(actual),
+            },
+        ))
+    }
+}
+
+/// Convert errors of the JSON body extractor, reporting too large bodies with 413.
+pub(super) fn json_error(
+    err: actix_web::error::JsonPayloadError,
+    _req: &actix_web::HttpRequest,
+) -> actix_web::Error {
+    use actix_web::error::JsonPayloadError;
+
+    let (limit, actual) = match err {
+        JsonPayloadError::OverflowKnownLength { length, limit } => (limit, Some(length)),
+        JsonPayloadError::Overflow { limit } => (limit, None),
+        err => return err.into(),
+    };
+    CustomError::limit_exceeded(
+        StatusCode::PAYLOAD_TOO_LARGE,
+        LimitViolation {
+            reason: LimitReason::PayloadTooLarge,
+            limit: limit as u64,
+            actual: actual.map(|actual| actual as u64),
+        },
+    )
+    .into()
+}
+
+#[cfg(test)]
+mod test {
+    use actix_web::{http::StatusCode, ResponseError as _};
+
+    use super::{LimitReason, Limits};
+
+    #[test]
+    fn checks() {
+        let limits = Limits {
+            max_body_bytes: 100,
+            max_terms: 2,
+            max_batch_size: 3,
+            max_complexity: 10,
+        };
+
+        assert!(limits.check_terms(2).is_ok());
+        assert!(limits.check_batch(3).is_ok());
+        assert!(limits.check_complexity(2, 5).is_ok());
+
+        let err = limits.check_terms(3).expect_err("too many terms");
+        assert_eq!(err.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+        let limit = err.limit.expect("limit must be reported");
+        assert_eq!(limit.reason, LimitReason::TooManyTerms);
+        assert_eq!((limit.limit, limit.actual), (2, Some(3)));
+
+        assert!(limits.check_batch(4).is_err());
+        let err = limits
+            .check_complexity(usize::MAX, 2)
+            .expect_err("too complex");
+        assert_eq!(err.limit.and_then(|limit| limit.actual), Some(u64::MAX));
+    }
+
+    #[actix_web::test]
+    async fn json_error() {
+        async fn echo(body: actix_web::web::Json<serde_json::Value>) -> String {
+            body.to_string()
+        }
+
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(
+                    actix_web::web::JsonConfig::default()
+                        .limit(16)
+                        .error_handler(super::json_error),
+                )
+                .route("/", actix_web::web::post().to(echo)),
+        )
+        .await;
+        let post = |body: &'static str| {
+            actix_web::test::TestRequest::post()
+                .uri("/")
+                .insert_header(("content-type", "application/json"))
+                .insert_header(("content-length", body.len()))
+                .set_payload(body)
+                .to_request()
+        };
+
+        let resp = actix_web::test::call_service(&app, post(r#"{"terms": ["HP:0000001"]}"#)).await;
+
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let body: serde_json::Value = actix_web::test::read_body_json(resp).await;
+        assert_eq!(
+            body["limit"],
+            serde_json::json!({"reason": "payload_too_large", "limit": 16, "actual": 25})
+        );
+
+        // Other errors of the extractor are passed through.
+        let resp = actix_web::test::call_service(&app, post("{")).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let resp = actix_web::test::call_service(&app, post("{}")).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+}
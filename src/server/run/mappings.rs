@@ -0,0 +1,158 @@
+//! Implementation of `/mappings/{system}/{code}`.
+
+use std::sync::Arc;
+
+use actix_web::{
+    get,
+    web::{Data, Json, Path},
+};
+
+use crate::{
+    common::{MappingSystem, Version},
+    server::run::WebServerData,
+};
+
+use super::{middleware::ComputeTimer, CustomError, ResultHpoTerm};
+
+/// The code of an external terminology as sent together with the response.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct MappingsQuery {
+    /// The external terminology.
+    pub system: MappingSystem,
+    /// The code without the terminology's prefix, e.g., `91175000`.
+    pub code: String,
+}
+
+/// Container for the result.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct MappingsResult {
+    /// Version information.
+    pub version: Version,
+    /// The original query.
+    pub query: MappingsQuery,
+    /// The HPO terms with the code as xref, sorted by term ID.
+    pub result: Vec<ResultHpoTerm>,
+}
+
+/// Translate the code of an external terminology into HPO terms.
+///
+/// The terms are those whose xrefs in the HPO OBO document contain the code, e.g.,
+/// `SNOMEDCT_US:91175000` for the SNOMED CT code `91175000`.  Codes without any
+/// mapped term yield an empty result.
+///
+/// # Errors
+///
+/// In the case that there is an error running the server.
+#[allow(clippy::unused_async)]
+#[utoipa::path(
+    get,
+    operation_id = "mappings",
+    params(
+        ("system" = MappingSystem, Path, description = "The external terminology."),
+        ("code" = String, Path, description = "The code, optionally with the terminology's xref prefix."),
+    ),
+    responses(
+        (status = 200, description = "The query was successful.", body = MappingsResult),
+        (status = 404, description = "The terminology is unknown.", body = CustomError),
+        (status = 500, description = "The server encountered an error.", body = CustomError)
+    )
+)]
+#[get("/api/v1/mappings/{system}/{code}")]
+async fn handle(
+    data: Data<Arc<WebServerData>>,
+    path: Path<(MappingSystem, String)>,
+    _timer: ComputeTimer,
+) -> actix_web::Result<Json<MappingsResult>, CustomError> {
+    let (system, code) = path.into_inner();
+    let code = system.strip_prefix(code.trim()).to_string();
+
+    let mut term_ids = system
+        .prefixes()
+        .iter()
+        .flat_map(|prefix| {
+            data.full_text_index
+                .terms_with_xref(&format!("{prefix}:{code}"))
+        })
+        .copied()
+        .collect::<Vec<_>>();
+    term_ids.sort();
+    term_ids.dedup();
+    let result = term_ids
+        .into_iter()
+        .filter_map(|term_id| data.ontology.hpo(term_id))
+        .map(|term| ResultHpoTerm {
+            term_id: term.id().to_string(),
+            name: term.name().to_string(),
+        })
+        .collect();
+
+    Ok(Json(MappingsResult {
+        version: Version::new(&data.ontology.hpo_version()),
+        query: MappingsQuery { system, code },
+        result,
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use crate::server::run::hpo_genes::test::web_server_data;
+
+    /// Helper function for running a query.
+    pub async fn run_query(
+        web_server_data: Arc<crate::server::run::WebServerData>,
+        uri: &str,
+    ) -> Result<super::MappingsResult, anyhow::Error> {
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(actix_web::web::Data::new(web_server_data))
+                .service(super::handle),
+        )
+        .await;
+        let req = actix_web::test::TestRequest::get().uri(uri).to_request();
+        let resp: super::MappingsResult = actix_web::test::call_and_read_body_json(&app, req).await;
+
+        Ok(resp)
+    }
+
+    #[rstest::rstest]
+    #[case("/api/v1/mappings/umls/C0036572")]
+    #[case("/api/v1/mappings/umls/UMLS:C0036572")]
+    #[actix_web::test]
+    async fn mappings_umls(
+        #[case] uri: &str,
+        web_server_data: &Arc<crate::server::run::WebServerData>,
+    ) -> Result<(), anyhow::Error> {
+        let result = run_query(web_server_data.clone(), uri).await?;
+
+        assert_eq!(result.query.code, "C0036572");
+        assert!(result
+            .result
+            .iter()
+            .any(|term| term.term_id == "HP:0001250"));
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[actix_web::test]
+    async fn mappings_unknown_system(
+        web_server_data: &Arc<crate::server::run::WebServerData>,
+    ) -> Result<(), anyhow::Error> {
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(actix_web::web::Data::new(web_server_data.clone()))
+                .service(super::handle),
+        )
+        .await;
+        let req = actix_web::test::TestRequest::get()
+            .uri("/api/v1/mappings/icd10/G40")
+            .to_request();
+        let resp = actix_web::test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
+
+        Ok(())
+    }
+}
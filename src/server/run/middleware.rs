@@ -0,0 +1,1131 @@
+//! Middleware functions applied to all routes of the server.
+
+use std::{
+    cell::RefCell,
+    collections::BTreeMap,
+    convert::Infallible,
+    future::{ready, Ready},
+    rc::Rc,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use actix_web::{
+    body::{BodySize, BoxBody, MessageBody},
+    dev::{Payload, ServiceRequest, ServiceResponse},
+    http::{
+        header::{HeaderValue, CONTENT_TYPE, RETRY_AFTER},
+        Method, StatusCode,
+    },
+    middleware::Next,
+    web::{self, Bytes, Data},
+    FromRequest, HttpMessage as _, HttpRequest, HttpResponse, ResponseError as _,
+};
+use strum::IntoEnumIterator as _;
+use utoipa::openapi::{
+    path::{ParameterBuilder, ParameterIn},
+    schema::Schema,
+    Ref, RefOr, Required, Response,
+};
+
+use super::{routes::Route, tenants::Tenants, CustomError, WebServerData};
+
+/// The settings of the middleware in generic ontology mode, which has no `WebServerData`.
+#[derive(Debug, Default)]
+pub struct GenericSettings {
+    /// The version of the loaded ontology, reported as its release.
+    pub release: String,
+    /// Requests taking longer are logged as a warning, if set.
+    pub slow_request_threshold: Option<Duration>,
+    /// Memory budget in bytes, batch requests are rejected close to it, if set.
+    pub max_memory_bytes: Option<u64>,
+    /// The tenants served below `/t/{tenant}`, empty if not configured.
+    pub tenants: Tenants,
+}
+
+/// The server settings that the middleware depends on, from the app data of the HPO or
+/// the generic ontology mode.
+pub(super) enum Settings {
+    /// The HPO mode.
+    Hpo(Data<Arc<WebServerData>>),
+    /// The generic ontology mode.
+    Generic(Data<GenericSettings>),
+}
+
+impl Settings {
+    /// Return the settings from the app data of `req`, if any.
+    pub(super) fn of(req: &ServiceRequest) -> Option<Self> {
+        req.app_data::<Data<Arc<WebServerData>>>()
+            .cloned()
+            .map(Self::Hpo)
+            .or_else(|| {
+                req.app_data::<Data<GenericSettings>>()
+                    .cloned()
+                    .map(Self::Generic)
+            })
+    }
+
+    /// Return the release of the loaded ontology.
+    fn release(&self) -> String {
+        match self {
+            Self::Hpo(data) => data.ontology.hpo_version(),
+            Self::Generic(settings) => settings.release.clone(),
+        }
+    }
+
+    /// Return the build date of the annotations, if known.
+    fn annotation_date(&self) -> Option<String> {
+        match self {
+            Self::Hpo(data) => data.annotation_date.clone(),
+            Self::Generic(_) => None,
+        }
+    }
+
+    /// Return the threshold for logging slow requests, if set.
+    fn slow_request_threshold(&self) -> Option<Duration> {
+        match self {
+            Self::Hpo(data) => data.slow_request_threshold,
+            Self::Generic(settings) => settings.slow_request_threshold,
+        }
+    }
+
+    /// Return the memory budget in bytes, if set.
+    fn max_memory_bytes(&self) -> Option<u64> {
+        match self {
+            Self::Hpo(data) => data.max_memory_bytes,
+            Self::Generic(settings) => settings.max_memory_bytes,
+        }
+    }
+
+    /// Return the configured tenants.
+    pub(super) fn tenants(&self) -> &Tenants {
+        match self {
+            Self::Hpo(data) => &data.tenants,
+            Self::Generic(settings) => &settings.tenants,
+        }
+    }
+}
+
+/// Name of the header for pinning the expected HPO release.
+pub const EXPECTED_RELEASE_HEADER: &str = "X-Expected-HPO-Release";
+
+/// Query parameters that are interpreted by the middleware on all routes.
+#[derive(Debug, Default, serde::Deserialize)]
+struct GlobalQuery {
+    /// The expected HPO release.
+    hpo_release: Option<String>,
+    /// Whether to add the per-stage timings to the response metadata.
+    debug_timings: Option<bool>,
+}
+
+/// Body of the response when the expected HPO release does not match.
+#[derive(Debug, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct ReleaseMismatch {
+    /// The error message.
+    pub err: String,
+    /// The HPO release expected by the client.
+    pub expected: String,
+    /// The HPO release loaded by the server.
+    pub actual: String,
+}
+
+/// Return the HPO release the client expects, if any.
+///
+/// The header takes precedence over the `hpo_release` query parameter.
+fn expected_release(req: &ServiceRequest) -> Option<String> {
+    req.headers()
+        .get(EXPECTED_RELEASE_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+        .or_else(|| {
+            web::Query::<GlobalQuery>::from_query(req.query_string())
+                .ok()
+                .and_then(|query| query.into_inner().hpo_release)
+        })
+}
+
+/// Answer HEAD requests like GET requests, e.g., from monitoring tools.
+///
+/// The handlers only match GET, so the method is rewritten before routing.  The HTTP
+/// layer still knows that the request was HEAD and sends the headers of the response,
+/// including its `Content-Length`, without the body.
+///
+/// # Errors
+///
+/// In the case that the wrapped service fails.
+pub async fn answer_head(
+    mut req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    if req.method() == Method::HEAD {
+        req.head_mut().method = Method::GET;
+    }
+    next.call(req).await
+}
+
+/// Reject requests with 409 when the expected HPO release differs from the loaded one.
+///
+/// # Errors
+///
+/// In the case that the wrapped service fails.
+pub async fn check_expected_release(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, actix_web::Error> {
+    if let (Some(expected), Some(settings)) = (expected_release(&req), Settings::of(&req)) {
+        let actual = settings.release();
+        if expected != actual {
+            let response = HttpResponse::Conflict().json(ReleaseMismatch {
+                err: format!("expected HPO release {expected} but server has {actual}"),
+                expected,
+                actual,
+            });
+            return Ok(req.into_response(response));
+        }
+    }
+
+    Ok(next.call(req).await?.map_into_boxed_body())
+}
+
+/// Fraction of the memory budget above which batch requests are rejected.
+const LOAD_SHEDDING_RATIO: f64 = 0.9;
+
+/// Parse the resident set size in bytes from the contents of `/proc/self/status`.
+fn parse_vm_rss(status: &str) -> Option<u64> {
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    let kb = line
+        .trim_start_matches("VmRSS:")
+        .trim()
+        .trim_end_matches("kB")
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+    Some(kb * 1024)
+}
+
+/// Return the resident set size of the server process in bytes, if available.
+///
+/// Only supported on Linux.
+fn resident_memory_bytes() -> Option<u64> {
+    parse_vm_rss(&std::fs::read_to_string("/proc/self/status").ok()?)
+}
+
+/// Reject batch requests with 503 when the memory use is close to the budget.
+///
+/// The batch routes are those of `Route::is_batch`.  Simple lookups are always passed on
+/// so that the server stays responsive.
+///
+/// # Errors
+///
+/// In the case that the wrapped service fails.
+pub async fn shed_load(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, actix_web::Error> {
+    let is_batch = Route::iter().any(|route| route.is_batch() && route.matches(req.path()));
+    let max_memory_bytes = Settings::of(&req).and_then(|settings| settings.max_memory_bytes());
+    if let (true, Some(max_memory_bytes)) = (is_batch, max_memory_bytes) {
+        if let Some(used) = resident_memory_bytes() {
+            #[allow(clippy::cast_precision_loss)]
+            if used as f64 >= max_memory_bytes as f64 * LOAD_SHEDDING_RATIO {
+                tracing::warn!(used, max_memory_bytes, "shedding load");
+                let mut response = CustomError::with_status(
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    anyhow::anyhow!("server is low on memory, retry later"),
+                )
+                .error_response();
+                response
+                    .headers_mut()
+                    .insert(RETRY_AFTER, HeaderValue::from_static("5"));
+                return Ok(req.into_response(response));
+            }
+        }
+    }
+
+    Ok(next.call(req).await?.map_into_boxed_body())
+}
+
+/// Instants at which the handler started and finished computing the response.
+#[derive(Debug, Default)]
+struct ComputeInstants {
+    /// When the handler arguments had been extracted.
+    started: Option<Instant>,
+    /// The stages of the computation marked by the handler with the instants they ended.
+    stages: Vec<(&'static str, Instant)>,
+    /// When the handler returned.
+    finished: Option<Instant>,
+}
+
+/// Compute instants shared between the middleware and `ComputeTimer`.
+type SharedComputeInstants = Rc<RefCell<ComputeInstants>>;
+
+/// Extractor that records when a handler starts and finishes computing.
+///
+/// Must be the last argument of the handler so that it is extracted after the query
+/// has been parsed.  It is dropped when the handler returns, i.e., before the response
+/// is serialized.  The default records nothing, e.g., for computing results outside of the
+/// server.
+#[derive(Debug, Default)]
+pub struct ComputeTimer(Option<SharedComputeInstants>);
+
+impl FromRequest for ComputeTimer {
+    type Error = Infallible;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        let instants = req.extensions().get::<SharedComputeInstants>().cloned();
+        if let Some(instants) = &instants {
+            instants.borrow_mut().started = Some(Instant::now());
+        }
+        ready(Ok(Self(instants)))
+    }
+}
+
+impl ComputeTimer {
+    /// Mark the end of the computation stage `name`, e.g., `scoring`.
+    ///
+    /// The stage starts when the previous stage ended or, for the first stage, when the
+    /// handler started.
+    pub fn stage(&self, name: &'static str) {
+        if let Some(instants) = &self.0 {
+            instants.borrow_mut().stages.push((name, Instant::now()));
+        }
+    }
+}
+
+impl Drop for ComputeTimer {
+    fn drop(&mut self) {
+        if let Some(instants) = &self.0 {
+            instants.borrow_mut().finished = Some(Instant::now());
+        }
+    }
+}
+
+/// Return the query string with decoded parameters sorted by name.
+fn normalize_query(query_string: &str) -> String {
+    let mut params = web::Query::<Vec<(String, String)>>::from_query(query_string)
+        .map(web::Query::into_inner)
+        .unwrap_or_default();
+    params.sort();
+    params
+        .iter()
+        .map(|(key, value)| format!("{key}={value}"))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+/// Log requests taking longer than the configured threshold as a warning.
+///
+/// The warning contains the normalized query and, for handlers using `ComputeTimer`,
+/// the time spent parsing the request, computing the result, and serializing the response.
+///
+/// # Errors
+///
+/// In the case that the wrapped service fails.
+pub async fn log_slow_requests(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, actix_web::Error> {
+    let Some(threshold) = Settings::of(&req).and_then(|settings| settings.slow_request_threshold())
+    else {
+        return Ok(next.call(req).await?.map_into_boxed_body());
+    };
+
+    let start = Instant::now();
+    let instants = SharedComputeInstants::default();
+    req.extensions_mut().insert(instants.clone());
+    let path = req.path().to_string();
+    let query = normalize_query(req.query_string());
+
+    let res = next.call(req).await?;
+    let total = start.elapsed();
+    if total >= threshold {
+        let status = res.status().as_u16();
+        let total_ms = total.as_millis();
+        if let ComputeInstants {
+            started: Some(started),
+            finished: Some(finished),
+            ..
+        } = *instants.borrow()
+        {
+            let parse_ms = started.duration_since(start).as_millis();
+            let compute_ms = finished.duration_since(started).as_millis();
+            let serialize_ms = total
+                .saturating_sub(finished.duration_since(start))
+                .as_millis();
+            tracing::warn!(
+                path,
+                query,
+                status,
+                total_ms,
+                parse_ms,
+                compute_ms,
+                serialize_ms,
+                "slow request"
+            );
+        } else {
+            tracing::warn!(path, query, status, total_ms, "slow request");
+        }
+    }
+
+    Ok(res.map_into_boxed_body())
+}
+
+/// Metadata added to all result responses for reproducibility statements.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct ResponseMeta {
+    /// The HPO release loaded by the server, the version of the ontology in generic
+    /// ontology mode.
+    pub hpo_release: String,
+    /// Build date of the HPO annotations, if known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub annotation_date: Option<String>,
+    /// Version of the `viguno` server.
+    pub server_version: String,
+    /// Time spent handling the request in milliseconds.
+    pub compute_time_ms: f64,
+    /// Whether the result was served from a cache.
+    pub cache_hit: bool,
+    /// Seed of the random queries the result was computed with, if any, e.g., for the
+    /// p-values.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    /// Query parameters not given by the client, with the applied default values.
+    #[schema(value_type = Object)]
+    pub applied_defaults: BTreeMap<String, serde_json::Value>,
+    /// Alternative IDs of merged terms from the query with the primary IDs they were
+    /// resolved to.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub resolved_from: BTreeMap<String, String>,
+    /// The changes the server made to the terms of the query, in the order of the query.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub query_normalization: Vec<QueryNormalization>,
+    /// Where the server spent the time handling the request, with `debug_timings=true`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub debug_timings: Option<DebugTimings>,
+}
+
+/// Time spent in a stage of the computation.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct StageTiming {
+    /// The name of the stage, e.g., `ontology_lookup` or `scoring`.
+    pub stage: String,
+    /// Time spent in the stage in milliseconds.
+    pub ms: f64,
+}
+
+/// Breakdown of the time spent handling a request.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct DebugTimings {
+    /// Time spent deserializing the request in milliseconds.
+    pub deserialize_ms: f64,
+    /// Time spent computing the result in milliseconds.
+    pub compute_ms: f64,
+    /// The stages of the computation in the order they ran, if the route marks any.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stages: Vec<StageTiming>,
+    /// Time spent serializing the result in milliseconds.
+    pub serialize_ms: f64,
+}
+
+impl DebugTimings {
+    /// Compute the timings from the `instants` of a request that started at `start` and
+    /// whose response was serialized at `serialized`.
+    ///
+    /// Returns `None` if the handler does not use `ComputeTimer`.
+    fn new(start: Instant, instants: &ComputeInstants, serialized: Instant) -> Option<Self> {
+        let ms = |from: Instant, to: Instant| to.duration_since(from).as_secs_f64() * 1000.0;
+        let (started, finished) = (instants.started?, instants.finished?);
+        let mut previous = started;
+        let stages = instants
+            .stages
+            .iter()
+            .map(|(stage, ended)| {
+                let timing = StageTiming {
+                    stage: (*stage).to_string(),
+                    ms: ms(previous, *ended),
+                };
+                previous = *ended;
+                timing
+            })
+            .collect();
+        Some(Self {
+            deserialize_ms: ms(start, started),
+            compute_ms: ms(started, finished),
+            stages,
+            serialize_ms: ms(finished, serialized),
+        })
+    }
+}
+
+/// The kind of a change to a term of the query.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize, utoipa::ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryNormalizationAction {
+    /// The alternative ID of a merged term was replaced by the primary ID.
+    AltIdMapped,
+    /// A local term was replaced by its nearest HPO terms.
+    LocalTermMapped,
+    /// An obsolete term was replaced by the term it was replaced by in the ontology.
+    ObsoleteReplaced,
+    /// An obsolete term without replacement was dropped.
+    ObsoleteDropped,
+    /// A term unknown to the ontology was dropped.
+    UnknownDropped,
+    /// A term given more than once was removed.
+    DuplicateRemoved,
+}
+
+/// A change to a term of the query, e.g., for warning the user about dropped terms.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct QueryNormalization {
+    /// The kind of the change.
+    pub action: QueryNormalizationAction,
+    /// The term as given in the query.
+    pub term_id: String,
+    /// The terms that the term was replaced by, if any.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub replaced_by: Vec<String>,
+}
+
+/// Marker in the response extensions of results that were served from a cache, inserted
+/// by the handlers, e.g., when the simulated score distributions were already cached.
+#[derive(Debug, Clone, Copy)]
+pub struct CacheHit;
+
+/// The seed of the random queries a result was computed with, in the response extensions.
+#[derive(Debug, Clone, Copy)]
+pub struct RandomSeed(pub u64);
+
+/// The resolved term IDs and changes to the query recorded by `ResolvedFrom`.
+#[derive(Debug, Default)]
+struct Resolutions {
+    /// Resolved IDs for the `resolved_from` field of the response metadata.
+    resolved_from: BTreeMap<String, String>,
+    /// Changes for the `query_normalization` field of the response metadata.
+    query_normalization: Vec<QueryNormalization>,
+}
+
+/// Resolutions shared between `add_response_meta` and `ResolvedFrom`.
+type SharedResolvedFrom = Rc<RefCell<Resolutions>>;
+
+/// Extractor for recording alternative term IDs that were resolved to primary IDs and
+/// the other changes to the terms of the query.
+///
+/// The recorded IDs are reported in the `resolved_from` field and the changes in the
+/// `query_normalization` field of the response metadata.  The default records nothing,
+/// e.g., for computing results outside of the server.
+#[derive(Debug, Default)]
+pub struct ResolvedFrom(Option<SharedResolvedFrom>);
+
+impl ResolvedFrom {
+    /// Record that `alt_id` from the query was resolved to `primary_id`.
+    pub fn record(&self, alt_id: &hpo::HpoTermId, primary_id: &hpo::HpoTermId) {
+        if let Some(resolved_from) = &self.0 {
+            resolved_from
+                .borrow_mut()
+                .resolved_from
+                .insert(alt_id.to_string(), primary_id.to_string());
+        }
+        self.record_change(
+            QueryNormalizationAction::AltIdMapped,
+            &alt_id.to_string(),
+            &[*primary_id],
+        );
+    }
+
+    /// Record that the local term `local_id` from the query stands for `term_ids`.
+    pub fn record_local(&self, local_id: &str, term_ids: &[hpo::HpoTermId]) {
+        if let Some(resolved_from) = &self.0 {
+            resolved_from.borrow_mut().resolved_from.insert(
+                local_id.to_string(),
+                itertools::Itertools::join(&mut term_ids.iter(), ","),
+            );
+        }
+        self.record_change(
+            QueryNormalizationAction::LocalTermMapped,
+            local_id,
+            term_ids,
+        );
+    }
+
+    /// Record that the term `term_id` from the query was changed by `action` into
+    /// `replaced_by`.
+    pub fn record_change(
+        &self,
+        action: QueryNormalizationAction,
+        term_id: &str,
+        replaced_by: &[hpo::HpoTermId],
+    ) {
+        if let Some(resolved_from) = &self.0 {
+            resolved_from
+                .borrow_mut()
+                .query_normalization
+                .push(QueryNormalization {
+                    action,
+                    term_id: term_id.to_string(),
+                    replaced_by: replaced_by.iter().map(ToString::to_string).collect(),
+                });
+        }
+    }
+}
+
+impl FromRequest for ResolvedFrom {
+    type Error = Infallible;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        ready(Ok(Self(
+            req.extensions().get::<SharedResolvedFrom>().cloned(),
+        )))
+    }
+}
+
+/// Whether a JSON object has a `result` field, read without building the values.
+struct HasResult(bool);
+
+impl<'de> serde::Deserialize<'de> for HasResult {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct Visitor;
+
+        impl<'de> serde::de::Visitor<'de> for Visitor {
+            type Value = HasResult;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str("a JSON object")
+            }
+
+            fn visit_map<A: serde::de::MapAccess<'de>>(
+                self,
+                mut map: A,
+            ) -> Result<Self::Value, A::Error> {
+                let mut has_result = false;
+                while let Some(key) = map.next_key::<std::borrow::Cow<'de, str>>()? {
+                    has_result |= key == "result";
+                    map.next_value::<serde::de::IgnoredAny>()?;
+                }
+                Ok(HasResult(has_result))
+            }
+        }
+
+        deserializer.deserialize_map(Visitor)
+    }
+}
+
+/// Add a `meta` object with a `ResponseMeta` to all JSON result responses.
+///
+/// Result responses are JSON objects with a `result` field.  Other responses are passed
+/// through unchanged, i.e., the errors, the non-JSON responses, and the responses whose
+/// shape is fixed by an external specification (`/api/v1/mme/match`, `/api/v1/beacon`)
+/// or by monitoring (`/readyz`), and the streamed responses.  The applied defaults are
+/// recorded by `StrictQuery`, i.e., the defaults configured for the route and the defaults
+/// of the query struct.  The `meta` object is appended to the body as is, so the order of
+/// the fields is kept.  With `debug_timings=true`, the timings of the handler are added as
+/// well.
+///
+/// # Errors
+///
+/// In the case that the wrapped service fails or the response cannot be read.
+pub async fn add_response_meta(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, actix_web::Error> {
+    let start = Instant::now();
+    let settings = Settings::of(&req);
+
+    let resolved_from = SharedResolvedFrom::default();
+    req.extensions_mut().insert(resolved_from.clone());
+    let applied_defaults = super::defaults::SharedAppliedDefaults::default();
+    req.extensions_mut().insert(applied_defaults.clone());
+    let debug_timings = web::Query::<GlobalQuery>::from_query(req.query_string())
+        .ok()
+        .and_then(|query| query.into_inner().debug_timings)
+        .unwrap_or_default();
+    // Share the instants with `log_slow_requests`, if it is enabled.
+    let instants = debug_timings.then(|| {
+        let existing = req.extensions().get::<SharedComputeInstants>().cloned();
+        existing.unwrap_or_else(|| {
+            let instants = SharedComputeInstants::default();
+            req.extensions_mut().insert(instants.clone());
+            instants
+        })
+    });
+
+    let res = next.call(req).await?.map_into_boxed_body();
+    let serialized = Instant::now();
+    let is_json = res
+        .headers()
+        .get(CONTENT_TYPE)
+        .is_some_and(|value| value.as_bytes().starts_with(b"application/json"));
+    let is_streamed = matches!(res.response().body().size(), BodySize::Stream);
+    let Some(settings) = settings.filter(|_| is_json && !is_streamed) else {
+        return Ok(res);
+    };
+    let cache_hit = res.response().extensions().get::<CacheHit>().is_some();
+    let seed = res
+        .response()
+        .extensions()
+        .get::<RandomSeed>()
+        .map(|seed| seed.0);
+
+    let (req, res) = res.into_parts();
+    let (res, body) = res.into_parts();
+    let bytes = actix_web::body::to_bytes(body)
+        .await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    let body = match serde_json::from_slice::<HasResult>(&bytes) {
+        Ok(HasResult(true)) => {
+            let resolutions = resolved_from.take();
+            let meta = ResponseMeta {
+                hpo_release: settings.release(),
+                annotation_date: settings.annotation_date(),
+                server_version: crate::common::version().to_string(),
+                compute_time_ms: start.elapsed().as_secs_f64() * 1000.0,
+                cache_hit,
+                seed,
+                applied_defaults: applied_defaults.take(),
+                resolved_from: resolutions.resolved_from,
+                query_normalization: resolutions.query_normalization,
+                debug_timings: instants
+                    .and_then(|instants| DebugTimings::new(start, &instants.borrow(), serialized)),
+            };
+            // The body is a non-empty object, so `meta` goes before its closing brace.
+            let end = bytes
+                .iter()
+                .rposition(|b| *b == b'}')
+                .unwrap_or(bytes.len());
+            let mut body = Vec::with_capacity(bytes.len() + 512);
+            body.extend_from_slice(&bytes[..end]);
+            body.extend_from_slice(b",\"meta\":");
+            serde_json::to_writer(&mut body, &meta)?;
+            body.extend_from_slice(&bytes[end..]);
+            Bytes::from(body)
+        }
+        _ => bytes,
+    };
+
+    Ok(ServiceResponse::new(req, res.set_body(BoxBody::new(body))))
+}
+
+/// Document the `meta` object on all result schemas of the `OpenAPI` specification.
+///
+/// Result schemas are the object schemas with a `result` property.
+pub struct ResponseMetaDoc;
+
+impl utoipa::Modify for ResponseMetaDoc {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let Some(components) = openapi.components.as_mut() else {
+            return;
+        };
+        for schema in components.schemas.values_mut() {
+            if let RefOr::T(Schema::Object(object)) = schema {
+                if object.properties.contains_key("result") {
+                    object.properties.insert(
+                        "meta".to_string(),
+                        Ref::from_schema_name("ResponseMeta").into(),
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Document the release pinning on all routes of the `OpenAPI` specification.
+pub struct ExpectedReleaseDoc;
+
+impl utoipa::Modify for ExpectedReleaseDoc {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        for path_item in openapi.paths.paths.values_mut() {
+            let parameters = path_item.parameters.get_or_insert_with(Vec::new);
+            parameters.push(
+                ParameterBuilder::new()
+                    .name(EXPECTED_RELEASE_HEADER)
+                    .parameter_in(ParameterIn::Header)
+                    .required(Required::False)
+                    .description(Some("Fail with 409 if the server's HPO release differs."))
+                    .schema(Some(string_schema()))
+                    .build(),
+            );
+            parameters.push(
+                ParameterBuilder::new()
+                    .name("hpo_release")
+                    .parameter_in(ParameterIn::Query)
+                    .required(Required::False)
+                    .description(Some("Alternative to the `X-Expected-HPO-Release` header."))
+                    .schema(Some(string_schema()))
+                    .build(),
+            );
+            for operation in [
+                &mut path_item.get,
+                &mut path_item.post,
+                &mut path_item.put,
+                &mut path_item.delete,
+            ]
+            .into_iter()
+            .flatten()
+            {
+                operation.responses.responses.insert(
+                    "409".to_string(),
+                    Response::new("The server's HPO release differs from the expected one.").into(),
+                );
+            }
+        }
+    }
+}
+
+/// Document the `debug_timings` parameter on all routes of the `OpenAPI` specification.
+pub struct DebugTimingsDoc;
+
+impl utoipa::Modify for DebugTimingsDoc {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        for path_item in openapi.paths.paths.values_mut() {
+            path_item.parameters.get_or_insert_with(Vec::new).push(
+                ParameterBuilder::new()
+                    .name("debug_timings")
+                    .parameter_in(ParameterIn::Query)
+                    .required(Required::False)
+                    .description(Some(
+                        "Add the time spent per stage of the request to `meta.debug_timings`.",
+                    ))
+                    .schema(Some(boolean_schema()))
+                    .build(),
+            );
+        }
+    }
+}
+
+/// Return an inline `OpenAPI` schema for string values.
+fn string_schema() -> utoipa::openapi::RefOr<utoipa::openapi::Schema> {
+    utoipa::openapi::ObjectBuilder::new()
+        .schema_type(utoipa::openapi::schema::Type::String)
+        .into()
+}
+
+/// Return an inline `OpenAPI` schema for boolean values.
+fn boolean_schema() -> utoipa::openapi::RefOr<utoipa::openapi::Schema> {
+    utoipa::openapi::ObjectBuilder::new()
+        .schema_type(utoipa::openapi::schema::Type::Boolean)
+        .into()
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use actix_web::http::StatusCode;
+
+    use crate::server::run::hpo_genes::test::web_server_data;
+
+    /// Helper function for running a query against `/api/v1/hpo/genes` with the middleware.
+    async fn run_query(
+        web_server_data: Arc<crate::server::run::WebServerData>,
+        req: actix_web::test::TestRequest,
+    ) -> actix_web::dev::ServiceResponse {
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(actix_web::web::Data::new(web_server_data))
+                .wrap(actix_web::middleware::from_fn(
+                    super::check_expected_release,
+                ))
+                .service(crate::server::run::hpo_genes::handle),
+        )
+        .await;
+        actix_web::test::call_service(&app, req.to_request()).await
+    }
+
+    #[rstest::rstest]
+    #[actix_web::test]
+    async fn response_meta(
+        web_server_data: &Arc<crate::server::run::WebServerData>,
+    ) -> Result<(), anyhow::Error> {
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(actix_web::web::Data::new(web_server_data.clone()))
+                .wrap(actix_web::middleware::from_fn(super::add_response_meta))
+                .service(crate::server::run::hpo_genes::handle),
+        )
+        .await;
+        let req = actix_web::test::TestRequest::get()
+            .uri("/api/v1/hpo/genes?gene_id=2348")
+            .to_request();
+        let resp: serde_json::Value = actix_web::test::call_and_read_body_json(&app, req).await;
+        let meta: super::ResponseMeta = serde_json::from_value(resp["meta"].clone())?;
+
+        assert_eq!(meta.hpo_release, web_server_data.ontology.hpo_version());
+        assert_eq!(meta.server_version, "0.0.0");
+        assert!(!meta.cache_hit);
+        assert_eq!(meta.applied_defaults["max_results"], 100);
+        assert!(!meta.applied_defaults.contains_key("gene_id"));
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn response_meta_shapes() -> Result<(), anyhow::Error> {
+        let settings = super::GenericSettings {
+            release: "2024-01-01".to_string(),
+            slow_request_threshold: None,
+            max_memory_bytes: None,
+            tenants: super::Tenants::default(),
+        };
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(actix_web::web::Data::new(settings))
+                .wrap(actix_web::middleware::from_fn(super::add_response_meta))
+                .route(
+                    "/result",
+                    actix_web::web::get().to(|| async {
+                        let mut response =
+                            actix_web::HttpResponse::Ok().json(serde_json::json!({"result": []}));
+                        response.extensions_mut().insert(super::CacheHit);
+                        response.extensions_mut().insert(super::RandomSeed(7));
+                        response
+                    }),
+                )
+                .route(
+                    "/results",
+                    actix_web::web::get().to(|| async {
+                        actix_web::HttpResponse::Ok().json(serde_json::json!({"results": []}))
+                    }),
+                )
+                .route(
+                    "/ordered",
+                    actix_web::web::get().to(|| async {
+                        actix_web::HttpResponse::Ok()
+                            .content_type("application/json")
+                            .body(r#"{"version":1,"result":[] }"#)
+                    }),
+                ),
+        )
+        .await;
+
+        let req = actix_web::test::TestRequest::get()
+            .uri("/result")
+            .to_request();
+        let resp: serde_json::Value = actix_web::test::call_and_read_body_json(&app, req).await;
+        let meta: super::ResponseMeta = serde_json::from_value(resp["meta"].clone())?;
+        assert_eq!(meta.hpo_release, "2024-01-01");
+        assert!(meta.cache_hit);
+        assert_eq!(meta.seed, Some(7));
+
+        let req = actix_web::test::TestRequest::get()
+            .uri("/results")
+            .to_request();
+        let resp: serde_json::Value = actix_web::test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp, serde_json::json!({"results": []}));
+
+        let req = actix_web::test::TestRequest::get()
+            .uri("/ordered")
+            .to_request();
+        let body = actix_web::test::call_and_read_body(&app, req).await;
+        let body = std::str::from_utf8(&body)?;
+        assert!(body.starts_with(r#"{"version":1,"result":[] ,"meta":{"#));
+        assert!(body.ends_with('}'));
+        serde_json::from_str::<serde_json::Value>(body)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn resolved_from_records_changes() {
+        let shared = super::SharedResolvedFrom::default();
+        let resolved_from = super::ResolvedFrom(Some(shared.clone()));
+
+        resolved_from.record(&hpo::HpoTermId::from(1u32), &hpo::HpoTermId::from(2u32));
+        resolved_from.record_change(
+            super::QueryNormalizationAction::DuplicateRemoved,
+            "HP:0000002",
+            &[],
+        );
+
+        let resolutions = shared.take();
+        assert_eq!(resolutions.resolved_from["HP:0000001"], "HP:0000002");
+        assert_eq!(
+            resolutions.query_normalization,
+            vec![
+                super::QueryNormalization {
+                    action: super::QueryNormalizationAction::AltIdMapped,
+                    term_id: "HP:0000001".to_string(),
+                    replaced_by: vec!["HP:0000002".to_string()],
+                },
+                super::QueryNormalization {
+                    action: super::QueryNormalizationAction::DuplicateRemoved,
+                    term_id: "HP:0000002".to_string(),
+                    replaced_by: Vec::new(),
+                },
+            ]
+        );
+    }
+
+    #[rstest::rstest]
+    #[actix_web::test]
+    async fn query_normalization(
+        web_server_data: &Arc<crate::server::run::WebServerData>,
+    ) -> Result<(), anyhow::Error> {
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(actix_web::web::Data::new(web_server_data.clone()))
+                .wrap(actix_web::middleware::from_fn(super::add_response_meta))
+                .service(crate::server::run::hpo_sim::term_gene::handle),
+        )
+        .await;
+        let req = actix_web::test::TestRequest::get()
+            .uri("/api/v1/hpo/sim/term-gene?terms=HP:0001166,HP:0001166,HP:9999999&gene_symbols=FBN1")
+            .to_request();
+        let resp: serde_json::Value = actix_web::test::call_and_read_body_json(&app, req).await;
+        let meta: super::ResponseMeta = serde_json::from_value(resp["meta"].clone())?;
+
+        assert_eq!(meta.applied_defaults["method"], "phenomizer");
+        assert!(!meta.applied_defaults.contains_key("genes"));
+        assert!(!meta.applied_defaults.contains_key("gene_symbols"));
+        assert_eq!(
+            meta.query_normalization
+                .iter()
+                .map(|change| (change.action, change.term_id.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    super::QueryNormalizationAction::DuplicateRemoved,
+                    "HP:0001166"
+                ),
+                (
+                    super::QueryNormalizationAction::UnknownDropped,
+                    "HP:9999999"
+                ),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn debug_timings() {
+        let start = std::time::Instant::now();
+        let ms = std::time::Duration::from_millis;
+        let instants = super::ComputeInstants {
+            started: Some(start + ms(1)),
+            stages: vec![
+                ("ontology_lookup", start + ms(3)),
+                ("scoring", start + ms(7)),
+            ],
+            finished: Some(start + ms(8)),
+        };
+
+        let timings =
+            super::DebugTimings::new(start, &instants, start + ms(10)).expect("must have timings");
+        assert!((timings.deserialize_ms - 1.0).abs() < 1e-6);
+        assert!((timings.compute_ms - 7.0).abs() < 1e-6);
+        assert_eq!(
+            timings
+                .stages
+                .iter()
+                .map(|stage| stage.stage.as_str())
+                .collect::<Vec<_>>(),
+            vec!["ontology_lookup", "scoring"]
+        );
+        assert!(timings
+            .stages
+            .iter()
+            .zip([2.0, 4.0])
+            .all(|(stage, ms)| (stage.ms - ms).abs() < 1e-6));
+        assert!((timings.serialize_ms - 2.0).abs() < 1e-6);
+
+        assert_eq!(
+            super::DebugTimings::new(start, &super::ComputeInstants::default(), start),
+            None
+        );
+    }
+
+    #[rstest::rstest]
+    #[actix_web::test]
+    async fn response_meta_debug_timings(
+        web_server_data: &Arc<crate::server::run::WebServerData>,
+    ) -> Result<(), anyhow::Error> {
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(actix_web::web::Data::new(web_server_data.clone()))
+                .wrap(actix_web::middleware::from_fn(super::add_response_meta))
+                .service(crate::server::run::hpo_sim::term_gene::handle),
+        )
+        .await;
+        let uri = "/api/v1/hpo/sim/term-gene?terms=HP:0001166&gene_symbols=FBN1";
+        let req = actix_web::test::TestRequest::get().uri(uri).to_request();
+        let resp: serde_json::Value = actix_web::test::call_and_read_body_json(&app, req).await;
+        assert!(resp["meta"].get("debug_timings").is_none());
+
+        let req = actix_web::test::TestRequest::get()
+            .uri(&format!("{uri}&debug_timings=true"))
+            .to_request();
+        let resp: serde_json::Value = actix_web::test::call_and_read_body_json(&app, req).await;
+        let meta: super::ResponseMeta = serde_json::from_value(resp["meta"].clone())?;
+        let timings = meta.debug_timings.expect("must have timings");
+        assert_eq!(
+            timings
+                .stages
+                .iter()
+                .map(|stage| stage.stage.as_str())
+                .collect::<Vec<_>>(),
+            vec!["ontology_lookup", "scoring"]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_vm_rss() {
+        assert_eq!(
+            super::parse_vm_rss("Name:\tviguno\nVmPeak:\t  300 kB\nVmRSS:\t  1234 kB\n"),
+            Some(1234 * 1024)
+        );
+        assert_eq!(super::parse_vm_rss("Name:\tviguno\n"), None);
+    }
+
+    #[test]
+    fn normalize_query() {
+        assert_eq!(
+            super::normalize_query("terms=HP%3A0000252,HP:0000118&gene_ids=2348&max_results=10"),
+            "gene_ids=2348&max_results=10&terms=HP:0000252,HP:0000118"
+        );
+        assert_eq!(super::normalize_query(""), "");
+    }
+
+    #[rstest::rstest]
+    #[actix_web::test]
+    async fn release_header_mismatch(web_server_data: &Arc<crate::server::run::WebServerData>) {
+        let req = actix_web::test::TestRequest::get()
+            .uri("/api/v1/hpo/genes?gene_id=2348")
+            .insert_header((super::EXPECTED_RELEASE_HEADER, "1900-01-01"));
+        let resp = run_query(web_server_data.clone(), req).await;
+        assert_eq!(resp.status(), StatusCode::CONFLICT);
+    }
+
+    #[rstest::rstest]
+    #[actix_web::test]
+    async fn release_param_match(web_server_data: &Arc<crate::server::run::WebServerData>) {
+        let uri = format!(
+            "/api/v1/hpo/genes?gene_id=2348&hpo_release={}",
+            web_server_data.ontology.hpo_version()
+        );
+        let req = actix_web::test::TestRequest::get().uri(&uri);
+        let resp = run_query(web_server_data.clone(), req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    #[actix_web::test]
+    async fn answer_head() {
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .wrap(actix_web::middleware::from_fn(super::answer_head))
+                .route("/ping", actix_web::web::get().to(|| async { "pong" })),
+        )
+        .await;
+        let req = actix_web::test::TestRequest::with_uri("/ping")
+            .method(actix_web::http::Method::HEAD)
+            .to_request();
+        let resp = actix_web::test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+}
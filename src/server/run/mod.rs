@@ -1,53 +1,451 @@
 //! Implementation of the Actix server.
 
+pub mod arrow_ipc;
+pub mod beacon;
+pub mod defaults;
+pub mod disease_mappings;
+pub mod generic;
+pub mod hpo_expr;
 pub mod hpo_genes;
+pub mod hpo_genes_matching;
+pub mod hpo_genes_profile;
+pub mod hpo_infer_labs;
 pub mod hpo_omims;
+pub mod hpo_ontology;
+pub mod hpo_qc;
 pub mod hpo_sim;
+pub mod hpo_stats;
 pub mod hpo_terms;
+pub mod hpo_terms_distance;
+pub mod hpo_terms_dump;
+pub mod hpo_terms_genes;
+pub mod hpo_terms_history;
+pub mod hpo_terms_mappings;
+pub mod hpo_terms_obo;
+pub mod hpo_terms_paths;
+pub mod hpo_terms_treatments;
+pub mod limits;
+pub mod mappings;
+pub mod middleware;
+pub mod patients;
+pub mod readyz;
+pub mod record;
+pub mod routes;
+pub mod strict_query;
+pub mod tenants;
+#[cfg(feature = "ui")]
+pub mod ui;
 
-use std::{collections::HashMap, sync::Arc};
+use std::{collections::HashMap, sync::Arc, time::Duration};
 
-use actix_web::{middleware::Logger, web::Data, App, HttpServer, ResponseError};
+use actix_web::{
+    body::MessageBody,
+    dev::{ServiceFactory, ServiceRequest, ServiceResponse},
+    http::{KeepAlive, Method, StatusCode},
+    middleware::{from_fn, Logger},
+    web::{Data, ServiceConfig},
+    App, HttpServer, ResponseError,
+};
 use serde::{Deserialize, Deserializer, Serialize};
 use utoipa::OpenApi;
 
-use crate::common::load_hpo;
+use crate::{common::load_hpo, progress::Loader};
+use middleware::{DebugTimingsDoc, ExpectedReleaseDoc, ResponseMetaDoc};
 
 /// Data structure for the web server data.
 pub struct WebServerData {
     /// The HPO ontology (`hpo` crate).
     pub ontology: hpo::Ontology,
-    /// Xlink map from NCBI gene ID to HGNC gene ID.
-    pub ncbi_to_hgnc: HashMap<u32, String>,
-    /// Xlink map from HGNC gene ID to NCBI gene ID.
-    pub hgnc_to_ncbi: HashMap<String, u32>,
+    /// Xlinks between NCBI and HGNC gene IDs.
+    pub hgnc_xlink: crate::sources::hgnc_xlink::HgncXlink,
+    /// Map from NCBI gene ID to NCBI gene information, empty if not loaded.
+    pub gene_info: HashMap<u32, crate::sources::gene_info::GeneInfo>,
+    /// Map from NCBI gene ID to genomic coordinates, empty if not loaded.
+    pub gene_coords: HashMap<u32, crate::sources::gene_coords::Entry>,
+    /// Gene interaction network for network-boosted prioritization, if loaded.
+    pub network: Option<crate::network::Network>,
+    /// The loaded annotation sources, e.g., the gene constraint table or the `ClinVar`
+    /// gene summary.
+    pub sources: crate::sources::AnnotationSources,
+    /// Fraction of the score subtracted for genes not expressed in the requested tissue.
+    pub expression_penalty: f32,
+    /// Translation of disease codes by Orphanet's mappings, empty if not loaded.
+    pub disease_codes: crate::sources::disease_codes::DiseaseCodes,
+    /// Inference of terms from lab observations, without rules if not loaded.
+    pub lab_inference: Box<dyn crate::algos::labs::LabInference>,
+    /// Translations of term labels, empty if not loaded.
+    pub translations: crate::sources::translations::Translations,
+    /// Translations of Orphanet disease names, empty if not loaded.
+    pub disease_translations: crate::sources::translations::DiseaseTranslations,
+    /// Supplementary term-gene association scores by name, empty if not loaded.
+    pub association_scores:
+        std::collections::BTreeMap<String, crate::sources::associations::AssociationScores>,
+    /// Experimental features that may be enabled per request.
+    pub experimental_allowlist: Vec<crate::common::ExperimentalFeature>,
     /// The full text index over the HPO OBO document.
     pub full_text_index: crate::index::Index,
+    /// Local extension terms, empty if not loaded.
+    pub local_terms: crate::sources::local_terms::LocalTerms,
+    /// Build date of the HPO annotations, if known.
+    pub annotation_date: Option<String>,
+    /// Information content tables computed at load time.
+    pub ic_tables: crate::algos::ic::IcTables,
+    /// Bitset index from terms to annotated genes.
+    pub term_gene_index: crate::algos::term_genes::TermGeneIndex,
+    /// Bitset index from terms to annotated OMIM diseases.
+    pub term_disease_index: crate::algos::term_diseases::TermDiseaseIndex,
+    /// Modes of inheritance of the diseases.
+    pub inheritance_index: crate::algos::inheritance::InheritanceIndex,
+    /// Onset ranges of the genes.
+    pub onset_index: crate::algos::onset::OnsetIndex,
+    /// Fraction of the score subtracted for genes with incompatible age of onset.
+    pub onset_penalty: f32,
+    /// Methods fused into the ensemble score.
+    pub ensemble_methods: Vec<crate::algos::ensemble::EnsembleMethod>,
+    /// Registry of patients for similar patient queries, if enabled.
+    pub patient_registry: Option<patients::PatientRegistry>,
+    /// Settings for the Matchmaker Exchange endpoint.
+    pub mme_config: patients::mme::MmeConfig,
+    /// Settings for the beacon endpoint.
+    pub beacon_config: beacon::BeaconConfig,
+    /// Annotations restricted to one sex.
+    pub sex_index: crate::algos::sex::SexIndex,
+    /// OMIM diseases linked to the genes, for scoring genes via their diseases.
+    pub disease_gene_index: crate::algos::routes::DiseaseGeneIndex,
+    /// Whether annotations propagate to ancestors unless overridden per query.
+    pub annotation_propagation: crate::common::AnnotationPropagation,
+    /// Limits on the size and complexity of requests.
+    pub limits: limits::Limits,
+    /// Settings for the ontology export endpoint.
+    pub export_config: hpo_ontology::ExportConfig,
+    /// Requests taking longer are logged as a warning, if set.
+    pub slow_request_threshold: Option<std::time::Duration>,
+    /// Memory budget in bytes, batch requests are rejected close to it, if set.
+    pub max_memory_bytes: Option<u64>,
+    /// The optional data that failed to load, the server runs without it.
+    pub degraded: Vec<crate::progress::Degraded>,
+    /// The tenants served below `/t/{tenant}`, empty if not configured.
+    pub tenants: tenants::Tenants,
+    /// Key for signing reports with HMAC-SHA256, reports are only hashed if missing.
+    pub report_signing_key: Option<String>,
+    /// Default query parameters by route from the configuration file.
+    pub query_defaults: defaults::QueryDefaults,
+    /// Directory to record requests and responses to, if set.
+    pub record_requests: Option<std::path::PathBuf>,
+    /// Simulated score distributions for the p-values, built on first use.
+    pub simulation_cache: crate::algos::simulation::SimulationCache,
 }
 
 /// Command line arguments for `server run` sub command.
 #[derive(clap::Parser, Debug)]
+#[allow(clippy::struct_excessive_bools)]
 #[command(author, version, about = "Run viguno REST API server", long_about = None)]
 pub struct Args {
     /// Path to the directory with the HPO files.
-    #[arg(long, required = true)]
-    pub path_hpo_dir: String,
+    #[arg(
+        long,
+        env = "SEAHORSE_PATH_HPO_DIR",
+        required_unless_present = "generic_obo"
+    )]
+    pub path_hpo_dir: Option<std::path::PathBuf>,
+    /// Path to an arbitrary OBO file, e.g., MAXO, to serve only the term search, detail,
+    /// and hierarchy endpoints below `/api/v1/obo` instead of the HPO.
+    #[arg(long, env = "SEAHORSE_GENERIC_OBO", conflicts_with = "path_hpo_dir")]
+    pub generic_obo: Option<std::path::PathBuf>,
+    /// How to verify the HPO directory against its `MANIFEST.sha256` on startup, "off",
+    /// "warn", or "strict".
+    #[arg(
+        long,
+        env = "SEAHORSE_VERIFY_DATA",
+        default_value_t = crate::data::verify::DataVerification::Warn
+    )]
+    pub verify_data: crate::data::verify::DataVerification,
+    /// Whether to refuse to start if an optional data file cannot be loaded, instead of
+    /// running without it and flagging it in `/readyz`.
+    #[arg(long, env = "SEAHORSE_REQUIRE_OPTIONAL_DATA", default_value_t = false)]
+    pub require_optional_data: bool,
 
     /// Whether to suppress printing hints.
-    #[arg(long, default_value_t = false)]
+    #[arg(long, env = "SEAHORSE_SUPPRESS_HINTS", default_value_t = false)]
     pub suppress_hints: bool,
 
     /// IP to listen on.
-    #[arg(long, default_value = "127.0.0.1")]
+    #[arg(long, env = "SEAHORSE_LISTEN_HOST", default_value = "127.0.0.1")]
     pub listen_host: String,
     /// Port to listen on.
-    #[arg(long, default_value_t = 8080)]
+    #[arg(long, env = "SEAHORSE_LISTEN_PORT", default_value_t = 8080)]
     pub listen_port: u16,
+    /// Whether to accept HTTP/2 over cleartext (h2c) in addition to HTTP/1.x.
+    #[arg(long, env = "SEAHORSE_HTTP2_CLEARTEXT", default_value_t = false)]
+    pub http2_cleartext: bool,
+    /// Keep-alive timeout of idle connections in seconds, 0 disables keep-alive.
+    #[arg(long, env = "SEAHORSE_KEEP_ALIVE_SECS", default_value_t = 5)]
+    pub keep_alive_secs: u64,
+    /// Timeout for receiving the request head in milliseconds, 0 disables the timeout.
+    #[arg(
+        long,
+        env = "SEAHORSE_CLIENT_REQUEST_TIMEOUT_MS",
+        default_value_t = 5000
+    )]
+    pub client_request_timeout_ms: u64,
+    /// Timeout for shutting down connections in milliseconds, 0 disables the timeout.
+    #[arg(
+        long,
+        env = "SEAHORSE_CLIENT_DISCONNECT_TIMEOUT_MS",
+        default_value_t = 5000
+    )]
+    pub client_disconnect_timeout_ms: u64,
+    /// Maximal number of concurrent connections per worker.
+    #[arg(long, env = "SEAHORSE_MAX_CONNECTIONS", default_value_t = 25_000)]
+    pub max_connections: usize,
+
+    /// Whether annotations propagate to ancestors, "none" or "ancestors".
+    #[arg(long, env = "SEAHORSE_ANNOTATION_PROPAGATION", default_value_t = crate::common::AnnotationPropagation::Ancestors)]
+    pub annotation_propagation: crate::common::AnnotationPropagation,
+
+    /// Optional path to a TSV file with externally computed information content.
+    #[arg(long, env = "SEAHORSE_PATH_CUSTOM_IC")]
+    pub path_custom_ic: Option<std::path::PathBuf>,
+    /// Optional path to a (slimmed) NCBI `Homo_sapiens.gene_info` file.
+    #[arg(long, env = "SEAHORSE_PATH_GENE_INFO")]
+    pub path_gene_info: Option<std::path::PathBuf>,
+    /// Optional path to a TSV file with gene coordinates for region filters.
+    #[arg(long, env = "SEAHORSE_PATH_GENE_COORDS")]
+    pub path_gene_coords: Option<std::path::PathBuf>,
+    /// Optional path to a TSV edge list of gene interactions (e.g., STRING) for network boosting.
+    #[arg(long, env = "SEAHORSE_PATH_NETWORK")]
+    pub path_network: Option<std::path::PathBuf>,
+    /// Optional path to a TSV file with gene constraint metrics, e.g., gnomAD's
+    /// `lof_metrics.by_gene.txt`, for re-ranking by constraint.
+    #[arg(long, env = "SEAHORSE_PATH_GENE_CONSTRAINT")]
+    pub path_gene_constraint: Option<std::path::PathBuf>,
+    /// Optional path to a TSV file with the numbers of pathogenic or likely pathogenic
+    /// `ClinVar` variants by gene, e.g., `ClinVar`'s `gene_specific_summary.txt`.
+    #[arg(long, env = "SEAHORSE_PATH_CLINVAR_GENES")]
+    pub path_clinvar_genes: Option<std::path::PathBuf>,
+    /// Optional path to a gene-by-tissue expression matrix, e.g., `GTEx`'s
+    /// `gene_median_tpm.gct`, for restricting genes to a tissue.
+    #[arg(long, env = "SEAHORSE_PATH_EXPRESSION")]
+    pub path_expression: Option<std::path::PathBuf>,
+    /// Optional path to Orphanet's `en_product1.xml` or to a TSV file with the ICD-10 and OMIM
+    /// mappings of ORPHA codes.
+    #[arg(long, env = "SEAHORSE_PATH_ORPHANET_MAPPINGS")]
+    pub path_orphanet_mappings: Option<std::path::PathBuf>,
+    /// Optional path to Orphanet's `en_product9_prev.xml` or to a TSV file with the prevalence
+    /// classes of ORPHA codes.
+    #[arg(long, env = "SEAHORSE_PATH_ORPHANET_PREVALENCE")]
+    pub path_orphanet_prevalence: Option<std::path::PathBuf>,
+    /// Optional path to a TSV file with gene panels, e.g., from `PanelApp`, with the
+    /// columns `panel_id`, `panel_name`, and `gene_symbol`.
+    #[arg(long, env = "SEAHORSE_PATH_GENE_PANELS")]
+    pub path_gene_panels: Option<std::path::PathBuf>,
+    /// Optional path to a TSV file with rules mapping lab observations to HPO terms.
+    #[arg(long, env = "SEAHORSE_PATH_LAB_RULES")]
+    pub path_lab_rules: Option<std::path::PathBuf>,
+    /// Optional path to a file with stopwords for the term search, one per line.
+    #[arg(long, env = "SEAHORSE_PATH_STOPWORDS")]
+    pub path_stopwords: Option<std::path::PathBuf>,
+    /// Comma-separated paths to Babelon TSV files with translations (e.g., `hp-de.babelon.tsv`).
+    #[arg(long, env = "SEAHORSE_PATH_TRANSLATIONS", value_delimiter = ',')]
+    pub path_translations: Vec<std::path::PathBuf>,
+    /// Comma-separated paths to Orphanet's translated `product1` files (e.g.,
+    /// `de_product1.xml`) or to TSV files with the columns `language`, `orpha_code`, and
+    /// `name`.
+    #[arg(
+        long,
+        env = "SEAHORSE_PATH_DISEASE_TRANSLATIONS",
+        value_delimiter = ','
+    )]
+    pub path_disease_translations: Vec<std::path::PathBuf>,
+
+    /// Supplementary term-gene association scores as `name=path` to TSV files with the
+    /// columns `hpo_id`, `ncbi_gene_id`, and `score`, e.g., exported from CADA.
+    #[arg(long, env = "SEAHORSE_PATH_ASSOCIATION_SCORES", value_delimiter = ',')]
+    pub path_association_scores: Vec<String>,
+    /// Path to a TSV file with MAXO annotations of medical actions to HPO terms, with the
+    /// columns `maxo_id`, `maxo_label`, `hpo_id`, and `predicate`.
+    #[arg(long, env = "SEAHORSE_PATH_MAXO_ANNOTATIONS")]
+    pub path_maxo_annotations: Option<std::path::PathBuf>,
+    /// Path to an OBO file with local extension terms, e.g., `HP:LAB:0001`, each below
+    /// an HPO term.
+    #[arg(long, env = "SEAHORSE_PATH_LOCAL_TERMS")]
+    pub path_local_terms: Option<std::path::PathBuf>,
+
+    /// Comma-separated routes to disable, e.g., "hpo-sim-term-gene,hpo-terms-dump", they
+    /// respond with 404 and are removed from the `OpenAPI` spec.
+    #[arg(long, env = "SEAHORSE_DISABLE_ROUTES", value_delimiter = ',')]
+    pub disable_routes: Vec<routes::Route>,
+
+    /// Comma-separated experimental features that may be enabled per request.
+    #[arg(long, env = "SEAHORSE_EXPERIMENTAL_ALLOWLIST", value_delimiter = ',')]
+    pub experimental_allowlist: Vec<crate::common::ExperimentalFeature>,
+
+    /// Bearer token required for the ontology export endpoint, which is disabled if missing.
+    #[arg(long, env = "SEAHORSE_EXPORT_TOKEN", hide_env_values = true)]
+    pub export_token: Option<String>,
+    /// Maximal size of an ontology export in bytes.
+    #[arg(long, env = "SEAHORSE_EXPORT_MAX_BYTES", default_value_t = 100_000_000)]
+    pub export_max_bytes: usize,
+
+    /// Record the requests below `/api/` with their responses as JSON files in this
+    /// directory, for `server replay`.
+    #[arg(long, env = "SEAHORSE_RECORD_REQUESTS")]
+    pub record_requests: Option<std::path::PathBuf>,
+
+    /// Log requests taking longer than this many milliseconds as a warning.
+    #[arg(long, env = "SEAHORSE_SLOW_REQUEST_THRESHOLD_MS")]
+    pub slow_request_threshold_ms: Option<u64>,
+
+    /// Maximal size of a request body in bytes, larger bodies are rejected with 413.
+    #[arg(long, env = "SEAHORSE_MAX_BODY_BYTES", default_value_t = limits::Limits::default().max_body_bytes)]
+    pub max_body_bytes: usize,
+    /// Maximal number of terms in a set of terms, larger sets are rejected with 422.
+    #[arg(long, env = "SEAHORSE_MAX_TERMS", default_value_t = limits::Limits::default().max_terms)]
+    pub max_terms: usize,
+    /// Maximal number of genes, diseases, or relatives in a request, larger batches are
+    /// rejected with 422.
+    #[arg(long, env = "SEAHORSE_MAX_BATCH_SIZE", default_value_t = limits::Limits::default().max_batch_size)]
+    pub max_batch_size: usize,
+    /// Maximal estimated number of pairwise term comparisons of a request, more complex
+    /// requests are rejected with 422.
+    #[arg(long, env = "SEAHORSE_MAX_COMPLEXITY", default_value_t = limits::Limits::default().max_complexity)]
+    pub max_complexity: u64,
+
+    /// Memory budget in MiB, batch endpoints respond with 503 when close to it.
+    #[arg(long, env = "SEAHORSE_MAX_MEMORY_MB")]
+    pub max_memory_mb: Option<u64>,
+
+    /// Directory to persist precomputed artifacts in, e.g., the simulated score
+    /// distributions for p-values, kept in memory only if missing.
+    #[arg(long, env = "SEAHORSE_CACHE_DIR")]
+    pub cache_dir: Option<std::path::PathBuf>,
+    /// Size limit of the cache directory in MiB, the least recently used entries are
+    /// removed beyond it.
+    #[arg(long, env = "SEAHORSE_CACHE_MAX_MB", default_value_t = 1024)]
+    pub cache_max_mb: u64,
+    /// Path to the score distributions for the p-values from `precompute sim-pvalues`, the
+    /// missing numbers of terms are simulated on first use.
+    #[arg(long, env = "SEAHORSE_PATH_SIM_PVALUES")]
+    pub path_sim_pvalues: Option<std::path::PathBuf>,
+    /// Number of random queries simulated per number of terms for the p-values.
+    #[arg(long, env = "SEAHORSE_P_VALUE_SIMULATIONS", default_value_t = crate::algos::simulation::DEFAULT_SIMULATIONS)]
+    pub p_value_simulations: usize,
+    /// Comma-separated seeds that clients may request for the p-values besides the seed of
+    /// the precomputed distributions, each is simulated on first use.
+    #[arg(long, env = "SEAHORSE_P_VALUE_SEEDS", value_delimiter = ',')]
+    pub p_value_seeds: Vec<u64>,
+
+    /// Whether to enable the in-memory patient registry for similar patient queries.
+    #[arg(
+        long,
+        env = "SEAHORSE_ENABLE_PATIENT_REGISTRY",
+        default_value_t = false
+    )]
+    pub enable_patient_registry: bool,
+    /// Path to a TOML configuration file with default query parameters by route in its
+    /// `[defaults]` section.
+    #[arg(long, env = "SEAHORSE_PATH_CONFIG")]
+    pub path_config: Option<std::path::PathBuf>,
+    /// Path to a JSON file with the tenants served below `/t/{tenant}`, each with its own
+    /// API keys, patient registry, and optionally custom information content.
+    #[arg(long, env = "SEAHORSE_PATH_TENANTS")]
+    pub path_tenants: Option<std::path::PathBuf>,
+    /// Token required in the `X-Auth-Token` header of Matchmaker Exchange requests, the
+    /// endpoint answers 403 if missing.
+    #[arg(long, env = "SEAHORSE_MME_TOKEN", hide_env_values = true)]
+    pub mme_token: Option<String>,
+    /// Key for signing the reports of `/api/v1/hpo/sim/term-gene/report` with
+    /// HMAC-SHA256, reports are only hashed with SHA-256 if missing.
+    #[arg(long, env = "SEAHORSE_REPORT_SIGNING_KEY", hide_env_values = true)]
+    pub report_signing_key: Option<String>,
+    /// The most revealing answer of `/api/v1/beacon` for the diseases, "boolean" or
+    /// "count".
+    #[arg(
+        long,
+        env = "SEAHORSE_BEACON_MAX_GRANULARITY_DISEASES",
+        default_value_t = beacon::BeaconGranularity::Count
+    )]
+    pub beacon_max_granularity_diseases: beacon::BeaconGranularity,
+    /// The most revealing answer of `/api/v1/beacon` for the patients, "boolean" or
+    /// "count".
+    #[arg(
+        long,
+        env = "SEAHORSE_BEACON_MAX_GRANULARITY_PATIENTS",
+        default_value_t = beacon::BeaconGranularity::Boolean
+    )]
+    pub beacon_max_granularity_patients: beacon::BeaconGranularity,
+    /// Name of the contact reported for Matchmaker Exchange matches.
+    #[arg(long, env = "SEAHORSE_MME_CONTACT_NAME", requires = "mme_contact_href")]
+    pub mme_contact_name: Option<String>,
+    /// URL of the contact reported for Matchmaker Exchange matches, e.g., `mailto:` URL.
+    #[arg(long, env = "SEAHORSE_MME_CONTACT_HREF", requires = "mme_contact_name")]
+    pub mme_contact_href: Option<String>,
+
+    /// Fraction of the score subtracted for genes with incompatible age of onset.
+    #[arg(long, env = "SEAHORSE_ONSET_PENALTY", default_value_t = 0.5, value_parser = parse_penalty)]
+    pub onset_penalty: f32,
+    /// Fraction of the score subtracted for genes not expressed in the requested tissue
+    /// with `expression_mode=penalize`.
+    #[arg(long, env = "SEAHORSE_EXPRESSION_PENALTY", default_value_t = 0.5, value_parser = parse_penalty)]
+    pub expression_penalty: f32,
+
+    /// Comma-separated methods fused into the ensemble score, similarity methods or
+    /// `jaccard`.
+    #[arg(long, env = "SEAHORSE_ENSEMBLE_METHODS", value_delimiter = ',', default_value = crate::algos::ensemble::DEFAULT_METHODS)]
+    pub ensemble_methods: Vec<crate::algos::ensemble::EnsembleMethod>,
+}
+
+impl Args {
+    /// Return the path to the directory with the HPO files.
+    ///
+    /// # Panics
+    ///
+    /// Never, the directory is required unless in generic ontology mode, which does not
+    /// load the HPO data.
+    pub fn path_hpo_dir(&self) -> &std::path::Path {
+        self.path_hpo_dir
+            .as_deref()
+            .expect("--path-hpo-dir is required without --generic-obo")
+    }
+
+    /// Return the routes disabled with `--disable-routes` and the ontology export without
+    /// `--export-token`.
+    pub fn disabled_routes(&self) -> Vec<routes::Route> {
+        let mut result = self.disable_routes.clone();
+        if self.export_token.is_none() && !result.contains(&routes::Route::HpoOntologyExport) {
+            result.push(routes::Route::HpoOntologyExport);
+        }
+        result
+    }
+}
+
+/// Parse a penalty from the command line, a fraction of the score in `[0, 1]`.
+fn parse_penalty(value: &str) -> Result<f32, String> {
+    let penalty = value
+        .parse::<f32>()
+        .map_err(|e| format!("invalid penalty {value:?}: {e}"))?;
+    if (0.0..=1.0).contains(&penalty) {
+        Ok(penalty)
+    } else {
+        Err(format!("penalty must be between 0 and 1, got {value}"))
+    }
 }
 
 #[derive(Debug, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
 struct CustomError {
     err: String,
+    /// The malformed term ID from the query, if this is the reason for the error.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    term_id: Option<String>,
+    /// The exceeded limit, if this is the reason for the error.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    limit: Option<limits::LimitViolation>,
+    /// The unknown query parameters, if this is the reason for the error.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    unknown_params: Option<Vec<strict_query::UnknownParam>>,
+    /// The HTTP status code to respond with.
+    #[serde(skip)]
+    status: StatusCode,
 }
 
 impl std::fmt::Display for CustomError {
@@ -59,13 +457,53 @@ impl std::fmt::Display for CustomError {
 impl CustomError {
     #[allow(clippy::needless_pass_by_value)]
     fn new(err: anyhow::Error) -> Self {
+        Self::with_status(StatusCode::INTERNAL_SERVER_ERROR, err)
+    }
+
+    /// Construct with a specific HTTP status code.
+    #[allow(clippy::needless_pass_by_value)]
+    fn with_status(status: StatusCode, err: anyhow::Error) -> Self {
+        CustomError {
+            err: err.to_string(),
+            term_id: None,
+            limit: None,
+            unknown_params: None,
+            status,
+        }
+    }
+
+    /// Construct an error for the exceeded `limit`.
+    fn limit_exceeded(status: StatusCode, limit: limits::LimitViolation) -> Self {
+        CustomError {
+            err: format!("request exceeds limit {}: {}", limit.reason, limit.limit),
+            term_id: None,
+            limit: Some(limit),
+            unknown_params: None,
+            status,
+        }
+    }
+
+    /// Construct a 400 error for the malformed term ID `value`.
+    fn invalid_term_id(value: &str, err: &anyhow::Error) -> Self {
         CustomError {
             err: err.to_string(),
+            term_id: Some(value.to_string()),
+            limit: None,
+            unknown_params: None,
+            status: StatusCode::BAD_REQUEST,
         }
     }
 }
 
-impl ResponseError for CustomError {}
+impl ResponseError for CustomError {
+    fn status_code(&self) -> StatusCode {
+        self.status
+    }
+
+    fn error_response(&self) -> actix_web::HttpResponse {
+        actix_web::HttpResponse::build(self.status).json(self)
+    }
+}
 
 /// Specify how to perform query matches in the API calls.
 #[derive(Serialize, Deserialize, utoipa::ToSchema, Debug, Clone, Copy, Default, PartialEq, Eq)]
@@ -103,6 +541,25 @@ pub struct ResultGene {
     pub gene_symbol: String,
     /// The HGNC ID.
     pub hgnc_id: Option<String>,
+    /// The gene's NCBI gene information, if requested and available.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub gene_info: Option<crate::sources::gene_info::GeneInfo>,
+}
+
+impl ResultGene {
+    /// Set the gene information from `gene_info`, if given and available.
+    #[must_use]
+    pub fn with_gene_info(
+        self,
+        gene_info: Option<&HashMap<u32, crate::sources::gene_info::GeneInfo>>,
+    ) -> Self {
+        Self {
+            gene_info: gene_info
+                .and_then(|gene_info| gene_info.get(&self.ncbi_gene_id))
+                .cloned(),
+            ..self
+        }
+    }
 }
 
 /// Representation of an HPO term.
@@ -125,16 +582,72 @@ pub struct ResultHpoTerm {
     pub name: String,
 }
 
+/// A comma-separated list of strings from a query, e.g., the terms of the hot endpoints.
+///
+/// The list is kept as one string and split on access, so deserializing it allocates at
+/// most once rather than once per value.  It is serialized as a list of strings.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CommaSeparated(String);
+
+impl CommaSeparated {
+    /// Return the values of the list.
+    pub fn iter(&self) -> std::str::Split<'_, char> {
+        self.0.split(',')
+    }
+
+    /// Return the values of the list as string slices, stored inline for typical query
+    /// sizes.
+    pub fn to_strs(&self) -> smallvec::SmallVec<[&str; 16]> {
+        self.iter().collect()
+    }
+}
+
+impl<'a> IntoIterator for &'a CommaSeparated {
+    type Item = &'a str;
+    type IntoIter = std::str::Split<'a, char>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl Serialize for CommaSeparated {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<'de> Deserialize<'de> for CommaSeparated {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        // Owned input, e.g., percent-decoded from the query string, is taken over as is.
+        String::deserialize(deserializer).map(CommaSeparated)
+    }
+}
+
+/// Visitor splitting a comma-separated list of strings.
+///
+/// The list is split from the borrowed input so no intermediate string is allocated, but
+/// each value is, see `CommaSeparated` for the hot endpoints.
+struct CommaSeparatedVisitor;
+
+impl serde::de::Visitor<'_> for CommaSeparatedVisitor {
+    type Value = Vec<String>;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("a comma-separated list of strings")
+    }
+
+    fn visit_str<E: serde::de::Error>(self, value: &str) -> Result<Self::Value, E> {
+        Ok(value.split(',').map(ToOwned::to_owned).collect())
+    }
+}
+
 /// Helper to deserialize a comma-separated list of strings.
 fn vec_str_deserialize<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
 where
     D: Deserializer<'de>,
 {
-    let str_sequence = String::deserialize(deserializer)?;
-    Ok(str_sequence
-        .split(',')
-        .map(std::borrow::ToOwned::to_owned)
-        .collect())
+    deserializer.deserialize_str(CommaSeparatedVisitor)
 }
 
 /// Helper to deserialize a comma-separated list of strings.
@@ -142,81 +655,513 @@ fn option_vec_str_deserialize<'de, D>(deserializer: D) -> Result<Option<Vec<Stri
 where
     D: Deserializer<'de>,
 {
-    let str_sequence = String::deserialize(deserializer)?;
-    if str_sequence.is_empty() {
+    let values = deserializer.deserialize_str(CommaSeparatedVisitor)?;
+    if values.len() == 1 && values[0].is_empty() {
         Ok(None)
     } else {
-        Ok(Some(
-            str_sequence
-                .split(',')
-                .map(std::borrow::ToOwned::to_owned)
-                .collect(),
-        ))
+        Ok(Some(values))
+    }
+}
+
+/// Helper to parse the optional `region` query parameter.
+///
+/// Fails with 400 if the region is invalid or no gene coordinates were loaded.
+fn parse_region(
+    region: Option<&str>,
+    data: &WebServerData,
+) -> Result<Option<crate::sources::gene_coords::Region>, CustomError> {
+    let Some(region) = region else {
+        return Ok(None);
+    };
+    if data.gene_coords.is_empty() {
+        return Err(CustomError::with_status(
+            StatusCode::BAD_REQUEST,
+            anyhow::anyhow!("region filter requested but no gene coordinates were loaded"),
+        ));
+    }
+    region
+        .parse()
+        .map(Some)
+        .map_err(|e| CustomError::with_status(StatusCode::BAD_REQUEST, e))
+}
+
+/// Term IDs parsed from a query, stored inline for typical query sizes.
+pub type TermIds = smallvec::SmallVec<[hpo::HpoTermId; 16]>;
+
+/// Helper to parse the term IDs from a query into canonical form.
+///
+/// Alternative IDs of merged terms are resolved to the primary IDs and local terms to
+/// their nearest HPO terms, both recorded in `resolved_from`.  Fails with 400 naming the
+/// first malformed term ID.
+fn parse_term_ids<S: AsRef<str>>(
+    values: &[S],
+    data: &WebServerData,
+    resolved_from: &middleware::ResolvedFrom,
+) -> Result<TermIds, CustomError> {
+    data.limits.check_terms(values.len())?;
+    let mut result = TermIds::new();
+    for value in values {
+        result.extend(parse_term_id(value.as_ref(), data, resolved_from)?);
     }
+    Ok(result)
+}
+
+/// Helper to parse one term ID from a query into canonical form, see `parse_term_ids`.
+///
+/// Returns several term IDs for a local term below several HPO terms.
+fn parse_term_id(
+    value: &str,
+    data: &WebServerData,
+    resolved_from: &middleware::ResolvedFrom,
+) -> Result<TermIds, CustomError> {
+    if let Some(local_term) = data.local_terms.get(value.trim()) {
+        resolved_from.record_local(&local_term.id, &local_term.parents);
+        return Ok(local_term.parents.iter().copied().collect());
+    }
+    crate::common::parse_term_id(value)
+        .map(|term_id| smallvec::smallvec![resolve_alt_id(term_id, data, resolved_from)])
+        .map_err(|e| CustomError::invalid_term_id(value, &e))
+}
+
+/// Helper to parse term IDs with clinical modifiers, e.g., `HP:0001249[HP:0012828]`.
+///
+/// Returns the term IDs and the weights of the terms with modifiers.  Fails with 400 if
+/// a term ID is malformed or a modifier is not below "Clinical modifier".
+fn parse_modified_term_ids<S: AsRef<str>>(
+    values: &[S],
+    data: &WebServerData,
+    resolved_from: &middleware::ResolvedFrom,
+) -> Result<(TermIds, crate::algos::phenomizer::TermWeights), CustomError> {
+    use crate::algos::modifiers;
+
+    data.limits.check_terms(values.len())?;
+    let mut term_ids = TermIds::new();
+    let mut weights = crate::algos::phenomizer::TermWeights::new();
+    for value in values {
+        let value = value.as_ref();
+        let (term, modifiers) = modifiers::split_modifiers(value)
+            .map_err(|e| CustomError::invalid_term_id(value, &e))?;
+        let term_ids_of_value = parse_term_id(term, data, resolved_from)?;
+        let modifiers = parse_term_ids(&modifiers, data, resolved_from)?;
+        if let Some(modifier) = modifiers
+            .iter()
+            .find(|modifier| !modifiers::is_clinical_modifier(&data.ontology, **modifier))
+        {
+            return Err(CustomError::invalid_term_id(
+                value,
+                &anyhow::anyhow!("not a clinical modifier: {}", modifier),
+            ));
+        }
+        for term_id in term_ids_of_value {
+            if !modifiers.is_empty() {
+                weights.insert(term_id, modifiers::modifier_weight(&modifiers));
+            }
+            term_ids.push(term_id);
+        }
+    }
+    Ok((term_ids, weights))
+}
+
+/// Helper to resolve `term_id` to the primary ID if it is the alternative ID of a merged term.
+fn resolve_alt_id(
+    term_id: hpo::HpoTermId,
+    data: &WebServerData,
+    resolved_from: &middleware::ResolvedFrom,
+) -> hpo::HpoTermId {
+    if data.ontology.hpo(term_id).is_some() {
+        return term_id;
+    }
+    match data.full_text_index.primary_id(term_id) {
+        Some(primary_id) => {
+            resolved_from.record(&term_id, &primary_id);
+            primary_id
+        }
+        None => term_id,
+    }
+}
+
+/// Helper to collect the `term_ids` into an `HpoGroup` of the terms for scoring.
+///
+/// Unknown terms are dropped, obsolete terms are replaced by their replacements or
+/// dropped, and duplicates are removed, each recorded in `resolved_from`.
+fn to_hpo_group(
+    term_ids: impl IntoIterator<Item = hpo::HpoTermId>,
+    data: &WebServerData,
+    resolved_from: &middleware::ResolvedFrom,
+) -> hpo::term::HpoGroup {
+    use middleware::QueryNormalizationAction;
+
+    let mut result = hpo::term::HpoGroup::new();
+    for term_id in term_ids {
+        let Some(term) = data.ontology.hpo(term_id) else {
+            resolved_from.record_change(
+                QueryNormalizationAction::UnknownDropped,
+                &term_id.to_string(),
+                &[],
+            );
+            continue;
+        };
+        let term_id = if term.is_obsolete() {
+            let replacement_id = term
+                .replacement_id()
+                .filter(|replacement_id| data.ontology.hpo(*replacement_id).is_some());
+            let Some(replacement_id) = replacement_id else {
+                resolved_from.record_change(
+                    QueryNormalizationAction::ObsoleteDropped,
+                    &term_id.to_string(),
+                    &[],
+                );
+                continue;
+            };
+            resolved_from.record_change(
+                QueryNormalizationAction::ObsoleteReplaced,
+                &term_id.to_string(),
+                &[replacement_id],
+            );
+            replacement_id
+        } else {
+            term_id
+        };
+        if !result.insert(term_id) {
+            resolved_from.record_change(
+                QueryNormalizationAction::DuplicateRemoved,
+                &term_id.to_string(),
+                &[],
+            );
+        }
+    }
+    result
+}
+
+/// Helper to parse the optional `inheritance` query parameter.
+///
+/// Fails with 400 if a mode of inheritance is unknown.
+fn parse_inheritance(
+    inheritance: Option<&[String]>,
+) -> Result<Option<Vec<crate::algos::inheritance::InheritanceMode>>, CustomError> {
+    inheritance
+        .map(|modes| {
+            modes
+                .iter()
+                .map(|mode| mode.parse())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| CustomError::with_status(StatusCode::BAD_REQUEST, e))
+        })
+        .transpose()
+}
+
+/// Helper to parse the optional `experimental` query parameter.
+///
+/// Fails with 400 if a feature is unknown or not in the server's allowlist.
+fn parse_experimental(
+    experimental: Option<&[String]>,
+    data: &WebServerData,
+) -> Result<Vec<crate::common::ExperimentalFeature>, CustomError> {
+    let mut result = Vec::new();
+    for name in experimental.unwrap_or_default() {
+        let feature = name
+            .parse::<crate::common::ExperimentalFeature>()
+            .map_err(|e| CustomError::with_status(StatusCode::BAD_REQUEST, e))?;
+        if !data.experimental_allowlist.contains(&feature) {
+            return Err(CustomError::with_status(
+                StatusCode::BAD_REQUEST,
+                anyhow::anyhow!(
+                    "experimental feature not enabled on this server: {}",
+                    feature
+                ),
+            ));
+        }
+        result.push(feature);
+    }
+    result.sort();
+    result.dedup();
+    Ok(result)
 }
 
 /// Utoipa-based `OpenAPI` generation helper.
 #[derive(utoipa::OpenApi)]
 #[openapi(
+    modifiers(&ExpectedReleaseDoc, &DebugTimingsDoc, &ResponseMetaDoc),
     paths(
+        hpo_expr::handle,
         hpo_genes::handle,
+        hpo_genes_matching::handle,
+        hpo_genes_profile::handle,
+        hpo_infer_labs::handle,
         hpo_terms::handle,
+        hpo_terms_genes::handle,
+        hpo_terms_dump::handle,
+        hpo_terms_mappings::handle,
+        hpo_terms_paths::handle,
+        hpo_terms_history::handle,
+        hpo_terms_obo::handle,
+        hpo_terms_treatments::handle,
+        hpo_terms_distance::handle,
+        mappings::handle,
+        disease_mappings::handle,
         hpo_omims::handle,
+        hpo_ontology::handle,
+        hpo_qc::handle,
+        hpo_stats::handle,
         hpo_sim::term_term::handle,
         hpo_sim::term_gene::handle,
+        hpo_sim::term_gene_report::handle,
+        hpo_sim::family_gene::handle,
+        hpo_sim::omim_omim::handle,
+        hpo_sim::gene_gene::handle,
+        patients::register::handle,
+        patients::similar::handle,
+        patients::mme::handle,
+        beacon::handle,
+        readyz::handle,
     ),
     components(schemas(
+        hpo_expr::HpoExprRequest,
+        hpo_expr::HpoExprResult,
+        hpo_expr::HpoExprMatches,
+        hpo_expr::HpoExprDisease,
+        crate::algos::term_expr::TermExpr,
         hpo_genes::HpoGenesQuery,
         hpo_genes::HpoGenesResult,
         hpo_genes::HpoGenesResultEntry,
+        hpo_genes_matching::HpoGenesMatchingQuery,
+        hpo_genes_matching::HpoGenesMatchingResult,
+        hpo_genes_matching::HpoGenesMatchingResultEntry,
+        hpo_genes_profile::HpoGenesProfileQuery,
+        hpo_genes_profile::HpoGenesProfileResult,
+        hpo_genes_profile::HpoGenesProfile,
+        hpo_genes_profile::HpoGenesProfileCategory,
+        hpo_genes_profile::HpoGenesProfileTerm,
+        hpo_infer_labs::HpoInferLabsRequest,
+        hpo_infer_labs::HpoInferLabsResult,
+        hpo_infer_labs::HpoInferLabsResultEntry,
+        crate::algos::labs::LabObservation,
+        crate::algos::labs::LabDirection,
         hpo_omims::HpoOmimsQuery,
         hpo_omims::HpoOmimsResult,
         hpo_omims::HpoOmimsResultEntry,
+        hpo_ontology::HpoOntologyExportQuery,
+        hpo_ontology::ExportFormat,
+        hpo_qc::HpoQcQuery,
+        hpo_qc::HpoQcResult,
+        crate::algos::qc::QcReport,
+        crate::algos::qc::QcTerm,
+        crate::algos::qc::QcObsoleteTerm,
+        crate::algos::qc::QcRedundantPair,
+        hpo_stats::HpoStatsCategoriesResult,
+        hpo_stats::HpoStatsCategory,
         hpo_terms::HpoTermsQuery,
         hpo_terms::HpoTermsResult,
         hpo_terms::HpoTermsResultEntry,
+        hpo_terms::HpoTermsHighlights,
+        hpo_terms::Highlight,
+        hpo_terms::HighlightRange,
+        hpo_terms_genes::HpoTermsGenesQuery,
+        hpo_terms_genes::HpoTermsGenesResult,
+        hpo_terms_genes::HpoTermsGenesResultEntry,
+        hpo_terms_dump::HpoTermsDumpRecord,
+        hpo_terms_mappings::HpoTermsMappingsQuery,
+        hpo_terms_mappings::HpoTermsMappingsResult,
+        hpo_terms_mappings::HpoTermsMappingsResultEntry,
+        hpo_terms_history::HpoTermsHistoryResult,
+        crate::index::TermHistory,
+        crate::index::TermPropertyValue,
+        hpo_terms_obo::HpoTermsOboResult,
+        hpo_terms_treatments::HpoTermsTreatmentsQuery,
+        hpo_terms_treatments::HpoTermsTreatmentsResult,
+        crate::sources::treatments::Treatment,
+        hpo_terms_paths::HpoTermsPathsQuery,
+        hpo_terms_paths::HpoTermsPathNode,
+        hpo_terms_paths::HpoTermsPath,
+        hpo_terms_paths::HpoTermsPathsResult,
+        hpo_terms_distance::HpoTermsDistanceQuery,
+        hpo_terms_distance::HpoTermsDistanceResultEntry,
+        hpo_terms_distance::HpoTermsDistanceResult,
+        mappings::MappingsQuery,
+        mappings::MappingsResult,
+        disease_mappings::DiseaseCodeSystem,
+        disease_mappings::DiseaseMappingsQuery,
+        disease_mappings::DiseaseMappingsResult,
+        disease_mappings::DiseaseMappingsResultEntry,
+        crate::sources::disease_codes::Icd10Code,
+        crate::sources::prevalence::Prevalence,
         hpo_sim::term_gene::HpoSimTermGeneQuery,
         crate::query::query_result::HpoSimTermGeneResult,
         crate::query::query_result::HpoSimTermGeneResultEntry,
         crate::query::query_result::HpoSimTermGeneTermDetails,
+        crate::query::query_result::HpoSimTermGeneCategoryScore,
+        crate::query::query_result::HpoSimTermGenePanel,
+        hpo_sim::term_gene::TermGeneGroupBy,
+        crate::algos::routes::AnnotationRoute,
         crate::query::HpoTerm,
         hpo_sim::term_term::HpoSimTermTermQuery,
         hpo_sim::term_term::HpoSimTermTermResult,
         hpo_sim::term_term::HpoSimTermTermResultEntry,
+        hpo_sim::term_term::HpoSimTermTermMatrix,
+        hpo_sim::term_term::TermTermLayout,
+        hpo_sim::family_gene::HpoSimFamilyGeneRequest,
+        hpo_sim::family_gene::Relative,
+        hpo_sim::family_gene::HpoSimFamilyGeneResult,
+        hpo_sim::family_gene::HpoSimFamilyGeneResultEntry,
+        hpo_sim::family_gene::HpoSimFamilyGeneRelativeScore,
+        hpo_sim::omim_omim::HpoSimOmimOmimQuery,
+        hpo_sim::omim_omim::HpoSimOmimOmimResult,
+        hpo_sim::omim_omim::HpoSimOmimOmimResultEntry,
+        hpo_sim::gene_gene::HpoSimGeneGeneQuery,
+        hpo_sim::gene_gene::HpoSimGeneGeneResult,
+        hpo_sim::gene_gene::HpoSimGeneGeneResultEntry,
+        patients::register::PatientsRegisterRequest,
+        patients::register::PatientsRegisterResult,
+        patients::similar::PatientsSimilarResultQuery,
+        patients::similar::PatientsSimilarResult,
+        patients::similar::PatientsSimilarResultEntry,
+        patients::mme::MmeMatchRequest,
+        patients::mme::MmeMatchResponse,
+        patients::mme::MmeMatch,
+        patients::mme::MmeScore,
+        patients::mme::MmePatient,
+        patients::mme::MmeFeature,
+        patients::mme::MmeContact,
+        beacon::BeaconSource,
+        beacon::BeaconGranularity,
+        beacon::BeaconQuery,
+        beacon::BeaconResult,
+        readyz::ReadyzStatus,
+        readyz::ReadyzResult,
+        crate::progress::Degraded,
+        crate::sources::SourceSummary,
+        crate::sources::SourceStats,
         ResultGene,
+        crate::sources::gene_info::GeneInfo,
         ResultHpoTerm,
         Match,
+        middleware::ReleaseMismatch,
+        middleware::ResponseMeta,
+        middleware::DebugTimings,
+        middleware::StageTiming,
+        middleware::QueryNormalization,
+        middleware::QueryNormalizationAction,
         crate::common::Version,
         crate::common::IcBasedOn,
+        crate::common::AnnotationPropagation,
+        crate::common::ExperimentalFeature,
+        crate::common::MappingSystem,
+        crate::algos::inheritance::InheritanceMode,
+        crate::algos::sex::Sex,
         crate::common::SimilarityMethod,
         crate::common::ScoreCombiner,
+        crate::common::ScoreNormalization,
+        crate::common::NormalizationConstants,
+        crate::sources::expression::ExpressionMode,
+        crate::common::TermGeneMethod,
+        limits::LimitReason,
+        limits::LimitViolation,
+        strict_query::UnknownParam,
     ))
 )]
 pub struct ApiDoc;
 
+/// Build the application with the API routes but the `disabled_routes` and the middleware,
+/// as served by `main` and replayed by `server replay`.
+pub fn app(
+    dbs: Data<Arc<WebServerData>>,
+    disabled_routes: &[routes::Route],
+) -> App<
+    impl ServiceFactory<
+        ServiceRequest,
+        Config = (),
+        Response = ServiceResponse<impl MessageBody>,
+        Error = actix_web::Error,
+        InitError = (),
+    >,
+> {
+    let max_body_bytes = dbs.limits.max_body_bytes;
+    App::new()
+        .app_data(dbs)
+        .app_data(
+            actix_web::web::JsonConfig::default()
+                .limit(max_body_bytes)
+                .error_handler(limits::json_error),
+        )
+        .app_data(actix_web::web::PayloadConfig::new(max_body_bytes))
+        .configure(|cfg| routes::configure(cfg, disabled_routes))
+        .service(readyz::handle)
+        .configure(|cfg| {
+            routes::register_options(cfg, "/readyz", &[Method::GET, Method::HEAD]);
+        })
+        .wrap(from_fn(middleware::add_response_meta))
+        .wrap(from_fn(middleware::answer_head))
+        .wrap(from_fn(middleware::check_expected_release))
+        .wrap(from_fn(middleware::shed_load))
+        .wrap(from_fn(middleware::log_slow_requests))
+        .wrap(from_fn(record::record_requests))
+        .wrap(from_fn(tenants::scope_tenant))
+        .wrap(from_fn(routes::add_cors_headers))
+        .wrap(Logger::default())
+}
+
 /// Main entry point for running the REST server.
 #[allow(clippy::unused_async)]
 #[actix_web::main]
 pub async fn main(args: &Args, dbs: Data<Arc<WebServerData>>) -> std::io::Result<()> {
-    let openapi = ApiDoc::openapi();
-
-    HttpServer::new(move || {
-        App::new()
-            .app_data(dbs.clone())
-            .service(hpo_genes::handle)
-            .service(hpo_terms::handle)
-            .service(hpo_omims::handle)
-            .service(hpo_sim::term_term::handle)
-            .service(hpo_sim::term_gene::handle)
+    let mut openapi = ApiDoc::openapi();
+    let disabled_routes = args.disabled_routes();
+    routes::document_disabled(&mut openapi, &disabled_routes);
+    if !disabled_routes.is_empty() {
+        tracing::info!(
+            "Disabled routes: {}",
+            disabled_routes
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+    if !dbs.query_defaults.is_empty() {
+        tracing::info!(
+            "Configured defaults for {} routes: {:?}",
+            dbs.query_defaults.len(),
+            dbs.query_defaults
+        );
+    }
+
+    let server = HttpServer::new(move || {
+        app(dbs.clone(), &disabled_routes)
+            .configure(configure_ui)
             .service(
                 utoipa_swagger_ui::SwaggerUi::new("/swagger-ui/{_:.*}")
                     .url("/api-docs/openapi.json", openapi.clone()),
             )
-            .wrap(Logger::default())
     })
-    .bind((args.listen_host.as_str(), args.listen_port))?
-    .run()
-    .await
+    .keep_alive(keep_alive(args.keep_alive_secs))
+    .client_request_timeout(Duration::from_millis(args.client_request_timeout_ms))
+    .client_disconnect_timeout(Duration::from_millis(args.client_disconnect_timeout_ms))
+    .max_connections(args.max_connections);
+
+    let addr = (args.listen_host.as_str(), args.listen_port);
+    let server = if args.http2_cleartext {
+        server.bind_auto_h2c(addr)?
+    } else {
+        server.bind(addr)?
+    };
+    server.run().await
+}
+
+/// Return the keep-alive setting for a timeout of `secs` seconds, 0 disables keep-alive.
+fn keep_alive(secs: u64) -> KeepAlive {
+    if secs == 0 {
+        KeepAlive::Disabled
+    } else {
+        KeepAlive::Timeout(Duration::from_secs(secs))
+    }
+}
+
+/// Register the embedded web UI if the `ui` feature is enabled.
+fn configure_ui(cfg: &mut ServiceConfig) {
+    #[cfg(feature = "ui")]
+    cfg.service(ui::handle);
+    #[cfg(not(feature = "ui"))]
+    let _ = cfg;
 }
 
 /// Print some hints via `tracing::info!`.
@@ -237,6 +1182,327 @@ pub fn print_hints(args: &Args) {
         args.listen_host.as_str(),
         args.listen_port
     );
+    #[cfg(feature = "ui")]
+    tracing::info!(
+        "  SEE WEB UI FOR INTERACTIVE EXPLORATION: http://{}:{}/ui/",
+        args.listen_host.as_str(),
+        args.listen_port
+    );
+}
+
+/// Compute the information content tables, with the custom table at `path_custom_ic`.
+fn compute_ic_tables(
+    ontology: &hpo::Ontology,
+    path_custom_ic: Option<&std::path::Path>,
+) -> Result<crate::algos::ic::IcTables, anyhow::Error> {
+    let custom_ic = path_custom_ic
+        .map(crate::algos::ic::IcTable::from_path)
+        .transpose()?;
+    Ok(crate::algos::ic::IcTables::new(ontology).with_custom(custom_ic))
+}
+
+/// Load the HPO OBO file from `path_hpo_dir` and build the full text index without
+/// `stopwords`.
+fn load_full_text_index(
+    path_hpo_dir: &std::path::Path,
+    translations: &crate::sources::translations::Translations,
+    stopwords: &[String],
+) -> Result<crate::index::Index, anyhow::Error> {
+    crate::index::Index::from_path(
+        crate::common::data_file(path_hpo_dir, "hp.obo", "HPO OBO")?,
+        translations,
+        stopwords,
+    )
+    .map_err(|e| anyhow::anyhow!("Error indexing HPO OBO: {}", e))
+}
+
+/// The ontology together with the indices derived from it.
+struct OntologyData {
+    /// The HPO ontology.
+    ontology: hpo::Ontology,
+    /// Information content tables.
+    ic_tables: crate::algos::ic::IcTables,
+    /// Bitset index from terms to annotated genes.
+    term_gene_index: crate::algos::term_genes::TermGeneIndex,
+    /// Bitset index from terms to annotated OMIM diseases.
+    term_disease_index: crate::algos::term_diseases::TermDiseaseIndex,
+    /// Modes of inheritance of the diseases.
+    inheritance_index: crate::algos::inheritance::InheritanceIndex,
+    /// Onset ranges of the genes.
+    onset_index: crate::algos::onset::OnsetIndex,
+}
+
+/// Load the ontology from `args.path_hpo_dir` and build the indices derived from it.
+fn load_ontology_data(args: &Args, loader: &Loader) -> Result<OntologyData, anyhow::Error> {
+    let ontology = loader.required("HPO", || load_hpo(args.path_hpo_dir()))?;
+    let ic_tables = loader.required("information content tables", || {
+        compute_ic_tables(&ontology, args.path_custom_ic.as_deref())
+    })?;
+    let (term_gene_index, term_disease_index, inheritance_index, onset_index) =
+        loader.required("term annotation indices", || {
+            Ok((
+                crate::algos::term_genes::TermGeneIndex::new(&ontology),
+                crate::algos::term_diseases::TermDiseaseIndex::new(&ontology),
+                crate::algos::inheritance::InheritanceIndex::new(&ontology),
+                crate::algos::onset::OnsetIndex::new(&ontology),
+            ))
+        })?;
+
+    Ok(OntologyData {
+        ontology,
+        ic_tables,
+        term_gene_index,
+        term_disease_index,
+        inheritance_index,
+        onset_index,
+    })
+}
+
+/// Wait for the loading thread of `handle`, propagating a panic of it.
+fn join<T>(
+    handle: std::thread::ScopedJoinHandle<'_, Result<T, anyhow::Error>>,
+) -> Result<T, anyhow::Error> {
+    handle
+        .join()
+        .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+}
+
+/// The data loaded at startup.
+struct LoadedData {
+    /// The ontology together with the indices derived from it.
+    ontology_data: OntologyData,
+    /// Annotations restricted to one sex.
+    sex_index: crate::algos::sex::SexIndex,
+    /// OMIM diseases linked to the genes.
+    disease_gene_index: crate::algos::routes::DiseaseGeneIndex,
+    /// Xlinks between NCBI and HGNC gene IDs.
+    hgnc_xlink: crate::sources::hgnc_xlink::HgncXlink,
+    /// NCBI gene information, empty if not configured.
+    gene_info: HashMap<u32, crate::sources::gene_info::GeneInfo>,
+    /// Genomic coordinates of the genes, empty if not configured.
+    gene_coords: HashMap<u32, crate::sources::gene_coords::Entry>,
+    /// Gene interaction network, if configured.
+    network: Option<crate::network::Network>,
+    /// The configured annotation sources.
+    sources: crate::sources::AnnotationSources,
+    /// Translation of disease codes, empty if not configured.
+    disease_codes: crate::sources::disease_codes::DiseaseCodes,
+    /// Rules for inferring terms from lab observations, empty if not configured.
+    lab_rules: crate::algos::labs::LabRules,
+    /// Translations of term labels, empty if not configured.
+    translations: crate::sources::translations::Translations,
+    /// Translations of Orphanet disease names, empty if not configured.
+    disease_translations: crate::sources::translations::DiseaseTranslations,
+    /// The full text index over the HPO OBO document, including the local terms.
+    full_text_index: crate::index::Index,
+    /// Local extension terms, empty if not configured.
+    local_terms: crate::sources::local_terms::LocalTerms,
+    /// Supplementary term-gene association scores by name, empty if not configured.
+    association_scores:
+        std::collections::BTreeMap<String, crate::sources::associations::AssociationScores>,
+}
+
+/// Load the data for running the server.
+///
+/// The independent stages are loaded concurrently, each reporting its progress with
+/// `loader`.  Optional stages that fail are left empty and recorded as degraded.
+#[allow(clippy::too_many_lines)]
+fn load_data(args: &Args, loader: &Loader) -> Result<LoadedData, anyhow::Error> {
+    let before_loading = std::time::Instant::now();
+    let result = std::thread::scope(|scope| -> Result<_, anyhow::Error> {
+        let ontology_data = scope.spawn(|| load_ontology_data(args, loader));
+        let sex_index = scope.spawn(|| {
+            loader.optional(
+                "sex-specific annotations",
+                Some(args.path_hpo_dir()),
+                crate::algos::sex::SexIndex::from_hpo_dir,
+            )
+        });
+        let disease_gene_index = scope.spawn(|| {
+            loader.optional(
+                "disease-gene links",
+                Some(args.path_hpo_dir()),
+                crate::algos::routes::DiseaseGeneIndex::from_hpo_dir,
+            )
+        });
+        let hgnc_xlink = scope.spawn(|| {
+            loader.required("HGNC xlink", || {
+                crate::sources::hgnc_xlink::HgncXlink::load(crate::common::data_file(
+                    args.path_hpo_dir(),
+                    "hgnc_xlink.tsv",
+                    "HGNC xlink",
+                )?)
+            })
+        });
+        let gene_info = scope.spawn(|| {
+            loader.optional(
+                "gene info",
+                args.path_gene_info.as_ref(),
+                crate::sources::gene_info::load_gene_info,
+            )
+        });
+        let gene_coords = scope.spawn(|| {
+            loader.optional(
+                "gene coordinates",
+                args.path_gene_coords.as_ref(),
+                crate::sources::gene_coords::load_gene_coords,
+            )
+        });
+        let network = scope.spawn(|| {
+            loader.optional(
+                "gene interaction network",
+                args.path_network.as_ref(),
+                crate::network::Network::from_path,
+            )
+        });
+        let sources = [
+            scope.spawn(|| {
+                crate::sources::load::<crate::sources::constraint::ConstraintTable>(
+                    loader,
+                    args.path_gene_constraint.as_deref(),
+                )
+            }),
+            scope.spawn(|| {
+                crate::sources::load::<crate::sources::clinvar::ClinvarCounts>(
+                    loader,
+                    args.path_clinvar_genes.as_deref(),
+                )
+            }),
+            scope.spawn(|| {
+                crate::sources::load::<crate::sources::expression::ExpressionMatrix>(
+                    loader,
+                    args.path_expression.as_deref(),
+                )
+            }),
+            scope.spawn(|| {
+                crate::sources::load::<crate::sources::treatments::Treatments>(
+                    loader,
+                    args.path_maxo_annotations.as_deref(),
+                )
+            }),
+            scope.spawn(|| {
+                crate::sources::load::<crate::sources::prevalence::DiseasePrevalences>(
+                    loader,
+                    args.path_orphanet_prevalence.as_deref(),
+                )
+            }),
+            scope.spawn(|| {
+                crate::sources::load::<crate::sources::panels::GenePanels>(
+                    loader,
+                    args.path_gene_panels.as_deref(),
+                )
+            }),
+        ];
+        let disease_codes = scope.spawn(|| {
+            loader.optional(
+                "Orphanet mappings",
+                args.path_orphanet_mappings.as_ref(),
+                crate::sources::disease_codes::DiseaseCodes::load,
+            )
+        });
+        let lab_rules = scope.spawn(|| {
+            loader.optional(
+                "lab rules",
+                args.path_lab_rules.as_ref(),
+                crate::algos::labs::LabRules::from_path,
+            )
+        });
+        let full_text_index = scope.spawn(|| {
+            let translations = loader
+                .optional(
+                    "translations",
+                    Some(args.path_translations.as_slice()).filter(|paths| !paths.is_empty()),
+                    crate::sources::translations::Translations::from_paths,
+                )?
+                .unwrap_or_default();
+            let stopwords = loader
+                .optional(
+                    "stopwords",
+                    args.path_stopwords.as_ref(),
+                    crate::index::read_stopwords,
+                )?
+                .unwrap_or_default();
+            let full_text_index = loader.required("full text index", || {
+                load_full_text_index(args.path_hpo_dir(), &translations, &stopwords)
+            })?;
+            Ok((translations, full_text_index))
+        });
+        let disease_translations = scope.spawn(|| {
+            loader.optional(
+                "disease translations",
+                Some(args.path_disease_translations.as_slice()).filter(|paths| !paths.is_empty()),
+                crate::sources::translations::DiseaseTranslations::from_paths,
+            )
+        });
+        let association_scores = scope.spawn(|| {
+            loader.optional(
+                "association scores",
+                Some(args.path_association_scores.as_slice()).filter(|values| !values.is_empty()),
+                crate::sources::associations::load_named,
+            )
+        });
+        let (translations, full_text_index) = join(full_text_index)?;
+        Ok(LoadedData {
+            ontology_data: join(ontology_data)?,
+            sex_index: join(sex_index)?.unwrap_or_default(),
+            disease_gene_index: join(disease_gene_index)?.unwrap_or_default(),
+            hgnc_xlink: join(hgnc_xlink)?,
+            gene_info: join(gene_info)?.unwrap_or_default(),
+            gene_coords: join(gene_coords)?.unwrap_or_default(),
+            network: join(network)?,
+            sources: sources
+                .into_iter()
+                .filter_map(|source| join(source).transpose())
+                .collect::<Result<_, _>>()?,
+            disease_codes: join(disease_codes)?.unwrap_or_default(),
+            lab_rules: join(lab_rules)?.unwrap_or_default(),
+            translations,
+            disease_translations: join(disease_translations)?.unwrap_or_default(),
+            full_text_index,
+            local_terms: crate::sources::local_terms::LocalTerms::default(),
+            association_scores: join(association_scores)?.unwrap_or_default(),
+        })
+    })?;
+
+    let result = load_local_terms(args, loader, result)?;
+    for source in result.sources.iter() {
+        let stats = source.stats();
+        tracing::info!(
+            entries = stats.entries,
+            memory_bytes = stats.memory_bytes,
+            "loaded annotation source {}",
+            source.name()
+        );
+    }
+    tracing::info!(
+        stages = loader.progress.completed(),
+        "... done loading all data in {:?}",
+        before_loading.elapsed()
+    );
+    Ok(result)
+}
+
+/// Load the local terms into `data` if configured.
+///
+/// The local terms are checked against the ontology and added to the index.
+fn load_local_terms(
+    args: &Args,
+    loader: &Loader,
+    data: LoadedData,
+) -> Result<LoadedData, anyhow::Error> {
+    let local_terms = loader.optional("local terms", args.path_local_terms.as_ref(), |path| {
+        crate::sources::local_terms::LocalTerms::from_path(path, &data.ontology_data.ontology)
+    })?;
+    Ok(match local_terms {
+        Some(local_terms) => {
+            data.full_text_index.add_local_terms(&local_terms)?;
+            LoadedData {
+                local_terms,
+                ..data
+            }
+        }
+        None => data,
+    })
 }
 
 /// Main entry point for `run-server` sub command.
@@ -253,43 +1519,15 @@ pub fn run(args_common: &crate::common::Args, args: &Args) -> Result<(), anyhow:
         env_logger::init_from_env(env_logger::Env::new().default_filter_or("info"));
     }
 
-    // Load data that we need for running the server.
-    tracing::info!("Loading HPO...");
-    let before_loading = std::time::Instant::now();
-    let ontology = load_hpo(&args.path_hpo_dir)?;
-    tracing::info!("...done loading HPO in {:?}", before_loading.elapsed());
-
-    tracing::info!("Loading HGNC xlink...");
-    let before_load_xlink = std::time::Instant::now();
-    let path_hgnc_xlink = format!("{}/hgnc_xlink.tsv", args.path_hpo_dir);
-    let ncbi_to_hgnc = crate::common::hgnc_xlink::load_ncbi_to_hgnc(path_hgnc_xlink)?;
-    let hgnc_to_ncbi = crate::common::hgnc_xlink::inverse_hashmap(&ncbi_to_hgnc);
-    tracing::info!(
-        "... done loading HGNC xlink in {:?}",
-        before_load_xlink.elapsed()
-    );
-
-    tracing::info!("Loading HPO OBO...");
-    let before_load_obo = std::time::Instant::now();
-    let hpo_doc = fastobo::from_file(format!("{}/{}", &args.path_hpo_dir, "hp.obo"))
-        .map_err(|e| anyhow::anyhow!("Error loading HPO OBO: {}", e))?;
-    tracing::info!(
-        "... done loading HPO OBO in {:?}",
-        before_load_obo.elapsed()
-    );
-
-    tracing::info!("Indexing OBO...");
-    let before_index_obo = std::time::Instant::now();
-    let full_text_index = crate::index::Index::new(hpo_doc)
-        .map_err(|e| anyhow::anyhow!("Error indexing HPO OBO: {}", e))?;
-    tracing::info!("... done indexing OBO in {:?}", before_index_obo.elapsed());
+    if let Some(path) = &args.generic_obo {
+        return generic::run(args, path);
+    }
 
-    let data = actix_web::web::Data::new(Arc::new(WebServerData {
-        ontology,
-        ncbi_to_hgnc,
-        hgnc_to_ncbi,
-        full_text_index,
-    }));
+    // Load data that we need for running the server.
+    let data = actix_web::web::Data::new(Arc::new(load_web_server_data(
+        args,
+        crate::progress::Mode::Logs,
+    )?));
 
     // Print the server URL and some hints (the latter: unless suppressed).
     print_hints(args);
@@ -299,3 +1537,298 @@ pub fn run(args_common: &crate::common::Args, args: &Args) -> Result<(), anyhow:
     tracing::info!("All done. Have a nice day!");
     Ok(())
 }
+
+/// Verify the data directory and load the data for running the server, reporting the
+/// progress in `mode`.
+///
+/// # Errors
+///
+/// In the case that the data directory failed verification or loading failed.
+#[allow(clippy::too_many_lines)]
+pub fn load_web_server_data(
+    args: &Args,
+    mode: crate::progress::Mode,
+) -> Result<WebServerData, anyhow::Error> {
+    if args.generic_obo.is_some() {
+        anyhow::bail!("the HPO data is not loaded in generic ontology mode (--generic-obo)");
+    }
+    crate::data::verify::check_on_startup(args.path_hpo_dir(), args.verify_data)?;
+
+    let loader = Loader::new(mode, args.require_optional_data);
+
+    let LoadedData {
+        ontology_data:
+            OntologyData {
+                ontology,
+                ic_tables,
+                term_gene_index,
+                term_disease_index,
+                inheritance_index,
+                onset_index,
+            },
+        sex_index,
+        disease_gene_index,
+        hgnc_xlink,
+        gene_info,
+        gene_coords,
+        network,
+        sources,
+        disease_codes,
+        lab_rules,
+        translations,
+        disease_translations,
+        full_text_index,
+        local_terms,
+        association_scores,
+    } = load_data(args, &loader)?;
+    let tenants = args
+        .path_tenants
+        .as_ref()
+        .map(|path| loader.required("tenants", || tenants::Tenants::load(path, &ic_tables)))
+        .transpose()?
+        .unwrap_or_default();
+    let disk_cache = args
+        .cache_dir
+        .as_ref()
+        .map(|dir| {
+            loader.required("cache directory", || {
+                crate::cache::DiskCache::new(dir, args.cache_max_mb * 1024 * 1024)
+            })
+        })
+        .transpose()?;
+    let (sim_seed, sim_pvalues) = loader
+        .optional(
+            "score distributions",
+            args.path_sim_pvalues.as_ref(),
+            |path| {
+                crate::algos::simulation::decode_artifact(
+                    &std::fs::read(path)?,
+                    &ontology.hpo_version(),
+                )
+            },
+        )?
+        .unwrap_or_default();
+    let query_defaults = args
+        .path_config
+        .as_ref()
+        .map(|path| loader.required("configuration", || defaults::QueryDefaults::load(path)))
+        .transpose()?
+        .unwrap_or_default();
+
+    Ok(WebServerData {
+        ontology,
+        hgnc_xlink,
+        gene_info,
+        gene_coords,
+        network,
+        sources,
+        expression_penalty: args.expression_penalty,
+        disease_codes,
+        lab_inference: Box::new(lab_rules),
+        translations,
+        disease_translations,
+        association_scores,
+        experimental_allowlist: args.experimental_allowlist.clone(),
+        full_text_index,
+        local_terms,
+        annotation_date: crate::common::load_annotation_date(args.path_hpo_dir()),
+        ic_tables,
+        term_gene_index,
+        term_disease_index,
+        inheritance_index,
+        onset_index,
+        onset_penalty: args.onset_penalty,
+        ensemble_methods: args.ensemble_methods.clone(),
+        patient_registry: args
+            .enable_patient_registry
+            .then(patients::PatientRegistry::default),
+        mme_config: patients::mme::MmeConfig::from_args(args),
+        beacon_config: beacon::BeaconConfig::from_args(args),
+        limits: limits::Limits::from_args(args),
+        sex_index,
+        disease_gene_index,
+        annotation_propagation: args.annotation_propagation,
+        export_config: hpo_ontology::ExportConfig {
+            token: args.export_token.clone(),
+            max_bytes: args.export_max_bytes,
+        },
+        slow_request_threshold: args
+            .slow_request_threshold_ms
+            .map(std::time::Duration::from_millis),
+        max_memory_bytes: args.max_memory_mb.map(|mb| mb * 1024 * 1024),
+        degraded: loader.into_degraded(),
+        tenants,
+        report_signing_key: args.report_signing_key.clone(),
+        query_defaults,
+        record_requests: args.record_requests.clone(),
+        simulation_cache: crate::algos::simulation::SimulationCache::new(
+            disk_cache,
+            args.p_value_simulations,
+        )
+        .with_memory_budget(args.max_memory_mb.map(|mb| mb * 1024 * 1024))
+        .with_precomputed(sim_seed, sim_pvalues)
+        .with_allowed_seeds(args.p_value_seeds.iter().copied()),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use clap::CommandFactory as _;
+    use serde::de::{value, IntoDeserializer as _};
+
+    #[test]
+    fn args_env() {
+        let command = super::Args::command();
+
+        for arg in command.get_arguments() {
+            let Some(long) = arg.get_long() else {
+                continue;
+            };
+            if matches!(long, "help" | "version") {
+                continue;
+            }
+            let expected = format!("SEAHORSE_{}", long.replace('-', "_").to_uppercase());
+            assert_eq!(
+                arg.get_env().and_then(|env| env.to_str()),
+                Some(expected.as_str()),
+                "--{long}"
+            );
+        }
+    }
+
+    #[test]
+    fn disabled_routes() -> Result<(), anyhow::Error> {
+        use clap::Parser as _;
+
+        let args = super::Args::try_parse_from(["run", "--path-hpo-dir", "tests/data/hpo"])?;
+        assert_eq!(
+            args.disabled_routes(),
+            vec![super::routes::Route::HpoOntologyExport]
+        );
+
+        let args = super::Args::try_parse_from([
+            "run",
+            "--path-hpo-dir",
+            "tests/data/hpo",
+            "--export-token",
+            "secret",
+        ])?;
+        assert!(args.disabled_routes().is_empty());
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case("--onset-penalty")]
+    #[case("--expression-penalty")]
+    fn penalties(#[case] flag: &str) {
+        use clap::Parser as _;
+
+        let parse = |penalty: &str| {
+            super::Args::try_parse_from(["run", "--path-hpo-dir", "tests/data/hpo", flag, penalty])
+                .map(|args| match flag {
+                    "--onset-penalty" => args.onset_penalty,
+                    _ => args.expression_penalty,
+                })
+        };
+
+        assert_eq!(parse("0").ok(), Some(0.0));
+        assert_eq!(parse("1").ok(), Some(1.0));
+        assert!(parse("1.5").is_err());
+        assert!(parse("-0.1").is_err());
+        assert!(parse("NaN").is_err());
+    }
+
+    #[test]
+    fn vec_str_deserialize_allocations() -> Result<(), value::Error> {
+        let input = "HP:0001250,HP:0001263,HP:0001290";
+        let (values, allocations) =
+            crate::alloc_count::count(|| super::vec_str_deserialize(input.into_deserializer()));
+
+        // One allocation for the list and one per value.
+        assert_eq!(values?.len(), 3);
+        assert_eq!(allocations, 4);
+
+        Ok(())
+    }
+
+    #[test]
+    fn comma_separated_allocations() -> Result<(), anyhow::Error> {
+        let input = "HP:0001250,HP:0001263,HP:0001290".to_string();
+        let (values, allocations) = crate::alloc_count::count(|| {
+            <super::CommaSeparated as serde::Deserialize>::deserialize(
+                serde::de::IntoDeserializer::<value::Error>::into_deserializer(input),
+            )
+        });
+
+        // The owned input is taken over and split without allocating.
+        let values = values?;
+        assert_eq!(allocations, 0);
+        let (strs, allocations) = crate::alloc_count::count(|| values.to_strs());
+        assert_eq!(strs.as_slice(), ["HP:0001250", "HP:0001263", "HP:0001290"]);
+        assert_eq!(allocations, 0);
+        assert_eq!(
+            serde_json::to_value(&values)?,
+            serde_json::json!(["HP:0001250", "HP:0001263", "HP:0001290"])
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn term_ids_allocations() -> Result<(), anyhow::Error> {
+        let values = (1..=16).map(|i| format!("HP:{i:07}")).collect::<Vec<_>>();
+        let (term_ids, allocations) = crate::alloc_count::count(|| {
+            values
+                .iter()
+                .map(|value| crate::common::parse_term_id(value))
+                .collect::<Result<super::TermIds, _>>()
+        });
+
+        // Typical term lists are parsed without allocating.
+        assert_eq!(term_ids?.len(), 16);
+        assert_eq!(allocations, 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn result_gene_with_gene_info() {
+        let gene_info = crate::sources::gene_info::GeneInfo {
+            description: Some("TDP-glucose 4,6-dehydratase".to_string()),
+            gene_type: Some("protein-coding".to_string()),
+            map_location: Some("13q32.1".to_string()),
+        };
+        let gene_infos = std::collections::HashMap::from([(23483, gene_info.clone())]);
+        let gene = |ncbi_gene_id| super::ResultGene {
+            ncbi_gene_id,
+            gene_symbol: "TGDS".to_string(),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            gene(23483).with_gene_info(Some(&gene_infos)).gene_info,
+            Some(gene_info)
+        );
+        assert_eq!(gene(23483).with_gene_info(None).gene_info, None);
+        assert_eq!(gene(7273).with_gene_info(Some(&gene_infos)).gene_info, None);
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn vec_str_deserialize_fuzz(input in crate::fuzz::string(24)) {
+            let deserializer: value::StrDeserializer<'_, value::Error> =
+                input.as_str().into_deserializer();
+
+            // Splitting at commas loses nothing.
+            let values = super::vec_str_deserialize(deserializer)?;
+            proptest::prop_assert!(!values.is_empty());
+            proptest::prop_assert_eq!(values.join(","), input.as_str());
+            let values = super::option_vec_str_deserialize(deserializer)?;
+            proptest::prop_assert_eq!(
+                values.map(|values| values.join(",")),
+                Some(input).filter(|input| !input.is_empty())
+            );
+        }
+    }
+}
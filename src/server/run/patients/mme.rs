@@ -0,0 +1,399 @@
+//! Entry point `/mme/match` implementing the Matchmaker Exchange (MME) API v1 on top of
+//! the patient registry.
+
+use std::sync::Arc;
+
+use actix_web::{
+    http::{header, StatusCode},
+    post,
+    web::{Bytes, Data, Path},
+    HttpRequest, HttpResponse,
+};
+
+use subtle::ConstantTimeEq as _;
+
+use super::super::{
+    middleware::{ComputeTimer, ResolvedFrom},
+    CustomError,
+};
+use crate::{
+    common::{
+        AnnotationPropagation, IcBasedOn, NormalizationConstants, ScoreNormalization,
+        SimilarityMethod,
+    },
+    server::run::{tenants::TenantScope, WebServerData},
+};
+
+/// Media type of MME API v1 requests and responses.
+pub const MME_CONTENT_TYPE: &str = "application/vnd.ga4gh.matchmaker.v1.0+json";
+
+/// Header carrying the MME authentication token.
+const AUTH_TOKEN_HEADER: &str = "X-Auth-Token";
+
+/// Maximal number of matches returned.
+const MAX_RESULTS: usize = 20;
+
+/// Settings for the MME endpoint.
+#[derive(Debug, Clone, Default)]
+pub struct MmeConfig {
+    /// Token required in the `X-Auth-Token` header, if any.
+    pub token: Option<String>,
+    /// Contact reported for the matched patients, if any.
+    pub contact: Option<MmeContact>,
+}
+
+impl MmeConfig {
+    /// Construct from the command line arguments of the server.
+    pub fn from_args(args: &super::super::Args) -> Self {
+        Self {
+            token: args.mme_token.clone(),
+            contact: args
+                .mme_contact_name
+                .clone()
+                .zip(args.mme_contact_href.clone())
+                .map(|(name, href)| MmeContact {
+                    name,
+                    href,
+                    institution: None,
+                }),
+        }
+    }
+}
+
+/// Contact of an MME patient.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, utoipa::ToSchema)]
+pub struct MmeContact {
+    /// Name of the contact.
+    pub name: String,
+    /// URL to contact, e.g., a `mailto:` URL.
+    pub href: String,
+    /// The institution of the contact.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub institution: Option<String>,
+}
+
+/// Phenotypic feature of an MME patient.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, utoipa::ToSchema)]
+pub struct MmeFeature {
+    /// The term ID, e.g., `HP:0010442`.
+    pub id: String,
+    /// Whether the feature was observed, `yes` or `no`, observed if missing.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub observed: Option<String>,
+    /// The term label.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+}
+
+/// Patient of the MME API, genomic features are ignored.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, utoipa::ToSchema)]
+pub struct MmePatient {
+    /// The patient ID.
+    pub id: String,
+    /// The patient label.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// The contact for the patient.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub contact: Option<MmeContact>,
+    /// The phenotypic features of the patient.
+    #[serde(default)]
+    pub features: Vec<MmeFeature>,
+}
+
+/// Request body for `handle`.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, utoipa::ToSchema)]
+pub struct MmeMatchRequest {
+    /// The patient to find matches for.
+    pub patient: MmePatient,
+}
+
+/// Score of an MME match.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, utoipa::ToSchema)]
+pub struct MmeScore {
+    /// The phenotype similarity between 0 and 1.
+    pub patient: f32,
+}
+
+/// Match of the MME API.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, utoipa::ToSchema)]
+pub struct MmeMatch {
+    /// The score of the match.
+    pub score: MmeScore,
+    /// The matched patient.
+    pub patient: MmePatient,
+}
+
+/// Response body for `handle`.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, utoipa::ToSchema)]
+pub struct MmeMatchResponse {
+    /// The matches, best first.
+    pub results: Vec<MmeMatch>,
+}
+
+/// Check the `X-Auth-Token` header against the configured token.
+///
+/// The token is compared in constant time.  Without a configured token, the endpoint is
+/// not served at all.
+fn check_token(req: &HttpRequest, config: &MmeConfig) -> Result<(), CustomError> {
+    let Some(token) = &config.token else {
+        return Err(CustomError::with_status(
+            StatusCode::FORBIDDEN,
+            anyhow::anyhow!("the Matchmaker Exchange endpoint requires --mme-token"),
+        ));
+    };
+    let given = req
+        .headers()
+        .get(AUTH_TOKEN_HEADER)
+        .and_then(|value| value.to_str().ok());
+    if given.is_some_and(|given| bool::from(given.as_bytes().ct_eq(token.as_bytes()))) {
+        Ok(())
+    } else {
+        Err(CustomError::with_status(
+            StatusCode::UNAUTHORIZED,
+            anyhow::anyhow!("missing or invalid X-Auth-Token"),
+        ))
+    }
+}
+
+/// Check that the request is sent as MME v1 or plain JSON.
+fn check_content_type(req: &HttpRequest) -> Result<(), CustomError> {
+    let content_type = req
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .map(str::trim);
+    if matches!(content_type, Some(MME_CONTENT_TYPE | "application/json")) {
+        Ok(())
+    } else {
+        Err(CustomError::with_status(
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            anyhow::anyhow!("content type must be {}", MME_CONTENT_TYPE),
+        ))
+    }
+}
+
+/// Find the registered patients matching a patient by the Matchmaker Exchange API v1.
+///
+/// The patients are scored by their phenotypic features with the Phenomizer score
+/// divided by the maximal information content.  Features not observed are ignored, as
+/// are genomic features.  The patient registry must be enabled.
+#[allow(clippy::unused_async)]
+#[utoipa::path(
+    post,
+    operation_id = "mmeMatch",
+    request_body(content = MmeMatchRequest, content_type = "application/vnd.ga4gh.matchmaker.v1.0+json"),
+    responses(
+        (status = 200, description = "The matches.", body = MmeMatchResponse, content_type = "application/vnd.ga4gh.matchmaker.v1.0+json"),
+        (status = 400, description = "The request is malformed.", body = CustomError),
+        (status = 401, description = "Missing or invalid X-Auth-Token.", body = CustomError),
+        (status = 403, description = "No --mme-token is configured.", body = CustomError),
+        (status = 404, description = "The patient registry is not enabled.", body = CustomError),
+        (status = 413, description = "The request body exceeds the size limit.", body = CustomError),
+        (status = 415, description = "The content type is not supported.", body = CustomError),
+        (status = 422, description = "The request exceeds a size or complexity limit.", body = CustomError),
+        (status = 500, description = "The server encountered an error.", body = CustomError)
+    )
+)]
+#[post("/api/v1/mme/match")]
+async fn handle(
+    req: HttpRequest,
+    data: Data<Arc<WebServerData>>,
+    tenant: TenantScope,
+    _path: Path<()>,
+    body: Bytes,
+    resolved_from: ResolvedFrom,
+    _timer: ComputeTimer,
+) -> actix_web::Result<HttpResponse, CustomError> {
+    check_token(&req, &data.mme_config)?;
+    let ic_tables = tenant.ic_tables(&data);
+    let registry = super::registry(&data, &tenant)?;
+    check_content_type(&req)?;
+    let request: MmeMatchRequest = serde_json::from_slice(&body)
+        .map_err(|e| CustomError::with_status(StatusCode::BAD_REQUEST, anyhow::anyhow!(e)))?;
+
+    let observed = request
+        .patient
+        .features
+        .iter()
+        .filter(|feature| feature.observed.as_deref() != Some("no"))
+        .map(|feature| feature.id.clone())
+        .collect::<Vec<_>>();
+    let terms = super::parse_terms(&observed, &data, &resolved_from)?;
+
+    let normalization = NormalizationConstants::new(
+        ScoreNormalization::MaxIc,
+        SimilarityMethod::Resnik,
+        ic_tables.range(IcBasedOn::Gene, AnnotationPropagation::Ancestors),
+    );
+    let results = registry
+        .similar(&terms, &data.ontology, MAX_RESULTS + 1)
+        .into_iter()
+        .filter(|(id, _)| *id != request.patient.id)
+        .take(MAX_RESULTS)
+        .map(|(id, score)| {
+            let features = registry
+                .terms(&id)
+                .unwrap_or_default()
+                .iter()
+                .map(|term_id| MmeFeature {
+                    id: term_id.to_string(),
+                    observed: Some("yes".to_string()),
+                    label: data
+                        .ontology
+                        .hpo(term_id)
+                        .map(|term| term.name().to_string()),
+                })
+                .collect();
+            MmeMatch {
+                score: MmeScore {
+                    patient: normalization
+                        .as_ref()
+                        .map_or(score, |constants| constants.apply(score)),
+                },
+                patient: MmePatient {
+                    id,
+                    label: None,
+                    contact: data.mme_config.contact.clone(),
+                    features,
+                },
+            }
+        })
+        .collect();
+
+    Ok(HttpResponse::Ok()
+        .content_type(MME_CONTENT_TYPE)
+        .json(MmeMatchResponse { results }))
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    #[test]
+    fn check_token_and_content_type() {
+        let config = super::MmeConfig {
+            token: Some("secret".to_string()),
+            contact: None,
+        };
+        let req = actix_web::test::TestRequest::default()
+            .insert_header(("X-Auth-Token", "secret"))
+            .insert_header(("Content-Type", super::MME_CONTENT_TYPE))
+            .to_http_request();
+        assert!(super::check_token(&req, &config).is_ok());
+        assert!(super::check_content_type(&req).is_ok());
+        assert_eq!(
+            super::check_token(&req, &super::MmeConfig::default())
+                .map_err(|err| actix_web::ResponseError::status_code(&err)),
+            Err(actix_web::http::StatusCode::FORBIDDEN)
+        );
+
+        let req = actix_web::test::TestRequest::default()
+            .insert_header(("X-Auth-Token", "wrong"))
+            .insert_header(("Content-Type", "text/plain"))
+            .to_http_request();
+        assert!(super::check_token(&req, &config).is_err());
+        assert!(super::check_content_type(&req).is_err());
+
+        let req = actix_web::test::TestRequest::default()
+            .insert_header(("X-Auth-Token", "secret2"))
+            .to_http_request();
+        assert!(super::check_token(&req, &config).is_err());
+    }
+
+    #[actix_web::test]
+    async fn mme_match_auth_before_registry() {
+        // Without the registry, unauthorized requests are still rejected with 401.
+        let data = crate::server::run::WebServerData {
+            patient_registry: None,
+            mme_config: super::MmeConfig {
+                token: Some("secret".to_string()),
+                contact: None,
+            },
+            ..crate::server::run::hpo_genes::test::load_web_server_data()
+        };
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(actix_web::web::Data::new(Arc::new(data)))
+                .service(super::handle),
+        )
+        .await;
+        let req = actix_web::test::TestRequest::post()
+            .uri("/api/v1/mme/match")
+            .to_request();
+        let resp = actix_web::test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), actix_web::http::StatusCode::UNAUTHORIZED);
+    }
+
+    #[actix_web::test]
+    async fn mme_match() -> Result<(), anyhow::Error> {
+        let web_server_data = Arc::new(crate::server::run::WebServerData {
+            mme_config: super::MmeConfig {
+                token: Some("secret".to_string()),
+                contact: None,
+            },
+            ..crate::server::run::hpo_genes::test::load_web_server_data()
+        });
+        let registry = web_server_data
+            .patient_registry
+            .as_ref()
+            .expect("registry enabled");
+        registry.register(
+            "mme-1".to_string(),
+            [
+                hpo::HpoTermId::try_from("HP:0010442")?,
+                hpo::HpoTermId::try_from("HP:0000347")?,
+            ]
+            .into_iter()
+            .collect(),
+        );
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(actix_web::web::Data::new(web_server_data.clone()))
+                .service(super::handle),
+        )
+        .await;
+
+        let req = actix_web::test::TestRequest::post()
+            .uri("/api/v1/mme/match")
+            .insert_header(("Content-Type", super::MME_CONTENT_TYPE))
+            .insert_header(("X-Auth-Token", "secret"))
+            .set_payload(
+                serde_json::json!({
+                    "patient": {
+                        "id": "query",
+                        "contact": {"name": "Jane Doe", "href": "mailto:jane@example.com"},
+                        "features": [
+                            {"id": "HP:0010442"},
+                            {"id": "HP:0000347", "observed": "yes"},
+                            {"id": "HP:0001780", "observed": "no"},
+                        ],
+                        "genomicFeatures": [{"gene": {"id": "TGDS"}}],
+                    }
+                })
+                .to_string(),
+            )
+            .to_request();
+        let resp = actix_web::test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+        assert_eq!(
+            resp.headers()
+                .get("Content-Type")
+                .map(actix_web::http::header::HeaderValue::as_bytes),
+            Some(super::MME_CONTENT_TYPE.as_bytes())
+        );
+        let body: super::MmeMatchResponse = actix_web::test::read_body_json(resp).await;
+
+        let result = body
+            .results
+            .iter()
+            .find(|result| result.patient.id == "mme-1")
+            .expect("registered patient must match");
+        assert!((0.0..=1.0).contains(&result.score.patient));
+        assert_eq!(result.patient.features.len(), 2);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,186 @@
+//! Implementation of the opt-in patient registry endpoints `/patients`,
+//! `/patients/similar`, and `/mme/match`.
+
+use std::{collections::BTreeMap, sync::RwLock};
+
+use actix_web::http::StatusCode;
+use hpo::{term::HpoGroup, Ontology};
+
+use super::{middleware::ResolvedFrom, tenants::TenantScope, CustomError, WebServerData};
+use crate::algos::phenomizer;
+
+pub mod mme;
+pub mod register;
+pub mod similar;
+
+/// In-memory registry of patients by their terms.
+#[derive(Debug, Default)]
+pub struct PatientRegistry {
+    /// The terms of the registered patients by patient ID.
+    patients: RwLock<BTreeMap<String, HpoGroup>>,
+}
+
+impl PatientRegistry {
+    /// Register the patient with `id` and `terms`, replacing a patient with the same ID.
+    ///
+    /// Returns the number of registered patients.
+    ///
+    /// # Panics
+    ///
+    /// If the lock is poisoned.
+    pub fn register(&self, id: String, terms: HpoGroup) -> usize {
+        let mut patients = self.patients.write().expect("poisoned lock");
+        patients.insert(id, terms);
+        patients.len()
+    }
+
+    /// Return the number of registered patients.
+    ///
+    /// # Panics
+    ///
+    /// If the lock is poisoned.
+    pub fn len(&self) -> usize {
+        self.patients.read().expect("poisoned lock").len()
+    }
+
+    /// Return whether no patients are registered.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Return the number of registered patients whose terms pass `filter`.
+    ///
+    /// # Panics
+    ///
+    /// If the lock is poisoned.
+    pub fn count(&self, filter: impl Fn(&HpoGroup) -> bool) -> usize {
+        self.patients
+            .read()
+            .expect("poisoned lock")
+            .values()
+            .filter(|terms| filter(terms))
+            .count()
+    }
+
+    /// Return the terms of the patient with `id`, if registered.
+    ///
+    /// # Panics
+    ///
+    /// If the lock is poisoned.
+    pub fn terms(&self, id: &str) -> Option<HpoGroup> {
+        self.patients
+            .read()
+            .expect("poisoned lock")
+            .get(id)
+            .cloned()
+    }
+
+    /// Return the IDs and Phenomizer scores of the `max_results` registered patients most
+    /// similar to `terms`, best first.
+    ///
+    /// # Panics
+    ///
+    /// If the lock is poisoned.
+    pub fn similar(
+        &self,
+        terms: &HpoGroup,
+        hpo: &Ontology,
+        max_results: usize,
+    ) -> Vec<(String, f32)> {
+        let patients = self.patients.read().expect("poisoned lock");
+        let mut result = patients
+            .iter()
+            .map(|(id, patient)| (id.clone(), phenomizer::score(terms, patient, hpo)))
+            .collect::<Vec<_>>();
+        result.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        result.truncate(max_results);
+        result
+    }
+}
+
+/// Return the patient registry of the server or the tenant, or 404 if it is not enabled.
+pub(super) fn registry<'a>(
+    data: &'a WebServerData,
+    tenant: &TenantScope,
+) -> Result<&'a PatientRegistry, CustomError> {
+    tenant.patient_registry(data).ok_or_else(|| {
+        CustomError::with_status(
+            StatusCode::NOT_FOUND,
+            anyhow::anyhow!("the patient registry is not enabled on this server"),
+        )
+    })
+}
+
+/// Translate the term IDs into an `HpoGroup` of the terms for scoring.
+fn parse_terms(
+    values: &[String],
+    data: &WebServerData,
+    resolved_from: &ResolvedFrom,
+) -> Result<HpoGroup, CustomError> {
+    Ok(super::to_hpo_group(
+        super::parse_term_ids(values, data, resolved_from)?,
+        data,
+        resolved_from,
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use hpo::{annotations::GeneId, builder::Builder, term::HpoGroup, HpoTermId, Ontology};
+
+    use super::PatientRegistry;
+
+    /// Build an ontology with "Left" and "Right" below "Phenotypic abnormality" and "Eye"
+    /// below "Left".
+    ///
+    /// Gene 10 is annotated with "Eye" and gene 20 with "Right".
+    fn build_ontology() -> Ontology {
+        let mut builder = Builder::new();
+        builder.new_term("All", 1u32);
+        builder.new_term("Left", 2u32);
+        builder.new_term("Right", 3u32);
+        builder.new_term("Eye", 4u32);
+        builder.new_term("Phenotypic abnormality", 118u32);
+        let mut builder = builder.terms_complete();
+        builder.add_parent(1u32, 118u32).expect("term must exist");
+        builder.add_parent(118u32, 2u32).expect("term must exist");
+        builder.add_parent(118u32, 3u32).expect("term must exist");
+        builder.add_parent(2u32, 4u32).expect("term must exist");
+        let mut builder = builder.connect_all_terms();
+        for (gene_id, name, term) in [(10, "GENE10", 4u32), (20, "GENE20", 3u32)] {
+            builder
+                .annotate_gene(GeneId::from(gene_id), name, term.into())
+                .expect("term must exist");
+        }
+        builder
+            .calculate_information_content()
+            .expect("could not compute IC")
+            .build_with_defaults()
+            .expect("could not build ontology")
+    }
+
+    fn group(ids: &[u32]) -> HpoGroup {
+        ids.iter().map(|id| HpoTermId::from_u32(*id)).collect()
+    }
+
+    #[test]
+    fn register_and_similar() {
+        let hpo = build_ontology();
+        let registry = PatientRegistry::default();
+        assert!(registry.is_empty());
+
+        assert_eq!(registry.register("right".into(), group(&[3])), 1);
+        assert_eq!(registry.register("eye".into(), group(&[2])), 2);
+        // Registering an existing ID replaces the patient.
+        assert_eq!(registry.register("eye".into(), group(&[4])), 2);
+
+        let result = registry.similar(&group(&[4]), &hpo, 10);
+        assert_eq!(
+            result.iter().map(|(id, _)| id.as_str()).collect::<Vec<_>>(),
+            vec!["eye", "right"]
+        );
+        assert!(result[0].1 > result[1].1);
+        assert_eq!(registry.similar(&group(&[4]), &hpo, 1).len(), 1);
+        assert_eq!(registry.count(|terms| terms.contains(&4u32.into())), 1);
+    }
+}
@@ -0,0 +1,74 @@
+//! Entry point `/patients` that registers a patient with the patient registry.
+
+use std::sync::Arc;
+
+use actix_web::{
+    post,
+    web::{Data, Json, Path},
+};
+
+use super::super::{
+    middleware::{ComputeTimer, ResolvedFrom},
+    CustomError,
+};
+use crate::server::run::{tenants::TenantScope, WebServerData};
+
+/// Request body for `handle`.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, utoipa::ToSchema)]
+pub struct PatientsRegisterRequest {
+    /// The patient ID, a patient with the same ID is replaced.
+    pub id: String,
+    /// The patient's terms.
+    pub terms: Vec<String>,
+}
+
+/// Result for `handle`.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, utoipa::ToSchema)]
+pub struct PatientsRegisterResult {
+    /// The patient ID.
+    pub id: String,
+    /// The registered terms in canonical form, unknown terms are dropped.
+    pub terms: Vec<String>,
+    /// The number of registered patients.
+    pub n_patients: usize,
+}
+
+/// Register a patient with the patient registry.
+///
+/// The registry is held in memory and must be enabled with `--enable-patient-registry`.
+#[allow(clippy::unused_async)]
+#[utoipa::path(
+    post,
+    operation_id = "patientsRegister",
+    request_body = PatientsRegisterRequest,
+    responses(
+        (status = 200, description = "The patient was registered.", body = PatientsRegisterResult),
+        (status = 400, description = "A term ID is malformed.", body = CustomError),
+        (status = 404, description = "The patient registry is not enabled.", body = CustomError),
+        (status = 413, description = "The request body exceeds the size limit.", body = CustomError),
+        (status = 422, description = "The request exceeds a size or complexity limit.", body = CustomError),
+        (status = 500, description = "The server encountered an error.", body = CustomError)
+    )
+)]
+#[post("/api/v1/patients")]
+async fn handle(
+    data: Data<Arc<WebServerData>>,
+    tenant: TenantScope,
+    _path: Path<()>,
+    request: Json<PatientsRegisterRequest>,
+    resolved_from: ResolvedFrom,
+    _timer: ComputeTimer,
+) -> actix_web::Result<Json<PatientsRegisterResult>, CustomError> {
+    let registry = super::registry(&data, &tenant)?;
+    let terms = super::parse_terms(&request.terms, &data, &resolved_from)?;
+
+    let PatientsRegisterRequest { id, .. } = request.into_inner();
+    let term_ids = terms.iter().map(|term_id| term_id.to_string()).collect();
+    let n_patients = registry.register(id.clone(), terms);
+
+    Ok(Json(PatientsRegisterResult {
+        id,
+        terms: term_ids,
+        n_patients,
+    }))
+}
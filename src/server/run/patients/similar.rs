@@ -0,0 +1,155 @@
+//! Entry point `/patients/similar` that retrieves the registered patients most similar
+//! to a set of terms.
+
+use std::sync::Arc;
+
+use actix_web::{
+    get,
+    web::{Data, Json, Path},
+};
+
+use super::super::{
+    middleware::{ComputeTimer, ResolvedFrom},
+    CustomError,
+};
+use crate::server::run::strict_query::StrictQuery;
+use crate::server::run::tenants::TenantScope;
+use crate::{common::Version, server::run::WebServerData};
+
+/// Parameters for `handle`.
+///
+/// - `terms` -- set of terms to compare the registered patients to
+/// - `max_results` -- maximal number of patients to return
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, utoipa::IntoParams)]
+#[serde(deny_unknown_fields)]
+#[into_params(parameter_in = Query)]
+pub struct PatientsSimilarQuery {
+    /// Set of terms to compare the registered patients to.
+    #[serde(deserialize_with = "super::super::vec_str_deserialize")]
+    pub terms: Vec<String>,
+    /// Maximal number of patients to return.
+    #[serde(default = "_default_max_results")]
+    pub max_results: usize,
+}
+
+/// Return default of `PatientsSimilarQuery::max_results`.
+fn _default_max_results() -> usize {
+    10
+}
+
+/// Query as sent together with the response, with the terms in canonical form.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, utoipa::ToSchema)]
+pub struct PatientsSimilarResultQuery {
+    /// The terms in canonical form, unknown terms are dropped.
+    pub terms: Vec<String>,
+    /// Maximal number of patients to return.
+    pub max_results: usize,
+}
+
+/// Result entry for `handle`.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, utoipa::ToSchema)]
+pub struct PatientsSimilarResultEntry {
+    /// The patient ID.
+    pub id: String,
+    /// The Phenomizer score between the query terms and the patient's terms.
+    pub score: f32,
+}
+
+/// Result container for `handle`.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, utoipa::ToSchema)]
+pub struct PatientsSimilarResult {
+    /// Version information.
+    pub version: Version,
+    /// The original query.
+    pub query: PatientsSimilarResultQuery,
+    /// The most similar patients, best first.
+    pub result: Vec<PatientsSimilarResultEntry>,
+}
+
+/// Retrieve the registered patients most similar to a set of terms.
+///
+/// The patients are scored with the symmetric Phenomizer score.
+#[allow(clippy::unused_async)]
+#[utoipa::path(
+    get,
+    operation_id = "patientsSimilar",
+    params(PatientsSimilarQuery),
+    responses(
+        (status = 200, description = "The query was successful.", body = PatientsSimilarResult),
+        (status = 400, description = "A term ID is malformed.", body = CustomError),
+        (status = 404, description = "The patient registry is not enabled.", body = CustomError),
+        (status = 422, description = "The request exceeds a size or complexity limit.", body = CustomError),
+        (status = 500, description = "The server encountered an error.", body = CustomError)
+    )
+)]
+#[get("/api/v1/patients/similar")]
+async fn handle(
+    data: Data<Arc<WebServerData>>,
+    tenant: TenantScope,
+    _path: Path<()>,
+    query: StrictQuery<PatientsSimilarQuery>,
+    resolved_from: ResolvedFrom,
+    _timer: ComputeTimer,
+) -> actix_web::Result<Json<PatientsSimilarResult>, CustomError> {
+    let registry = super::registry(&data, &tenant)?;
+    let terms = super::parse_terms(&query.terms, &data, &resolved_from)?;
+
+    let result = registry
+        .similar(&terms, &data.ontology, query.max_results)
+        .into_iter()
+        .map(|(id, score)| PatientsSimilarResultEntry { id, score })
+        .collect();
+
+    Ok(Json(PatientsSimilarResult {
+        version: Version::new(&data.ontology.hpo_version()),
+        query: PatientsSimilarResultQuery {
+            terms: terms.iter().map(|term_id| term_id.to_string()).collect(),
+            max_results: query.max_results,
+        },
+        result,
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use crate::server::run::hpo_genes::test::web_server_data;
+
+    #[rstest::rstest]
+    #[actix_web::test]
+    async fn patients_similar(
+        web_server_data: &Arc<crate::server::run::WebServerData>,
+    ) -> Result<(), anyhow::Error> {
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(actix_web::web::Data::new(web_server_data.clone()))
+                .service(super::super::register::handle)
+                .service(super::handle),
+        )
+        .await;
+        for (id, terms) in [
+            ("p1", vec!["HP:0010442", "HP:0000347"]),
+            ("p2", vec!["HP:0001780"]),
+        ] {
+            let req = actix_web::test::TestRequest::post()
+                .uri("/api/v1/patients")
+                .set_json(serde_json::json!({"id": id, "terms": terms}))
+                .to_request();
+            let resp: super::super::register::PatientsRegisterResult =
+                actix_web::test::call_and_read_body_json(&app, req).await;
+            assert_eq!(resp.id, id);
+        }
+
+        let req = actix_web::test::TestRequest::get()
+            .uri("/api/v1/patients/similar?terms=HP:0010442,HP:0000347&max_results=1")
+            .to_request();
+        let resp: super::PatientsSimilarResult =
+            actix_web::test::call_and_read_body_json(&app, req).await;
+
+        assert_eq!(resp.result.len(), 1);
+        assert_eq!(resp.result[0].id, "p1");
+
+        Ok(())
+    }
+}
@@ -0,0 +1,137 @@
+//! Implementation of `/readyz`, the readiness probe.
+
+use std::sync::Arc;
+
+use actix_web::{
+    get,
+    web::{Data, Json, Path},
+};
+
+use crate::{
+    common::Version, progress::Degraded, server::run::WebServerData, sources::SourceSummary,
+};
+
+use super::CustomError;
+
+/// Whether the server runs with all of its data.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize, utoipa::ToSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum ReadyzStatus {
+    /// All configured data is loaded.
+    Ready,
+    /// Some optional data failed to load, the functionality depending on it is missing.
+    Degraded,
+}
+
+/// Container for the result.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct ReadyzResult {
+    /// Version information.
+    pub version: Version,
+    /// Whether the server runs with all of its data.
+    pub status: ReadyzStatus,
+    /// The optional data that failed to load.
+    pub degraded: Vec<Degraded>,
+    /// The loaded annotation sources with their memory use.
+    #[serde(default)]
+    pub sources: Vec<SourceSummary>,
+}
+
+impl ReadyzResult {
+    /// Construct the result for the `degraded` stages of the ontology in `version`.
+    pub fn new(version: &str, degraded: &[Degraded]) -> Self {
+        Self {
+            version: Version::new(version),
+            status: if degraded.is_empty() {
+                ReadyzStatus::Ready
+            } else {
+                ReadyzStatus::Degraded
+            },
+            degraded: degraded.to_vec(),
+            sources: Vec::new(),
+        }
+    }
+
+    /// Set the loaded annotation `sources`.
+    #[must_use]
+    pub fn with_sources(self, sources: Vec<SourceSummary>) -> Self {
+        Self { sources, ..self }
+    }
+}
+
+/// Query whether the server is ready and runs with all of its data.
+///
+/// The server only listens once its data is loaded, so it is ready whenever it answers.
+/// Optional data that failed to load is listed with status "degraded".  The loaded
+/// annotation sources are listed with their sizes and the result fields they fill.
+///
+/// # Errors
+///
+/// In the case that there is an error running the server.
+#[allow(clippy::unused_async)]
+#[utoipa::path(
+    get,
+    operation_id = "readyz",
+    responses(
+        (status = 200, description = "The server is ready.", body = ReadyzResult),
+        (status = 500, description = "The server encountered an error.", body = CustomError)
+    )
+)]
+#[get("/readyz")]
+async fn handle(
+    data: Data<Arc<WebServerData>>,
+    _path: Path<()>,
+) -> actix_web::Result<Json<ReadyzResult>, CustomError> {
+    Ok(Json(
+        ReadyzResult::new(&data.ontology.hpo_version(), &data.degraded)
+            .with_sources(data.sources.summaries()),
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use crate::{progress::Degraded, server::run::hpo_genes::test::web_server_data};
+
+    #[test]
+    fn readyz_result() {
+        assert_eq!(
+            super::ReadyzResult::new("2023-06-06", &[]).status,
+            super::ReadyzStatus::Ready
+        );
+
+        let degraded = vec![Degraded {
+            stage: "translations".to_string(),
+            error: "cannot read translations.tsv".to_string(),
+        }];
+        let result = super::ReadyzResult::new("2023-06-06", &degraded);
+        assert_eq!(result.status, super::ReadyzStatus::Degraded);
+        assert_eq!(result.degraded, degraded);
+    }
+
+    #[rstest::rstest]
+    #[actix_web::test]
+    async fn readyz(
+        web_server_data: &Arc<crate::server::run::WebServerData>,
+    ) -> Result<(), anyhow::Error> {
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(actix_web::web::Data::new(web_server_data.clone()))
+                .service(super::handle),
+        )
+        .await;
+        let req = actix_web::test::TestRequest::get()
+            .uri("/readyz")
+            .to_request();
+        let resp: super::ReadyzResult = actix_web::test::call_and_read_body_json(&app, req).await;
+
+        assert_eq!(resp.status, super::ReadyzStatus::Ready);
+        assert!(resp.degraded.is_empty());
+        assert!(resp.sources.is_empty());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,427 @@
+//! Recording of requests and responses with `--record-requests` for `server replay`.
+//!
+//! Each request below `/api/` is written with its response to a JSON file in the
+//! directory, named by the time of the request so that the files sort chronologically.
+//! Headers are not recorded so that no credentials end up on disk.  Only JSON responses
+//! of known size are recorded, the bodies of the streamed and non-JSON responses, e.g.,
+//! the term dump and the ontology export, are passed through and recorded as `null`.
+//!
+//! **Warning:** query strings and request bodies are stored in plain text, including the
+//! phenotypes of the patients sent to `/api/v1/patients` or `/api/v1/mme/match`, so the
+//! directory must be protected like the patient data itself.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use actix_service::IntoServiceFactory as _;
+use actix_web::{
+    body::{BodySize, BoxBody, MessageBody},
+    dev::{AppConfig, Service as _, ServiceFactory as _, ServiceRequest, ServiceResponse},
+    http::{
+        header::{HeaderValue, CONTENT_TYPE},
+        Method, Uri,
+    },
+    middleware::Next,
+    web::{self, Bytes, Data},
+    HttpMessage as _,
+};
+
+use super::{tenants::TenantScope, WebServerData};
+
+/// Counter to keep the names of recordings in the same millisecond apart.
+static RECORDING_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// A recorded request with its response.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Recording {
+    /// The HTTP method.
+    pub method: String,
+    /// The path, without the `/t/{tenant}` prefix of tenant-scoped requests.
+    pub path: String,
+    /// The query string.
+    pub query: String,
+    /// The tenant of the request, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant: Option<String>,
+    /// The content type of the request body, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    /// The request body, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+    /// The response status code.
+    pub status: u16,
+    /// The response body, `null` if it was not recorded, i.e., for streamed and non-JSON
+    /// responses.
+    pub response: serde_json::Value,
+}
+
+/// Parse the response `body` as JSON, or wrap it as a JSON string.
+pub fn response_value(body: &[u8]) -> serde_json::Value {
+    serde_json::from_slice(body)
+        .unwrap_or_else(|_| serde_json::Value::String(String::from_utf8_lossy(body).into_owned()))
+}
+
+/// Write `recording` to a new file in `dir`.
+///
+/// # Errors
+///
+/// In the case that the file cannot be written.
+pub fn write(dir: &Path, recording: &Recording) -> Result<PathBuf, anyhow::Error> {
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    let counter = RECORDING_COUNTER.fetch_add(1, Ordering::Relaxed);
+    let path = dir.join(format!("{millis:013}-{counter:06}.json"));
+    std::fs::create_dir_all(dir)?;
+    std::fs::write(&path, serde_json::to_vec_pretty(recording)?)?;
+    Ok(path)
+}
+
+/// Load the recordings in `dir`, sorted by file name.
+///
+/// # Errors
+///
+/// In the case that the directory or a recording cannot be read.
+pub fn load(dir: &Path) -> Result<Vec<(PathBuf, Recording)>, anyhow::Error> {
+    let mut paths = std::fs::read_dir(dir)
+        .map_err(|e| anyhow::anyhow!("cannot read recordings in {}: {}", dir.display(), e))?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    paths.retain(|path| path.extension().is_some_and(|ext| ext == "json"));
+    paths.sort();
+    paths
+        .into_iter()
+        .map(|path| {
+            let recording = std::fs::read(&path)
+                .map_err(anyhow::Error::from)
+                .and_then(|bytes| Ok(serde_json::from_slice(&bytes)?))
+                .map_err(|e| anyhow::anyhow!("invalid recording {}: {}", path.display(), e))?;
+            Ok((path, recording))
+        })
+        .collect()
+}
+
+/// Record requests below `/api/` and their responses if `--record-requests` is given.
+///
+/// Failures to write a recording are logged and do not affect the response.
+///
+/// # Errors
+///
+/// In the case that the wrapped service fails or a body cannot be read.
+pub async fn record_requests(
+    mut req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, actix_web::Error> {
+    let dir = req
+        .app_data::<Data<Arc<WebServerData>>>()
+        .and_then(|data| data.record_requests.clone());
+    let Some(dir) = dir.filter(|_| req.path().starts_with("/api/")) else {
+        return Ok(next.call(req).await?.map_into_boxed_body());
+    };
+
+    // Read the body and put it back for the handler.
+    let body = req.extract::<Bytes>().await?;
+    let (_, mut payload) = actix_http::h1::Payload::create(true);
+    payload.unread_data(body.clone());
+    req.set_payload(payload.into());
+
+    let mut recording = Recording {
+        method: req.method().to_string(),
+        path: req.path().to_string(),
+        query: req.query_string().to_string(),
+        tenant: req
+            .extensions()
+            .get::<TenantScope>()
+            .and_then(|tenant| tenant.0.clone()),
+        content_type: req
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string),
+        body: (!body.is_empty()).then(|| String::from_utf8_lossy(&body).into_owned()),
+        status: 0,
+        response: serde_json::Value::Null,
+    };
+
+    let res = next.call(req).await?.map_into_boxed_body();
+    let is_json = res
+        .headers()
+        .get(CONTENT_TYPE)
+        .is_some_and(|value| value.as_bytes().starts_with(b"application/json"));
+    if !is_json || matches!(res.response().body().size(), BodySize::Stream) {
+        recording.status = res.status().as_u16();
+        match web::block(move || write(&dir, &recording)).await {
+            Ok(Ok(_)) => (),
+            Ok(Err(e)) => tracing::warn!("could not record request: {}", e),
+            Err(e) => tracing::warn!("could not record request: {}", e),
+        }
+        return Ok(res);
+    }
+
+    let (req, res) = res.into_parts();
+    let (res, body) = res.into_parts();
+    let bytes = actix_web::body::to_bytes(body)
+        .await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    recording.status = res.status().as_u16();
+    recording.response = response_value(&bytes);
+    match web::block(move || write(&dir, &recording)).await {
+        Ok(Ok(_)) => (),
+        Ok(Err(e)) => tracing::warn!("could not record request: {}", e),
+        Err(e) => tracing::warn!("could not record request: {}", e),
+    }
+
+    Ok(ServiceResponse::new(req, res.set_body(BoxBody::new(bytes))))
+}
+
+/// Convert `recording` into a request as received over HTTP.
+///
+/// # Errors
+///
+/// In the case that the recording has an invalid method, URI, or content type.
+fn to_request(recording: &Recording) -> Result<actix_http::Request, anyhow::Error> {
+    let mut payload = actix_http::h1::Payload::create(true).1;
+    if let Some(body) = &recording.body {
+        payload.unread_data(Bytes::from(body.clone()));
+    }
+    let mut req = actix_http::Request::with_payload(payload.into());
+    req.head_mut().method = Method::from_bytes(recording.method.as_bytes())?;
+    req.head_mut().uri = if recording.query.is_empty() {
+        recording.path.parse::<Uri>()?
+    } else {
+        format!("{}?{}", recording.path, recording.query).parse::<Uri>()?
+    };
+    if let Some(content_type) = &recording.content_type {
+        req.headers_mut()
+            .insert(CONTENT_TYPE, HeaderValue::from_str(content_type)?);
+    }
+    if let Some(tenant) = &recording.tenant {
+        req.extensions_mut()
+            .insert(TenantScope(Some(tenant.clone())));
+    }
+    Ok(req)
+}
+
+/// Re-execute the `recordings` against `data` without HTTP.
+///
+/// The requests are dispatched to the application that `server run` serves, see
+/// `super::app`, with all of its routes and middleware.  Tenant-scoped requests are run in
+/// the scope of their tenant without authorization.  Returns the status and response of
+/// each recording.
+///
+/// # Errors
+///
+/// In the case that a recording has an invalid method or URI, or the application could not
+/// be started.
+pub async fn replay(
+    data: Arc<WebServerData>,
+    recordings: &[Recording],
+) -> Result<Vec<(u16, serde_json::Value)>, anyhow::Error> {
+    let app = super::app(Data::new(data), &[])
+        .into_factory()
+        .new_service(AppConfig::default())
+        .await
+        .map_err(|()| anyhow::anyhow!("could not start the application"))?;
+
+    let mut result = Vec::new();
+    for recording in recordings {
+        let res = app
+            .call(to_request(recording)?)
+            .await
+            .map_err(|e| anyhow::anyhow!("{}", e))?;
+        let status = res.status().as_u16();
+        let body = actix_web::body::to_bytes(res.map_into_boxed_body().into_body())
+            .await
+            .map_err(|e| anyhow::anyhow!("could not read response: {}", e))?;
+        result.push((status, response_value(&body)));
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::server::run::hpo_genes::test::web_server_data;
+
+    #[test]
+    fn response_value() {
+        assert_eq!(
+            super::response_value(br#"{"result": []}"#),
+            serde_json::json!({"result": []})
+        );
+        assert_eq!(
+            super::response_value(b"<html></html>"),
+            serde_json::json!("<html></html>")
+        );
+    }
+
+    #[actix_web::test]
+    async fn to_request() -> Result<(), anyhow::Error> {
+        // Echo the request as received by a handler.
+        let app = actix_web::test::init_service(actix_web::App::new().default_service(
+            actix_web::web::to(
+                |req: actix_web::HttpRequest,
+                 tenant: super::TenantScope,
+                 body: actix_web::web::Bytes| async move {
+                    actix_web::HttpResponse::Ok().json(serde_json::json!({
+                        "method": req.method().as_str(),
+                        "uri": req.uri().to_string(),
+                        "content_type": actix_web::HttpMessage::content_type(&req),
+                        "tenant": tenant.0,
+                        "body": String::from_utf8_lossy(&body),
+                    }))
+                },
+            ),
+        ))
+        .await;
+        let recording = super::Recording {
+            method: "POST".to_string(),
+            path: "/api/v1/hpo/expr".to_string(),
+            query: "debug_timings=true".to_string(),
+            tenant: Some("clinic-a".to_string()),
+            content_type: Some("application/json".to_string()),
+            body: Some(r#"{"expr": "HP:0001166"}"#.to_string()),
+            status: 0,
+            response: serde_json::Value::Null,
+        };
+
+        let resp: serde_json::Value =
+            actix_web::test::call_and_read_body_json(&app, super::to_request(&recording)?).await;
+
+        assert_eq!(
+            resp,
+            serde_json::json!({
+                "method": "POST",
+                "uri": "/api/v1/hpo/expr?debug_timings=true",
+                "content_type": "application/json",
+                "tenant": "clinic-a",
+                "body": r#"{"expr": "HP:0001166"}"#,
+            })
+        );
+
+        assert!(super::to_request(&super::Recording {
+            method: "GE T".to_string(),
+            ..recording
+        })
+        .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn write_load() -> Result<(), anyhow::Error> {
+        let tmp_dir = temp_testdir::TempDir::default();
+        let recording = super::Recording {
+            method: "GET".to_string(),
+            path: "/api/v1/hpo/genes".to_string(),
+            query: "gene_symbol=TGDS".to_string(),
+            tenant: None,
+            content_type: None,
+            body: None,
+            status: 200,
+            response: serde_json::json!({"result": []}),
+        };
+
+        let first = super::write(&tmp_dir, &recording)?;
+        let second = super::write(&tmp_dir, &recording)?;
+        std::fs::write(tmp_dir.join("README"), "not a recording")?;
+
+        let loaded = super::load(&tmp_dir)?;
+        assert_eq!(
+            loaded,
+            vec![(first, recording.clone()), (second, recording)]
+        );
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn record_requests() -> Result<(), anyhow::Error> {
+        let tmp_dir = temp_testdir::TempDir::default();
+        let data = crate::server::run::WebServerData {
+            record_requests: Some(tmp_dir.to_path_buf()),
+            ..crate::server::run::hpo_genes::test::load_web_server_data()
+        };
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(actix_web::web::Data::new(std::sync::Arc::new(data)))
+                .wrap(actix_web::middleware::from_fn(super::record_requests))
+                .route(
+                    "/api/v1/json",
+                    actix_web::web::get().to(|| async {
+                        actix_web::HttpResponse::Ok().json(serde_json::json!({"result": []}))
+                    }),
+                )
+                .route(
+                    "/api/v1/text",
+                    actix_web::web::get().to(|| async {
+                        actix_web::HttpResponse::Ok()
+                            .content_type("text/plain")
+                            .body("format-version: 1.2")
+                    }),
+                ),
+        )
+        .await;
+        for uri in ["/api/v1/json", "/api/v1/text"] {
+            let req = actix_web::test::TestRequest::get().uri(uri).to_request();
+            actix_web::test::call_and_read_body(&app, req).await;
+        }
+
+        let loaded = super::load(&tmp_dir)?;
+        assert_eq!(
+            loaded
+                .iter()
+                .map(|(_, recording)| (recording.path.as_str(), &recording.response))
+                .collect::<Vec<_>>(),
+            vec![
+                ("/api/v1/json", &serde_json::json!({"result": []})),
+                ("/api/v1/text", &serde_json::Value::Null),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[actix_web::test]
+    async fn replay(
+        web_server_data: &std::sync::Arc<crate::server::run::WebServerData>,
+    ) -> Result<(), anyhow::Error> {
+        let recording = super::Recording {
+            method: "GET".to_string(),
+            path: "/api/v1/hpo/genes".to_string(),
+            query: "gene_symbol=TGDS".to_string(),
+            tenant: None,
+            content_type: None,
+            body: None,
+            status: 200,
+            response: serde_json::Value::Null,
+        };
+
+        let replayed = super::replay(
+            web_server_data.clone(),
+            &[
+                recording.clone(),
+                super::Recording {
+                    path: "/api/v1/unknown".to_string(),
+                    ..recording
+                },
+            ],
+        )
+        .await?;
+
+        assert_eq!(replayed[0].0, 200);
+        assert_eq!(replayed[0].1["result"][0]["gene_symbol"], "TGDS");
+        assert_eq!(replayed[1].0, 404);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,526 @@
+//! Routes of the REST API that can be disabled with `--disable-routes`.
+
+use std::str::FromStr;
+
+use actix_web::{
+    body::{BoxBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    http::{
+        header::{
+            HeaderValue, ACCESS_CONTROL_ALLOW_HEADERS, ACCESS_CONTROL_ALLOW_METHODS,
+            ACCESS_CONTROL_ALLOW_ORIGIN, ACCESS_CONTROL_MAX_AGE, ALLOW,
+        },
+        Method, StatusCode,
+    },
+    middleware::Next,
+    web::{self, ServiceConfig},
+    HttpRequest, HttpResponse, ResponseError as _,
+};
+use strum::{EnumIter, IntoEnumIterator};
+
+use super::{
+    beacon, disease_mappings, hpo_expr, hpo_genes, hpo_genes_matching, hpo_genes_profile,
+    hpo_infer_labs, hpo_omims, hpo_ontology, hpo_qc, hpo_sim, hpo_stats, hpo_terms,
+    hpo_terms_distance, hpo_terms_dump, hpo_terms_genes, hpo_terms_history, hpo_terms_mappings,
+    hpo_terms_obo, hpo_terms_paths, hpo_terms_treatments, mappings, patients, CustomError,
+};
+
+/// A route of the REST API.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    EnumIter,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    derive_more::Display,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum Route {
+    /// `/api/v1/hpo/expr`
+    #[display("hpo-expr")]
+    HpoExpr,
+    /// `/api/v1/hpo/genes`
+    #[display("hpo-genes")]
+    HpoGenes,
+    /// `/api/v1/hpo/genes/matching`
+    #[display("hpo-genes-matching")]
+    HpoGenesMatching,
+    /// `/api/v1/hpo/genes/{gene}/profile`
+    #[display("hpo-genes-profile")]
+    HpoGenesProfile,
+    /// `/api/v1/hpo/infer/labs`
+    #[display("hpo-infer-labs")]
+    HpoInferLabs,
+    /// `/api/v1/hpo/terms`
+    #[display("hpo-terms")]
+    HpoTerms,
+    /// `/api/v1/hpo/terms/genes`
+    #[display("hpo-terms-genes")]
+    HpoTermsGenes,
+    /// `/api/v1/hpo/terms/dump`
+    #[display("hpo-terms-dump")]
+    HpoTermsDump,
+    /// `/api/v1/hpo/terms/{term_id}/mappings`
+    #[display("hpo-terms-mappings")]
+    HpoTermsMappings,
+    /// `/api/v1/hpo/terms/{term_id}/paths-to-root`
+    #[display("hpo-terms-paths-to-root")]
+    HpoTermsPathsToRoot,
+    /// `/api/v1/hpo/terms/{term_id}/treatments`
+    #[display("hpo-terms-treatments")]
+    HpoTermsTreatments,
+    /// `/api/v1/hpo/terms/{term_id}/obo`
+    #[display("hpo-terms-obo")]
+    HpoTermsObo,
+    /// `/api/v1/hpo/terms/{term_id}/history`
+    #[display("hpo-terms-history")]
+    HpoTermsHistory,
+    /// `/api/v1/hpo/terms/distance`
+    #[display("hpo-terms-distance")]
+    HpoTermsDistance,
+    /// `/api/v1/mappings/{system}/{code}`
+    #[display("mappings")]
+    Mappings,
+    /// `/api/v1/diseases/mappings/{system}/{code}`
+    #[display("disease-mappings")]
+    DiseaseMappings,
+    /// `/api/v1/hpo/omims`
+    #[display("hpo-omims")]
+    HpoOmims,
+    /// `/api/v1/hpo/ontology/export`
+    #[display("hpo-ontology-export")]
+    HpoOntologyExport,
+    /// `/api/v1/hpo/qc`
+    #[display("hpo-qc")]
+    HpoQc,
+    /// `/api/v1/hpo/stats/categories`
+    #[display("hpo-stats-categories")]
+    HpoStatsCategories,
+    /// `/api/v1/hpo/sim/term-term`
+    #[display("hpo-sim-term-term")]
+    HpoSimTermTerm,
+    /// `/api/v1/hpo/sim/term-gene`
+    #[display("hpo-sim-term-gene")]
+    HpoSimTermGene,
+    /// `/api/v1/hpo/sim/term-gene/report`
+    #[display("hpo-sim-term-gene-report")]
+    HpoSimTermGeneReport,
+    /// `/api/v1/hpo/sim/family-gene`
+    #[display("hpo-sim-family-gene")]
+    HpoSimFamilyGene,
+    /// `/api/v1/hpo/sim/omim-omim`
+    #[display("hpo-sim-omim-omim")]
+    HpoSimOmimOmim,
+    /// `/api/v1/hpo/sim/gene-gene`
+    #[display("hpo-sim-gene-gene")]
+    HpoSimGeneGene,
+    /// `/api/v1/patients`
+    #[display("patients-register")]
+    PatientsRegister,
+    /// `/api/v1/patients/similar`
+    #[display("patients-similar")]
+    PatientsSimilar,
+    /// `/api/v1/mme/match`
+    #[display("mme-match")]
+    MmeMatch,
+    /// `/api/v1/beacon`
+    #[display("beacon")]
+    Beacon,
+}
+
+impl FromStr for Route {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Route::iter()
+            .find(|m| m.to_string().as_str().eq(s))
+            .ok_or(anyhow::anyhow!("unknown route: {}", s))
+    }
+}
+
+impl Route {
+    /// Return the path of the route as documented in the `OpenAPI` spec.
+    pub fn path(self) -> &'static str {
+        match self {
+            Route::HpoExpr => "/api/v1/hpo/expr",
+            Route::HpoGenes => "/api/v1/hpo/genes",
+            Route::HpoGenesMatching => "/api/v1/hpo/genes/matching",
+            Route::HpoGenesProfile => "/api/v1/hpo/genes/{gene}/profile",
+            Route::HpoInferLabs => "/api/v1/hpo/infer/labs",
+            Route::HpoTerms => "/api/v1/hpo/terms",
+            Route::HpoTermsGenes => "/api/v1/hpo/terms/genes",
+            Route::HpoTermsDump => "/api/v1/hpo/terms/dump",
+            Route::HpoTermsMappings => "/api/v1/hpo/terms/{term_id}/mappings",
+            Route::HpoTermsPathsToRoot => "/api/v1/hpo/terms/{term_id}/paths-to-root",
+            Route::HpoTermsTreatments => "/api/v1/hpo/terms/{term_id}/treatments",
+            Route::HpoTermsObo => "/api/v1/hpo/terms/{term_id}/obo",
+            Route::HpoTermsHistory => "/api/v1/hpo/terms/{term_id}/history",
+            Route::HpoTermsDistance => "/api/v1/hpo/terms/distance",
+            Route::Mappings => "/api/v1/mappings/{system}/{code}",
+            Route::DiseaseMappings => "/api/v1/diseases/mappings/{system}/{code}",
+            Route::HpoOmims => "/api/v1/hpo/omims",
+            Route::HpoOntologyExport => "/api/v1/hpo/ontology/export",
+            Route::HpoQc => "/api/v1/hpo/qc",
+            Route::HpoStatsCategories => "/api/v1/hpo/stats/categories",
+            Route::HpoSimTermTerm => "/api/v1/hpo/sim/term-term",
+            Route::HpoSimTermGene => "/api/v1/hpo/sim/term-gene",
+            Route::HpoSimTermGeneReport => "/api/v1/hpo/sim/term-gene/report",
+            Route::HpoSimFamilyGene => "/api/v1/hpo/sim/family-gene",
+            Route::HpoSimOmimOmim => "/api/v1/hpo/sim/omim-omim",
+            Route::HpoSimGeneGene => "/api/v1/hpo/sim/gene-gene",
+            Route::PatientsRegister => "/api/v1/patients",
+            Route::PatientsSimilar => "/api/v1/patients/similar",
+            Route::MmeMatch => "/api/v1/mme/match",
+            Route::Beacon => "/api/v1/beacon",
+        }
+    }
+
+    /// Return whether `path` is the path of the route, with any value for its `{...}`
+    /// segments.
+    pub fn matches(self, path: &str) -> bool {
+        let template = self.path().split('/');
+        template.clone().count() == path.split('/').count()
+            && template
+                .zip(path.split('/'))
+                .all(|(expected, given)| expected == given || expected.starts_with('{'))
+    }
+
+    /// Return whether the route computes over many terms, genes, or records and is thus
+    /// rejected under memory pressure by `middleware::shed_load`.
+    pub fn is_batch(self) -> bool {
+        match self {
+            Route::HpoExpr
+            | Route::HpoGenesMatching
+            | Route::HpoTermsGenes
+            | Route::HpoTermsDump
+            | Route::HpoOntologyExport
+            | Route::HpoSimTermTerm
+            | Route::HpoSimTermGene
+            | Route::HpoSimTermGeneReport
+            | Route::HpoSimFamilyGene
+            | Route::HpoSimOmimOmim
+            | Route::HpoSimGeneGene
+            | Route::PatientsSimilar
+            | Route::MmeMatch
+            | Route::Beacon => true,
+            Route::HpoGenes
+            | Route::HpoGenesProfile
+            | Route::HpoInferLabs
+            | Route::HpoTerms
+            | Route::HpoTermsMappings
+            | Route::HpoTermsPathsToRoot
+            | Route::HpoTermsTreatments
+            | Route::HpoTermsObo
+            | Route::HpoTermsHistory
+            | Route::HpoTermsDistance
+            | Route::Mappings
+            | Route::DiseaseMappings
+            | Route::HpoOmims
+            | Route::HpoQc
+            | Route::HpoStatsCategories
+            | Route::PatientsRegister => false,
+        }
+    }
+
+    /// Return the methods the route answers besides OPTIONS.
+    pub fn methods(self) -> &'static [Method] {
+        match self {
+            Route::HpoExpr
+            | Route::HpoInferLabs
+            | Route::HpoSimFamilyGene
+            | Route::PatientsRegister
+            | Route::MmeMatch => &[Method::POST],
+            _ => &[Method::GET, Method::HEAD],
+        }
+    }
+
+    /// Register the service of the route with `cfg`.
+    fn register(self, cfg: &mut ServiceConfig) {
+        match self {
+            Route::HpoExpr => cfg.service(hpo_expr::handle),
+            Route::HpoGenes => cfg.service(hpo_genes::handle),
+            Route::HpoGenesMatching => cfg.service(hpo_genes_matching::handle),
+            Route::HpoGenesProfile => cfg.service(hpo_genes_profile::handle),
+            Route::HpoInferLabs => cfg.service(hpo_infer_labs::handle),
+            Route::HpoTerms => cfg.service(hpo_terms::handle),
+            Route::HpoTermsGenes => cfg.service(hpo_terms_genes::handle),
+            Route::HpoTermsDump => cfg.service(hpo_terms_dump::handle),
+            Route::HpoTermsMappings => cfg.service(hpo_terms_mappings::handle),
+            Route::HpoTermsPathsToRoot => cfg.service(hpo_terms_paths::handle),
+            Route::HpoTermsTreatments => cfg.service(hpo_terms_treatments::handle),
+            Route::HpoTermsObo => cfg.service(hpo_terms_obo::handle),
+            Route::HpoTermsHistory => cfg.service(hpo_terms_history::handle),
+            Route::HpoTermsDistance => cfg.service(hpo_terms_distance::handle),
+            Route::Mappings => cfg.service(mappings::handle),
+            Route::DiseaseMappings => cfg.service(disease_mappings::handle),
+            Route::HpoOmims => cfg.service(hpo_omims::handle),
+            Route::HpoOntologyExport => cfg.service(hpo_ontology::handle),
+            Route::HpoQc => cfg.service(hpo_qc::handle),
+            Route::HpoStatsCategories => cfg.service(hpo_stats::handle),
+            Route::HpoSimTermTerm => cfg.service(hpo_sim::term_term::handle),
+            Route::HpoSimTermGene => cfg.service(hpo_sim::term_gene::handle),
+            Route::HpoSimTermGeneReport => cfg.service(hpo_sim::term_gene_report::handle),
+            Route::HpoSimFamilyGene => cfg.service(hpo_sim::family_gene::handle),
+            Route::HpoSimOmimOmim => cfg.service(hpo_sim::omim_omim::handle),
+            Route::HpoSimGeneGene => cfg.service(hpo_sim::gene_gene::handle),
+            Route::PatientsRegister => cfg.service(patients::register::handle),
+            Route::PatientsSimilar => cfg.service(patients::similar::handle),
+            Route::MmeMatch => cfg.service(patients::mme::handle),
+            Route::Beacon => cfg.service(beacon::handle),
+        };
+    }
+}
+
+/// Request headers that browsers may send cross-origin.
+const ALLOWED_HEADERS: &str = "Authorization, Content-Type, X-Expected-HPO-Release, X-Auth-Token";
+
+/// Return the value of the `Allow` header for `methods`.
+fn allow_header(methods: &[Method]) -> String {
+    methods
+        .iter()
+        .chain([&Method::OPTIONS])
+        .map(Method::as_str)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Register the answers to the other methods than `methods` at `path` with `cfg`.
+///
+/// OPTIONS requests are answered with 204 and the `Allow` and CORS preflight headers,
+/// other methods with 405 and the `Allow` header.  Must be registered after the services
+/// of `path`, which take precedence.
+///
+/// # Panics
+///
+/// Never, the method names are valid header values.
+pub fn register_options(cfg: &mut ServiceConfig, path: &'static str, methods: &'static [Method]) {
+    cfg.service(
+        web::resource(path)
+            .route(web::method(Method::OPTIONS).to(move || async move {
+                let allow = allow_header(methods);
+                HttpResponse::NoContent()
+                    .insert_header((ALLOW, allow.as_str()))
+                    .insert_header((ACCESS_CONTROL_ALLOW_METHODS, allow.as_str()))
+                    .insert_header((ACCESS_CONTROL_ALLOW_HEADERS, ALLOWED_HEADERS))
+                    .insert_header((ACCESS_CONTROL_MAX_AGE, "86400"))
+                    .finish()
+            }))
+            .default_service(web::to(move |req: HttpRequest| async move {
+                let mut response = CustomError::with_status(
+                    StatusCode::METHOD_NOT_ALLOWED,
+                    anyhow::anyhow!("method not allowed: {}", req.method()),
+                )
+                .error_response();
+                response.headers_mut().insert(
+                    ALLOW,
+                    HeaderValue::from_str(&allow_header(methods))
+                        .expect("methods are valid header values"),
+                );
+                response
+            })),
+    );
+}
+
+/// Allow all origins on all responses, including the errors and the OPTIONS answers of
+/// `register_options`.
+///
+/// # Errors
+///
+/// In the case that the wrapped service fails.
+pub async fn add_cors_headers(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, actix_web::Error> {
+    let mut res = next.call(req).await?.map_into_boxed_body();
+    res.headers_mut()
+        .insert(ACCESS_CONTROL_ALLOW_ORIGIN, HeaderValue::from_static("*"));
+    Ok(res)
+}
+
+/// Register the services of all routes except the `disabled` ones with `cfg`.
+///
+/// Each route also answers OPTIONS, see `register_options`, and HEAD if it answers GET
+/// (with `middleware::answer_head`).
+pub fn configure(cfg: &mut ServiceConfig, disabled: &[Route]) {
+    let enabled = Route::iter()
+        .filter(|route| !disabled.contains(route))
+        .collect::<Vec<_>>();
+    for route in &enabled {
+        route.register(cfg);
+    }
+    for route in enabled {
+        register_options(cfg, route.path(), route.methods());
+    }
+}
+
+/// Remove the `disabled` routes from `openapi` and list them in its description.
+pub fn document_disabled(openapi: &mut utoipa::openapi::OpenApi, disabled: &[Route]) {
+    if disabled.is_empty() {
+        return;
+    }
+    for route in disabled {
+        openapi.paths.paths.remove(route.path());
+    }
+    let note = format!(
+        "Disabled on this server: {}.",
+        disabled
+            .iter()
+            .map(|route| format!("`{}`", route.path()))
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    openapi.info.description = Some(match openapi.info.description.take() {
+        Some(description) => format!("{description}\n\n{note}"),
+        None => note,
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use strum::IntoEnumIterator as _;
+    use utoipa::OpenApi as _;
+
+    use super::Route;
+
+    #[test]
+    fn route_from_str() -> Result<(), anyhow::Error> {
+        for route in Route::iter() {
+            assert_eq!(route.to_string().parse::<Route>()?, route);
+        }
+        assert!("jobs".parse::<Route>().is_err());
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case("/api/v1/hpo/sim/term-gene", true)]
+    #[case("/api/v1/hpo/sim/term-gene/report", true)]
+    #[case("/api/v1/hpo/genes/matching", true)]
+    #[case("/api/v1/patients/similar", true)]
+    #[case("/api/v1/mme/match", true)]
+    #[case("/api/v1/beacon", true)]
+    #[case("/api/v1/hpo/expr", true)]
+    #[case("/api/v1/hpo/genes", false)]
+    #[case("/api/v1/hpo/genes/FBN1/profile", false)]
+    #[case("/api/v1/hpo/terms/HP:0000118/mappings", false)]
+    #[case("/api/v1/patients", false)]
+    #[case("/readyz", false)]
+    fn is_batch(#[case] path: &str, #[case] expected: bool) {
+        assert_eq!(
+            Route::iter().any(|route| route.is_batch() && route.matches(path)),
+            expected
+        );
+    }
+
+    #[test]
+    fn route_paths_documented() {
+        let openapi = crate::server::run::ApiDoc::openapi();
+        let mut undocumented = Route::iter()
+            .map(Route::path)
+            .filter(|path| !openapi.paths.paths.contains_key(*path))
+            .collect::<Vec<_>>();
+        assert!(undocumented.is_empty(), "{undocumented:?}");
+
+        // All documented paths but the readiness probe can be disabled.
+        undocumented = openapi
+            .paths
+            .paths
+            .keys()
+            .map(String::as_str)
+            .filter(|path| *path != "/readyz" && !Route::iter().any(|route| route.path() == *path))
+            .collect();
+        assert!(undocumented.is_empty(), "{undocumented:?}");
+    }
+
+    #[test]
+    fn document_disabled() {
+        let mut openapi = crate::server::run::ApiDoc::openapi();
+        let n_paths = openapi.paths.paths.len();
+
+        super::document_disabled(&mut openapi, &[Route::HpoSimTermGene, Route::HpoTermsDump]);
+
+        assert_eq!(openapi.paths.paths.len(), n_paths - 2);
+        assert!(!openapi
+            .paths
+            .paths
+            .contains_key("/api/v1/hpo/sim/term-gene"));
+        assert!(openapi.info.description.expect("must be set").ends_with(
+            "Disabled on this server: `/api/v1/hpo/sim/term-gene`, `/api/v1/hpo/terms/dump`."
+        ));
+    }
+
+    #[actix_web::test]
+    async fn configure() {
+        let app = actix_web::test::init_service(
+            actix_web::App::new().configure(|cfg| super::configure(cfg, &[Route::HpoQc])),
+        )
+        .await;
+        let req = actix_web::test::TestRequest::get()
+            .uri("/api/v1/hpo/qc")
+            .to_request();
+        let resp = actix_web::test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
+    }
+
+    #[actix_web::test]
+    async fn options() {
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .configure(|cfg| super::configure(cfg, &[Route::HpoQc]))
+                .wrap(actix_web::middleware::from_fn(super::add_cors_headers)),
+        )
+        .await;
+
+        let req = actix_web::test::TestRequest::with_uri("/api/v1/hpo/terms")
+            .method(actix_web::http::Method::OPTIONS)
+            .to_request();
+        let resp = actix_web::test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::NO_CONTENT);
+        assert_eq!(
+            resp.headers().get(actix_web::http::header::ALLOW),
+            Some(&actix_web::http::header::HeaderValue::from_static(
+                "GET, HEAD, OPTIONS"
+            ))
+        );
+        assert!(resp
+            .headers()
+            .contains_key(actix_web::http::header::ACCESS_CONTROL_ALLOW_ORIGIN));
+        assert!(resp
+            .headers()
+            .get(actix_web::http::header::ACCESS_CONTROL_ALLOW_HEADERS)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.contains("Authorization")));
+
+        let req = actix_web::test::TestRequest::get()
+            .uri("/api/v1/patients")
+            .to_request();
+        let resp = actix_web::test::call_service(&app, req).await;
+        assert_eq!(
+            resp.status(),
+            actix_web::http::StatusCode::METHOD_NOT_ALLOWED
+        );
+        assert!(resp
+            .headers()
+            .contains_key(actix_web::http::header::ACCESS_CONTROL_ALLOW_ORIGIN));
+        assert_eq!(
+            resp.headers().get(actix_web::http::header::ALLOW),
+            Some(&actix_web::http::header::HeaderValue::from_static(
+                "POST, OPTIONS"
+            ))
+        );
+
+        // Disabled routes do not answer OPTIONS either.
+        let req = actix_web::test::TestRequest::with_uri("/api/v1/hpo/qc")
+            .method(actix_web::http::Method::OPTIONS)
+            .to_request();
+        let resp = actix_web::test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,307 @@
+//! Strict extraction of query parameters.
+//!
+//! `StrictQuery` replaces `web::Query` for the query structs, which have
+//! `#[serde(deny_unknown_fields)]`.  Unknown parameters, e.g., a misspelled
+//! `gene_symbl`, are rejected with 400 instead of being silently ignored.  The parameters
+//! not given by the client are recorded with their values for the `applied_defaults` field
+//! of the response metadata.
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    future::{ready, Ready},
+    sync::Arc,
+};
+
+use actix_web::{dev::Payload, http::StatusCode, web, FromRequest, HttpMessage as _, HttpRequest};
+use serde::{de::DeserializeOwned, Serialize};
+use strum::IntoEnumIterator as _;
+use utoipa::{openapi::path::ParameterIn, IntoParams};
+
+use super::{defaults::SharedAppliedDefaults, routes::Route, CustomError, WebServerData};
+
+/// Query parameters that are interpreted by the middleware on all routes.
+pub const GLOBAL_PARAMS: &[&str] = &["hpo_release", "debug_timings"];
+
+/// An unknown query parameter.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct UnknownParam {
+    /// The name of the parameter.
+    pub name: String,
+    /// The closest known parameter, if any is similar enough.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub suggestion: Option<String>,
+}
+
+impl std::fmt::Display for UnknownParam {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.suggestion {
+            Some(suggestion) => write!(f, "{} (did you mean {}?)", self.name, suggestion),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
+/// Query parameters of type `T`, rejecting unknown parameters.
+#[derive(Debug, Clone)]
+pub struct StrictQuery<T>(pub T);
+
+impl<T> StrictQuery<T> {
+    /// Unwrap into the query parameters.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> std::ops::Deref for StrictQuery<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+/// Return the known parameter closest to `name`, if any is similar enough.
+pub fn closest<'a>(name: &str, known: &'a [String]) -> Option<&'a str> {
+    let max_distance = (name.chars().count() / 3).max(1);
+    known
+        .iter()
+        .map(|candidate| (strsim::levenshtein(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min()
+        .map(|(_, candidate)| candidate.as_str())
+}
+
+/// Split `query_string` into its `key=value` segments with the decoded keys.
+fn segments(query_string: &str) -> Vec<(&str, String)> {
+    query_string
+        .split('&')
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| {
+            let key = web::Query::<Vec<(String, String)>>::from_query(segment)
+                .ok()
+                .and_then(|query| query.into_inner().into_iter().next())
+                .map_or_else(|| segment.to_string(), |(key, _)| key);
+            (segment, key)
+        })
+        .collect()
+}
+
+/// Return the query string of `req` with the configured defaults of its route.
+///
+/// The applied defaults are recorded in `applied`.
+fn with_defaults(
+    req: &HttpRequest,
+    given: &HashSet<String>,
+    applied: &mut BTreeMap<String, serde_json::Value>,
+) -> String {
+    let query_string = req.query_string();
+    let route = req
+        .match_pattern()
+        .and_then(|pattern| Route::iter().find(|route| route.path() == pattern));
+    let data = req.app_data::<web::Data<Arc<WebServerData>>>();
+    let (Some(route), Some(data)) = (route, data) else {
+        return query_string.to_string();
+    };
+    let (query_string, configured) = data.query_defaults.apply(route, query_string, given);
+    applied.extend(
+        configured
+            .into_iter()
+            .map(|(key, value)| (key, serde_json::Value::String(value))),
+    );
+    query_string
+}
+
+/// Record the fields of `query` that were not `given` in `applied`, i.e., the defaults of
+/// the query struct.
+///
+/// Missing optional fields are not recorded and a field like `match_` is given by `match`.
+fn struct_defaults<T: Serialize>(
+    query: &T,
+    given: &HashSet<String>,
+    applied: &mut BTreeMap<String, serde_json::Value>,
+) {
+    let Ok(serde_json::Value::Object(fields)) = serde_json::to_value(query) else {
+        return;
+    };
+    for (key, value) in fields {
+        if !value.is_null() && !given.contains(&key) && !given.contains(key.trim_end_matches('_')) {
+            applied.entry(key).or_insert(value);
+        }
+    }
+}
+
+/// Parse `query_string` into `T`, rejecting unknown parameters.
+///
+/// # Errors
+///
+/// With 400 listing the unknown parameters and their closest known parameters, or with
+/// the error of `web::Query` for malformed values.
+pub fn from_query<T: DeserializeOwned + IntoParams>(
+    query_string: &str,
+) -> Result<T, actix_web::Error> {
+    let segments = segments(query_string)
+        .into_iter()
+        .filter(|(_, key)| !GLOBAL_PARAMS.contains(&key.as_str()))
+        .collect::<Vec<_>>();
+
+    // A segment on its own fails with "unknown field" only for unknown parameters, so
+    // aliases like `match` for `match_` are accepted.
+    let mut unknown = Vec::<String>::new();
+    for (segment, key) in &segments {
+        if !unknown.contains(key)
+            && web::Query::<T>::from_query(segment)
+                .is_err_and(|e| e.to_string().contains("unknown field"))
+        {
+            unknown.push(key.clone());
+        }
+    }
+    if !unknown.is_empty() {
+        let known = T::into_params(|| Some(ParameterIn::Query))
+            .into_iter()
+            .map(|param| param.name)
+            .collect::<Vec<_>>();
+        let unknown = unknown
+            .into_iter()
+            .map(|name| UnknownParam {
+                suggestion: closest(&name, &known).map(str::to_string),
+                name,
+            })
+            .collect();
+        return Err(CustomError::unknown_params(unknown).into());
+    }
+
+    let query_string = segments
+        .iter()
+        .map(|(segment, _)| *segment)
+        .collect::<Vec<_>>()
+        .join("&");
+    Ok(web::Query::<T>::from_query(&query_string)?.into_inner())
+}
+
+impl<T: DeserializeOwned + Serialize + IntoParams> FromRequest for StrictQuery<T> {
+    type Error = actix_web::Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        let given = segments(req.query_string())
+            .into_iter()
+            .map(|(_, key)| key)
+            .collect::<HashSet<_>>();
+        let mut applied = BTreeMap::new();
+        let query = match from_query::<T>(&with_defaults(req, &given, &mut applied)) {
+            Ok(query) => query,
+            Err(e) => return ready(Err(e)),
+        };
+        struct_defaults(&query, &given, &mut applied);
+        if let Some(shared) = req.extensions().get::<SharedAppliedDefaults>() {
+            shared.borrow_mut().extend(applied);
+        }
+        ready(Ok(Self(query)))
+    }
+}
+
+impl CustomError {
+    /// Construct a 400 error for the `unknown` query parameters.
+    fn unknown_params(unknown: Vec<UnknownParam>) -> Self {
+        let mut err = CustomError::with_status(
+            StatusCode::BAD_REQUEST,
+            anyhow::anyhow!(
+                "unknown query parameters: {}",
+                unknown
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        );
+        err.unknown_params = Some(unknown);
+        err
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use actix_web::ResponseError as _;
+
+    use crate::server::run::hpo_genes::HpoGenesQuery;
+
+    #[rstest::rstest]
+    #[case("gene_symbl", Some("gene_symbol"))]
+    #[case("max_result", Some("max_results"))]
+    #[case("lang", None)]
+    #[case("x", None)]
+    fn closest(#[case] name: &str, #[case] expected: Option<&str>) {
+        let known = ["gene_id", "gene_symbol", "max_results", "hpo_terms"]
+            .map(str::to_string)
+            .to_vec();
+
+        assert_eq!(super::closest(name, &known), expected);
+    }
+
+    #[test]
+    fn from_query() -> Result<(), anyhow::Error> {
+        let query = super::from_query::<HpoGenesQuery>(
+            "gene_symbol=TGDS&match=prefix&hpo_release=2023-06-06&debug_timings=true&max_results=5",
+        )
+        .map_err(|e| anyhow::anyhow!("{}", e))?;
+
+        assert_eq!(query.gene_symbol.as_deref(), Some("TGDS"));
+        assert_eq!(query.max_results, 5);
+
+        Ok(())
+    }
+
+    #[test]
+    fn from_query_unknown() {
+        let err = super::from_query::<HpoGenesQuery>("gene_symbl=TGDS&foo=1&gene_id=1")
+            .expect_err("must fail");
+        let err = err
+            .as_error::<super::CustomError>()
+            .expect("must be a CustomError");
+
+        assert_eq!(err.status_code(), actix_web::http::StatusCode::BAD_REQUEST);
+        assert_eq!(
+            err.err,
+            "unknown query parameters: gene_symbl (did you mean gene_symbol?), foo"
+        );
+        assert_eq!(
+            err.unknown_params,
+            Some(vec![
+                super::UnknownParam {
+                    name: "gene_symbl".to_string(),
+                    suggestion: Some("gene_symbol".to_string()),
+                },
+                super::UnknownParam {
+                    name: "foo".to_string(),
+                    suggestion: None,
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn struct_defaults() -> Result<(), anyhow::Error> {
+        let given = ["gene_symbol".to_string(), "match".to_string()].into();
+        let query = super::from_query::<HpoGenesQuery>("gene_symbol=TGDS&match=prefix")
+            .map_err(|e| anyhow::anyhow!("{}", e))?;
+        let mut applied = super::BTreeMap::new();
+        super::struct_defaults(&query, &given, &mut applied);
+
+        assert_eq!(applied["max_results"], 100);
+        assert!(!applied.contains_key("gene_symbol"));
+        assert!(!applied.contains_key("gene_id"));
+        assert!(!applied.contains_key("match_"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn from_query_malformed() {
+        let err = super::from_query::<HpoGenesQuery>("max_results=many").expect_err("must fail");
+
+        assert_eq!(
+            err.as_response_error().status_code(),
+            actix_web::http::StatusCode::BAD_REQUEST
+        );
+    }
+}
@@ -0,0 +1,519 @@
+//! Tenant scoping of the routes below `/t/{tenant}`.
+//!
+//! When hosting several hospitals, each tenant has its own API keys, patient registry,
+//! and optionally custom information content, while the ontology and the indices are
+//! shared.  The tenants are configured in a JSON file, e.g.:
+//!
+//! ```json
+//! {"tenants": [{"id": "clinic-a", "api_keys": ["secret"], "enable_patient_registry": true}]}
+//! ```
+//!
+//! The `/t/{tenant}` prefix is stripped before routing, so all routes are available below
+//! it, e.g., `/t/clinic-a/api/v1/hpo/genes`.
+//!
+//! The custom information content applies to the routes with an `ic_base` parameter.  The
+//! gene prioritization routes, e.g., `/api/v1/hpo/sim/term-gene`, score with the
+//! information content of the genes built into the ontology and are not affected by it.
+
+use std::{
+    collections::BTreeMap,
+    convert::Infallible,
+    future::{ready, Ready},
+};
+
+use actix_web::{
+    body::{BoxBody, MessageBody},
+    dev::{Payload, ServiceRequest, ServiceResponse},
+    http::{header, Method, StatusCode, Uri},
+    middleware::Next,
+    FromRequest, HttpMessage as _, HttpRequest, ResponseError as _,
+};
+
+use subtle::{Choice, ConstantTimeEq as _};
+
+use crate::algos::ic::{IcTable, IcTables};
+
+use super::{middleware::Settings, patients::PatientRegistry, CustomError, WebServerData};
+
+/// Prefix of the tenant-scoped routes.
+pub const TENANT_PREFIX: &str = "/t/";
+
+/// Configuration of one tenant.
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TenantConfig {
+    /// The identifier of the tenant in the route, e.g., "clinic-a".
+    pub id: String,
+    /// The API keys accepted as bearer tokens, at least one.
+    pub api_keys: Vec<String>,
+    /// Path to a TSV file with custom information content, replacing the server's for
+    /// `ic_base=custom`.
+    #[serde(default)]
+    pub path_custom_ic: Option<std::path::PathBuf>,
+    /// Whether the tenant has its own patient registry.
+    #[serde(default)]
+    pub enable_patient_registry: bool,
+}
+
+/// The tenants configuration file.
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TenantsConfig {
+    /// The configured tenants.
+    tenants: Vec<TenantConfig>,
+}
+
+/// One tenant with its own data.
+#[derive(Debug)]
+pub struct Tenant {
+    /// The API keys accepted as bearer tokens.
+    pub api_keys: Vec<String>,
+    /// Information content tables with the tenant's custom table, if configured.
+    pub ic_tables: Option<IcTables>,
+    /// The tenant's patient registry, if enabled.
+    pub patient_registry: Option<PatientRegistry>,
+}
+
+/// The configured tenants by identifier.
+#[derive(Debug, Default)]
+pub struct Tenants {
+    /// The tenants by identifier.
+    by_id: BTreeMap<String, Tenant>,
+}
+
+impl Tenants {
+    /// Load the tenants configuration from the JSON file at `path`.
+    ///
+    /// The tenants with custom information content share the other tables of
+    /// `ic_tables`.
+    ///
+    /// # Errors
+    ///
+    /// In the case that the file could not be read, a tenant is invalid or duplicate, or
+    /// a custom information content file could not be loaded.
+    pub fn load<P: AsRef<std::path::Path>>(
+        path: P,
+        ic_tables: &IcTables,
+    ) -> Result<Self, anyhow::Error> {
+        Self::load_with(path.as_ref(), |tenant| {
+            let custom_ic = tenant
+                .path_custom_ic
+                .as_ref()
+                .map(IcTable::from_path)
+                .transpose()
+                .map_err(|e| anyhow::anyhow!("tenant {}: {}", tenant.id, e))?;
+            Ok(Tenant {
+                api_keys: tenant.api_keys,
+                ic_tables: custom_ic.map(|custom| ic_tables.clone().with_custom(Some(custom))),
+                patient_registry: tenant
+                    .enable_patient_registry
+                    .then(PatientRegistry::default),
+            })
+        })
+    }
+
+    /// Load the tenants configuration from the JSON file at `path` in generic ontology
+    /// mode, where the tenants only have their API keys.
+    ///
+    /// # Errors
+    ///
+    /// In the case that the file could not be read, a tenant is invalid or duplicate, or
+    /// has custom information content or a patient registry.
+    pub fn load_generic<P: AsRef<std::path::Path>>(path: P) -> Result<Self, anyhow::Error> {
+        Self::load_with(path.as_ref(), |tenant| {
+            if tenant.path_custom_ic.is_some() || tenant.enable_patient_registry {
+                anyhow::bail!(
+                    "tenant {}: custom information content and patient registries are not \
+                     available in generic ontology mode",
+                    tenant.id
+                );
+            }
+            Ok(Tenant {
+                api_keys: tenant.api_keys,
+                ic_tables: None,
+                patient_registry: None,
+            })
+        })
+    }
+
+    /// Load the tenants configuration from the JSON file at `path`, building each valid
+    /// tenant with `build`.
+    fn load_with(
+        path: &std::path::Path,
+        build: impl Fn(TenantConfig) -> Result<Tenant, anyhow::Error>,
+    ) -> Result<Self, anyhow::Error> {
+        let config: TenantsConfig = serde_json::from_reader(std::io::BufReader::new(
+            std::fs::File::open(path)
+                .map_err(|e| anyhow::anyhow!("cannot open {}: {}", path.display(), e))?,
+        ))
+        .map_err(|e| anyhow::anyhow!("invalid tenants file {}: {}", path.display(), e))?;
+
+        let mut by_id = BTreeMap::new();
+        for tenant in config.tenants {
+            if tenant.id.is_empty()
+                || !tenant
+                    .id
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+            {
+                anyhow::bail!("invalid tenant ID {:?}", tenant.id);
+            }
+            if tenant.api_keys.is_empty() || tenant.api_keys.iter().any(String::is_empty) {
+                anyhow::bail!("tenant {} needs non-empty API keys", tenant.id);
+            }
+            let id = tenant.id.clone();
+            if by_id.insert(id.clone(), build(tenant)?).is_some() {
+                anyhow::bail!("duplicate tenant ID {}", id);
+            }
+        }
+        Ok(Self { by_id })
+    }
+
+    /// Return the tenant with `id`, if any.
+    pub fn get(&self, id: &str) -> Option<&Tenant> {
+        self.by_id.get(id)
+    }
+
+    /// Return the number of tenants.
+    pub fn len(&self) -> usize {
+        self.by_id.len()
+    }
+
+    /// Return whether there are no tenants.
+    pub fn is_empty(&self) -> bool {
+        self.by_id.is_empty()
+    }
+}
+
+/// Extractor for the tenant of a request, `None` outside of `/t/{tenant}`.
+#[derive(Debug, Clone, Default)]
+pub struct TenantScope(pub Option<String>);
+
+impl TenantScope {
+    /// Return the tenant of the request in `data`, if any.
+    fn tenant<'a>(&self, data: &'a WebServerData) -> Option<&'a Tenant> {
+        self.0.as_deref().and_then(|id| data.tenants.get(id))
+    }
+
+    /// Return the information content tables of the tenant, or of the server.
+    pub fn ic_tables<'a>(&self, data: &'a WebServerData) -> &'a IcTables {
+        self.tenant(data)
+            .and_then(|tenant| tenant.ic_tables.as_ref())
+            .unwrap_or(&data.ic_tables)
+    }
+
+    /// Return the patient registry of the tenant, or of the server outside of a tenant.
+    pub fn patient_registry<'a>(&self, data: &'a WebServerData) -> Option<&'a PatientRegistry> {
+        match &self.0 {
+            Some(_) => self
+                .tenant(data)
+                .and_then(|tenant| tenant.patient_registry.as_ref()),
+            None => data.patient_registry.as_ref(),
+        }
+    }
+}
+
+impl FromRequest for TenantScope {
+    type Error = Infallible;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        ready(Ok(req
+            .extensions()
+            .get::<TenantScope>()
+            .cloned()
+            .unwrap_or_default()))
+    }
+}
+
+/// Split `path` below `/t/{tenant}` into the tenant and the remaining path.
+fn split_tenant(path: &str) -> Option<(&str, &str)> {
+    let rest = path.strip_prefix(TENANT_PREFIX)?;
+    match rest.find('/') {
+        Some(pos) => Some((&rest[..pos], &rest[pos..])),
+        None => Some((rest, "/")),
+    }
+}
+
+/// Check that `tenant_id` is configured in `tenants` and `authorization` carries one of
+/// its API keys.
+///
+/// The keys are compared in constant time and all keys are compared, so that the response
+/// time does not reveal how much of a key was guessed.
+///
+/// # Errors
+///
+/// With 404 for an unknown tenant and with 401 for a missing or invalid API key.
+fn authorize(
+    tenants: &Tenants,
+    tenant_id: &str,
+    authorization: Option<&str>,
+) -> Result<(), CustomError> {
+    let tenant = tenants.get(tenant_id).ok_or_else(|| {
+        CustomError::with_status(
+            StatusCode::NOT_FOUND,
+            anyhow::anyhow!("unknown tenant: {}", tenant_id),
+        )
+    })?;
+    let given = authorization.and_then(|value| value.strip_prefix("Bearer "));
+    let valid = given.is_some_and(|given| {
+        tenant
+            .api_keys
+            .iter()
+            .fold(Choice::from(0), |valid, key| {
+                valid | key.as_bytes().ct_eq(given.as_bytes())
+            })
+            .into()
+    });
+    if valid {
+        Ok(())
+    } else {
+        Err(CustomError::with_status(
+            StatusCode::UNAUTHORIZED,
+            anyhow::anyhow!("missing or invalid API key for tenant {}", tenant_id),
+        ))
+    }
+}
+
+/// Strip the `/t/{tenant}` prefix from the request path after checking the API key.
+///
+/// The tenant is available to the handlers with the `TenantScope` extractor.  CORS
+/// preflight requests, i.e., OPTIONS, carry no credentials and are not checked.
+///
+/// # Errors
+///
+/// In the case that the wrapped service fails.
+pub async fn scope_tenant(
+    mut req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, actix_web::Error> {
+    let Some((tenant_id, rest)) = split_tenant(req.path()) else {
+        return Ok(next.call(req).await?.map_into_boxed_body());
+    };
+    let (tenant_id, rest) = (tenant_id.to_string(), rest.to_string());
+
+    if req.method() != Method::OPTIONS {
+        let settings = Settings::of(&req);
+        let no_tenants = Tenants::default();
+        let authorized = authorize(
+            settings.as_ref().map_or(&no_tenants, Settings::tenants),
+            &tenant_id,
+            req.headers()
+                .get(header::AUTHORIZATION)
+                .and_then(|value| value.to_str().ok()),
+        );
+        if let Err(e) = authorized {
+            let response = e.error_response();
+            return Ok(req.into_response(response));
+        }
+    }
+
+    rewrite(&mut req, tenant_id, &rest)?;
+    Ok(next.call(req).await?.map_into_boxed_body())
+}
+
+/// Route `req` to the path `rest`, keeping the query string, in the scope of `tenant_id`.
+///
+/// # Errors
+///
+/// With 400 in the case that the rewritten URI is invalid.
+fn rewrite(req: &mut ServiceRequest, tenant_id: String, rest: &str) -> Result<(), CustomError> {
+    let path_and_query = match req.query_string() {
+        "" => rest.to_string(),
+        query => format!("{rest}?{query}"),
+    };
+    let uri = path_and_query
+        .parse::<Uri>()
+        .map_err(|e| CustomError::with_status(StatusCode::BAD_REQUEST, anyhow::anyhow!("{}", e)))?;
+    req.match_info_mut().get_mut().update(&uri);
+    req.head_mut().uri = uri;
+    req.extensions_mut().insert(TenantScope(Some(tenant_id)));
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use actix_web::{middleware::from_fn, web, App, HttpMessage as _, HttpResponse};
+
+    use super::{TenantScope, Tenants};
+    use crate::algos::ic::IcTables;
+
+    /// Write a tenants file with two tenants to `tmp_dir`.
+    fn write_tenants(tmp_dir: &std::path::Path) -> Result<std::path::PathBuf, anyhow::Error> {
+        std::fs::write(tmp_dir.join("ic.tsv"), "term_id\tic\nHP:0000118\t1.5\n")?;
+        let path = tmp_dir.join("tenants.json");
+        std::fs::write(
+            &path,
+            serde_json::json!({
+                "tenants": [
+                    {
+                        "id": "clinic-a",
+                        "api_keys": ["secret-a"],
+                        "path_custom_ic": tmp_dir.join("ic.tsv"),
+                        "enable_patient_registry": true,
+                    },
+                    {"id": "clinic-b", "api_keys": ["secret-b", "other-b"]},
+                ]
+            })
+            .to_string(),
+        )?;
+        Ok(path)
+    }
+
+    #[test]
+    fn tenants_load() -> Result<(), anyhow::Error> {
+        let tmp_dir = temp_testdir::TempDir::default();
+        let tenants = Tenants::load(write_tenants(&tmp_dir)?, &IcTables::default())?;
+
+        assert_eq!(tenants.len(), 2);
+        let clinic_a = tenants.get("clinic-a").expect("must exist");
+        assert!(clinic_a.patient_registry.is_some());
+        assert_eq!(
+            clinic_a
+                .ic_tables
+                .as_ref()
+                .and_then(|ic_tables| ic_tables.custom.as_ref())
+                .map(crate::algos::ic::IcTable::len),
+            Some(1)
+        );
+        let clinic_b = tenants.get("clinic-b").expect("must exist");
+        assert!(clinic_b.patient_registry.is_none());
+        assert!(clinic_b.ic_tables.is_none());
+        assert!(tenants.get("clinic-c").is_none());
+
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case(
+        r#"{"tenants": [{"id": "a/b", "api_keys": ["x"]}]}"#,
+        "invalid tenant ID"
+    )]
+    #[case(
+        r#"{"tenants": [{"id": "a", "api_keys": []}]}"#,
+        "needs non-empty API keys"
+    )]
+    #[case(
+        r#"{"tenants": [{"id": "a", "api_keys": ["x"]}, {"id": "a", "api_keys": ["y"]}]}"#,
+        "duplicate tenant ID"
+    )]
+    #[case(
+        r#"{"tenants": [{"id": "a", "api_key": "x"}]}"#,
+        "invalid tenants file"
+    )]
+    fn tenants_load_invalid(
+        #[case] json: &str,
+        #[case] expected: &str,
+    ) -> Result<(), anyhow::Error> {
+        let tmp_dir = temp_testdir::TempDir::default();
+        let path = tmp_dir.join("tenants.json");
+        std::fs::write(&path, json)?;
+
+        let err = Tenants::load(&path, &IcTables::default()).expect_err("must fail");
+
+        assert!(err.to_string().contains(expected), "{err}");
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case("/t/clinic-a/api/v1/hpo/genes", Some(("clinic-a", "/api/v1/hpo/genes")))]
+    #[case("/t/clinic-a", Some(("clinic-a", "/")))]
+    #[case("/api/v1/hpo/genes", None)]
+    #[case("/tenants", None)]
+    fn split_tenant(#[case] path: &str, #[case] expected: Option<(&str, &str)>) {
+        assert_eq!(super::split_tenant(path), expected);
+    }
+
+    #[test]
+    fn authorize() -> Result<(), anyhow::Error> {
+        let tmp_dir = temp_testdir::TempDir::default();
+        let tenants = Tenants::load(write_tenants(&tmp_dir)?, &IcTables::default())?;
+
+        assert!(super::authorize(&tenants, "clinic-b", Some("Bearer other-b")).is_ok());
+        let status = |result: Result<(), super::CustomError>| {
+            actix_web::ResponseError::status_code(&result.expect_err("must fail"))
+        };
+        assert_eq!(
+            status(super::authorize(
+                &tenants,
+                "clinic-b",
+                Some("Bearer secret-a")
+            )),
+            actix_web::http::StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            status(super::authorize(&tenants, "clinic-b", None)),
+            actix_web::http::StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            status(super::authorize(
+                &tenants,
+                "clinic-c",
+                Some("Bearer secret-a")
+            )),
+            actix_web::http::StatusCode::NOT_FOUND
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn rewrite() -> Result<(), super::CustomError> {
+        let mut req = actix_web::test::TestRequest::get()
+            .uri("/t/clinic-a/api/v1/hpo/genes?gene_symbol=TGDS")
+            .to_srv_request();
+
+        super::rewrite(&mut req, "clinic-a".to_string(), "/api/v1/hpo/genes")?;
+
+        assert_eq!(req.path(), "/api/v1/hpo/genes");
+        assert_eq!(req.query_string(), "gene_symbol=TGDS");
+        assert_eq!(
+            req.extensions()
+                .get::<TenantScope>()
+                .and_then(|scope| scope.0.clone()),
+            Some("clinic-a".to_string())
+        );
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn scope_tenant() {
+        let app = actix_web::test::init_service(
+            App::new()
+                .route(
+                    "/api/v1/echo",
+                    web::get().to(|tenant: TenantScope| async move {
+                        HttpResponse::Ok().body(format!("{:?}", tenant.0))
+                    }),
+                )
+                .route(
+                    "/api/v1/echo",
+                    web::method(actix_web::http::Method::OPTIONS)
+                        .to(|| async { HttpResponse::NoContent().finish() }),
+                )
+                .wrap(from_fn(super::scope_tenant)),
+        )
+        .await;
+
+        let req = actix_web::test::TestRequest::get()
+            .uri("/api/v1/echo")
+            .to_request();
+        let body = actix_web::test::call_and_read_body(&app, req).await;
+        assert_eq!(body, "None");
+
+        // Without server data, no tenant is known.
+        let req = actix_web::test::TestRequest::get()
+            .uri("/t/clinic-a/api/v1/echo")
+            .insert_header(("Authorization", "Bearer secret-a"))
+            .to_request();
+        let resp = actix_web::test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
+
+        // Preflight requests pass without API key.
+        let req = actix_web::test::TestRequest::with_uri("/t/clinic-a/api/v1/echo")
+            .method(actix_web::http::Method::OPTIONS)
+            .to_request();
+        let resp = actix_web::test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::NO_CONTENT);
+    }
+}
@@ -0,0 +1,35 @@
+//! Embedded web UI at `/ui/` for interactive exploration without a separate frontend.
+//!
+//! Only available with the `ui` feature.
+
+use actix_web::{get, http::header::ContentType, HttpResponse};
+
+/// The single page of the UI, talking to the REST API.
+const INDEX_HTML: &str = include_str!("ui/index.html");
+
+/// Serve the embedded UI page.
+#[allow(clippy::unused_async)]
+#[get("/ui/")]
+async fn handle() -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type(ContentType::html())
+        .body(INDEX_HTML)
+}
+
+#[cfg(test)]
+mod test {
+    use actix_web::http::{header, StatusCode};
+
+    #[actix_web::test]
+    async fn serve_index() {
+        let app = actix_web::test::init_service(actix_web::App::new().service(super::handle)).await;
+        let req = actix_web::test::TestRequest::get().uri("/ui/").to_request();
+        let resp = actix_web::test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            resp.headers().get(header::CONTENT_TYPE).unwrap(),
+            "text/html; charset=utf-8"
+        );
+    }
+}
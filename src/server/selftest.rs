@@ -0,0 +1,266 @@
+//! Self-test of the data for the REST API server without HTTP.
+//!
+//! Loads the data as `server run` does and runs canary queries against it, e.g., to
+//! smoke-test a data release in CI.
+
+use hpo::{annotations::AnnotationId as _, similarity::Similarity as _, HpoTermId};
+
+use crate::{
+    common::{parse_term_id, to_pairwise_sim, IcBasedOn, SimilarityMethod},
+    server::run::WebServerData,
+};
+
+/// The canary queries with their expected results.
+#[derive(clap::Args, Debug, Clone)]
+pub struct Canaries {
+    /// A term that must be present and not obsolete.
+    #[arg(long, default_value = "HP:0001250")]
+    pub canary_term: String,
+    /// The symbol of a gene that must be annotated and cross-linked to HGNC.
+    #[arg(long, default_value = "TGDS")]
+    pub canary_gene: String,
+    /// The first term of the canary similarity.
+    #[arg(long, default_value = "HP:0001250")]
+    pub canary_similarity_lhs: String,
+    /// The second term of the canary similarity.
+    #[arg(long, default_value = "HP:0007359")]
+    pub canary_similarity_rhs: String,
+    /// Minimal expected Resnik similarity (gene-based IC) of the canary terms.
+    #[arg(long, default_value_t = 0.5)]
+    pub canary_similarity_min: f32,
+    /// Maximal expected Resnik similarity (gene-based IC) of the canary terms.
+    #[arg(long, default_value_t = 5.0)]
+    pub canary_similarity_max: f32,
+}
+
+/// Command line arguments for `server selftest` sub command.
+#[derive(clap::Parser, Debug)]
+#[command(author, version, about = "Load the server data and run canary queries", long_about = None)]
+#[group(id = "selftest")]
+pub struct Args {
+    /// The arguments for loading the data as for `server run`.
+    #[command(flatten)]
+    pub server: crate::server::run::Args,
+    /// The canary queries.
+    #[command(flatten)]
+    pub canaries: Canaries,
+}
+
+/// The outcome of one canary query.
+#[derive(Debug)]
+pub struct Check {
+    /// The name of the check.
+    pub name: &'static str,
+    /// A description of the result, or the reason for failure.
+    pub result: Result<String, anyhow::Error>,
+}
+
+/// Look up the non-obsolete term with `term_id` in `data`.
+fn lookup_term<'a>(
+    data: &'a WebServerData,
+    term_id: &str,
+) -> Result<hpo::HpoTerm<'a>, anyhow::Error> {
+    let term = data
+        .ontology
+        .hpo(parse_term_id(term_id)?)
+        .ok_or_else(|| anyhow::anyhow!("term {} not found", term_id))?;
+    if term.is_obsolete() {
+        anyhow::bail!("term {} ({}) is obsolete", term.id(), term.name());
+    }
+    Ok(term)
+}
+
+/// Check that the canary term is present.
+fn check_term(data: &WebServerData, canaries: &Canaries) -> Result<String, anyhow::Error> {
+    let term = lookup_term(data, &canaries.canary_term)?;
+    Ok(format!("{} ({})", term.id(), term.name()))
+}
+
+/// Check that the canary gene is annotated and cross-linked.
+fn check_gene(data: &WebServerData, canaries: &Canaries) -> Result<String, anyhow::Error> {
+    let gene = data
+        .ontology
+        .gene_by_name(&canaries.canary_gene)
+        .ok_or_else(|| anyhow::anyhow!("gene {} not found", canaries.canary_gene))?;
+    let n_terms = gene.hpo_terms().len();
+    if n_terms == 0 {
+        anyhow::bail!("gene {} has no annotated terms", gene.name());
+    }
+    let ncbi_gene_id = gene.id().as_u32();
+    let hgnc_id = data.hgnc_xlink.hgnc_id(ncbi_gene_id).ok_or_else(|| {
+        anyhow::anyhow!(
+            "gene {} (NCBI {}) has no HGNC ID in hgnc_xlink.tsv",
+            gene.name(),
+            ncbi_gene_id
+        )
+    })?;
+    Ok(format!(
+        "{} (NCBI {}, {}) with {} terms",
+        gene.name(),
+        ncbi_gene_id,
+        hgnc_id,
+        n_terms
+    ))
+}
+
+/// Check that the similarity of the canary terms is in the expected range.
+fn check_similarity(data: &WebServerData, canaries: &Canaries) -> Result<String, anyhow::Error> {
+    let lhs = lookup_term(data, &canaries.canary_similarity_lhs)?;
+    let rhs = lookup_term(data, &canaries.canary_similarity_rhs)?;
+    let score = to_pairwise_sim(
+        SimilarityMethod::Resnik,
+        IcBasedOn::Gene,
+        data.annotation_propagation,
+        &data.ic_tables,
+    )
+    .calculate(&lhs, &rhs);
+    let range = canaries.canary_similarity_min..=canaries.canary_similarity_max;
+    if !range.contains(&score) {
+        anyhow::bail!(
+            "similarity of {} and {} is {}, expected {} to {}",
+            lhs.id(),
+            rhs.id(),
+            score,
+            range.start(),
+            range.end()
+        );
+    }
+    Ok(format!("{} ~ {} = {}", lhs.id(), rhs.id(), score))
+}
+
+/// Check that the term-gene index lists the canary gene for each of its terms.
+fn check_index(data: &WebServerData, canaries: &Canaries) -> Result<String, anyhow::Error> {
+    let gene = data
+        .ontology
+        .gene_by_name(&canaries.canary_gene)
+        .ok_or_else(|| anyhow::anyhow!("gene {} not found", canaries.canary_gene))?;
+    let terms = gene.hpo_terms().iter().collect::<Vec<HpoTermId>>();
+    if let Some(term_id) = terms.iter().find(|term_id| {
+        !data
+            .term_gene_index
+            .gene_ids(**term_id, false)
+            .contains(gene.id())
+    }) {
+        anyhow::bail!("index does not list gene {} for {}", gene.name(), term_id);
+    }
+    Ok(format!("{} terms of {}", terms.len(), gene.name()))
+}
+
+/// Run the canary queries against `data`.
+pub fn run_checks(data: &WebServerData, canaries: &Canaries) -> Vec<Check> {
+    vec![
+        Check {
+            name: "term",
+            result: check_term(data, canaries),
+        },
+        Check {
+            name: "gene",
+            result: check_gene(data, canaries),
+        },
+        Check {
+            name: "similarity",
+            result: check_similarity(data, canaries),
+        },
+        Check {
+            name: "index",
+            result: check_index(data, canaries),
+        },
+    ]
+}
+
+/// Main entry point for `server selftest` sub command.
+///
+/// # Errors
+///
+/// In the case that loading the data failed or a canary query failed.
+pub fn run(args_common: &crate::common::Args, args: &Args) -> Result<(), anyhow::Error> {
+    tracing::info!("args_common = {:?}", &args_common);
+    tracing::info!("args = {:?}", &args);
+
+    let data =
+        crate::server::run::load_web_server_data(&args.server, crate::progress::Mode::cli())?;
+
+    tracing::info!("Running canary queries...");
+    let checks = run_checks(&data, &args.canaries);
+    let mut failed = Vec::new();
+    for check in &checks {
+        match &check.result {
+            Ok(message) => tracing::info!("  ok {}: {}", check.name, message),
+            Err(e) => {
+                tracing::error!("  FAILED {}: {}", check.name, e);
+                failed.push(check.name);
+            }
+        }
+    }
+    if !failed.is_empty() {
+        anyhow::bail!(
+            "{} of {} canary queries failed: {}",
+            failed.len(),
+            checks.len(),
+            failed.join(", ")
+        );
+    }
+
+    tracing::info!("All done. Have a nice day!");
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use clap::Parser as _;
+
+    use crate::server::run::hpo_genes::test::web_server_data;
+
+    /// Return the default canaries.
+    fn canaries() -> super::Canaries {
+        super::Args::parse_from(["selftest", "--path-hpo-dir", "tests/data/hpo"]).canaries
+    }
+
+    #[test]
+    fn args() {
+        let args = super::Args::parse_from([
+            "selftest",
+            "--path-hpo-dir",
+            "tests/data/hpo",
+            "--canary-gene",
+            "ARID1B",
+        ]);
+
+        assert_eq!(
+            args.server.path_hpo_dir(),
+            std::path::Path::new("tests/data/hpo")
+        );
+        assert_eq!(args.canaries.canary_term, "HP:0001250");
+        assert_eq!(args.canaries.canary_gene, "ARID1B");
+    }
+
+    #[rstest::rstest]
+    fn run_checks(web_server_data: &Arc<crate::server::run::WebServerData>) {
+        let checks = super::run_checks(web_server_data, &canaries());
+
+        for check in &checks {
+            assert!(check.result.is_ok(), "{}: {:?}", check.name, check.result);
+        }
+    }
+
+    #[rstest::rstest]
+    fn run_checks_failing(web_server_data: &Arc<crate::server::run::WebServerData>) {
+        let canaries = super::Canaries {
+            canary_term: "HP:9999999".to_string(),
+            canary_gene: "NOT-A-GENE".to_string(),
+            canary_similarity_min: 100.0,
+            canary_similarity_max: 200.0,
+            ..canaries()
+        };
+
+        let checks = super::run_checks(web_server_data, &canaries);
+
+        assert!(checks.iter().all(|check| check.result.is_err()));
+        assert_eq!(
+            checks[0].result.as_ref().unwrap_err().to_string(),
+            "term HP:9999999 not found"
+        );
+    }
+}
@@ -0,0 +1,118 @@
+//! Code for supplementary term-gene association scores, e.g., from CADA or `Phen2Gene`.
+
+use std::collections::{BTreeMap, HashMap};
+
+use hpo::{term::HpoGroup, HpoTermId};
+
+/// Data structure for representing a record of the association scores TSV file.
+#[derive(Debug, Clone, serde::Deserialize)]
+struct Record {
+    /// HPO term ID.
+    hpo_id: String,
+    /// NCBI gene ID.
+    ncbi_gene_id: u32,
+    /// Association score of the term and the gene.
+    score: f32,
+}
+
+/// Association scores between terms and genes.
+#[derive(Debug, Clone, Default)]
+pub struct AssociationScores {
+    /// The scores by term and NCBI gene ID.
+    scores: HashMap<HpoTermId, HashMap<u32, f32>>,
+}
+
+impl AssociationScores {
+    /// Read the association scores from a TSV reader.
+    ///
+    /// The file must have a header and the columns `hpo_id`, `ncbi_gene_id`, and
+    /// `score`.
+    ///
+    /// # Errors
+    ///
+    /// In the case that the file could not be read or a term ID is malformed.
+    pub fn from_reader<R: std::io::Read>(reader: R) -> Result<Self, anyhow::Error> {
+        let mut rdr = csv::ReaderBuilder::new()
+            .delimiter(b'\t')
+            .has_headers(true)
+            .from_reader(reader);
+        let mut scores: HashMap<HpoTermId, HashMap<u32, f32>> = HashMap::new();
+        for result in rdr.deserialize() {
+            let record: Record = result?;
+            let term_id = crate::common::parse_term_id(&record.hpo_id)?;
+            scores
+                .entry(term_id)
+                .or_default()
+                .insert(record.ncbi_gene_id, record.score);
+        }
+        Ok(Self { scores })
+    }
+
+    /// Read the association scores from the TSV file at `path`, see `from_reader`.
+    ///
+    /// # Errors
+    ///
+    /// In the case that the file could not be read or a term ID is malformed.
+    pub fn from_path<P: AsRef<std::path::Path>>(path: P) -> Result<Self, anyhow::Error> {
+        Self::from_reader(std::fs::File::open(path.as_ref())?)
+    }
+
+    /// Return the score of the gene for `terms`, the sum of the per-term scores.
+    ///
+    /// Terms without a score for the gene contribute 0.
+    pub fn gene_score(&self, terms: &HpoGroup, ncbi_gene_id: u32) -> f32 {
+        terms
+            .iter()
+            .filter_map(|term_id| self.scores.get(&term_id)?.get(&ncbi_gene_id))
+            .sum()
+    }
+}
+
+/// Load the association scores from `name=path` values, keyed by the name.
+///
+/// # Errors
+///
+/// In the case that a value is not of the form `name=path` or loading fails.
+pub fn load_named(values: &[String]) -> Result<BTreeMap<String, AssociationScores>, anyhow::Error> {
+    values
+        .iter()
+        .map(|value| {
+            let (name, path) = value
+                .split_once('=')
+                .filter(|(name, path)| !name.is_empty() && !path.is_empty())
+                .ok_or_else(|| {
+                    anyhow::anyhow!("expected name=path for association scores: {}", value)
+                })?;
+            Ok((name.to_string(), AssociationScores::from_path(path)?))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use hpo::{term::HpoGroup, HpoTermId};
+
+    use super::AssociationScores;
+
+    #[test]
+    fn gene_score() -> Result<(), anyhow::Error> {
+        let scores = AssociationScores::from_reader(
+            "hpo_id\tncbi_gene_id\tscore\nHP:0000002\t10\t0.5\nHP:0000003\t10\t0.25\n\
+             HP:0000003\t20\t1.0\n"
+                .as_bytes(),
+        )?;
+        let terms = HpoGroup::from(vec![HpoTermId::from_u32(2), HpoTermId::from_u32(3)]);
+
+        assert!((scores.gene_score(&terms, 10) - 0.75).abs() < f32::EPSILON);
+        assert!((scores.gene_score(&terms, 20) - 1.0).abs() < f32::EPSILON);
+        assert!(scores.gene_score(&terms, 30).abs() < f32::EPSILON);
+
+        Ok(())
+    }
+
+    #[test]
+    fn load_named() {
+        assert!(super::load_named(&["cada".to_string()]).is_err());
+        assert!(super::load_named(&["=path".to_string()]).is_err());
+    }
+}
@@ -0,0 +1,141 @@
+//! Code related to the gene-level `ClinVar` summary.
+
+use std::{collections::HashMap, io::Read as _};
+
+/// A record of the `ClinVar` gene summary TSV file.
+///
+/// The aliases are the column names of `ClinVar`'s `gene_specific_summary.txt`.
+#[derive(Debug, Clone, serde::Deserialize)]
+struct Record {
+    /// NCBI gene ID.
+    #[serde(alias = "GeneID")]
+    ncbi_gene_id: u32,
+    /// Number of pathogenic or likely pathogenic variants in the gene.
+    #[serde(alias = "Alleles_reported_Pathogenic_Likely_pathogenic")]
+    pathogenic_variants: u32,
+}
+
+/// The numbers of pathogenic or likely pathogenic `ClinVar` variants by gene.
+#[derive(Debug, Clone, Default)]
+pub struct ClinvarCounts {
+    /// The counts by NCBI gene ID.
+    by_gene: HashMap<u32, u32>,
+}
+
+impl ClinvarCounts {
+    /// Read the counts from a TSV reader.
+    ///
+    /// The file must have a header and the columns `ncbi_gene_id` and
+    /// `pathogenic_variants` or the corresponding columns `GeneID` and
+    /// `Alleles_reported_Pathogenic_Likely_pathogenic` of `ClinVar`.  Further columns
+    /// are ignored.  As in `ClinVar`'s `gene_specific_summary.txt`, the header may be
+    /// preceded by comment lines and start with `#`.
+    ///
+    /// # Errors
+    ///
+    /// In the case that the file could not be read.
+    pub fn from_reader<R: std::io::BufRead>(mut reader: R) -> Result<Self, anyhow::Error> {
+        // Skip the comment lines up to the header, which has tab-separated columns.
+        let mut header = String::new();
+        while reader.read_line(&mut header)? > 0
+            && header.starts_with('#')
+            && !header.contains('\t')
+        {
+            header.clear();
+        }
+        let header = header.strip_prefix('#').unwrap_or(&header).to_string();
+
+        let mut rdr = csv::ReaderBuilder::new()
+            .delimiter(b'\t')
+            .has_headers(true)
+            .from_reader(std::io::Cursor::new(header).chain(reader));
+        let mut by_gene = HashMap::new();
+        for result in rdr.deserialize() {
+            let record: Record = result?;
+            by_gene.insert(record.ncbi_gene_id, record.pathogenic_variants);
+        }
+        Ok(Self { by_gene })
+    }
+
+    /// Read the counts from the TSV file at `path`, see `from_reader`.
+    ///
+    /// # Errors
+    ///
+    /// In the case that the file could not be read.
+    pub fn from_path<P: AsRef<std::path::Path>>(path: P) -> Result<Self, anyhow::Error> {
+        Self::from_reader(std::io::BufReader::new(std::fs::File::open(path.as_ref())?))
+    }
+
+    /// Return the number of pathogenic or likely pathogenic variants of the gene, 0
+    /// for genes missing from the summary.
+    pub fn get(&self, ncbi_gene_id: u32) -> u32 {
+        self.by_gene.get(&ncbi_gene_id).copied().unwrap_or_default()
+    }
+}
+
+impl crate::sources::AnnotationSource for ClinvarCounts {
+    fn name(&self) -> &'static str {
+        "clinvar"
+    }
+
+    fn stats(&self) -> crate::sources::SourceStats {
+        crate::sources::SourceStats {
+            entries: self.by_gene.len(),
+            memory_bytes: crate::sources::hash_map_bytes(&self.by_gene),
+        }
+    }
+
+    fn result_fields(&self) -> &'static [&'static str] {
+        &["clinvar_pathogenic"]
+    }
+}
+
+impl crate::sources::LoadSource for ClinvarCounts {
+    const STAGE: &'static str = "ClinVar gene summary";
+
+    fn load(path: &std::path::Path) -> Result<Self, anyhow::Error> {
+        Self::from_path(path)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ClinvarCounts;
+
+    /// The head of `ClinVar`'s `gene_specific_summary.txt`, with its comment line and
+    /// the `#` of the header.
+    const GENE_SPECIFIC_SUMMARY: &str = "\
+#Overview of interpretation of all variants in a gene. Conflicting interpretations are not included.
+#Symbol\tGeneID\tTotal_submissions\tTotal_alleles\tSubmissions_reported_pathogenic_variants\tAlleles_reported_Pathogenic_Likely_pathogenic\tGene_MIM_number\tNumber_uncertain\tNumber_with_conflicts
+A1BG\t1\t7\t7\t0\t0\t138670\t6\t0
+A1CF\t29974\t9\t9\t0\t0\t618199\t9\t0
+A2M\t2\t57\t51\t4\t3\t103950\t37\t1
+";
+
+    #[test]
+    fn from_reader_gene_specific_summary() -> Result<(), anyhow::Error> {
+        let counts = ClinvarCounts::from_reader(GENE_SPECIFIC_SUMMARY.as_bytes())?;
+
+        assert_eq!(counts.by_gene.len(), 3);
+        assert_eq!(counts.get(2), 3);
+        assert_eq!(counts.get(29974), 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn from_reader_clinvar() -> Result<(), anyhow::Error> {
+        let counts = ClinvarCounts::from_reader(
+            "Symbol\tGeneID\tTotal_alleles\tAlleles_reported_Pathogenic_Likely_pathogenic\n\
+             ARID1B\t57492\t2000\t350\n\
+             TGDS\t23483\t40\t3\n"
+                .as_bytes(),
+        )?;
+
+        assert_eq!(counts.get(57492), 350);
+        assert_eq!(counts.get(23483), 3);
+        assert_eq!(counts.get(1), 0);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,175 @@
+//! Code related to the gene constraint table, e.g., from gnomAD.
+
+/// LOEUF at or above which a gene is considered unconstrained.
+const MAX_LOEUF: f32 = 2.0;
+
+/// Constraint score of genes missing from the table.
+pub const DEFAULT_CONSTRAINT_SCORE: f32 = 0.5;
+
+/// A record of the gene constraint TSV file.
+///
+/// The aliases are the column names of gnomAD's `lof_metrics.by_gene.txt`.
+#[derive(Debug, Clone, serde::Deserialize)]
+struct Record {
+    /// The gene symbol.
+    #[serde(alias = "gene")]
+    gene_symbol: String,
+    /// The probability of loss-of-function intolerance, `NA` if missing.
+    #[serde(alias = "pLI", default, deserialize_with = "csv::invalid_option")]
+    pli: Option<f32>,
+    /// The upper bound of the observed/expected ratio of loss-of-function variants,
+    /// `NA` if missing.
+    #[serde(
+        alias = "oe_lof_upper",
+        default,
+        deserialize_with = "csv::invalid_option"
+    )]
+    loeuf: Option<f32>,
+}
+
+/// The constraint metrics of a gene.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct GeneConstraint {
+    /// The probability of loss-of-function intolerance, if known.
+    pub pli: Option<f32>,
+    /// The loss-of-function observed/expected upper bound fraction, if known.
+    pub loeuf: Option<f32>,
+}
+
+impl GeneConstraint {
+    /// Return the constraint score from 0 (unconstrained) to 1 (constrained).
+    ///
+    /// The score is the mean of the pLI and of the LOEUF mapped linearly from
+    /// `MAX_LOEUF` to 0 onto 0 to 1, `None` if neither is known.
+    pub fn score(&self) -> Option<f32> {
+        let loeuf_score = self
+            .loeuf
+            .map(|loeuf| 1.0 - (loeuf / MAX_LOEUF).clamp(0.0, 1.0));
+        let scores = [self.pli.map(|pli| pli.clamp(0.0, 1.0)), loeuf_score]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>();
+        #[allow(clippy::cast_precision_loss)]
+        (!scores.is_empty()).then(|| scores.iter().sum::<f32>() / scores.len() as f32)
+    }
+}
+
+/// The constraint metrics by gene symbol.
+#[derive(Debug, Clone, Default)]
+pub struct ConstraintTable {
+    /// The metrics by gene symbol.
+    by_symbol: crate::common::interner::SymbolMap<GeneConstraint>,
+}
+
+impl ConstraintTable {
+    /// Read the constraint table from a TSV reader.
+    ///
+    /// The file must have a header and the columns `gene_symbol`, `pli`, and `loeuf`
+    /// or the corresponding columns `gene`, `pLI`, and `oe_lof_upper` of gnomAD.
+    /// Further columns are ignored and the first record of a gene is used.
+    ///
+    /// # Errors
+    ///
+    /// In the case that the file could not be read.
+    pub fn from_reader<R: std::io::Read>(reader: R) -> Result<Self, anyhow::Error> {
+        let mut rdr = csv::ReaderBuilder::new()
+            .delimiter(b'\t')
+            .has_headers(true)
+            .from_reader(reader);
+        let mut by_symbol = crate::common::interner::SymbolMap::default();
+        for result in rdr.deserialize() {
+            let record: Record = result?;
+            by_symbol.insert_first(
+                &record.gene_symbol,
+                GeneConstraint {
+                    pli: record.pli,
+                    loeuf: record.loeuf,
+                },
+            );
+        }
+        Ok(Self { by_symbol })
+    }
+
+    /// Read the constraint table from the TSV file at `path`, see `from_reader`.
+    ///
+    /// # Errors
+    ///
+    /// In the case that the file could not be read.
+    pub fn from_path<P: AsRef<std::path::Path>>(path: P) -> Result<Self, anyhow::Error> {
+        Self::from_reader(std::fs::File::open(path.as_ref())?)
+    }
+
+    /// Return the constraint metrics of the gene with `gene_symbol`, if any.
+    pub fn get(&self, gene_symbol: &str) -> Option<&GeneConstraint> {
+        self.by_symbol.get(gene_symbol)
+    }
+
+    /// Return whether no genes are loaded.
+    pub fn is_empty(&self) -> bool {
+        self.by_symbol.is_empty()
+    }
+}
+
+impl crate::sources::AnnotationSource for ConstraintTable {
+    fn name(&self) -> &'static str {
+        "constraint"
+    }
+
+    fn stats(&self) -> crate::sources::SourceStats {
+        crate::sources::SourceStats {
+            entries: self.by_symbol.len(),
+            memory_bytes: self.by_symbol.memory_bytes(),
+        }
+    }
+
+    fn result_fields(&self) -> &'static [&'static str] {
+        &["constraint_score", "reranked_score"]
+    }
+}
+
+impl crate::sources::LoadSource for ConstraintTable {
+    const STAGE: &'static str = "gene constraint";
+
+    fn load(path: &std::path::Path) -> Result<Self, anyhow::Error> {
+        Self::from_path(path)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ConstraintTable, GeneConstraint};
+
+    #[test]
+    fn from_reader_gnomad() -> Result<(), anyhow::Error> {
+        let table = ConstraintTable::from_reader(
+            "gene\ttranscript\tpLI\toe_lof_upper\n\
+             ARID1B\tENST00000346085\t1.0\t0.1\n\
+             TTN\tENST00000589042\tNA\t0.9\n\
+             ARID1B\tENST00000636930\t0.0\t1.5\n"
+                .as_bytes(),
+        )?;
+
+        assert_eq!(
+            table.get("ARID1B"),
+            Some(&GeneConstraint {
+                pli: Some(1.0),
+                loeuf: Some(0.1),
+            })
+        );
+        assert_eq!(table.get("TTN").and_then(|gene| gene.pli), None);
+        assert!(table.get("TGDS").is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn score() {
+        let score = |pli, loeuf| GeneConstraint { pli, loeuf }.score();
+
+        assert!((score(Some(1.0), Some(0.0)).unwrap_or_default() - 1.0).abs() < 1e-6);
+        assert!((score(Some(0.0), Some(3.0)).unwrap_or_default()).abs() < 1e-6);
+        assert!((score(Some(0.5), None).unwrap_or_default() - 0.5).abs() < 1e-6);
+        assert!((score(None, Some(1.0)).unwrap_or_default() - 0.5).abs() < 1e-6);
+        assert_eq!(score(None, None), None);
+    }
+}
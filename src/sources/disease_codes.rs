@@ -0,0 +1,347 @@
+//! Code for translating disease codes by Orphanet's cross-references.
+//!
+//! The mappings are read from a TSV file with the external references of Orphanet's
+//! `en_product1.xml`, linking ORPHA codes to ICD-10 codes and OMIM IDs.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// Value of the `source` column for ICD-10 codes.
+const SOURCE_ICD10: &str = "ICD-10";
+/// Value of the `source` column for OMIM IDs.
+const SOURCE_OMIM: &str = "OMIM";
+
+/// A record of the Orphanet mapping TSV file.
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+pub struct Record {
+    /// The ORPHA code, e.g., `558`.
+    pub orpha_code: u32,
+    /// The referenced terminology, e.g., `ICD-10` or `OMIM`.
+    pub source: String,
+    /// The code in the referenced terminology, e.g., `Q87.4`.
+    pub reference: String,
+    /// The mapping relation, e.g., `E (Exact mapping...)`, if any.
+    #[serde(default, deserialize_with = "csv::invalid_option")]
+    pub mapping_relation: Option<String>,
+}
+
+/// An ICD-10 code that an Orphanet disease is mapped to.
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    serde::Serialize,
+    serde::Deserialize,
+    utoipa::ToSchema,
+)]
+pub struct Icd10Code {
+    /// The ICD-10 code, e.g., `Q87.4`.
+    pub code: String,
+    /// Orphanet's mapping relation, e.g., `E (Exact mapping...)`, if given.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub mapping_relation: Option<String>,
+}
+
+/// The codes of an Orphanet disease.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OrphaCodes {
+    /// The ICD-10 codes, sorted.
+    pub icd10_codes: Vec<Icd10Code>,
+    /// The OMIM IDs, sorted.
+    pub omim_ids: Vec<u32>,
+}
+
+/// Normalize `code` to upper case without any `ICD-10:` or `ICD10:` prefix.
+pub fn normalize_icd10(code: &str) -> String {
+    let code = code.trim().to_uppercase();
+    ["ICD-10:", "ICD10:"]
+        .iter()
+        .find_map(|prefix| code.strip_prefix(prefix))
+        .map_or(code.clone(), str::to_string)
+}
+
+/// Convert the sets of ORPHA codes in `map` into sorted vecs.
+fn to_vecs<K: std::hash::Hash + Eq>(map: HashMap<K, BTreeSet<u32>>) -> HashMap<K, Vec<u32>> {
+    map.into_iter()
+        .map(|(key, values)| (key, values.into_iter().collect()))
+        .collect()
+}
+
+/// Translation between ORPHA codes, ICD-10 codes, and OMIM IDs.
+#[derive(Debug, Clone, Default)]
+pub struct DiseaseCodes {
+    /// The codes by ORPHA code.
+    by_orpha: BTreeMap<u32, OrphaCodes>,
+    /// The ORPHA codes by normalized ICD-10 code, sorted.
+    orpha_by_icd10: HashMap<String, Vec<u32>>,
+    /// The ORPHA codes by OMIM ID, sorted.
+    orpha_by_omim: HashMap<u32, Vec<u32>>,
+}
+
+impl DiseaseCodes {
+    /// Build the translation from the `records`.
+    ///
+    /// Records of other sources and OMIM records with malformed IDs are skipped.
+    pub fn from_records(records: impl IntoIterator<Item = Record>) -> Self {
+        let mut by_orpha = BTreeMap::<u32, OrphaCodes>::new();
+        let mut orpha_by_icd10 = HashMap::<String, BTreeSet<u32>>::new();
+        let mut orpha_by_omim = HashMap::<u32, BTreeSet<u32>>::new();
+        for record in records {
+            match record.source.trim() {
+                SOURCE_ICD10 => {
+                    let code = normalize_icd10(&record.reference);
+                    orpha_by_icd10
+                        .entry(code.clone())
+                        .or_default()
+                        .insert(record.orpha_code);
+                    by_orpha
+                        .entry(record.orpha_code)
+                        .or_default()
+                        .icd10_codes
+                        .push(Icd10Code {
+                            code,
+                            mapping_relation: record.mapping_relation,
+                        });
+                }
+                SOURCE_OMIM => {
+                    let Ok(omim_id) = record.reference.trim().parse::<u32>() else {
+                        continue;
+                    };
+                    orpha_by_omim
+                        .entry(omim_id)
+                        .or_default()
+                        .insert(record.orpha_code);
+                    by_orpha
+                        .entry(record.orpha_code)
+                        .or_default()
+                        .omim_ids
+                        .push(omim_id);
+                }
+                _ => (),
+            }
+        }
+        for codes in by_orpha.values_mut() {
+            codes.icd10_codes.sort();
+            codes.icd10_codes.dedup();
+            codes.omim_ids.sort_unstable();
+            codes.omim_ids.dedup();
+        }
+        Self {
+            by_orpha,
+            orpha_by_icd10: to_vecs(orpha_by_icd10),
+            orpha_by_omim: to_vecs(orpha_by_omim),
+        }
+    }
+
+    /// Load the translation from the file at `path`, Orphanet's `en_product1.xml` if its
+    /// extension is `xml` and a TSV file otherwise.
+    ///
+    /// The TSV file must have a header and the columns `orpha_code`, `source`,
+    /// `reference`, and optionally `mapping_relation`.
+    ///
+    /// # Errors
+    ///
+    /// In the case that the file could not be read.
+    pub fn load<P: AsRef<std::path::Path>>(path: P) -> Result<Self, anyhow::Error> {
+        if path.as_ref().extension().is_some_and(|ext| ext == "xml") {
+            return Self::from_xml_reader(std::io::BufReader::new(std::fs::File::open(
+                path.as_ref(),
+            )?));
+        }
+        let mut rdr = csv::ReaderBuilder::new()
+            .delimiter(b'\t')
+            .has_headers(true)
+            .flexible(true)
+            .from_path(path.as_ref())?;
+        let records = rdr
+            .deserialize()
+            .collect::<Result<Vec<Record>, _>>()
+            .map_err(|e| anyhow::anyhow!("invalid Orphanet mapping record: {}", e))?;
+        Ok(Self::from_records(records))
+    }
+
+    /// Read the translation from the cross-references in Orphanet's `en_product1.xml`
+    /// in `reader`.
+    ///
+    /// # Errors
+    ///
+    /// In the case that the XML is malformed or an ORPHA code is not a number.
+    pub fn from_xml_reader<R: std::io::BufRead>(reader: R) -> Result<Self, anyhow::Error> {
+        use super::orphadata::{ends_with, Element};
+
+        let mut records = Vec::new();
+        let mut orpha_code = None;
+        let mut record = Record::default();
+        super::orphadata::read_xml(reader, |path, element| {
+            match element {
+                Element::Text(text) if ends_with(path, &["Disorder", "OrphaCode"]) => {
+                    orpha_code =
+                        Some(text.parse::<u32>().map_err(|e| {
+                            anyhow::anyhow!("invalid ORPHA code {:?}: {}", text, e)
+                        })?);
+                }
+                Element::Text(text) if ends_with(path, &["ExternalReference", "Source"]) => {
+                    record.source = text.to_string();
+                }
+                Element::Text(text) if ends_with(path, &["ExternalReference", "Reference"]) => {
+                    record.reference = text.to_string();
+                }
+                Element::Text(text) if ends_with(path, &["DisorderMappingRelation", "Name"]) => {
+                    record.mapping_relation = Some(text.to_string());
+                }
+                Element::End
+                    if ends_with(path, &["ExternalReferenceList", "ExternalReference"]) =>
+                {
+                    let record = std::mem::take(&mut record);
+                    if let Some(orpha_code) = orpha_code {
+                        records.push(Record {
+                            orpha_code,
+                            ..record
+                        });
+                    }
+                }
+                Element::End if ends_with(path, &["Disorder"]) => orpha_code = None,
+                _ => (),
+            }
+            Ok(())
+        })?;
+        Ok(Self::from_records(records))
+    }
+
+    /// Get the codes of the Orphanet disease with `orpha_code`, if any.
+    pub fn orpha(&self, orpha_code: u32) -> Option<&OrphaCodes> {
+        self.by_orpha.get(&orpha_code)
+    }
+
+    /// Get the ORPHA codes mapped to the ICD-10 `code`, sorted.
+    pub fn orpha_codes_for_icd10(&self, code: &str) -> &[u32] {
+        self.orpha_by_icd10
+            .get(&normalize_icd10(code))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// Get the ORPHA codes mapped to the OMIM ID `omim_id`, sorted.
+    pub fn orpha_codes_for_omim(&self, omim_id: u32) -> &[u32] {
+        self.orpha_by_omim.get(&omim_id).map_or(&[], Vec::as_slice)
+    }
+
+    /// Get the ICD-10 codes of the OMIM disease `omim_id` by way of its Orphanet
+    /// diseases, sorted and each code once.
+    pub fn icd10_codes_for_omim(&self, omim_id: u32) -> Vec<Icd10Code> {
+        let mut result = self
+            .orpha_codes_for_omim(omim_id)
+            .iter()
+            .filter_map(|orpha_code| self.orpha(*orpha_code))
+            .flat_map(|codes| codes.icd10_codes.iter().cloned())
+            .collect::<Vec<_>>();
+        result.sort();
+        result.dedup_by(|lhs, rhs| lhs.code == rhs.code);
+        result
+    }
+
+    /// Return whether no mappings were loaded.
+    pub fn is_empty(&self) -> bool {
+        self.by_orpha.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::DiseaseCodes;
+
+    #[test]
+    fn load() -> Result<(), anyhow::Error> {
+        let tmp_dir = temp_testdir::TempDir::default();
+        let path = tmp_dir.join("orphanet_mappings.tsv");
+        std::fs::write(
+            &path,
+            "orpha_code\tsource\treference\tmapping_relation\n\
+             558\tICD-10\tQ87.4\tE (Exact mapping)\n\
+             558\tOMIM\t154700\tE (Exact mapping)\n\
+             558\tMeSH\tD008382\tE (Exact mapping)\n\
+             1234\tICD-10\tq87.4\n\
+             1234\tOMIM\t154700\tBTNT (ORPHA code's Broader Term maps to a Narrower Term)\n\
+             1234\tOMIM\tnot-an-id\t\n",
+        )?;
+
+        let codes = DiseaseCodes::load(&path)?;
+
+        assert!(!codes.is_empty());
+        assert_eq!(codes.orpha_codes_for_icd10("ICD-10:Q87.4"), [558, 1234]);
+        assert_eq!(codes.orpha_codes_for_omim(154_700), [558, 1234]);
+        assert!(codes.orpha_codes_for_icd10("G40").is_empty());
+        let orpha = codes.orpha(558).expect("must be mapped");
+        assert_eq!(orpha.omim_ids, [154_700]);
+        assert_eq!(
+            orpha.icd10_codes[0].mapping_relation.as_deref(),
+            Some("E (Exact mapping)")
+        );
+        assert_eq!(
+            codes
+                .icd10_codes_for_omim(154_700)
+                .iter()
+                .map(|code| code.code.as_str())
+                .collect::<Vec<_>>(),
+            ["Q87.4"]
+        );
+
+        Ok(())
+    }
+
+    /// Excerpt of Orphanet's `en_product1.xml`.
+    const PRODUCT1: &str = r#"<?xml version="1.0" encoding="ISO-8859-1"?>
+<JDBOR date="2024-12-02 07:03:17" version="1.3.40 / 4.1.7 [2024-11-26] (orientdb version)" copyright="Orphanet (c) 2024" dbserver="orphadata">
+  <DisorderList count="1">
+<Disorder id="17601">
+  <OrphaCode>558</OrphaCode>
+  <ExpertLink lang="en">http://www.orpha.net/consor/cgi-bin/OC_Exp.php?lng=en&amp;Expert=558</ExpertLink>
+  <Name lang="en">Marfan syndrome</Name>
+  <DisorderType id="21394">
+    <Name lang="en">Disease</Name>
+  </DisorderType>
+  <ExternalReferenceList count="3">
+    <ExternalReference id="1466">
+      <Source>ICD-10</Source>
+      <Reference>Q87.4</Reference>
+      <DisorderMappingRelation id="21562">
+        <Name lang="en">E (Exact mapping: the two concepts are equivalent)</Name>
+      </DisorderMappingRelation>
+      <DisorderMappingICDRelation id="21465">
+        <Name lang="en">Attributed (The ICD code is attributed by Orphanet)</Name>
+      </DisorderMappingICDRelation>
+    </ExternalReference>
+    <ExternalReference id="1467">
+      <Source>OMIM</Source>
+      <Reference>154700</Reference>
+      <DisorderMappingRelation id="21562">
+        <Name lang="en">E (Exact mapping: the two concepts are equivalent)</Name>
+      </DisorderMappingRelation>
+    </ExternalReference>
+    <ExternalReference id="1468">
+      <Source>MeSH</Source>
+      <Reference>D008382</Reference>
+    </ExternalReference>
+  </ExternalReferenceList>
+</Disorder>
+  </DisorderList>
+</JDBOR>
+"#;
+
+    #[test]
+    fn from_xml_reader() -> Result<(), anyhow::Error> {
+        let codes = DiseaseCodes::from_xml_reader(PRODUCT1.as_bytes())?;
+
+        assert_eq!(codes.orpha_codes_for_icd10("Q87.4"), [558]);
+        assert_eq!(codes.orpha_codes_for_omim(154_700), [558]);
+        let orpha = codes.orpha(558).expect("must be mapped");
+        assert_eq!(orpha.omim_ids, [154_700]);
+        assert_eq!(
+            orpha.icd10_codes[0].mapping_relation.as_deref(),
+            Some("E (Exact mapping: the two concepts are equivalent)")
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,213 @@
+//! Code related to the gene-by-tissue expression matrix, e.g., `GTEx` medians.
+
+use std::io::BufRead as _;
+
+/// Separator between the organ and the subregion in `GTEx` tissue names, e.g.,
+/// `Brain - Cortex`.
+const SUBREGION_SEPARATOR: &str = " - ";
+
+/// How genes not expressed in the requested tissue are treated.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    serde::Serialize,
+    serde::Deserialize,
+    utoipa::ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum ExpressionMode {
+    /// The genes are excluded.
+    #[default]
+    Filter,
+    /// The scores of the genes are penalized.
+    Penalize,
+}
+
+/// Median expression of the genes by tissue.
+#[derive(Debug, Clone, Default)]
+pub struct ExpressionMatrix {
+    /// The tissue names, e.g., `Brain - Cortex`.
+    tissues: Vec<String>,
+    /// The median TPM by gene symbol, in the order of `tissues`.
+    by_symbol: crate::common::interner::SymbolMap<Vec<f32>>,
+}
+
+impl ExpressionMatrix {
+    /// Read the matrix from a TSV reader.
+    ///
+    /// The first two columns are the gene ID and the gene symbol, the remaining
+    /// columns are the tissues, as in `GTEx`'s `gene_median_tpm.gct` files.  The GCT
+    /// version and dimension lines are skipped.  The first row of a gene is used.
+    ///
+    /// # Errors
+    ///
+    /// In the case that the file could not be read or a value is malformed.
+    pub fn from_reader<R: std::io::Read>(reader: R) -> Result<Self, anyhow::Error> {
+        let mut lines = std::io::BufReader::new(reader)
+            .lines()
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .skip_while(|line| line.starts_with('#'));
+        let mut header = lines
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("expression matrix is empty"))?;
+        // Skip the dimensions line of GCT files, e.g., `56200\t54`.
+        if header
+            .split('\t')
+            .all(|value| value.parse::<usize>().is_ok())
+        {
+            header = lines
+                .next()
+                .ok_or_else(|| anyhow::anyhow!("expression matrix has no header"))?;
+        }
+        let tissues = header
+            .split('\t')
+            .skip(2)
+            .map(str::to_string)
+            .collect::<Vec<_>>();
+
+        let mut by_symbol = crate::common::interner::SymbolMap::default();
+        for line in lines.filter(|line| !line.is_empty()) {
+            let mut values = line.split('\t');
+            let symbol = values
+                .nth(1)
+                .ok_or_else(|| anyhow::anyhow!("expression matrix row without symbol"))?;
+            let tpms = values
+                .map(|value| {
+                    value.parse::<f32>().map_err(|e| {
+                        anyhow::anyhow!("invalid TPM {:?} of {}: {}", value, symbol, e)
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            if tpms.len() != tissues.len() {
+                anyhow::bail!(
+                    "expression matrix row of {} has {} instead of {} tissues",
+                    symbol,
+                    tpms.len(),
+                    tissues.len()
+                );
+            }
+            by_symbol.insert_first(symbol, tpms);
+        }
+        Ok(Self { tissues, by_symbol })
+    }
+
+    /// Read the matrix from the TSV file at `path`, see `from_reader`.
+    ///
+    /// # Errors
+    ///
+    /// In the case that the file could not be read or a value is malformed.
+    pub fn from_path<P: AsRef<std::path::Path>>(path: P) -> Result<Self, anyhow::Error> {
+        Self::from_reader(std::fs::File::open(path.as_ref())?)
+    }
+
+    /// Return the columns of the tissues matching `tissue`, case-insensitive.
+    ///
+    /// An organ matches all of its subregions, e.g., `brain` matches `Brain - Cortex`
+    /// and `Brain - Cerebellum`.
+    pub fn tissue_columns(&self, tissue: &str) -> Vec<usize> {
+        let tissue = tissue.trim().to_lowercase();
+        self.tissues
+            .iter()
+            .enumerate()
+            .filter(|(_, name)| {
+                let name = name.to_lowercase();
+                name == tissue
+                    || name
+                        .split_once(SUBREGION_SEPARATOR)
+                        .is_some_and(|(organ, _)| organ == tissue)
+            })
+            .map(|(column, _)| column)
+            .collect()
+    }
+
+    /// Return the maximal median TPM of the gene in the `columns`, `None` if the gene is
+    /// missing from the matrix.
+    pub fn max_tpm(&self, gene_symbol: &str, columns: &[usize]) -> Option<f32> {
+        let tpms = self.by_symbol.get(gene_symbol)?;
+        Some(
+            columns
+                .iter()
+                .filter_map(|column| tpms.get(*column))
+                .fold(0.0, |max: f32, tpm| max.max(*tpm)),
+        )
+    }
+}
+
+impl crate::sources::AnnotationSource for ExpressionMatrix {
+    fn name(&self) -> &'static str {
+        "expression"
+    }
+
+    fn stats(&self) -> crate::sources::SourceStats {
+        crate::sources::SourceStats {
+            entries: self.by_symbol.len(),
+            memory_bytes: self.by_symbol.memory_bytes()
+                + self
+                    .tissues
+                    .iter()
+                    .map(|tissue| std::mem::size_of::<String>() + tissue.capacity())
+                    .sum::<usize>()
+                + self
+                    .by_symbol
+                    .iter()
+                    .map(|(_, tpms)| tpms.capacity() * std::mem::size_of::<f32>())
+                    .sum::<usize>(),
+        }
+    }
+
+    fn result_fields(&self) -> &'static [&'static str] {
+        &["expression_tpm", "expression_penalty"]
+    }
+}
+
+impl crate::sources::LoadSource for ExpressionMatrix {
+    const STAGE: &'static str = "expression matrix";
+
+    fn load(path: &std::path::Path) -> Result<Self, anyhow::Error> {
+        Self::from_path(path)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ExpressionMatrix;
+
+    #[test]
+    fn from_reader_gct() -> Result<(), anyhow::Error> {
+        let matrix = ExpressionMatrix::from_reader(
+            "#1.2\n2\t3\n\
+             Name\tDescription\tBrain - Cortex\tBrain - Cerebellum\tLiver\n\
+             ENSG00000049618.23\tARID1B\t12.5\t30.0\t4.0\n\
+             ENSG00000088451.10\tTGDS\t0.5\t0.2\t8.0\n"
+                .as_bytes(),
+        )?;
+
+        let brain = matrix.tissue_columns("brain");
+        assert_eq!(brain, vec![0, 1]);
+        assert_eq!(matrix.tissue_columns("Brain - Cortex"), vec![0]);
+        assert!(matrix.tissue_columns("heart").is_empty());
+        assert_eq!(matrix.max_tpm("ARID1B", &brain), Some(30.0));
+        assert_eq!(matrix.max_tpm("TGDS", &brain), Some(0.5));
+        assert_eq!(matrix.max_tpm("TTN", &brain), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn from_reader_malformed() {
+        assert!(ExpressionMatrix::from_reader("".as_bytes()).is_err());
+        assert!(ExpressionMatrix::from_reader(
+            "gene_id\tgene_symbol\tLiver\nENSG1\tA\tx\n".as_bytes()
+        )
+        .is_err());
+        assert!(ExpressionMatrix::from_reader(
+            "gene_id\tgene_symbol\tLiver\nENSG1\tA\t1.0\t2.0\n".as_bytes()
+        )
+        .is_err());
+    }
+}
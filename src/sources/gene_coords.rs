@@ -0,0 +1,153 @@
+//! Code related to genomic coordinates of genes and region filters.
+
+use std::{collections::HashMap, str::FromStr};
+
+/// Normalize chromosome names by removing any `chr` prefix.
+fn normalize_chrom(chrom: &str) -> String {
+    let chrom = chrom.trim();
+    chrom
+        .strip_prefix("chr")
+        .or_else(|| chrom.strip_prefix("CHR"))
+        .unwrap_or(chrom)
+        .to_uppercase()
+}
+
+/// Data structure for representing an entry of the gene coordinates TSV file.
+///
+/// Coordinates are 1-based and inclusive.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize)]
+pub struct Entry {
+    /// NCBI gene ID.
+    pub ncbi_gene_id: u32,
+    /// Chromosome name, with or without `chr` prefix.
+    pub chrom: String,
+    /// Start position.
+    pub start: u64,
+    /// End position.
+    pub end: u64,
+}
+
+/// Genomic region as given in queries, e.g., `chr7:100000-5000000`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Region {
+    /// Normalized chromosome name.
+    pub chrom: String,
+    /// Start position, 1-based.
+    pub start: u64,
+    /// End position, 1-based, inclusive.
+    pub end: u64,
+}
+
+impl Region {
+    /// Return whether the gene with the coordinates in `entry` overlaps the region.
+    pub fn overlaps(&self, entry: &Entry) -> bool {
+        normalize_chrom(&entry.chrom) == self.chrom
+            && entry.start <= self.end
+            && self.start <= entry.end
+    }
+
+    /// Return whether the gene with `ncbi_gene_id` overlaps the region.
+    ///
+    /// Genes without known coordinates are never in the region.
+    pub fn contains_gene<S: std::hash::BuildHasher>(
+        &self,
+        ncbi_gene_id: u32,
+        gene_coords: &HashMap<u32, Entry, S>,
+    ) -> bool {
+        gene_coords
+            .get(&ncbi_gene_id)
+            .is_some_and(|entry| self.overlaps(entry))
+    }
+}
+
+impl FromStr for Region {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (chrom, range) = s
+            .split_once(':')
+            .ok_or_else(|| anyhow::anyhow!("invalid region, expected chrom:start-end: {}", s))?;
+        let (start, end) = range
+            .split_once('-')
+            .ok_or_else(|| anyhow::anyhow!("invalid region, expected chrom:start-end: {}", s))?;
+        let parse = |pos: &str| {
+            pos.replace(',', "")
+                .parse::<u64>()
+                .map_err(|e| anyhow::anyhow!("invalid position in region {}: {}", s, e))
+        };
+        let (start, end) = (parse(start)?, parse(end)?);
+        if chrom.trim().is_empty() || start > end {
+            anyhow::bail!("invalid region: {}", s);
+        }
+        Ok(Self {
+            chrom: normalize_chrom(chrom),
+            start,
+            end,
+        })
+    }
+}
+
+/// Read the gene coordinates TSV file into a map from NCBI gene ID to coordinates.
+///
+/// The file must have a header and the columns `ncbi_gene_id`, `chrom`, `start`,
+/// and `end`.
+///
+/// # Errors
+///
+/// In the case that the file could not be read.
+pub fn load_gene_coords<P: AsRef<std::path::Path>>(
+    path: P,
+) -> Result<HashMap<u32, Entry>, anyhow::Error> {
+    let mut rdr = csv::ReaderBuilder::new()
+        .delimiter(b'\t')
+        .has_headers(true)
+        .from_path(path.as_ref())?;
+    let mut map = HashMap::new();
+    for result in rdr.deserialize() {
+        let entry: Entry = result?;
+        map.insert(entry.ncbi_gene_id, entry);
+    }
+    Ok(map)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Entry, Region};
+
+    #[test]
+    fn parse_region() -> Result<(), anyhow::Error> {
+        assert_eq!(
+            "chr7:100000-5,000,000".parse::<Region>()?,
+            Region {
+                chrom: "7".to_string(),
+                start: 100_000,
+                end: 5_000_000,
+            }
+        );
+        assert_eq!("X:1-2".parse::<Region>()?.chrom, "X");
+        assert!("chr7".parse::<Region>().is_err());
+        assert!("chr7:5-1".parse::<Region>().is_err());
+        assert!("chr7:a-b".parse::<Region>().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn overlaps() -> Result<(), anyhow::Error> {
+        let region = "chr7:100-200".parse::<Region>()?;
+        let entry = |chrom: &str, start, end| Entry {
+            ncbi_gene_id: 1,
+            chrom: chrom.to_string(),
+            start,
+            end,
+        };
+
+        assert!(region.overlaps(&entry("7", 50, 100)));
+        assert!(region.overlaps(&entry("chr7", 150, 160)));
+        assert!(region.overlaps(&entry("7", 200, 300)));
+        assert!(!region.overlaps(&entry("7", 201, 300)));
+        assert!(!region.overlaps(&entry("8", 150, 160)));
+
+        Ok(())
+    }
+}
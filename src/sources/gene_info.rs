@@ -0,0 +1,114 @@
+//! Code related to the NCBI `Homo_sapiens.gene_info` file.
+
+use std::collections::HashMap;
+
+/// Data structure for representing a record of the (possibly slimmed) file.
+///
+/// Only the columns below are read, the other columns may be omitted.
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct Entry {
+    /// NCBI gene ID.
+    #[serde(rename = "GeneID")]
+    pub ncbi_gene_id: u32,
+    /// Cytogenetic location.
+    pub map_location: Option<String>,
+    /// Gene description.
+    pub description: Option<String>,
+    /// Type of the gene, e.g., `protein-coding`.
+    pub type_of_gene: Option<String>,
+}
+
+/// Gene information that is returned by the HTTP server.
+#[derive(
+    Debug,
+    Clone,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    serde::Serialize,
+    serde::Deserialize,
+    utoipa::ToSchema,
+)]
+pub struct GeneInfo {
+    /// Gene description.
+    pub description: Option<String>,
+    /// Type of the gene, e.g., `protein-coding`.
+    pub gene_type: Option<String>,
+    /// Cytogenetic location.
+    pub map_location: Option<String>,
+}
+
+/// Map the NCBI placeholder `-` for missing values to `None`.
+fn non_empty(value: Option<String>) -> Option<String> {
+    value.filter(|value| !value.is_empty() && value != "-")
+}
+
+impl From<Entry> for GeneInfo {
+    fn from(entry: Entry) -> Self {
+        Self {
+            description: non_empty(entry.description),
+            gene_type: non_empty(entry.type_of_gene),
+            map_location: non_empty(entry.map_location),
+        }
+    }
+}
+
+/// Read the `gene_info` file into a map from NCBI gene ID to gene information.
+///
+/// # Errors
+///
+/// In the case that the file could not be read.
+pub fn load_gene_info<P: AsRef<std::path::Path>>(
+    path: P,
+) -> Result<HashMap<u32, GeneInfo>, anyhow::Error> {
+    let mut rdr = csv::ReaderBuilder::new()
+        .delimiter(b'\t')
+        .has_headers(true)
+        .quoting(false)
+        .from_path(path.as_ref())?;
+    let mut map = HashMap::new();
+    for result in rdr.deserialize() {
+        let entry: Entry = result?;
+        map.insert(entry.ncbi_gene_id, entry.into());
+    }
+    Ok(map)
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn load_gene_info() -> Result<(), anyhow::Error> {
+        let tmp_dir = temp_testdir::TempDir::default();
+        let path = tmp_dir.join("gene_info.tsv");
+        std::fs::write(
+            &path,
+            "#tax_id\tGeneID\tSymbol\tmap_location\tdescription\ttype_of_gene\n\
+             9606\t2348\tFOLR1\t11q13.4\tfolate receptor alpha\tprotein-coding\n\
+             9606\t7000001\tXYZ\t-\t-\tunknown\n",
+        )?;
+
+        let map = super::load_gene_info(&path)?;
+
+        assert_eq!(map.len(), 2);
+        assert_eq!(
+            map[&2348],
+            super::GeneInfo {
+                description: Some("folate receptor alpha".to_string()),
+                gene_type: Some("protein-coding".to_string()),
+                map_location: Some("11q13.4".to_string()),
+            }
+        );
+        assert_eq!(
+            map[&7_000_001],
+            super::GeneInfo {
+                description: None,
+                gene_type: Some("unknown".to_string()),
+                map_location: None,
+            }
+        );
+
+        Ok(())
+    }
+}
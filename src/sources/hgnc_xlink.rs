@@ -0,0 +1,138 @@
+//! Code related to the HGNC xlink table.
+
+use std::collections::HashMap;
+
+/// Data structure for representing an entry of the table.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde_with::skip_serializing_none]
+pub struct Entry {
+    /// HGNC gene ID.
+    pub hgnc_id: String,
+    /// Ensembl gene ID.
+    pub ensembl_gene_id: Option<String>,
+    /// Entrez gene ID.
+    #[serde(alias = "entrez_id")]
+    pub ncgi_gene_id: Option<u32>,
+    /// Gene symbol.
+    pub gene_symbol: String,
+}
+
+/// Read the `hgnc_xlink.tsv` file using the `csv` crate via serde.
+///
+/// # Errors
+///
+/// In the case that the file could not be read.
+pub fn load_entries<P: AsRef<std::path::Path>>(path: &P) -> Result<Vec<Entry>, anyhow::Error> {
+    let mut rdr = csv::ReaderBuilder::new()
+        .delimiter(b'\t')
+        .has_headers(true)
+        .from_path(path.as_ref())?;
+    let mut entries = Vec::new();
+    for result in rdr.deserialize() {
+        let entry: Entry = result?;
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+/// Return the number of the HGNC gene ID `hgnc_id`, e.g., `5` for `HGNC:5`.
+fn hgnc_number(hgnc_id: &str) -> Option<u32> {
+    hgnc_id.strip_prefix("HGNC:")?.parse().ok()
+}
+
+/// Cross-links between NCBI and HGNC gene IDs.
+///
+/// The HGNC gene IDs are interned so each is stored once and the NCBI gene IDs map
+/// to `u32` handles rather than strings.  Lookups by HGNC gene ID use its number, so
+/// neither direction hashes strings.
+#[derive(Debug, Clone, Default)]
+pub struct HgncXlink {
+    /// The interned HGNC gene IDs.
+    hgnc_ids: crate::common::interner::Interner,
+    /// Map from NCBI gene ID to the interned HGNC gene ID.
+    by_ncbi: HashMap<u32, crate::common::interner::Symbol>,
+    /// Map from the number of the HGNC gene ID to the NCBI gene ID.
+    by_hgnc: HashMap<u32, u32>,
+}
+
+impl HgncXlink {
+    /// Build the cross-links from the entries of the table.
+    ///
+    /// Entries without an NCBI gene ID are skipped.
+    pub fn from_entries(entries: impl IntoIterator<Item = Entry>) -> Self {
+        let mut result = Self::default();
+        for entry in entries {
+            let Some(ncbi_gene_id) = entry.ncgi_gene_id else {
+                continue;
+            };
+            let Some(hgnc_number) = hgnc_number(&entry.hgnc_id) else {
+                tracing::warn!("skipping malformed HGNC gene ID {:?}", entry.hgnc_id);
+                continue;
+            };
+            let symbol = result.hgnc_ids.intern(&entry.hgnc_id);
+            result.by_hgnc.insert(hgnc_number, ncbi_gene_id);
+            result.by_ncbi.insert(ncbi_gene_id, symbol);
+        }
+        result
+    }
+
+    /// Read the cross-links from the `hgnc_xlink.tsv` file.
+    ///
+    /// # Errors
+    ///
+    /// In the case that the file could not be read.
+    pub fn load<P: AsRef<std::path::Path>>(path: P) -> Result<Self, anyhow::Error> {
+        Ok(Self::from_entries(load_entries(&path)?))
+    }
+
+    /// Return the HGNC gene ID of the gene with `ncbi_gene_id`, if any.
+    pub fn hgnc_id(&self, ncbi_gene_id: u32) -> Option<&str> {
+        self.by_ncbi
+            .get(&ncbi_gene_id)
+            .map(|symbol| self.hgnc_ids.resolve(*symbol))
+    }
+
+    /// Return the NCBI gene ID of the gene with `hgnc_id`, if any.
+    pub fn ncbi_gene_id(&self, hgnc_id: &str) -> Option<u32> {
+        self.by_hgnc.get(&hgnc_number(hgnc_id)?).copied()
+    }
+
+    /// Return the number of cross-linked genes.
+    pub fn len(&self) -> usize {
+        self.by_ncbi.len()
+    }
+
+    /// Return whether there are no cross-linked genes.
+    pub fn is_empty(&self) -> bool {
+        self.by_ncbi.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Entry, HgncXlink};
+
+    #[test]
+    fn hgnc_xlink() {
+        let entry = |hgnc_id: &str, ncbi_gene_id: Option<u32>| Entry {
+            hgnc_id: hgnc_id.to_string(),
+            ensembl_gene_id: None,
+            ncgi_gene_id: ncbi_gene_id,
+            gene_symbol: String::new(),
+        };
+        let xlink = HgncXlink::from_entries([
+            entry("HGNC:5", Some(1)),
+            entry("HGNC:37133", Some(503_538)),
+            entry("HGNC:24086", None),
+            entry("24087", Some(7)),
+        ]);
+
+        assert_eq!(xlink.len(), 2);
+        assert_eq!(xlink.hgnc_id(7), None);
+        assert_eq!(xlink.hgnc_id(1), Some("HGNC:5"));
+        assert_eq!(xlink.hgnc_id(2), None);
+        assert_eq!(xlink.ncbi_gene_id("HGNC:37133"), Some(503_538));
+        assert_eq!(xlink.ncbi_gene_id("HGNC:24086"), None);
+        assert_eq!(xlink.ncbi_gene_id("HGNC:x"), None);
+    }
+}
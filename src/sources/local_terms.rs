@@ -0,0 +1,209 @@
+//! Code for local extension terms maintained by a lab, e.g., `HP:LAB:0001`.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use hpo::HpoTermId;
+
+use crate::index::ident_to_string;
+
+/// A local extension term.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LocalTerm {
+    /// The term ID, e.g., `HP:LAB:0001`.
+    pub id: String,
+    /// The term name.
+    pub name: String,
+    /// The definition, if any.
+    pub definition: Option<String>,
+    /// The synonyms.
+    pub synonyms: Vec<String>,
+    /// The nearest HPO terms above the term, through other local terms if needed.
+    pub parents: Vec<HpoTermId>,
+}
+
+/// Local extension terms by ID.
+///
+/// The terms are not part of the ontology.  In queries they stand for their nearest
+/// HPO terms so they do not contribute information content of their own.
+#[derive(Debug, Clone, Default)]
+pub struct LocalTerms {
+    /// Map from term ID to local term.
+    terms: BTreeMap<String, LocalTerm>,
+}
+
+impl LocalTerms {
+    /// Read the local terms from an OBO document.
+    ///
+    /// # Errors
+    ///
+    /// In the case that a term ID clashes with an HPO term ID or a term is not below
+    /// any term of `ontology`.
+    pub fn from_doc(
+        doc: &fastobo::ast::OboDoc,
+        ontology: &hpo::Ontology,
+    ) -> Result<Self, anyhow::Error> {
+        // Collect the terms and their direct parents, HPO or local.
+        let mut terms = BTreeMap::new();
+        let mut is_a = BTreeMap::new();
+        for frame in doc
+            .entities()
+            .iter()
+            .filter_map(fastobo::ast::EntityFrame::as_term)
+        {
+            let id = ident_to_string(frame.id().as_inner().as_ref());
+            if crate::common::parse_term_id(&id).is_ok() {
+                anyhow::bail!("local term ID {} clashes with an HPO term ID", id);
+            }
+            let mut term = LocalTerm {
+                id: id.clone(),
+                ..Default::default()
+            };
+            let mut parents = Vec::new();
+            for clause in frame.clauses().iter().map(fastobo::ast::Line::as_inner) {
+                match clause {
+                    fastobo::ast::TermClause::Name(name) => {
+                        term.name = name.as_str().to_string();
+                    }
+                    fastobo::ast::TermClause::Def(def) => {
+                        term.definition = Some(def.text().as_str().to_string());
+                    }
+                    fastobo::ast::TermClause::Synonym(synonym) => {
+                        term.synonyms
+                            .push(synonym.description().as_str().to_string());
+                    }
+                    fastobo::ast::TermClause::IsA(parent) => {
+                        parents.push(ident_to_string(parent.as_ref().as_ref()));
+                    }
+                    _ => (),
+                }
+            }
+            terms.insert(id.clone(), term);
+            is_a.insert(id, parents);
+        }
+
+        // Resolve the parents to the nearest HPO terms.
+        for (id, term) in &mut terms {
+            let mut parents = BTreeSet::new();
+            let mut seen = BTreeSet::new();
+            let mut stack = vec![id.as_str()];
+            while let Some(id) = stack.pop() {
+                if !seen.insert(id) {
+                    continue;
+                }
+                for parent in is_a.get(id).into_iter().flatten() {
+                    if is_a.contains_key(parent) {
+                        stack.push(parent);
+                    } else if let Some(parent) = crate::common::parse_term_id(parent)
+                        .ok()
+                        .and_then(|parent| ontology.hpo(parent))
+                    {
+                        parents.insert(parent.id());
+                    }
+                }
+            }
+            if parents.is_empty() {
+                anyhow::bail!("local term {} is not below any HPO term", id);
+            }
+            term.parents = parents.into_iter().collect();
+        }
+
+        Ok(Self { terms })
+    }
+
+    /// Read the local terms from the OBO file at `path`, see `from_doc`.
+    ///
+    /// # Errors
+    ///
+    /// In the case that the file could not be read or is invalid.
+    pub fn from_path<P: AsRef<std::path::Path>>(
+        path: P,
+        ontology: &hpo::Ontology,
+    ) -> Result<Self, anyhow::Error> {
+        let doc = fastobo::from_file(path.as_ref())
+            .map_err(|e| anyhow::anyhow!("Error loading local terms {:?}: {}", path.as_ref(), e))?;
+        Self::from_doc(&doc, ontology)
+    }
+
+    /// Return the local term with `id`, if any.
+    pub fn get(&self, id: &str) -> Option<&LocalTerm> {
+        self.terms.get(id)
+    }
+
+    /// Return an iterator over the local terms.
+    pub fn iter(&self) -> impl Iterator<Item = &LocalTerm> {
+        self.terms.values()
+    }
+
+    /// Return the number of local terms.
+    pub fn len(&self) -> usize {
+        self.terms.len()
+    }
+
+    /// Return whether there are no local terms.
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use hpo::{builder::Builder, HpoTermId, Ontology};
+
+    use super::LocalTerms;
+
+    /// Build an ontology with "Seizure" below the root.
+    fn build_ontology() -> Ontology {
+        let mut builder = Builder::new();
+        builder.new_term("All", 1u32);
+        builder.new_term("Seizure", 1250u32);
+        let mut builder = builder.terms_complete();
+        builder.add_parent(1u32, 1250u32).expect("term must exist");
+        builder
+            .connect_all_terms()
+            .calculate_information_content()
+            .expect("could not compute IC")
+            .build_minimal()
+    }
+
+    #[test]
+    fn from_doc() -> Result<(), anyhow::Error> {
+        let doc = fastobo::from_str(
+            "format-version: 1.2\n\n\
+             [Term]\n\
+             id: HP:LAB:0001\n\
+             name: Lab seizure\n\
+             synonym: \"Local fit\" EXACT []\n\
+             is_a: HP:0001250\n\n\
+             [Term]\n\
+             id: HP:LAB:0002\n\
+             name: Lab seizure subtype\n\
+             is_a: HP:LAB:0001\n",
+        )?;
+
+        let local_terms = LocalTerms::from_doc(&doc, &build_ontology())?;
+
+        assert_eq!(local_terms.len(), 2);
+        let term = local_terms.get("HP:LAB:0002").expect("must exist");
+        assert_eq!(term.name, "Lab seizure subtype");
+        assert_eq!(term.parents, vec![HpoTermId::from_u32(1250)]);
+        assert_eq!(
+            local_terms.get("HP:LAB:0001").expect("must exist").synonyms,
+            vec!["Local fit"]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn from_doc_invalid() -> Result<(), anyhow::Error> {
+        let ontology = build_ontology();
+        let clash = fastobo::from_str("format-version: 1.2\n\n[Term]\nid: HP:0009999\n")?;
+        assert!(LocalTerms::from_doc(&clash, &ontology).is_err());
+        let orphan = fastobo::from_str(
+            "format-version: 1.2\n\n[Term]\nid: HP:LAB:0001\nis_a: HP:LAB:0002\n",
+        )?;
+        assert!(LocalTerms::from_doc(&orphan, &ontology).is_err());
+
+        Ok(())
+    }
+}
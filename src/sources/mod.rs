@@ -0,0 +1,255 @@
+//! Pluggable annotation sources, e.g., the `ClinVar` gene summary or the gene constraint
+//! table.
+//!
+//! Each source declares how it is loaded, how much memory it uses, and which fields of
+//! the results it contributes.  The server keeps the loaded sources in a registry
+//! (`AnnotationSources`) and looks them up by type, so adding a source does not require
+//! new fields in the server data.
+//!
+//! The submodules hold the loaders of the data files next to their implementations of
+//! the traits, while `crate::common` keeps the types shared by them.
+
+pub mod associations;
+pub mod clinvar;
+pub mod constraint;
+pub mod disease_codes;
+pub mod expression;
+pub mod gene_coords;
+pub mod gene_info;
+pub mod hgnc_xlink;
+pub mod local_terms;
+pub mod orphadata;
+pub mod panels;
+pub mod prevalence;
+pub mod translations;
+pub mod treatments;
+
+use std::{any::Any, collections::HashMap, path::Path, sync::Arc};
+
+use crate::progress::Loader;
+
+/// Statistics of a loaded annotation source.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize, utoipa::ToSchema,
+)]
+pub struct SourceStats {
+    /// The number of entries, e.g., genes or terms.
+    pub entries: usize,
+    /// The approximate memory used by the entries in bytes.
+    pub memory_bytes: usize,
+}
+
+/// An annotation source held by the server.
+pub trait AnnotationSource: Send + Sync + 'static {
+    /// Return the name of the source, e.g., `clinvar`.
+    fn name(&self) -> &'static str;
+
+    /// Return the statistics of the loaded source.
+    fn stats(&self) -> SourceStats;
+
+    /// Return the names of the result fields filled from the source, e.g.,
+    /// `clinvar_pathogenic`, empty if the source only backs its own endpoints.
+    fn result_fields(&self) -> &'static [&'static str];
+}
+
+/// Loading of an annotation source from a file.
+pub trait LoadSource: AnnotationSource + Sized {
+    /// The name of the loading stage, e.g., "`ClinVar` gene summary".
+    const STAGE: &'static str;
+
+    /// Load the source from the file at `path`.
+    ///
+    /// # Errors
+    ///
+    /// In the case that the file could not be read or is malformed.
+    fn load(path: &Path) -> Result<Self, anyhow::Error>;
+}
+
+/// Load the optional source `T` from `path` with `loader`, if `path` is given.
+///
+/// # Errors
+///
+/// In the case that loading fails and optional stages are required.
+pub fn load<T: LoadSource>(
+    loader: &Loader,
+    path: Option<&Path>,
+) -> Result<Option<RegisteredSource>, anyhow::Error> {
+    Ok(loader
+        .optional(T::STAGE, path, T::load)?
+        .map(RegisteredSource::new))
+}
+
+/// Return the approximate memory used by the slots of `map` in bytes, without the heap
+/// memory owned by the keys and values.
+pub fn hash_map_bytes<K, V, S>(map: &HashMap<K, V, S>) -> usize {
+    map.capacity() * std::mem::size_of::<(K, V)>()
+}
+
+/// A source in the registry, type-erased.
+#[derive(Clone)]
+pub struct RegisteredSource {
+    /// The source for the common interface.
+    source: Arc<dyn AnnotationSource>,
+    /// The same source for lookup by type.
+    any: Arc<dyn Any + Send + Sync>,
+}
+
+impl RegisteredSource {
+    /// Register `source`.
+    pub fn new<T: AnnotationSource>(source: T) -> Self {
+        let source = Arc::new(source);
+        Self {
+            source: source.clone(),
+            any: source,
+        }
+    }
+}
+
+/// Summary of a loaded annotation source, e.g., for the readiness probe.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct SourceSummary {
+    /// The name of the source, e.g., `clinvar`.
+    pub name: String,
+    /// The statistics of the loaded source.
+    pub stats: SourceStats,
+    /// The result fields filled from the source.
+    pub result_fields: Vec<String>,
+}
+
+/// Registry of the loaded annotation sources, at most one per type.
+#[derive(Clone, Default)]
+pub struct AnnotationSources {
+    /// The sources in the order of registration.
+    sources: Vec<RegisteredSource>,
+}
+
+impl std::fmt::Debug for AnnotationSources {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list()
+            .entries(self.iter().map(AnnotationSource::name))
+            .finish()
+    }
+}
+
+impl AnnotationSources {
+    /// Add `source`, replacing a source of the same type.
+    pub fn insert<T: AnnotationSource>(&mut self, source: T) {
+        self.push(RegisteredSource::new(source));
+    }
+
+    /// Add the registered `source`, replacing a source of the same type.
+    fn push(&mut self, source: RegisteredSource) {
+        let type_id = source.any.as_ref().type_id();
+        self.sources
+            .retain(|registered| registered.any.as_ref().type_id() != type_id);
+        self.sources.push(source);
+    }
+
+    /// Return the source of type `T`, if loaded.
+    pub fn get<T: AnnotationSource>(&self) -> Option<&T> {
+        self.sources
+            .iter()
+            .find_map(|registered| registered.any.downcast_ref::<T>())
+    }
+
+    /// Return the loaded sources in the order of registration.
+    pub fn iter(&self) -> impl Iterator<Item = &dyn AnnotationSource> {
+        self.sources
+            .iter()
+            .map(|registered| registered.source.as_ref())
+    }
+
+    /// Return the summaries of the loaded sources.
+    pub fn summaries(&self) -> Vec<SourceSummary> {
+        self.iter()
+            .map(|source| SourceSummary {
+                name: source.name().to_string(),
+                stats: source.stats(),
+                result_fields: source
+                    .result_fields()
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect(),
+            })
+            .collect()
+    }
+}
+
+impl FromIterator<RegisteredSource> for AnnotationSources {
+    fn from_iter<I: IntoIterator<Item = RegisteredSource>>(iter: I) -> Self {
+        let mut result = Self::default();
+        for source in iter {
+            result.push(source);
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{AnnotationSource, AnnotationSources, SourceStats};
+
+    struct Counts(Vec<u32>);
+
+    impl AnnotationSource for Counts {
+        fn name(&self) -> &'static str {
+            "counts"
+        }
+
+        fn stats(&self) -> SourceStats {
+            SourceStats {
+                entries: self.0.len(),
+                memory_bytes: self.0.capacity() * std::mem::size_of::<u32>(),
+            }
+        }
+
+        fn result_fields(&self) -> &'static [&'static str] {
+            &["count"]
+        }
+    }
+
+    struct Labels;
+
+    impl AnnotationSource for Labels {
+        fn name(&self) -> &'static str {
+            "labels"
+        }
+
+        fn stats(&self) -> SourceStats {
+            SourceStats {
+                entries: 0,
+                memory_bytes: 0,
+            }
+        }
+
+        fn result_fields(&self) -> &'static [&'static str] {
+            &[]
+        }
+    }
+
+    #[test]
+    fn registry() {
+        let mut sources = AnnotationSources::default();
+        assert!(sources.get::<Counts>().is_none());
+
+        sources.insert(Counts(vec![1, 2]));
+        sources.insert(Labels);
+        sources.insert(Counts(vec![3]));
+
+        assert_eq!(
+            sources.get::<Counts>().map(|counts| counts.0.clone()),
+            Some(vec![3])
+        );
+        assert!(sources.get::<Labels>().is_some());
+        assert_eq!(
+            sources
+                .iter()
+                .map(AnnotationSource::name)
+                .collect::<Vec<_>>(),
+            vec!["labels", "counts"]
+        );
+        let summaries = sources.summaries();
+        assert_eq!(summaries[1].stats.entries, 1);
+        assert_eq!(summaries[1].result_fields, vec!["count"]);
+    }
+}
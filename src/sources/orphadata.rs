@@ -0,0 +1,133 @@
+//! Streaming reader of the XML files of Orphadata, e.g., `en_product9_prev.xml`.
+
+use std::io::BufRead;
+
+use quick_xml::events::Event;
+
+/// Part of an element as visited by `read_xml`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Element<'a> {
+    /// An attribute of the element with its name and value.
+    Attribute(&'a str, &'a str),
+    /// The text of the element, without surrounding whitespace.
+    Text(&'a str),
+    /// The end of the element.
+    End,
+}
+
+/// Return whether the names of the open elements in `path` end with `suffix`.
+pub fn ends_with(path: &[String], suffix: &[&str]) -> bool {
+    path.len() >= suffix.len()
+        && path[path.len() - suffix.len()..]
+            .iter()
+            .zip(suffix)
+            .all(|(name, expected)| name == expected)
+}
+
+/// Visit the attributes of the element `start` with `visit`.
+fn visit_attributes(
+    path: &[String],
+    start: &quick_xml::events::BytesStart<'_>,
+    decoder: quick_xml::encoding::Decoder,
+    visit: &mut impl FnMut(&[String], Element<'_>) -> Result<(), anyhow::Error>,
+) -> Result<(), anyhow::Error> {
+    for attribute in start.attributes() {
+        let attribute = attribute.map_err(|e| anyhow::anyhow!("invalid XML attribute: {}", e))?;
+        let value = attribute
+            .decode_and_unescape_value(decoder)
+            .map_err(|e| anyhow::anyhow!("invalid XML attribute: {}", e))?;
+        visit(
+            path,
+            Element::Attribute(
+                &String::from_utf8_lossy(attribute.key.local_name().as_ref()),
+                &value,
+            ),
+        )?;
+    }
+    Ok(())
+}
+
+/// Read the XML document from `reader` and call `visit` for the attributes, the text, and
+/// the end of each element, with the names of the open elements from the root, e.g.,
+/// `["JDBOR", "DisorderList", "Disorder", "OrphaCode"]`.
+///
+/// # Errors
+///
+/// In the case that the XML is malformed or `visit` fails.
+pub fn read_xml<R: BufRead>(
+    reader: R,
+    mut visit: impl FnMut(&[String], Element<'_>) -> Result<(), anyhow::Error>,
+) -> Result<(), anyhow::Error> {
+    let mut reader = quick_xml::Reader::from_reader(reader);
+    reader.config_mut().trim_text(true);
+    let mut path = Vec::<String>::new();
+    let mut buf = Vec::new();
+    loop {
+        let event = reader.read_event_into(&mut buf).map_err(|e| {
+            anyhow::anyhow!("invalid XML at position {}: {}", reader.error_position(), e)
+        })?;
+        match event {
+            Event::Start(start) => {
+                path.push(String::from_utf8_lossy(start.local_name().as_ref()).into_owned());
+                visit_attributes(&path, &start, reader.decoder(), &mut visit)?;
+            }
+            Event::Empty(start) => {
+                path.push(String::from_utf8_lossy(start.local_name().as_ref()).into_owned());
+                visit_attributes(&path, &start, reader.decoder(), &mut visit)?;
+                visit(&path, Element::End)?;
+                path.pop();
+            }
+            Event::Text(text) => {
+                let text = text
+                    .unescape()
+                    .map_err(|e| anyhow::anyhow!("invalid XML text: {}", e))?;
+                visit(&path, Element::Text(&text))?;
+            }
+            Event::End(_) => {
+                visit(&path, Element::End)?;
+                path.pop();
+            }
+            Event::Eof => break,
+            _ => (),
+        }
+        buf.clear();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::Element;
+
+    #[test]
+    fn read_xml() -> Result<(), anyhow::Error> {
+        let mut visited = Vec::new();
+        super::read_xml(
+            "<?xml version=\"1.0\"?>\n<A><B id=\"1\"> x &amp; y </B><C/></A>".as_bytes(),
+            |path, element| {
+                visited.push((path.join("/"), format!("{element:?}")));
+                Ok(())
+            },
+        )?;
+
+        assert_eq!(
+            visited,
+            [
+                ("A/B", "Attribute(\"id\", \"1\")"),
+                ("A/B", "Text(\"x & y\")"),
+                ("A/B", "End"),
+                ("A/C", "End"),
+                ("A", "End"),
+            ]
+            .map(|(path, element)| (path.to_string(), element.to_string()))
+        );
+        assert!(super::ends_with(
+            &["A".to_string(), "B".to_string()],
+            &["A", "B"]
+        ));
+        assert!(!super::ends_with(&["B".to_string()], &["A", "B"]));
+        assert!(super::read_xml("<A><B></A>".as_bytes(), |_, _| Ok(())).is_err());
+
+        Ok(())
+    }
+}
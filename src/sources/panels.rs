@@ -0,0 +1,180 @@
+//! Code related to gene panels, e.g., from `PanelApp`, for grouping gene results.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+/// A record of the gene panel TSV file.
+#[derive(Debug, Clone, serde::Deserialize)]
+struct Record {
+    /// The panel ID, e.g., `PanelApp:285`.
+    panel_id: String,
+    /// The panel name, e.g., `Intellectual disability`, if given.
+    #[serde(default, deserialize_with = "csv::invalid_option")]
+    panel_name: Option<String>,
+    /// The symbol of a gene on the panel.
+    #[serde(alias = "gene")]
+    gene_symbol: String,
+}
+
+/// A gene panel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GenePanel {
+    /// The panel ID, e.g., `PanelApp:285`.
+    pub id: String,
+    /// The panel name, the ID if not given.
+    pub name: String,
+    /// The symbols of the genes on the panel.
+    pub gene_symbols: BTreeSet<String>,
+}
+
+/// The gene panels with lookup by gene symbol.
+#[derive(Debug, Clone, Default)]
+pub struct GenePanels {
+    /// The panels, sorted by ID.
+    panels: Vec<GenePanel>,
+    /// The positions of the panels in `panels` by gene symbol.
+    by_symbol: crate::common::interner::SymbolMap<Vec<usize>>,
+}
+
+impl GenePanels {
+    /// Read the panels from a TSV reader.
+    ///
+    /// The file must have a header and the columns `panel_id`, `gene_symbol`, and
+    /// optionally `panel_name`, with one row per gene and panel, e.g., converted from
+    /// the `PanelApp` API.  Further columns are ignored.
+    ///
+    /// # Errors
+    ///
+    /// In the case that the file could not be read.
+    pub fn from_reader<R: std::io::Read>(reader: R) -> Result<Self, anyhow::Error> {
+        let mut rdr = csv::ReaderBuilder::new()
+            .delimiter(b'\t')
+            .has_headers(true)
+            .flexible(true)
+            .from_reader(reader);
+        let mut panels = BTreeMap::<String, GenePanel>::new();
+        for result in rdr.deserialize() {
+            let record: Record =
+                result.map_err(|e| anyhow::anyhow!("invalid gene panel record: {}", e))?;
+            let panel = panels
+                .entry(record.panel_id.clone())
+                .or_insert_with(|| GenePanel {
+                    name: record.panel_id.clone(),
+                    id: record.panel_id,
+                    gene_symbols: BTreeSet::new(),
+                });
+            if let Some(name) = record.panel_name.filter(|name| !name.is_empty()) {
+                panel.name = name;
+            }
+            panel.gene_symbols.insert(record.gene_symbol);
+        }
+        let panels = panels.into_values().collect::<Vec<_>>();
+        let mut by_symbol = crate::common::interner::SymbolMap::<Vec<usize>>::default();
+        for (i, panel) in panels.iter().enumerate() {
+            for gene_symbol in &panel.gene_symbols {
+                by_symbol.get_or_default_mut(gene_symbol).push(i);
+            }
+        }
+        Ok(Self { panels, by_symbol })
+    }
+
+    /// Read the panels from the TSV file at `path`, see `from_reader`.
+    ///
+    /// # Errors
+    ///
+    /// In the case that the file could not be read.
+    pub fn from_path<P: AsRef<std::path::Path>>(path: P) -> Result<Self, anyhow::Error> {
+        Self::from_reader(std::fs::File::open(path.as_ref())?)
+    }
+
+    /// Return the panels of the gene with `gene_symbol`, sorted by ID.
+    pub fn panels_of<'a>(&'a self, gene_symbol: &str) -> impl Iterator<Item = &'a GenePanel> {
+        self.by_symbol
+            .get(gene_symbol)
+            .into_iter()
+            .flatten()
+            .map(|i| &self.panels[*i])
+    }
+
+    /// Return the number of panels.
+    pub fn len(&self) -> usize {
+        self.panels.len()
+    }
+
+    /// Return whether no panels are loaded.
+    pub fn is_empty(&self) -> bool {
+        self.panels.is_empty()
+    }
+}
+
+impl crate::sources::AnnotationSource for GenePanels {
+    fn name(&self) -> &'static str {
+        "panels"
+    }
+
+    fn stats(&self) -> crate::sources::SourceStats {
+        crate::sources::SourceStats {
+            entries: self.panels.len(),
+            memory_bytes: self.panels.capacity() * std::mem::size_of::<GenePanel>()
+                + self
+                    .panels
+                    .iter()
+                    .flat_map(|panel| &panel.gene_symbols)
+                    .map(String::capacity)
+                    .sum::<usize>()
+                + self.by_symbol.memory_bytes()
+                + self
+                    .by_symbol
+                    .iter()
+                    .map(|(_, positions)| positions.capacity() * std::mem::size_of::<usize>())
+                    .sum::<usize>(),
+        }
+    }
+
+    fn result_fields(&self) -> &'static [&'static str] {
+        &["panels"]
+    }
+}
+
+impl crate::sources::LoadSource for GenePanels {
+    const STAGE: &'static str = "gene panels";
+
+    fn load(path: &std::path::Path) -> Result<Self, anyhow::Error> {
+        Self::from_path(path)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::GenePanels;
+
+    #[test]
+    fn from_reader() -> Result<(), anyhow::Error> {
+        let panels = GenePanels::from_reader(
+            "panel_id\tpanel_name\tgene_symbol\tconfidence_level\n\
+             PanelApp:402\tEpilepsy\tSCN1A\t3\n\
+             PanelApp:285\tIntellectual disability\tARID1B\t3\n\
+             PanelApp:285\t\tSCN1A\t3\n\
+             local:1\t\tTGDS\t\n"
+                .as_bytes(),
+        )?;
+
+        assert_eq!(panels.len(), 3);
+        assert_eq!(
+            panels
+                .panels_of("SCN1A")
+                .map(|panel| panel.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["Intellectual disability", "Epilepsy"]
+        );
+        assert_eq!(
+            panels
+                .panels_of("TGDS")
+                .map(|panel| panel.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["local:1"]
+        );
+        assert_eq!(panels.panels_of("TTN").count(), 0);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,336 @@
+//! Code related to Orphanet's epidemiology data, e.g., the prevalence classes of
+//! `en_product9_prev.xml`.
+
+use std::collections::HashMap;
+
+use super::orphadata::{ends_with, Element};
+
+/// Value of the `geographic` column for worldwide prevalences.
+const GEOGRAPHIC_WORLDWIDE: &str = "Worldwide";
+/// Value of the `prevalence_type` column for point prevalences.
+const TYPE_POINT: &str = "Point prevalence";
+
+/// A record of the Orphanet prevalence TSV file.
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+struct Record {
+    /// The ORPHA code, e.g., `558`.
+    orpha_code: u32,
+    /// The kind of prevalence, e.g., `Point prevalence` or `Birth prevalence`.
+    prevalence_type: String,
+    /// The prevalence class, e.g., `1-5 / 10 000`.
+    prevalence_class: String,
+    /// The region, e.g., `Worldwide` or `Europe`, if given.
+    #[serde(default, deserialize_with = "csv::invalid_option")]
+    geographic: Option<String>,
+}
+
+/// The prevalence of an Orphanet disease.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct Prevalence {
+    /// The kind of prevalence, e.g., `Point prevalence` or `Birth prevalence`.
+    pub prevalence_type: String,
+    /// Orphanet's prevalence class, e.g., `1-5 / 10 000`.
+    pub prevalence_class: String,
+    /// The region, e.g., `Worldwide` or `Europe`, if given.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub geographic: Option<String>,
+    /// The prevalence in the middle of the class as a fraction, e.g., `0.0003` for
+    /// `1-5 / 10 000`, `None` for `Unknown` and `Not yet documented`.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub estimate: Option<f64>,
+}
+
+/// Return the prevalence in the middle of Orphanet's prevalence `class`, if known.
+///
+/// The estimate may serve as the prior probability of the disease, e.g., so that
+/// ultra-rare diseases need stronger phenotypic evidence.
+pub fn class_estimate(class: &str) -> Option<f64> {
+    let class = class.split_whitespace().collect::<String>();
+    match class.as_str() {
+        ">1/1000" => Some(1e-3),
+        "6-9/10000" => Some(7.5e-4),
+        "1-5/10000" => Some(3e-4),
+        "1-9/100000" => Some(5e-5),
+        "1-9/1000000" => Some(5e-6),
+        "<1/1000000" => Some(5e-7),
+        _ => None,
+    }
+}
+
+/// The prevalences of the Orphanet diseases.
+#[derive(Debug, Clone, Default)]
+pub struct DiseasePrevalences {
+    /// The prevalences by ORPHA code, in the order of the file.
+    by_orpha: HashMap<u32, Vec<Prevalence>>,
+}
+
+impl DiseasePrevalences {
+    /// Read the prevalences from a TSV reader.
+    ///
+    /// The file must have a header and the columns `orpha_code`, `prevalence_type`,
+    /// `prevalence_class`, and optionally `geographic`, e.g., extracted from Orphanet's
+    /// `en_product9_prev.xml`.
+    ///
+    /// # Errors
+    ///
+    /// In the case that the file could not be read.
+    pub fn from_reader<R: std::io::Read>(reader: R) -> Result<Self, anyhow::Error> {
+        let mut rdr = csv::ReaderBuilder::new()
+            .delimiter(b'\t')
+            .has_headers(true)
+            .flexible(true)
+            .from_reader(reader);
+        let mut result = Self::default();
+        for record in rdr.deserialize() {
+            result.insert(record.map_err(|e| anyhow::anyhow!("invalid prevalence record: {}", e))?);
+        }
+        Ok(result)
+    }
+
+    /// Read the prevalences from Orphanet's `en_product9_prev.xml` in `reader`.
+    ///
+    /// # Errors
+    ///
+    /// In the case that the XML is malformed or an ORPHA code is not a number.
+    pub fn from_xml_reader<R: std::io::BufRead>(reader: R) -> Result<Self, anyhow::Error> {
+        let mut result = Self::default();
+        let mut orpha_code = None;
+        let mut record = Record::default();
+        super::orphadata::read_xml(reader, |path, element| {
+            match element {
+                Element::Text(text) if ends_with(path, &["Disorder", "OrphaCode"]) => {
+                    orpha_code =
+                        Some(text.parse::<u32>().map_err(|e| {
+                            anyhow::anyhow!("invalid ORPHA code {:?}: {}", text, e)
+                        })?);
+                }
+                Element::Text(text) if ends_with(path, &["PrevalenceType", "Name"]) => {
+                    record.prevalence_type = text.to_string();
+                }
+                Element::Text(text) if ends_with(path, &["PrevalenceClass", "Name"]) => {
+                    record.prevalence_class = text.to_string();
+                }
+                Element::Text(text) if ends_with(path, &["PrevalenceGeographic", "Name"]) => {
+                    record.geographic = Some(text.to_string());
+                }
+                Element::End if ends_with(path, &["PrevalenceList", "Prevalence"]) => {
+                    let record = std::mem::take(&mut record);
+                    if let Some(orpha_code) = orpha_code {
+                        result.insert(Record {
+                            orpha_code,
+                            ..record
+                        });
+                    }
+                }
+                Element::End if ends_with(path, &["Disorder"]) => orpha_code = None,
+                _ => (),
+            }
+            Ok(())
+        })?;
+        Ok(result)
+    }
+
+    /// Read the prevalences from the file at `path`, Orphanet's `en_product9_prev.xml`
+    /// if its extension is `xml` and a TSV file otherwise, see `from_reader`.
+    ///
+    /// # Errors
+    ///
+    /// In the case that the file could not be read.
+    pub fn from_path<P: AsRef<std::path::Path>>(path: P) -> Result<Self, anyhow::Error> {
+        let file = std::fs::File::open(path.as_ref())?;
+        if path.as_ref().extension().is_some_and(|ext| ext == "xml") {
+            Self::from_xml_reader(std::io::BufReader::new(file))
+        } else {
+            Self::from_reader(file)
+        }
+    }
+
+    /// Insert the prevalence of `record`.
+    fn insert(&mut self, record: Record) {
+        self.by_orpha
+            .entry(record.orpha_code)
+            .or_default()
+            .push(Prevalence {
+                estimate: class_estimate(&record.prevalence_class),
+                prevalence_type: record.prevalence_type,
+                prevalence_class: record.prevalence_class,
+                geographic: record.geographic,
+            });
+    }
+
+    /// Return all prevalences of the disease with `orpha_code`.
+    pub fn get(&self, orpha_code: u32) -> &[Prevalence] {
+        self.by_orpha.get(&orpha_code).map_or(&[], Vec::as_slice)
+    }
+
+    /// Return the most representative prevalence of the disease with `orpha_code`.
+    ///
+    /// Known prevalences are preferred over unknown ones, worldwide over regional
+    /// ones, and point prevalences over other kinds.
+    pub fn best(&self, orpha_code: u32) -> Option<&Prevalence> {
+        self.get(orpha_code)
+            .iter()
+            .enumerate()
+            .max_by_key(|(i, prevalence)| {
+                (
+                    prevalence.estimate.is_some(),
+                    prevalence.geographic.as_deref() == Some(GEOGRAPHIC_WORLDWIDE),
+                    prevalence.prevalence_type == TYPE_POINT,
+                    // Prefer the first of equally representative prevalences.
+                    std::cmp::Reverse(*i),
+                )
+            })
+            .map(|(_, prevalence)| prevalence)
+    }
+
+    /// Return the best known prevalence of the first disease of `orpha_codes` that
+    /// has one, e.g., of the Orphanet diseases mapped to an OMIM disease.
+    pub fn best_of(&self, orpha_codes: &[u32]) -> Option<&Prevalence> {
+        orpha_codes
+            .iter()
+            .filter_map(|orpha_code| self.best(*orpha_code))
+            .find(|prevalence| prevalence.estimate.is_some())
+    }
+
+    /// Return whether no prevalences are loaded.
+    pub fn is_empty(&self) -> bool {
+        self.by_orpha.is_empty()
+    }
+}
+
+impl crate::sources::AnnotationSource for DiseasePrevalences {
+    fn name(&self) -> &'static str {
+        "prevalence"
+    }
+
+    fn stats(&self) -> crate::sources::SourceStats {
+        crate::sources::SourceStats {
+            entries: self.by_orpha.len(),
+            memory_bytes: crate::sources::hash_map_bytes(&self.by_orpha)
+                + self
+                    .by_orpha
+                    .values()
+                    .map(|prevalences| prevalences.capacity() * std::mem::size_of::<Prevalence>())
+                    .sum::<usize>(),
+        }
+    }
+
+    fn result_fields(&self) -> &'static [&'static str] {
+        &["prevalence"]
+    }
+}
+
+impl crate::sources::LoadSource for DiseasePrevalences {
+    const STAGE: &'static str = "Orphanet prevalences";
+
+    fn load(path: &std::path::Path) -> Result<Self, anyhow::Error> {
+        Self::from_path(path)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::DiseasePrevalences;
+
+    #[test]
+    fn from_reader() -> Result<(), anyhow::Error> {
+        let prevalences = DiseasePrevalences::from_reader(
+            "orpha_code\tprevalence_type\tprevalence_class\tgeographic\n\
+             558\tBirth prevalence\t1-9 / 100 000\tEurope\n\
+             558\tPoint prevalence\t1-5 / 10 000\tWorldwide\n\
+             1234\tPoint prevalence\tUnknown\tWorldwide\n\
+             1234\tAnnual incidence\t<1 / 1 000 000\tEurope\n"
+                .as_bytes(),
+        )?;
+
+        assert!(!prevalences.is_empty());
+        assert_eq!(prevalences.get(558).len(), 2);
+        let best = prevalences.best(558).expect("must be known");
+        assert_eq!(best.prevalence_class, "1-5 / 10 000");
+        assert_eq!(best.estimate, Some(3e-4));
+        let best = prevalences.best(1234).expect("must be known");
+        assert_eq!(best.prevalence_class, "<1 / 1 000 000");
+        assert_eq!(
+            prevalences
+                .best_of(&[42, 1234, 558])
+                .map(|best| best.prevalence_class.as_str()),
+            Some("<1 / 1 000 000")
+        );
+        assert!(prevalences.best(42).is_none());
+
+        Ok(())
+    }
+
+    /// Excerpt of Orphanet's `en_product9_prev.xml`.
+    const PRODUCT9_PREV: &str = r#"<?xml version="1.0" encoding="ISO-8859-1"?>
+<JDBOR date="2024-12-02 07:09:45" version="1.3.40 / 4.1.7 [2024-11-26] (orientdb version)" copyright="Orphanet (c) 2024" dbserver="orphadata">
+  <DisorderList count="2">
+<Disorder id="17601">
+  <OrphaCode>558</OrphaCode>
+  <Name lang="en">Marfan syndrome</Name>
+  <PrevalenceList count="2">
+    <Prevalence id="2304">
+      <Source>ORPHANET_11</Source>
+      <PrevalenceType id="40862">
+        <Name lang="en">Point prevalence</Name>
+      </PrevalenceType>
+      <PrevalenceClass id="409974">
+        <Name lang="en">1-5 / 10 000</Name>
+      </PrevalenceClass>
+      <ValMoy>0.0</ValMoy>
+      <PrevalenceGeographic id="52448">
+        <Name lang="en">Worldwide</Name>
+      </PrevalenceGeographic>
+    </Prevalence>
+    <Prevalence id="2305">
+      <Source>11389160[PMID]</Source>
+      <PrevalenceType id="40870">
+        <Name lang="en">Cases/families</Name>
+      </PrevalenceType>
+      <PrevalenceClass/>
+      <ValMoy>2.0</ValMoy>
+      <PrevalenceGeographic id="52448">
+        <Name lang="en">Europe</Name>
+      </PrevalenceGeographic>
+    </Prevalence>
+  </PrevalenceList>
+</Disorder>
+<Disorder id="17602">
+  <OrphaCode>1234</OrphaCode>
+  <Name lang="en">Syndrome de Gräfe</Name>
+  <PrevalenceList count="0"/>
+</Disorder>
+  </DisorderList>
+</JDBOR>
+"#;
+
+    #[test]
+    fn from_xml_reader() -> Result<(), anyhow::Error> {
+        // The file is encoded in ISO-8859-1 as declared.
+        let xml = PRODUCT9_PREV
+            .chars()
+            .map(|c| u8::try_from(u32::from(c)).expect("must be ISO-8859-1"))
+            .collect::<Vec<_>>();
+        let prevalences = DiseasePrevalences::from_xml_reader(xml.as_slice())?;
+
+        assert_eq!(prevalences.get(558).len(), 2);
+        let best = prevalences.best(558).expect("must be known");
+        assert_eq!(best.prevalence_type, "Point prevalence");
+        assert_eq!(best.prevalence_class, "1-5 / 10 000");
+        assert_eq!(best.geographic.as_deref(), Some("Worldwide"));
+        assert_eq!(best.estimate, Some(3e-4));
+        let cases = &prevalences.get(558)[1];
+        assert_eq!(cases.prevalence_class, "");
+        assert_eq!(cases.geographic.as_deref(), Some("Europe"));
+        assert!(prevalences.get(1234).is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn class_estimate() {
+        assert_eq!(super::class_estimate(">1 / 1000"), Some(1e-3));
+        assert_eq!(super::class_estimate("1-9 / 1 000 000"), Some(5e-6));
+        assert_eq!(super::class_estimate("Not yet documented"), None);
+    }
+}
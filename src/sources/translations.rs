@@ -0,0 +1,342 @@
+//! Code related to translations of term labels, e.g., from `hp-international`.
+
+use std::collections::HashMap;
+
+/// Predicate of translated term names.
+const PREDICATE_NAME: &str = "rdfs:label";
+/// Predicate of translated term definitions.
+const PREDICATE_DEFINITION: &str = "IAO:0000115";
+/// Predicate of translated exact synonyms.
+const PREDICATE_SYNONYM: &str = "oboInOwl:hasExactSynonym";
+
+/// Data structure for representing an entry of a Babelon TSV file.
+#[derive(Debug, Clone, serde::Deserialize)]
+struct Entry {
+    /// Language of the translation, e.g., `de`.
+    translation_language: String,
+    /// The HPO term ID.
+    subject_id: String,
+    /// The translated property, e.g., `rdfs:label`.
+    predicate_id: String,
+    /// The translated value.
+    translation_value: String,
+}
+
+/// Translated labels of one term.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Translation {
+    /// The translated term name, if any.
+    pub name: Option<String>,
+    /// The translated definition, if any.
+    pub definition: Option<String>,
+    /// The translated synonyms.
+    pub synonyms: Vec<String>,
+}
+
+/// Translations of term labels by language and term ID.
+#[derive(Debug, Clone, Default)]
+pub struct Translations {
+    /// Map from language to map from term ID to translation.
+    by_lang: HashMap<String, HashMap<String, Translation>>,
+}
+
+impl Translations {
+    /// Load the translations from Babelon TSV files, e.g., `hp-de.babelon.tsv`.
+    ///
+    /// Rows with empty values or other predicates than names, definitions, and exact
+    /// synonyms are ignored.
+    ///
+    /// # Errors
+    ///
+    /// In the case that a file could not be read.
+    pub fn from_paths<P: AsRef<std::path::Path>>(paths: &[P]) -> Result<Self, anyhow::Error> {
+        let mut result = Self::default();
+        for path in paths {
+            let mut rdr = csv::ReaderBuilder::new()
+                .delimiter(b'\t')
+                .has_headers(true)
+                .quoting(false)
+                .from_path(path.as_ref())?;
+            for record in rdr.deserialize() {
+                let entry: Entry = record?;
+                result.insert(entry);
+            }
+        }
+        Ok(result)
+    }
+
+    /// Add the translation from `entry`.
+    fn insert(&mut self, entry: Entry) {
+        let value = entry.translation_value.trim().to_string();
+        let predicate = entry.predicate_id.as_str();
+        if value.is_empty()
+            || ![PREDICATE_NAME, PREDICATE_DEFINITION, PREDICATE_SYNONYM].contains(&predicate)
+        {
+            return;
+        }
+        let translation = self
+            .by_lang
+            .entry(entry.translation_language)
+            .or_default()
+            .entry(entry.subject_id)
+            .or_default();
+        match predicate {
+            PREDICATE_NAME => translation.name = Some(value),
+            PREDICATE_DEFINITION => translation.definition = Some(value),
+            _ => translation.synonyms.push(value),
+        }
+    }
+
+    /// Return the translation of the term with `term_id` into `lang`, if any.
+    pub fn get(&self, lang: &str, term_id: &str) -> Option<&Translation> {
+        self.by_lang.get(lang)?.get(term_id)
+    }
+
+    /// Return the loaded languages, sorted.
+    pub fn languages(&self) -> Vec<&str> {
+        let mut result = self.by_lang.keys().map(String::as_str).collect::<Vec<_>>();
+        result.sort_unstable();
+        result
+    }
+}
+
+/// Data structure for representing an entry of a TSV file with translated Orphanet
+/// disease names.
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+struct DiseaseEntry {
+    /// Language of the translation, e.g., `de`.
+    language: String,
+    /// The ORPHA code, e.g., `558`.
+    orpha_code: u32,
+    /// The translated disease name.
+    name: String,
+}
+
+/// Translated names of Orphanet diseases by language and ORPHA code.
+#[derive(Debug, Clone, Default)]
+pub struct DiseaseTranslations {
+    /// Map from language to map from ORPHA code to name.
+    by_lang: HashMap<String, HashMap<u32, String>>,
+}
+
+impl DiseaseTranslations {
+    /// Load the translations from Orphanet's translated `product1` files, e.g.,
+    /// `de_product1.xml`, for files with the extension `xml`, and from TSV files with the
+    /// columns `language`, `orpha_code`, and `name` otherwise.
+    ///
+    /// Rows with empty names are ignored.
+    ///
+    /// # Errors
+    ///
+    /// In the case that a file could not be read.
+    pub fn from_paths<P: AsRef<std::path::Path>>(paths: &[P]) -> Result<Self, anyhow::Error> {
+        let mut result = Self::default();
+        for path in paths {
+            if path.as_ref().extension().is_some_and(|ext| ext == "xml") {
+                result.read_xml(std::io::BufReader::new(std::fs::File::open(path.as_ref())?))?;
+                continue;
+            }
+            let mut rdr = csv::ReaderBuilder::new()
+                .delimiter(b'\t')
+                .has_headers(true)
+                .quoting(false)
+                .from_path(path.as_ref())?;
+            for record in rdr.deserialize() {
+                result.insert(record?);
+            }
+        }
+        Ok(result)
+    }
+
+    /// Read the translated names of the diseases from Orphanet's translated `product1`
+    /// file in `reader`, e.g., `de_product1.xml`.
+    ///
+    /// The language is taken from the `lang` attribute of the names.
+    ///
+    /// # Errors
+    ///
+    /// In the case that the XML is malformed or an ORPHA code is not a number.
+    fn read_xml<R: std::io::BufRead>(&mut self, reader: R) -> Result<(), anyhow::Error> {
+        use super::orphadata::{ends_with, Element};
+
+        let mut entry = DiseaseEntry::default();
+        super::orphadata::read_xml(reader, |path, element| {
+            match element {
+                Element::Text(text) if ends_with(path, &["Disorder", "OrphaCode"]) => {
+                    entry.orpha_code = text
+                        .parse::<u32>()
+                        .map_err(|e| anyhow::anyhow!("invalid ORPHA code {:?}: {}", text, e))?;
+                }
+                Element::Attribute("lang", lang) if ends_with(path, &["Disorder", "Name"]) => {
+                    entry.language = lang.to_string();
+                }
+                Element::Text(text) if ends_with(path, &["Disorder", "Name"]) => {
+                    entry.name = text.to_string();
+                }
+                Element::End if ends_with(path, &["Disorder"]) => {
+                    self.insert(std::mem::take(&mut entry));
+                }
+                _ => (),
+            }
+            Ok(())
+        })
+    }
+
+    /// Insert the translated name of `entry`, unless empty.
+    fn insert(&mut self, entry: DiseaseEntry) {
+        let name = entry.name.trim();
+        if !name.is_empty() && !entry.language.is_empty() {
+            self.by_lang
+                .entry(entry.language)
+                .or_default()
+                .insert(entry.orpha_code, name.to_string());
+        }
+    }
+
+    /// Return the name of the Orphanet disease with `orpha_code` in `lang`, if any.
+    pub fn get(&self, lang: &str, orpha_code: u32) -> Option<&str> {
+        self.by_lang.get(lang)?.get(&orpha_code).map(String::as_str)
+    }
+
+    /// Return the name of the OMIM disease with `omim_id` in `lang`, if any.
+    ///
+    /// OMIM diseases are translated by way of Orphanet's mappings and only if they map
+    /// to exactly one Orphanet disease.
+    pub fn get_omim(
+        &self,
+        lang: &str,
+        omim_id: u32,
+        disease_codes: &super::disease_codes::DiseaseCodes,
+    ) -> Option<&str> {
+        match disease_codes.orpha_codes_for_omim(omim_id) {
+            [orpha_code] => self.get(lang, *orpha_code),
+            _ => None,
+        }
+    }
+
+    /// Return the loaded languages, sorted.
+    pub fn languages(&self) -> Vec<&str> {
+        let mut result = self.by_lang.keys().map(String::as_str).collect::<Vec<_>>();
+        result.sort_unstable();
+        result
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn from_paths() -> Result<(), anyhow::Error> {
+        let tmp_dir = temp_testdir::TempDir::default();
+        let path = tmp_dir.join("hp-de.babelon.tsv");
+        std::fs::write(
+            &path,
+            "source_language\ttranslation_language\tsubject_id\tpredicate_id\t\
+             source_value\ttranslation_value\ttranslation_status\n\
+             en\tde\tHP:0001250\trdfs:label\tSeizure\tKrampfanfall\tOFFICIAL\n\
+             en\tde\tHP:0001250\toboInOwl:hasExactSynonym\tSeizures\tAnfall\tOFFICIAL\n\
+             en\tde\tHP:0001250\toboInOwl:hasExactSynonym\tEpileptic seizure\t\tCANDIDATE\n\
+             en\tde\tHP:0001250\toboInOwl:hasBroadSynonym\tFits\tAnfälle\tOFFICIAL\n",
+        )?;
+
+        let translations = super::Translations::from_paths(&[&path])?;
+
+        assert_eq!(translations.languages(), vec!["de"]);
+        assert_eq!(
+            translations.get("de", "HP:0001250"),
+            Some(&super::Translation {
+                name: Some("Krampfanfall".to_string()),
+                definition: None,
+                synonyms: vec!["Anfall".to_string()],
+            })
+        );
+        assert_eq!(translations.get("de", "HP:0000118"), None);
+        assert_eq!(translations.get("es", "HP:0001250"), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn disease_translations() -> Result<(), anyhow::Error> {
+        let tmp_dir = temp_testdir::TempDir::default();
+        let path = tmp_dir.join("orphanet_de.tsv");
+        std::fs::write(
+            &path,
+            "language\torpha_code\tname\n\
+             de\t558\tMarfan-Syndrom\n\
+             de\t1234\t\n",
+        )?;
+        let disease_codes = crate::sources::disease_codes::DiseaseCodes::from_records([
+            crate::sources::disease_codes::Record {
+                orpha_code: 558,
+                source: "OMIM".to_string(),
+                reference: "154700".to_string(),
+                mapping_relation: None,
+            },
+            crate::sources::disease_codes::Record {
+                orpha_code: 558,
+                source: "OMIM".to_string(),
+                reference: "610168".to_string(),
+                mapping_relation: None,
+            },
+            crate::sources::disease_codes::Record {
+                orpha_code: 1234,
+                source: "OMIM".to_string(),
+                reference: "610168".to_string(),
+                mapping_relation: None,
+            },
+        ]);
+
+        let translations = super::DiseaseTranslations::from_paths(&[&path])?;
+
+        assert_eq!(translations.languages(), vec!["de"]);
+        assert_eq!(translations.get("de", 558), Some("Marfan-Syndrom"));
+        assert_eq!(translations.get("de", 1234), None);
+        assert_eq!(translations.get("fr", 558), None);
+        assert_eq!(
+            translations.get_omim("de", 154_700, &disease_codes),
+            Some("Marfan-Syndrom")
+        );
+        assert_eq!(translations.get_omim("de", 610_168, &disease_codes), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn disease_translations_xml() -> Result<(), anyhow::Error> {
+        let tmp_dir = temp_testdir::TempDir::default();
+        let path = tmp_dir.join("de_product1.xml");
+        // Excerpt of Orphanet's `de_product1.xml`, encoded in ISO-8859-1 as declared.
+        let xml = r#"<?xml version="1.0" encoding="ISO-8859-1"?>
+<JDBOR date="2024-12-02 07:03:17" version="1.3.40 / 4.1.7 [2024-11-26] (orientdb version)" copyright="Orphanet (c) 2024" dbserver="orphadata">
+  <DisorderList count="2">
+<Disorder id="17601">
+  <OrphaCode>558</OrphaCode>
+  <ExpertLink lang="de">http://www.orpha.net/consor/cgi-bin/OC_Exp.php?lng=de&amp;Expert=558</ExpertLink>
+  <Name lang="de">Marfan-Syndrom</Name>
+  <DisorderType id="21394">
+    <Name lang="de">Krankheit</Name>
+  </DisorderType>
+</Disorder>
+<Disorder id="17602">
+  <OrphaCode>1234</OrphaCode>
+  <Name lang="de">Gräfe-Syndrom</Name>
+</Disorder>
+  </DisorderList>
+</JDBOR>
+"#;
+        std::fs::write(
+            &path,
+            xml.chars()
+                .map(|c| u8::try_from(u32::from(c)).expect("must be ISO-8859-1"))
+                .collect::<Vec<_>>(),
+        )?;
+
+        let translations = super::DiseaseTranslations::from_paths(&[&path])?;
+
+        assert_eq!(translations.languages(), vec!["de"]);
+        assert_eq!(translations.get("de", 558), Some("Marfan-Syndrom"));
+        assert_eq!(translations.get("de", 1234), Some("Gräfe-Syndrom"));
+
+        Ok(())
+    }
+}
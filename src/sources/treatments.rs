@@ -0,0 +1,197 @@
+//! Code related to the MAXO annotations of medical actions to HPO terms.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use hpo::HpoTermId;
+
+/// A record of the MAXO annotations TSV file.
+#[derive(Debug, Clone, serde::Deserialize)]
+struct Record {
+    /// The disease the annotation was made for, e.g., `OMIM:154700`.
+    #[serde(default, deserialize_with = "csv::invalid_option")]
+    disease_id: Option<String>,
+    /// The MAXO term ID of the medical action, e.g., `MAXO:0000004`.
+    maxo_id: String,
+    /// The label of the medical action.
+    maxo_label: String,
+    /// The HPO term ID of the phenotype, empty for disease-level annotations.
+    #[serde(default, deserialize_with = "csv::invalid_option")]
+    hpo_id: Option<String>,
+    /// The relation of the action to the phenotype, e.g., `TREATS`.
+    predicate: String,
+}
+
+/// A medical action relevant to a phenotype.
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    serde::Serialize,
+    serde::Deserialize,
+    utoipa::ToSchema,
+)]
+pub struct Treatment {
+    /// The MAXO term ID of the medical action, e.g., `MAXO:0000004`.
+    pub maxo_id: String,
+    /// The label of the medical action, e.g., `surgical procedure`.
+    pub maxo_label: String,
+    /// The relation of the action to the phenotype, e.g., `TREATS` or `PREVENTS`.
+    pub predicate: String,
+    /// The diseases the annotation was made for, sorted.
+    pub disease_ids: Vec<String>,
+}
+
+/// The medical actions by HPO term from the MAXO annotations.
+#[derive(Debug, Clone, Default)]
+pub struct Treatments {
+    /// The actions by term, sorted by MAXO term ID and predicate.
+    by_term: HashMap<HpoTermId, Vec<Treatment>>,
+}
+
+impl Treatments {
+    /// Read the MAXO annotations from a TSV reader.
+    ///
+    /// The file must have a header and the columns `maxo_id`, `maxo_label`, `hpo_id`,
+    /// and `predicate`, and may have `disease_id`.  Records without an HPO term are
+    /// skipped and records of the same action and predicate for a term are merged.
+    ///
+    /// # Errors
+    ///
+    /// In the case that the file could not be read or a term ID is malformed.
+    pub fn from_reader<R: std::io::Read>(reader: R) -> Result<Self, anyhow::Error> {
+        let mut rdr = csv::ReaderBuilder::new()
+            .delimiter(b'\t')
+            .has_headers(true)
+            .comment(Some(b'#'))
+            .from_reader(reader);
+        let mut merged =
+            HashMap::<HpoTermId, BTreeMap<(String, String), (String, BTreeSet<String>)>>::new();
+        for result in rdr.deserialize() {
+            let record: Record = result?;
+            let Some(hpo_id) = record.hpo_id.filter(|hpo_id| !hpo_id.trim().is_empty()) else {
+                continue;
+            };
+            let term_id = crate::common::parse_term_id(hpo_id.trim())?;
+            let (_, disease_ids) = merged
+                .entry(term_id)
+                .or_default()
+                .entry((record.maxo_id, record.predicate.to_uppercase()))
+                .or_insert_with(|| (record.maxo_label, BTreeSet::new()));
+            disease_ids.extend(record.disease_id.filter(|id| !id.trim().is_empty()));
+        }
+        let by_term = merged
+            .into_iter()
+            .map(|(term_id, treatments)| {
+                let treatments = treatments
+                    .into_iter()
+                    .map(
+                        |((maxo_id, predicate), (maxo_label, disease_ids))| Treatment {
+                            maxo_id,
+                            maxo_label,
+                            predicate,
+                            disease_ids: disease_ids.into_iter().collect(),
+                        },
+                    )
+                    .collect();
+                (term_id, treatments)
+            })
+            .collect();
+        Ok(Self { by_term })
+    }
+
+    /// Read the MAXO annotations from the TSV file at `path`, see `from_reader`.
+    ///
+    /// # Errors
+    ///
+    /// In the case that the file could not be read or a term ID is malformed.
+    pub fn from_path<P: AsRef<std::path::Path>>(path: P) -> Result<Self, anyhow::Error> {
+        Self::from_reader(std::fs::File::open(path.as_ref())?)
+    }
+
+    /// Return the medical actions for `term_id`, sorted by MAXO term ID and predicate.
+    pub fn get(&self, term_id: HpoTermId) -> &[Treatment] {
+        self.by_term.get(&term_id).map_or(&[], Vec::as_slice)
+    }
+
+    /// Return whether no annotations are loaded.
+    pub fn is_empty(&self) -> bool {
+        self.by_term.is_empty()
+    }
+}
+
+impl crate::sources::AnnotationSource for Treatments {
+    fn name(&self) -> &'static str {
+        "maxo"
+    }
+
+    fn stats(&self) -> crate::sources::SourceStats {
+        let treatment_bytes = |treatment: &Treatment| {
+            std::mem::size_of::<Treatment>()
+                + treatment.maxo_id.capacity()
+                + treatment.maxo_label.capacity()
+                + treatment.predicate.capacity()
+                + treatment
+                    .disease_ids
+                    .iter()
+                    .map(|id| std::mem::size_of::<String>() + id.capacity())
+                    .sum::<usize>()
+        };
+        crate::sources::SourceStats {
+            entries: self.by_term.len(),
+            memory_bytes: crate::sources::hash_map_bytes(&self.by_term)
+                + self
+                    .by_term
+                    .values()
+                    .flatten()
+                    .map(treatment_bytes)
+                    .sum::<usize>(),
+        }
+    }
+
+    fn result_fields(&self) -> &'static [&'static str] {
+        &[]
+    }
+}
+
+impl crate::sources::LoadSource for Treatments {
+    const STAGE: &'static str = "MAXO annotations";
+
+    fn load(path: &std::path::Path) -> Result<Self, anyhow::Error> {
+        Self::from_path(path)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use hpo::HpoTermId;
+
+    use super::Treatments;
+
+    #[test]
+    fn from_reader() -> Result<(), anyhow::Error> {
+        let treatments = Treatments::from_reader(
+            "disease_id\tdisease_name\tmaxo_id\tmaxo_label\thpo_id\tpredicate\n\
+             OMIM:154700\tMarfan\tMAXO:0000004\tsurgical procedure\tHP:0002616\tTREATS\n\
+             OMIM:609192\tLoeys-Dietz\tMAXO:0000004\tsurgical procedure\tHP:0002616\ttreats\n\
+             OMIM:154700\tMarfan\tMAXO:0000058\tavoid contact sports\tHP:0002616\tPREVENTS\n\
+             OMIM:154700\tMarfan\tMAXO:0000127\tgenetic counseling\t\tTREATS\n"
+                .as_bytes(),
+        )?;
+
+        let result = treatments.get(HpoTermId::from_u32(2616));
+        assert_eq!(
+            result
+                .iter()
+                .map(|treatment| (treatment.maxo_id.as_str(), treatment.predicate.as_str()))
+                .collect::<Vec<_>>(),
+            vec![("MAXO:0000004", "TREATS"), ("MAXO:0000058", "PREVENTS")]
+        );
+        assert_eq!(result[0].disease_ids, vec!["OMIM:154700", "OMIM:609192"]);
+        assert!(treatments.get(HpoTermId::from_u32(1250)).is_empty());
+
+        Ok(())
+    }
+}